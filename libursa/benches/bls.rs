@@ -1,46 +1,43 @@
 #[macro_use]
 extern crate criterion;
 extern crate amcl_wrapper;
+extern crate rand;
 extern crate ursa;
 
 use amcl_wrapper::field_elem::FieldElement;
-use amcl_wrapper::group_elem::GroupElement;
 use criterion::Criterion;
 
 use ursa::signatures::bls::{
     normal::{
         generate as usual_generate, AggregatedPublicKey as UsualAggregatedPublicKey,
-        AggregatedSignature as UsualAggregatedSignature, Generator as UsualGenerator,
-        PublicKey as UsualPublicKey, Signature as UsualSignature,
+        AggregatedSignature as UsualAggregatedSignature, PublicKey as UsualPublicKey,
+        Signature as UsualSignature,
     },
     small::{
         generate as small_generate, AggregatedPublicKey as SmallAggregatedPublicKey,
-        AggregatedSignature as SmallAggregatedSignature, Generator as SmallGenerator,
-        PublicKey as SmallPublicKey, Signature as SmallSignature,
+        AggregatedSignature as SmallAggregatedSignature, PublicKey as SmallPublicKey,
+        Signature as SmallSignature,
     },
+    Scratch,
 };
 
 fn keypair_benchmark(c: &mut Criterion) {
-    let g = UsualGenerator::generator();
     c.bench_function(format!("Create usual bls key pair").as_str(), move |b| {
-        b.iter(|| usual_generate(&g));
+        b.iter(|| usual_generate());
     });
-    let g = SmallGenerator::generator();
     c.bench_function(format!("Create small bls key pair").as_str(), move |b| {
-        b.iter(|| small_generate(&g));
+        b.iter(|| small_generate());
     });
 }
 
 fn sign_benchmark(c: &mut Criterion) {
     let msg = b"This is a test message";
-    let g = UsualGenerator::generator();
-    let (_, usk) = usual_generate(&g);
+    let (_, usk) = usual_generate();
     c.bench_function(format!("Sign usual bls").as_str(), move |b| {
         b.iter(|| UsualSignature::new(&msg[..], None, &usk));
     });
 
-    let g = SmallGenerator::generator();
-    let (_, ssk) = small_generate(&g);
+    let (_, ssk) = small_generate();
     c.bench_function(format!("Sign small bls").as_str(), move |b| {
         b.iter(|| SmallSignature::new(&msg[..], None, &ssk));
     });
@@ -48,18 +45,16 @@ fn sign_benchmark(c: &mut Criterion) {
 
 fn verify_benchmark(c: &mut Criterion) {
     let msg = b"This is a test message to verify";
-    let g = UsualGenerator::generator();
-    let (upk, usk) = usual_generate(&g);
+    let (upk, usk) = usual_generate();
     let usg = UsualSignature::new(&msg[..], None, &usk);
     c.bench_function(format!("Verify usual bls").as_str(), move |b| {
-        b.iter(|| assert!(usg.verify(&msg[..], None, &upk, &g)));
+        b.iter(|| assert!(usg.verify(&msg[..], None, &upk)));
     });
 
-    let g = SmallGenerator::generator();
-    let (spk, ssk) = small_generate(&g);
+    let (spk, ssk) = small_generate();
     let ssg = SmallSignature::new(&msg[..], None, &ssk);
     c.bench_function(format!("Verify small bls").as_str(), move |b| {
-        b.iter(|| assert!(ssg.verify(&msg[..], None, &spk, &g)));
+        b.iter(|| assert!(ssg.verify(&msg[..], None, &spk)));
     });
 }
 
@@ -68,15 +63,14 @@ fn verify_aggregate_no_rk_benchmark(c: &mut Criterion) {
     let msg = b"This is a test message for aggregate signatures";
     let mut upks = Vec::new();
     let mut usig = Vec::new();
-    let g = UsualGenerator::generator();
     for _ in 0..MSG_COUNT {
-        let (pk, sk) = usual_generate(&g);
+        let (pk, sk) = usual_generate();
         let sig = UsualSignature::new(&msg[..], None, &sk);
         upks.push(pk);
         usig.push(sig);
     }
 
-    let uasg = UsualAggregatedSignature::new(usig.as_slice());
+    let uasg = UsualAggregatedSignature::new(usig.as_slice()).unwrap();
 
     c.bench_function(
         format!("Usual bls aggregate signatures no rogue key protection").as_str(),
@@ -88,20 +82,19 @@ fn verify_aggregate_no_rk_benchmark(c: &mut Criterion) {
     c.bench_function(
         format!("Usual bls aggregate signatures no rogue key protection verify").as_str(),
         move |b| {
-            b.iter(|| assert!(uasg.verify_no_rk(&msg[..], None, upks.as_slice(), &g)));
+            b.iter(|| assert!(uasg.verify_no_rk(&msg[..], None, upks.as_slice())));
         },
     );
 
-    let g = SmallGenerator::generator();
     let mut spks = Vec::new();
     let mut ssig = Vec::new();
     for _ in 0..MSG_COUNT {
-        let (pk, sk) = small_generate(&g);
+        let (pk, sk) = small_generate();
         let sig = SmallSignature::new(&msg[..], None, &sk);
         spks.push(pk);
         ssig.push(sig);
     }
-    let sasg = SmallAggregatedSignature::new(ssig.as_slice());
+    let sasg = SmallAggregatedSignature::new(ssig.as_slice()).unwrap();
 
     c.bench_function(
         format!("Small bls aggregate signatures no rogue key protection").as_str(),
@@ -113,28 +106,28 @@ fn verify_aggregate_no_rk_benchmark(c: &mut Criterion) {
     c.bench_function(
         format!("Small bls aggregate signatures no rogue key protection verify").as_str(),
         move |b| {
-            b.iter(|| assert!(sasg.verify_no_rk(&msg[..], None, spks.as_slice(), &g)));
+            b.iter(|| assert!(sasg.verify_no_rk(&msg[..], None, spks.as_slice())));
         },
     );
 }
 
 fn verify_aggregate_rk_benchmark(c: &mut Criterion) {
     const MSG_COUNT: usize = 10;
-    let g = UsualGenerator::generator();
     let msg = b"This is a test message for aggregate signatures with rogue key protection";
     let mut upks = Vec::new();
     let mut usks = Vec::new();
     for _ in 0..MSG_COUNT {
-        let (pk, sk) = usual_generate(&g);
+        let (pk, sk) = usual_generate();
         upks.push(pk);
         usks.push(sk);
     }
-    let uapk = UsualAggregatedPublicKey::new(upks.as_slice());
+    let uapk = UsualAggregatedPublicKey::new(upks.as_slice()).unwrap();
 
     let mut usig = Vec::new();
     for i in 0..MSG_COUNT {
         let sig =
-            UsualSignature::new_with_rk_mitigation(&msg[..], None, &usks[i], i, upks.as_slice());
+            UsualSignature::new_with_rk_mitigation(&msg[..], None, &usks[i], i, upks.as_slice())
+                .unwrap();
         usig.push(sig);
     }
 
@@ -147,30 +140,30 @@ fn verify_aggregate_rk_benchmark(c: &mut Criterion) {
         },
     );
 
-    let uasg = UsualAggregatedSignature::new(usig.as_slice());
+    let uasg = UsualAggregatedSignature::new(usig.as_slice()).unwrap();
 
     c.bench_function(
         format!("Usual bls aggregate signatures rogue key protection verify").as_str(),
         move |b| {
-            b.iter(|| assert!(uasg.verify(&msg[..], None, &uapk, &g)));
+            b.iter(|| assert!(uasg.verify(&msg[..], None, &uapk)));
         },
     );
 
-    let g = SmallGenerator::generator();
     let mut spks = Vec::new();
     let mut ssks = Vec::new();
     for _ in 0..MSG_COUNT {
-        let (pk, sk) = small_generate(&g);
+        let (pk, sk) = small_generate();
         spks.push(pk);
         ssks.push(sk);
     }
 
-    let sapk = SmallAggregatedPublicKey::new(spks.as_slice());
+    let sapk = SmallAggregatedPublicKey::new(spks.as_slice()).unwrap();
 
     let mut ssig = Vec::new();
     for i in 0..MSG_COUNT {
         let sig =
-            SmallSignature::new_with_rk_mitigation(&msg[..], None, &ssks[i], i, spks.as_slice());
+            SmallSignature::new_with_rk_mitigation(&msg[..], None, &ssks[i], i, spks.as_slice())
+                .unwrap();
         ssig.push(sig);
     }
 
@@ -183,12 +176,12 @@ fn verify_aggregate_rk_benchmark(c: &mut Criterion) {
         },
     );
 
-    let sasg = SmallAggregatedSignature::new(ssig.as_slice());
+    let sasg = SmallAggregatedSignature::new(ssig.as_slice()).unwrap();
 
     c.bench_function(
         format!("Small bls aggregate signatures rogue key protection verify").as_str(),
         move |b| {
-            b.iter(|| assert!(sasg.verify(&msg[..], None, &sapk, &g)));
+            b.iter(|| assert!(sasg.verify(&msg[..], None, &sapk)));
         },
     );
 }
@@ -196,12 +189,11 @@ fn verify_aggregate_rk_benchmark(c: &mut Criterion) {
 fn verify_multisig(c: &mut Criterion) {
     const MSG_COUNT: usize = 10;
 
-    let g = UsualGenerator::generator();
     let mut usgs = Vec::new();
     let mut msgs = Vec::new();
 
     for _ in 0..MSG_COUNT {
-        let (pk, sk) = usual_generate(&g);
+        let (pk, sk) = usual_generate();
         let msg = FieldElement::random();
         let sig = UsualSignature::new(msg.to_bytes().as_slice(), None, &sk);
         usgs.push(sig);
@@ -218,16 +210,15 @@ fn verify_multisig(c: &mut Criterion) {
                 .iter()
                 .map(|(m, p)| (m.as_slice(), p))
                 .collect::<Vec<(&[u8], &UsualPublicKey)>>();
-            b.iter(|| assert!(usig.verify_multi(refs.as_slice(), None, &g)));
+            b.iter(|| assert!(usig.verify_multi(refs.as_slice(), None)));
         },
     );
 
-    let g = SmallGenerator::generator();
     let mut ssgs = Vec::new();
     let mut msgs = Vec::new();
 
     for _ in 0..MSG_COUNT {
-        let (pk, sk) = small_generate(&g);
+        let (pk, sk) = small_generate();
         let msg = FieldElement::random();
         let sig = SmallSignature::new(msg.to_bytes().as_slice(), None, &sk);
         ssgs.push(sig);
@@ -244,7 +235,47 @@ fn verify_multisig(c: &mut Criterion) {
                 .iter()
                 .map(|(m, p)| (m.as_slice(), p))
                 .collect::<Vec<(&[u8], &SmallPublicKey)>>();
-            b.iter(|| assert!(ssig.verify_multi(refs.as_slice(), None, &g)));
+            b.iter(|| assert!(ssig.verify_multi(refs.as_slice(), None)));
+        },
+    );
+}
+
+fn batch_verify_benchmark(c: &mut Criterion) {
+    const SIG_COUNT: usize = 1000;
+
+    let mut inputs = Vec::new();
+    for _ in 0..SIG_COUNT {
+        let (pk, sk) = usual_generate();
+        let msg = FieldElement::random().to_bytes();
+        let sig = UsualSignature::new(msg.as_slice(), None, &sk);
+        inputs.push((msg, sig, pk));
+    }
+
+    let batch = inputs
+        .iter()
+        .map(|(m, s, p)| (m.as_slice(), s, p))
+        .collect::<Vec<(&[u8], &UsualSignature, &UsualPublicKey)>>();
+    c.bench_function(
+        format!("Usual bls batch verify {} signatures", SIG_COUNT).as_str(),
+        |b| b.iter(|| assert!(UsualSignature::batch_verify(&batch, None))),
+    );
+    let mut scratch = Scratch::new();
+    let mut rng = rand::rngs::OsRng;
+    c.bench_function(
+        format!(
+            "Usual bls batch verify {} signatures with scratch",
+            SIG_COUNT
+        )
+        .as_str(),
+        |b| {
+            b.iter(|| {
+                assert!(UsualSignature::batch_verify_with_scratch(
+                    &batch,
+                    None,
+                    &mut rng,
+                    &mut scratch
+                ))
+            })
         },
     );
 }
@@ -252,7 +283,7 @@ fn verify_multisig(c: &mut Criterion) {
 criterion_group!(
     name = bench_bls;
     config = Criterion::default();
-    targets = keypair_benchmark, sign_benchmark, verify_benchmark, verify_aggregate_no_rk_benchmark, verify_aggregate_rk_benchmark, verify_multisig
+    targets = keypair_benchmark, sign_benchmark, verify_benchmark, verify_aggregate_no_rk_benchmark, verify_aggregate_rk_benchmark, verify_multisig, batch_verify_benchmark
 );
 
 criterion_main!(bench_bls);
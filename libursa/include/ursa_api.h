@@ -0,0 +1,78 @@
+#ifndef __ursa__api__included__
+#define __ursa__api__included__
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The version of the API declared here */
+#define URSA_API_VERSION 1
+
+/* ExternError codes */
+#define URSA_API_SUCCESS 0
+#define URSA_API_NO_SUCH_ALGORITHM 1
+#define URSA_API_PARSE_ERROR 2
+#define URSA_API_SIGNING_ERROR 3
+#define URSA_API_KEY_GEN_ERROR 4
+#define URSA_API_DIGEST_GEN_ERROR 5
+#define URSA_API_GENERAL_ERROR 6
+#define URSA_API_INVALID_PARAM 7
+#define URSA_API_INVALID_GROUP_ELEMENT 8
+#define URSA_API_PANIC -1
+#define URSA_API_INVALID_HANDLE -1000
+
+/* Input bytes, owned by the caller */
+struct ByteArray {
+    size_t length;
+    const uint8_t *data;
+};
+
+extern uint32_t ursa_api_version(void);
+
+/* Comma separated algorithm names, free with ursa_string_free */
+extern char* ursa_algorithms(struct ExternError* err);
+
+/* Keypair handles, free with ursa_keypair_free */
+extern uint64_t ursa_keypair_generate(const char* algorithm,
+                                      struct ExternError* err);
+
+extern uint64_t ursa_keypair_from_seed(const char* algorithm,
+                                       const struct ByteArray* seed,
+                                       struct ExternError* err);
+
+extern uint64_t ursa_keypair_from_private_key(const char* algorithm,
+                                              const struct ByteArray* private_key,
+                                              struct ExternError* err);
+
+extern void ursa_keypair_free(uint64_t handle, struct ExternError* err);
+
+extern char* ursa_keypair_algorithm(uint64_t handle, struct ExternError* err);
+
+extern struct ByteBuffer ursa_keypair_public_key(uint64_t handle,
+                                                 struct ExternError* err);
+
+extern struct ByteBuffer ursa_keypair_private_key(uint64_t handle,
+                                                  struct ExternError* err);
+
+extern struct ByteBuffer ursa_keypair_sign(uint64_t handle,
+                                           const struct ByteArray* message,
+                                           struct ExternError* err);
+
+extern struct ByteBuffer ursa_keypair_key_exchange(uint64_t handle,
+                                                   const struct ByteArray* peer_public_key,
+                                                   struct ExternError* err);
+
+/* 1 if the signature is valid, 0 otherwise */
+extern uint8_t ursa_verify(const char* algorithm,
+                           const struct ByteArray* public_key,
+                           const struct ByteArray* message,
+                           const struct ByteArray* signature,
+                           struct ExternError* err);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -31,6 +31,7 @@ struct ExternError {
 extern void ursa_bytebuffer_free(struct ByteBuffer buffer);
 extern void ursa_string_free(char *s);
 
+#include "ursa_api.h"
 #include "ursa_crypto_ed25519.h"
 #include "ursa_crypto_encryption.h"
 
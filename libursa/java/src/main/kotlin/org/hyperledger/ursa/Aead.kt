@@ -0,0 +1,27 @@
+package org.hyperledger.ursa
+
+/** The ciphers, named as in `EncryptorType` */
+enum class Cipher(val id: String) {
+    AES_128_GCM("aes-128-gcm"),
+    AES_256_GCM("aes-256-gcm"),
+    AES_128_CBC_HMAC_256("aes-128-cbc-hmac-256"),
+    AES_256_CBC_HMAC_512("aes-256-cbc-hmac-512"),
+    XCHACHA20_POLY1305("xchacha20poly1305"),
+}
+
+/** Authenticated encryption with `key`. The nonce is prepended to each ciphertext. */
+class Aead(val cipher: Cipher, private val key: ByteArray) {
+    @JvmOverloads
+    fun encrypt(plaintext: ByteArray, aad: ByteArray = ByteArray(0)): ByteArray =
+        UrsaNative.encrypt(cipher.id, key, aad, plaintext)
+
+    @JvmOverloads
+    fun decrypt(ciphertext: ByteArray, aad: ByteArray = ByteArray(0)): ByteArray =
+        UrsaNative.decrypt(cipher.id, key, aad, ciphertext)
+
+    companion object {
+        /** A cipher with a random key */
+        @JvmStatic
+        fun generate(cipher: Cipher): Aead = Aead(cipher, UrsaNative.generateKey(cipher.id))
+    }
+}
@@ -0,0 +1,46 @@
+package org.hyperledger.ursa
+
+/** The key algorithms, named as in `registry::Algorithm` */
+enum class Algorithm(val id: String) {
+    ED25519("ed25519"),
+    ECDSA_SECP256K1("ecdsa_secp256k1"),
+    X25519("x25519"),
+    ECDH_SECP256K1("ecdh_secp256k1"),
+}
+
+class PublicKey(val algorithm: Algorithm, val bytes: ByteArray) {
+    fun verify(message: ByteArray, signature: ByteArray): Boolean =
+        UrsaNative.verify(algorithm.id, bytes, message, signature)
+}
+
+class KeyPair private constructor(
+    val algorithm: Algorithm,
+    val publicKey: PublicKey,
+    private val privateKey: ByteArray
+) {
+    fun sign(message: ByteArray): ByteArray = UrsaNative.sign(algorithm.id, privateKey, message)
+
+    fun keyExchange(peer: PublicKey): ByteArray =
+        UrsaNative.keyExchange(algorithm.id, privateKey, peer.bytes)
+
+    /** A copy of the private key, for storing it */
+    fun exportPrivateKey(): ByteArray = privateKey.copyOf()
+
+    /** Overwrite the private key, the keypair can't be used afterwards */
+    fun wipe() = privateKey.fill(0)
+
+    companion object {
+        @JvmStatic
+        @JvmOverloads
+        fun generate(algorithm: Algorithm, seed: ByteArray? = null): KeyPair {
+            val (pk, sk) = UrsaNative.generateKeyPair(algorithm.id, seed)
+            return KeyPair(algorithm, PublicKey(algorithm, pk), sk)
+        }
+
+        @JvmStatic
+        fun fromPrivateKey(algorithm: Algorithm, privateKey: ByteArray): KeyPair {
+            val pk = UrsaNative.publicKey(algorithm.id, privateKey)
+            return KeyPair(algorithm, PublicKey(algorithm, pk), privateKey.copyOf())
+        }
+    }
+}
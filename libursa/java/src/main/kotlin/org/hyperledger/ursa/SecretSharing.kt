@@ -0,0 +1,13 @@
+package org.hyperledger.ursa
+
+/**
+ * Shamir secret sharing in the field of `prime`, given as big endian bytes.
+ * The prime must be larger than the secrets.
+ */
+class SecretSharing(private val prime: ByteArray) {
+    fun split(secret: ByteArray, threshold: Int, total: Int): List<ByteArray> =
+        UrsaNative.splitSecret(secret, threshold, total, prime).toList()
+
+    fun combine(shares: List<ByteArray>): ByteArray =
+        UrsaNative.combineShares(shares.toTypedArray(), prime)
+}
@@ -0,0 +1,4 @@
+package org.hyperledger.ursa
+
+/** Thrown by the native methods for any error from libursa */
+class UrsaException(message: String) : Exception(message)
@@ -0,0 +1,52 @@
+package org.hyperledger.ursa
+
+/**
+ * The native methods exported by libursa when built with the `java` feature.
+ * Prefer the wrappers in this package, which keep the algorithm with the keys.
+ */
+object UrsaNative {
+    init {
+        System.loadLibrary("ursa")
+    }
+
+    /** Returns `[publicKey, privateKey]`. The keys are random when `seed` is null. */
+    @JvmStatic
+    external fun generateKeyPair(algorithm: String, seed: ByteArray?): Array<ByteArray>
+
+    @JvmStatic
+    external fun publicKey(algorithm: String, privateKey: ByteArray): ByteArray
+
+    @JvmStatic
+    external fun sign(algorithm: String, privateKey: ByteArray, message: ByteArray): ByteArray
+
+    @JvmStatic
+    external fun verify(
+        algorithm: String,
+        publicKey: ByteArray,
+        message: ByteArray,
+        signature: ByteArray
+    ): Boolean
+
+    @JvmStatic
+    external fun keyExchange(
+        algorithm: String,
+        privateKey: ByteArray,
+        peerPublicKey: ByteArray
+    ): ByteArray
+
+    @JvmStatic
+    external fun generateKey(cipher: String): ByteArray
+
+    /** Returns the random nonce followed by the ciphertext */
+    @JvmStatic
+    external fun encrypt(cipher: String, key: ByteArray, aad: ByteArray, plaintext: ByteArray): ByteArray
+
+    @JvmStatic
+    external fun decrypt(cipher: String, key: ByteArray, aad: ByteArray, ciphertext: ByteArray): ByteArray
+
+    @JvmStatic
+    external fun splitSecret(secret: ByteArray, threshold: Int, total: Int, prime: ByteArray): Array<ByteArray>
+
+    @JvmStatic
+    external fun combineShares(shares: Array<ByteArray>, prime: ByteArray): ByteArray
+}
@@ -0,0 +1,70 @@
+//! Key backends keep private keys away from the caller, e.g. in an HSM, and
+//! perform operations on them by handle.
+//!
+//! [`SoftwareBackend`](software/struct.SoftwareBackend.html) holds keys in
+//! memory and uses the schemes in this crate. With the `pkcs11` feature,
+//! [`Pkcs11Backend`](pkcs11/struct.Pkcs11Backend.html) routes operations to
+//! a PKCS#11 token. Signatures and shared secrets from either backend match
+//! what `SignatureScheme` and `KeyExchangeScheme` produce for the same key.
+
+#[cfg(feature = "pkcs11")]
+pub mod pkcs11;
+pub mod software;
+
+pub mod prelude {
+    #[cfg(feature = "pkcs11")]
+    pub use super::pkcs11::Pkcs11Backend;
+    pub use super::software::SoftwareBackend;
+    pub use super::{BackendSigner, KeyBackend};
+}
+
+pub use encoding::pkcs8::KeyAlgorithm;
+use keys::{PublicKey, SessionKey};
+use CryptoError;
+
+pub trait KeyBackend {
+    /// Refers to a key held by the backend
+    type KeyHandle: Clone;
+
+    /// Generate a key for `algorithm` stored under `label`
+    fn generate(
+        &self,
+        algorithm: KeyAlgorithm,
+        label: &str,
+    ) -> Result<Self::KeyHandle, CryptoError>;
+    /// Look up a key previously stored under `label`
+    fn find(&self, label: &str) -> Result<Option<Self::KeyHandle>, CryptoError>;
+    fn public_key(&self, key: &Self::KeyHandle) -> Result<PublicKey, CryptoError>;
+    /// Sign `message` with a signing key
+    fn sign(&self, key: &Self::KeyHandle, message: &[u8]) -> Result<Vec<u8>, CryptoError>;
+    /// Compute the shared secret of a key exchange key and `remote_public_key`
+    fn derive(
+        &self,
+        key: &Self::KeyHandle,
+        remote_public_key: &PublicKey,
+    ) -> Result<SessionKey, CryptoError>;
+}
+
+/// The backend counterpart of [`Signer`](../signatures/struct.Signer.html)
+pub struct BackendSigner<'a, B: 'a + KeyBackend> {
+    backend: &'a B,
+    key: B::KeyHandle,
+}
+
+impl<'a, B: 'a + KeyBackend> BackendSigner<'a, B> {
+    pub fn new(backend: &'a B, key: B::KeyHandle) -> Self {
+        BackendSigner { backend, key }
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        self.backend.sign(&self.key, message)
+    }
+
+    pub fn get_public_key(&self) -> Result<PublicKey, CryptoError> {
+        self.backend.public_key(&self.key)
+    }
+}
+
+fn unsupported(algorithm: KeyAlgorithm, operation: &str) -> CryptoError {
+    CryptoError::NoSuchAlgorithm(format!("{:?} keys cannot {}", algorithm, operation))
+}
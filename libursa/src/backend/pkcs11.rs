@@ -0,0 +1,351 @@
+//! Keys held by a PKCS#11 token.
+//!
+//! Ed25519 uses `CKM_EDDSA` and X25519 uses `CKM_ECDH1_DERIVE` as defined by
+//! PKCS#11 3.0. secp256k1 keys sign with `CKM_ECDSA` over the SHA-256 digest
+//! and the signature is normalized to low S, as the software scheme does.
+//! Shared secrets are hashed with SHA-256 like `X25519Sha256`.
+//! secp256k1 key exchange is not supported because `CKD_NULL` only returns the
+//! x coordinate, not the point the software scheme hashes.
+
+use super::{unsupported, KeyAlgorithm, KeyBackend};
+use encoding::der::{self, DerReader};
+use keys::{PublicKey, SessionKey};
+use rustpkcs11::types::*;
+use rustpkcs11::Ctx;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::Mutex;
+use std::{mem, ptr};
+use zeroize::Zeroize;
+use CryptoError;
+
+// PKCS#11 3.0 values missing from the 2.40 headers
+const CKK_EC_EDWARDS: CK_KEY_TYPE = 0x40;
+const CKK_EC_MONTGOMERY: CK_KEY_TYPE = 0x41;
+const CKM_EC_EDWARDS_KEY_PAIR_GEN: CK_MECHANISM_TYPE = 0x1055;
+const CKM_EC_MONTGOMERY_KEY_PAIR_GEN: CK_MECHANISM_TYPE = 0x1056;
+const CKM_EDDSA: CK_MECHANISM_TYPE = 0x1057;
+
+/// DER encoded curve OIDs for `CKA_EC_PARAMS`
+const EC_PARAMS_ED25519: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x70];
+const EC_PARAMS_X25519: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x6e];
+const EC_PARAMS_SECP256K1: &[u8] = &[0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x0a];
+
+/// A key pair on the token
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Pkcs11KeyHandle {
+    pub algorithm: KeyAlgorithm,
+    pub private_key: CK_OBJECT_HANDLE,
+    pub public_key: CK_OBJECT_HANDLE,
+}
+
+/// A logged in session on one slot of a PKCS#11 module
+pub struct Pkcs11Backend {
+    ctx: Ctx,
+    session: CK_SESSION_HANDLE,
+    /// PKCS#11 sessions must not be used by two threads at once
+    lock: Mutex<()>,
+}
+
+impl Pkcs11Backend {
+    /// Load the module at `module`, open a read/write session on `slot`
+    /// and log in with `pin`
+    pub fn open<P: AsRef<Path>>(
+        module: P,
+        slot: CK_SLOT_ID,
+        pin: &str,
+    ) -> Result<Self, CryptoError> {
+        let ctx = Ctx::new_and_initialize(module.as_ref()).map_err(p11_error)?;
+        let session = ctx
+            .open_session(slot, CKF_SERIAL_SESSION | CKF_RW_SESSION, None, None)
+            .map_err(p11_error)?;
+        ctx.login(session, CKU_USER, Some(pin)).map_err(p11_error)?;
+        Ok(Pkcs11Backend {
+            ctx,
+            session,
+            lock: Mutex::new(()),
+        })
+    }
+
+    fn find_object(
+        &self,
+        class: CK_OBJECT_CLASS,
+        label: &str,
+    ) -> Result<Option<CK_OBJECT_HANDLE>, CryptoError> {
+        let label = label.to_string();
+        let template = vec![
+            CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&class),
+            CK_ATTRIBUTE::new(CKA_LABEL).with_string(&label),
+        ];
+        self.ctx
+            .find_objects_init(self.session, &template)
+            .map_err(p11_error)?;
+        let found = self.ctx.find_objects(self.session, 1);
+        self.ctx
+            .find_objects_final(self.session)
+            .map_err(p11_error)?;
+        Ok(found.map_err(p11_error)?.first().cloned())
+    }
+
+    fn key_algorithm(&self, key: CK_OBJECT_HANDLE) -> Result<KeyAlgorithm, CryptoError> {
+        let key_type = self.get_attribute(key, CKA_KEY_TYPE)?;
+        if key_type.len() != mem::size_of::<CK_KEY_TYPE>() {
+            return Err(CryptoError::ParseError(
+                "Invalid PKCS#11 key type".to_string(),
+            ));
+        }
+        let key_type = unsafe { ptr::read_unaligned(key_type.as_ptr() as *const CK_KEY_TYPE) };
+        let params = self.get_attribute(key, CKA_EC_PARAMS)?;
+        match (key_type, &params[..]) {
+            (CKK_EC_EDWARDS, EC_PARAMS_ED25519) => Ok(KeyAlgorithm::Ed25519),
+            (CKK_EC_MONTGOMERY, EC_PARAMS_X25519) => Ok(KeyAlgorithm::X25519),
+            (CKK_EC, EC_PARAMS_SECP256K1) => Ok(KeyAlgorithm::EcSecp256k1),
+            _ => Err(CryptoError::NoSuchAlgorithm(
+                "Unsupported PKCS#11 key type".to_string(),
+            )),
+        }
+    }
+
+    fn get_attribute(
+        &self,
+        object: CK_OBJECT_HANDLE,
+        attribute: CK_ATTRIBUTE_TYPE,
+    ) -> Result<Vec<u8>, CryptoError> {
+        // The first call reads the length, the second the value
+        let mut template = vec![CK_ATTRIBUTE::new(attribute)];
+        self.ctx
+            .get_attribute_value(self.session, object, &mut template)
+            .map_err(p11_error)?;
+        let value = vec![0u8; template[0].ulValueLen as usize];
+        let mut template = vec![CK_ATTRIBUTE::new(attribute).with_bytes(&value)];
+        self.ctx
+            .get_attribute_value(self.session, object, &mut template)
+            .map_err(p11_error)?;
+        Ok(value)
+    }
+}
+
+impl KeyBackend for Pkcs11Backend {
+    type KeyHandle = Pkcs11KeyHandle;
+
+    fn generate(
+        &self,
+        algorithm: KeyAlgorithm,
+        label: &str,
+    ) -> Result<Pkcs11KeyHandle, CryptoError> {
+        let (mechanism, key_type, params, sign) = match algorithm {
+            KeyAlgorithm::Ed25519 => (
+                CKM_EC_EDWARDS_KEY_PAIR_GEN,
+                CKK_EC_EDWARDS,
+                EC_PARAMS_ED25519,
+                true,
+            ),
+            KeyAlgorithm::X25519 => (
+                CKM_EC_MONTGOMERY_KEY_PAIR_GEN,
+                CKK_EC_MONTGOMERY,
+                EC_PARAMS_X25519,
+                false,
+            ),
+            KeyAlgorithm::EcSecp256k1 => (CKM_EC_KEY_PAIR_GEN, CKK_EC, EC_PARAMS_SECP256K1, true),
+        };
+        let _lock = self.lock.lock().unwrap();
+        if self.find_object(CKO_PRIVATE_KEY, label)?.is_some() {
+            return Err(CryptoError::KeyGenError(format!(
+                "A key labelled {} already exists",
+                label
+            )));
+        }
+        let mechanism = CK_MECHANISM {
+            mechanism,
+            pParameter: ptr::null_mut(),
+            ulParameterLen: 0,
+        };
+        let label = label.to_string();
+        let (can_sign, can_derive) = if sign {
+            (CK_TRUE, CK_FALSE)
+        } else {
+            (CK_FALSE, CK_TRUE)
+        };
+        let public_template = vec![
+            CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_KEY_TYPE).with_ck_ulong(&key_type),
+            CK_ATTRIBUTE::new(CKA_EC_PARAMS).with_bytes(params),
+            CK_ATTRIBUTE::new(CKA_VERIFY).with_bool(&can_sign),
+            CK_ATTRIBUTE::new(CKA_LABEL).with_string(&label),
+        ];
+        let private_template = vec![
+            CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_PRIVATE).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_SENSITIVE).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_EXTRACTABLE).with_bool(&CK_FALSE),
+            CK_ATTRIBUTE::new(CKA_SIGN).with_bool(&can_sign),
+            CK_ATTRIBUTE::new(CKA_DERIVE).with_bool(&can_derive),
+            CK_ATTRIBUTE::new(CKA_LABEL).with_string(&label),
+        ];
+        let (public_key, private_key) = self
+            .ctx
+            .generate_key_pair(
+                self.session,
+                &mechanism,
+                &public_template,
+                &private_template,
+            )
+            .map_err(p11_error)?;
+        Ok(Pkcs11KeyHandle {
+            algorithm,
+            private_key,
+            public_key,
+        })
+    }
+
+    fn find(&self, label: &str) -> Result<Option<Pkcs11KeyHandle>, CryptoError> {
+        let _lock = self.lock.lock().unwrap();
+        let private_key = match self.find_object(CKO_PRIVATE_KEY, label)? {
+            Some(k) => k,
+            None => return Ok(None),
+        };
+        let public_key = self.find_object(CKO_PUBLIC_KEY, label)?.ok_or_else(|| {
+            CryptoError::GeneralError(format!("No public key labelled {}", label))
+        })?;
+        Ok(Some(Pkcs11KeyHandle {
+            algorithm: self.key_algorithm(private_key)?,
+            private_key,
+            public_key,
+        }))
+    }
+
+    fn public_key(&self, key: &Pkcs11KeyHandle) -> Result<PublicKey, CryptoError> {
+        let point = {
+            let _lock = self.lock.lock().unwrap();
+            self.get_attribute(key.public_key, CKA_EC_POINT)?
+        };
+        // CKA_EC_POINT is usually an OCTET STRING, some tokens return the raw point
+        let raw = match DerReader::new(&point).read(der::TAG_OCTET_STRING) {
+            Ok(inner) => inner.to_vec(),
+            Err(_) => point.clone(),
+        };
+        match key.algorithm {
+            KeyAlgorithm::EcSecp256k1 => secp256k1::parse(&raw),
+            _ => Ok(PublicKey(raw)),
+        }
+    }
+
+    fn sign(&self, key: &Pkcs11KeyHandle, message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let (mechanism, data) = match key.algorithm {
+            KeyAlgorithm::Ed25519 => (CKM_EDDSA, message.to_vec()),
+            KeyAlgorithm::EcSecp256k1 => (CKM_ECDSA, Sha256::digest(message).to_vec()),
+            a => return Err(unsupported(a, "sign")),
+        };
+        let mechanism = CK_MECHANISM {
+            mechanism,
+            pParameter: ptr::null_mut(),
+            ulParameterLen: 0,
+        };
+        let mut signature = {
+            let _lock = self.lock.lock().unwrap();
+            self.ctx
+                .sign_init(self.session, &mechanism, key.private_key)
+                .map_err(p11_error)?;
+            self.ctx.sign(self.session, &data).map_err(p11_error)?
+        };
+        if key.algorithm == KeyAlgorithm::EcSecp256k1 {
+            secp256k1::normalize_s(&mut signature)?;
+        }
+        Ok(signature)
+    }
+
+    fn derive(
+        &self,
+        key: &Pkcs11KeyHandle,
+        remote_public_key: &PublicKey,
+    ) -> Result<SessionKey, CryptoError> {
+        if key.algorithm != KeyAlgorithm::X25519 {
+            return Err(unsupported(key.algorithm, "derive"));
+        }
+        let mut params = CK_ECDH1_DERIVE_PARAMS {
+            kdf: CKD_NULL,
+            ulSharedDataLen: 0,
+            pSharedData: ptr::null_mut(),
+            ulPublicDataLen: remote_public_key.len() as CK_ULONG,
+            pPublicData: remote_public_key[..].as_ptr() as CK_BYTE_PTR,
+        };
+        let mechanism = CK_MECHANISM {
+            mechanism: CKM_ECDH1_DERIVE,
+            pParameter: &mut params as *mut CK_ECDH1_DERIVE_PARAMS as CK_VOID_PTR,
+            ulParameterLen: std::mem::size_of::<CK_ECDH1_DERIVE_PARAMS>() as CK_ULONG,
+        };
+        let value_len: CK_ULONG = 32;
+        let template = vec![
+            CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&CKO_SECRET_KEY),
+            CK_ATTRIBUTE::new(CKA_KEY_TYPE).with_ck_ulong(&CKK_GENERIC_SECRET),
+            CK_ATTRIBUTE::new(CKA_VALUE_LEN).with_ck_ulong(&value_len),
+            CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_FALSE),
+            CK_ATTRIBUTE::new(CKA_SENSITIVE).with_bool(&CK_FALSE),
+            CK_ATTRIBUTE::new(CKA_EXTRACTABLE).with_bool(&CK_TRUE),
+        ];
+        let _lock = self.lock.lock().unwrap();
+        let secret = self
+            .ctx
+            .derive_key(self.session, &mechanism, key.private_key, &template)
+            .map_err(p11_error)?;
+        let value = self.get_attribute(secret, CKA_VALUE);
+        self.ctx
+            .destroy_object(self.session, secret)
+            .map_err(p11_error)?;
+        let mut value = value?;
+        let hash = Sha256::digest(&value);
+        value.zeroize();
+        Ok(SessionKey(hash.to_vec()))
+    }
+}
+
+impl Drop for Pkcs11Backend {
+    fn drop(&mut self) {
+        let _ = self.ctx.logout(self.session);
+        let _ = self.ctx.close_session(self.session);
+    }
+}
+
+/// secp256k1 keys go through the software scheme when it is compiled in
+#[cfg(any(
+    feature = "ecdsa_secp256k1",
+    feature = "ecdsa_secp256k1_native",
+    feature = "ecdsa_secp256k1_asm"
+))]
+mod secp256k1 {
+    use keys::PublicKey;
+    use signatures::secp256k1::EcdsaSecp256k1Sha256;
+    use signatures::{EcdsaPublicKeyHandler, SignatureScheme};
+    use CryptoError;
+
+    pub fn parse(raw: &[u8]) -> Result<PublicKey, CryptoError> {
+        EcdsaSecp256k1Sha256::new().parse(raw)
+    }
+
+    pub fn normalize_s(signature: &mut [u8]) -> Result<(), CryptoError> {
+        EcdsaSecp256k1Sha256::new().normalize_s(signature)
+    }
+}
+
+#[cfg(not(any(
+    feature = "ecdsa_secp256k1",
+    feature = "ecdsa_secp256k1_native",
+    feature = "ecdsa_secp256k1_asm"
+)))]
+mod secp256k1 {
+    use super::{unsupported, KeyAlgorithm};
+    use keys::PublicKey;
+    use CryptoError;
+
+    pub fn parse(_: &[u8]) -> Result<PublicKey, CryptoError> {
+        Err(unsupported(KeyAlgorithm::EcSecp256k1, "be parsed"))
+    }
+
+    pub fn normalize_s(_: &mut [u8]) -> Result<(), CryptoError> {
+        Err(unsupported(KeyAlgorithm::EcSecp256k1, "sign"))
+    }
+}
+
+fn p11_error(e: ::rustpkcs11::errors::Error) -> CryptoError {
+    CryptoError::GeneralError(format!("PKCS#11 error: {}", e))
+}
@@ -0,0 +1,204 @@
+use super::{unsupported, KeyAlgorithm, KeyBackend};
+use keys::{KeyGenOption, PrivateKey, PublicKey, SessionKey};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use CryptoError;
+
+#[cfg(any(
+    feature = "ecdh_secp256k1",
+    feature = "ecdh_secp256k1_native",
+    feature = "ecdh_secp256k1_asm",
+    feature = "x25519",
+    feature = "x25519_asm"
+))]
+use kex::KeyExchangeScheme;
+#[cfg(any(
+    feature = "ed25519",
+    feature = "ed25519_asm",
+    feature = "ecdsa_secp256k1",
+    feature = "ecdsa_secp256k1_native",
+    feature = "ecdsa_secp256k1_asm"
+))]
+use signatures::SignatureScheme;
+
+/// Holds keys in process memory, keyed by label
+#[derive(Default)]
+pub struct SoftwareBackend {
+    keys: Mutex<BTreeMap<String, (KeyAlgorithm, PrivateKey)>>,
+}
+
+impl SoftwareBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store an existing private key under `label`
+    pub fn import(
+        &self,
+        algorithm: KeyAlgorithm,
+        label: &str,
+        sk: PrivateKey,
+    ) -> Result<String, CryptoError> {
+        keypair(algorithm, Some(KeyGenOption::FromSecretKey(sk.clone())))?;
+        let mut keys = self.keys.lock().unwrap();
+        if keys.contains_key(label) {
+            return Err(CryptoError::KeyGenError(format!(
+                "A key labelled {} already exists",
+                label
+            )));
+        }
+        keys.insert(label.to_string(), (algorithm, sk));
+        Ok(label.to_string())
+    }
+
+    fn with_key<T, F>(&self, label: &str, f: F) -> Result<T, CryptoError>
+    where
+        F: FnOnce(KeyAlgorithm, &PrivateKey) -> Result<T, CryptoError>,
+    {
+        let keys = self.keys.lock().unwrap();
+        match keys.get(label) {
+            Some((algorithm, sk)) => f(*algorithm, sk),
+            None => Err(CryptoError::GeneralError(format!(
+                "No key labelled {}",
+                label
+            ))),
+        }
+    }
+}
+
+impl KeyBackend for SoftwareBackend {
+    /// The label of the key
+    type KeyHandle = String;
+
+    fn generate(&self, algorithm: KeyAlgorithm, label: &str) -> Result<String, CryptoError> {
+        let (_, sk) = keypair(algorithm, None)?;
+        self.import(algorithm, label, sk)
+    }
+
+    fn find(&self, label: &str) -> Result<Option<String>, CryptoError> {
+        Ok(self
+            .keys
+            .lock()
+            .unwrap()
+            .get(label)
+            .map(|_| label.to_string()))
+    }
+
+    fn public_key(&self, key: &String) -> Result<PublicKey, CryptoError> {
+        self.with_key(key, |algorithm, sk| {
+            keypair(algorithm, Some(KeyGenOption::FromSecretKey(sk.clone()))).map(|(pk, _)| pk)
+        })
+    }
+
+    #[allow(unreachable_patterns)]
+    fn sign(&self, key: &String, message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        self.with_key(key, |algorithm, sk| match algorithm {
+            #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+            KeyAlgorithm::Ed25519 => ::signatures::ed25519::Ed25519Sha512::new().sign(message, sk),
+            #[cfg(any(
+                feature = "ecdsa_secp256k1",
+                feature = "ecdsa_secp256k1_native",
+                feature = "ecdsa_secp256k1_asm"
+            ))]
+            KeyAlgorithm::EcSecp256k1 => {
+                ::signatures::secp256k1::EcdsaSecp256k1Sha256::new().sign(message, sk)
+            }
+            a => Err(unsupported(a, "sign")),
+        })
+    }
+
+    #[allow(unreachable_patterns)]
+    fn derive(
+        &self,
+        key: &String,
+        remote_public_key: &PublicKey,
+    ) -> Result<SessionKey, CryptoError> {
+        self.with_key(key, |algorithm, sk| match algorithm {
+            #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+            KeyAlgorithm::X25519 => {
+                ::kex::x25519::X25519Sha256::new().compute_shared_secret(sk, remote_public_key)
+            }
+            #[cfg(any(
+                feature = "ecdh_secp256k1",
+                feature = "ecdh_secp256k1_native",
+                feature = "ecdh_secp256k1_asm"
+            ))]
+            KeyAlgorithm::EcSecp256k1 => ::kex::secp256k1::EcdhSecp256k1Sha256::new()
+                .compute_shared_secret(sk, remote_public_key),
+            a => Err(unsupported(a, "derive")),
+        })
+    }
+}
+
+#[allow(unreachable_patterns)]
+fn keypair(
+    algorithm: KeyAlgorithm,
+    option: Option<KeyGenOption>,
+) -> Result<(PublicKey, PrivateKey), CryptoError> {
+    match algorithm {
+        #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+        KeyAlgorithm::Ed25519 => ::signatures::ed25519::Ed25519Sha512::new().keypair(option),
+        #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+        KeyAlgorithm::X25519 => ::kex::x25519::X25519Sha256::new().keypair(option),
+        #[cfg(any(
+            feature = "ecdsa_secp256k1",
+            feature = "ecdsa_secp256k1_native",
+            feature = "ecdsa_secp256k1_asm"
+        ))]
+        KeyAlgorithm::EcSecp256k1 => {
+            ::signatures::secp256k1::EcdsaSecp256k1Sha256::new().keypair(option)
+        }
+        a => Err(unsupported(a, "be generated")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backend::BackendSigner;
+
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    #[test]
+    fn software_signing() {
+        use signatures::{ed25519::Ed25519Sha512, SignatureScheme};
+
+        let backend = SoftwareBackend::new();
+        let key = backend.generate(KeyAlgorithm::Ed25519, "signing").unwrap();
+        assert!(backend.generate(KeyAlgorithm::Ed25519, "signing").is_err());
+        assert_eq!(Some(key.clone()), backend.find("signing").unwrap());
+        assert_eq!(None, backend.find("missing").unwrap());
+
+        let signer = BackendSigner::new(&backend, key.clone());
+        let signature = signer.sign(b"message").unwrap();
+        let pk = signer.get_public_key().unwrap();
+        assert!(Ed25519Sha512::new()
+            .verify(b"message", &signature, &pk)
+            .unwrap());
+
+        let (_, sk) = Ed25519Sha512::new().keypair(None).unwrap();
+        let imported = backend
+            .import(KeyAlgorithm::Ed25519, "imported", sk.clone())
+            .unwrap();
+        assert_eq!(
+            Ed25519Sha512::new().sign(b"message", &sk).unwrap(),
+            backend.sign(&imported, b"message").unwrap()
+        );
+        assert!(backend.derive(&key, &pk).is_err());
+    }
+
+    #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+    #[test]
+    fn software_key_exchange() {
+        use kex::{x25519::X25519Sha256, KeyExchangeScheme};
+
+        let backend = SoftwareBackend::new();
+        let key = backend.generate(KeyAlgorithm::X25519, "kex").unwrap();
+        let (remote_pk, remote_sk) = X25519Sha256::new().keypair(None).unwrap();
+        let shared = backend.derive(&key, &remote_pk).unwrap();
+        let expected = X25519Sha256::new()
+            .compute_shared_secret(&remote_sk, &backend.public_key(&key).unwrap())
+            .unwrap();
+        assert_eq!(expected, shared);
+        assert!(backend.sign(&key, b"message").is_err());
+    }
+}
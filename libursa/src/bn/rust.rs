@@ -1,12 +1,13 @@
 use errors::prelude::*;
 
 use glass_pumpkin::{prime, safe_prime};
+use hash::simd::Sha256;
 use num_bigint::{BigInt, BigUint, RandBigInt, Sign, ToBigInt};
 use num_integer::Integer;
 use num_traits::identities::{One, Zero};
 use num_traits::{Num, Pow, Signed, ToPrimitive};
 use rand::rngs::OsRng;
-use sha2::{self, Digest};
+use sha2::Digest;
 
 #[cfg(feature = "serde")]
 use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
@@ -199,7 +200,7 @@ impl BigNumber {
     }
 
     pub fn hash(data: &[u8]) -> UrsaCryptoResult<Vec<u8>> {
-        Ok(sha2::Sha256::digest(data).as_slice().to_vec())
+        Ok(Sha256::digest(data).as_slice().to_vec())
     }
 
     pub fn add(&self, a: &BigNumber) -> UrsaCryptoResult<BigNumber> {
@@ -489,7 +490,7 @@ impl BigNumber {
     }
 
     pub fn hash_array(nums: &[Vec<u8>]) -> UrsaCryptoResult<Vec<u8>> {
-        let mut hasher = sha2::Sha256::new();
+        let mut hasher = Sha256::new();
 
         for num in nums.iter() {
             hasher.input(&num);
@@ -9,6 +9,9 @@ use utils::commitment::get_pedersen_commitment;
 
 use std::collections::{BTreeSet, HashMap, HashSet};
 use std::iter::FromIterator;
+#[cfg(feature = "async")]
+use ursa_core::blocking::{self, Blocking};
+use ursa_core::parallel;
 
 /// Trust source that provides credentials to prover.
 pub struct Issuer {}
@@ -103,6 +106,29 @@ impl Issuer {
         Ok((cred_pub_key, cred_priv_key, cred_key_correctness_proof))
     }
 
+    /// `new_credential_def` on another thread, for callers on an async executor.
+    /// Generating the primary keys takes seconds as it searches for safe primes.
+    #[cfg(feature = "async")]
+    pub fn new_credential_def_async(
+        credential_schema: CredentialSchema,
+        non_credential_schema: NonCredentialSchema,
+        support_revocation: bool,
+    ) -> Blocking<
+        UrsaCryptoResult<(
+            CredentialPublicKey,
+            CredentialPrivateKey,
+            CredentialKeyCorrectnessProof,
+        )>,
+    > {
+        blocking::spawn_blocking(move || {
+            Issuer::new_credential_def(
+                &credential_schema,
+                &non_credential_schema,
+                support_revocation,
+            )
+        })
+    }
+
     /// Creates and returns revocation registry definition (public and private keys, accumulator and tails generator) entities.
     ///
     /// # Arguments
@@ -459,6 +485,123 @@ impl Issuer {
         Ok((cred_signature, signature_correctness_proof, rev_reg_delta))
     }
 
+    /// Signs the credential values requested in a `CredentialRequest`.
+    ///
+    /// Hidden attributes are signed through the commitment in the request,
+    /// so `credential_values` only has to contain the known ones.
+    ///
+    /// # Arguments
+    /// * `credential_request` - Credential request received from Prover.
+    /// * `credential_nonce` - Nonce Prover used for creation of the request.
+    /// * `credential_values` - Known credential values to be signed.
+    /// * `credential_pub_key` - Credential public key.
+    /// * `credential_priv_key` - Credential private key.
+    pub fn sign_credential_request(
+        credential_request: &CredentialRequest,
+        credential_nonce: &Nonce,
+        credential_values: &CredentialValues,
+        credential_pub_key: &CredentialPublicKey,
+        credential_priv_key: &CredentialPrivateKey,
+    ) -> UrsaCryptoResult<CredentialResponse> {
+        let (credential_signature, signature_correctness_proof) = Issuer::sign_credential(
+            &credential_request.prover_id,
+            &credential_request.blinded_credential_secrets,
+            &credential_request.blinded_credential_secrets_correctness_proof,
+            credential_nonce,
+            &credential_request.credential_issuance_nonce,
+            credential_values,
+            credential_pub_key,
+            credential_priv_key,
+        )?;
+
+        Ok(CredentialResponse {
+            credential_signature,
+            signature_correctness_proof,
+        })
+    }
+
+    /// Signs many `CredentialRequest`s with the same keys, in parallel with the
+    /// `parallel` feature. Fails if any of the requests can't be signed.
+    ///
+    /// # Arguments
+    /// * `credential_requests` - Credential requests received from Provers, each with
+    ///   the nonce the Prover used for its creation and the known values to be signed.
+    /// * `credential_pub_key` - Credential public key.
+    /// * `credential_priv_key` - Credential private key.
+    pub fn sign_credential_requests(
+        credential_requests: &[(&CredentialRequest, &Nonce, &CredentialValues)],
+        credential_pub_key: &CredentialPublicKey,
+        credential_priv_key: &CredentialPrivateKey,
+    ) -> UrsaCryptoResult<Vec<CredentialResponse>> {
+        parallel::try_map(credential_requests, |&(request, nonce, values)| {
+            Issuer::sign_credential_request(
+                request,
+                nonce,
+                values,
+                credential_pub_key,
+                credential_priv_key,
+            )
+        })
+    }
+
+    /// Signs the credential values requested in a `CredentialRequest` with both primary and revocation keys.
+    ///
+    /// # Arguments
+    /// * `credential_request` - Credential request received from Prover.
+    /// * `credential_nonce` - Nonce Prover used for creation of the request.
+    /// * `credential_values` - Known credential values to be signed.
+    /// * `credential_pub_key` - Credential public key.
+    /// * `credential_priv_key` - Credential private key.
+    /// * `rev_idx` - User index in revocation accumulator.
+    /// * `max_cred_num` - Max credential number in generated registry.
+    /// * `issuance_by_default` - Type of issuance.
+    /// * `rev_reg` - Revocation registry.
+    /// * `rev_key_priv` - Revocation registry private key.
+    /// * `rev_tails_accessor` - Revocation registry tails accessor.
+    #[allow(clippy::too_many_arguments)]
+    pub fn sign_credential_request_with_revoc<RTA>(
+        credential_request: &CredentialRequest,
+        credential_nonce: &Nonce,
+        credential_values: &CredentialValues,
+        credential_pub_key: &CredentialPublicKey,
+        credential_priv_key: &CredentialPrivateKey,
+        rev_idx: u32,
+        max_cred_num: u32,
+        issuance_by_default: bool,
+        rev_reg: &mut RevocationRegistry,
+        rev_key_priv: &RevocationKeyPrivate,
+        rev_tails_accessor: &RTA,
+    ) -> UrsaCryptoResult<(CredentialResponse, Option<RevocationRegistryDelta>)>
+    where
+        RTA: RevocationTailsAccessor,
+    {
+        let (credential_signature, signature_correctness_proof, rev_reg_delta) =
+            Issuer::sign_credential_with_revoc(
+                &credential_request.prover_id,
+                &credential_request.blinded_credential_secrets,
+                &credential_request.blinded_credential_secrets_correctness_proof,
+                credential_nonce,
+                &credential_request.credential_issuance_nonce,
+                credential_values,
+                credential_pub_key,
+                credential_priv_key,
+                rev_idx,
+                max_cred_num,
+                issuance_by_default,
+                rev_reg,
+                rev_key_priv,
+                rev_tails_accessor,
+            )?;
+
+        Ok((
+            CredentialResponse {
+                credential_signature,
+                signature_correctness_proof,
+            },
+            rev_reg_delta,
+        ))
+    }
+
     /// Revokes a credential by a rev_idx in a given revocation registry.
     ///
     /// # Arguments
@@ -1512,6 +1655,19 @@ mod tests {
         assert!(res.is_err())
     }
 
+    #[cfg(feature = "async")]
+    #[test]
+    fn issuer_new_credential_def_async_works_for_empty_attributes() {
+        let cred_attrs = CredentialSchema {
+            attrs: BTreeSet::new(),
+        };
+        let non_cred_attrs = NonCredentialSchema {
+            attrs: BTreeSet::new(),
+        };
+        let res = Issuer::new_credential_def_async(cred_attrs, non_cred_attrs, false).wait();
+        assert!(res.is_err())
+    }
+
     #[test]
     fn issuer_new_revocation_registry_def_works() {
         MockHelper::inject();
@@ -172,6 +172,78 @@ impl CredentialValues {
     }
 }
 
+/// Typed attribute value with a canonical integer encoding.
+///
+/// Predicates compare attributes as `i32`, so every variant maps into that range
+/// while preserving its natural order.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AttributeValue {
+    /// A signed integer, encoded as itself
+    Integer(i32),
+    /// A calendar date, encoded as the number of days since 1970-01-01
+    Date { year: i32, month: u32, day: u32 },
+    /// One of an ordered list of variants, encoded as its position in the list
+    Enumeration {
+        value: String,
+        variants: Vec<String>,
+    },
+}
+
+impl AttributeValue {
+    pub fn encode(&self) -> UrsaCryptoResult<i32> {
+        match *self {
+            AttributeValue::Integer(value) => Ok(value),
+            AttributeValue::Date { year, month, day } => days_from_epoch(year, month, day),
+            AttributeValue::Enumeration {
+                ref value,
+                ref variants,
+            } => variants
+                .iter()
+                .position(|v| v == value)
+                .map(|idx| idx as i32)
+                .ok_or_else(|| {
+                    err_msg(
+                        UrsaCryptoErrorKind::InvalidStructure,
+                        format!("Value {:?} is not one of {:?}", value, variants),
+                    )
+                }),
+        }
+    }
+
+    pub fn to_bignumber(&self) -> UrsaCryptoResult<BigNumber> {
+        BigNumber::from_dec(&self.encode()?.to_string())
+    }
+}
+
+/// Days between 1970-01-01 and the given date in the proleptic Gregorian calendar
+fn days_from_epoch(year: i32, month: u32, day: u32) -> UrsaCryptoResult<i32> {
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let month_len = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => 0,
+    };
+    if day == 0 || day > month_len || year.abs() > 1_000_000 {
+        return Err(err_msg(
+            UrsaCryptoErrorKind::InvalidStructure,
+            format!("Invalid date: {}-{}-{}", year, month, day),
+        ));
+    }
+
+    // Count years from March so the leap day is the last day of the year
+    let (year, month, day) = (i64::from(year), i64::from(month), i64::from(day));
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Ok((era * 146_097 + doe - 719_468) as i32)
+}
+
 /// A Builder of `Credential Values`.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug)]
@@ -242,6 +314,14 @@ impl CredentialValuesBuilder {
         Ok(())
     }
 
+    pub fn add_typed_known(&mut self, attr: &str, value: &AttributeValue) -> UrsaCryptoResult<()> {
+        self.add_value_known(attr, &value.to_bignumber()?)
+    }
+
+    pub fn add_typed_hidden(&mut self, attr: &str, value: &AttributeValue) -> UrsaCryptoResult<()> {
+        self.add_value_hidden(attr, &value.to_bignumber()?)
+    }
+
     pub fn add_value_commitment(
         &mut self,
         attr: &str,
@@ -502,6 +582,76 @@ impl RevocationRegistryDelta {
 
         Ok(())
     }
+
+    /// Merges a chain of consecutive deltas into a single delta.
+    ///
+    /// Each delta must start from the accumulator the previous one ends at.
+    /// Updating a witness with the result touches every tail only once
+    /// instead of once per delta.
+    pub fn merge_all(
+        deltas: &[RevocationRegistryDelta],
+    ) -> UrsaCryptoResult<RevocationRegistryDelta> {
+        let (first, rest) = deltas
+            .split_first()
+            .ok_or_else(|| err_msg(UrsaCryptoErrorKind::InvalidStructure, "No deltas to merge."))?;
+
+        let mut merged = first.clone();
+        for delta in rest {
+            merged.merge(delta)?;
+        }
+        Ok(merged)
+    }
+
+    /// Moves `rev_reg` forward to the state this delta ends at.
+    ///
+    /// Fails if the delta was built on top of a different accumulator value.
+    pub fn apply(&self, rev_reg: &mut RevocationRegistry) -> UrsaCryptoResult<()> {
+        if let Some(prev_accum) = self.prev_accum {
+            if prev_accum != rev_reg.accum {
+                return Err(err_msg(
+                    UrsaCryptoErrorKind::InvalidStructure,
+                    "Delta can not be applied to the revocation registry.",
+                ));
+            }
+        }
+
+        rev_reg.accum = self.accum;
+        Ok(())
+    }
+
+    /// Brings the `witness` for credential `rev_idx` up to date with this delta.
+    ///
+    /// Fails if the credential itself was revoked by the delta since no valid
+    /// witness exists for it anymore.
+    pub fn update_witness<RTA>(
+        &self,
+        witness: &mut Witness,
+        rev_idx: u32,
+        max_cred_num: u32,
+        rev_tails_accessor: &RTA,
+    ) -> UrsaCryptoResult<()>
+    where
+        RTA: RevocationTailsAccessor,
+    {
+        if self.revoked.contains(&rev_idx) {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::CredentialRevoked,
+                format!("Credential {} was revoked", rev_idx),
+            ));
+        }
+
+        witness.update(rev_idx, max_cred_num, self, rev_tails_accessor)
+    }
+
+    /// Indices of the credentials issued by this delta
+    pub fn issued(&self) -> &HashSet<u32> {
+        &self.issued
+    }
+
+    /// Indices of the credentials revoked by this delta
+    pub fn revoked(&self) -> &HashSet<u32> {
+        &self.revoked
+    }
 }
 
 /// `Revocation Key Public` Accumulator public key.
@@ -865,6 +1015,53 @@ impl BlindedCredentialSecretsCorrectnessProof {
     }
 }
 
+/// Message sent by Prover to Issuer to request a credential.
+///
+/// Carries the commitment to the master secret and other hidden attributes
+/// together with the proof that it is well formed, so Issuer signs over values it never sees.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug)]
+pub struct CredentialRequest {
+    prover_id: String,
+    blinded_credential_secrets: BlindedCredentialSecrets,
+    blinded_credential_secrets_correctness_proof: BlindedCredentialSecretsCorrectnessProof,
+    credential_issuance_nonce: Nonce,
+}
+
+impl CredentialRequest {
+    pub fn prover_id(&self) -> &str {
+        &self.prover_id
+    }
+
+    pub fn try_clone(&self) -> UrsaCryptoResult<Self> {
+        Ok(Self {
+            prover_id: self.prover_id.clone(),
+            blinded_credential_secrets: self.blinded_credential_secrets.try_clone()?,
+            blinded_credential_secrets_correctness_proof: self
+                .blinded_credential_secrets_correctness_proof
+                .try_clone()?,
+            credential_issuance_nonce: self.credential_issuance_nonce.try_clone()?,
+        })
+    }
+}
+
+/// Data Prover keeps to unblind the credential once Issuer responds to a `CredentialRequest`.
+/// Must not be sent to Issuer.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug)]
+pub struct CredentialRequestMetadata {
+    credential_secrets_blinding_factors: CredentialSecretsBlindingFactors,
+    credential_issuance_nonce: Nonce,
+}
+
+/// Message sent by Issuer to Prover in response to a `CredentialRequest`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug)]
+pub struct CredentialResponse {
+    credential_signature: CredentialSignature,
+    signature_correctness_proof: SignatureCorrectnessProof,
+}
+
 /// “Sub Proof Request” - input to create a Proof for a credential;
 /// Contains attributes to be revealed and predicates.
 #[derive(Debug, Clone)]
@@ -924,6 +1121,46 @@ impl SubProofRequestBuilder {
         Ok(())
     }
 
+    /// Request a proof that `lower <= attr_value <= upper` without revealing the value.
+    /// This is the conjunction of a `GE` and an `LE` predicate on the same attribute.
+    pub fn add_range_predicate(
+        &mut self,
+        attr_name: &str,
+        lower: i32,
+        upper: i32,
+    ) -> UrsaCryptoResult<()> {
+        if lower > upper {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::InvalidStructure,
+                format!("Invalid range: {} > {}", lower, upper),
+            ));
+        }
+
+        self.add_predicate(attr_name, "GE", lower)?;
+        self.add_predicate(attr_name, "LE", upper)
+    }
+
+    /// Same as `add_predicate` with the value given in its typed form.
+    /// The attribute must have been issued with the same encoding.
+    pub fn add_typed_predicate(
+        &mut self,
+        attr_name: &str,
+        p_type: &str,
+        value: &AttributeValue,
+    ) -> UrsaCryptoResult<()> {
+        self.add_predicate(attr_name, p_type, value.encode()?)
+    }
+
+    /// Same as `add_range_predicate` with bounds given in their typed form.
+    pub fn add_typed_range_predicate(
+        &mut self,
+        attr_name: &str,
+        lower: &AttributeValue,
+        upper: &AttributeValue,
+    ) -> UrsaCryptoResult<()> {
+        self.add_range_predicate(attr_name, lower.encode()?, upper.encode()?)
+    }
+
     pub fn finalize(self) -> UrsaCryptoResult<SubProofRequest> {
         Ok(self.value)
     }
@@ -1473,6 +1710,293 @@ mod test {
         assert!(proof_verifier.verify(&proof, &proof_request_nonce).unwrap());
     }
 
+    #[test]
+    fn range_predicate_works() {
+        let mut credential_schema_builder = Issuer::new_credential_schema_builder().unwrap();
+        credential_schema_builder.add_attr("age").unwrap();
+        let credential_schema = credential_schema_builder.finalize().unwrap();
+
+        let non_credential_schema_builder = NonCredentialSchemaBuilder::new().unwrap();
+        let non_credential_schema = non_credential_schema_builder.finalize().unwrap();
+
+        let (cred_pub_key, cred_priv_key, cred_key_correctness_proof) =
+            Issuer::new_credential_def(&credential_schema, &non_credential_schema, false).unwrap();
+
+        let credential_nonce = new_nonce().unwrap();
+
+        let mut credential_values_builder = Issuer::new_credential_values_builder().unwrap();
+        credential_values_builder
+            .add_dec_known("age", "28")
+            .unwrap();
+        let cred_values = credential_values_builder.finalize().unwrap();
+
+        let (
+            blinded_credential_secrets,
+            credential_secrets_blinding_factors,
+            blinded_credential_secrets_correctness_proof,
+        ) = Prover::blind_credential_secrets(
+            &cred_pub_key,
+            &cred_key_correctness_proof,
+            &cred_values,
+            &credential_nonce,
+        )
+        .unwrap();
+
+        let cred_issuance_nonce = new_nonce().unwrap();
+
+        let (mut cred_signature, signature_correctness_proof) = Issuer::sign_credential(
+            "CnEDk9HrMnmiHXEV1WFgbVCRteYnPqsJwrTdcZaNhFVW",
+            &blinded_credential_secrets,
+            &blinded_credential_secrets_correctness_proof,
+            &credential_nonce,
+            &cred_issuance_nonce,
+            &cred_values,
+            &cred_pub_key,
+            &cred_priv_key,
+        )
+        .unwrap();
+
+        Prover::process_credential_signature(
+            &mut cred_signature,
+            &cred_values,
+            &signature_correctness_proof,
+            &credential_secrets_blinding_factors,
+            &cred_pub_key,
+            &cred_issuance_nonce,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let mut sub_proof_request_builder = Verifier::new_sub_proof_request_builder().unwrap();
+        sub_proof_request_builder
+            .add_range_predicate("age", 18, 65)
+            .unwrap();
+        let sub_proof_request = sub_proof_request_builder.finalize().unwrap();
+
+        let mut proof_builder = Prover::new_proof_builder().unwrap();
+        proof_builder
+            .add_sub_proof_request(
+                &sub_proof_request,
+                &credential_schema,
+                &non_credential_schema,
+                &cred_signature,
+                &cred_values,
+                &cred_pub_key,
+                None,
+                None,
+            )
+            .unwrap();
+
+        let proof_request_nonce = new_nonce().unwrap();
+        let proof = proof_builder.finalize(&proof_request_nonce).unwrap();
+
+        let mut proof_verifier = Verifier::new_proof_verifier().unwrap();
+        proof_verifier
+            .add_sub_proof_request(
+                &sub_proof_request,
+                &credential_schema,
+                &non_credential_schema,
+                &cred_pub_key,
+                None,
+                None,
+            )
+            .unwrap();
+        assert!(proof_verifier.verify(&proof, &proof_request_nonce).unwrap());
+
+        // The attribute is outside the range so no proof can be built
+        let mut sub_proof_request_builder = Verifier::new_sub_proof_request_builder().unwrap();
+        sub_proof_request_builder
+            .add_range_predicate("age", 30, 65)
+            .unwrap();
+        let sub_proof_request = sub_proof_request_builder.finalize().unwrap();
+
+        let mut proof_builder = Prover::new_proof_builder().unwrap();
+        assert!(proof_builder
+            .add_sub_proof_request(
+                &sub_proof_request,
+                &credential_schema,
+                &non_credential_schema,
+                &cred_signature,
+                &cred_values,
+                &cred_pub_key,
+                None,
+                None,
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn attribute_value_encoding_works() {
+        let date = |year, month, day| AttributeValue::Date { year, month, day };
+        assert_eq!(0, date(1970, 1, 1).encode().unwrap());
+        assert_eq!(-1, date(1969, 12, 31).encode().unwrap());
+        assert_eq!(11_016, date(2000, 2, 29).encode().unwrap());
+        assert_eq!(18_262, date(2020, 1, 1).encode().unwrap());
+        assert!(date(2020, 1, 1).encode().unwrap() < date(2020, 1, 2).encode().unwrap());
+        assert!(date(2019, 2, 29).encode().is_err());
+        assert!(date(2020, 13, 1).encode().is_err());
+        assert!(date(2020, 4, 0).encode().is_err());
+
+        assert_eq!(-5, AttributeValue::Integer(-5).encode().unwrap());
+
+        let variants = vec![
+            "bronze".to_string(),
+            "silver".to_string(),
+            "gold".to_string(),
+        ];
+        let level = |value: &str| AttributeValue::Enumeration {
+            value: value.to_string(),
+            variants: variants.clone(),
+        };
+        assert_eq!(2, level("gold").encode().unwrap());
+        assert!(level("platinum").encode().is_err());
+        assert_eq!(
+            BigNumber::from_dec("1").unwrap(),
+            level("silver").to_bignumber().unwrap()
+        );
+    }
+
+    #[test]
+    fn typed_predicates_work() {
+        let mut credential_schema_builder = Issuer::new_credential_schema_builder().unwrap();
+        credential_schema_builder.add_attr("birthdate").unwrap();
+        credential_schema_builder.add_attr("level").unwrap();
+        let credential_schema = credential_schema_builder.finalize().unwrap();
+
+        let non_credential_schema_builder = NonCredentialSchemaBuilder::new().unwrap();
+        let non_credential_schema = non_credential_schema_builder.finalize().unwrap();
+
+        let (cred_pub_key, cred_priv_key, cred_key_correctness_proof) =
+            Issuer::new_credential_def(&credential_schema, &non_credential_schema, false).unwrap();
+
+        let credential_nonce = new_nonce().unwrap();
+
+        let levels = vec![
+            "bronze".to_string(),
+            "silver".to_string(),
+            "gold".to_string(),
+        ];
+        let mut credential_values_builder = Issuer::new_credential_values_builder().unwrap();
+        credential_values_builder
+            .add_typed_known(
+                "birthdate",
+                &AttributeValue::Date {
+                    year: 1990,
+                    month: 6,
+                    day: 15,
+                },
+            )
+            .unwrap();
+        credential_values_builder
+            .add_typed_known(
+                "level",
+                &AttributeValue::Enumeration {
+                    value: "silver".to_string(),
+                    variants: levels.clone(),
+                },
+            )
+            .unwrap();
+        let cred_values = credential_values_builder.finalize().unwrap();
+
+        let (
+            blinded_credential_secrets,
+            credential_secrets_blinding_factors,
+            blinded_credential_secrets_correctness_proof,
+        ) = Prover::blind_credential_secrets(
+            &cred_pub_key,
+            &cred_key_correctness_proof,
+            &cred_values,
+            &credential_nonce,
+        )
+        .unwrap();
+
+        let cred_issuance_nonce = new_nonce().unwrap();
+
+        let (mut cred_signature, signature_correctness_proof) = Issuer::sign_credential(
+            "CnEDk9HrMnmiHXEV1WFgbVCRteYnPqsJwrTdcZaNhFVW",
+            &blinded_credential_secrets,
+            &blinded_credential_secrets_correctness_proof,
+            &credential_nonce,
+            &cred_issuance_nonce,
+            &cred_values,
+            &cred_pub_key,
+            &cred_priv_key,
+        )
+        .unwrap();
+
+        Prover::process_credential_signature(
+            &mut cred_signature,
+            &cred_values,
+            &signature_correctness_proof,
+            &credential_secrets_blinding_factors,
+            &cred_pub_key,
+            &cred_issuance_nonce,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let mut sub_proof_request_builder = Verifier::new_sub_proof_request_builder().unwrap();
+        sub_proof_request_builder
+            .add_typed_predicate(
+                "birthdate",
+                "LE",
+                &AttributeValue::Date {
+                    year: 2000,
+                    month: 1,
+                    day: 1,
+                },
+            )
+            .unwrap();
+        sub_proof_request_builder
+            .add_typed_range_predicate(
+                "level",
+                &AttributeValue::Enumeration {
+                    value: "silver".to_string(),
+                    variants: levels.clone(),
+                },
+                &AttributeValue::Enumeration {
+                    value: "gold".to_string(),
+                    variants: levels.clone(),
+                },
+            )
+            .unwrap();
+        let sub_proof_request = sub_proof_request_builder.finalize().unwrap();
+
+        let mut proof_builder = Prover::new_proof_builder().unwrap();
+        proof_builder
+            .add_sub_proof_request(
+                &sub_proof_request,
+                &credential_schema,
+                &non_credential_schema,
+                &cred_signature,
+                &cred_values,
+                &cred_pub_key,
+                None,
+                None,
+            )
+            .unwrap();
+
+        let proof_request_nonce = new_nonce().unwrap();
+        let proof = proof_builder.finalize(&proof_request_nonce).unwrap();
+
+        let mut proof_verifier = Verifier::new_proof_verifier().unwrap();
+        proof_verifier
+            .add_sub_proof_request(
+                &sub_proof_request,
+                &credential_schema,
+                &non_credential_schema,
+                &cred_pub_key,
+                None,
+                None,
+            )
+            .unwrap();
+        assert!(proof_verifier.verify(&proof, &proof_request_nonce).unwrap());
+    }
+
     #[test]
     fn multiple_predicates() {
         let mut credential_schema_builder = Issuer::new_credential_schema_builder().unwrap();
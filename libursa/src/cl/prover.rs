@@ -278,6 +278,143 @@ impl Prover {
         Ok(())
     }
 
+    /// Creates a request for a credential over `credential_values`.
+    ///
+    /// The master secret and other hidden attributes are committed to and
+    /// only the commitment with its correctness proof goes to Issuer.
+    /// The returned metadata must be kept by Prover to process the response.
+    ///
+    /// # Arguments
+    /// * `prover_id` - Prover identifier.
+    /// * `credential_pub_key` - Credential public key.
+    /// * `credential_key_correctness_proof` - Credential key correctness proof.
+    /// * `credential_values` - Credential values.
+    /// * `credential_nonce` - Nonce received from Issuer.
+    ///
+    /// # Example
+    /// ```
+    /// use ursa::cl::new_nonce;
+    /// use ursa::cl::issuer::Issuer;
+    /// use ursa::cl::prover::Prover;
+    ///
+    /// let mut credential_schema_builder = Issuer::new_credential_schema_builder().unwrap();
+    /// credential_schema_builder.add_attr("sex").unwrap();
+    /// let credential_schema = credential_schema_builder.finalize().unwrap();
+    ///
+    /// let mut non_credential_schema_builder = Issuer::new_non_credential_schema_builder().unwrap();
+    /// non_credential_schema_builder.add_attr("master_secret").unwrap();
+    /// let non_credential_schema = non_credential_schema_builder.finalize().unwrap();
+    ///
+    /// let (credential_pub_key, credential_priv_key, cred_key_correctness_proof) = Issuer::new_credential_def(&credential_schema, &non_credential_schema, false).unwrap();
+    ///
+    /// let master_secret = Prover::new_master_secret().unwrap();
+    /// let credential_nonce = new_nonce().unwrap();
+    ///
+    /// let mut credential_values_builder = Issuer::new_credential_values_builder().unwrap();
+    /// credential_values_builder.add_value_hidden("master_secret", &master_secret.value().unwrap()).unwrap();
+    /// credential_values_builder.add_dec_known("sex", "5944657099558967239210949258394887428692050081607692519917050011144233115103").unwrap();
+    /// let credential_values = credential_values_builder.finalize().unwrap();
+    ///
+    /// let (credential_request, credential_request_metadata) =
+    ///     Prover::new_credential_request("CnEDk9HrMnmiHXEV1WFgbVCRteYnPqsJwrTdcZaNhFVW",
+    ///                                    &credential_pub_key,
+    ///                                    &cred_key_correctness_proof,
+    ///                                    &credential_values,
+    ///                                    &credential_nonce).unwrap();
+    ///
+    /// let mut known_values_builder = Issuer::new_credential_values_builder().unwrap();
+    /// known_values_builder.add_dec_known("sex", "5944657099558967239210949258394887428692050081607692519917050011144233115103").unwrap();
+    /// let known_values = known_values_builder.finalize().unwrap();
+    ///
+    /// let credential_response =
+    ///     Issuer::sign_credential_request(&credential_request,
+    ///                                     &credential_nonce,
+    ///                                     &known_values,
+    ///                                     &credential_pub_key,
+    ///                                     &credential_priv_key).unwrap();
+    ///
+    /// let _credential_signature =
+    ///     Prover::process_credential_response(credential_response,
+    ///                                         &credential_request_metadata,
+    ///                                         &credential_values,
+    ///                                         &credential_pub_key,
+    ///                                         None, None, None).unwrap();
+    /// ```
+    pub fn new_credential_request(
+        prover_id: &str,
+        credential_pub_key: &CredentialPublicKey,
+        credential_key_correctness_proof: &CredentialKeyCorrectnessProof,
+        credential_values: &CredentialValues,
+        credential_nonce: &Nonce,
+    ) -> UrsaCryptoResult<(CredentialRequest, CredentialRequestMetadata)> {
+        let (
+            blinded_credential_secrets,
+            credential_secrets_blinding_factors,
+            blinded_credential_secrets_correctness_proof,
+        ) = Prover::blind_credential_secrets(
+            credential_pub_key,
+            credential_key_correctness_proof,
+            credential_values,
+            credential_nonce,
+        )?;
+
+        let credential_issuance_nonce = new_nonce()?;
+
+        let credential_request = CredentialRequest {
+            prover_id: prover_id.to_owned(),
+            blinded_credential_secrets,
+            blinded_credential_secrets_correctness_proof,
+            credential_issuance_nonce: credential_issuance_nonce.try_clone()?,
+        };
+
+        let credential_request_metadata = CredentialRequestMetadata {
+            credential_secrets_blinding_factors,
+            credential_issuance_nonce,
+        };
+
+        Ok((credential_request, credential_request_metadata))
+    }
+
+    /// Unblinds the credential signature in Issuer's response to a `CredentialRequest`
+    /// and checks its correctness proof.
+    ///
+    /// # Arguments
+    /// * `credential_response` - Credential response received from Issuer.
+    /// * `credential_request_metadata` - Metadata returned with the credential request.
+    /// * `credential_values` - Credential values.
+    /// * `credential_pub_key` - Credential public key.
+    /// * `rev_key_pub` - (Optional) Revocation registry public key.
+    /// * `rev_reg` - (Optional) Revocation registry.
+    /// * `witness` - (Optional) Witness.
+    pub fn process_credential_response(
+        credential_response: CredentialResponse,
+        credential_request_metadata: &CredentialRequestMetadata,
+        credential_values: &CredentialValues,
+        credential_pub_key: &CredentialPublicKey,
+        rev_key_pub: Option<&RevocationKeyPublic>,
+        rev_reg: Option<&RevocationRegistry>,
+        witness: Option<&Witness>,
+    ) -> UrsaCryptoResult<CredentialSignature> {
+        let CredentialResponse {
+            mut credential_signature,
+            signature_correctness_proof,
+        } = credential_response;
+
+        Prover::process_credential_signature(
+            &mut credential_signature,
+            credential_values,
+            &signature_correctness_proof,
+            &credential_request_metadata.credential_secrets_blinding_factors,
+            credential_pub_key,
+            &credential_request_metadata.credential_issuance_nonce,
+            rev_key_pub,
+            rev_reg,
+            witness,
+        )?;
+
+        Ok(credential_signature)
+    }
+
     /// Creates and returns proof builder.
     ///
     /// The purpose of proof builder is building of proof entity according to the given request .
@@ -662,6 +662,26 @@ mod tests {
         assert!(sub_proof_request.predicates.contains(&predicate()));
     }
 
+    #[test]
+    fn sub_proof_request_builder_range_predicate_works() {
+        let mut sub_proof_request_builder = Verifier::new_sub_proof_request_builder().unwrap();
+        sub_proof_request_builder
+            .add_range_predicate("age", 18, 65)
+            .unwrap();
+        assert!(sub_proof_request_builder
+            .add_range_predicate("age", 65, 18)
+            .is_err());
+        let sub_proof_request = sub_proof_request_builder.finalize().unwrap();
+
+        assert_eq!(sub_proof_request.predicates.len(), 2);
+        assert!(sub_proof_request.predicates.contains(&predicate()));
+        assert!(sub_proof_request.predicates.contains(&Predicate {
+            attr_name: "age".to_string(),
+            p_type: PredicateType::LE,
+            value: 65,
+        }));
+    }
+
     #[test]
     fn verify_equality_works() {
         MockHelper::inject();
@@ -0,0 +1,358 @@
+//! Deterministic random bit generators.
+//!
+//! `HmacDrbg` is the HMAC_DRBG of NIST SP 800-90A for deployments that have
+//! to use an approved generator. `ChaChaDrbg` is a faster generator built on
+//! the ChaCha20 stream cipher. Both are seeded from entropy supplied by the
+//! caller and implement `CryptoRng + RngCore`, so they can be handed to any
+//! of the `*_with_rng` functions. Seeding one with a fixed value replays the
+//! same run, which is useful for simulating a protocol.
+//!
+//! # Example
+//! ```
+//! use ursa::drbg::HmacSha256Drbg;
+//! use rand::RngCore;
+//!
+//! let mut drbg = HmacSha256Drbg::new(&[7u8; 32], b"nonce", b"simulation").unwrap();
+//! let mut key = [0u8; 32];
+//! drbg.fill_bytes(&mut key);
+//! ```
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use hmac::{Hmac, Mac};
+use rand::{CryptoRng, Error, RngCore, SeedableRng};
+use rand_chacha::ChaChaRng;
+use sha2::digest::generic_array::{typenum::Unsigned, ArrayLength};
+use sha2::digest::{BlockInput, FixedOutput, Input, Reset};
+use sha2::{Sha256, Sha512};
+use std::num::NonZeroU32;
+use zeroize::Zeroize;
+
+use CryptoError;
+
+/// Least number of entropy bytes accepted when seeding
+pub const MIN_ENTROPY_SIZE: usize = 32;
+/// Most bytes `HmacDrbg::generate` returns per call
+pub const MAX_REQUEST_SIZE: usize = 1 << 16;
+/// Number of `HmacDrbg::generate` calls allowed between reseeds
+pub const RESEED_INTERVAL: u64 = 1 << 48;
+
+/// Error code `try_fill_bytes` fails with when the generator must be reseeded
+pub const RESEED_REQUIRED: u32 = Error::CUSTOM_START;
+
+fn check_entropy(entropy: &[u8]) -> Result<(), CryptoError> {
+    if entropy.len() < MIN_ENTROPY_SIZE {
+        return Err(CryptoError::GeneralError(format!(
+            "Expected at least {} bytes of entropy, found {}",
+            MIN_ENTROPY_SIZE,
+            entropy.len()
+        )));
+    }
+    Ok(())
+}
+
+/// HMAC_DRBG from NIST SP 800-90A section 10.1.2 with the hash function `D`.
+///
+/// The internal state is zeroed when dropped.
+pub struct HmacDrbg<D>
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+    D::OutputSize: ArrayLength<u8>,
+{
+    k: Vec<u8>,
+    v: Vec<u8>,
+    reseed_counter: u64,
+    _digest: std::marker::PhantomData<D>,
+}
+
+pub type HmacSha256Drbg = HmacDrbg<Sha256>;
+pub type HmacSha512Drbg = HmacDrbg<Sha512>;
+
+impl<D> HmacDrbg<D>
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+    D::OutputSize: ArrayLength<u8>,
+{
+    /// Instantiate from at least `MIN_ENTROPY_SIZE` bytes of `entropy`, a
+    /// `nonce` and an optional `personalization` string
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<Self, CryptoError> {
+        check_entropy(entropy)?;
+        let size = D::OutputSize::to_usize();
+        let mut drbg = HmacDrbg {
+            k: vec![0u8; size],
+            v: vec![1u8; size],
+            reseed_counter: 1,
+            _digest: std::marker::PhantomData,
+        };
+        drbg.update(&[entropy, nonce, personalization]);
+        Ok(drbg)
+    }
+
+    /// Mix fresh `entropy` and optional `additional` input into the state
+    pub fn reseed(&mut self, entropy: &[u8], additional: &[u8]) -> Result<(), CryptoError> {
+        check_entropy(entropy)?;
+        self.update(&[entropy, additional]);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// Fill `out` with up to `MAX_REQUEST_SIZE` bytes, mixing in the optional
+    /// `additional` input. Fails once `RESEED_INTERVAL` requests were served
+    /// since the last reseed.
+    pub fn generate(&mut self, out: &mut [u8], additional: &[u8]) -> Result<(), CryptoError> {
+        if out.len() > MAX_REQUEST_SIZE {
+            return Err(CryptoError::GeneralError(format!(
+                "Expected at most {} bytes per request, found {}",
+                MAX_REQUEST_SIZE,
+                out.len()
+            )));
+        }
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(CryptoError::GeneralError(
+                "The generator must be reseeded".to_string(),
+            ));
+        }
+        if !additional.is_empty() {
+            self.update(&[additional]);
+        }
+        for chunk in out.chunks_mut(self.v.len()) {
+            self.v = self.hmac(&[&self.v]);
+            chunk.copy_from_slice(&self.v[..chunk.len()]);
+        }
+        self.update(&[additional]);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    fn hmac(&self, parts: &[&[u8]]) -> Vec<u8> {
+        let mut mac = Hmac::<D>::new_varkey(&self.k).unwrap();
+        for part in parts {
+            mac.input(part);
+        }
+        mac.result().code().to_vec()
+    }
+
+    fn update(&mut self, provided: &[&[u8]]) {
+        let empty = provided.iter().all(|p| p.is_empty());
+        for round in &[0u8, 1] {
+            if *round == 1 && empty {
+                break;
+            }
+            let mut parts = vec![self.v.as_slice(), std::slice::from_ref(round)];
+            parts.extend_from_slice(provided);
+            let k = self.hmac(&parts);
+            self.k.zeroize();
+            self.k = k;
+            self.v = self.hmac(&[&self.v]);
+        }
+    }
+}
+
+impl<D> RngCore for HmacDrbg<D>
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+    D::OutputSize: ArrayLength<u8>,
+{
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Panics when the generator must be reseeded
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.try_fill_bytes(dest)
+            .expect("The generator must be reseeded")
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        for chunk in dest.chunks_mut(MAX_REQUEST_SIZE) {
+            self.generate(chunk, &[])
+                .map_err(|_| Error::from(NonZeroU32::new(RESEED_REQUIRED).unwrap()))?;
+        }
+        Ok(())
+    }
+}
+
+impl<D> CryptoRng for HmacDrbg<D>
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+    D::OutputSize: ArrayLength<u8>,
+{
+}
+
+impl<D> Drop for HmacDrbg<D>
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+    D::OutputSize: ArrayLength<u8>,
+{
+    fn drop(&mut self) {
+        self.k.zeroize();
+        self.v.zeroize();
+    }
+}
+
+/// A generator running ChaCha20 keyed with a hash of the seed material
+pub struct ChaChaDrbg(ChaChaRng);
+
+impl ChaChaDrbg {
+    /// Instantiate from at least `MIN_ENTROPY_SIZE` bytes of `entropy` and an
+    /// optional `personalization` string
+    pub fn new(entropy: &[u8], personalization: &[u8]) -> Result<Self, CryptoError> {
+        check_entropy(entropy)?;
+        Ok(Self::from_seed(derive_seed(&[entropy, personalization])))
+    }
+
+    /// Use `seed` as the ChaCha20 key as is, for reproducible runs
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        ChaChaDrbg(ChaChaRng::from_seed(seed))
+    }
+
+    /// Rekey from fresh `entropy` and the current output
+    pub fn reseed(&mut self, entropy: &[u8]) -> Result<(), CryptoError> {
+        check_entropy(entropy)?;
+        let mut current = [0u8; 32];
+        self.0.fill_bytes(&mut current);
+        self.0 = ChaChaRng::from_seed(derive_seed(&[&current, entropy]));
+        current.zeroize();
+        Ok(())
+    }
+}
+
+/// SHA-256 of the length prefixed `parts`
+fn derive_seed(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::default();
+    for part in parts {
+        hasher.input((part.len() as u64).to_be_bytes());
+        hasher.input(part);
+    }
+    let mut seed = [0u8; 32];
+    seed.copy_from_slice(hasher.fixed_result().as_slice());
+    seed
+}
+
+impl RngCore for ChaChaDrbg {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+impl CryptoRng for ChaChaDrbg {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hmac_sha256_vector(entropy: &str, nonce: &str, additional: [&str; 2], expected: &str) {
+        let mut drbg = HmacSha256Drbg::new(
+            &hex::decode(entropy).unwrap(),
+            &hex::decode(nonce).unwrap(),
+            &[],
+        )
+        .unwrap();
+        let mut out = [0u8; 128];
+        for a in additional.iter() {
+            drbg.generate(&mut out, &hex::decode(a).unwrap()).unwrap();
+        }
+        assert_eq!(hex::encode(&out[..]), expected);
+    }
+
+    #[test]
+    fn hmac_drbg_nist_vectors() {
+        // From the CAVP HMAC_DRBG test vectors, SHA-256 without prediction
+        // resistance
+        hmac_sha256_vector(
+            "ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488",
+            "659ba96c601dc69fc902940805ec0ca8",
+            ["", ""],
+            "e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89\
+             d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc1\
+             07694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668\
+             961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8",
+        );
+        hmac_sha256_vector(
+            "d3cc4d1acf3dde0c4bd2290d262337042dc632948223d3a2eaab87da44295fbd",
+            "0109b0e729f457328aa18569a9224921",
+            [
+                "3c311848183c9a212a26f27f8c6647e40375e466a0857cc39c4e47575d53f1f6",
+                "fcb9abd19ccfbccef88c9c39bfb3dd7b1c12266c9808992e305bc3cff566e4e4",
+            ],
+            "9c7b758b212cd0fcecd5daa489821712e3cdea4467b560ef5ddc24ab47749a1f\
+             1ffdbbb118f4e62fcfca3371b8fbfc5b0646b83e06bfbbab5fac30ea09ea2bc7\
+             6f1ea568c9be0444b2cc90517b20ca825f2d0eccd88e7175538b85d90ab39018\
+             3ca6395535d34473af6b5a5b88f5a59ee7561573337ea819da0dcc3573a22974",
+        );
+    }
+
+    #[test]
+    fn hmac_drbg_limits() {
+        assert!(HmacSha512Drbg::new(&[0u8; 31], &[], &[]).is_err());
+        let mut drbg = HmacSha512Drbg::new(&[0u8; 32], &[], &[]).unwrap();
+        assert!(drbg
+            .generate(&mut vec![0u8; MAX_REQUEST_SIZE + 1], &[])
+            .is_err());
+        // fill_bytes splits large requests
+        drbg.fill_bytes(&mut vec![0u8; MAX_REQUEST_SIZE + 1]);
+
+        drbg.reseed_counter = RESEED_INTERVAL + 1;
+        let error = drbg.try_fill_bytes(&mut [0u8; 8]).unwrap_err();
+        assert_eq!(error.code().unwrap().get(), RESEED_REQUIRED);
+        drbg.reseed(&[1u8; 32], b"more").unwrap();
+        drbg.next_u64();
+    }
+
+    #[test]
+    fn chacha_drbg_reproducible() {
+        let mut a = ChaChaDrbg::new(&[3u8; 32], b"run 1").unwrap();
+        let mut b = ChaChaDrbg::new(&[3u8; 32], b"run 1").unwrap();
+        let mut c = ChaChaDrbg::new(&[3u8; 32], b"run 2").unwrap();
+        assert_eq!(a.next_u64(), b.next_u64());
+        assert_ne!(a.next_u64(), c.next_u64());
+        assert!(ChaChaDrbg::new(&[3u8; 16], &[]).is_err());
+
+        a.reseed(&[4u8; 32]).unwrap();
+        b.reseed(&[5u8; 32]).unwrap();
+        assert_ne!(a.next_u64(), b.next_u64());
+        assert_eq!(
+            ChaChaDrbg::from_seed([9u8; 32]).next_u32(),
+            ChaChaDrbg::from_seed([9u8; 32]).next_u32()
+        );
+    }
+
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    #[test]
+    fn drives_key_generation() {
+        use signatures::ed25519::Ed25519Sha512;
+        use signatures::SignatureScheme;
+
+        let scheme = Ed25519Sha512;
+        let mut drbg = HmacSha256Drbg::new(&[1u8; 32], b"nonce", &[]).unwrap();
+        let (pk1, _) = scheme.keypair_with_rng(&mut drbg).unwrap();
+        let mut drbg = HmacSha256Drbg::new(&[1u8; 32], b"nonce", &[]).unwrap();
+        let (pk2, _) = scheme.keypair_with_rng(&mut drbg).unwrap();
+        assert_eq!(pk1, pk2);
+    }
+}
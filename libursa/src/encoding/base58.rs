@@ -0,0 +1,77 @@
+//! Base58 with the Bitcoin alphabet.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use CryptoError;
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Encode `data`. Each leading zero byte becomes a leading `1`.
+pub fn encode(data: &[u8]) -> String {
+    let zeros = data.iter().take_while(|b| **b == 0).count();
+    // Little endian base 58 digits
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for b in &data[zeros..] {
+        let mut carry = u32::from(*b);
+        for d in digits.iter_mut() {
+            carry += u32::from(*d) << 8;
+            *d = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let mut out = String::with_capacity(zeros + digits.len());
+    for _ in 0..zeros {
+        out.push('1');
+    }
+    for d in digits.iter().rev() {
+        out.push(ALPHABET[*d as usize] as char);
+    }
+    out
+}
+
+/// Decode `data`
+pub fn decode(data: &str) -> Result<Vec<u8>, CryptoError> {
+    let zeros = data.bytes().take_while(|c| *c == b'1').count();
+    // Little endian bytes
+    let mut bytes: Vec<u8> = Vec::with_capacity(data.len() * 733 / 1000 + 1);
+    for c in data.bytes().skip(zeros) {
+        let mut carry = ALPHABET.iter().position(|a| *a == c).ok_or_else(|| {
+            CryptoError::ParseError(format!("Invalid base58 character {}", c as char))
+        })? as u32;
+        for b in bytes.iter_mut() {
+            carry += u32::from(*b) * 58;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut out = vec![0u8; zeros];
+    out.extend(bytes.iter().rev());
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base58_vectors() {
+        for (data, encoded) in &[
+            (&b""[..], ""),
+            (&b"Hello World!"[..], "2NEpo7TZRRrLZSi2U"),
+            (&[0u8, 0, 0x28, 0x7f, 0xb4, 0xcd][..], "11233QC4"),
+            (&[0u8][..], "1"),
+        ] {
+            assert_eq!(*encoded, encode(data));
+            assert_eq!(*data, &decode(encoded).unwrap()[..]);
+        }
+        assert!(decode("0OIl").is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! Base64 as defined in RFC 4648 with the standard and the URL safe alphabets.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use CryptoError;
+
+const STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encode with the standard alphabet and padding
+pub fn encode(data: &[u8]) -> String {
+    encode_with(data, STANDARD, true)
+}
+
+/// Decode the standard alphabet. Padding is required.
+pub fn decode(data: &str) -> Result<Vec<u8>, CryptoError> {
+    decode_with(data, STANDARD, true)
+}
+
+/// Encode with the URL safe alphabet and no padding
+pub fn encode_url(data: &[u8]) -> String {
+    encode_with(data, URL_SAFE, false)
+}
+
+/// Decode the URL safe alphabet without padding
+pub fn decode_url(data: &str) -> Result<Vec<u8>, CryptoError> {
+    decode_with(data, URL_SAFE, false)
+}
+
+fn encode_with(data: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut out = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(alphabet[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else if pad {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn decode_with(data: &str, alphabet: &[u8; 64], pad: bool) -> Result<Vec<u8>, CryptoError> {
+    let bytes = data.as_bytes();
+    let unpadded = if pad {
+        if bytes.len() % 4 != 0 {
+            return Err(CryptoError::ParseError("Invalid base64 length".to_string()));
+        }
+        let padding = bytes
+            .iter()
+            .rev()
+            .take(2)
+            .take_while(|b| **b == b'=')
+            .count();
+        &bytes[..bytes.len() - padding]
+    } else {
+        bytes
+    };
+    if unpadded.len() % 4 == 1 {
+        return Err(CryptoError::ParseError("Invalid base64 length".to_string()));
+    }
+
+    let mut out = Vec::with_capacity(unpadded.len() * 3 / 4);
+    for chunk in unpadded.chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let v = alphabet.iter().position(|a| a == c).ok_or_else(|| {
+                CryptoError::ParseError(format!("Invalid base64 character {}", *c as char))
+            })?;
+            n |= (v as u32) << (18 - 6 * i);
+        }
+        let decoded = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        out.extend_from_slice(&decoded[..chunk.len() - 1]);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (plain, encoded) in vectors.iter() {
+            assert_eq!(*encoded, encode(plain.as_bytes()));
+            assert_eq!(plain.as_bytes(), &decode(encoded).unwrap()[..]);
+            assert_eq!(encoded.trim_end_matches('='), encode_url(plain.as_bytes()));
+        }
+        assert_eq!("-_8", encode_url(&[0xfb, 0xff]));
+        assert_eq!(vec![0xfb, 0xff], decode_url("-_8").unwrap());
+        assert!(decode("Zm9").is_err());
+        assert!(decode("Zm9*").is_err());
+        assert!(decode_url("Z").is_err());
+    }
+}
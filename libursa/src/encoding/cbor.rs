@@ -0,0 +1,164 @@
+//! Versioned CBOR envelopes for keys and signatures.
+//!
+//! The envelope `[id, version, payload]` is defined in `ursa_core::cbor` and is
+//! shared with the other ursa crates. Keys are stored in the raw encodings of
+//! [`Pkcs8KeyHandler`], which don't depend on the backend used to create them.
+//!
+//! ```
+//! # extern crate ursa;
+//! use ursa::encoding::cbor::CborKeyHandler;
+//! use ursa::signatures::{ed25519::Ed25519Sha512, SignatureScheme};
+//!
+//! let scheme = Ed25519Sha512::new();
+//! let (pk, sk) = scheme.keypair(None).unwrap();
+//! let cbor = scheme.public_key_to_cbor(&pk).unwrap();
+//! assert_eq!(pk, scheme.public_key_from_cbor(&cbor).unwrap());
+//! let cbor = scheme.private_key_to_cbor(&sk).unwrap();
+//! assert_eq!((pk, sk), scheme.keypair_from_cbor(cbor.expose_secret()).unwrap());
+//! ```
+
+use super::pkcs8::{KeyAlgorithm, Pkcs8KeyHandler};
+use alloc::string::String;
+use alloc::vec::Vec;
+use keys::{PrivateKey, PublicKey, Secret};
+pub use ursa_core::cbor::{CborError, CborSerializable, Envelope, Reader, Writer};
+use CryptoError;
+
+/// The version of the key and signature payloads
+pub const KEY_PAYLOAD_VERSION: u32 = 1;
+
+/// The scheme part of the envelope ids of keys of `algorithm`
+pub fn scheme_id(algorithm: KeyAlgorithm) -> &'static str {
+    match algorithm {
+        KeyAlgorithm::Ed25519 => "ed25519",
+        KeyAlgorithm::X25519 => "x25519",
+        KeyAlgorithm::EcSecp256k1 => "secp256k1",
+    }
+}
+
+/// CBOR envelopes for the key types of [`Pkcs8KeyHandler`]
+pub trait CborKeyHandler: Pkcs8KeyHandler {
+    /// Encode `pk` in a `<scheme>/public-key` envelope
+    fn public_key_to_cbor(&self, pk: &PublicKey) -> Result<Vec<u8>, CryptoError> {
+        Ok(Envelope::new(
+            envelope_id(self, "public-key"),
+            KEY_PAYLOAD_VERSION,
+            self.public_key_to_raw(pk)?,
+        )
+        .to_cbor())
+    }
+
+    /// Decode a `<scheme>/public-key` envelope
+    fn public_key_from_cbor(&self, data: &[u8]) -> Result<PublicKey, CryptoError> {
+        let envelope = open(self, "public-key", data)?;
+        self.public_key_from_raw(&envelope.expose_secret().payload)
+    }
+
+    /// Encode `sk` in a `<scheme>/private-key` envelope
+    fn private_key_to_cbor(&self, sk: &PrivateKey) -> Result<Secret<Vec<u8>>, CryptoError> {
+        let envelope = Secret::new(Envelope::new(
+            envelope_id(self, "private-key"),
+            KEY_PAYLOAD_VERSION,
+            self.private_key_to_raw(sk)?,
+        ));
+        Ok(Secret::new(envelope.expose_secret().to_cbor()))
+    }
+
+    /// Decode a `<scheme>/private-key` envelope and derive its public key
+    fn keypair_from_cbor(&self, data: &[u8]) -> Result<(PublicKey, PrivateKey), CryptoError> {
+        let envelope = open(self, "private-key", data)?;
+        self.keypair_from_raw(&envelope.expose_secret().payload)
+    }
+
+    /// Encode `signature` in a `<scheme>/signature` envelope
+    fn signature_to_cbor(&self, signature: &[u8]) -> Vec<u8> {
+        Envelope::new(
+            envelope_id(self, "signature"),
+            KEY_PAYLOAD_VERSION,
+            signature.to_vec(),
+        )
+        .to_cbor()
+    }
+
+    /// Decode a `<scheme>/signature` envelope
+    fn signature_from_cbor(&self, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let envelope = open(self, "signature", data)?;
+        Ok(envelope.expose_secret().payload.clone())
+    }
+}
+
+impl<H: Pkcs8KeyHandler> CborKeyHandler for H {}
+
+fn envelope_id<H: Pkcs8KeyHandler + ?Sized>(handler: &H, kind: &str) -> String {
+    format!("{}/{}", scheme_id(handler.key_algorithm()), kind)
+}
+
+/// Decode an envelope, which may hold a private key
+fn open<H: Pkcs8KeyHandler + ?Sized>(
+    handler: &H,
+    kind: &str,
+    data: &[u8],
+) -> Result<Secret<Envelope>, CryptoError> {
+    let envelope = Secret::new(Envelope::from_cbor(data)?);
+    let e = envelope.expose_secret();
+    e.expect_id(&envelope_id(handler, kind))?;
+    if e.version != KEY_PAYLOAD_VERSION {
+        return Err(CborError::UnsupportedVersion {
+            id: e.id.clone(),
+            version: e.version,
+        }
+        .into());
+    }
+    Ok(envelope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    #[test]
+    fn ed25519_cbor() {
+        use signatures::{ed25519::Ed25519Sha512, SignatureScheme};
+
+        let scheme = Ed25519Sha512::new();
+        let (pk, sk) = scheme.keypair(None).unwrap();
+        let cbor = scheme.public_key_to_cbor(&pk).unwrap();
+        assert_eq!("ed25519/public-key", Envelope::from_cbor(&cbor).unwrap().id);
+        assert_eq!(pk, scheme.public_key_from_cbor(&cbor).unwrap());
+        assert!(scheme.keypair_from_cbor(&cbor).is_err());
+
+        let cbor = scheme.private_key_to_cbor(&sk).unwrap();
+        assert_eq!(
+            (pk.clone(), sk.clone()),
+            scheme.keypair_from_cbor(cbor.expose_secret()).unwrap()
+        );
+
+        let signature = scheme.sign(b"message", &sk).unwrap();
+        let cbor = scheme.signature_to_cbor(&signature);
+        assert_eq!(signature, scheme.signature_from_cbor(&cbor).unwrap());
+
+        let v2 = Envelope::new("ed25519/signature", 2, signature).to_cbor();
+        assert!(scheme.signature_from_cbor(&v2).is_err());
+    }
+
+    #[cfg(any(
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    ))]
+    #[test]
+    fn secp256k1_cbor() {
+        use signatures::{secp256k1::EcdsaSecp256k1Sha256, SignatureScheme};
+
+        let scheme = EcdsaSecp256k1Sha256::new();
+        let (pk, sk) = scheme.keypair(None).unwrap();
+        let cbor = scheme.public_key_to_cbor(&pk).unwrap();
+        assert_eq!(pk, scheme.public_key_from_cbor(&cbor).unwrap());
+        let cbor = scheme.private_key_to_cbor(&sk).unwrap();
+        assert_eq!(
+            (pk, sk),
+            scheme.keypair_from_cbor(cbor.expose_secret()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! COSE keys (RFC 8152 section 7) for the key types supported by
+//! [`Pkcs8KeyHandler`](../pkcs8/trait.Pkcs8KeyHandler.html).
+//!
+//! Ed25519 and X25519 keys are `OKP` keys, secp256k1 keys are `EC2` keys
+//! on the curve registered by RFC 8812.
+
+use super::pkcs8::{KeyAlgorithm, Pkcs8KeyHandler};
+use keys::{PrivateKey, PublicKey};
+use serde_cbor::Value;
+use std::collections::BTreeMap;
+use zeroize::Zeroize;
+use CryptoError;
+
+pub const KTY_OKP: i128 = 1;
+pub const KTY_EC2: i128 = 2;
+
+pub const CRV_X25519: i128 = 4;
+pub const CRV_ED25519: i128 = 6;
+pub const CRV_SECP256K1: i128 = 8;
+
+const LABEL_KTY: i128 = 1;
+const LABEL_KID: i128 = 2;
+const LABEL_ALG: i128 = 3;
+const LABEL_CRV: i128 = -1;
+const LABEL_X: i128 = -2;
+const LABEL_Y: i128 = -3;
+const LABEL_D: i128 = -4;
+
+/// Size of each secp256k1 coordinate
+const EC_FIELD_SIZE: usize = 32;
+
+/// A COSE_Key. The private part `d` is wiped when the key is dropped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoseKey {
+    pub kty: i128,
+    pub crv: i128,
+    pub x: Vec<u8>,
+    pub y: Option<Vec<u8>>,
+    pub d: Option<Vec<u8>>,
+    pub kid: Option<Vec<u8>>,
+    pub alg: Option<i128>,
+}
+
+impl CoseKey {
+    /// A COSE key holding only the public key
+    pub fn from_public_key<H: Pkcs8KeyHandler>(
+        handler: &H,
+        pk: &PublicKey,
+    ) -> Result<Self, CryptoError> {
+        let raw = handler.public_key_to_raw(pk)?;
+        let algorithm = handler.key_algorithm();
+        let (kty, crv) = kty_crv(algorithm);
+        let (x, y) = match algorithm {
+            KeyAlgorithm::Ed25519 | KeyAlgorithm::X25519 => (raw, None),
+            KeyAlgorithm::EcSecp256k1 => (
+                // Uncompressed point 0x04 || x || y
+                raw[1..1 + EC_FIELD_SIZE].to_vec(),
+                Some(raw[1 + EC_FIELD_SIZE..].to_vec()),
+            ),
+        };
+        Ok(CoseKey {
+            kty,
+            crv,
+            x,
+            y,
+            d: None,
+            kid: None,
+            alg: None,
+        })
+    }
+
+    /// A COSE key holding the private key and its public key
+    pub fn from_private_key<H: Pkcs8KeyHandler>(
+        handler: &H,
+        sk: &PrivateKey,
+    ) -> Result<Self, CryptoError> {
+        let raw = handler.private_key_to_raw(sk)?;
+        let (pk, _) = handler.keypair_from_raw(&raw)?;
+        let mut key = Self::from_public_key(handler, &pk)?;
+        key.d = Some(raw);
+        Ok(key)
+    }
+
+    /// Set the key id
+    pub fn with_kid(mut self, kid: &[u8]) -> Self {
+        self.kid = Some(kid.to_vec());
+        self
+    }
+
+    /// Set the algorithm the key is restricted to
+    pub fn with_alg(mut self, alg: i128) -> Self {
+        self.alg = Some(alg);
+        self
+    }
+
+    /// The public key, checking the COSE key is for the scheme of `handler`
+    pub fn to_public_key<H: Pkcs8KeyHandler>(&self, handler: &H) -> Result<PublicKey, CryptoError> {
+        let algorithm = handler.key_algorithm();
+        let (kty, crv) = kty_crv(algorithm);
+        if self.kty != kty || self.crv != crv {
+            return Err(CryptoError::ParseError(format!(
+                "Expected a COSE key with kty {} and crv {}, found {} and {}",
+                kty, crv, self.kty, self.crv
+            )));
+        }
+        let raw = match (algorithm, &self.y) {
+            (KeyAlgorithm::Ed25519, None) | (KeyAlgorithm::X25519, None) => self.x.clone(),
+            (KeyAlgorithm::EcSecp256k1, Some(y))
+                if self.x.len() == EC_FIELD_SIZE && y.len() == EC_FIELD_SIZE =>
+            {
+                let mut raw = vec![0x04];
+                raw.extend_from_slice(&self.x);
+                raw.extend_from_slice(y);
+                raw
+            }
+            _ => {
+                return Err(CryptoError::ParseError(
+                    "Invalid COSE key coordinates".to_string(),
+                ))
+            }
+        };
+        handler.public_key_from_raw(&raw)
+    }
+
+    /// The keypair, checking the public part matches the private key
+    pub fn to_keypair<H: Pkcs8KeyHandler>(
+        &self,
+        handler: &H,
+    ) -> Result<(PublicKey, PrivateKey), CryptoError> {
+        let expected = self.to_public_key(handler)?;
+        let d = self
+            .d
+            .as_ref()
+            .ok_or_else(|| CryptoError::ParseError("COSE key has no private key".to_string()))?;
+        let (pk, sk) = handler.keypair_from_raw(d)?;
+        if handler.public_key_to_raw(&pk)? != handler.public_key_to_raw(&expected)? {
+            return Err(CryptoError::ParseError(
+                "COSE key public key does not match the private key".to_string(),
+            ));
+        }
+        Ok((pk, sk))
+    }
+
+    /// The COSE key without the private key
+    pub fn to_public(&self) -> Self {
+        let mut public = self.clone();
+        public.d.zeroize();
+        public.d = None;
+        public
+    }
+
+    /// Encode as a canonical CBOR map
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CryptoError> {
+        let mut map = BTreeMap::new();
+        map.insert(Value::Integer(LABEL_KTY), Value::Integer(self.kty));
+        if let Some(ref kid) = self.kid {
+            map.insert(Value::Integer(LABEL_KID), Value::Bytes(kid.clone()));
+        }
+        if let Some(alg) = self.alg {
+            map.insert(Value::Integer(LABEL_ALG), Value::Integer(alg));
+        }
+        map.insert(Value::Integer(LABEL_CRV), Value::Integer(self.crv));
+        map.insert(Value::Integer(LABEL_X), Value::Bytes(self.x.clone()));
+        if let Some(ref y) = self.y {
+            map.insert(Value::Integer(LABEL_Y), Value::Bytes(y.clone()));
+        }
+        if let Some(ref d) = self.d {
+            map.insert(Value::Integer(LABEL_D), Value::Bytes(d.clone()));
+        }
+        let mut value = Value::Map(map);
+        let out = serde_cbor::to_vec(&value).map_err(|e| CryptoError::GeneralError(e.to_string()));
+        wipe(&mut value);
+        out
+    }
+
+    /// Decode from a CBOR map. Unknown labels are ignored.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, CryptoError> {
+        let mut map = match serde_cbor::from_slice(data) {
+            Ok(Value::Map(map)) => map,
+            _ => {
+                return Err(CryptoError::ParseError(
+                    "COSE key is not a CBOR map".to_string(),
+                ))
+            }
+        };
+        let key = CoseKey {
+            kty: integer(&mut map, LABEL_KTY)?.ok_or_else(|| missing_label("kty"))?,
+            crv: integer(&mut map, LABEL_CRV)?.ok_or_else(|| missing_label("crv"))?,
+            x: bytes(&mut map, LABEL_X)?.ok_or_else(|| missing_label("x"))?,
+            y: bytes(&mut map, LABEL_Y)?,
+            d: bytes(&mut map, LABEL_D)?,
+            kid: bytes(&mut map, LABEL_KID)?,
+            alg: integer(&mut map, LABEL_ALG)?,
+        };
+        Ok(key)
+    }
+}
+
+impl Drop for CoseKey {
+    fn drop(&mut self) {
+        self.d.zeroize();
+    }
+}
+
+fn kty_crv(algorithm: KeyAlgorithm) -> (i128, i128) {
+    match algorithm {
+        KeyAlgorithm::Ed25519 => (KTY_OKP, CRV_ED25519),
+        KeyAlgorithm::X25519 => (KTY_OKP, CRV_X25519),
+        KeyAlgorithm::EcSecp256k1 => (KTY_EC2, CRV_SECP256K1),
+    }
+}
+
+fn integer(map: &mut BTreeMap<Value, Value>, label: i128) -> Result<Option<i128>, CryptoError> {
+    match map.remove(&Value::Integer(label)) {
+        None => Ok(None),
+        Some(Value::Integer(i)) => Ok(Some(i)),
+        Some(_) => Err(CryptoError::ParseError(format!(
+            "COSE key label {} must be an integer",
+            label
+        ))),
+    }
+}
+
+fn bytes(map: &mut BTreeMap<Value, Value>, label: i128) -> Result<Option<Vec<u8>>, CryptoError> {
+    match map.remove(&Value::Integer(label)) {
+        None => Ok(None),
+        Some(Value::Bytes(b)) => Ok(Some(b)),
+        Some(_) => Err(CryptoError::ParseError(format!(
+            "COSE key label {} must be a byte string",
+            label
+        ))),
+    }
+}
+
+fn missing_label(name: &str) -> CryptoError {
+    CryptoError::ParseError(format!("COSE key is missing {}", name))
+}
+
+/// Clear the byte strings of an encoded map before it is dropped
+fn wipe(value: &mut Value) {
+    if let Value::Map(ref mut map) = value {
+        for v in map.values_mut() {
+            if let Value::Bytes(ref mut b) = v {
+                b.zeroize();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    #[test]
+    fn ed25519_cose_key() {
+        use signatures::{ed25519::Ed25519Sha512, SignatureScheme};
+
+        let scheme = Ed25519Sha512::new();
+        let sk = PrivateKey(
+            hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+                .unwrap(),
+        );
+        let key = CoseKey::from_private_key(&scheme, &sk).unwrap();
+        let public = key.to_public().with_kid(b"11");
+        // {1: 1, 2: h'3131', -1: 6, -2: h'd75a...'}
+        assert_eq!(
+            "a40101024231312006215820d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            hex::encode(public.to_bytes().unwrap())
+        );
+
+        let decoded = CoseKey::from_bytes(&key.to_bytes().unwrap()).unwrap();
+        assert_eq!(key, decoded);
+        let (pk, sk2) = decoded.to_keypair(&scheme).unwrap();
+        assert_eq!(sk, sk2);
+        assert_eq!(pk, public.to_public_key(&scheme).unwrap());
+        assert!(public.to_keypair(&scheme).is_err());
+
+        let (other, _) = scheme.keypair(None).unwrap();
+        let mut mismatched = key.clone();
+        mismatched.x = other[..].to_vec();
+        assert!(mismatched.to_keypair(&scheme).is_err());
+
+        assert!(CoseKey::from_bytes(&[0xa1, 0x01, 0x01]).is_err());
+        assert!(CoseKey::from_bytes(&[0x80]).is_err());
+    }
+
+    #[cfg(any(
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    ))]
+    #[test]
+    fn secp256k1_cose_key() {
+        use signatures::{secp256k1::EcdsaSecp256k1Sha256, SignatureScheme};
+
+        let scheme = EcdsaSecp256k1Sha256::new();
+        let (pk, sk) = scheme.keypair(None).unwrap();
+        let key = CoseKey::from_private_key(&scheme, &sk).unwrap();
+        assert_eq!(KTY_EC2, key.kty);
+        assert_eq!(CRV_SECP256K1, key.crv);
+        let (pk2, sk2) = CoseKey::from_bytes(&key.to_bytes().unwrap())
+            .unwrap()
+            .to_keypair(&scheme)
+            .unwrap();
+        assert_eq!(pk, pk2);
+        assert_eq!(sk, sk2);
+
+        let mut no_y = key.to_public();
+        no_y.y = None;
+        assert!(no_y.to_public_key(&scheme).is_err());
+    }
+}
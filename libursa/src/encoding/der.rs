@@ -0,0 +1,246 @@
+//! The subset of ASN.1 DER needed to read and write key structures.
+
+use alloc::vec::Vec;
+use CryptoError;
+
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OBJECT_IDENTIFIER: u8 = 0x06;
+pub const TAG_UTF8_STRING: u8 = 0x0c;
+pub const TAG_PRINTABLE_STRING: u8 = 0x13;
+pub const TAG_IA5_STRING: u8 = 0x16;
+pub const TAG_UTC_TIME: u8 = 0x17;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+
+/// Tag of an explicitly tagged, constructed context specific field `[n]`
+pub fn context_tag(n: u8) -> u8 {
+    0xa0 | n
+}
+
+/// Encode a tag, length and value
+pub fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(value.len() + 6);
+    out.push(tag);
+    let len = value.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let len_bytes = (len as u32).to_be_bytes();
+        let skip = len_bytes.iter().take_while(|b| **b == 0).count();
+        out.push(0x80 | (4 - skip) as u8);
+        out.extend_from_slice(&len_bytes[skip..]);
+    }
+    out.extend_from_slice(value);
+    out
+}
+
+/// Encode a sequence of already encoded fields
+pub fn sequence(fields: &[&[u8]]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &fields.concat())
+}
+
+/// Encode a set of already encoded fields, which must already be in DER order
+pub fn set(fields: &[&[u8]]) -> Vec<u8> {
+    tlv(TAG_SET, &fields.concat())
+}
+
+/// Encode a boolean
+pub fn boolean(value: bool) -> Vec<u8> {
+    tlv(TAG_BOOLEAN, &[if value { 0xff } else { 0 }])
+}
+
+/// Encode a non-negative integer given as big endian bytes
+pub fn integer(value: &[u8]) -> Vec<u8> {
+    let skip = value.iter().take_while(|b| **b == 0).count();
+    let mut v = value[skip..].to_vec();
+    if v.is_empty() || v[0] & 0x80 != 0 {
+        v.insert(0, 0);
+    }
+    tlv(TAG_INTEGER, &v)
+}
+
+/// Encode an octet string
+pub fn octet_string(value: &[u8]) -> Vec<u8> {
+    tlv(TAG_OCTET_STRING, value)
+}
+
+/// Encode a bit string with no unused bits
+pub fn bit_string(value: &[u8]) -> Vec<u8> {
+    let mut v = Vec::with_capacity(value.len() + 1);
+    v.push(0);
+    v.extend_from_slice(value);
+    tlv(TAG_BIT_STRING, &v)
+}
+
+/// Encode an object identifier given as its encoded content bytes
+pub fn object_identifier(oid: &[u8]) -> Vec<u8> {
+    tlv(TAG_OBJECT_IDENTIFIER, oid)
+}
+
+/// Reads DER fields one after another
+pub struct DerReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        DerReader { data }
+    }
+
+    /// True when all fields have been read
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The tag of the next field
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    /// Read the next field, which must have `tag`, and return its value
+    pub fn read(&mut self, tag: u8) -> Result<&'a [u8], CryptoError> {
+        if self.data.len() < 2 {
+            return Err(parse_error("truncated field"));
+        }
+        if self.data[0] != tag {
+            return Err(CryptoError::ParseError(format!(
+                "Expected DER tag {:#04x}, found {:#04x}",
+                tag, self.data[0]
+            )));
+        }
+        let (len, header) = match self.data[1] {
+            l if l < 0x80 => (l as usize, 2),
+            l => {
+                let n = (l & 0x7f) as usize;
+                if n == 0 || n > 4 || self.data.len() < 2 + n {
+                    return Err(parse_error("invalid length"));
+                }
+                let len = self.data[2..2 + n]
+                    .iter()
+                    .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+                if len < 0x80 || self.data[2] == 0 {
+                    return Err(parse_error("length is not minimally encoded"));
+                }
+                (len, 2 + n)
+            }
+        };
+        if self.data.len() - header < len {
+            return Err(parse_error("truncated field"));
+        }
+        let value = &self.data[header..header + len];
+        self.data = &self.data[header + len..];
+        Ok(value)
+    }
+
+    /// Read a sequence and return a reader over its fields
+    pub fn read_sequence(&mut self) -> Result<DerReader<'a>, CryptoError> {
+        self.read(TAG_SEQUENCE).map(DerReader::new)
+    }
+
+    /// Read a non-negative integer and return its big endian bytes without leading zeros
+    pub fn read_integer(&mut self) -> Result<&'a [u8], CryptoError> {
+        let value = self.read(TAG_INTEGER)?;
+        match value {
+            [] => Err(parse_error("empty integer")),
+            [b, ..] if b & 0x80 != 0 => Err(parse_error("negative integer")),
+            [0, b, ..] if b & 0x80 == 0 => Err(parse_error("integer is not minimally encoded")),
+            [0, rest @ ..] => Ok(rest),
+            _ => Ok(value),
+        }
+    }
+
+    /// Read the next field whatever its tag and return the tag and value
+    pub fn read_any(&mut self) -> Result<(u8, &'a [u8]), CryptoError> {
+        let tag = self.peek_tag().ok_or_else(|| parse_error("truncated field"))?;
+        self.read(tag).map(|value| (tag, value))
+    }
+
+    /// Read the next field and return all of its bytes, including the tag and length
+    pub fn read_raw(&mut self) -> Result<&'a [u8], CryptoError> {
+        let start = self.data;
+        self.read_any()?;
+        Ok(&start[..start.len() - self.data.len()])
+    }
+
+    /// Read a boolean
+    pub fn read_boolean(&mut self) -> Result<bool, CryptoError> {
+        match self.read(TAG_BOOLEAN)? {
+            [0] => Ok(false),
+            [0xff] => Ok(true),
+            _ => Err(parse_error("invalid boolean")),
+        }
+    }
+
+    /// Read a bit string with no unused bits
+    pub fn read_bit_string(&mut self) -> Result<&'a [u8], CryptoError> {
+        match self.read(TAG_BIT_STRING)? {
+            [0, rest @ ..] => Ok(rest),
+            _ => Err(parse_error("bit string has unused bits")),
+        }
+    }
+
+    /// Fail unless all fields have been read
+    pub fn finish(&self) -> Result<(), CryptoError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(parse_error("unexpected trailing data"))
+        }
+    }
+}
+
+fn parse_error(msg: &str) -> CryptoError {
+    CryptoError::ParseError(format!("Invalid DER: {}", msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn der_round_trip() {
+        let long = vec![7u8; 300];
+        let encoded = sequence(&[
+            &integer(&[0, 0, 0x80]),
+            &octet_string(&long),
+            &bit_string(&[1]),
+        ]);
+        assert_eq!(
+            &[0x30, 0x82, 0x01, 0x38, 0x02, 0x02, 0x00, 0x80],
+            &encoded[..8]
+        );
+
+        let mut reader = DerReader::new(&encoded);
+        let mut fields = reader.read_sequence().unwrap();
+        reader.finish().unwrap();
+        assert_eq!(&[0x80], fields.read_integer().unwrap());
+        assert_eq!(&long[..], fields.read(TAG_OCTET_STRING).unwrap());
+        assert_eq!(&[1], fields.read_bit_string().unwrap());
+        fields.finish().unwrap();
+    }
+
+    #[test]
+    fn der_rejects_malformed_input() {
+        assert!(DerReader::new(&[0x04, 0x03, 0x00])
+            .read(TAG_OCTET_STRING)
+            .is_err());
+        assert!(DerReader::new(&[0x04, 0x81, 0x01, 0x00])
+            .read(TAG_OCTET_STRING)
+            .is_err());
+        assert!(DerReader::new(&[0x02, 0x01, 0x00])
+            .read(TAG_OCTET_STRING)
+            .is_err());
+        assert!(DerReader::new(&[0x02, 0x02, 0x00, 0x01])
+            .read_integer()
+            .is_err());
+        assert!(DerReader::new(&[0x02, 0x01, 0xff]).read_integer().is_err());
+        assert!(DerReader::new(&[0x03, 0x02, 0x01, 0x00])
+            .read_bit_string()
+            .is_err());
+    }
+}
@@ -0,0 +1,221 @@
+//! The `did:key` method: a DID whose identifier is a multicodec public key.
+//!
+//! ```
+//! # extern crate ursa;
+//! use ursa::encoding::did_key::{DidKey, DidKeyHandler};
+//! use ursa::signatures::{ed25519::Ed25519Sha512, SignatureScheme};
+//!
+//! let scheme = Ed25519Sha512::new();
+//! let (pk, _) = scheme.keypair(None).unwrap();
+//! let did = scheme.public_key_to_did_key(&pk).unwrap();
+//! assert!(did.starts_with("did:key:z6Mk"));
+//! assert_eq!(pk, scheme.public_key_from_did_key(&did).unwrap());
+//!
+//! let resolved: DidKey = did.parse().unwrap();
+//! assert_eq!(format!("{}#{}", did, &did[8..]), resolved.key_id());
+//! ```
+
+use super::multibase::{self, MultibaseKeyHandler, Multicodec};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use keys::PublicKey;
+use std::fmt;
+use std::str::FromStr;
+use CryptoError;
+
+pub const DID_KEY_PREFIX: &str = "did:key:";
+
+/// A resolved `did:key`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DidKey {
+    codec: Multicodec,
+    public_key: Vec<u8>,
+    identifier: String,
+}
+
+impl DidKey {
+    /// The DID of a public key in the encoding its codec expects
+    pub fn new(codec: Multicodec, public_key: &[u8]) -> Result<Self, CryptoError> {
+        check_public(codec)?;
+        Ok(DidKey {
+            codec,
+            public_key: public_key.to_vec(),
+            identifier: multibase::encode_key(codec, public_key),
+        })
+    }
+
+    pub fn codec(&self) -> Multicodec {
+        self.codec
+    }
+
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// The multibase identifier after `did:key:`
+    pub fn identifier(&self) -> &str {
+        &self.identifier
+    }
+
+    /// The id of the DID document's only verification method
+    pub fn key_id(&self) -> String {
+        format!("{}#{}", self, self.identifier)
+    }
+}
+
+impl fmt::Display for DidKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", DID_KEY_PREFIX, self.identifier)
+    }
+}
+
+impl FromStr for DidKey {
+    type Err = CryptoError;
+
+    /// Accepts a DID or a DID URL whose fragment is the key's own id
+    fn from_str(s: &str) -> Result<Self, CryptoError> {
+        let mut parts = s.splitn(2, '#');
+        let did = parts.next().unwrap_or_default();
+        let identifier = did
+            .strip_prefix(DID_KEY_PREFIX)
+            .ok_or_else(|| CryptoError::ParseError(format!("{} is not a did:key", s)))?;
+        if !identifier.starts_with('z') {
+            return Err(CryptoError::ParseError(
+                "did:key identifiers must be base58btc".to_string(),
+            ));
+        }
+        if let Some(fragment) = parts.next() {
+            if fragment != identifier {
+                return Err(CryptoError::ParseError(format!(
+                    "Unknown did:key fragment {}",
+                    fragment
+                )));
+            }
+        }
+        let (codec, public_key) = multibase::decode_key(identifier)?;
+        check_public(codec)?;
+        Ok(DidKey {
+            codec,
+            public_key,
+            identifier: identifier.to_string(),
+        })
+    }
+}
+
+/// `did:key` conversion for the key types of [`MultibaseKeyHandler`]
+pub trait DidKeyHandler: MultibaseKeyHandler {
+    fn public_key_to_did_key(&self, pk: &PublicKey) -> Result<String, CryptoError> {
+        Ok(format!(
+            "{}{}",
+            DID_KEY_PREFIX,
+            self.public_key_to_multibase(pk)?
+        ))
+    }
+
+    fn public_key_from_did_key(&self, did: &str) -> Result<PublicKey, CryptoError> {
+        let did = did.parse::<DidKey>()?;
+        self.public_key_from_multibase(did.identifier())
+    }
+}
+
+impl<H: MultibaseKeyHandler> DidKeyHandler for H {}
+
+fn check_public(codec: Multicodec) -> Result<(), CryptoError> {
+    match codec {
+        Multicodec::Ed25519Pub
+        | Multicodec::X25519Pub
+        | Multicodec::Secp256k1Pub
+        | Multicodec::Bls12381G1Pub
+        | Multicodec::Bls12381G2Pub => Ok(()),
+        c => Err(CryptoError::ParseError(format!(
+            "{:?} is not a public key codec",
+            c
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn did_key_vectors() {
+        // From the did:key method specification
+        for (did, codec, len) in &[
+            (
+                "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp",
+                Multicodec::Ed25519Pub,
+                32,
+            ),
+            (
+                "did:key:z6LSeu9HkTHSfLLeUs2nnzUSNedgDUevfNQgQjQC23ZCit6F",
+                Multicodec::X25519Pub,
+                32,
+            ),
+            (
+                "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme",
+                Multicodec::Secp256k1Pub,
+                33,
+            ),
+        ] {
+            let parsed: DidKey = did.parse().unwrap();
+            assert_eq!(*codec, parsed.codec());
+            assert_eq!(*len, parsed.public_key().len());
+            assert_eq!(*did, parsed.to_string());
+            assert_eq!(parsed, DidKey::new(*codec, parsed.public_key()).unwrap());
+            assert_eq!(parsed, parsed.key_id().parse().unwrap());
+        }
+    }
+
+    #[test]
+    fn invalid_did_keys() {
+        let id = "z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp";
+        assert!(id.parse::<DidKey>().is_err());
+        assert!(format!("did:web:{}", id).parse::<DidKey>().is_err());
+        assert!(format!("did:key:{}#key-1", id).parse::<DidKey>().is_err());
+        let base64 = multibase::encode(
+            multibase::Base::Base64Url,
+            &multibase::decode(id).unwrap().1,
+        );
+        assert!(format!("did:key:{}", base64).parse::<DidKey>().is_err());
+        let private = multibase::encode_key(Multicodec::Ed25519Priv, &[1u8; 32]);
+        assert!(format!("did:key:{}", private).parse::<DidKey>().is_err());
+        assert!(DidKey::new(Multicodec::Secp256k1Priv, &[1u8; 32]).is_err());
+    }
+
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    #[test]
+    fn ed25519_did_key() {
+        use signatures::{ed25519::Ed25519Sha512, SignatureScheme};
+
+        let scheme = Ed25519Sha512::new();
+        let did = "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp";
+        let pk = scheme.public_key_from_did_key(did).unwrap();
+        assert_eq!(did, scheme.public_key_to_did_key(&pk).unwrap());
+        let (pk, _) = scheme.keypair(None).unwrap();
+        assert_eq!(
+            pk,
+            scheme
+                .public_key_from_did_key(&scheme.public_key_to_did_key(&pk).unwrap())
+                .unwrap()
+        );
+        assert!(scheme
+            .public_key_from_did_key("did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme")
+            .is_err());
+    }
+
+    #[cfg(any(
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    ))]
+    #[test]
+    fn secp256k1_did_key() {
+        use signatures::{secp256k1::EcdsaSecp256k1Sha256, SignatureScheme};
+
+        let scheme = EcdsaSecp256k1Sha256::new();
+        let did = "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme";
+        let pk = scheme.public_key_from_did_key(did).unwrap();
+        assert_eq!(did, scheme.public_key_to_did_key(&pk).unwrap());
+    }
+}
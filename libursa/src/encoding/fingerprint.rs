@@ -0,0 +1,184 @@
+//! SHA-256 key fingerprints for pinning, logging and lookup tables.
+//!
+//! The fingerprint of a key is its JWK thumbprint (RFC 7638): the SHA-256
+//! digest of the required JWK members, written in lexicographic order with
+//! no whitespace. It only depends on the key, not on how it was encoded
+//! or which backend produced it, and matches thumbprints computed by
+//! JOSE libraries once rendered with [`Fingerprint::to_base64url`].
+
+use super::pkcs8::{KeyAlgorithm, Pkcs8KeyHandler};
+use super::{base58, base64, multibase};
+use alloc::string::String;
+use keys::PublicKey;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use CryptoError;
+
+/// Size of a fingerprint in bytes
+pub const FINGERPRINT_SIZE: usize = 32;
+
+/// The multihash code of SHA-256
+const MULTIHASH_SHA2_256: u8 = 0x12;
+
+/// Size of each secp256k1 coordinate in an uncompressed point
+const EC_FIELD_SIZE: usize = 32;
+
+/// A SHA-256 key fingerprint
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint([u8; FINGERPRINT_SIZE]);
+
+impl Fingerprint {
+    /// The SHA-256 digest of `data`
+    pub fn digest(data: &[u8]) -> Self {
+        let mut fingerprint = [0u8; FINGERPRINT_SIZE];
+        fingerprint.copy_from_slice(&Sha256::digest(data));
+        Fingerprint(fingerprint)
+    }
+
+    /// The RFC 7638 thumbprint of a JWK with the given required members
+    pub fn jwk_thumbprint(kty: &str, crv: &str, x: &str, y: Option<&str>) -> Self {
+        let members = match y {
+            Some(y) => format!(
+                r#"{{"crv":"{}","kty":"{}","x":"{}","y":"{}"}}"#,
+                crv, kty, x, y
+            ),
+            None => format!(r#"{{"crv":"{}","kty":"{}","x":"{}"}}"#, crv, kty, x),
+        };
+        Self::digest(members.as_bytes())
+    }
+
+    pub fn as_bytes(&self) -> &[u8; FINGERPRINT_SIZE] {
+        &self.0
+    }
+
+    /// Lowercase hex, the `Display` format
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    pub fn to_base58(&self) -> String {
+        base58::encode(&self.0)
+    }
+
+    /// Unpadded URL safe base64, the usual format of JWK thumbprints
+    pub fn to_base64url(&self) -> String {
+        base64::encode_url(&self.0)
+    }
+
+    /// A base58btc multibase string of the SHA-256 multihash, as used by IPFS
+    pub fn to_multibase(&self) -> String {
+        let mut multihash = vec![MULTIHASH_SHA2_256, FINGERPRINT_SIZE as u8];
+        multihash.extend_from_slice(&self.0);
+        multibase::encode(multibase::Base::Base58Btc, &multihash)
+    }
+}
+
+impl From<[u8; FINGERPRINT_SIZE]> for Fingerprint {
+    fn from(bytes: [u8; FINGERPRINT_SIZE]) -> Self {
+        Fingerprint(bytes)
+    }
+}
+
+impl AsRef<[u8]> for Fingerprint {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+impl fmt::Debug for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Fingerprint({})", self.to_hex())
+    }
+}
+
+/// Fingerprints for the key types of [`Pkcs8KeyHandler`]
+pub trait FingerprintKeyHandler: Pkcs8KeyHandler {
+    /// The JWK thumbprint of `pk`
+    fn fingerprint(&self, pk: &PublicKey) -> Result<Fingerprint, CryptoError> {
+        let raw = self.public_key_to_raw(pk)?;
+        Ok(match self.key_algorithm() {
+            KeyAlgorithm::Ed25519 => {
+                Fingerprint::jwk_thumbprint("OKP", "Ed25519", &base64::encode_url(&raw), None)
+            }
+            KeyAlgorithm::X25519 => {
+                Fingerprint::jwk_thumbprint("OKP", "X25519", &base64::encode_url(&raw), None)
+            }
+            // Uncompressed point 0x04 || x || y
+            KeyAlgorithm::EcSecp256k1 => Fingerprint::jwk_thumbprint(
+                "EC",
+                "secp256k1",
+                &base64::encode_url(&raw[1..1 + EC_FIELD_SIZE]),
+                Some(&base64::encode_url(&raw[1 + EC_FIELD_SIZE..])),
+            ),
+        })
+    }
+}
+
+impl<H: Pkcs8KeyHandler> FingerprintKeyHandler for H {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn renderings() {
+        let fingerprint = Fingerprint::digest(b"abc");
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            fingerprint.to_string()
+        );
+        assert_eq!(
+            "ungWv48Bz-pBQUDeXa4iI7ADYaOWF3qctBD_YfIAFa0",
+            fingerprint.to_base64url()
+        );
+        assert_eq!(
+            base58::encode(fingerprint.as_bytes()),
+            fingerprint.to_base58()
+        );
+        let multibase = fingerprint.to_multibase();
+        assert!(multibase.starts_with("zQm"));
+        let (_, multihash) = multibase::decode(&multibase).unwrap();
+        assert_eq!(&[0x12, 0x20][..], &multihash[..2]);
+        assert_eq!(fingerprint.as_bytes(), &multihash[2..]);
+    }
+
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    #[test]
+    fn ed25519_thumbprint() {
+        use signatures::{ed25519::Ed25519Sha512, SignatureScheme};
+
+        // RFC 8037 Appendix A.3
+        let scheme = Ed25519Sha512::new();
+        let pk =
+            PublicKey(base64::decode_url("11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo").unwrap());
+        assert_eq!(
+            "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k",
+            scheme.fingerprint(&pk).unwrap().to_base64url()
+        );
+    }
+
+    #[cfg(any(
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    ))]
+    #[test]
+    fn secp256k1_fingerprint() {
+        use signatures::{secp256k1::EcdsaSecp256k1Sha256, EcdsaPublicKeyHandler, SignatureScheme};
+
+        let scheme = EcdsaSecp256k1Sha256::new();
+        let (pk, _) = scheme.keypair(None).unwrap();
+        let compressed = scheme.parse(&scheme.public_key_compressed(&pk)).unwrap();
+        assert_eq!(
+            scheme.fingerprint(&pk).unwrap(),
+            scheme.fingerprint(&compressed).unwrap()
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! JSON Canonicalization Scheme (RFC 8785).
+//!
+//! The canonical form of a JSON value is independent of key order,
+//! whitespace and number formatting, so it can be signed in one language
+//! and verified in another.
+
+use serde_json::{Map, Number, Value};
+use CryptoError;
+
+/// Largest integer that is exactly representable as an IEEE 754 double
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Serialize `value` in its canonical form.
+///
+/// Fails for integers that an IEEE 754 double can't represent, which
+/// RFC 8785 doesn't allow.
+pub fn canonicalize(value: &Value) -> Result<String, CryptoError> {
+    let mut out = String::new();
+    write_value(&mut out, value)?;
+    Ok(out)
+}
+
+fn write_value(out: &mut String, value: &Value) -> Result<(), CryptoError> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => write_number(out, n)?,
+        Value::String(s) => write_string(out, s),
+        Value::Array(a) => {
+            out.push('[');
+            for (i, v) in a.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, v)?;
+            }
+            out.push(']');
+        }
+        Value::Object(o) => write_object(out, o)?,
+    }
+    Ok(())
+}
+
+/// Members are sorted by the UTF-16 code units of their names
+fn write_object(out: &mut String, object: &Map<String, Value>) -> Result<(), CryptoError> {
+    let mut members = object
+        .iter()
+        .map(|(k, v)| (k.encode_utf16().collect::<Vec<u16>>(), k, v))
+        .collect::<Vec<_>>();
+    members.sort_by(|a, b| a.0.cmp(&b.0));
+
+    out.push('{');
+    for (i, (_, k, v)) in members.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_string(out, k);
+        out.push(':');
+        write_value(out, v)?;
+    }
+    out.push('}');
+    Ok(())
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_number(out: &mut String, n: &Number) -> Result<(), CryptoError> {
+    let f = if let Some(u) = n.as_u64() {
+        check_integer(u)?;
+        u as f64
+    } else if let Some(i) = n.as_i64() {
+        check_integer(i.unsigned_abs())?;
+        i as f64
+    } else {
+        n.as_f64()
+            .ok_or_else(|| CryptoError::ParseError(format!("Invalid JSON number {}", n)))?
+    };
+    out.push_str(&format_double(f));
+    Ok(())
+}
+
+fn check_integer(magnitude: u64) -> Result<(), CryptoError> {
+    if magnitude > MAX_SAFE_INTEGER {
+        return Err(CryptoError::ParseError(format!(
+            "Integer {} is too large for canonical JSON",
+            magnitude
+        )));
+    }
+    Ok(())
+}
+
+/// Format `f` like ECMAScript's `Number.prototype.toString`
+fn format_double(f: f64) -> String {
+    if f == 0.0 {
+        return "0".to_string();
+    }
+    // `{:e}` gives the shortest digits that round trip, e.g. `-1.25e-7`
+    let scientific = format!("{:e}", f.abs());
+    let (mantissa, exponent) = scientific.split_at(scientific.find('e').unwrap());
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    // The decimal point is after the first `n` digits
+    let n = exponent[1..].parse::<i32>().unwrap() + 1;
+
+    let mut out = if f < 0.0 {
+        "-".to_string()
+    } else {
+        String::new()
+    };
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.push_str(&"0".repeat((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.push_str(&"0".repeat(-n as usize));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        out.push('e');
+        out.push(if n > 0 { '+' } else { '-' });
+        out.push_str(&(n - 1).abs().to_string());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc8785_example() {
+        // Section 3.2.2
+        let value: Value = serde_json::from_str(
+            r#"{
+                "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#,
+            canonicalize(&value).unwrap()
+        );
+    }
+
+    #[test]
+    fn utf16_key_order() {
+        // Section 3.2.3
+        let value: Value = serde_json::from_str(
+            r#"{
+                "\u20ac": "Euro Sign",
+                "\r": "Carriage Return",
+                "\ufb33": "Hebrew Letter Dalet With Dagesh",
+                "1": "One",
+                "\ud83d\ude00": "Emoji: Grinning Face",
+                "\u0080": "Control",
+                "\u00f6": "Latin Small Letter O With Diaeresis"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\
+             \"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",\
+             \"\u{1f600}\":\"Emoji: Grinning Face\",\
+             \"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}",
+            canonicalize(&value).unwrap()
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        for (f, expected) in &[
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1.0, "1"),
+            (-1.5, "-1.5"),
+            (1e21, "1e+21"),
+            (1e20, "100000000000000000000"),
+            (123456789012345680000.0, "123456789012345680000"),
+            (0.000001, "0.000001"),
+            (0.0000001, "1e-7"),
+            (-1.25e-7, "-1.25e-7"),
+            (5e-324, "5e-324"),
+            (1.7976931348623157e308, "1.7976931348623157e+308"),
+            (9007199254740991.0, "9007199254740991"),
+        ] {
+            assert_eq!(*expected, format_double(*f));
+        }
+        assert_eq!("-42", canonicalize(&Value::from(-42)).unwrap());
+        assert!(canonicalize(&Value::from(1u64 << 53)).is_err());
+        assert!(canonicalize(&Value::from(-(1i64 << 53))).is_err());
+    }
+
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    #[test]
+    fn sign_json() {
+        use signatures::{ed25519::Ed25519Sha512, sign_json, verify_json, SignatureScheme};
+
+        let scheme = Ed25519Sha512::new();
+        let (pk, sk) = scheme.keypair(None).unwrap();
+        let value: Value =
+            serde_json::from_str(r#"{"b": [1.0, "x"], "a": {"d": 2, "c": null}}"#).unwrap();
+        let signature = sign_json(&scheme, &value, &sk).unwrap();
+
+        let reordered: Value =
+            serde_json::from_str(r#"{"a":{"c":null,"d":2e0},"b":[1,"x"]}"#).unwrap();
+        assert!(verify_json(&scheme, &reordered, &signature, &pk).unwrap());
+        let changed: Value = serde_json::from_str(r#"{"a":{"c":null,"d":3},"b":[1,"x"]}"#).unwrap();
+        assert!(!verify_json(&scheme, &changed, &signature, &pk).unwrap_or(false));
+    }
+}
@@ -0,0 +1,241 @@
+//! JSON Web Keys (RFC 7517) for the key types supported by
+//! [`Pkcs8KeyHandler`](../pkcs8/trait.Pkcs8KeyHandler.html).
+//!
+//! Ed25519 and X25519 keys are `OKP` keys (RFC 8037), secp256k1 keys are
+//! `EC` keys on the `secp256k1` curve (RFC 8812).
+
+use super::base64;
+#[cfg(feature = "sha2")]
+use super::fingerprint::Fingerprint;
+use super::pkcs8::{KeyAlgorithm, Pkcs8KeyHandler};
+use keys::{PrivateKey, PublicKey};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+use CryptoError;
+
+/// Size of each secp256k1 coordinate and of the private scalar
+const EC_FIELD_SIZE: usize = 32;
+
+/// A JSON Web Key. The private part `d` is wiped when the key is dropped.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    pub crv: String,
+    pub x: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub d: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+}
+
+impl Jwk {
+    /// A JWK holding only the public key
+    pub fn from_public_key<H: Pkcs8KeyHandler>(
+        handler: &H,
+        pk: &PublicKey,
+    ) -> Result<Self, CryptoError> {
+        let raw = handler.public_key_to_raw(pk)?;
+        let algorithm = handler.key_algorithm();
+        let (kty, crv) = kty_crv(algorithm);
+        let (x, y) = match algorithm {
+            KeyAlgorithm::Ed25519 | KeyAlgorithm::X25519 => (base64::encode_url(&raw), None),
+            KeyAlgorithm::EcSecp256k1 => (
+                // Uncompressed point 0x04 || x || y
+                base64::encode_url(&raw[1..1 + EC_FIELD_SIZE]),
+                Some(base64::encode_url(&raw[1 + EC_FIELD_SIZE..])),
+            ),
+        };
+        Ok(Jwk {
+            kty: kty.to_string(),
+            crv: crv.to_string(),
+            x,
+            y,
+            d: None,
+            kid: None,
+        })
+    }
+
+    /// A JWK holding the private key and its public key
+    pub fn from_private_key<H: Pkcs8KeyHandler>(
+        handler: &H,
+        sk: &PrivateKey,
+    ) -> Result<Self, CryptoError> {
+        let mut raw = handler.private_key_to_raw(sk)?;
+        let (pk, _) = handler.keypair_from_raw(&raw)?;
+        let mut jwk = Self::from_public_key(handler, &pk)?;
+        jwk.d = Some(base64::encode_url(&raw));
+        raw.zeroize();
+        Ok(jwk)
+    }
+
+    /// Set the key id
+    pub fn with_kid(mut self, kid: &str) -> Self {
+        self.kid = Some(kid.to_string());
+        self
+    }
+
+    /// The public key, checking the JWK is for the scheme of `handler`
+    pub fn to_public_key<H: Pkcs8KeyHandler>(&self, handler: &H) -> Result<PublicKey, CryptoError> {
+        let algorithm = handler.key_algorithm();
+        let (kty, crv) = kty_crv(algorithm);
+        if self.kty != kty || self.crv != crv {
+            return Err(CryptoError::ParseError(format!(
+                "Expected a {} {} JWK, found {} {}",
+                kty, crv, self.kty, self.crv
+            )));
+        }
+        let x = base64::decode_url(&self.x)?;
+        let raw = match (algorithm, &self.y) {
+            (KeyAlgorithm::Ed25519, None) | (KeyAlgorithm::X25519, None) => x,
+            (KeyAlgorithm::EcSecp256k1, Some(y)) => {
+                let y = base64::decode_url(y)?;
+                if x.len() != EC_FIELD_SIZE || y.len() != EC_FIELD_SIZE {
+                    return Err(CryptoError::ParseError(
+                        "Invalid EC JWK coordinate length".to_string(),
+                    ));
+                }
+                let mut raw = vec![0x04];
+                raw.extend_from_slice(&x);
+                raw.extend_from_slice(&y);
+                raw
+            }
+            _ => {
+                return Err(CryptoError::ParseError(
+                    "Invalid JWK coordinates".to_string(),
+                ))
+            }
+        };
+        handler.public_key_from_raw(&raw)
+    }
+
+    /// The keypair, checking the public part matches the private key
+    pub fn to_keypair<H: Pkcs8KeyHandler>(
+        &self,
+        handler: &H,
+    ) -> Result<(PublicKey, PrivateKey), CryptoError> {
+        let expected = self.to_public_key(handler)?;
+        let mut raw = match self.d {
+            Some(ref d) => base64::decode_url(d)?,
+            None => {
+                return Err(CryptoError::ParseError(
+                    "JWK has no private key".to_string(),
+                ))
+            }
+        };
+        let result = handler.keypair_from_raw(&raw);
+        raw.zeroize();
+        let (pk, sk) = result?;
+        if handler.public_key_to_raw(&pk)? != handler.public_key_to_raw(&expected)? {
+            return Err(CryptoError::ParseError(
+                "JWK public key does not match the private key".to_string(),
+            ));
+        }
+        Ok((pk, sk))
+    }
+
+    /// The RFC 7638 thumbprint, computed from the public members only
+    #[cfg(feature = "sha2")]
+    pub fn thumbprint(&self) -> Fingerprint {
+        Fingerprint::jwk_thumbprint(&self.kty, &self.crv, &self.x, self.y.as_deref())
+    }
+
+    /// The JWK without the private key
+    pub fn to_public(&self) -> Self {
+        let mut public = self.clone();
+        public.d.zeroize();
+        public.d = None;
+        public
+    }
+}
+
+impl Drop for Jwk {
+    fn drop(&mut self) {
+        self.d.zeroize();
+    }
+}
+
+fn kty_crv(algorithm: KeyAlgorithm) -> (&'static str, &'static str) {
+    match algorithm {
+        KeyAlgorithm::Ed25519 => ("OKP", "Ed25519"),
+        KeyAlgorithm::X25519 => ("OKP", "X25519"),
+        KeyAlgorithm::EcSecp256k1 => ("EC", "secp256k1"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    #[test]
+    fn ed25519_jwk() {
+        use signatures::{ed25519::Ed25519Sha512, SignatureScheme};
+
+        // RFC 8037 Appendix A.1
+        let jwk: Jwk = serde_json::from_str(
+            r#"{"kty":"OKP","crv":"Ed25519",
+                "d":"nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A",
+                "x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}"#,
+        )
+        .unwrap();
+        let scheme = Ed25519Sha512::new();
+        let (pk, sk) = jwk.to_keypair(&scheme).unwrap();
+        assert_eq!(
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            hex::encode(&pk[..])
+        );
+        assert_eq!(jwk, Jwk::from_private_key(&scheme, &sk).unwrap());
+        assert_eq!(jwk.to_public(), Jwk::from_public_key(&scheme, &pk).unwrap());
+        assert_eq!(pk, jwk.to_public().to_public_key(&scheme).unwrap());
+        assert!(jwk.to_public().to_keypair(&scheme).is_err());
+        // RFC 8037 Appendix A.3
+        assert_eq!(
+            "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k",
+            jwk.thumbprint().to_base64url()
+        );
+
+        let public = serde_json::to_string(&jwk.to_public().with_kid("key-1")).unwrap();
+        assert_eq!(
+            r#"{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo","kid":"key-1"}"#,
+            public
+        );
+
+        let (other, _) = scheme.keypair(None).unwrap();
+        let mut mismatched = jwk.clone();
+        mismatched.x = Jwk::from_public_key(&scheme, &other).unwrap().x.clone();
+        assert!(mismatched.to_keypair(&scheme).is_err());
+    }
+
+    #[cfg(any(
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    ))]
+    #[test]
+    fn secp256k1_jwk() {
+        use signatures::{secp256k1::EcdsaSecp256k1Sha256, SignatureScheme};
+
+        let scheme = EcdsaSecp256k1Sha256::new();
+        let (pk, sk) = scheme.keypair(None).unwrap();
+        let jwk = Jwk::from_private_key(&scheme, &sk).unwrap();
+        assert_eq!("EC", jwk.kty);
+        assert_eq!("secp256k1", jwk.crv);
+        let json = serde_json::to_string(&jwk).unwrap();
+        let parsed: Jwk = serde_json::from_str(&json).unwrap();
+        let (pk2, sk2) = parsed.to_keypair(&scheme).unwrap();
+        assert_eq!(pk, pk2);
+        assert_eq!(sk, sk2);
+
+        let mut no_y = jwk.to_public();
+        no_y.y = None;
+        assert!(no_y.to_public_key(&scheme).is_err());
+
+        #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+        {
+            use signatures::ed25519::Ed25519Sha512;
+            assert!(jwk.to_public_key(&Ed25519Sha512).is_err());
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Standard encodings for keys so they can be exchanged with other tooling.
+
+pub mod base58;
+pub mod base64;
+#[cfg(feature = "openssh")]
+mod bcrypt_pbkdf;
+pub mod cbor;
+#[cfg(feature = "cose")]
+pub mod cose;
+pub mod der;
+pub mod did_key;
+#[cfg(feature = "sha2")]
+pub mod fingerprint;
+#[cfg(feature = "jose")]
+pub mod jcs;
+#[cfg(feature = "jose")]
+pub mod jwk;
+pub mod multibase;
+#[cfg(feature = "encrypted_pem")]
+pub mod pbes2;
+#[cfg(all(feature = "openssh", any(feature = "ed25519", feature = "ed25519_asm")))]
+pub mod openssh;
+pub mod pem;
+pub mod pkcs8;
+#[cfg(feature = "protobuf")]
+pub mod proto;
+#[cfg(all(
+    feature = "x509",
+    any(
+        feature = "ed25519",
+        feature = "ed25519_asm",
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    )
+))]
+pub mod x509;
@@ -0,0 +1,325 @@
+//! Multibase strings and multicodec prefixed keys, as used by DID methods
+//! and IPLD.
+//!
+//! Signatures have no multicodec and are written with [`encode`] directly.
+
+use super::pkcs8::{KeyAlgorithm, Pkcs8KeyHandler};
+use super::{base58, base64};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use keys::{PrivateKey, PublicKey};
+use zeroize::Zeroize;
+use CryptoError;
+
+/// The supported multibase encodings
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Base {
+    /// Lowercase hex, prefix `f`
+    Base16,
+    /// Bitcoin base58, prefix `z`
+    Base58Btc,
+    /// Unpadded URL safe base64, prefix `u`
+    Base64Url,
+}
+
+impl Base {
+    pub fn prefix(self) -> char {
+        match self {
+            Base::Base16 => 'f',
+            Base::Base58Btc => 'z',
+            Base::Base64Url => 'u',
+        }
+    }
+}
+
+/// Encode `data` with `base` and its prefix
+pub fn encode(base: Base, data: &[u8]) -> String {
+    let mut out = String::new();
+    out.push(base.prefix());
+    match base {
+        Base::Base16 => {
+            for b in data {
+                out.push_str(&format!("{:02x}", b));
+            }
+        }
+        Base::Base58Btc => out.push_str(&base58::encode(data)),
+        Base::Base64Url => out.push_str(&base64::encode_url(data)),
+    }
+    out
+}
+
+/// Decode a multibase string and return its encoding and bytes
+pub fn decode(data: &str) -> Result<(Base, Vec<u8>), CryptoError> {
+    let mut chars = data.chars();
+    let prefix = chars
+        .next()
+        .ok_or_else(|| CryptoError::ParseError("Empty multibase string".to_string()))?;
+    let body = chars.as_str();
+    match prefix {
+        'f' => {
+            if body.len() % 2 != 0 {
+                return Err(CryptoError::ParseError("Invalid base16 length".to_string()));
+            }
+            let bytes = (0..body.len())
+                .step_by(2)
+                .map(|i| {
+                    body.get(i..i + 2)
+                        .and_then(|h| u8::from_str_radix(h, 16).ok())
+                        .ok_or_else(|| CryptoError::ParseError("Invalid base16".to_string()))
+                })
+                .collect::<Result<Vec<u8>, CryptoError>>()?;
+            Ok((Base::Base16, bytes))
+        }
+        'z' => Ok((Base::Base58Btc, base58::decode(body)?)),
+        'u' => Ok((Base::Base64Url, base64::decode_url(body)?)),
+        p => Err(CryptoError::ParseError(format!(
+            "Unsupported multibase prefix {}",
+            p
+        ))),
+    }
+}
+
+/// Registered multicodec key types
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Multicodec {
+    Ed25519Pub,
+    X25519Pub,
+    Secp256k1Pub,
+    Bls12381G1Pub,
+    Bls12381G2Pub,
+    Ed25519Priv,
+    Secp256k1Priv,
+    X25519Priv,
+}
+
+impl Multicodec {
+    pub fn code(self) -> u64 {
+        match self {
+            Multicodec::Ed25519Pub => 0xed,
+            Multicodec::X25519Pub => 0xec,
+            Multicodec::Secp256k1Pub => 0xe7,
+            Multicodec::Bls12381G1Pub => 0xea,
+            Multicodec::Bls12381G2Pub => 0xeb,
+            Multicodec::Ed25519Priv => 0x1300,
+            Multicodec::Secp256k1Priv => 0x1301,
+            Multicodec::X25519Priv => 0x1302,
+        }
+    }
+
+    pub fn from_code(code: u64) -> Result<Self, CryptoError> {
+        match code {
+            0xed => Ok(Multicodec::Ed25519Pub),
+            0xec => Ok(Multicodec::X25519Pub),
+            0xe7 => Ok(Multicodec::Secp256k1Pub),
+            0xea => Ok(Multicodec::Bls12381G1Pub),
+            0xeb => Ok(Multicodec::Bls12381G2Pub),
+            0x1300 => Ok(Multicodec::Ed25519Priv),
+            0x1301 => Ok(Multicodec::Secp256k1Priv),
+            0x1302 => Ok(Multicodec::X25519Priv),
+            c => Err(CryptoError::ParseError(format!(
+                "Unsupported multicodec {:#x}",
+                c
+            ))),
+        }
+    }
+
+    fn public(algorithm: KeyAlgorithm) -> Self {
+        match algorithm {
+            KeyAlgorithm::Ed25519 => Multicodec::Ed25519Pub,
+            KeyAlgorithm::X25519 => Multicodec::X25519Pub,
+            KeyAlgorithm::EcSecp256k1 => Multicodec::Secp256k1Pub,
+        }
+    }
+
+    fn private(algorithm: KeyAlgorithm) -> Self {
+        match algorithm {
+            KeyAlgorithm::Ed25519 => Multicodec::Ed25519Priv,
+            KeyAlgorithm::X25519 => Multicodec::X25519Priv,
+            KeyAlgorithm::EcSecp256k1 => Multicodec::Secp256k1Priv,
+        }
+    }
+}
+
+/// Prefix `key` with the varint of `codec` and encode it as base58btc
+pub fn encode_key(codec: Multicodec, key: &[u8]) -> String {
+    let mut data = Vec::with_capacity(key.len() + 3);
+    let mut code = codec.code();
+    while code >= 0x80 {
+        data.push(code as u8 | 0x80);
+        code >>= 7;
+    }
+    data.push(code as u8);
+    data.extend_from_slice(key);
+    let out = encode(Base::Base58Btc, &data);
+    data.zeroize();
+    out
+}
+
+/// Decode a multibase string holding a multicodec prefixed key
+pub fn decode_key(data: &str) -> Result<(Multicodec, Vec<u8>), CryptoError> {
+    let (_, mut bytes) = decode(data)?;
+    let mut code = 0u64;
+    let mut len = 0;
+    loop {
+        let b = match bytes.get(len) {
+            Some(b) if len < 9 => *b,
+            _ => {
+                bytes.zeroize();
+                return Err(CryptoError::ParseError(
+                    "Invalid multicodec varint".to_string(),
+                ));
+            }
+        };
+        code |= u64::from(b & 0x7f) << (7 * len);
+        len += 1;
+        if b & 0x80 == 0 {
+            break;
+        }
+    }
+    let key = bytes[len..].to_vec();
+    bytes.zeroize();
+    Ok((Multicodec::from_code(code)?, key))
+}
+
+/// Multibase encoding for the key types of [`Pkcs8KeyHandler`].
+/// secp256k1 public keys are written compressed.
+pub trait MultibaseKeyHandler: Pkcs8KeyHandler {
+    fn public_key_to_multibase(&self, pk: &PublicKey) -> Result<String, CryptoError> {
+        let algorithm = self.key_algorithm();
+        let mut raw = self.public_key_to_raw(pk)?;
+        if algorithm == KeyAlgorithm::EcSecp256k1 {
+            // 0x04 || x || y to (0x02 | y odd) || x
+            let odd = raw[raw.len() - 1] & 1;
+            raw.truncate(33);
+            raw[0] = 0x02 | odd;
+        }
+        Ok(encode_key(Multicodec::public(algorithm), &raw))
+    }
+
+    fn public_key_from_multibase(&self, data: &str) -> Result<PublicKey, CryptoError> {
+        let (codec, raw) = decode_key(data)?;
+        expect_codec(Multicodec::public(self.key_algorithm()), codec)?;
+        self.public_key_from_raw(&raw)
+    }
+
+    fn private_key_to_multibase(&self, sk: &PrivateKey) -> Result<String, CryptoError> {
+        let mut raw = self.private_key_to_raw(sk)?;
+        let out = encode_key(Multicodec::private(self.key_algorithm()), &raw);
+        raw.zeroize();
+        Ok(out)
+    }
+
+    fn keypair_from_multibase(&self, data: &str) -> Result<(PublicKey, PrivateKey), CryptoError> {
+        let (codec, mut raw) = decode_key(data)?;
+        let result = expect_codec(Multicodec::private(self.key_algorithm()), codec)
+            .and_then(|_| self.keypair_from_raw(&raw));
+        raw.zeroize();
+        result
+    }
+}
+
+impl<H: Pkcs8KeyHandler> MultibaseKeyHandler for H {}
+
+fn expect_codec(expected: Multicodec, found: Multicodec) -> Result<(), CryptoError> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(CryptoError::ParseError(format!(
+            "Expected multicodec {:?}, found {:?}",
+            expected, found
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multibase_round_trip() {
+        let data = b"Decentralize everything!!";
+        assert_eq!(
+            "zUXE7GvtEk8XTXs1GF8HSGbVA9FCX9SEBPe",
+            encode(Base::Base58Btc, data)
+        );
+        assert_eq!(
+            "f446563656e7472616c697a652065766572797468696e672121",
+            encode(Base::Base16, data)
+        );
+        assert_eq!(
+            "uRGVjZW50cmFsaXplIGV2ZXJ5dGhpbmchIQ",
+            encode(Base::Base64Url, data)
+        );
+        for base in &[Base::Base16, Base::Base58Btc, Base::Base64Url] {
+            assert_eq!(
+                (*base, data.to_vec()),
+                decode(&encode(*base, data)).unwrap()
+            );
+        }
+        assert!(decode("").is_err());
+        assert!(decode("mAAAA").is_err());
+        assert!(decode("f0").is_err());
+    }
+
+    #[test]
+    fn multicodec_keys() {
+        let key = [7u8; 32];
+        let encoded = encode_key(Multicodec::Ed25519Pub, &key);
+        assert!(encoded.starts_with("z6Mk"));
+        assert_eq!(
+            (Multicodec::Ed25519Pub, key.to_vec()),
+            decode_key(&encoded).unwrap()
+        );
+        let encoded = encode_key(Multicodec::Secp256k1Priv, &key);
+        assert_eq!(
+            (Multicodec::Secp256k1Priv, key.to_vec()),
+            decode_key(&encoded).unwrap()
+        );
+        assert_eq!(
+            Multicodec::Bls12381G2Pub,
+            decode_key(&encode_key(Multicodec::Bls12381G2Pub, &[1u8; 96]))
+                .unwrap()
+                .0
+        );
+        assert!(decode_key(&encode(Base::Base58Btc, &[0x80])).is_err());
+        assert!(decode_key(&encode(Base::Base58Btc, &[0x01, 0x00])).is_err());
+    }
+
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    #[test]
+    fn ed25519_multibase() {
+        use signatures::{ed25519::Ed25519Sha512, SignatureScheme};
+
+        let scheme = Ed25519Sha512::new();
+        let (pk, sk) = scheme.keypair(None).unwrap();
+        let encoded = scheme.public_key_to_multibase(&pk).unwrap();
+        assert!(encoded.starts_with("z6Mk"));
+        assert_eq!(pk, scheme.public_key_from_multibase(&encoded).unwrap());
+        let (pk2, sk2) = scheme
+            .keypair_from_multibase(&scheme.private_key_to_multibase(&sk).unwrap())
+            .unwrap();
+        assert_eq!(pk, pk2);
+        assert_eq!(sk, sk2);
+        assert!(scheme.keypair_from_multibase(&encoded).is_err());
+    }
+
+    #[cfg(any(
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    ))]
+    #[test]
+    fn secp256k1_multibase() {
+        use signatures::{secp256k1::EcdsaSecp256k1Sha256, SignatureScheme};
+
+        let scheme = EcdsaSecp256k1Sha256::new();
+        let (pk, sk) = scheme.keypair(None).unwrap();
+        let encoded = scheme.public_key_to_multibase(&pk).unwrap();
+        assert!(encoded.starts_with("zQ3s"));
+        assert_eq!(pk, scheme.public_key_from_multibase(&encoded).unwrap());
+        let (_, sk2) = scheme
+            .keypair_from_multibase(&scheme.private_key_to_multibase(&sk).unwrap())
+            .unwrap();
+        assert_eq!(sk, sk2);
+    }
+}
@@ -0,0 +1,94 @@
+//! PEM armor as defined in RFC 7468.
+
+use super::base64;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use CryptoError;
+
+pub const PRIVATE_KEY_LABEL: &str = "PRIVATE KEY";
+pub const ENCRYPTED_PRIVATE_KEY_LABEL: &str = "ENCRYPTED PRIVATE KEY";
+pub const PUBLIC_KEY_LABEL: &str = "PUBLIC KEY";
+
+/// Wrap DER bytes in a PEM block with `label`
+pub fn encode(label: &str, der: &[u8]) -> String {
+    encode_with_width(label, der, 64)
+}
+
+/// Wrap bytes in a PEM block with `label` and lines of `width` characters.
+/// OpenSSH uses 70.
+pub fn encode_with_width(label: &str, der: &[u8], width: usize) -> String {
+    let body = base64::encode(der);
+    let mut out = format!("-----BEGIN {}-----\n", label);
+    for line in body.as_bytes().chunks(width) {
+        // base64 output is always ASCII
+        out.push_str(std::str::from_utf8(line).unwrap());
+        out.push('\n');
+    }
+    out.push_str(&format!("-----END {}-----\n", label));
+    out
+}
+
+/// Read the first PEM block, which must have `label`, and return its DER bytes.
+/// Text before the block and whitespace inside it are ignored.
+pub fn decode(label: &str, pem: &str) -> Result<Vec<u8>, CryptoError> {
+    let (found, der) = decode_any(pem)?;
+    if found != label {
+        return Err(CryptoError::ParseError(format!(
+            "Expected PEM label {}, found {}",
+            label, found
+        )));
+    }
+    Ok(der)
+}
+
+/// Read the first PEM block and return its label and DER bytes
+pub fn decode_any(pem: &str) -> Result<(String, Vec<u8>), CryptoError> {
+    let mut lines = pem.lines().map(str::trim);
+    let label = lines
+        .by_ref()
+        .find_map(|l| {
+            if l.starts_with("-----BEGIN ") && l.ends_with("-----") && l.len() >= 16 {
+                Some(l[11..l.len() - 5].to_string())
+            } else {
+                None
+            }
+        })
+        .ok_or_else(|| CryptoError::ParseError("No PEM block found".to_string()))?;
+
+    let end = format!("-----END {}-----", label);
+    let mut body = String::new();
+    for line in lines {
+        if line == end {
+            return Ok((label, base64::decode(&body)?));
+        }
+        body.push_str(line);
+    }
+    Err(CryptoError::ParseError(format!(
+        "Missing PEM end line for {}",
+        label
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pem_round_trip() {
+        let der = (0..100u8).collect::<Vec<u8>>();
+        let pem = encode(PUBLIC_KEY_LABEL, &der);
+        assert!(pem.starts_with("-----BEGIN PUBLIC KEY-----\n"));
+        assert!(pem.lines().all(|l| l.len() <= 64));
+        assert_eq!(der, decode(PUBLIC_KEY_LABEL, &pem).unwrap());
+        assert_eq!(
+            der,
+            decode(
+                PUBLIC_KEY_LABEL,
+                &format!("comment\r\n{}", pem.replace('\n', "\r\n"))
+            )
+            .unwrap()
+        );
+        assert!(decode(PRIVATE_KEY_LABEL, &pem).is_err());
+        assert!(decode(PUBLIC_KEY_LABEL, &pem[..pem.len() - 10]).is_err());
+    }
+}
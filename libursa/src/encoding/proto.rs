@@ -0,0 +1,10 @@
+//! Protobuf messages for keys, signatures and shares.
+//!
+//! The messages are the ones of `ursa_core::proto`, defined in
+//! `ursa_core/proto/ursa.proto`, so services in other languages can generate
+//! them with `protoc`. The `algorithm` field holds [`Algorithm::name`] for
+//! the registry types and the scheme name, e.g. `bls12381-g1`, for BLS.
+//!
+//! [`Algorithm::name`]: ../../registry/enum.Algorithm.html#method.name
+
+pub use ursa_core::proto::*;
@@ -0,0 +1,1126 @@
+//! Minimal X.509 (RFC 5280) certificates and PKCS#10 (RFC 2986) signing
+//! requests for Ed25519 and ECDSA secp256k1 keys.
+//!
+//! Certificates can be self signed, issued by a CA from a public key or from
+//! a signing request, and checked as a chain. Chain verification covers
+//! signatures, validity periods and basic constraints; it does not check
+//! revocation, name constraints or policies.
+//!
+//! ```
+//! # extern crate ursa;
+//! use ursa::encoding::x509::{verify_chain, CertificateBuilder, CertificationRequest, Name};
+//! use ursa::signatures::{ed25519::Ed25519Sha512, SignatureScheme};
+//!
+//! let scheme = Ed25519Sha512::new();
+//! let now = 1_600_000_000;
+//! let (ca_pk, ca_sk) = scheme.keypair(None).unwrap();
+//! let ca = CertificateBuilder::new(Name::new("Example CA"), now, now + 86_400)
+//!     .ca(None)
+//!     .self_signed(&scheme, &ca_pk, &ca_sk)
+//!     .unwrap();
+//!
+//! let (pk, sk) = scheme.keypair(None).unwrap();
+//! let csr = CertificationRequest::new(&scheme, Name::new("service"), &pk, &sk).unwrap();
+//! let leaf = CertificateBuilder::from_request(&csr, now, now + 3_600)
+//!     .unwrap()
+//!     .issued_by(&ca, &scheme, &ca_sk)
+//!     .unwrap();
+//! verify_chain(&[leaf], &[ca], now + 60).unwrap();
+//! ```
+
+use super::der::{self, DerReader};
+use super::pem;
+use super::pkcs8::{KeyAlgorithm, Pkcs8KeyHandler};
+use keys::{PrivateKey, PublicKey};
+use rand::{rngs::OsRng, RngCore};
+use CryptoError;
+
+pub const CERTIFICATE_LABEL: &str = "CERTIFICATE";
+pub const CERTIFICATE_REQUEST_LABEL: &str = "CERTIFICATE REQUEST";
+
+const OID_ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const OID_BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1d, 0x13];
+const OID_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x0f];
+/// The keyCertSign bit of KeyUsage, counted from the most significant bit
+const KEY_USAGE_KEY_CERT_SIGN: usize = 5;
+const SERIAL_SIZE: usize = 16;
+
+/// The signature algorithms that certificates can be signed with
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+    Ed25519,
+    EcdsaSha256,
+}
+
+impl SignatureAlgorithm {
+    fn to_der(self) -> Vec<u8> {
+        match self {
+            SignatureAlgorithm::Ed25519 => der::sequence(&[&der::object_identifier(OID_ED25519)]),
+            SignatureAlgorithm::EcdsaSha256 => {
+                der::sequence(&[&der::object_identifier(OID_ECDSA_WITH_SHA256)])
+            }
+        }
+    }
+
+    fn read(reader: &mut DerReader) -> Result<Self, CryptoError> {
+        let mut algorithm = reader.read_sequence()?;
+        let oid = algorithm.read(der::TAG_OBJECT_IDENTIFIER)?;
+        algorithm.finish()?;
+        if oid == OID_ED25519 {
+            Ok(SignatureAlgorithm::Ed25519)
+        } else if oid == OID_ECDSA_WITH_SHA256 {
+            Ok(SignatureAlgorithm::EcdsaSha256)
+        } else {
+            Err(CryptoError::NoSuchAlgorithm(
+                "Unsupported certificate signature algorithm".to_string(),
+            ))
+        }
+    }
+}
+
+/// Signs and verifies certificate structures with a scheme's keys
+pub trait X509SignatureHandler: Pkcs8KeyHandler {
+    fn signature_algorithm(&self) -> SignatureAlgorithm;
+    /// Sign `data` and return the signature as it appears in a certificate
+    fn sign_x509(&self, data: &[u8], sk: &PrivateKey) -> Result<Vec<u8>, CryptoError>;
+    /// Verify a signature as it appears in a certificate
+    fn verify_x509(
+        &self,
+        data: &[u8],
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<bool, CryptoError>;
+}
+
+#[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+impl X509SignatureHandler for ::signatures::ed25519::Ed25519Sha512 {
+    fn signature_algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::Ed25519
+    }
+    fn sign_x509(&self, data: &[u8], sk: &PrivateKey) -> Result<Vec<u8>, CryptoError> {
+        ::signatures::SignatureScheme::sign(self, data, sk)
+    }
+    fn verify_x509(
+        &self,
+        data: &[u8],
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<bool, CryptoError> {
+        ::signatures::SignatureScheme::verify(self, data, signature, pk)
+    }
+}
+
+#[cfg(any(
+    feature = "ecdsa_secp256k1",
+    feature = "ecdsa_secp256k1_native",
+    feature = "ecdsa_secp256k1_asm"
+))]
+impl X509SignatureHandler for ::signatures::secp256k1::EcdsaSecp256k1Sha256 {
+    fn signature_algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::EcdsaSha256
+    }
+    /// ECDSA signatures are DER encoded `Ecdsa-Sig-Value` structures
+    fn sign_x509(&self, data: &[u8], sk: &PrivateKey) -> Result<Vec<u8>, CryptoError> {
+        let compact = ::signatures::SignatureScheme::sign(self, data, sk)?;
+        ::signatures::secp256k1::compact_signature_to_der(&compact)
+    }
+    fn verify_x509(
+        &self,
+        data: &[u8],
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<bool, CryptoError> {
+        let mut compact = ::signatures::secp256k1::der_signature_to_compact(
+            signature,
+            ::signatures::secp256k1::SignatureParseFlags::STRICT,
+        )?;
+        // Other implementations do not always produce low S signatures
+        self.normalize_s(&mut compact)?;
+        ::signatures::SignatureScheme::verify(self, data, &compact, pk)
+    }
+}
+
+/// The attributes supported in names
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NameAttribute {
+    CommonName,
+    Country,
+    Organization,
+    OrganizationalUnit,
+}
+
+impl NameAttribute {
+    fn oid(self) -> [u8; 3] {
+        match self {
+            NameAttribute::CommonName => [0x55, 0x04, 0x03],
+            NameAttribute::Country => [0x55, 0x04, 0x06],
+            NameAttribute::Organization => [0x55, 0x04, 0x0a],
+            NameAttribute::OrganizationalUnit => [0x55, 0x04, 0x0b],
+        }
+    }
+
+    fn from_oid(oid: &[u8]) -> Option<Self> {
+        [
+            NameAttribute::CommonName,
+            NameAttribute::Country,
+            NameAttribute::Organization,
+            NameAttribute::OrganizationalUnit,
+        ]
+        .iter()
+        .find(|a| a.oid() == oid)
+        .copied()
+    }
+}
+
+/// A distinguished name. Names are compared by their DER encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Name {
+    der: Vec<u8>,
+}
+
+impl Name {
+    /// A name with only a common name
+    pub fn new(common_name: &str) -> Self {
+        Self::from_attributes(&[(NameAttribute::CommonName, common_name)])
+    }
+
+    /// A name with one attribute per relative distinguished name, in order
+    pub fn from_attributes(attributes: &[(NameAttribute, &str)]) -> Self {
+        let rdns = attributes
+            .iter()
+            .map(|(attribute, value)| {
+                let tag = if *attribute == NameAttribute::Country {
+                    der::TAG_PRINTABLE_STRING
+                } else {
+                    der::TAG_UTF8_STRING
+                };
+                der::set(&[&der::sequence(&[
+                    &der::object_identifier(&attribute.oid()),
+                    &der::tlv(tag, value.as_bytes()),
+                ])])
+            })
+            .collect::<Vec<Vec<u8>>>();
+        Name {
+            der: der::tlv(der::TAG_SEQUENCE, &rdns.concat()),
+        }
+    }
+
+    /// The supported attributes in the name. Others are skipped.
+    pub fn attributes(&self) -> Result<Vec<(NameAttribute, String)>, CryptoError> {
+        let mut out = Vec::new();
+        let mut rdns = DerReader::new(&self.der).read_sequence()?;
+        while !rdns.is_empty() {
+            let mut rdn = DerReader::new(rdns.read(der::TAG_SET)?);
+            while !rdn.is_empty() {
+                let mut pair = rdn.read_sequence()?;
+                let oid = pair.read(der::TAG_OBJECT_IDENTIFIER)?;
+                let (tag, value) = pair.read_any()?;
+                pair.finish()?;
+                match (NameAttribute::from_oid(oid), tag) {
+                    (Some(attribute), der::TAG_UTF8_STRING)
+                    | (Some(attribute), der::TAG_PRINTABLE_STRING)
+                    | (Some(attribute), der::TAG_IA5_STRING) => {
+                        let value = String::from_utf8(value.to_vec()).map_err(|_| {
+                            CryptoError::ParseError("Invalid name attribute".to_string())
+                        })?;
+                        out.push((attribute, value));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn common_name(&self) -> Option<String> {
+        self.attributes().ok().and_then(|attributes| {
+            attributes
+                .into_iter()
+                .find(|(a, _)| *a == NameAttribute::CommonName)
+                .map(|(_, v)| v)
+        })
+    }
+
+    pub fn to_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    fn read(reader: &mut DerReader) -> Result<Self, CryptoError> {
+        let der = reader.read_raw()?;
+        if der[0] != der::TAG_SEQUENCE {
+            return Err(CryptoError::ParseError("Invalid name".to_string()));
+        }
+        let name = Name { der: der.to_vec() };
+        name.attributes()?;
+        Ok(name)
+    }
+}
+
+/// A PKCS#10 certificate signing request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificationRequest {
+    der: Vec<u8>,
+    subject: Name,
+    subject_public_key_info: Vec<u8>,
+}
+
+impl CertificationRequest {
+    /// Create a request for `pk` signed with `sk`
+    pub fn new<H: X509SignatureHandler>(
+        handler: &H,
+        subject: Name,
+        pk: &PublicKey,
+        sk: &PrivateKey,
+    ) -> Result<Self, CryptoError> {
+        let spki = handler.to_spki_der(pk)?;
+        let info = der::sequence(&[
+            &der::integer(&[0]),
+            subject.to_der(),
+            &spki,
+            // No attributes
+            &der::tlv(der::context_tag(0), &[]),
+        ]);
+        let signature = handler.sign_x509(&info, sk)?;
+        let der = der::sequence(&[
+            &info,
+            &handler.signature_algorithm().to_der(),
+            &der::bit_string(&signature),
+        ]);
+        Ok(CertificationRequest {
+            der,
+            subject,
+            subject_public_key_info: spki,
+        })
+    }
+
+    /// Parse a request and check its signature
+    pub fn from_der(data: &[u8]) -> Result<Self, CryptoError> {
+        let (info, algorithm, signature) = read_signed(data)?;
+        let mut fields = DerReader::new(info).read_sequence()?;
+        if !fields.read_integer()?.is_empty() {
+            return Err(CryptoError::ParseError(
+                "Unsupported certificate request version".to_string(),
+            ));
+        }
+        let subject = Name::read(&mut fields)?;
+        let spki = fields.read_raw()?.to_vec();
+        fields.read(der::context_tag(0))?;
+        fields.finish()?;
+        if !verify_signature(algorithm, &spki, info, signature)? {
+            return Err(CryptoError::SigningError(
+                "Invalid certificate request signature".to_string(),
+            ));
+        }
+        Ok(CertificationRequest {
+            der: data.to_vec(),
+            subject,
+            subject_public_key_info: spki,
+        })
+    }
+
+    pub fn from_pem(data: &str) -> Result<Self, CryptoError> {
+        Self::from_der(&pem::decode(CERTIFICATE_REQUEST_LABEL, data)?)
+    }
+
+    pub fn to_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    pub fn to_pem(&self) -> String {
+        pem::encode(CERTIFICATE_REQUEST_LABEL, &self.der)
+    }
+
+    pub fn subject(&self) -> &Name {
+        &self.subject
+    }
+
+    /// The DER encoded SubjectPublicKeyInfo being certified
+    pub fn subject_public_key_info(&self) -> &[u8] {
+        &self.subject_public_key_info
+    }
+}
+
+/// The basic constraints extension
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BasicConstraints {
+    pub ca: bool,
+    /// The most CA certificates that may follow this one in a chain
+    pub path_len: Option<u8>,
+}
+
+/// Collects the fields of a new certificate
+#[derive(Debug, Clone)]
+pub struct CertificateBuilder {
+    subject: Name,
+    subject_public_key_info: Option<Vec<u8>>,
+    serial_number: Option<Vec<u8>>,
+    not_before: u64,
+    not_after: u64,
+    basic_constraints: Option<BasicConstraints>,
+}
+
+impl CertificateBuilder {
+    /// A certificate for `subject` valid between two times in seconds since the Unix epoch
+    pub fn new(subject: Name, not_before: u64, not_after: u64) -> Self {
+        CertificateBuilder {
+            subject,
+            subject_public_key_info: None,
+            serial_number: None,
+            not_before,
+            not_after,
+            basic_constraints: None,
+        }
+    }
+
+    /// A certificate for the subject and key of a signing request.
+    /// Fails if the request signature is invalid.
+    pub fn from_request(
+        request: &CertificationRequest,
+        not_before: u64,
+        not_after: u64,
+    ) -> Result<Self, CryptoError> {
+        // Requests built with `new` were never parsed, so check them here
+        let request = CertificationRequest::from_der(request.to_der())?;
+        let mut builder = Self::new(request.subject.clone(), not_before, not_after);
+        builder.subject_public_key_info = Some(request.subject_public_key_info);
+        Ok(builder)
+    }
+
+    /// Use a specific serial number instead of a random one
+    pub fn serial_number(mut self, serial_number: &[u8]) -> Self {
+        self.serial_number = Some(serial_number.to_vec());
+        self
+    }
+
+    /// Make the certificate a CA that may be followed by at most `path_len` CAs
+    pub fn ca(mut self, path_len: Option<u8>) -> Self {
+        self.basic_constraints = Some(BasicConstraints { ca: true, path_len });
+        self
+    }
+
+    /// Sign a certificate for `pk` with its own private key
+    pub fn self_signed<H: X509SignatureHandler>(
+        self,
+        handler: &H,
+        pk: &PublicKey,
+        sk: &PrivateKey,
+    ) -> Result<Certificate, CryptoError> {
+        let spki = handler.to_spki_der(pk)?;
+        let issuer = self.subject.clone();
+        self.sign(handler, issuer, spki, sk)
+    }
+
+    /// Certify `pk`, for certificates that are not built from a request
+    pub fn public_key<H: Pkcs8KeyHandler>(
+        mut self,
+        handler: &H,
+        pk: &PublicKey,
+    ) -> Result<Self, CryptoError> {
+        self.subject_public_key_info = Some(handler.to_spki_der(pk)?);
+        Ok(self)
+    }
+
+    /// Sign the certificate with the key of `issuer`, which must be a CA
+    pub fn issued_by<H: X509SignatureHandler>(
+        self,
+        issuer: &Certificate,
+        handler: &H,
+        issuer_sk: &PrivateKey,
+    ) -> Result<Certificate, CryptoError> {
+        if !issuer.is_ca() {
+            return Err(CryptoError::SigningError(
+                "The issuer is not a CA".to_string(),
+            ));
+        }
+        let (issuer_pk, _) = handler.keypair_from_raw(&handler.private_key_to_raw(issuer_sk)?)?;
+        if handler.to_spki_der(&issuer_pk)? != issuer.subject_public_key_info {
+            return Err(CryptoError::SigningError(
+                "The private key does not belong to the issuer".to_string(),
+            ));
+        }
+        let spki = self.subject_public_key_info.clone().ok_or_else(|| {
+            CryptoError::SigningError("The certificate has no public key".to_string())
+        })?;
+        self.sign(handler, issuer.subject.clone(), spki, issuer_sk)
+    }
+
+    fn sign<H: X509SignatureHandler>(
+        self,
+        handler: &H,
+        issuer: Name,
+        spki: Vec<u8>,
+        sk: &PrivateKey,
+    ) -> Result<Certificate, CryptoError> {
+        if self.not_after < self.not_before {
+            return Err(CryptoError::SigningError(
+                "The certificate expires before it is valid".to_string(),
+            ));
+        }
+        let serial_number = match self.serial_number {
+            Some(ref s) => s.clone(),
+            None => {
+                let mut s = vec![0u8; SERIAL_SIZE];
+                OsRng.fill_bytes(&mut s);
+                // Serial numbers must be positive
+                s[0] = s[0] & 0x7f | 0x01;
+                s
+            }
+        };
+        let algorithm = handler.signature_algorithm().to_der();
+        let mut fields = vec![
+            der::tlv(der::context_tag(0), &der::integer(&[2])),
+            der::integer(&serial_number),
+            algorithm.clone(),
+            issuer.to_der().to_vec(),
+            der::sequence(&[&write_time(self.not_before), &write_time(self.not_after)]),
+            self.subject.to_der().to_vec(),
+            spki,
+        ];
+        if let Some(constraints) = self.basic_constraints {
+            let mut value = Vec::new();
+            if constraints.ca {
+                value.extend_from_slice(&der::boolean(true));
+            }
+            if let Some(path_len) = constraints.path_len {
+                value.extend_from_slice(&der::integer(&[path_len]));
+            }
+            let extension = der::sequence(&[
+                &der::object_identifier(OID_BASIC_CONSTRAINTS),
+                &der::boolean(true),
+                &der::octet_string(&der::tlv(der::TAG_SEQUENCE, &value)),
+            ]);
+            fields.push(der::tlv(der::context_tag(3), &der::sequence(&[&extension])));
+        }
+        let tbs = der::sequence(&fields.iter().map(|f| &f[..]).collect::<Vec<&[u8]>>());
+        let signature = handler.sign_x509(&tbs, sk)?;
+        Certificate::from_der(&der::sequence(&[
+            &tbs,
+            &algorithm,
+            &der::bit_string(&signature),
+        ]))
+    }
+}
+
+/// A parsed certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate {
+    der: Vec<u8>,
+    serial_number: Vec<u8>,
+    issuer: Name,
+    subject: Name,
+    not_before: u64,
+    not_after: u64,
+    subject_public_key_info: Vec<u8>,
+    basic_constraints: Option<BasicConstraints>,
+    key_cert_sign: Option<bool>,
+    unknown_critical_extension: bool,
+}
+
+impl Certificate {
+    /// Parse a certificate. The signature is checked by [`verify_chain`].
+    pub fn from_der(data: &[u8]) -> Result<Self, CryptoError> {
+        let (tbs, algorithm, _) = read_signed(data)?;
+        let mut fields = DerReader::new(tbs).read_sequence()?;
+        let mut version = DerReader::new(fields.read(der::context_tag(0))?);
+        if version.read_integer()? != [2] {
+            return Err(CryptoError::ParseError(
+                "Only version 3 certificates are supported".to_string(),
+            ));
+        }
+        version.finish()?;
+        let serial_number = fields.read_integer()?.to_vec();
+        if SignatureAlgorithm::read(&mut fields)? != algorithm {
+            return Err(CryptoError::ParseError(
+                "Mismatched certificate signature algorithms".to_string(),
+            ));
+        }
+        let issuer = Name::read(&mut fields)?;
+        let mut validity = fields.read_sequence()?;
+        let not_before = read_time(&mut validity)?;
+        let not_after = read_time(&mut validity)?;
+        validity.finish()?;
+        let subject = Name::read(&mut fields)?;
+        let subject_public_key_info = fields.read_raw()?.to_vec();
+
+        let mut certificate = Certificate {
+            der: data.to_vec(),
+            serial_number,
+            issuer,
+            subject,
+            not_before,
+            not_after,
+            subject_public_key_info,
+            basic_constraints: None,
+            key_cert_sign: None,
+            unknown_critical_extension: false,
+        };
+        // Issuer and subject unique identifiers are skipped
+        for tag in &[0x81, 0x82] {
+            if fields.peek_tag() == Some(*tag) {
+                fields.read(*tag)?;
+            }
+        }
+        if fields.peek_tag() == Some(der::context_tag(3)) {
+            let mut wrapper = DerReader::new(fields.read(der::context_tag(3))?);
+            let mut extensions = wrapper.read_sequence()?;
+            wrapper.finish()?;
+            while !extensions.is_empty() {
+                certificate.read_extension(&mut extensions.read_sequence()?)?;
+            }
+        }
+        fields.finish()?;
+        Ok(certificate)
+    }
+
+    pub fn from_pem(data: &str) -> Result<Self, CryptoError> {
+        Self::from_der(&pem::decode(CERTIFICATE_LABEL, data)?)
+    }
+
+    pub fn to_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    pub fn to_pem(&self) -> String {
+        pem::encode(CERTIFICATE_LABEL, &self.der)
+    }
+
+    pub fn serial_number(&self) -> &[u8] {
+        &self.serial_number
+    }
+
+    pub fn issuer(&self) -> &Name {
+        &self.issuer
+    }
+
+    pub fn subject(&self) -> &Name {
+        &self.subject
+    }
+
+    /// Seconds since the Unix epoch
+    pub fn not_before(&self) -> u64 {
+        self.not_before
+    }
+
+    /// Seconds since the Unix epoch
+    pub fn not_after(&self) -> u64 {
+        self.not_after
+    }
+
+    /// The DER encoded SubjectPublicKeyInfo
+    pub fn subject_public_key_info(&self) -> &[u8] {
+        &self.subject_public_key_info
+    }
+
+    /// The subject public key, read with `handler`
+    pub fn public_key<H: Pkcs8KeyHandler>(&self, handler: &H) -> Result<PublicKey, CryptoError> {
+        handler.from_spki_der(&self.subject_public_key_info)
+    }
+
+    pub fn basic_constraints(&self) -> Option<BasicConstraints> {
+        self.basic_constraints
+    }
+
+    pub fn is_ca(&self) -> bool {
+        self.basic_constraints.map(|c| c.ca).unwrap_or(false) && self.key_cert_sign != Some(false)
+    }
+
+    fn read_extension(&mut self, extension: &mut DerReader) -> Result<(), CryptoError> {
+        let oid = extension.read(der::TAG_OBJECT_IDENTIFIER)?;
+        let critical = if extension.peek_tag() == Some(der::TAG_BOOLEAN) {
+            extension.read_boolean()?
+        } else {
+            false
+        };
+        let value = extension.read(der::TAG_OCTET_STRING)?;
+        extension.finish()?;
+
+        let mut reader = DerReader::new(value);
+        if oid == OID_BASIC_CONSTRAINTS {
+            let mut fields = reader.read_sequence()?;
+            let ca = if fields.peek_tag() == Some(der::TAG_BOOLEAN) {
+                fields.read_boolean()?
+            } else {
+                false
+            };
+            let path_len = if fields.is_empty() {
+                None
+            } else {
+                match fields.read_integer()? {
+                    [] => Some(0),
+                    [n] => Some(*n),
+                    _ => {
+                        return Err(CryptoError::ParseError(
+                            "Unsupported path length".to_string(),
+                        ))
+                    }
+                }
+            };
+            fields.finish()?;
+            self.basic_constraints = Some(BasicConstraints { ca, path_len });
+        } else if oid == OID_KEY_USAGE {
+            let (tag, bits) = reader.read_any()?;
+            if tag != der::TAG_BIT_STRING || bits.is_empty() {
+                return Err(CryptoError::ParseError("Invalid key usage".to_string()));
+            }
+            let byte = 1 + KEY_USAGE_KEY_CERT_SIGN / 8;
+            let mask = 0x80 >> (KEY_USAGE_KEY_CERT_SIGN % 8);
+            self.key_cert_sign = Some(bits.get(byte).map(|b| b & mask != 0).unwrap_or(false));
+        } else {
+            self.unknown_critical_extension |= critical;
+            return Ok(());
+        }
+        reader.finish()
+    }
+}
+
+/// Check that `chain`, ordered from the leaf up, leads to one of `trust_anchors`
+/// at time `now` in seconds since the Unix epoch. The anchors are trusted as
+/// given but must be valid at `now`.
+pub fn verify_chain(
+    chain: &[Certificate],
+    trust_anchors: &[Certificate],
+    now: u64,
+) -> Result<(), CryptoError> {
+    let leaf = chain
+        .first()
+        .ok_or_else(|| CryptoError::SigningError("The chain is empty".to_string()))?;
+    check_certificate(leaf, now)?;
+    // CA certificates seen so far, not counting self issued ones
+    let mut ca_count = 0u8;
+    for (i, cert) in chain.iter().enumerate() {
+        if trust_anchors.iter().any(|anchor| anchor.der == cert.der) {
+            return Ok(());
+        }
+        let issuer = match chain.get(i + 1) {
+            Some(issuer) => issuer,
+            None => trust_anchors
+                .iter()
+                .find(|anchor| anchor.subject == cert.issuer)
+                .ok_or_else(|| {
+                    CryptoError::SigningError("The chain has no trusted issuer".to_string())
+                })?,
+        };
+        check_certificate(issuer, now)?;
+        if issuer.subject != cert.issuer {
+            return Err(CryptoError::SigningError(format!(
+                "Certificate {} was not issued by the next certificate",
+                i
+            )));
+        }
+        if !issuer.is_ca() {
+            return Err(CryptoError::SigningError(
+                "An issuer is not a CA".to_string(),
+            ));
+        }
+        if i > 0 && cert.issuer != cert.subject {
+            ca_count += 1;
+        }
+        if let Some(path_len) = issuer.basic_constraints.and_then(|c| c.path_len) {
+            if ca_count > path_len {
+                return Err(CryptoError::SigningError(
+                    "The chain exceeds a path length constraint".to_string(),
+                ));
+            }
+        }
+        let (tbs, algorithm, signature) = read_signed(&cert.der)?;
+        if !verify_signature(algorithm, &issuer.subject_public_key_info, tbs, signature)? {
+            return Err(CryptoError::SigningError(format!(
+                "Invalid signature on certificate {}",
+                i
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn check_certificate(cert: &Certificate, now: u64) -> Result<(), CryptoError> {
+    if now < cert.not_before || now > cert.not_after {
+        return Err(CryptoError::SigningError(
+            "A certificate is not valid at this time".to_string(),
+        ));
+    }
+    if cert.unknown_critical_extension {
+        return Err(CryptoError::SigningError(
+            "A certificate has an unsupported critical extension".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Split a signed structure into the signed bytes, algorithm and signature
+fn read_signed(data: &[u8]) -> Result<(&[u8], SignatureAlgorithm, &[u8]), CryptoError> {
+    let mut reader = DerReader::new(data);
+    let mut fields = reader.read_sequence()?;
+    reader.finish()?;
+    let signed = fields.read_raw()?;
+    let algorithm = SignatureAlgorithm::read(&mut fields)?;
+    let signature = fields.read_bit_string()?;
+    fields.finish()?;
+    Ok((signed, algorithm, signature))
+}
+
+fn verify_signature(
+    algorithm: SignatureAlgorithm,
+    spki: &[u8],
+    data: &[u8],
+    signature: &[u8],
+) -> Result<bool, CryptoError> {
+    let result = match (algorithm, KeyAlgorithm::from_spki_der(spki)?) {
+        #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+        (SignatureAlgorithm::Ed25519, KeyAlgorithm::Ed25519) => {
+            let scheme = ::signatures::ed25519::Ed25519Sha512;
+            scheme.verify_x509(data, signature, &scheme.from_spki_der(spki)?)
+        }
+        #[cfg(any(
+            feature = "ecdsa_secp256k1",
+            feature = "ecdsa_secp256k1_native",
+            feature = "ecdsa_secp256k1_asm"
+        ))]
+        (SignatureAlgorithm::EcdsaSha256, KeyAlgorithm::EcSecp256k1) => {
+            let scheme = <::signatures::secp256k1::EcdsaSecp256k1Sha256 as ::signatures::SignatureScheme>::new();
+            scheme.verify_x509(data, signature, &scheme.from_spki_der(spki)?)
+        }
+        (a, k) => {
+            return Err(CryptoError::NoSuchAlgorithm(format!(
+                "Cannot verify {:?} signatures with {:?} keys",
+                a, k
+            )))
+        }
+    };
+    // Schemes report some invalid signatures as errors
+    Ok(result.unwrap_or(false))
+}
+
+/// UTCTime through 2049, GeneralizedTime after, as RFC 5280 requires
+fn write_time(time: u64) -> Vec<u8> {
+    let days = time / 86_400;
+    let seconds = time % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    let rest = format!(
+        "{:02}{:02}{:02}{:02}{:02}Z",
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    if year < 2050 {
+        der::tlv(
+            der::TAG_UTC_TIME,
+            format!("{:02}{}", year % 100, rest).as_bytes(),
+        )
+    } else {
+        der::tlv(
+            der::TAG_GENERALIZED_TIME,
+            format!("{:04}{}", year, rest).as_bytes(),
+        )
+    }
+}
+
+fn read_time(reader: &mut DerReader) -> Result<u64, CryptoError> {
+    let (tag, value) = reader.read_any()?;
+    let invalid = || CryptoError::ParseError("Invalid certificate time".to_string());
+    let (year, rest) = match (tag, value.len()) {
+        (der::TAG_UTC_TIME, 13) => {
+            let year = parse_digits(&value[..2]).ok_or_else(invalid)?;
+            (
+                if year < 50 { 2000 + year } else { 1900 + year },
+                &value[2..],
+            )
+        }
+        (der::TAG_GENERALIZED_TIME, 15) => {
+            (parse_digits(&value[..4]).ok_or_else(invalid)?, &value[4..])
+        }
+        _ => return Err(invalid()),
+    };
+    if rest[10] != b'Z' {
+        return Err(invalid());
+    }
+    let field = |i: usize| parse_digits(&rest[i..i + 2]).ok_or_else(invalid);
+    let (month, day, hour, minute, second) =
+        (field(0)?, field(2)?, field(4)?, field(6)?, field(8)?);
+    if year < 1970
+        || month == 0
+        || month > 12
+        || day == 0
+        || day > 31
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return Err(invalid());
+    }
+    let days = days_from_civil(year, month, day);
+    Ok(days * 86_400 + hour * 3600 + minute * 60 + second)
+}
+
+fn parse_digits(digits: &[u8]) -> Option<u64> {
+    digits.iter().try_fold(0u64, |acc, d| {
+        if d.is_ascii_digit() {
+            Some(acc * 10 + u64::from(d - b'0'))
+        } else {
+            None
+        }
+    })
+}
+
+/// Days since 1970-01-01 of a date on or after it
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date `days` after 1970-01-01
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use signatures::SignatureScheme;
+    use std::slice::from_ref;
+
+    const NOW: u64 = 1_800_000_000;
+
+    // Generated with OpenSSL: a secp256k1 CA and an Ed25519 leaf from a request
+    const OPENSSL_CA: &str = "-----BEGIN CERTIFICATE-----
+MIIBjTCCATSgAwIBAgIURYrPaB17BJjjebskbqJUP1vhvQkwCgYIKoZIzj0EAwIw
+FTETMBEGA1UEAwwKT3BlblNTTCBDQTAgFw0yNjEwMTYxMjEwMjdaGA8yMTI2MDky
+MjEyMTAyN1owFTETMBEGA1UEAwwKT3BlblNTTCBDQTBWMBAGByqGSM49AgEGBSuB
+BAAKA0IABMqXmyGyhsqWxQa0DsAkmjKL21F4xnJUUYKco0VD7a/A4qZqiQMGq+kn
+vF1Hn7nuiBMH7+YcxC/SKt76347Y1K2jYzBhMB0GA1UdDgQWBBQ3f/s+aSJVgp5+
+pYTiaksICyHXjDAfBgNVHSMEGDAWgBQ3f/s+aSJVgp5+pYTiaksICyHXjDAPBgNV
+HRMBAf8EBTADAQH/MA4GA1UdDwEB/wQEAwICBDAKBggqhkjOPQQDAgNHADBEAiA7
+DUWVbXV9ziN/ix/TXj+qh5wDkRdTfFG/vT8gekUG7wIgeFMp4/pigLwbsr+U9781
+nT2lfXgS/lgc4b/Ln2a6oVM=
+-----END CERTIFICATE-----
+";
+    const OPENSSL_LEAF: &str = "-----BEGIN CERTIFICATE-----
+MIIBRjCB7qADAgECAgISNDAKBggqhkjOPQQDAjAVMRMwEQYDVQQDDApPcGVuU1NM
+IENBMCAXDTI2MTAxNjEyMTAyOFoYDzIxMjYwOTIyMTIxMDI4WjAuMQswCQYDVQQG
+EwJVUzEQMA4GA1UECgwHRXhhbXBsZTENMAsGA1UEAwwEbGVhZjAqMAUGAytlcAMh
+AFkq7BNKCI+qMHtw9ib8P6HDR04zrGhuU5KYeWfPCf95o0IwQDAdBgNVHQ4EFgQU
+loWyRhT2FbMQPLL/o3z4V3s/oaEwHwYDVR0jBBgwFoAUN3/7PmkiVYKefqWE4mpL
+CAsh14wwCgYIKoZIzj0EAwIDRwAwRAIgIG75njHgT1Sbg2XH+WQPAyxLQqIMJvX3
+KAkBdNkUx14CIEMK+9l/L71XY6usXyDnrRkz49h0euZ62HB6WJ8pNpl6
+-----END CERTIFICATE-----
+";
+    const OPENSSL_REQUEST: &str = "-----BEGIN CERTIFICATE REQUEST-----
+MIGtMGECAQAwLjELMAkGA1UEBhMCVVMxEDAOBgNVBAoMB0V4YW1wbGUxDTALBgNV
+BAMMBGxlYWYwKjAFBgMrZXADIQBZKuwTSgiPqjB7cPYm/D+hw0dOM6xoblOSmHln
+zwn/eaAAMAUGAytlcANBAN5j5p2sk3i0HVLAmwPDREc5Jl6WLuS7VWVLml+tgqLC
+mnrAiGy7bVj6/rAQrBXgmFwqkHjmoLYzxVoH/MkDsgs=
+-----END CERTIFICATE REQUEST-----
+";
+
+    #[cfg(all(
+        any(feature = "ed25519", feature = "ed25519_asm"),
+        any(
+            feature = "ecdsa_secp256k1",
+            feature = "ecdsa_secp256k1_native",
+            feature = "ecdsa_secp256k1_asm"
+        )
+    ))]
+    #[test]
+    fn parse_openssl_certificates() {
+        let ca = Certificate::from_pem(OPENSSL_CA).unwrap();
+        let leaf = Certificate::from_pem(OPENSSL_LEAF).unwrap();
+        assert!(ca.is_ca());
+        assert!(!leaf.is_ca());
+        assert_eq!(Some("OpenSSL CA".to_string()), ca.subject().common_name());
+        assert_eq!(ca.subject(), leaf.issuer());
+        assert_eq!(&[0x12, 0x34], leaf.serial_number());
+        assert_eq!(1_792_152_628, leaf.not_before());
+        assert_eq!(4_945_752_628, leaf.not_after());
+        assert_eq!(
+            vec![
+                (NameAttribute::Country, "US".to_string()),
+                (NameAttribute::Organization, "Example".to_string()),
+                (NameAttribute::CommonName, "leaf".to_string()),
+            ],
+            leaf.subject().attributes().unwrap()
+        );
+        assert_eq!(OPENSSL_LEAF, leaf.to_pem());
+
+        verify_chain(from_ref(&leaf), from_ref(&ca), NOW).unwrap();
+        verify_chain(&[leaf.clone(), ca.clone()], from_ref(&ca), NOW).unwrap();
+        assert!(verify_chain(from_ref(&leaf), from_ref(&ca), 1_700_000_000).is_err());
+        assert!(verify_chain(from_ref(&leaf), &[], NOW).is_err());
+        assert!(verify_chain(from_ref(&ca), &[leaf], NOW).is_err());
+
+        let request = CertificationRequest::from_pem(OPENSSL_REQUEST).unwrap();
+        let scheme = ::signatures::ed25519::Ed25519Sha512::new();
+        assert_eq!(
+            Certificate::from_pem(OPENSSL_LEAF)
+                .unwrap()
+                .public_key(&scheme)
+                .unwrap(),
+            scheme
+                .from_spki_der(request.subject_public_key_info())
+                .unwrap()
+        );
+    }
+
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    #[test]
+    fn ed25519_chain() {
+        let scheme = ::signatures::ed25519::Ed25519Sha512::new();
+        let (root_pk, root_sk) = scheme.keypair(None).unwrap();
+        let root = CertificateBuilder::new(Name::new("Root"), NOW, NOW + 1000)
+            .ca(Some(0))
+            .self_signed(&scheme, &root_pk, &root_sk)
+            .unwrap();
+        let (int_pk, int_sk) = scheme.keypair(None).unwrap();
+        let intermediate = CertificateBuilder::new(Name::new("Intermediate"), NOW, NOW + 1000)
+            .ca(None)
+            .public_key(&scheme, &int_pk)
+            .unwrap()
+            .issued_by(&root, &scheme, &root_sk)
+            .unwrap();
+        let (pk, sk) = scheme.keypair(None).unwrap();
+        let request = CertificationRequest::new(
+            &scheme,
+            Name::from_attributes(&[
+                (NameAttribute::Organization, "Ursa"),
+                (NameAttribute::CommonName, "leaf"),
+            ]),
+            &pk,
+            &sk,
+        )
+        .unwrap();
+        let request = CertificationRequest::from_pem(&request.to_pem()).unwrap();
+        let leaf = CertificateBuilder::from_request(&request, NOW, NOW + 100)
+            .unwrap()
+            .serial_number(&[1])
+            .issued_by(&root, &scheme, &root_sk)
+            .unwrap();
+        assert_eq!(&[1], leaf.serial_number());
+        assert_eq!(pk, leaf.public_key(&scheme).unwrap());
+        assert_eq!(
+            leaf,
+            Certificate::from_der(Certificate::from_pem(&leaf.to_pem()).unwrap().to_der()).unwrap()
+        );
+
+        verify_chain(from_ref(&leaf), from_ref(&root), NOW + 50).unwrap();
+        assert!(verify_chain(from_ref(&leaf), from_ref(&root), NOW + 101).is_err());
+        assert!(verify_chain(from_ref(&leaf), from_ref(&intermediate), NOW).is_err());
+
+        // The root allows no intermediate CAs
+        let below_intermediate = CertificateBuilder::new(Name::new("leaf"), NOW, NOW + 100)
+            .public_key(&scheme, &pk)
+            .unwrap()
+            .issued_by(&intermediate, &scheme, &int_sk)
+            .unwrap();
+        verify_chain(from_ref(&below_intermediate), from_ref(&intermediate), NOW).unwrap();
+        assert!(verify_chain(
+            &[below_intermediate, intermediate.clone()],
+            from_ref(&root),
+            NOW
+        )
+        .is_err());
+
+        // Leaves cannot issue certificates
+        assert!(CertificateBuilder::new(Name::new("x"), NOW, NOW + 1)
+            .public_key(&scheme, &pk)
+            .unwrap()
+            .issued_by(&leaf, &scheme, &sk)
+            .is_err());
+        let fake_issuer = CertificateBuilder::new(Name::new("Root"), NOW, NOW + 1000)
+            .ca(None)
+            .self_signed(&scheme, &pk, &sk)
+            .unwrap();
+        let forged = CertificateBuilder::new(Name::new("leaf"), NOW, NOW + 100)
+            .public_key(&scheme, &pk)
+            .unwrap()
+            .issued_by(&fake_issuer, &scheme, &sk)
+            .unwrap();
+        assert!(verify_chain(&[forged], from_ref(&root), NOW).is_err());
+        // The private key must match the issuer
+        assert!(CertificateBuilder::new(Name::new("x"), NOW, NOW + 1)
+            .public_key(&scheme, &pk)
+            .unwrap()
+            .issued_by(&root, &scheme, &sk)
+            .is_err());
+
+        let mut tampered = leaf.to_der().to_vec();
+        let i = tampered.windows(4).position(|w| w == b"leaf").unwrap();
+        tampered[i] = b'L';
+        let tampered = Certificate::from_der(&tampered).unwrap();
+        assert!(verify_chain(&[tampered], &[root], NOW).is_err());
+    }
+
+    #[cfg(all(
+        any(feature = "ed25519", feature = "ed25519_asm"),
+        any(
+            feature = "ecdsa_secp256k1",
+            feature = "ecdsa_secp256k1_native",
+            feature = "ecdsa_secp256k1_asm"
+        )
+    ))]
+    #[test]
+    fn secp256k1_chain() {
+        let scheme = ::signatures::secp256k1::EcdsaSecp256k1Sha256::new();
+        let (ca_pk, ca_sk) = scheme.keypair(None).unwrap();
+        let ca = CertificateBuilder::new(Name::new("CA"), NOW, 4_000_000_000)
+            .ca(None)
+            .self_signed(&scheme, &ca_pk, &ca_sk)
+            .unwrap();
+        assert_eq!(4_000_000_000, ca.not_after());
+        let ed25519 = ::signatures::ed25519::Ed25519Sha512::new();
+        let (pk, sk) = ed25519.keypair(None).unwrap();
+        let request = CertificationRequest::new(&ed25519, Name::new("leaf"), &pk, &sk).unwrap();
+        let leaf = CertificateBuilder::from_request(&request, NOW, NOW + 100)
+            .unwrap()
+            .issued_by(&ca, &scheme, &ca_sk)
+            .unwrap();
+        verify_chain(&[leaf], &[ca], NOW).unwrap();
+
+        let mut signature = request.to_der().to_vec();
+        let last = signature.len() - 1;
+        signature[last] ^= 1;
+        assert!(CertificationRequest::from_der(&signature).is_err());
+    }
+
+    #[test]
+    fn times() {
+        for (time, encoded) in &[
+            (0u64, "170d3730303130313030303030305a"),
+            (951_782_400, "170d3030303232393030303030305a"),
+            (2_524_607_999, "170d3439313233313233353935395a"),
+            (2_524_608_000, "180f32303530303130313030303030305a"),
+        ] {
+            assert_eq!(*encoded, hex::encode(write_time(*time)));
+            let der = hex::decode(encoded).unwrap();
+            assert_eq!(*time, read_time(&mut DerReader::new(&der)).unwrap());
+        }
+        for bad in &[
+            "170d3730313330313030303030305a",
+            "170d37303031303130303030303058",
+            "170b37303031303130303030305a",
+        ] {
+            let der = hex::decode(bad).unwrap();
+            assert!(read_time(&mut DerReader::new(&der)).is_err());
+        }
+    }
+}
@@ -0,0 +1,664 @@
+//! Envelope encryption with data keys wrapped by a key encryption key.
+//!
+//! Each [`Envelope`] encrypts its payload with a fresh AES-256-GCM data key
+//! and carries that key wrapped by a [`KeyWrapper`]. The key encryption key
+//! never touches the payload, so it can live in a KMS or an HSM and only see
+//! 32 byte data keys, and rotating it only rewraps data keys
+//! (see [`Envelope::rewrap`]).
+//!
+//! Wrappers provided here:
+//! - [`AesKeyWrapper`] wraps with AES-256 key wrap (RFC 3394)
+//! - [`EciesKeyWrapper`] wraps to an X25519 public key, so anyone can seal
+//!   envelopes that only the private key holder opens
+//! - [`BackendKeyWrapper`] is the same scheme with the private key held by a
+//!   [`KeyBackend`], e.g. a PKCS#11 token
+//! - [`ExternalKeyWrapper`] calls out to a remote service, like a cloud KMS
+//!
+//! The format is a version byte, the wrapper's key id and the wrapped data key
+//! each preceded by a 2 byte big endian length, the 12 byte nonce and the
+//! ciphertext.
+
+use super::symm::{aesgcm::Aes256Gcm, SymmetricEncryptor};
+use aes::{
+    cipher::{generic_array::GenericArray, BlockCipher, NewBlockCipher},
+    Aes256,
+};
+use backend::KeyBackend;
+use hkdf::Hkdf;
+use kex::{x25519::X25519Sha256, KeyExchangeScheme};
+use keys::{KeyGenOption, PrivateKey, PublicKey, SessionKey};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+use CryptoError;
+
+/// The envelope format written by this version
+pub const ENVELOPE_VERSION: u8 = 1;
+/// Bytes in a data key
+pub const DATA_KEY_SIZE: usize = 32;
+
+const NONCE_SIZE: usize = 12;
+const KEY_WRAP_IV: [u8; 8] = [0xa6; 8];
+const ECIES_INFO: &[u8] = b"ursa envelope x25519 key wrap";
+
+/// Wraps and unwraps data keys with a key encryption key
+pub trait KeyWrapper {
+    /// Names the key encryption key. It is stored in envelopes so the right
+    /// wrapper can be found to open them.
+    fn key_id(&self) -> &str;
+    fn wrap(&self, data_key: &[u8]) -> Result<Vec<u8>, CryptoError>;
+    fn unwrap(&self, wrapped_key: &[u8]) -> Result<Vec<u8>, CryptoError>;
+}
+
+/// A payload encrypted under a wrapped data key
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    key_id: String,
+    wrapped_key: Vec<u8>,
+    nonce: [u8; NONCE_SIZE],
+    ciphertext: Vec<u8>,
+}
+
+impl Envelope {
+    /// Encrypt `plaintext` under a new data key wrapped by `wrapper`. `aad` is
+    /// authenticated but not stored, and must be given again to open. Fails if
+    /// the key id or the wrapped key is longer than `u16::MAX` bytes.
+    pub fn seal<W: KeyWrapper + ?Sized>(
+        wrapper: &W,
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Self, CryptoError> {
+        Self::seal_with_rng(wrapper, aad, plaintext, &mut OsRng)
+    }
+
+    pub fn seal_with_rng<W: KeyWrapper + ?Sized, R: CryptoRng + RngCore>(
+        wrapper: &W,
+        aad: &[u8],
+        plaintext: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, CryptoError> {
+        let mut data_key = [0u8; DATA_KEY_SIZE];
+        rng.fill_bytes(&mut data_key);
+        let mut nonce = [0u8; NONCE_SIZE];
+        rng.fill_bytes(&mut nonce);
+
+        let sealed = wrapper.wrap(&data_key).and_then(|wrapped_key| {
+            check_field_length(wrapper.key_id().as_bytes(), "key id")?;
+            check_field_length(&wrapped_key, "wrapped key")?;
+            let ciphertext = encryptor(&data_key)?
+                .encrypt(&nonce[..], &payload_aad(aad)[..], plaintext)
+                .map_err(|_| CryptoError::GeneralError("Unable to encrypt payload".to_string()))?;
+            Ok(Envelope {
+                key_id: wrapper.key_id().to_string(),
+                wrapped_key,
+                nonce,
+                ciphertext,
+            })
+        });
+        data_key.zeroize();
+        sealed
+    }
+
+    /// Decrypt the payload, unwrapping the data key with `wrapper`
+    pub fn open<W: KeyWrapper + ?Sized>(
+        &self,
+        wrapper: &W,
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let mut data_key = self.unwrap_data_key(wrapper)?;
+        let plaintext = encryptor(&data_key).and_then(|e| {
+            e.decrypt(&self.nonce[..], &payload_aad(aad)[..], &self.ciphertext[..])
+                .map_err(|_| CryptoError::GeneralError("Invalid envelope".to_string()))
+        });
+        data_key.zeroize();
+        plaintext
+    }
+
+    /// Move the data key from `old` to `new`, e.g. when rotating the key
+    /// encryption key. The payload is not decrypted.
+    pub fn rewrap<W: KeyWrapper + ?Sized, V: KeyWrapper + ?Sized>(
+        &mut self,
+        old: &W,
+        new: &V,
+    ) -> Result<(), CryptoError> {
+        let mut data_key = self.unwrap_data_key(old)?;
+        let wrapped_key = new.wrap(&data_key);
+        data_key.zeroize();
+        let wrapped_key = wrapped_key?;
+        check_field_length(new.key_id().as_bytes(), "key id")?;
+        check_field_length(&wrapped_key, "wrapped key")?;
+        self.wrapped_key = wrapped_key;
+        self.key_id = new.key_id().to_string();
+        Ok(())
+    }
+
+    /// The id of the key encryption key that wrapped the data key
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![ENVELOPE_VERSION];
+        bytes.extend_from_slice(&(self.key_id.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(self.key_id.as_bytes());
+        bytes.extend_from_slice(&(self.wrapped_key.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&self.wrapped_key);
+        bytes.extend_from_slice(&self.nonce);
+        bytes.extend_from_slice(&self.ciphertext);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        match bytes.first() {
+            Some(&ENVELOPE_VERSION) => (),
+            Some(v) => {
+                return Err(CryptoError::ParseError(format!(
+                    "Unsupported envelope version {}",
+                    v
+                )))
+            }
+            None => return Err(CryptoError::ParseError("Empty envelope".to_string())),
+        }
+        let (key_id, rest) = read_field(&bytes[1..])?;
+        let key_id = String::from_utf8(key_id.to_vec())
+            .map_err(|_| CryptoError::ParseError("Key id is not UTF-8".to_string()))?;
+        let (wrapped_key, rest) = read_field(rest)?;
+        if rest.len() < NONCE_SIZE {
+            return Err(CryptoError::ParseError("Envelope is too short".to_string()));
+        }
+        let mut nonce = [0u8; NONCE_SIZE];
+        nonce.copy_from_slice(&rest[..NONCE_SIZE]);
+        Ok(Envelope {
+            key_id,
+            wrapped_key: wrapped_key.to_vec(),
+            nonce,
+            ciphertext: rest[NONCE_SIZE..].to_vec(),
+        })
+    }
+
+    fn unwrap_data_key<W: KeyWrapper + ?Sized>(&self, wrapper: &W) -> Result<Vec<u8>, CryptoError> {
+        if wrapper.key_id() != self.key_id {
+            return Err(CryptoError::GeneralError(format!(
+                "Envelope was sealed with key {}, not {}",
+                self.key_id,
+                wrapper.key_id()
+            )));
+        }
+        let mut data_key = wrapper.unwrap(&self.wrapped_key)?;
+        if data_key.len() != DATA_KEY_SIZE {
+            data_key.zeroize();
+            return Err(CryptoError::GeneralError(
+                "Unwrapped data key has the wrong size".to_string(),
+            ));
+        }
+        Ok(data_key)
+    }
+}
+
+/// Wraps data keys with AES-256 key wrap from RFC 3394
+pub struct AesKeyWrapper {
+    key_id: String,
+    key: [u8; 32],
+}
+
+impl AesKeyWrapper {
+    pub fn new(key_id: &str, key: &[u8]) -> Result<Self, CryptoError> {
+        if key.len() != 32 {
+            return Err(CryptoError::KeyGenError(format!(
+                "Expected a 32 byte key, found {}",
+                key.len()
+            )));
+        }
+        let mut wrapper = AesKeyWrapper {
+            key_id: key_id.to_string(),
+            key: [0u8; 32],
+        };
+        wrapper.key.copy_from_slice(key);
+        Ok(wrapper)
+    }
+}
+
+impl KeyWrapper for AesKeyWrapper {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    fn wrap(&self, data_key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        aes_key_wrap(&self.key, data_key)
+    }
+
+    fn unwrap(&self, wrapped_key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        aes_key_unwrap(&self.key, wrapped_key)
+    }
+}
+
+impl Drop for AesKeyWrapper {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+/// Wraps data keys to an X25519 public key.
+///
+/// A wrapped key is an ephemeral public key followed by the data key wrapped
+/// with AES key wrap under a key derived with HKDF-SHA256 from the shared
+/// secret and both public keys.
+pub struct EciesKeyWrapper {
+    key_id: String,
+    public_key: PublicKey,
+    private_key: Option<PrivateKey>,
+}
+
+impl EciesKeyWrapper {
+    /// A wrapper that can seal but not open envelopes
+    pub fn new(key_id: &str, public_key: PublicKey) -> Result<Self, CryptoError> {
+        check_x25519_key(&public_key)?;
+        Ok(EciesKeyWrapper {
+            key_id: key_id.to_string(),
+            public_key,
+            private_key: None,
+        })
+    }
+
+    pub fn with_private_key(key_id: &str, private_key: PrivateKey) -> Result<Self, CryptoError> {
+        let (public_key, private_key) =
+            X25519Sha256::new().keypair(Some(KeyGenOption::FromSecretKey(private_key)))?;
+        Ok(EciesKeyWrapper {
+            key_id: key_id.to_string(),
+            public_key,
+            private_key: Some(private_key),
+        })
+    }
+}
+
+impl KeyWrapper for EciesKeyWrapper {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    fn wrap(&self, data_key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        ecies_wrap(&self.public_key, data_key, &mut OsRng)
+    }
+
+    fn unwrap(&self, wrapped_key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let private_key = self.private_key.as_ref().ok_or_else(|| {
+            CryptoError::GeneralError(format!("No private key to unwrap with {}", self.key_id))
+        })?;
+        ecies_unwrap(&self.public_key, wrapped_key, |ephemeral| {
+            X25519Sha256::new().compute_shared_secret(private_key, ephemeral)
+        })
+    }
+}
+
+/// [`EciesKeyWrapper`] with an X25519 private key held by a [`KeyBackend`]
+pub struct BackendKeyWrapper<'a, B: 'a + KeyBackend> {
+    key_id: String,
+    backend: &'a B,
+    key: B::KeyHandle,
+    public_key: PublicKey,
+}
+
+impl<'a, B: 'a + KeyBackend> BackendKeyWrapper<'a, B> {
+    pub fn new(key_id: &str, backend: &'a B, key: B::KeyHandle) -> Result<Self, CryptoError> {
+        let public_key = backend.public_key(&key)?;
+        check_x25519_key(&public_key)?;
+        Ok(BackendKeyWrapper {
+            key_id: key_id.to_string(),
+            backend,
+            key,
+            public_key,
+        })
+    }
+}
+
+impl<'a, B: 'a + KeyBackend> KeyWrapper for BackendKeyWrapper<'a, B> {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    fn wrap(&self, data_key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        ecies_wrap(&self.public_key, data_key, &mut OsRng)
+    }
+
+    fn unwrap(&self, wrapped_key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        ecies_unwrap(&self.public_key, wrapped_key, |ephemeral| {
+            self.backend.derive(&self.key, ephemeral)
+        })
+    }
+}
+
+/// Wraps data keys by calling out to a service that holds the key
+/// encryption key, such as a cloud KMS's encrypt and decrypt operations
+pub struct ExternalKeyWrapper<W, U>
+where
+    W: Fn(&[u8]) -> Result<Vec<u8>, CryptoError>,
+    U: Fn(&[u8]) -> Result<Vec<u8>, CryptoError>,
+{
+    key_id: String,
+    wrap: W,
+    unwrap: U,
+}
+
+impl<W, U> ExternalKeyWrapper<W, U>
+where
+    W: Fn(&[u8]) -> Result<Vec<u8>, CryptoError>,
+    U: Fn(&[u8]) -> Result<Vec<u8>, CryptoError>,
+{
+    pub fn new(key_id: &str, wrap: W, unwrap: U) -> Self {
+        ExternalKeyWrapper {
+            key_id: key_id.to_string(),
+            wrap,
+            unwrap,
+        }
+    }
+}
+
+impl<W, U> KeyWrapper for ExternalKeyWrapper<W, U>
+where
+    W: Fn(&[u8]) -> Result<Vec<u8>, CryptoError>,
+    U: Fn(&[u8]) -> Result<Vec<u8>, CryptoError>,
+{
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    fn wrap(&self, data_key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        (self.wrap)(data_key)
+    }
+
+    fn unwrap(&self, wrapped_key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        (self.unwrap)(wrapped_key)
+    }
+}
+
+/// Wrap `data` with the AES-256 key wrap of RFC 3394
+pub fn aes_key_wrap(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if data.len() < 16 || data.len() % 8 != 0 {
+        return Err(CryptoError::GeneralError(
+            "Key wrap needs a multiple of 8 bytes and at least 16".to_string(),
+        ));
+    }
+    let cipher = Aes256::new(GenericArray::from_slice(key));
+    let n = data.len() / 8;
+    let mut out = KEY_WRAP_IV.to_vec();
+    out.extend_from_slice(data);
+    let mut block = GenericArray::default();
+    for j in 0..6 {
+        for i in 1..=n {
+            block[..8].copy_from_slice(&out[..8]);
+            block[8..].copy_from_slice(&out[i * 8..i * 8 + 8]);
+            cipher.encrypt_block(&mut block);
+            let t = ((n * j + i) as u64).to_be_bytes();
+            for k in 0..8 {
+                out[k] = block[k] ^ t[k];
+            }
+            out[i * 8..i * 8 + 8].copy_from_slice(&block[8..]);
+        }
+    }
+    block.zeroize();
+    Ok(out)
+}
+
+/// Unwrap and check `data` wrapped by [`aes_key_wrap`]
+pub fn aes_key_unwrap(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if data.len() < 24 || data.len() % 8 != 0 {
+        return Err(CryptoError::GeneralError(
+            "Invalid wrapped key length".to_string(),
+        ));
+    }
+    let cipher = Aes256::new(GenericArray::from_slice(key));
+    let n = data.len() / 8 - 1;
+    let mut a = [0u8; 8];
+    a.copy_from_slice(&data[..8]);
+    let mut r = data[8..].to_vec();
+    let mut block = GenericArray::default();
+    for j in (0..6).rev() {
+        for i in (1..=n).rev() {
+            let t = ((n * j + i) as u64).to_be_bytes();
+            for k in 0..8 {
+                block[k] = a[k] ^ t[k];
+            }
+            block[8..].copy_from_slice(&r[(i - 1) * 8..i * 8]);
+            cipher.decrypt_block(&mut block);
+            a.copy_from_slice(&block[..8]);
+            r[(i - 1) * 8..i * 8].copy_from_slice(&block[8..]);
+        }
+    }
+    block.zeroize();
+    if bool::from(a.ct_eq(&KEY_WRAP_IV)) {
+        Ok(r)
+    } else {
+        r.zeroize();
+        Err(CryptoError::GeneralError("Invalid wrapped key".to_string()))
+    }
+}
+
+fn ecies_wrap<R: CryptoRng + RngCore>(
+    public_key: &PublicKey,
+    data_key: &[u8],
+    rng: &mut R,
+) -> Result<Vec<u8>, CryptoError> {
+    let scheme = X25519Sha256::new();
+    let (ephemeral, ephemeral_private) = scheme.keypair_with_rng(rng)?;
+    let shared = scheme.compute_shared_secret(&ephemeral_private, public_key)?;
+    let mut kek = ecies_kek(&shared, &ephemeral, public_key)?;
+    let wrapped = aes_key_wrap(&kek, data_key);
+    kek.zeroize();
+    let mut out = ephemeral.0.clone();
+    out.extend_from_slice(&wrapped?);
+    Ok(out)
+}
+
+fn ecies_unwrap<F>(
+    public_key: &PublicKey,
+    wrapped_key: &[u8],
+    derive: F,
+) -> Result<Vec<u8>, CryptoError>
+where
+    F: FnOnce(&PublicKey) -> Result<SessionKey, CryptoError>,
+{
+    let size = X25519Sha256::public_key_size();
+    if wrapped_key.len() < size {
+        return Err(CryptoError::GeneralError(
+            "Invalid wrapped key length".to_string(),
+        ));
+    }
+    let ephemeral = PublicKey(wrapped_key[..size].to_vec());
+    let shared = derive(&ephemeral)?;
+    let mut kek = ecies_kek(&shared, &ephemeral, public_key)?;
+    let data_key = aes_key_unwrap(&kek, &wrapped_key[size..]);
+    kek.zeroize();
+    data_key
+}
+
+fn ecies_kek(
+    shared: &SessionKey,
+    ephemeral: &PublicKey,
+    public_key: &PublicKey,
+) -> Result<[u8; 32], CryptoError> {
+    let mut salt = ephemeral.0.clone();
+    salt.extend_from_slice(&public_key[..]);
+    let mut kek = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&salt), &shared[..])
+        .expand(ECIES_INFO, &mut kek)
+        .map_err(|_| CryptoError::KeyGenError("Unable to derive key".to_string()))?;
+    Ok(kek)
+}
+
+fn check_x25519_key(public_key: &PublicKey) -> Result<(), CryptoError> {
+    if public_key.len() != X25519Sha256::public_key_size() {
+        return Err(CryptoError::ParseError(format!(
+            "Expected a {} byte X25519 public key, found {}",
+            X25519Sha256::public_key_size(),
+            public_key.len()
+        )));
+    }
+    Ok(())
+}
+
+fn encryptor(data_key: &[u8]) -> Result<SymmetricEncryptor<Aes256Gcm>, CryptoError> {
+    SymmetricEncryptor::new_with_key(data_key)
+        .map_err(|_| CryptoError::KeyGenError("Invalid data key".to_string()))
+}
+
+/// The payload's associated data covers the version so envelopes cannot be
+/// downgraded, but not the key id or wrapped key so they can be rewrapped
+fn payload_aad(aad: &[u8]) -> Vec<u8> {
+    let mut out = vec![ENVELOPE_VERSION];
+    out.extend_from_slice(aad);
+    out
+}
+
+/// The key id and wrapped key are stored after a 2 byte length
+fn check_field_length(field: &[u8], what: &str) -> Result<(), CryptoError> {
+    if field.len() > u16::MAX as usize {
+        return Err(CryptoError::GeneralError(format!(
+            "The {} is longer than {} bytes",
+            what,
+            u16::MAX
+        )));
+    }
+    Ok(())
+}
+
+fn read_field(bytes: &[u8]) -> Result<(&[u8], &[u8]), CryptoError> {
+    if bytes.len() < 2 {
+        return Err(CryptoError::ParseError("Envelope is too short".to_string()));
+    }
+    let len = u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+    if bytes.len() < 2 + len {
+        return Err(CryptoError::ParseError("Envelope is too short".to_string()));
+    }
+    Ok(bytes[2..].split_at(len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backend::{software::SoftwareBackend, KeyAlgorithm};
+
+    #[test]
+    fn rfc3394_vector() {
+        let mut key = [0u8; 32];
+        for (i, b) in key.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let data = hex::decode("00112233445566778899AABBCCDDEEFF000102030405060708090A0B0C0D0E0F")
+            .unwrap();
+        let wrapped = aes_key_wrap(&key, &data).unwrap();
+        assert_eq!(
+            hex::encode_upper(&wrapped),
+            "28C9F404C4B810F4CBCCB35CFB87F8263F5786E2D80ED326CBC7F0E71A99F43BFB988B9B7A02DD21"
+        );
+        assert_eq!(aes_key_unwrap(&key, &wrapped).unwrap(), data);
+
+        let mut tampered = wrapped.clone();
+        tampered[10] ^= 1;
+        assert!(aes_key_unwrap(&key, &tampered).is_err());
+        assert!(aes_key_wrap(&key, &data[..12]).is_err());
+    }
+
+    #[test]
+    fn aes_envelopes() {
+        let wrapper = AesKeyWrapper::new("kek-1", &[7u8; 32]).unwrap();
+        let envelope = Envelope::seal(&wrapper, b"context", b"payload").unwrap();
+        assert_eq!(envelope.key_id(), "kek-1");
+
+        let envelope = Envelope::from_bytes(&envelope.to_bytes()).unwrap();
+        assert_eq!(envelope.open(&wrapper, b"context").unwrap(), b"payload");
+        assert!(envelope.open(&wrapper, b"other").is_err());
+        let other = AesKeyWrapper::new("kek-1", &[8u8; 32]).unwrap();
+        assert!(envelope.open(&other, b"context").is_err());
+
+        let mut bytes = envelope.to_bytes();
+        bytes[0] = 2;
+        assert!(Envelope::from_bytes(&bytes).is_err());
+        assert!(Envelope::from_bytes(&bytes[..10]).is_err());
+
+        // Rotating the key encryption key keeps the payload
+        let mut envelope = envelope;
+        let rotated = AesKeyWrapper::new("kek-2", &[9u8; 32]).unwrap();
+        envelope.rewrap(&wrapper, &rotated).unwrap();
+        assert_eq!(envelope.key_id(), "kek-2");
+        assert!(envelope.open(&wrapper, b"context").is_err());
+        assert_eq!(envelope.open(&rotated, b"context").unwrap(), b"payload");
+    }
+
+    #[test]
+    fn ecies_envelopes() {
+        let (pk, sk) = X25519Sha256::new().keypair(None).unwrap();
+        let sealer = EciesKeyWrapper::new("recipient", pk).unwrap();
+        let envelope = Envelope::seal(&sealer, b"", b"payload").unwrap();
+        assert!(envelope.open(&sealer, b"").is_err());
+        let opener = EciesKeyWrapper::with_private_key("recipient", sk).unwrap();
+        assert_eq!(envelope.open(&opener, b"").unwrap(), b"payload");
+
+        let backend = SoftwareBackend::new();
+        let key = backend.generate(KeyAlgorithm::X25519, "kek").unwrap();
+        let wrapper = BackendKeyWrapper::new("hsm", &backend, key.clone()).unwrap();
+        let sealer = EciesKeyWrapper::new("hsm", backend.public_key(&key).unwrap()).unwrap();
+        let envelope = Envelope::seal(&sealer, b"", b"payload").unwrap();
+        assert_eq!(envelope.open(&wrapper, b"").unwrap(), b"payload");
+    }
+
+    #[test]
+    fn external_envelopes() {
+        let kms = AesKeyWrapper::new("remote", &[1u8; 32]).unwrap();
+        let wrapper = ExternalKeyWrapper::new(
+            "arn:kms:key/1",
+            |key: &[u8]| kms.wrap(key),
+            |wrapped: &[u8]| kms.unwrap(wrapped),
+        );
+        let envelope = Envelope::seal(&wrapper, b"", b"payload").unwrap();
+        assert_eq!(envelope.key_id(), "arn:kms:key/1");
+        assert_eq!(envelope.open(&wrapper, b"").unwrap(), b"payload");
+
+        let failing = ExternalKeyWrapper::new(
+            "arn:kms:key/1",
+            |_: &[u8]| Err(CryptoError::GeneralError("unavailable".to_string())),
+            |_: &[u8]| Err(CryptoError::GeneralError("unavailable".to_string())),
+        );
+        assert!(Envelope::seal(&failing, b"", b"payload").is_err());
+        assert!(envelope.open(&failing, b"").is_err());
+    }
+
+    #[test]
+    fn oversized_fields() {
+        let kms = AesKeyWrapper::new("remote", &[1u8; 32]).unwrap();
+        let long_id = "k".repeat(u16::MAX as usize + 1);
+        let wrapper = ExternalKeyWrapper::new(
+            long_id.as_str(),
+            |key: &[u8]| kms.wrap(key),
+            |wrapped: &[u8]| kms.unwrap(wrapped),
+        );
+        assert!(Envelope::seal(&wrapper, b"", b"payload").is_err());
+        let padding = u16::MAX as usize + 1 - 40;
+        let wrapper = ExternalKeyWrapper::new(
+            "padded",
+            |key: &[u8]| kms.wrap(key).map(|w| [w, vec![0u8; padding]].concat()),
+            |wrapped: &[u8]| kms.unwrap(&wrapped[..40]),
+        );
+        assert!(Envelope::seal(&wrapper, b"", b"payload").is_err());
+
+        // The largest fields still round trip
+        let wrapper = ExternalKeyWrapper::new(
+            &long_id[1..],
+            |key: &[u8]| kms.wrap(key).map(|w| [w, vec![0u8; padding - 1]].concat()),
+            |wrapped: &[u8]| kms.unwrap(&wrapped[..40]),
+        );
+        let mut envelope = Envelope::seal(&wrapper, b"", b"payload").unwrap();
+        let parsed = Envelope::from_bytes(&envelope.to_bytes()).unwrap();
+        assert_eq!(parsed, envelope);
+        assert_eq!(parsed.open(&wrapper, b"").unwrap(), b"payload");
+
+        let long = ExternalKeyWrapper::new(
+            long_id.as_str(),
+            |key: &[u8]| kms.wrap(key),
+            |wrapped: &[u8]| kms.unwrap(wrapped),
+        );
+        assert!(envelope.rewrap(&wrapper, &long).is_err());
+        assert_eq!(envelope, parsed);
+    }
+}
@@ -2,19 +2,42 @@
 //! `symm` provides symmetric AEAD cryptographic algorithms.
 use aead::generic_array::{ArrayLength, GenericArray};
 use aead::Error;
-use rand::{rngs::OsRng, RngCore};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
 
+#[cfg(feature = "envelope")]
+pub mod envelope;
+#[cfg(feature = "sse")]
+pub mod sse;
 pub mod symm;
 
 // Helpful for generating bytes using the operating system random number generator
+#[cfg(feature = "std")]
 pub fn random_vec(bytes: usize) -> Result<Vec<u8>, Error> {
+    random_vec_with_rng(bytes, &mut OsRng)
+}
+
+pub fn random_vec_with_rng<R: CryptoRng + RngCore>(
+    bytes: usize,
+    rng: &mut R,
+) -> Result<Vec<u8>, Error> {
     let mut value = vec![0u8; bytes];
-    OsRng.fill_bytes(value.as_mut_slice());
+    rng.try_fill_bytes(value.as_mut_slice())
+        .map_err(|_| Error)?;
     Ok(value)
 }
 
+#[cfg(feature = "std")]
 pub fn random_bytes<T: ArrayLength<u8>>() -> Result<GenericArray<u8, T>, Error> {
+    random_bytes_with_rng(&mut OsRng)
+}
+
+pub fn random_bytes_with_rng<T: ArrayLength<u8>, R: CryptoRng + RngCore>(
+    rng: &mut R,
+) -> Result<GenericArray<u8, T>, Error> {
     Ok(GenericArray::clone_from_slice(
-        random_vec(T::to_usize())?.as_slice(),
+        random_vec_with_rng(T::to_usize(), rng)?.as_slice(),
     ))
 }
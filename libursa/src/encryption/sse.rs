@@ -0,0 +1,363 @@
+//! Searchable symmetric encryption for single keyword queries.
+//!
+//! The client keeps a key and one counter per keyword. Each document added
+//! under a keyword becomes an index entry whose label is an HMAC-SHA256 of the
+//! keyword and the counter and whose value is the document id encrypted with
+//! XChaCha20-Poly1305. The server only sees random labels and ciphertexts.
+//!
+//! To search, the client sends a [`Trapdoor`] holding the labels of the
+//! entries added so far, and decrypts the values the server returns. The labels
+//! of later entries use counters the server has never seen, so earlier searches
+//! do not reveal whether new entries match them (forward privacy). Searches do
+//! reveal which entries match and how many there are.
+//!
+//! The client's counters are part of its state and must be kept with the key,
+//! see [`Client::key`] and [`Client::counters`].
+//!
+//! ```
+//! use ursa::encryption::sse::{Client, Index};
+//!
+//! let mut client = Client::generate();
+//! let mut index = Index::new();
+//! index.insert(client.build_index(&[(b"doc1".to_vec(), vec![b"apple".to_vec()])]).unwrap());
+//! index.insert(client.build_index(&[(b"doc2".to_vec(), vec![b"apple".to_vec()])]).unwrap());
+//!
+//! let results = index.search(&client.trapdoor(b"apple"));
+//! assert_eq!(client.decrypt_results(&results).unwrap(), vec![b"doc1".to_vec(), b"doc2".to_vec()]);
+//! ```
+
+use super::symm::{xchacha20poly1305::XChaCha20Poly1305, SymmetricEncryptor};
+use hmac::{Hmac, Mac};
+use keys::Secret;
+use rand::{rngs::OsRng, seq::SliceRandom, CryptoRng, RngCore};
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap};
+use zeroize::Zeroize;
+
+use CryptoError;
+
+/// Bytes in a client key
+pub const KEY_SIZE: usize = 32;
+/// Bytes in an index label
+pub const LABEL_SIZE: usize = 32;
+
+const NONCE_SIZE: usize = 24;
+const LABEL_KEY_INFO: &[u8] = b"ursa sse label key";
+const DOCUMENT_KEY_INFO: &[u8] = b"ursa sse document key";
+
+/// An index entry's label
+pub type Label = [u8; LABEL_SIZE];
+
+/// The party that owns the documents, holds the key and makes queries
+pub struct Client {
+    key: Secret<[u8; KEY_SIZE]>,
+    label_key: [u8; KEY_SIZE],
+    document_key: [u8; KEY_SIZE],
+    counters: BTreeMap<Vec<u8>, u64>,
+}
+
+impl Client {
+    /// A client with a new random key
+    pub fn generate() -> Self {
+        Self::generate_with_rng(&mut OsRng)
+    }
+
+    pub fn generate_with_rng<R: CryptoRng + RngCore>(rng: &mut R) -> Self {
+        let mut key = [0u8; KEY_SIZE];
+        rng.fill_bytes(&mut key);
+        let client = Self::new(&key, BTreeMap::new());
+        key.zeroize();
+        client.expect("Generated key has the right size")
+    }
+
+    /// Restore a client from its key and the counters it had reached
+    pub fn new(key: &[u8], counters: BTreeMap<Vec<u8>, u64>) -> Result<Self, CryptoError> {
+        if key.len() != KEY_SIZE {
+            return Err(CryptoError::KeyGenError(format!(
+                "Expected a {} byte key, found {}",
+                KEY_SIZE,
+                key.len()
+            )));
+        }
+        let mut bytes = [0u8; KEY_SIZE];
+        bytes.copy_from_slice(key);
+        Ok(Client {
+            key: Secret::new(bytes),
+            label_key: prf(key, &[LABEL_KEY_INFO]),
+            document_key: prf(key, &[DOCUMENT_KEY_INFO]),
+            counters,
+        })
+    }
+
+    /// The key to restore the client with, along with its counters
+    pub fn key(&self) -> &Secret<[u8; KEY_SIZE]> {
+        &self.key
+    }
+
+    /// How many documents were added under each keyword. This must be saved
+    /// along with the key after every [`build_index`](Client::build_index).
+    pub fn counters(&self) -> &BTreeMap<Vec<u8>, u64> {
+        &self.counters
+    }
+
+    /// Encrypt the entries for `documents`, each an id and its keywords.
+    /// The result is sent to the server and merged into its [`Index`].
+    pub fn build_index(
+        &mut self,
+        documents: &[(Vec<u8>, Vec<Vec<u8>>)],
+    ) -> Result<IndexUpdate, CryptoError> {
+        self.build_index_with_rng(documents, &mut OsRng)
+    }
+
+    pub fn build_index_with_rng<R: CryptoRng + RngCore>(
+        &mut self,
+        documents: &[(Vec<u8>, Vec<Vec<u8>>)],
+        rng: &mut R,
+    ) -> Result<IndexUpdate, CryptoError> {
+        let encryptor = self.encryptor()?;
+        let mut counters = self.counters.clone();
+        let mut entries = Vec::new();
+        for (id, keywords) in documents {
+            for keyword in keywords {
+                let counter = counters.entry(keyword.clone()).or_insert(0);
+                let label = self.label(keyword, *counter);
+                *counter += 1;
+                let mut value = vec![0u8; NONCE_SIZE];
+                rng.fill_bytes(&mut value);
+                let ciphertext = encryptor
+                    .encrypt(&value[..], &label[..], id.as_slice())
+                    .map_err(|_| {
+                        CryptoError::GeneralError("Unable to encrypt document id".to_string())
+                    })?;
+                value.extend_from_slice(&ciphertext);
+                entries.push((label, value));
+            }
+        }
+        // Shuffle so the server cannot tell which entries belong together
+        entries.shuffle(rng);
+        self.counters = counters;
+        Ok(IndexUpdate { entries })
+    }
+
+    /// The query for documents with `keyword`
+    pub fn trapdoor(&self, keyword: &[u8]) -> Trapdoor {
+        let count = self.counters.get(keyword).cloned().unwrap_or(0);
+        Trapdoor {
+            labels: (0..count).map(|c| self.label(keyword, c)).collect(),
+        }
+    }
+
+    /// Decrypt the document ids the server returned for a search
+    pub fn decrypt_results(
+        &self,
+        results: &[(Label, Vec<u8>)],
+    ) -> Result<Vec<Vec<u8>>, CryptoError> {
+        let encryptor = self.encryptor()?;
+        results
+            .iter()
+            .map(|(label, value)| {
+                if value.len() < NONCE_SIZE {
+                    return Err(CryptoError::ParseError(
+                        "Search result is too short".to_string(),
+                    ));
+                }
+                let (nonce, ciphertext) = value.split_at(NONCE_SIZE);
+                encryptor
+                    .decrypt(nonce, &label[..], ciphertext)
+                    .map_err(|_| CryptoError::GeneralError("Invalid search result".to_string()))
+            })
+            .collect()
+    }
+
+    fn label(&self, keyword: &[u8], counter: u64) -> Label {
+        prf(
+            &self.label_key,
+            &[
+                &(keyword.len() as u64).to_be_bytes(),
+                keyword,
+                &counter.to_be_bytes(),
+            ],
+        )
+    }
+
+    fn encryptor(&self) -> Result<SymmetricEncryptor<XChaCha20Poly1305>, CryptoError> {
+        SymmetricEncryptor::new_with_key(&self.document_key[..])
+            .map_err(|_| CryptoError::KeyGenError("Invalid document key".to_string()))
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.label_key.zeroize();
+        self.document_key.zeroize();
+    }
+}
+
+impl std::fmt::Debug for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("keywords", &self.counters.len())
+            .finish()
+    }
+}
+
+/// Encrypted entries for the server to add to its index
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexUpdate {
+    entries: Vec<(Label, Vec<u8>)>,
+}
+
+impl IndexUpdate {
+    /// The labels and encrypted document ids, for servers with their own storage
+    pub fn entries(&self) -> &[(Label, Vec<u8>)] {
+        &self.entries
+    }
+}
+
+/// A query for one keyword
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trapdoor {
+    labels: Vec<Label>,
+}
+
+impl Trapdoor {
+    pub fn labels(&self) -> &[Label] {
+        &self.labels
+    }
+}
+
+/// The server's encrypted index
+#[derive(Debug, Clone, Default)]
+pub struct Index {
+    entries: HashMap<Label, Vec<u8>>,
+}
+
+impl Index {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Merge an update from the client
+    pub fn insert(&mut self, update: IndexUpdate) {
+        self.entries.extend(update.entries);
+    }
+
+    /// The entries matching `trapdoor`, to return to the client
+    pub fn search(&self, trapdoor: &Trapdoor) -> Vec<(Label, Vec<u8>)> {
+        trapdoor
+            .labels
+            .iter()
+            .filter_map(|label| self.entries.get(label).map(|v| (*label, v.clone())))
+            .collect()
+    }
+}
+
+fn prf(key: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC takes keys of any size");
+    for part in parts {
+        mac.input(part);
+    }
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&mac.result().code());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn documents() -> Vec<(Vec<u8>, Vec<Vec<u8>>)> {
+        vec![
+            (b"a".to_vec(), vec![b"red".to_vec(), b"round".to_vec()]),
+            (b"b".to_vec(), vec![b"red".to_vec()]),
+            (b"c".to_vec(), vec![b"round".to_vec(), b"blue".to_vec()]),
+        ]
+    }
+
+    fn sorted(mut ids: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn search() {
+        let mut client = Client::generate();
+        let mut index = Index::new();
+        index.insert(client.build_index(&documents()).unwrap());
+        assert_eq!(index.len(), 5);
+
+        let results = index.search(&client.trapdoor(b"red"));
+        assert_eq!(
+            sorted(client.decrypt_results(&results).unwrap()),
+            vec![b"a".to_vec(), b"b".to_vec()]
+        );
+        assert!(index.search(&client.trapdoor(b"green")).is_empty());
+
+        // Another client's key does not decrypt the results
+        let other = Client::generate();
+        assert!(other.decrypt_results(&results).is_err());
+        assert!(index.search(&other.trapdoor(b"red")).is_empty());
+    }
+
+    #[test]
+    fn forward_privacy() {
+        let mut client = Client::generate();
+        let mut index = Index::new();
+        index.insert(client.build_index(&documents()).unwrap());
+        let old = client.trapdoor(b"round");
+
+        let update = client
+            .build_index(&[(b"d".to_vec(), vec![b"round".to_vec()])])
+            .unwrap();
+        // The new entry's label is not in an earlier trapdoor
+        assert!(!old.labels().contains(&update.entries()[0].0));
+        index.insert(update);
+        assert_eq!(index.search(&old).len(), 2);
+
+        let results = index.search(&client.trapdoor(b"round"));
+        assert_eq!(
+            sorted(client.decrypt_results(&results).unwrap()),
+            vec![b"a".to_vec(), b"c".to_vec(), b"d".to_vec()]
+        );
+    }
+
+    #[test]
+    fn restore() {
+        let mut client = Client::generate();
+        let mut index = Index::new();
+        index.insert(client.build_index(&documents()).unwrap());
+
+        // A restored client searches, decrypts and continues from its counters
+        let key = client.key().expose_secret().to_vec();
+        let mut restored = Client::new(&key, client.counters().clone()).unwrap();
+        index.insert(
+            restored
+                .build_index(&[(b"d".to_vec(), vec![b"round".to_vec()])])
+                .unwrap(),
+        );
+        let trapdoor = restored.trapdoor(b"round");
+        assert_eq!(trapdoor.labels().len(), 3);
+        let results = index.search(&trapdoor);
+        assert_eq!(
+            sorted(restored.decrypt_results(&results).unwrap()),
+            vec![b"a".to_vec(), b"c".to_vec(), b"d".to_vec()]
+        );
+        assert_eq!(
+            sorted(client.decrypt_results(&results).unwrap()),
+            vec![b"a".to_vec(), b"c".to_vec(), b"d".to_vec()]
+        );
+
+        // The wrong key finds nothing
+        let wrong = Client::new(&[0u8; KEY_SIZE], restored.counters().clone()).unwrap();
+        assert!(index.search(&wrong.trapdoor(b"round")).is_empty());
+        assert!(Client::new(&[0u8; 16], BTreeMap::new()).is_err());
+    }
+}
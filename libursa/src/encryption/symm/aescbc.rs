@@ -72,7 +72,7 @@ macro_rules! aes_cbc_hmac_impl {
             ) -> Result<Vec<u8>, Error> {
                 let payload = ciphertext.into();
 
-                if payload.msg.len() < Self::TagSize::to_usize() + $algokeysize::to_usize() {
+                if payload.msg.len() < Self::TagSize::to_usize() + Self::NonceSize::to_usize() {
                     return Err(Error);
                 }
 
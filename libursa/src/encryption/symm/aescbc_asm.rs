@@ -76,7 +76,7 @@ macro_rules! aes_cbc_hmac_impl {
                 let payload = ciphertext.into();
                 let cipher = OpenSslCipher::$cipherid();
 
-                if payload.msg.len() < Self::TagSize::to_usize() + cipher.key_len() {
+                if payload.msg.len() < Self::TagSize::to_usize() + cipher.block_size() {
                     return Err(Error);
                 }
 
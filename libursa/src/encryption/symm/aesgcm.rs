@@ -7,6 +7,7 @@ use aead::{
     Aead, Error, NewAead, Payload,
 };
 use aes_gcm::{Aes128Gcm as SysAes128Gcm, Aes256Gcm as SysAes256Gcm};
+use alloc::vec::Vec;
 #[cfg(feature = "serde")]
 use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
 use zeroize::Zeroize;
@@ -52,7 +53,7 @@ macro_rules! aes_gcm_impl {
             ) -> Result<Vec<u8>, Error> {
                 let payload = ciphertext.into();
 
-                if payload.msg.len() < Self::TagSize::to_usize() + Self::NonceSize::to_usize() {
+                if payload.msg.len() < Self::TagSize::to_usize() {
                     return Err(Error);
                 }
 
@@ -6,6 +6,7 @@ use aead::{
     },
     Aead, Error, NewAead, Payload,
 };
+use alloc::vec::Vec;
 use rustchacha20poly1305::ChaCha20Poly1305 as SysChaCha20Poly1305;
 #[cfg(feature = "serde")]
 use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
@@ -34,13 +34,20 @@
 //! More ciphers will added as needed like AES-GCM-SIV or [XCHACHA20POLY1305-SIV](https://tools.ietf.org/id/draft-madden-generalised-siv-00.html)
 //! where using poly1305 instead of HMAC might be appropriate.
 
-use super::random_bytes;
+use super::random_bytes_with_rng;
 use aead::{
     generic_array::{typenum::Unsigned, ArrayLength, GenericArray},
     Aead, Error, NewAead, Payload,
 };
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
 use std::io::{Read, Write};
 use std::str::FromStr;
 
@@ -109,6 +116,7 @@ macro_rules! drop_impl {
 
 macro_rules! default_impl {
     ($name:ident) => {
+        #[cfg(feature = "std")]
         impl Default for $name {
             fn default() -> Self {
                 $name::new(&$name::key_gen().unwrap())
@@ -154,10 +162,21 @@ impl<E: Encryptor> SymmetricEncryptor<E> {
 
     // Encrypt `plaintext` and integrity protect `aad`. The result is the ciphertext.
     // This method handles safely generating a `nonce` and prepends it to the ciphertext
+    #[cfg(feature = "std")]
     pub fn encrypt_easy<A: AsRef<[u8]>>(&self, aad: A, plaintext: A) -> Result<Vec<u8>, Error> {
         self.encryptor.encrypt_easy(aad, plaintext)
     }
 
+    // Same as `encrypt_easy` but the nonce is drawn from `rng`
+    pub fn encrypt_easy_with_rng<A: AsRef<[u8]>, R: CryptoRng + RngCore>(
+        &self,
+        aad: A,
+        plaintext: A,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        self.encryptor.encrypt_easy_with_rng(aad, plaintext, rng)
+    }
+
     // Encrypt `plaintext` and integrity protect `aad`. The result is the ciphertext.
     pub fn encrypt<A: AsRef<[u8]>>(
         &self,
@@ -201,6 +220,7 @@ impl<E: Encryptor> SymmetricEncryptor<E> {
     }
 
     // Similar to `encrypt_easy` but reads from a stream instead of a slice
+    #[cfg(feature = "std")]
     pub fn encrypt_buffer<A: AsRef<[u8]>, I: Read, O: Write>(
         &self,
         aad: A,
@@ -211,6 +231,7 @@ impl<E: Encryptor> SymmetricEncryptor<E> {
     }
 
     // Similar to `decrypt_easy` but reads from a stream instead of a slice
+    #[cfg(feature = "std")]
     pub fn decrypt_buffer<A: AsRef<[u8]>, I: Read, O: Write>(
         &self,
         aad: A,
@@ -234,8 +255,18 @@ pub trait Encryptor: Aead + NewAead {
     /// The minimum size that the ciphertext will yield from plaintext
     type MinSize: ArrayLength<u8>;
 
+    #[cfg(feature = "std")]
     fn encrypt_easy<M: AsRef<[u8]>>(&self, aad: M, plaintext: M) -> Result<Vec<u8>, Error> {
-        let nonce = Self::nonce_gen()?;
+        self.encrypt_easy_with_rng(aad, plaintext, &mut OsRng)
+    }
+
+    fn encrypt_easy_with_rng<M: AsRef<[u8]>, R: CryptoRng + RngCore>(
+        &self,
+        aad: M,
+        plaintext: M,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let nonce = Self::nonce_gen_with_rng(rng)?;
         let payload = Payload {
             msg: plaintext.as_ref(),
             aad: aad.as_ref(),
@@ -261,6 +292,7 @@ pub trait Encryptor: Aead + NewAead {
         Ok(plaintext)
     }
 
+    #[cfg(feature = "std")]
     fn encrypt_buffer<M: AsRef<[u8]>, I: Read, O: Write>(
         &self,
         aad: M,
@@ -273,6 +305,7 @@ pub trait Encryptor: Aead + NewAead {
         Ok(())
     }
 
+    #[cfg(feature = "std")]
     fn decrypt_buffer<M: AsRef<[u8]>, I: Read, O: Write>(
         &self,
         aad: M,
@@ -285,12 +318,26 @@ pub trait Encryptor: Aead + NewAead {
         Ok(())
     }
 
+    #[cfg(feature = "std")]
     fn key_gen() -> Result<GenericArray<u8, Self::KeySize>, Error> {
-        random_bytes()
+        Self::key_gen_with_rng(&mut OsRng)
     }
 
+    fn key_gen_with_rng<R: CryptoRng + RngCore>(
+        rng: &mut R,
+    ) -> Result<GenericArray<u8, Self::KeySize>, Error> {
+        random_bytes_with_rng(rng)
+    }
+
+    #[cfg(feature = "std")]
     fn nonce_gen() -> Result<GenericArray<u8, Self::NonceSize>, Error> {
-        random_bytes()
+        Self::nonce_gen_with_rng(&mut OsRng)
+    }
+
+    fn nonce_gen_with_rng<R: CryptoRng + RngCore>(
+        rng: &mut R,
+    ) -> Result<GenericArray<u8, Self::NonceSize>, Error> {
+        random_bytes_with_rng(rng)
     }
 }
 
@@ -299,6 +346,7 @@ pub trait Encryptor: Aead + NewAead {
 pub trait DynEncryptor {
     fn keysize(&self) -> usize;
     fn noncesize(&self) -> usize;
+    #[cfg(feature = "std")]
     fn encrypt_easy(&self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error>;
     fn encrypt(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error>;
     fn decrypt_easy(&self, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error>;
@@ -314,6 +362,7 @@ impl<D: Encryptor> DynEncryptor for D {
         <Self as Aead>::NonceSize::to_usize()
     }
 
+    #[cfg(feature = "std")]
     fn encrypt_easy(&self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
         self.encrypt_easy(aad, plaintext)
     }
@@ -372,6 +421,28 @@ impl EncryptorType {
         }
     }
 
+    /// The key size in bytes
+    pub fn keysize(self) -> usize {
+        match self {
+            #[cfg(any(feature = "aescbc", feature = "aescbc_native"))]
+            EncryptorType::Aes128CbcHmac256 => {
+                <aescbc::Aes128CbcHmac256 as NewAead>::KeySize::to_usize()
+            }
+            #[cfg(any(feature = "aescbc", feature = "aescbc_native"))]
+            EncryptorType::Aes256CbcHmac512 => {
+                <aescbc::Aes256CbcHmac512 as NewAead>::KeySize::to_usize()
+            }
+            #[cfg(any(feature = "aesgcm", feature = "aesgcm_native"))]
+            EncryptorType::Aes128Gcm => <aesgcm::Aes128Gcm as NewAead>::KeySize::to_usize(),
+            #[cfg(any(feature = "aesgcm", feature = "aesgcm_native"))]
+            EncryptorType::Aes256Gcm => <aesgcm::Aes256Gcm as NewAead>::KeySize::to_usize(),
+            #[cfg(any(feature = "chacha20poly1305", feature = "chacha20poly1305_native"))]
+            EncryptorType::XChaCha20Poly1305 => {
+                <xchacha20poly1305::XChaCha20Poly1305 as NewAead>::KeySize::to_usize()
+            }
+        }
+    }
+
     pub fn is_valid_noncesize(self, size: usize) -> bool {
         match self {
             #[cfg(any(feature = "aescbc", feature = "aescbc_native"))]
@@ -457,6 +528,7 @@ impl std::fmt::Display for EncryptorType {
     }
 }
 
+#[cfg(feature = "std")]
 fn read_buffer<I: Read>(buffer: &mut I) -> Result<Vec<u8>, Error> {
     let mut v = Vec::new();
     let bytes_read = buffer.read_to_end(&mut v).map_err(|_| Error)?;
@@ -483,6 +555,26 @@ macro_rules! tests_impl {
             assert_eq!(message, res.unwrap());
         }
 
+        #[test]
+        fn encrypt_easy_with_rng_works() {
+            use rand::{rngs::StdRng, SeedableRng};
+
+            let key = $name::key_gen_with_rng(&mut StdRng::seed_from_u64(1)).unwrap();
+            assert_eq!(key, $name::key_gen_with_rng(&mut StdRng::seed_from_u64(1)).unwrap());
+            let aes = $name::new(&key);
+            let aad = b"encrypt with rng".to_vec();
+            let message = b"Hello and Goodbye!".to_vec();
+            let ciphertext = aes
+                .encrypt_easy_with_rng(&aad, &message, &mut StdRng::seed_from_u64(2))
+                .unwrap();
+            assert_eq!(
+                ciphertext,
+                aes.encrypt_easy_with_rng(&aad, &message, &mut StdRng::seed_from_u64(2))
+                    .unwrap()
+            );
+            assert_eq!(message, aes.decrypt_easy(&aad, &ciphertext).unwrap());
+        }
+
         #[test]
         fn encrypt_works() {
             let aes = $name::default();
@@ -499,6 +591,19 @@ macro_rules! tests_impl {
             assert_eq!(message, res.unwrap());
         }
 
+        #[test]
+        fn short_message_works() {
+            // Ciphertexts shorter than a nonce plus a tag must still decrypt
+            let aes = $name::default();
+            let nonce = $name::nonce_gen().unwrap();
+            for message in &[&b""[..], b"short"] {
+                let payload = Payload { msg: *message, aad: b"" };
+                let ciphertext = aes.encrypt(&nonce, payload).unwrap();
+                let payload = Payload { msg: ciphertext.as_slice(), aad: b"" };
+                assert_eq!(aes.decrypt(&nonce, payload).unwrap(), *message);
+            }
+        }
+
         #[test]
         fn decrypt_should_fail() {
             let aes = $name::default();
@@ -0,0 +1,487 @@
+//! Eth2 validator deposits.
+//!
+//! A validator joins by sending the deposit contract a `DepositData`: its
+//! BLS public key, withdrawal credentials and amount, signed over the
+//! `DOMAIN_DEPOSIT` domain of the network's genesis fork version. This
+//! module builds the SSZ roots and signing root of a deposit and reads and
+//! writes the JSON files of the staking deposit CLI. Keys are kept in EIP-2335
+//! keystores, see [`Keystore::encrypt_eip2335`](../keystore/struct.Keystore.html).
+//!
+//! Deposits are signed and verified with the Eth2 ciphersuite of
+//! [`bls_ietf::eth2`](../signatures/bls_ietf/eth2/index.html).
+//! [`DepositMessage::sign_with`] and [`DepositData::verify_with`] take a
+//! signer kept elsewhere instead, such as a remote signer.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use signatures::bls_ietf::eth2;
+use CryptoError;
+
+/// The domain type of deposits
+pub const DOMAIN_DEPOSIT: [u8; 4] = [3, 0, 0, 0];
+/// The genesis fork version of mainnet
+pub const GENESIS_FORK_VERSION: [u8; 4] = [0; 4];
+/// The most a validator's balance counts for, 32 ETH in Gwei
+pub const MAX_EFFECTIVE_BALANCE: u64 = 32_000_000_000;
+/// A compressed BLS12-381 G1 point
+pub const PUBLIC_KEY_SIZE: usize = 48;
+/// A compressed BLS12-381 G2 point
+pub const SIGNATURE_SIZE: usize = 96;
+
+const BLS_WITHDRAWAL_PREFIX: u8 = 0;
+const ETH1_ADDRESS_WITHDRAWAL_PREFIX: u8 = 1;
+
+fn hash(left: &[u8], right: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&Sha256::new().chain(left).chain(right).result());
+    out
+}
+
+/// Split `bytes` into zero padded 32 byte chunks
+fn pack(bytes: &[u8]) -> Vec<[u8; 32]> {
+    bytes
+        .chunks(32)
+        .map(|c| {
+            let mut chunk = [0u8; 32];
+            chunk[..c.len()].copy_from_slice(c);
+            chunk
+        })
+        .collect()
+}
+
+/// The root of a binary merkle tree with `chunks` as leaves, padded with
+/// zero chunks to a power of two
+fn merkleize(mut chunks: Vec<[u8; 32]>) -> [u8; 32] {
+    chunks.resize(chunks.len().next_power_of_two(), [0u8; 32]);
+    while chunks.len() > 1 {
+        chunks = chunks.chunks(2).map(|p| hash(&p[0], &p[1])).collect();
+    }
+    chunks[0]
+}
+
+fn uint64_root(value: u64) -> [u8; 32] {
+    pack(&value.to_le_bytes())[0]
+}
+
+/// `domain_type` followed by the first 28 bytes of the fork data root
+pub fn compute_domain(
+    domain_type: [u8; 4],
+    fork_version: [u8; 4],
+    genesis_validators_root: [u8; 32],
+) -> [u8; 32] {
+    let fork_data_root = merkleize(vec![pack(&fork_version)[0], genesis_validators_root]);
+    let mut domain = [0u8; 32];
+    domain[..4].copy_from_slice(&domain_type);
+    domain[4..].copy_from_slice(&fork_data_root[..28]);
+    domain
+}
+
+/// The root that is signed for an object in `domain`
+pub fn compute_signing_root(object_root: [u8; 32], domain: [u8; 32]) -> [u8; 32] {
+    hash(&object_root, &domain)
+}
+
+/// Withdrawals go to the BLS key `withdrawal_pubkey`
+pub fn bls_withdrawal_credentials(withdrawal_pubkey: &[u8]) -> [u8; 32] {
+    let mut credentials = [0u8; 32];
+    credentials.copy_from_slice(&Sha256::digest(withdrawal_pubkey));
+    credentials[0] = BLS_WITHDRAWAL_PREFIX;
+    credentials
+}
+
+/// Withdrawals go to the execution layer `address`
+pub fn eth1_withdrawal_credentials(address: [u8; 20]) -> [u8; 32] {
+    let mut credentials = [0u8; 32];
+    credentials[0] = ETH1_ADDRESS_WITHDRAWAL_PREFIX;
+    credentials[12..].copy_from_slice(&address);
+    credentials
+}
+
+/// The part of a deposit that the validator key signs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepositMessage {
+    pubkey: Vec<u8>,
+    withdrawal_credentials: [u8; 32],
+    /// Gwei
+    amount: u64,
+}
+
+impl DepositMessage {
+    /// Fails if `pubkey` isn't `PUBLIC_KEY_SIZE` bytes
+    pub fn new(
+        pubkey: &[u8],
+        withdrawal_credentials: [u8; 32],
+        amount: u64,
+    ) -> Result<Self, CryptoError> {
+        if pubkey.len() != PUBLIC_KEY_SIZE {
+            return Err(CryptoError::ParseError(format!(
+                "Expected a {} byte public key, found {}",
+                PUBLIC_KEY_SIZE,
+                pubkey.len()
+            )));
+        }
+        Ok(DepositMessage {
+            pubkey: pubkey.to_vec(),
+            withdrawal_credentials,
+            amount,
+        })
+    }
+
+    pub fn pubkey(&self) -> &[u8] {
+        &self.pubkey
+    }
+
+    pub fn withdrawal_credentials(&self) -> [u8; 32] {
+        self.withdrawal_credentials
+    }
+
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// The SSZ root of the message
+    pub fn hash_tree_root(&self) -> [u8; 32] {
+        merkleize(vec![
+            merkleize(pack(&self.pubkey)),
+            self.withdrawal_credentials,
+            uint64_root(self.amount),
+        ])
+    }
+
+    /// The root to sign. Deposits are valid across forks so the domain is
+    /// computed with the genesis `fork_version` and no validators root.
+    pub fn signing_root(&self, fork_version: [u8; 4]) -> [u8; 32] {
+        compute_signing_root(
+            self.hash_tree_root(),
+            compute_domain(DOMAIN_DEPOSIT, fork_version, [0u8; 32]),
+        )
+    }
+
+    /// Sign the message with the validator's key `sk`. Fails if `sk` is not
+    /// the key of the message's public key.
+    pub fn sign(
+        &self,
+        fork_version: [u8; 4],
+        sk: &eth2::PrivateKey,
+    ) -> Result<DepositData, CryptoError> {
+        if eth2::PublicKey::new(sk).to_bytes() != self.pubkey {
+            return Err(CryptoError::SigningError(
+                "The private key does not match the deposit's public key".to_string(),
+            ));
+        }
+        self.sign_with(fork_version, |root| Ok(eth2::sign(sk, root).to_bytes()))
+    }
+
+    /// Sign the message with `sign`, which is given the signing root and
+    /// returns a compressed Eth2 BLS signature
+    pub fn sign_with<F>(&self, fork_version: [u8; 4], sign: F) -> Result<DepositData, CryptoError>
+    where
+        F: FnOnce(&[u8]) -> Result<Vec<u8>, CryptoError>,
+    {
+        let signature = sign(&self.signing_root(fork_version))?;
+        if signature.len() != SIGNATURE_SIZE {
+            return Err(CryptoError::SigningError(format!(
+                "Expected a {} byte signature, found {}",
+                SIGNATURE_SIZE,
+                signature.len()
+            )));
+        }
+        let deposit_data_root = merkleize(vec![
+            merkleize(pack(&self.pubkey)),
+            self.withdrawal_credentials,
+            uint64_root(self.amount),
+            merkleize(pack(&signature)),
+        ]);
+        Ok(DepositData {
+            pubkey: hex::encode(&self.pubkey),
+            withdrawal_credentials: hex::encode(self.withdrawal_credentials),
+            amount: self.amount,
+            signature: hex::encode(&signature),
+            deposit_message_root: hex::encode(self.hash_tree_root()),
+            deposit_data_root: hex::encode(deposit_data_root),
+            fork_version: hex::encode(fork_version),
+        })
+    }
+}
+
+/// A signed deposit as written by the staking deposit CLI, which stores a
+/// JSON array of these. Values are lowercase hex without a `0x` prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepositData {
+    pub pubkey: String,
+    pub withdrawal_credentials: String,
+    /// Gwei
+    pub amount: u64,
+    pub signature: String,
+    pub deposit_message_root: String,
+    /// The root the deposit contract is called with
+    pub deposit_data_root: String,
+    pub fork_version: String,
+}
+
+impl DepositData {
+    /// The message that was signed
+    pub fn message(&self) -> Result<DepositMessage, CryptoError> {
+        DepositMessage::new(
+            &decode(&self.pubkey)?,
+            decode_fixed(&self.withdrawal_credentials)?,
+            self.amount,
+        )
+    }
+
+    pub fn signature(&self) -> Result<Vec<u8>, CryptoError> {
+        let signature = decode(&self.signature)?;
+        if signature.len() != SIGNATURE_SIZE {
+            return Err(CryptoError::ParseError(format!(
+                "Expected a {} byte signature, found {}",
+                SIGNATURE_SIZE,
+                signature.len()
+            )));
+        }
+        Ok(signature)
+    }
+
+    pub fn fork_version(&self) -> Result<[u8; 4], CryptoError> {
+        decode_fixed(&self.fork_version)
+    }
+
+    /// Check both roots against the deposit and the signature against its
+    /// public key. Fails if the public key is not a valid Eth2 key.
+    pub fn verify(&self) -> Result<bool, CryptoError> {
+        let pk = eth2::PublicKey::from_bytes(&decode(&self.pubkey)?)?;
+        self.verify_with(|root, signature| {
+            Ok(eth2::Signature::from_bytes(signature)
+                .map(|signature| eth2::verify(&pk, root, &signature))
+                .unwrap_or(false))
+        })
+    }
+
+    /// Check both roots against the deposit and the signature with
+    /// `verify`, which is given the signing root and the signature
+    pub fn verify_with<F>(&self, verify: F) -> Result<bool, CryptoError>
+    where
+        F: FnOnce(&[u8], &[u8]) -> Result<bool, CryptoError>,
+    {
+        let message = self.message()?;
+        let signature = self.signature()?;
+        let expected = message.sign_with(self.fork_version()?, |_| Ok(signature.clone()))?;
+        if expected.deposit_message_root != self.deposit_message_root.to_lowercase()
+            || expected.deposit_data_root != self.deposit_data_root.to_lowercase()
+        {
+            return Ok(false);
+        }
+        verify(&message.signing_root(self.fork_version()?), &signature)
+    }
+
+    pub fn to_json(&self) -> Result<String, CryptoError> {
+        serde_json::to_string_pretty(self).map_err(|e| CryptoError::GeneralError(e.to_string()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, CryptoError> {
+        serde_json::from_str(json).map_err(|e| CryptoError::ParseError(e.to_string()))
+    }
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, CryptoError> {
+    let value = value.trim_start_matches("0x");
+    hex::decode(value).map_err(|e| CryptoError::ParseError(e.to_string()))
+}
+
+fn decode_fixed<T: AsMut<[u8]> + Default>(value: &str) -> Result<T, CryptoError> {
+    let bytes = decode(value)?;
+    let mut out = T::default();
+    if out.as_mut().len() != bytes.len() {
+        return Err(CryptoError::ParseError(format!(
+            "Expected {} bytes, found {}",
+            out.as_mut().len(),
+            bytes.len()
+        )));
+    }
+    out.as_mut().copy_from_slice(&bytes);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message() -> DepositMessage {
+        let pubkey = [0x8au8; PUBLIC_KEY_SIZE];
+        DepositMessage::new(
+            &pubkey,
+            bls_withdrawal_credentials(&[0x99u8; PUBLIC_KEY_SIZE]),
+            MAX_EFFECTIVE_BALANCE,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn domain() {
+        // The fork data root of mainnet genesis is the hash of 64 zero bytes
+        assert_eq!(
+            hex::encode(compute_domain(
+                DOMAIN_DEPOSIT,
+                GENESIS_FORK_VERSION,
+                [0u8; 32]
+            )),
+            "03000000f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a9"
+        );
+        assert_eq!(
+            hex::encode(compute_domain(DOMAIN_DEPOSIT, [0, 0, 0, 1], [0u8; 32]))[..8],
+            *"03000000"
+        );
+        assert_ne!(
+            compute_domain(DOMAIN_DEPOSIT, [0, 0, 0, 1], [0u8; 32]),
+            compute_domain(DOMAIN_DEPOSIT, GENESIS_FORK_VERSION, [0u8; 32])
+        );
+    }
+
+    #[test]
+    fn withdrawal_credentials() {
+        let credentials = bls_withdrawal_credentials(b"withdrawal key");
+        assert_eq!(credentials[0], 0);
+        assert_eq!(credentials[1..], Sha256::digest(b"withdrawal key")[1..]);
+        let credentials = eth1_withdrawal_credentials([0xabu8; 20]);
+        assert_eq!(
+            hex::encode(credentials),
+            "010000000000000000000000abababababababababababababababababababab"
+        );
+    }
+
+    #[test]
+    fn roots() {
+        let message = message();
+        let pubkey_root = hash(&[0x8au8; 32], &pack(&[0x8au8; 16])[0]);
+        let mut amount = [0u8; 32];
+        amount[..8].copy_from_slice(&MAX_EFFECTIVE_BALANCE.to_le_bytes());
+        assert_eq!(
+            message.hash_tree_root(),
+            hash(
+                &hash(&pubkey_root, &message.withdrawal_credentials()),
+                &hash(&amount, &[0u8; 32])
+            )
+        );
+
+        let signature = [0x5cu8; SIGNATURE_SIZE];
+        let deposit = message
+            .sign_with(GENESIS_FORK_VERSION, |_| Ok(signature.to_vec()))
+            .unwrap();
+        let signature_root = hash(
+            &hash(&signature[..32], &signature[32..64]),
+            &hash(&signature[64..], &[0u8; 32]),
+        );
+        assert_eq!(
+            deposit.deposit_data_root,
+            hex::encode(hash(
+                &hash(&pubkey_root, &message.withdrawal_credentials()),
+                &hash(&amount, &signature_root)
+            ))
+        );
+        assert_eq!(
+            deposit.deposit_message_root,
+            hex::encode(message.hash_tree_root())
+        );
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let message = message();
+        let signing_root = message.signing_root(GENESIS_FORK_VERSION);
+        assert_eq!(
+            signing_root,
+            hash(
+                &message.hash_tree_root(),
+                &compute_domain(DOMAIN_DEPOSIT, GENESIS_FORK_VERSION, [0u8; 32])
+            )
+        );
+        // A stand in for a BLS signer
+        let sign = |root: &[u8]| Ok([root, root, root].concat());
+        let deposit = message.sign_with(GENESIS_FORK_VERSION, sign).unwrap();
+        assert_eq!(deposit.message().unwrap(), message);
+        assert_eq!(deposit.fork_version().unwrap(), GENESIS_FORK_VERSION);
+        let verify =
+            |root: &[u8], signature: &[u8]| Ok(signature == &[root, root, root].concat()[..]);
+        assert!(deposit.verify_with(verify).unwrap());
+
+        let json = deposit.to_json().unwrap();
+        let parsed = DepositData::from_json(&json).unwrap();
+        assert_eq!(parsed, deposit);
+        assert!(parsed.verify_with(verify).unwrap());
+
+        // Signed for another network
+        let mut other = deposit.clone();
+        other.fork_version = "00000001".to_string();
+        assert!(!other.verify_with(verify).unwrap());
+        let mut other = deposit.clone();
+        other.amount = 1_000_000_000;
+        assert!(!other.verify_with(verify).unwrap());
+        let mut other = deposit;
+        other.signature = hex::encode([0u8; SIGNATURE_SIZE]);
+        assert!(!other.verify_with(verify).unwrap());
+        other.signature = "00".to_string();
+        assert!(other.verify_with(verify).is_err());
+
+        assert!(message
+            .sign_with(GENESIS_FORK_VERSION, |root| Ok(root.to_vec()))
+            .is_err());
+        assert!(DepositMessage::new(&[0u8; 32], [0u8; 32], 0).is_err());
+    }
+
+    #[test]
+    fn bls_deposit() {
+        // A deposit file in the staking deposit CLI's layout, for the EIP-2333
+        // test case 0 master key. It was signed by this module rather than
+        // taken from the CLI, so it pins the encoding, not compatibility.
+        let file = r#"[{
+            "pubkey": "a2c975348667926acf12f3eecb005044e08a7a9b7d95f30bd281b55445107367a2e5d0558be7943c8bd13f9a1a7036fb",
+            "withdrawal_credentials": "0100000000000000000000001b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b",
+            "amount": 32000000000,
+            "signature": "a1516195abfc59b385c75ada21eae391b2eed53b84d52114037221a129c6b975e3616de489e72174b1cb0a8a7b47df5f00d18b1b499d37d8bc9abfbeb1756fb9e9b762a7704bbb57aa1105ce0de4418a3d3b072832e4fabd42c170b974157a7a",
+            "deposit_message_root": "2a868051f107333872c8b85fad8f5483305f3d464141de58bf23c1750080486a",
+            "deposit_data_root": "6c02d7acc2a7fbaa668d2104b8030d5d56b1811baec1393039195cc26a06e64a",
+            "fork_version": "00000000",
+            "network_name": "mainnet",
+            "deposit_cli_version": "2.7.0"
+        }]"#;
+        let deposits: Vec<DepositData> = serde_json::from_str(file).unwrap();
+        let deposit = &deposits[0];
+        assert!(deposit.verify().unwrap());
+
+        let sk = eth2::PrivateKey::from_bytes(
+            &hex::decode("0d7359d57963ab8fbbde1852dcf553fedbc31f464d80ee7d40ae683122b45070")
+                .unwrap(),
+        )
+        .unwrap();
+        let message = DepositMessage::new(
+            &eth2::PublicKey::new(&sk).to_bytes(),
+            eth1_withdrawal_credentials([0x1bu8; 20]),
+            MAX_EFFECTIVE_BALANCE,
+        )
+        .unwrap();
+        assert_eq!(message.sign(GENESIS_FORK_VERSION, &sk).unwrap(), *deposit);
+
+        let mut other = deposit.clone();
+        other.fork_version = "00000001".to_string();
+        assert!(!other.verify().unwrap());
+        let mut other = deposit.clone();
+        other.withdrawal_credentials = hex::encode(eth1_withdrawal_credentials([0xabu8; 20]));
+        assert!(!other.verify().unwrap());
+        // Roots that match the tampered message don't help without a new
+        // signature
+        let forged = DepositMessage::new(
+            &message.pubkey,
+            eth1_withdrawal_credentials([0xabu8; 20]),
+            MAX_EFFECTIVE_BALANCE,
+        )
+        .unwrap()
+        .sign_with(GENESIS_FORK_VERSION, |_| deposit.signature())
+        .unwrap();
+        assert!(!forged.verify().unwrap());
+        let mut other = deposit.clone();
+        other.pubkey = hex::encode([0u8; PUBLIC_KEY_SIZE]);
+        assert!(other.verify().is_err());
+
+        let other = eth2::PrivateKey::key_gen(&[7u8; 32], &[]).unwrap();
+        assert!(message.sign(GENESIS_FORK_VERSION, &other).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+// Versioned C API over the algorithm registry
+//
+// Keypairs live in a thread safe handle map and are only reachable through
+// their opaque `uint64_t` handle, so private keys never cross the FFI unless
+// `ursa_keypair_private_key` is called. Every function takes an
+// `ExternError` out parameter: its `code` is 0 on success, one of
+// `api_error_codes` for a `CryptoError`, -1 if a panic was caught and -1000
+// for a stale or invalid handle. Error messages and returned strings are
+// freed with `ursa_string_free`, byte buffers with `ursa_bytebuffer_free`.
+//
+// Example of how to use these functions from C
+// #include "ursa_crypto.h"
+//
+// int main(void) {
+//     struct ExternError err;
+//     uint64_t keys = ursa_keypair_generate("ed25519", &err);
+//     if (err.code != 0) {
+//         ursa_string_free(err.message);
+//         return 1;
+//     }
+//     struct ByteArray message = { 5, (const uint8_t *)"hello" };
+//     struct ByteBuffer signature = ursa_keypair_sign(keys, &message, &err);
+//     struct ByteBuffer public_key = ursa_keypair_public_key(keys, &err);
+//
+//     struct ByteArray pk = { public_key.len, public_key.data };
+//     struct ByteArray sig = { signature.len, signature.data };
+//     uint8_t valid = ursa_verify("ed25519", &pk, &message, &sig, &err);
+//
+//     ursa_bytebuffer_free(signature);
+//     ursa_bytebuffer_free(public_key);
+//     ursa_keypair_free(keys, &err);
+//     return valid ? 0 : 1;
+// }
+
+use super::ByteArray;
+use ffi_support::{
+    call_with_result, ByteBuffer, ConcurrentHandleMap, ErrorCode, ExternError, FfiStr,
+};
+use keys::{KeyGenOption, PrivateKey, PublicKey};
+use registry::{Algorithm, AnyKeyPair, AnyPublicKey};
+use std::os::raw::c_char;
+use ursa_core::error::codes;
+use CryptoError;
+
+/// The version of this API. It only changes when existing functions change
+/// or are removed.
+pub const URSA_API_VERSION: u32 = 1;
+
+/// The error codes for each kind of `CryptoError`, see `CryptoError::code`
+pub mod api_error_codes {
+    pub use ursa_core::error::codes::{
+        DIGEST_GEN_ERROR, GENERAL_ERROR, INVALID_GROUP_ELEMENT, INVALID_PARAM, KEY_GEN_ERROR,
+        NO_SUCH_ALGORITHM, PARSE_ERROR, SIGNING_ERROR,
+    };
+}
+
+impl From<CryptoError> for ExternError {
+    fn from(e: CryptoError) -> Self {
+        ExternError::new_error(ErrorCode::new(e.code()), e.to_string())
+    }
+}
+
+lazy_static! {
+    static ref KEYPAIRS: ConcurrentHandleMap<AnyKeyPair> = ConcurrentHandleMap::new();
+}
+
+define_handle_map_deleter!(KEYPAIRS, ursa_keypair_free);
+
+/// Return `URSA_API_VERSION`
+#[no_mangle]
+pub extern "C" fn ursa_api_version() -> u32 {
+    URSA_API_VERSION
+}
+
+/// Return the names of the algorithms in this build, separated by commas.
+/// Caller will need to call `ursa_string_free` on the result.
+#[no_mangle]
+pub extern "C" fn ursa_algorithms(err: &mut ExternError) -> *mut c_char {
+    call_with_result(err, || -> Result<String, CryptoError> {
+        Ok(Algorithm::all()
+            .iter()
+            .map(|a| a.name())
+            .collect::<Vec<&str>>()
+            .join(","))
+    })
+}
+
+/// Return the name of error `code`, such as `PARSE_ERROR`, or null with
+/// `NO_SUCH_ALGORITHM` in `err` if the code is unknown.
+/// Caller will need to call `ursa_string_free` on the result.
+#[no_mangle]
+pub extern "C" fn ursa_error_code_name(code: i32, err: &mut ExternError) -> *mut c_char {
+    call_with_result(err, || -> Result<String, CryptoError> {
+        codes::name(code)
+            .map(str::to_string)
+            .ok_or_else(|| CryptoError::NoSuchAlgorithm(format!("Unknown error code {}", code)))
+    })
+}
+
+/// Return every error code with its name as `code=NAME`, separated by
+/// commas. The codes are stable across releases.
+/// Caller will need to call `ursa_string_free` on the result.
+#[no_mangle]
+pub extern "C" fn ursa_error_codes(err: &mut ExternError) -> *mut c_char {
+    call_with_result(err, || -> Result<String, CryptoError> {
+        Ok(codes::ALL
+            .iter()
+            .map(|(code, name)| format!("{}={}", code, name))
+            .collect::<Vec<String>>()
+            .join(","))
+    })
+}
+
+/// Generate a random keypair of `algorithm` and return its handle
+#[no_mangle]
+pub extern "C" fn ursa_keypair_generate(algorithm: FfiStr, err: &mut ExternError) -> u64 {
+    keypair(algorithm, None, err)
+}
+
+/// Generate a keypair of `algorithm` from `seed` and return its handle
+#[no_mangle]
+pub extern "C" fn ursa_keypair_from_seed(
+    algorithm: FfiStr,
+    seed: &ByteArray,
+    err: &mut ExternError,
+) -> u64 {
+    keypair(algorithm, Some(KeyGenOption::UseSeed(seed.to_vec())), err)
+}
+
+/// Load a private key of `algorithm` and return the handle of its keypair
+#[no_mangle]
+pub extern "C" fn ursa_keypair_from_private_key(
+    algorithm: FfiStr,
+    private_key: &ByteArray,
+    err: &mut ExternError,
+) -> u64 {
+    let sk = PrivateKey(private_key.to_vec());
+    keypair(algorithm, Some(KeyGenOption::FromSecretKey(sk)), err)
+}
+
+/// Return the algorithm name of a keypair.
+/// Caller will need to call `ursa_string_free` on the result.
+#[no_mangle]
+pub extern "C" fn ursa_keypair_algorithm(handle: u64, err: &mut ExternError) -> *mut c_char {
+    KEYPAIRS.call_with_output(err, handle, |k| k.algorithm().name().to_string())
+}
+
+/// Return the public key of a keypair.
+/// Caller will need to call `ursa_bytebuffer_free` on the result.
+#[no_mangle]
+pub extern "C" fn ursa_keypair_public_key(handle: u64, err: &mut ExternError) -> ByteBuffer {
+    KEYPAIRS.call_with_output(err, handle, |k| {
+        ByteBuffer::from_vec(k.keys().public_key.0.clone())
+    })
+}
+
+/// Export the private key of a keypair.
+/// Caller will need to call `ursa_bytebuffer_free` on the result,
+/// after wiping it.
+#[no_mangle]
+pub extern "C" fn ursa_keypair_private_key(handle: u64, err: &mut ExternError) -> ByteBuffer {
+    KEYPAIRS.call_with_output(err, handle, |k| {
+        ByteBuffer::from_vec(k.private_key().0.clone())
+    })
+}
+
+/// Sign `message` with a keypair of a signature algorithm.
+/// Caller will need to call `ursa_bytebuffer_free` on the result.
+#[no_mangle]
+pub extern "C" fn ursa_keypair_sign(
+    handle: u64,
+    message: &ByteArray,
+    err: &mut ExternError,
+) -> ByteBuffer {
+    let message = message.to_vec();
+    KEYPAIRS.call_with_result(err, handle, |k| k.sign(&message).map(ByteBuffer::from_vec))
+}
+
+/// Compute the shared secret of a keypair of a key exchange algorithm and
+/// `peer_public_key` of the same algorithm.
+/// Caller will need to call `ursa_bytebuffer_free` on the result.
+#[no_mangle]
+pub extern "C" fn ursa_keypair_key_exchange(
+    handle: u64,
+    peer_public_key: &ByteArray,
+    err: &mut ExternError,
+) -> ByteBuffer {
+    let peer = PublicKey(peer_public_key.to_vec());
+    KEYPAIRS.call_with_result(err, handle, |k| {
+        k.key_exchange(&AnyPublicKey::new(k.algorithm(), peer))
+            .map(|s| ByteBuffer::from_vec(s.0.clone()))
+    })
+}
+
+/// Verify `signature` over `message` with a public key of `algorithm`.
+/// Returns 1 if the signature is valid and 0 otherwise.
+#[no_mangle]
+pub extern "C" fn ursa_verify(
+    algorithm: FfiStr,
+    public_key: &ByteArray,
+    message: &ByteArray,
+    signature: &ByteArray,
+    err: &mut ExternError,
+) -> u8 {
+    let algorithm = algorithm.as_opt_str().map(str::to_string);
+    let pk = PublicKey(public_key.to_vec());
+    let message = message.to_vec();
+    let signature = signature.to_vec();
+    call_with_result(err, || -> Result<bool, ExternError> {
+        let pk = AnyPublicKey::new(parse_algorithm(algorithm)?, pk);
+        Ok(pk.verify(&message, &signature)?)
+    })
+}
+
+fn keypair(algorithm: FfiStr, option: Option<KeyGenOption>, err: &mut ExternError) -> u64 {
+    let algorithm = algorithm.as_opt_str().map(str::to_string);
+    KEYPAIRS.insert_with_result(err, || -> Result<AnyKeyPair, ExternError> {
+        Ok(parse_algorithm(algorithm)?.keypair(option)?)
+    })
+}
+
+fn parse_algorithm(name: Option<String>) -> Result<Algorithm, ExternError> {
+    match name {
+        Some(name) => Ok(name.parse()?),
+        None => Err(ExternError::new_error(
+            ErrorCode::new(api_error_codes::INVALID_PARAM),
+            "Algorithm must be a non null UTF-8 string",
+        )),
+    }
+}
+
+#[cfg(all(test, any(feature = "ed25519", feature = "ed25519_asm")))]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    fn release(err: &mut ExternError) {
+        unsafe { std::mem::replace(err, ExternError::success()).manually_release() };
+    }
+
+    #[test]
+    fn ffi_keypair_handles() {
+        let name = CString::new("ed25519").unwrap();
+        let mut err = ExternError::success();
+        assert_eq!(1, ursa_api_version());
+
+        let seed = vec![1u8; 32];
+        let handle =
+            ursa_keypair_from_seed(FfiStr::from_cstr(&name), &ByteArray::from(&seed), &mut err);
+        assert!(err.get_code().is_success());
+        let pk = ursa_keypair_public_key(handle, &mut err).destroy_into_vec();
+        assert_eq!(
+            "3b77a042f1de02f6d5f418f36a20fd68c8329fe3bbfbecd26a2d72878cd827f8",
+            hex::encode(&pk)
+        );
+
+        let message = b"Wepa! This is a message that should be signed.";
+        let signature =
+            ursa_keypair_sign(handle, &ByteArray::from(&message[..]), &mut err).destroy_into_vec();
+        assert!(err.get_code().is_success());
+        assert_eq!(
+            1,
+            ursa_verify(
+                FfiStr::from_cstr(&name),
+                &ByteArray::from(&pk),
+                &ByteArray::from(&message[..]),
+                &ByteArray::from(&signature),
+                &mut err
+            )
+        );
+
+        ursa_keypair_free(handle, &mut err);
+        assert!(err.get_code().is_success());
+        ursa_keypair_public_key(handle, &mut err);
+        assert_eq!(ErrorCode::INVALID_HANDLE, err.get_code());
+        release(&mut err);
+    }
+
+    #[test]
+    fn ffi_error_codes() {
+        let mut err = ExternError::success();
+        let name = CString::new("rsa").unwrap();
+        ursa_keypair_generate(FfiStr::from_cstr(&name), &mut err);
+        assert_eq!(api_error_codes::NO_SUCH_ALGORITHM, err.get_code().code());
+        release(&mut err);
+
+        let name = CString::new("ed25519").unwrap();
+        let sk = vec![1u8; 3];
+        ursa_keypair_from_private_key(FfiStr::from_cstr(&name), &ByteArray::from(&sk), &mut err);
+        assert_eq!(api_error_codes::KEY_GEN_ERROR, err.get_code().code());
+        release(&mut err);
+
+        let name = ursa_error_code_name(api_error_codes::PARSE_ERROR, &mut err);
+        assert!(err.get_code().is_success());
+        assert_eq!(
+            "PARSE_ERROR",
+            unsafe { CString::from_raw(name) }.to_str().unwrap()
+        );
+        assert!(ursa_error_code_name(0, &mut err).is_null());
+        assert_eq!(api_error_codes::NO_SUCH_ALGORITHM, err.get_code().code());
+        release(&mut err);
+
+        let all = unsafe { CString::from_raw(ursa_error_codes(&mut err)) };
+        assert!(all
+            .to_str()
+            .unwrap()
+            .starts_with("1=NO_SUCH_ALGORITHM,2=PARSE_ERROR,"));
+    }
+}
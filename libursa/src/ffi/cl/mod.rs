@@ -859,6 +859,46 @@ pub extern "C" fn ursa_cl_sub_proof_request_builder_add_predicate(
     res
 }
 
+/// Adds range predicate to sub proof request.
+/// The proof will show `lower <= attr_value <= upper`.
+///
+/// # Arguments
+/// * `sub_proof_request_builder` - Reference that contains sub proof request builder instance pointer.
+/// * `attr_name` - Related attribute
+/// * `lower` - Inclusive lower bound.
+/// * `upper` - Inclusive upper bound.
+#[no_mangle]
+pub extern "C" fn ursa_cl_sub_proof_request_builder_add_range_predicate(
+    sub_proof_request_builder: *const c_void,
+    attr_name: *const c_char,
+    lower: i32,
+    upper: i32,
+) -> ErrorCode {
+    trace!("ursa_cl_sub_proof_request_builder_add_range_predicate: >>> sub_proof_request_builder: {:?}, attr_name: {:?}, lower: {:?}, upper: {:?}",
+           sub_proof_request_builder, attr_name, lower, upper);
+
+    check_useful_mut_c_reference!(
+        sub_proof_request_builder,
+        SubProofRequestBuilder,
+        ErrorCode::CommonInvalidParam1
+    );
+    check_useful_c_str!(attr_name, ErrorCode::CommonInvalidParam2);
+
+    trace!("ursa_cl_sub_proof_request_builder_add_range_predicate: entities: >>> sub_proof_request_builder: {:?}, attr_name: {:?}, lower: {:?}, upper: {:?}",
+           sub_proof_request_builder, attr_name, lower, upper);
+
+    let res = match sub_proof_request_builder.add_range_predicate(&attr_name, lower, upper) {
+        Ok(_) => ErrorCode::Success,
+        Err(err) => err.into(),
+    };
+
+    trace!(
+        "ursa_cl_sub_proof_request_builder_add_range_predicate: <<< res: {:?}",
+        res
+    );
+    res
+}
+
 /// Deallocates sub proof request builder and returns sub proof request entity instead.
 ///
 /// Note: Sub proof request instance deallocation must be performed by
@@ -1400,6 +1440,31 @@ mod tests {
         _free_sub_proof_request_builder(sub_proof_request_builder);
     }
 
+    #[test]
+    fn ursa_cl_sub_proof_request_builder_add_range_predicate_works() {
+        let sub_proof_request_builder = _sub_proof_request_builder();
+
+        let attr_name = CString::new("age").unwrap();
+
+        let err_code = ursa_cl_sub_proof_request_builder_add_range_predicate(
+            sub_proof_request_builder,
+            attr_name.as_ptr(),
+            18,
+            65,
+        );
+        assert_eq!(err_code, ErrorCode::Success);
+
+        let err_code = ursa_cl_sub_proof_request_builder_add_range_predicate(
+            sub_proof_request_builder,
+            attr_name.as_ptr(),
+            65,
+            18,
+        );
+        assert_eq!(err_code, ErrorCode::CommonInvalidStructure);
+
+        _free_sub_proof_request_builder(sub_proof_request_builder);
+    }
+
     #[test]
     fn ursa_cl_sub_proof_request_builder_finalize_works() {
         let sub_proof_request_builder = _sub_proof_request_builder();
@@ -1,3 +1,16 @@
+#[cfg(any(
+    feature = "ecdh_secp256k1",
+    feature = "ecdh_secp256k1_native",
+    feature = "ecdh_secp256k1_asm",
+    feature = "ecdsa_secp256k1",
+    feature = "ecdsa_secp256k1_native",
+    feature = "ecdsa_secp256k1_asm",
+    feature = "ed25519",
+    feature = "ed25519_asm",
+    feature = "x25519",
+    feature = "x25519_asm"
+))]
+pub mod api;
 #[cfg(any(feature = "bls_bn254", feature = "bls_bn254_asm"))]
 pub mod bls;
 #[cfg(any(feature = "cl", feature = "cl_native"))]
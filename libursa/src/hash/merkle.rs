@@ -0,0 +1,450 @@
+//! Merkle trees as described in RFC 6962 section 2.1
+//! (see <https://tools.ietf.org/html/rfc6962#section-2.1>)
+//! with inclusion and consistency proof verification from
+//! RFC 9162 section 2.1.3 and 2.1.4.
+//!
+//! Leaves are hashed as `H(0x00 || data)` and interior nodes as
+//! `H(0x01 || left || right)` so a leaf can never be confused with a node.
+//!
+//! With the `parallel` feature the leaves of `from_leaves` and `extend` are
+//! hashed in parallel and so are the two halves of large subtrees.
+use super::simd::Sha256;
+use alloc::vec::Vec;
+use sha2::digest::generic_array::GenericArray;
+use sha2::Digest;
+use ursa_core::parallel::{self, MaybeSync};
+
+use CryptoError;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+/// Subtrees with fewer leaves are hashed on a single thread
+const PARALLEL_LEAVES: usize = 1024;
+
+/// The output of the hash function used by the tree
+pub type MerkleHash<D> = GenericArray<u8, <D as Digest>::OutputSize>;
+
+/// Compute the hash of a leaf
+pub fn leaf_hash<D: Digest>(data: &[u8]) -> MerkleHash<D> {
+    D::new().chain([LEAF_PREFIX]).chain(data).result()
+}
+
+/// Compute the hash of an interior node
+pub fn node_hash<D: Digest>(left: &MerkleHash<D>, right: &MerkleHash<D>) -> MerkleHash<D> {
+    D::new()
+        .chain([NODE_PREFIX])
+        .chain(left)
+        .chain(right)
+        .result()
+}
+
+/// An append-only Merkle tree. Only the leaf hashes are retained.
+#[derive(Debug, Clone)]
+pub struct MerkleTree<D: Digest = Sha256> {
+    leaves: Vec<MerkleHash<D>>,
+}
+
+impl<D: Digest> Default for MerkleTree<D> {
+    fn default() -> Self {
+        MerkleTree { leaves: Vec::new() }
+    }
+}
+
+impl<D: Digest> MerkleTree<D> {
+    /// Create an empty tree
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a leaf to the tree and return its index
+    pub fn append<B: AsRef<[u8]>>(&mut self, data: B) -> usize {
+        self.leaves.push(leaf_hash::<D>(data.as_ref()));
+        self.leaves.len() - 1
+    }
+
+    /// Create a tree from the leaves
+    pub fn from_leaves<B: AsRef<[u8]> + MaybeSync>(data: &[B]) -> Self {
+        let mut tree = Self::new();
+        tree.extend(data);
+        tree
+    }
+
+    /// Append the leaves to the tree
+    pub fn extend<B: AsRef<[u8]> + MaybeSync>(&mut self, data: &[B]) {
+        let hashes = parallel::map(data, |d| leaf_hash::<D>(d.as_ref()));
+        self.leaves.extend(hashes);
+    }
+
+    /// Append a leaf that has already been hashed with `leaf_hash`
+    pub fn append_leaf_hash(&mut self, hash: MerkleHash<D>) -> usize {
+        self.leaves.push(hash);
+        self.leaves.len() - 1
+    }
+
+    /// The number of leaves in the tree
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// True if the tree has no leaves
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// The root hash of the current tree
+    pub fn root(&self) -> MerkleHash<D> {
+        subtree_root::<D>(&self.leaves)
+    }
+
+    /// The root hash of the tree when it had `tree_size` leaves
+    pub fn root_at(&self, tree_size: usize) -> Result<MerkleHash<D>, CryptoError> {
+        if tree_size > self.leaves.len() {
+            return Err(CryptoError::GeneralError(format!(
+                "Tree size {} exceeds the number of leaves {}",
+                tree_size,
+                self.leaves.len()
+            )));
+        }
+        Ok(subtree_root::<D>(&self.leaves[..tree_size]))
+    }
+
+    /// Create a proof that the leaf at `index` is included
+    /// in the tree when it had `tree_size` leaves
+    pub fn inclusion_proof(
+        &self,
+        index: usize,
+        tree_size: usize,
+    ) -> Result<InclusionProof<D>, CryptoError> {
+        if index >= tree_size || tree_size > self.leaves.len() {
+            return Err(CryptoError::GeneralError(format!(
+                "Invalid leaf index {} for tree size {}",
+                index, tree_size
+            )));
+        }
+        let mut path = Vec::new();
+        inclusion_path::<D>(index, &self.leaves[..tree_size], &mut path);
+        Ok(InclusionProof {
+            index,
+            tree_size,
+            path,
+        })
+    }
+
+    /// Create a proof that the tree with `old_size` leaves
+    /// is a prefix of the tree with `new_size` leaves
+    pub fn consistency_proof(
+        &self,
+        old_size: usize,
+        new_size: usize,
+    ) -> Result<ConsistencyProof<D>, CryptoError> {
+        if old_size > new_size || new_size > self.leaves.len() {
+            return Err(CryptoError::GeneralError(format!(
+                "Invalid tree sizes {} and {}",
+                old_size, new_size
+            )));
+        }
+        let mut path = Vec::new();
+        if old_size > 0 {
+            consistency_path::<D>(old_size, &self.leaves[..new_size], true, &mut path);
+        }
+        Ok(ConsistencyProof {
+            old_size,
+            new_size,
+            path,
+        })
+    }
+}
+
+/// Proof that a leaf is included in a tree of a given size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionProof<D: Digest = Sha256> {
+    /// The index of the leaf
+    pub index: usize,
+    /// The number of leaves in the tree
+    pub tree_size: usize,
+    /// The sibling hashes from the leaf to the root
+    pub path: Vec<MerkleHash<D>>,
+}
+
+impl<D: Digest> InclusionProof<D> {
+    /// Verify the proof for the leaf `data` against the tree `root`
+    pub fn verify<B: AsRef<[u8]>>(&self, data: B, root: &MerkleHash<D>) -> bool {
+        self.verify_leaf_hash(&leaf_hash::<D>(data.as_ref()), root)
+    }
+
+    /// Verify the proof for a leaf that has already been hashed with `leaf_hash`
+    pub fn verify_leaf_hash(&self, leaf: &MerkleHash<D>, root: &MerkleHash<D>) -> bool {
+        if self.index >= self.tree_size {
+            return false;
+        }
+        let mut f_n = self.index;
+        let mut s_n = self.tree_size - 1;
+        let mut r = leaf.clone();
+        for p in &self.path {
+            if s_n == 0 {
+                return false;
+            }
+            if f_n & 1 == 1 || f_n == s_n {
+                r = node_hash::<D>(p, &r);
+                while f_n & 1 == 0 && f_n != 0 {
+                    f_n >>= 1;
+                    s_n >>= 1;
+                }
+            } else {
+                r = node_hash::<D>(&r, p);
+            }
+            f_n >>= 1;
+            s_n >>= 1;
+        }
+        s_n == 0 && &r == root
+    }
+}
+
+/// Proof that a tree is an append-only extension of an earlier tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyProof<D: Digest = Sha256> {
+    /// The number of leaves in the earlier tree
+    pub old_size: usize,
+    /// The number of leaves in the later tree
+    pub new_size: usize,
+    /// The subtree hashes needed to compute both roots
+    pub path: Vec<MerkleHash<D>>,
+}
+
+impl<D: Digest> ConsistencyProof<D> {
+    /// Verify the proof against the earlier and later tree roots
+    pub fn verify(&self, old_root: &MerkleHash<D>, new_root: &MerkleHash<D>) -> bool {
+        if self.old_size > self.new_size {
+            return false;
+        }
+        if self.old_size == self.new_size {
+            return self.path.is_empty() && old_root == new_root;
+        }
+        if self.old_size == 0 {
+            return self.path.is_empty();
+        }
+        if self.path.is_empty() {
+            return false;
+        }
+
+        let mut path = self.path.iter();
+        // When the old tree is a complete subtree its root is the first node
+        let seed = if self.old_size.is_power_of_two() {
+            old_root.clone()
+        } else {
+            path.next().unwrap().clone()
+        };
+        let mut f_n = self.old_size - 1;
+        let mut s_n = self.new_size - 1;
+        while f_n & 1 == 1 {
+            f_n >>= 1;
+            s_n >>= 1;
+        }
+        let mut f_r = seed.clone();
+        let mut s_r = seed;
+        for c in path {
+            if s_n == 0 {
+                return false;
+            }
+            if f_n & 1 == 1 || f_n == s_n {
+                f_r = node_hash::<D>(c, &f_r);
+                s_r = node_hash::<D>(c, &s_r);
+                while f_n & 1 == 0 && f_n != 0 {
+                    f_n >>= 1;
+                    s_n >>= 1;
+                }
+            } else {
+                s_r = node_hash::<D>(&s_r, c);
+            }
+            f_n >>= 1;
+            s_n >>= 1;
+        }
+        s_n == 0 && &f_r == old_root && &s_r == new_root
+    }
+}
+
+/// The largest power of two strictly less than `n`
+fn split_point(n: usize) -> usize {
+    debug_assert!(n > 1);
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+fn subtree_root<D: Digest>(leaves: &[MerkleHash<D>]) -> MerkleHash<D> {
+    match leaves.len() {
+        0 => D::digest(&[]),
+        1 => leaves[0].clone(),
+        n if n >= PARALLEL_LEAVES => {
+            let k = split_point(n);
+            let (left, right) = parallel::join(
+                || subtree_root::<D>(&leaves[..k]),
+                || subtree_root::<D>(&leaves[k..]),
+            );
+            node_hash::<D>(&left, &right)
+        }
+        n => {
+            let k = split_point(n);
+            node_hash::<D>(
+                &subtree_root::<D>(&leaves[..k]),
+                &subtree_root::<D>(&leaves[k..]),
+            )
+        }
+    }
+}
+
+fn inclusion_path<D: Digest>(
+    index: usize,
+    leaves: &[MerkleHash<D>],
+    path: &mut Vec<MerkleHash<D>>,
+) {
+    let n = leaves.len();
+    if n <= 1 {
+        return;
+    }
+    let k = split_point(n);
+    if index < k {
+        inclusion_path::<D>(index, &leaves[..k], path);
+        path.push(subtree_root::<D>(&leaves[k..]));
+    } else {
+        inclusion_path::<D>(index - k, &leaves[k..], path);
+        path.push(subtree_root::<D>(&leaves[..k]));
+    }
+}
+
+fn consistency_path<D: Digest>(
+    m: usize,
+    leaves: &[MerkleHash<D>],
+    complete: bool,
+    path: &mut Vec<MerkleHash<D>>,
+) {
+    let n = leaves.len();
+    if m == n {
+        if !complete {
+            path.push(subtree_root::<D>(leaves));
+        }
+        return;
+    }
+    let k = split_point(n);
+    if m <= k {
+        consistency_path::<D>(m, &leaves[..k], complete, path);
+        path.push(subtree_root::<D>(&leaves[k..]));
+    } else {
+        consistency_path::<D>(m - k, &leaves[k..], false, path);
+        path.push(subtree_root::<D>(&leaves[..k]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    // Test vectors from the certificate transparency reference implementation
+    const LEAVES: [&[u8]; 8] = [
+        b"",
+        b"\x00",
+        b"\x10",
+        b"\x20\x21",
+        b"\x30\x31",
+        b"\x40\x41\x42\x43",
+        b"\x50\x51\x52\x53\x54\x55\x56\x57",
+        b"\x60\x61\x62\x63\x64\x65\x66\x67\x68\x69\x6a\x6b\x6c\x6d\x6e\x6f",
+    ];
+    const ROOTS: [&str; 8] = [
+        "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+        "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+        "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+        "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+        "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+        "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+        "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+        "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+    ];
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn tree(size: usize) -> MerkleTree {
+        let mut tree = MerkleTree::new();
+        for i in 0..size {
+            tree.append(format!("leaf {}", i));
+        }
+        tree
+    }
+
+    #[test]
+    fn empty_tree() {
+        let tree: MerkleTree = MerkleTree::new();
+        assert_eq!(
+            to_hex(&tree.root()),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn root_vectors() {
+        let mut tree: MerkleTree = MerkleTree::new();
+        for (i, leaf) in LEAVES.iter().enumerate() {
+            tree.append(leaf);
+            assert_eq!(to_hex(&tree.root()), ROOTS[i]);
+        }
+        for (i, root) in ROOTS.iter().enumerate() {
+            assert_eq!(to_hex(&tree.root_at(i + 1).unwrap()), *root);
+        }
+        assert!(tree.root_at(9).is_err());
+    }
+
+    #[test]
+    fn inclusion_proofs() {
+        let tree = tree(20);
+        for size in 1..=tree.len() {
+            let root = tree.root_at(size).unwrap();
+            for index in 0..size {
+                let proof = tree.inclusion_proof(index, size).unwrap();
+                assert!(proof.verify(format!("leaf {}", index), &root));
+                assert!(!proof.verify(format!("leaf {}", index + 1), &root));
+            }
+        }
+        assert!(tree.inclusion_proof(5, 5).is_err());
+    }
+
+    #[test]
+    fn consistency_proofs() {
+        let tree = tree(20);
+        for new_size in 1..=tree.len() {
+            let new_root = tree.root_at(new_size).unwrap();
+            for old_size in 1..=new_size {
+                let old_root = tree.root_at(old_size).unwrap();
+                let proof = tree.consistency_proof(old_size, new_size).unwrap();
+                assert!(proof.verify(&old_root, &new_root));
+                if old_size != new_size {
+                    assert!(!proof.verify(&new_root, &old_root));
+                }
+            }
+        }
+        assert!(tree.consistency_proof(4, 3).is_err());
+    }
+
+    #[test]
+    fn large_trees() {
+        let leaves = (0..3000).map(|i| format!("leaf {}", i)).collect::<Vec<_>>();
+        let tree: MerkleTree = MerkleTree::from_leaves(&leaves);
+        assert_eq!(tree.root(), self::tree(3000).root());
+
+        let mut extended: MerkleTree = MerkleTree::from_leaves(&leaves[..1500]);
+        extended.extend(&leaves[1500..]);
+        assert_eq!(tree.root(), extended.root());
+
+        let root = tree.root();
+        for index in &[0, 1023, 1024, 2047, 2048, 2999] {
+            let proof = tree.inclusion_proof(*index, tree.len()).unwrap();
+            assert!(proof.verify(&leaves[*index], &root));
+        }
+        let proof = tree.consistency_proof(1024, 3000).unwrap();
+        assert!(proof.verify(&tree.root_at(1024).unwrap(), &root));
+    }
+}
@@ -8,3 +8,7 @@ pub use sha2::Digest;
 
 #[cfg(feature = "blake2")]
 pub mod blake2;
+#[cfg(feature = "sha2")]
+pub mod merkle;
+#[cfg(feature = "sha2")]
+pub mod simd;
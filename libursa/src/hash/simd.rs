@@ -0,0 +1,526 @@
+//! Hash functions that pick a SIMD implementation for the CPU at runtime.
+//!
+//! `Sha256` uses the SHA extensions on x86 and x86_64 and the ARMv8 crypto
+//! extensions on aarch64, and falls back to the `sha2` crate on other CPUs.
+//! `Blake2b` wraps `blake2b_simd`, which selects AVX2 or SSE4.1 itself and
+//! has a portable fallback. Both implement `Digest`, so they can be used
+//! anywhere the `sha2` and `blake2` types are, and produce the same output.
+//!
+//! The Merkle trees and the hashes of the CL signatures use these by default.
+#[cfg(feature = "blake2b_simd")]
+use sha2::digest::generic_array::typenum::U128;
+use sha2::digest::generic_array::typenum::{U32, U64};
+use sha2::digest::generic_array::GenericArray;
+use sha2::digest::{BlockInput, FixedOutput, Input, Reset};
+use std::fmt;
+
+const BLOCK_SIZE: usize = 64;
+
+/// Process whole blocks into the state
+type CompressFn = fn(&mut [u32; 8], &[[u8; BLOCK_SIZE]]);
+
+const H256: [u32; 8] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+const K256: [u32; 64] = [
+    0x428a_2f98,
+    0x7137_4491,
+    0xb5c0_fbcf,
+    0xe9b5_dba5,
+    0x3956_c25b,
+    0x59f1_11f1,
+    0x923f_82a4,
+    0xab1c_5ed5,
+    0xd807_aa98,
+    0x1283_5b01,
+    0x2431_85be,
+    0x550c_7dc3,
+    0x72be_5d74,
+    0x80de_b1fe,
+    0x9bdc_06a7,
+    0xc19b_f174,
+    0xe49b_69c1,
+    0xefbe_4786,
+    0x0fc1_9dc6,
+    0x240c_a1cc,
+    0x2de9_2c6f,
+    0x4a74_84aa,
+    0x5cb0_a9dc,
+    0x76f9_88da,
+    0x983e_5152,
+    0xa831_c66d,
+    0xb003_27c8,
+    0xbf59_7fc7,
+    0xc6e0_0bf3,
+    0xd5a7_9147,
+    0x06ca_6351,
+    0x1429_2967,
+    0x27b7_0a85,
+    0x2e1b_2138,
+    0x4d2c_6dfc,
+    0x5338_0d13,
+    0x650a_7354,
+    0x766a_0abb,
+    0x81c2_c92e,
+    0x9272_2c85,
+    0xa2bf_e8a1,
+    0xa81a_664b,
+    0xc24b_8b70,
+    0xc76c_51a3,
+    0xd192_e819,
+    0xd699_0624,
+    0xf40e_3585,
+    0x106a_a070,
+    0x19a4_c116,
+    0x1e37_6c08,
+    0x2748_774c,
+    0x34b0_bcb5,
+    0x391c_0cb3,
+    0x4ed8_aa4a,
+    0x5b9c_ca4f,
+    0x682e_6ff3,
+    0x748f_82ee,
+    0x78a5_636f,
+    0x84c8_7814,
+    0x8cc7_0208,
+    0x90be_fffa,
+    0xa450_6ceb,
+    0xbef9_a3f7,
+    0xc671_78f2,
+];
+
+/// An implementation of the SHA-256 compression function
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Backend {
+    /// The `sha2` crate
+    Portable,
+    /// The x86 SHA extensions
+    ShaNi,
+    /// The ARMv8 SHA-2 crypto extensions
+    ArmSha2,
+}
+
+impl Backend {
+    /// The fastest implementation this CPU supports
+    pub fn detect() -> Self {
+        if Backend::ShaNi.is_supported() {
+            Backend::ShaNi
+        } else if Backend::ArmSha2.is_supported() {
+            Backend::ArmSha2
+        } else {
+            Backend::Portable
+        }
+    }
+
+    /// True if this CPU can run the implementation
+    pub fn is_supported(self) -> bool {
+        match self {
+            Backend::Portable => true,
+            #[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+            Backend::ShaNi => {
+                is_x86_feature_detected!("sha")
+                    && is_x86_feature_detected!("sse2")
+                    && is_x86_feature_detected!("ssse3")
+                    && is_x86_feature_detected!("sse4.1")
+            }
+            #[cfg(all(feature = "std", target_arch = "aarch64"))]
+            Backend::ArmSha2 => std::arch::is_aarch64_feature_detected!("sha2"),
+            // Without std the CPU can't be queried, so only the features the
+            // crate was compiled for are used
+            #[cfg(all(not(feature = "std"), any(target_arch = "x86", target_arch = "x86_64")))]
+            Backend::ShaNi => {
+                cfg!(target_feature = "sha")
+                    && cfg!(target_feature = "sse2")
+                    && cfg!(target_feature = "ssse3")
+                    && cfg!(target_feature = "sse4.1")
+            }
+            #[cfg(all(not(feature = "std"), target_arch = "aarch64"))]
+            Backend::ArmSha2 => cfg!(target_feature = "sha2"),
+            _ => false,
+        }
+    }
+
+    fn compress(self) -> Option<CompressFn> {
+        match self {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::ShaNi => Some(x86::compress),
+            #[cfg(target_arch = "aarch64")]
+            Backend::ArmSha2 => Some(aarch64::compress),
+            _ => None,
+        }
+    }
+}
+
+/// SHA-256 with the compression function chosen by `Backend::detect`
+#[derive(Clone)]
+pub struct Sha256(Sha256Inner);
+
+#[derive(Clone)]
+enum Sha256Inner {
+    Portable(sha2::Sha256),
+    Accelerated {
+        backend: Backend,
+        compress: CompressFn,
+        state: [u32; 8],
+        buffer: [u8; BLOCK_SIZE],
+        buffered: usize,
+        length: u64,
+    },
+}
+
+impl Sha256 {
+    /// Use `backend` for the compression function,
+    /// or `None` if this CPU doesn't support it
+    pub fn with_backend(backend: Backend) -> Option<Self> {
+        if !backend.is_supported() {
+            return None;
+        }
+        Some(Sha256(match backend.compress() {
+            Some(compress) => Sha256Inner::Accelerated {
+                backend,
+                compress,
+                state: H256,
+                buffer: [0u8; BLOCK_SIZE],
+                buffered: 0,
+                length: 0,
+            },
+            None => Sha256Inner::Portable(sha2::Sha256::default()),
+        }))
+    }
+
+    /// The implementation of the compression function in use
+    pub fn backend(&self) -> Backend {
+        match self.0 {
+            Sha256Inner::Portable(_) => Backend::Portable,
+            Sha256Inner::Accelerated { backend, .. } => backend,
+        }
+    }
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::with_backend(Backend::detect()).expect("The detected backend is supported")
+    }
+}
+
+impl fmt::Debug for Sha256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Sha256({:?})", self.backend())
+    }
+}
+
+impl BlockInput for Sha256 {
+    type BlockSize = U64;
+}
+
+impl Input for Sha256 {
+    fn input<B: AsRef<[u8]>>(&mut self, data: B) {
+        match self.0 {
+            Sha256Inner::Portable(ref mut hasher) => hasher.input(data),
+            Sha256Inner::Accelerated {
+                compress,
+                ref mut state,
+                ref mut buffer,
+                ref mut buffered,
+                ref mut length,
+                ..
+            } => {
+                let mut data = data.as_ref();
+                *length += data.len() as u64;
+                if *buffered > 0 {
+                    let n = (BLOCK_SIZE - *buffered).min(data.len());
+                    buffer[*buffered..*buffered + n].copy_from_slice(&data[..n]);
+                    *buffered += n;
+                    data = &data[n..];
+                    if *buffered < BLOCK_SIZE {
+                        return;
+                    }
+                    compress(state, &[*buffer]);
+                    *buffered = 0;
+                }
+                let blocks = data.len() / BLOCK_SIZE;
+                // [u8; 64] has the same alignment as u8
+                compress(state, unsafe {
+                    std::slice::from_raw_parts(data.as_ptr() as *const [u8; BLOCK_SIZE], blocks)
+                });
+                let rest = &data[blocks * BLOCK_SIZE..];
+                buffer[..rest.len()].copy_from_slice(rest);
+                *buffered = rest.len();
+            }
+        }
+    }
+}
+
+impl FixedOutput for Sha256 {
+    type OutputSize = U32;
+
+    fn fixed_result(self) -> GenericArray<u8, U32> {
+        match self.0 {
+            Sha256Inner::Portable(hasher) => hasher.fixed_result(),
+            Sha256Inner::Accelerated {
+                compress,
+                mut state,
+                mut buffer,
+                buffered,
+                length,
+                ..
+            } => {
+                // Append 0x80, pad with zeros and end with the length in bits
+                buffer[buffered] = 0x80;
+                for b in buffer[buffered + 1..].iter_mut() {
+                    *b = 0;
+                }
+                if buffered + 1 > BLOCK_SIZE - 8 {
+                    compress(&mut state, &[buffer]);
+                    buffer = [0u8; BLOCK_SIZE];
+                }
+                buffer[BLOCK_SIZE - 8..].copy_from_slice(&(length * 8).to_be_bytes());
+                compress(&mut state, &[buffer]);
+
+                let mut output = GenericArray::default();
+                for (chunk, word) in output.chunks_mut(4).zip(state.iter()) {
+                    chunk.copy_from_slice(&word.to_be_bytes());
+                }
+                output
+            }
+        }
+    }
+}
+
+impl Reset for Sha256 {
+    fn reset(&mut self) {
+        match self.0 {
+            Sha256Inner::Portable(ref mut hasher) => hasher.reset(),
+            Sha256Inner::Accelerated {
+                ref mut state,
+                ref mut buffered,
+                ref mut length,
+                ..
+            } => {
+                *state = H256;
+                *buffered = 0;
+                *length = 0;
+            }
+        }
+    }
+}
+
+/// BLAKE2b-512 with the SIMD implementation chosen by `blake2b_simd`
+#[cfg(feature = "blake2b_simd")]
+#[derive(Clone, Debug)]
+pub struct Blake2b(blake2b_simd::State);
+
+#[cfg(feature = "blake2b_simd")]
+impl Default for Blake2b {
+    fn default() -> Self {
+        Blake2b(blake2b_simd::State::new())
+    }
+}
+
+#[cfg(feature = "blake2b_simd")]
+impl BlockInput for Blake2b {
+    type BlockSize = U128;
+}
+
+#[cfg(feature = "blake2b_simd")]
+impl Input for Blake2b {
+    fn input<B: AsRef<[u8]>>(&mut self, data: B) {
+        self.0.update(data.as_ref());
+    }
+}
+
+#[cfg(feature = "blake2b_simd")]
+impl FixedOutput for Blake2b {
+    type OutputSize = U64;
+
+    fn fixed_result(self) -> GenericArray<u8, U64> {
+        GenericArray::clone_from_slice(self.0.finalize().as_bytes())
+    }
+}
+
+#[cfg(feature = "blake2b_simd")]
+impl Reset for Blake2b {
+    fn reset(&mut self) {
+        self.0 = blake2b_simd::State::new();
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86 {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    use super::{BLOCK_SIZE, K256};
+
+    /// Only called after `Backend::ShaNi.is_supported()`
+    pub fn compress(state: &mut [u32; 8], blocks: &[[u8; BLOCK_SIZE]]) {
+        unsafe { compress_sha_ni(state, blocks) }
+    }
+
+    unsafe fn schedule(v0: __m128i, v1: __m128i, v2: __m128i, v3: __m128i) -> __m128i {
+        let t1 = _mm_sha256msg1_epu32(v0, v1);
+        let t2 = _mm_alignr_epi8(v3, v2, 4);
+        _mm_sha256msg2_epu32(_mm_add_epi32(t1, t2), v3)
+    }
+
+    #[allow(clippy::cast_ptr_alignment)]
+    #[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+    unsafe fn compress_sha_ni(state: &mut [u32; 8], blocks: &[[u8; BLOCK_SIZE]]) {
+        let mask = _mm_set_epi64x(
+            0x0c0d_0e0f_0809_0a0bu64 as i64,
+            0x0405_0607_0001_0203u64 as i64,
+        );
+
+        // The rounds instructions take the state as ABEF and CDGH
+        let dcba = _mm_loadu_si128(state.as_ptr() as *const __m128i);
+        let hgfe = _mm_loadu_si128(state.as_ptr().add(4) as *const __m128i);
+        let cdab = _mm_shuffle_epi32(dcba, 0xb1);
+        let efgh = _mm_shuffle_epi32(hgfe, 0x1b);
+        let mut abef = _mm_alignr_epi8(cdab, efgh, 8);
+        let mut cdgh = _mm_blend_epi16(efgh, cdab, 0xf0);
+
+        for block in blocks {
+            let abef_save = abef;
+            let cdgh_save = cdgh;
+
+            let data = block.as_ptr() as *const __m128i;
+            let mut w = [
+                _mm_shuffle_epi8(_mm_loadu_si128(data), mask),
+                _mm_shuffle_epi8(_mm_loadu_si128(data.add(1)), mask),
+                _mm_shuffle_epi8(_mm_loadu_si128(data.add(2)), mask),
+                _mm_shuffle_epi8(_mm_loadu_si128(data.add(3)), mask),
+            ];
+            for i in 0..16 {
+                if i >= 4 {
+                    w[i % 4] = schedule(w[i % 4], w[(i + 1) % 4], w[(i + 2) % 4], w[(i + 3) % 4]);
+                }
+                let k = _mm_loadu_si128(K256.as_ptr().add(4 * i) as *const __m128i);
+                let wk = _mm_add_epi32(w[i % 4], k);
+                cdgh = _mm_sha256rnds2_epu32(cdgh, abef, wk);
+                abef = _mm_sha256rnds2_epu32(abef, cdgh, _mm_shuffle_epi32(wk, 0x0e));
+            }
+
+            abef = _mm_add_epi32(abef, abef_save);
+            cdgh = _mm_add_epi32(cdgh, cdgh_save);
+        }
+
+        let feba = _mm_shuffle_epi32(abef, 0x1b);
+        let dchg = _mm_shuffle_epi32(cdgh, 0xb1);
+        let dcba = _mm_blend_epi16(feba, dchg, 0xf0);
+        let hgef = _mm_alignr_epi8(dchg, feba, 8);
+        _mm_storeu_si128(state.as_mut_ptr() as *mut __m128i, dcba);
+        _mm_storeu_si128(state.as_mut_ptr().add(4) as *mut __m128i, hgef);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64 {
+    use std::arch::aarch64::*;
+
+    use super::{BLOCK_SIZE, K256};
+
+    /// Only called after `Backend::ArmSha2.is_supported()`
+    pub fn compress(state: &mut [u32; 8], blocks: &[[u8; BLOCK_SIZE]]) {
+        unsafe { compress_sha2(state, blocks) }
+    }
+
+    #[target_feature(enable = "sha2")]
+    unsafe fn compress_sha2(state: &mut [u32; 8], blocks: &[[u8; BLOCK_SIZE]]) {
+        let mut abcd = vld1q_u32(state.as_ptr());
+        let mut efgh = vld1q_u32(state.as_ptr().add(4));
+
+        for block in blocks {
+            let abcd_save = abcd;
+            let efgh_save = efgh;
+
+            let data = block.as_ptr();
+            let mut w = [
+                vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(data))),
+                vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(data.add(16)))),
+                vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(data.add(32)))),
+                vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(data.add(48)))),
+            ];
+            for i in 0..16 {
+                if i >= 4 {
+                    w[i % 4] = vsha256su1q_u32(
+                        vsha256su0q_u32(w[i % 4], w[(i + 1) % 4]),
+                        w[(i + 2) % 4],
+                        w[(i + 3) % 4],
+                    );
+                }
+                let wk = vaddq_u32(w[i % 4], vld1q_u32(K256.as_ptr().add(4 * i)));
+                let abcd_prev = abcd;
+                abcd = vsha256hq_u32(abcd, efgh, wk);
+                efgh = vsha256h2q_u32(efgh, abcd_prev, wk);
+            }
+
+            abcd = vaddq_u32(abcd, abcd_save);
+            efgh = vaddq_u32(efgh, efgh_save);
+        }
+
+        vst1q_u32(state.as_mut_ptr(), abcd);
+        vst1q_u32(state.as_mut_ptr().add(4), efgh);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Backend, Sha256};
+    use alloc::vec::Vec;
+    use sha2::Digest;
+
+    #[test]
+    fn sha256_backends() {
+        assert!(Backend::detect().is_supported());
+        assert_eq!(Backend::detect(), Sha256::default().backend());
+        assert_eq!(
+            Backend::Portable,
+            Sha256::with_backend(Backend::Portable).unwrap().backend()
+        );
+        let data = (0..1000u32).map(|i| i as u8).collect::<Vec<_>>();
+        for backend in &[Backend::Portable, Backend::ShaNi, Backend::ArmSha2] {
+            let hasher = match Sha256::with_backend(*backend) {
+                Some(h) => h,
+                None => continue,
+            };
+            // Every padding case and inputs split across calls
+            for len in (0..130).chain(vec![999, 1000]) {
+                let expected = sha2::Sha256::digest(&data[..len]);
+                let mut h = hasher.clone();
+                h.input(&data[..len / 3]);
+                h.input(&data[len / 3..len]);
+                assert_eq!(expected, h.result_reset());
+                h.input(&data[..len]);
+                assert_eq!(expected, h.result());
+            }
+        }
+    }
+
+    #[cfg(feature = "blake2")]
+    #[cfg(feature = "blake2b_simd")]
+    #[test]
+    fn blake2b() {
+        use super::Blake2b;
+
+        let data = (0..1000u32).map(|i| i as u8).collect::<Vec<_>>();
+        for &len in &[0, 1, 127, 128, 129, 1000] {
+            let mut h = Blake2b::new();
+            h.input(&data[..len / 2]);
+            h.input(&data[len / 2..len]);
+            // blake2 implements a newer version of Digest
+            let expected = <::blake2::Blake2b as ::blake2::Digest>::digest(&data[..len]);
+            assert_eq!(&expected[..], &h.result()[..]);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! BIP-32 derivation of secp256k1 private keys
+
+use super::{ChildNumber, DerivationPath, ExtendedPrivateKey};
+use keys::KeyGenOption;
+use signatures::secp256k1::EcdsaSecp256k1Sha256;
+use signatures::{EcdsaPublicKeyHandler, SignatureScheme};
+use CryptoError;
+
+/// The order of the secp256k1 group, big endian
+const CURVE_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// The master key for `seed`
+pub fn master_key(seed: &[u8]) -> Result<ExtendedPrivateKey, CryptoError> {
+    if !(16..=64).contains(&seed.len()) {
+        return Err(CryptoError::KeyGenError(format!(
+            "Invalid BIP-32 seed length {}",
+            seed.len()
+        )));
+    }
+    let key = ExtendedPrivateKey::from_hmac(b"Bitcoin seed", seed, 0, ChildNumber(0));
+    if !is_valid_scalar(key.secret.expose_secret()) {
+        return Err(CryptoError::KeyGenError(
+            "Seed produced an invalid master key".to_string(),
+        ));
+    }
+    Ok(key)
+}
+
+/// The child of `parent` at `child`
+pub fn child_key(
+    parent: &ExtendedPrivateKey,
+    child: ChildNumber,
+) -> Result<ExtendedPrivateKey, CryptoError> {
+    let mut data = Vec::with_capacity(37);
+    if child.is_hardened() {
+        data.push(0u8);
+        data.extend_from_slice(parent.secret());
+    } else {
+        let scheme = EcdsaSecp256k1Sha256::new();
+        let (pk, _) = scheme.keypair(Some(KeyGenOption::FromSecretKey(parent.private_key())))?;
+        data.extend_from_slice(&scheme.public_key_compressed(&pk));
+    }
+    data.extend_from_slice(&child.to_u32().to_be_bytes());
+
+    let mut key =
+        ExtendedPrivateKey::from_hmac(&parent.chain_code, &data, parent.next_depth()?, child);
+    if !is_valid_scalar(key.secret.expose_secret())
+        || !add_scalar(
+            key.secret.expose_secret_mut(),
+            parent.secret.expose_secret(),
+        )
+    {
+        return Err(CryptoError::KeyGenError(format!(
+            "Child {} is invalid",
+            child
+        )));
+    }
+    Ok(key)
+}
+
+/// The key at `path` from `seed`
+pub fn derive(seed: &[u8], path: &DerivationPath) -> Result<ExtendedPrivateKey, CryptoError> {
+    path.iter()
+        .try_fold(master_key(seed)?, |key, child| child_key(&key, *child))
+}
+
+/// 0 < `scalar` < n
+fn is_valid_scalar(scalar: &[u8; 32]) -> bool {
+    scalar.iter().any(|b| *b != 0) && scalar[..] < CURVE_ORDER[..]
+}
+
+/// Set `a` to `a + b mod n` for `a, b < n`. Returns false if the sum is zero.
+fn add_scalar(a: &mut [u8; 32], b: &[u8; 32]) -> bool {
+    let mut carry = 0u16;
+    for i in (0..32).rev() {
+        let sum = u16::from(a[i]) + u16::from(b[i]) + carry;
+        a[i] = sum as u8;
+        carry = sum >> 8;
+    }
+    if carry == 1 || a[..] >= CURVE_ORDER[..] {
+        let mut borrow = 0i16;
+        for i in (0..32).rev() {
+            let diff = i16::from(a[i]) - i16::from(CURVE_ORDER[i]) - borrow;
+            a[i] = diff as u8;
+            borrow = if diff < 0 { 1 } else { 0 };
+        }
+    }
+    a.iter().any(|b| *b != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bip32_vector_1() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        for (path, secret, chain_code) in &[
+            (
+                "m",
+                "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+                "873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508",
+            ),
+            (
+                "m/0'",
+                "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+                "47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141",
+            ),
+            (
+                "m/0'/1",
+                "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368",
+                "2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19",
+            ),
+            (
+                "m/0'/1/2'/2/1000000000",
+                "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8",
+                "c783e67b921d2beb8f6b389cc646d7263b4145701dadd2161548a8b078e65e9e",
+            ),
+        ] {
+            let key = derive(&seed, &path.parse().unwrap()).unwrap();
+            assert_eq!(*secret, hex::encode(key.secret()));
+            assert_eq!(*chain_code, hex::encode(&key.chain_code));
+        }
+        assert!(master_key(&[0u8; 8]).is_err());
+    }
+
+    #[test]
+    fn scalar_addition() {
+        let mut a = CURVE_ORDER;
+        a[31] -= 1;
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        let mut two = one;
+        two[31] = 2;
+        assert!(add_scalar(&mut a, &two));
+        assert_eq!(one, a);
+        let mut a = CURVE_ORDER;
+        a[31] -= 1;
+        assert!(!add_scalar(&mut a, &one));
+        assert!(!is_valid_scalar(&CURVE_ORDER));
+        assert!(!is_valid_scalar(&[0u8; 32]));
+    }
+}
@@ -0,0 +1,286 @@
+//! BIP-39 mnemonic phrases with the English word list.
+//!
+//! Phrases and passphrases are not NFKD normalized, so only ASCII passphrases
+//! are accepted. The English words are all ASCII.
+
+use super::HmacSha512;
+use hmac::Mac;
+use keys::Secret;
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
+use CryptoError;
+
+const WORDS: &str = include_str!("english.txt");
+const PBKDF2_ROUNDS: usize = 2048;
+pub const SEED_SIZE: usize = 64;
+
+lazy_static! {
+    static ref WORDLIST: Vec<&'static str> = WORDS.lines().collect();
+}
+
+/// A mnemonic phrase and the entropy it encodes
+pub struct Mnemonic {
+    phrase: Secret<String>,
+    entropy: Secret<Vec<u8>>,
+}
+
+impl Mnemonic {
+    /// Create a random mnemonic with 12, 15, 18, 21 or 24 words
+    pub fn generate(word_count: usize) -> Result<Self, CryptoError> {
+        Self::generate_with_rng(word_count, &mut OsRng)
+    }
+
+    /// Create a mnemonic from entropy drawn from `rng`
+    pub fn generate_with_rng<R: CryptoRng + RngCore>(
+        word_count: usize,
+        rng: &mut R,
+    ) -> Result<Self, CryptoError> {
+        if !(12..=24).contains(&word_count) || word_count % 3 != 0 {
+            return Err(CryptoError::KeyGenError(format!(
+                "Invalid mnemonic length {}",
+                word_count
+            )));
+        }
+        let mut entropy = vec![0u8; word_count / 3 * 4];
+        rng.fill_bytes(&mut entropy);
+        let mnemonic = Self::from_entropy(&entropy);
+        entropy.zeroize();
+        mnemonic
+    }
+
+    /// Encode 16, 20, 24, 28 or 32 bytes of entropy
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, CryptoError> {
+        if !(16..=32).contains(&entropy.len()) || entropy.len() % 4 != 0 {
+            return Err(CryptoError::KeyGenError(format!(
+                "Invalid mnemonic entropy length {}",
+                entropy.len()
+            )));
+        }
+        let checksum_bits = entropy.len() / 4;
+        let mut bits = entropy.to_vec();
+        bits.push(Sha256::digest(entropy)[0]);
+        let words = (entropy.len() * 8 + checksum_bits) / 11;
+        let phrase = (0..words)
+            .map(|w| WORDLIST[read_bits(&bits, w * 11)])
+            .collect::<Vec<&str>>()
+            .join(" ");
+        bits.zeroize();
+        Ok(Mnemonic {
+            phrase: Secret::new(phrase),
+            entropy: Secret::new(entropy.to_vec()),
+        })
+    }
+
+    /// Parse and check a phrase. Words may be separated by any whitespace.
+    pub fn from_phrase(phrase: &str) -> Result<Self, CryptoError> {
+        let words = phrase.split_whitespace().collect::<Vec<&str>>();
+        if !(12..=24).contains(&words.len()) || words.len() % 3 != 0 {
+            return Err(CryptoError::ParseError(format!(
+                "Invalid mnemonic length {}",
+                words.len()
+            )));
+        }
+        let mut bits = vec![0u8; (words.len() * 11 + 7) / 8];
+        for (w, word) in words.iter().enumerate() {
+            let index = WORDLIST
+                .binary_search(word)
+                .map_err(|_| CryptoError::ParseError(format!("Unknown mnemonic word {}", word)))?;
+            write_bits(&mut bits, w * 11, index);
+        }
+        let checksum_bits = words.len() / 3;
+        let entropy_len = (words.len() * 11 - checksum_bits) / 8;
+        let checksum = bits[entropy_len] >> (8 - checksum_bits);
+        let expected = Sha256::digest(&bits[..entropy_len])[0] >> (8 - checksum_bits);
+        let entropy = bits[..entropy_len].to_vec();
+        bits.zeroize();
+        if !bool::from(checksum.ct_eq(&expected)) {
+            return Err(CryptoError::ParseError(
+                "Invalid mnemonic checksum".to_string(),
+            ));
+        }
+        Ok(Mnemonic {
+            phrase: Secret::new(words.join(" ")),
+            entropy: Secret::new(entropy),
+        })
+    }
+
+    pub fn phrase(&self) -> &str {
+        self.phrase.expose_secret()
+    }
+
+    pub fn entropy(&self) -> &[u8] {
+        self.entropy.expose_secret()
+    }
+
+    /// PBKDF2-HMAC-SHA512 of the phrase salted with `passphrase`
+    pub fn to_seed(&self, passphrase: &str) -> Result<Seed, CryptoError> {
+        if !passphrase.is_ascii() {
+            return Err(CryptoError::KeyGenError(
+                "Mnemonic passphrases must be ASCII".to_string(),
+            ));
+        }
+        let mut salt = format!("mnemonic{}", passphrase).into_bytes();
+        salt.extend_from_slice(&[0, 0, 0, 1]);
+        let password = self.phrase().as_bytes();
+
+        let mut u = hmac_sha512(password, &salt);
+        salt.zeroize();
+        let mut seed = Seed(Secret::new([0u8; SEED_SIZE]));
+        seed.0.expose_secret_mut().copy_from_slice(&u);
+        for _ in 1..PBKDF2_ROUNDS {
+            let next = hmac_sha512(password, &u);
+            u.zeroize();
+            u = next;
+            for (s, b) in seed.0.expose_secret_mut().iter_mut().zip(u.iter()) {
+                *s ^= b;
+            }
+        }
+        u.zeroize();
+        Ok(seed)
+    }
+}
+
+impl fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Mnemonic({} words)", self.phrase().split(' ').count())
+    }
+}
+
+/// The 64 byte seed derived from a mnemonic
+pub struct Seed(Secret<[u8; SEED_SIZE]>);
+
+impl AsRef<[u8]> for Seed {
+    fn as_ref(&self) -> &[u8] {
+        &self.0.expose_secret()[..]
+    }
+}
+
+impl ConstantTimeEq for Seed {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for Seed {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for Seed {}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut hmac = HmacSha512::new_varkey(key).expect("HMAC accepts any key length");
+    hmac.input(data);
+    hmac.result().code().to_vec()
+}
+
+/// Read the 11 bits starting at bit `offset`
+fn read_bits(data: &[u8], offset: usize) -> usize {
+    (0..11).fold(0, |acc, i| {
+        let bit = offset + i;
+        acc << 1 | usize::from(data[bit / 8] >> (7 - bit % 8) & 1)
+    })
+}
+
+/// Write the low 11 bits of `value` starting at bit `offset`
+fn write_bits(data: &mut [u8], offset: usize, value: usize) {
+    for i in 0..11 {
+        if value >> (10 - i) & 1 == 1 {
+            let bit = offset + i;
+            data[bit / 8] |= 1 << (7 - bit % 8);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wordlist() {
+        assert_eq!(2048, WORDLIST.len());
+        assert!(WORDLIST.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!("abandon", WORDLIST[0]);
+        assert_eq!("zoo", WORDLIST[2047]);
+    }
+
+    #[test]
+    fn trezor_vectors() {
+        for (entropy, phrase, seed) in &[
+            (
+                "00000000000000000000000000000000",
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+            ),
+            (
+                "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+                "legal winner thank year wave sausage worth useful legal winner thank yellow",
+                "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+            ),
+            (
+                "6610b25967cdcca9d59875f5cb50b0ea75433311869e930b",
+                "gravity machine north sort system female filter attitude volume fold club stay feature office ecology stable narrow fog",
+                "628c3827a8823298ee685db84f55caa34b5cc195a778e52d45f59bcf75aba68e4d7590e101dc414bc1bbd5737666fbbef35d1f1903953b66624f910feef245ac",
+            ),
+            (
+                "f585c11aec520db57dd353c69554b21a89b20fb0650966fa0a9d6f74fd989d8f",
+                "void come effort suffer camp survey warrior heavy shoot primary clutch crush open amazing screen patrol group space point ten exist slush involve unfold",
+                "01f5bced59dec48e362f2c45b5de68b9fd6c92c6634f44d6d40aab69056506f0e35524a518034ddc1192e1dacd32c1ed3eaa3c3b131c88ed8e7e54c49a5d0998",
+            ),
+        ] {
+            let entropy = hex::decode(entropy).unwrap();
+            let mnemonic = Mnemonic::from_entropy(&entropy).unwrap();
+            assert_eq!(*phrase, mnemonic.phrase());
+            let parsed = Mnemonic::from_phrase(&format!("  {}\n", phrase)).unwrap();
+            assert_eq!(&entropy[..], parsed.entropy());
+            assert_eq!(
+                *seed,
+                hex::encode(parsed.to_seed("TREZOR").unwrap().as_ref())
+            );
+            assert!(parsed.to_seed("TREZOR").unwrap() == mnemonic.to_seed("TREZOR").unwrap());
+            assert!(parsed.to_seed("").unwrap() != mnemonic.to_seed("TREZOR").unwrap());
+        }
+    }
+
+    #[test]
+    fn invalid_phrases() {
+        // Bad checksum
+        assert!(Mnemonic::from_phrase(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon"
+        )
+        .is_err());
+        assert!(Mnemonic::from_phrase("abandon about").is_err());
+        assert!(Mnemonic::from_phrase(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abou"
+        )
+        .is_err());
+        assert!(Mnemonic::from_entropy(&[0u8; 15]).is_err());
+        assert!(Mnemonic::generate(13).is_err());
+        let mnemonic = Mnemonic::generate(24).unwrap();
+        assert_eq!(32, mnemonic.entropy().len());
+        assert!(mnemonic.to_seed("pässword").is_err());
+        assert_eq!("Mnemonic(24 words)", format!("{:?}", mnemonic));
+        assert_eq!(
+            mnemonic.entropy(),
+            Mnemonic::from_phrase(mnemonic.phrase()).unwrap().entropy()
+        );
+    }
+
+    #[test]
+    fn generate_with_rng() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mnemonic = Mnemonic::generate_with_rng(12, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(16, mnemonic.entropy().len());
+        assert_eq!(
+            mnemonic.phrase(),
+            Mnemonic::generate_with_rng(12, &mut StdRng::seed_from_u64(1))
+                .unwrap()
+                .phrase()
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! EIP-2333 derivation of BLS12-381 private keys.
+//!
+//! Keys are 32 byte big endian scalars. EIP-2333 has no hardened children,
+//! every index is already hardened by the Lamport step.
+
+use super::DerivationPath;
+use hkdf::Hkdf;
+use keys::PrivateKey;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+use CryptoError;
+
+/// The order of the BLS12-381 groups, big endian
+const CURVE_ORDER: [u8; 32] = [
+    0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8, 0x05,
+    0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+];
+const LAMPORT_CHUNKS: usize = 255;
+
+/// The master key for `seed`, which must be at least 32 bytes
+pub fn master_key(seed: &[u8]) -> Result<PrivateKey, CryptoError> {
+    if seed.len() < 32 {
+        return Err(CryptoError::KeyGenError(format!(
+            "Invalid EIP-2333 seed length {}",
+            seed.len()
+        )));
+    }
+    Ok(hkdf_mod_r(seed))
+}
+
+/// The child of `parent` at `index`
+pub fn child_key(parent: &PrivateKey, index: u32) -> Result<PrivateKey, CryptoError> {
+    if parent.0.len() != 32 {
+        return Err(CryptoError::ParseError(
+            "EIP-2333 keys are 32 bytes".to_string(),
+        ));
+    }
+    let salt = index.to_be_bytes();
+    let mut not_parent = parent.0.iter().map(|b| !b).collect::<Vec<u8>>();
+    let mut hasher = Sha256::new();
+    for ikm in &[&parent.0[..], &not_parent[..]] {
+        let mut lamport = vec![0u8; 32 * LAMPORT_CHUNKS];
+        Hkdf::<Sha256>::new(Some(&salt[..]), ikm)
+            .expand(&[], &mut lamport)
+            .map_err(|e| CryptoError::KeyGenError(format!("{:?}", e)))?;
+        for chunk in lamport.chunks(32) {
+            hasher.input(Sha256::digest(chunk));
+        }
+        lamport.zeroize();
+    }
+    not_parent.zeroize();
+    Ok(hkdf_mod_r(&hasher.result()))
+}
+
+/// The key at `path` from `seed`, for example `m/12381/3600/0/0/0`
+pub fn derive(seed: &[u8], path: &DerivationPath) -> Result<PrivateKey, CryptoError> {
+    path.iter().try_fold(master_key(seed)?, |key, child| {
+        if child.is_hardened() {
+            return Err(CryptoError::KeyGenError(format!(
+                "EIP-2333 child {} must not be hardened",
+                child
+            )));
+        }
+        child_key(&key, child.index())
+    })
+}
+
+fn hkdf_mod_r(ikm: &[u8]) -> PrivateKey {
+    let mut salt = Sha256::digest(b"BLS-SIG-KEYGEN-SALT-");
+    let mut input = ikm.to_vec();
+    input.push(0u8);
+    loop {
+        let mut okm = [0u8; 48];
+        Hkdf::<Sha256>::new(Some(&salt[..]), &input)
+            .expand(&[0u8, 48], &mut okm)
+            .expect("48 bytes is a valid HKDF output length");
+        let sk = reduce(&okm);
+        okm.zeroize();
+        if sk.iter().any(|b| *b != 0) {
+            input.zeroize();
+            return PrivateKey(sk.to_vec());
+        }
+        salt = Sha256::digest(&salt);
+    }
+}
+
+/// `data mod r`, one bit at a time
+fn reduce(data: &[u8]) -> [u8; 32] {
+    let mut r = [0u8; 32];
+    for byte in data {
+        for bit in (0..8).rev() {
+            // r < 2^255 so doubling cannot overflow 256 bits
+            let mut carry = byte >> bit & 1;
+            for limb in r.iter_mut().rev() {
+                let next = *limb >> 7;
+                *limb = *limb << 1 | carry;
+                carry = next;
+            }
+            if r[..] >= CURVE_ORDER[..] {
+                let mut borrow = 0i16;
+                for i in (0..32).rev() {
+                    let diff = i16::from(r[i]) - i16::from(CURVE_ORDER[i]) - borrow;
+                    r[i] = diff as u8;
+                    borrow = if diff < 0 { 1 } else { 0 };
+                }
+            }
+        }
+    }
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eip2333_vectors() {
+        for (seed, master, index, child) in &[
+            (
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+                "0d7359d57963ab8fbbde1852dcf553fedbc31f464d80ee7d40ae683122b45070",
+                0u32,
+                "2d18bd6c14e6d15bf8b5085c9b74f3daae3b03cc2014770a599d8c1539e50f8e",
+            ),
+            (
+                "3141592653589793238462643383279502884197169399375105820974944592",
+                "41c9e07822b092a93fd6797396338c3ada4170cc81829fdfce6b5d34bd5e7ec7",
+                3141592653u32,
+                "384843fad5f3d777ea39de3e47a8f999ae91f89e42bffa993d91d9782d152a0f",
+            ),
+        ] {
+            let seed = hex::decode(seed).unwrap();
+            let sk = master_key(&seed).unwrap();
+            assert_eq!(*master, hex::encode(&sk));
+            assert_eq!(*child, hex::encode(child_key(&sk, *index).unwrap()));
+        }
+        assert!(master_key(&[0u8; 31]).is_err());
+        let seed = [1u8; 32];
+        assert!(derive(&seed, &"m/12381/3600/0/0'".parse().unwrap()).is_err());
+        assert_eq!(
+            child_key(
+                &child_key(&master_key(&seed).unwrap(), 12381).unwrap(),
+                3600
+            )
+            .unwrap(),
+            derive(&seed, &"m/12381/3600".parse().unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn reduce_mod_r() {
+        assert_eq!([0u8; 32], reduce(&CURVE_ORDER));
+        let mut r_plus_one = CURVE_ORDER;
+        r_plus_one[31] += 1;
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        assert_eq!(one, reduce(&r_plus_one));
+        assert_eq!(one, reduce(&one));
+    }
+}
@@ -0,0 +1,347 @@
+//! Hierarchical deterministic keys.
+//!
+//! A BIP-39 [`bip39::Mnemonic`] produces a seed, and the seed is expanded
+//! along a [`DerivationPath`] with the scheme used for each curve:
+//!
+//! * secp256k1 uses BIP-32
+//! * ed25519 and x25519 use SLIP-0010, which only supports hardened children
+//! * BLS12-381 uses EIP-2333, which has no hardened children
+//!
+//! ```
+//! # extern crate ursa;
+//! use ursa::hd::{bip39::Mnemonic, derive_keypair, DerivationPath};
+//! use ursa::signatures::{ed25519::Ed25519Sha512, SignatureScheme};
+//!
+//! let mnemonic = Mnemonic::generate(24).unwrap();
+//! let seed = mnemonic.to_seed("").unwrap();
+//! let path: DerivationPath = "m/44'/501'/0'/0'".parse().unwrap();
+//! let scheme = Ed25519Sha512::new();
+//! let (pk, sk) = derive_keypair(&scheme, seed.as_ref(), &path).unwrap();
+//! let signature = scheme.sign(b"message", &sk).unwrap();
+//! assert!(scheme.verify(b"message", &signature, &pk).unwrap());
+//! ```
+
+#[cfg(any(
+    feature = "ecdsa_secp256k1",
+    feature = "ecdsa_secp256k1_native",
+    feature = "ecdsa_secp256k1_asm"
+))]
+pub mod bip32;
+pub mod bip39;
+pub mod eip2333;
+pub mod slip10;
+
+use encoding::pkcs8::{KeyAlgorithm, Pkcs8KeyHandler};
+use hmac::{Hmac, Mac};
+use keys::{PrivateKey, PublicKey, Secret};
+use sha2::Sha512;
+use std::fmt;
+use std::str::FromStr;
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
+use CryptoError;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// One step of a derivation path
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ChildNumber(u32);
+
+impl ChildNumber {
+    pub const HARDENED: u32 = 0x8000_0000;
+
+    pub fn normal(index: u32) -> Result<Self, CryptoError> {
+        if index & Self::HARDENED != 0 {
+            return Err(CryptoError::ParseError(format!(
+                "Child index {} is out of range",
+                index
+            )));
+        }
+        Ok(ChildNumber(index))
+    }
+
+    pub fn hardened(index: u32) -> Result<Self, CryptoError> {
+        Self::normal(index).map(|c| ChildNumber(c.0 | Self::HARDENED))
+    }
+
+    pub fn is_hardened(self) -> bool {
+        self.0 & Self::HARDENED != 0
+    }
+
+    /// The index without the hardened bit
+    pub fn index(self) -> u32 {
+        self.0 & !Self::HARDENED
+    }
+
+    /// The index as serialized by BIP-32
+    pub fn to_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for ChildNumber {
+    fn from(value: u32) -> Self {
+        ChildNumber(value)
+    }
+}
+
+impl fmt::Display for ChildNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.index())?;
+        if self.is_hardened() {
+            write!(f, "'")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ChildNumber {
+    type Err = CryptoError;
+
+    /// Hardened children are marked with a trailing `'`, `h` or `H`
+    fn from_str(s: &str) -> Result<Self, CryptoError> {
+        let (index, hardened) = match s.chars().last() {
+            Some('\'') | Some('h') | Some('H') => (&s[..s.len() - 1], true),
+            _ => (s, false),
+        };
+        if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(CryptoError::ParseError(format!(
+                "Invalid child number {}",
+                s
+            )));
+        }
+        let index = index
+            .parse::<u32>()
+            .map_err(|_| CryptoError::ParseError(format!("Invalid child number {}", s)))?;
+        if hardened {
+            Self::hardened(index)
+        } else {
+            Self::normal(index)
+        }
+    }
+}
+
+/// A path such as `m/44'/0'/0'/0/0`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct DerivationPath(Vec<ChildNumber>);
+
+impl DerivationPath {
+    /// The path `m`
+    pub fn master() -> Self {
+        Self::default()
+    }
+
+    pub fn child(&self, child: ChildNumber) -> Self {
+        let mut path = self.clone();
+        path.0.push(child);
+        path
+    }
+
+    pub fn iter(&self) -> ::std::slice::Iter<'_, ChildNumber> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<ChildNumber>> for DerivationPath {
+    fn from(children: Vec<ChildNumber>) -> Self {
+        DerivationPath(children)
+    }
+}
+
+impl AsRef<[ChildNumber]> for DerivationPath {
+    fn as_ref(&self) -> &[ChildNumber] {
+        &self.0
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "m")?;
+        for child in &self.0 {
+            write!(f, "/{}", child)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, CryptoError> {
+        let mut parts = s.split('/');
+        if parts.next() != Some("m") {
+            return Err(CryptoError::ParseError(format!(
+                "Derivation path {} must start with m",
+                s
+            )));
+        }
+        parts
+            .map(ChildNumber::from_str)
+            .collect::<Result<Vec<ChildNumber>, CryptoError>>()
+            .map(DerivationPath)
+    }
+}
+
+/// A private key and chain code, as used by BIP-32 and SLIP-0010
+#[derive(Clone)]
+pub struct ExtendedPrivateKey {
+    pub depth: u8,
+    pub child_number: ChildNumber,
+    pub chain_code: [u8; 32],
+    secret: Secret<[u8; 32]>,
+}
+
+impl ExtendedPrivateKey {
+    /// Split `HMAC-SHA512(key, data)` into a secret and chain code
+    fn from_hmac(
+        key: &[u8],
+        data: &[u8],
+        depth: u8,
+        child_number: ChildNumber,
+    ) -> ExtendedPrivateKey {
+        let mut hmac = HmacSha512::new_varkey(key).expect("HMAC accepts any key length");
+        hmac.input(data);
+        let mut i = hmac.result().code();
+        let mut key = ExtendedPrivateKey {
+            depth,
+            child_number,
+            chain_code: [0u8; 32],
+            secret: Secret::new([0u8; 32]),
+        };
+        key.secret.expose_secret_mut().copy_from_slice(&i[..32]);
+        key.chain_code.copy_from_slice(&i[32..]);
+        i.as_mut_slice().zeroize();
+        key
+    }
+
+    /// The 32 byte private scalar or seed
+    pub fn secret(&self) -> &[u8] {
+        &self.secret.expose_secret()[..]
+    }
+
+    pub fn private_key(&self) -> PrivateKey {
+        PrivateKey(self.secret().to_vec())
+    }
+
+    fn next_depth(&self) -> Result<u8, CryptoError> {
+        self.depth
+            .checked_add(1)
+            .ok_or_else(|| CryptoError::KeyGenError("Maximum derivation depth".to_string()))
+    }
+}
+
+impl fmt::Debug for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExtendedPrivateKey")
+            .field("depth", &self.depth)
+            .field("child_number", &self.child_number)
+            .finish()
+    }
+}
+
+impl ConstantTimeEq for ExtendedPrivateKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.depth.ct_eq(&other.depth)
+            & self.child_number.0.ct_eq(&other.child_number.0)
+            & self.chain_code.ct_eq(&other.chain_code)
+            & self.secret.ct_eq(&other.secret)
+    }
+}
+
+impl PartialEq for ExtendedPrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for ExtendedPrivateKey {}
+
+impl Drop for ExtendedPrivateKey {
+    fn drop(&mut self) {
+        self.chain_code.zeroize();
+    }
+}
+
+/// Derive the key for `path` from `seed` with the scheme matching
+/// the key type of `handler`
+pub fn derive_keypair<H: Pkcs8KeyHandler>(
+    handler: &H,
+    seed: &[u8],
+    path: &DerivationPath,
+) -> Result<(PublicKey, PrivateKey), CryptoError> {
+    let key = match handler.key_algorithm() {
+        KeyAlgorithm::Ed25519 => slip10::derive(slip10::Curve::Ed25519, seed, path)?,
+        KeyAlgorithm::X25519 => slip10::derive(slip10::Curve::Curve25519, seed, path)?,
+        #[cfg(any(
+            feature = "ecdsa_secp256k1",
+            feature = "ecdsa_secp256k1_native",
+            feature = "ecdsa_secp256k1_asm"
+        ))]
+        KeyAlgorithm::EcSecp256k1 => bip32::derive(seed, path)?,
+        #[cfg(not(any(
+            feature = "ecdsa_secp256k1",
+            feature = "ecdsa_secp256k1_native",
+            feature = "ecdsa_secp256k1_asm"
+        )))]
+        KeyAlgorithm::EcSecp256k1 => {
+            return Err(CryptoError::NoSuchAlgorithm(
+                "BIP-32 derivation requires an ecdsa_secp256k1 feature".to_string(),
+            ))
+        }
+    };
+    handler.keypair_from_raw(key.secret())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derivation_path_parse() {
+        let path: DerivationPath = "m/44'/60h/0H/0/7".parse().unwrap();
+        assert_eq!(
+            vec![
+                ChildNumber::hardened(44).unwrap(),
+                ChildNumber::hardened(60).unwrap(),
+                ChildNumber::hardened(0).unwrap(),
+                ChildNumber::normal(0).unwrap(),
+                ChildNumber::normal(7).unwrap(),
+            ],
+            path.as_ref().to_vec()
+        );
+        assert_eq!("m/44'/60'/0'/0/7", path.to_string());
+        assert_eq!(0x8000_002c, path.as_ref()[0].to_u32());
+        assert_eq!(DerivationPath::master(), "m".parse().unwrap());
+        assert_eq!(
+            "m/12381/3600/0/0/0",
+            "m/12381/3600/0/0/0"
+                .parse::<DerivationPath>()
+                .unwrap()
+                .to_string()
+        );
+
+        for bad in &[
+            "",
+            "44'/0'",
+            "m/",
+            "m//1",
+            "m/a",
+            "m/-1",
+            "m/+1",
+            "m/1''",
+            "m/2147483648",
+            "m/2147483648'",
+            "M/0",
+        ] {
+            assert!(bad.parse::<DerivationPath>().is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! SLIP-0010 derivation of ed25519 and x25519 private keys.
+//!
+//! Only hardened children exist for these curves.
+
+use super::{ChildNumber, DerivationPath, ExtendedPrivateKey};
+use zeroize::Zeroize;
+use CryptoError;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Curve {
+    Ed25519,
+    /// X25519 keys
+    Curve25519,
+}
+
+impl Curve {
+    fn hmac_key(self) -> &'static [u8] {
+        match self {
+            Curve::Ed25519 => b"ed25519 seed",
+            Curve::Curve25519 => b"curve25519 seed",
+        }
+    }
+}
+
+/// The master key for `seed`
+pub fn master_key(curve: Curve, seed: &[u8]) -> Result<ExtendedPrivateKey, CryptoError> {
+    if !(16..=64).contains(&seed.len()) {
+        return Err(CryptoError::KeyGenError(format!(
+            "Invalid SLIP-0010 seed length {}",
+            seed.len()
+        )));
+    }
+    Ok(ExtendedPrivateKey::from_hmac(
+        curve.hmac_key(),
+        seed,
+        0,
+        ChildNumber(0),
+    ))
+}
+
+/// The child of `parent` at `child`, which must be hardened
+pub fn child_key(
+    parent: &ExtendedPrivateKey,
+    child: ChildNumber,
+) -> Result<ExtendedPrivateKey, CryptoError> {
+    if !child.is_hardened() {
+        return Err(CryptoError::KeyGenError(format!(
+            "SLIP-0010 child {} must be hardened",
+            child
+        )));
+    }
+    let mut data = Vec::with_capacity(37);
+    data.push(0u8);
+    data.extend_from_slice(parent.secret());
+    data.extend_from_slice(&child.to_u32().to_be_bytes());
+    let key = ExtendedPrivateKey::from_hmac(&parent.chain_code, &data, parent.next_depth()?, child);
+    data.zeroize();
+    Ok(key)
+}
+
+/// The key at `path` from `seed`
+pub fn derive(
+    curve: Curve,
+    seed: &[u8],
+    path: &DerivationPath,
+) -> Result<ExtendedPrivateKey, CryptoError> {
+    path.iter()
+        .try_fold(master_key(curve, seed)?, |key, child| {
+            child_key(&key, *child)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slip10_ed25519_vector_1() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        for (path, secret, chain_code) in &[
+            (
+                "m",
+                "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+                "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb",
+            ),
+            (
+                "m/0'",
+                "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+                "8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69",
+            ),
+            (
+                "m/0'/1'/2'",
+                "92a5b23c0b8a99e37d07df3fb9966917f5d06e02ddbd909c7e184371463e9fc9",
+                "2e69929e00b5ab250f49c3fb1c12f252de4fed2c1db88387094a0f8c4c9ccd6c",
+            ),
+        ] {
+            let key = derive(Curve::Ed25519, &seed, &path.parse().unwrap()).unwrap();
+            assert_eq!(*secret, hex::encode(key.secret()));
+            assert_eq!(*chain_code, hex::encode(&key.chain_code));
+        }
+        assert!(derive(Curve::Ed25519, &seed, &"m/0".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn slip10_curve25519() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let key = derive(Curve::Curve25519, &seed, &"m/0'".parse().unwrap()).unwrap();
+        assert_eq!(
+            "cd7630d7513cbe80515f7317cdb9a47ad4a56b63c3f1dc29583ab8d4cc25a9b2",
+            hex::encode(key.secret())
+        );
+        assert_eq!(1, key.depth);
+    }
+}
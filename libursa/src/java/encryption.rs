@@ -0,0 +1,89 @@
+use super::{bytes_from_java, bytes_to_java, jni_error, throw_on_err};
+use encryption::random_vec;
+use encryption::symm::EncryptorType;
+use jni::objects::{JClass, JString};
+use jni::sys::jbyteArray;
+use jni::JNIEnv;
+use std::ptr;
+use CryptoError;
+
+/// A random key for `cipher`, one of the names of `EncryptorType`
+#[no_mangle]
+pub extern "system" fn Java_org_hyperledger_ursa_UrsaNative_generateKey(
+    env: JNIEnv,
+    _class: JClass,
+    cipher: JString,
+) -> jbyteArray {
+    throw_on_err(&env, ptr::null_mut(), || {
+        let cipher = cipher_from_java(&env, cipher)?;
+        let key = random_vec(cipher.keysize())
+            .map_err(|_| CryptoError::KeyGenError("Unable to generate key".to_string()))?;
+        bytes_to_java(&env, &key)
+    })
+}
+
+/// Encrypt `plaintext` under a random nonce, which is prepended to the result
+#[no_mangle]
+pub extern "system" fn Java_org_hyperledger_ursa_UrsaNative_encrypt(
+    env: JNIEnv,
+    _class: JClass,
+    cipher: JString,
+    key: jbyteArray,
+    aad: jbyteArray,
+    plaintext: jbyteArray,
+) -> jbyteArray {
+    throw_on_err(&env, ptr::null_mut(), || {
+        let cipher = cipher_from_java(&env, cipher)?;
+        let key = key_from_java(&env, cipher, key)?;
+        let aad = bytes_from_java(&env, aad)?;
+        let plaintext = bytes_from_java(&env, plaintext)?;
+        let ciphertext = cipher
+            .gen_encryptor(&key)
+            .encrypt_easy(&aad, &plaintext)
+            .map_err(|_| CryptoError::GeneralError("Encryption failed".to_string()))?;
+        bytes_to_java(&env, &ciphertext)
+    })
+}
+
+/// Decrypt the output of `encrypt`
+#[no_mangle]
+pub extern "system" fn Java_org_hyperledger_ursa_UrsaNative_decrypt(
+    env: JNIEnv,
+    _class: JClass,
+    cipher: JString,
+    key: jbyteArray,
+    aad: jbyteArray,
+    ciphertext: jbyteArray,
+) -> jbyteArray {
+    throw_on_err(&env, ptr::null_mut(), || {
+        let cipher = cipher_from_java(&env, cipher)?;
+        let key = key_from_java(&env, cipher, key)?;
+        let aad = bytes_from_java(&env, aad)?;
+        let ciphertext = bytes_from_java(&env, ciphertext)?;
+        let plaintext = cipher
+            .gen_encryptor(&key)
+            .decrypt_easy(&aad, &ciphertext)
+            .map_err(|_| CryptoError::GeneralError("Decryption failed".to_string()))?;
+        bytes_to_java(&env, &plaintext)
+    })
+}
+
+fn cipher_from_java(env: &JNIEnv, cipher: JString) -> Result<EncryptorType, CryptoError> {
+    let name: String = env.get_string(cipher).map_err(jni_error)?.into();
+    name.parse().map_err(CryptoError::NoSuchAlgorithm)
+}
+
+fn key_from_java(
+    env: &JNIEnv,
+    cipher: EncryptorType,
+    key: jbyteArray,
+) -> Result<Vec<u8>, CryptoError> {
+    let key = bytes_from_java(env, key)?;
+    if !cipher.is_valid_keysize(key.len()) {
+        return Err(CryptoError::ParseError(format!(
+            "Invalid key length, expected {} bytes",
+            cipher.keysize()
+        )));
+    }
+    Ok(key)
+}
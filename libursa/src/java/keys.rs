@@ -0,0 +1,119 @@
+use super::{byte_arrays_to_java, bytes_from_java, bytes_to_java, jni_error, throw_on_err};
+use jni::objects::{JClass, JString};
+use jni::sys::{jboolean, jbyteArray, jobjectArray, JNI_FALSE, JNI_TRUE};
+use jni::JNIEnv;
+use keys::{KeyGenOption, PrivateKey, PublicKey};
+use registry::{Algorithm, AnyPublicKey};
+use std::ptr;
+use CryptoError;
+
+/// Generate a keypair of `algorithm`, from `seed` unless it's null.
+/// Returns `{ publicKey, privateKey }`.
+#[no_mangle]
+pub extern "system" fn Java_org_hyperledger_ursa_UrsaNative_generateKeyPair(
+    env: JNIEnv,
+    _class: JClass,
+    algorithm: JString,
+    seed: jbyteArray,
+) -> jobjectArray {
+    throw_on_err(&env, ptr::null_mut(), || {
+        let algorithm = algorithm_from_java(&env, algorithm)?;
+        let option = if seed.is_null() {
+            None
+        } else {
+            Some(KeyGenOption::UseSeed(bytes_from_java(&env, seed)?))
+        };
+        let keys = algorithm.keypair(option)?;
+        byte_arrays_to_java(
+            &env,
+            &[&keys.keys().public_key[..], &keys.private_key()[..]],
+        )
+    })
+}
+
+/// The public key of `privateKey`
+#[no_mangle]
+pub extern "system" fn Java_org_hyperledger_ursa_UrsaNative_publicKey(
+    env: JNIEnv,
+    _class: JClass,
+    algorithm: JString,
+    private_key: jbyteArray,
+) -> jbyteArray {
+    throw_on_err(&env, ptr::null_mut(), || {
+        let algorithm = algorithm_from_java(&env, algorithm)?;
+        let sk = PrivateKey(bytes_from_java(&env, private_key)?);
+        let keys = algorithm.keypair(Some(KeyGenOption::FromSecretKey(sk)))?;
+        bytes_to_java(&env, &keys.keys().public_key[..])
+    })
+}
+
+/// Sign `message` with `privateKey`
+#[no_mangle]
+pub extern "system" fn Java_org_hyperledger_ursa_UrsaNative_sign(
+    env: JNIEnv,
+    _class: JClass,
+    algorithm: JString,
+    private_key: jbyteArray,
+    message: jbyteArray,
+) -> jbyteArray {
+    throw_on_err(&env, ptr::null_mut(), || {
+        let algorithm = algorithm_from_java(&env, algorithm)?;
+        let sk = PrivateKey(bytes_from_java(&env, private_key)?);
+        let message = bytes_from_java(&env, message)?;
+        let signature = algorithm
+            .keypair(Some(KeyGenOption::FromSecretKey(sk)))?
+            .sign(&message)?;
+        bytes_to_java(&env, &signature)
+    })
+}
+
+/// Verify `signature` over `message` with `publicKey`
+#[no_mangle]
+pub extern "system" fn Java_org_hyperledger_ursa_UrsaNative_verify(
+    env: JNIEnv,
+    _class: JClass,
+    algorithm: JString,
+    public_key: jbyteArray,
+    message: jbyteArray,
+    signature: jbyteArray,
+) -> jboolean {
+    throw_on_err(&env, JNI_FALSE, || {
+        let algorithm = algorithm_from_java(&env, algorithm)?;
+        let pk = AnyPublicKey::new(algorithm, PublicKey(bytes_from_java(&env, public_key)?));
+        let message = bytes_from_java(&env, message)?;
+        let signature = bytes_from_java(&env, signature)?;
+        Ok(match pk.verify(&message, &signature) {
+            Ok(true) => JNI_TRUE,
+            Ok(false) | Err(CryptoError::SigningError(_)) => JNI_FALSE,
+            Err(e) => return Err(e),
+        })
+    })
+}
+
+/// The shared secret of `privateKey` and `peerPublicKey`
+#[no_mangle]
+pub extern "system" fn Java_org_hyperledger_ursa_UrsaNative_keyExchange(
+    env: JNIEnv,
+    _class: JClass,
+    algorithm: JString,
+    private_key: jbyteArray,
+    peer_public_key: jbyteArray,
+) -> jbyteArray {
+    throw_on_err(&env, ptr::null_mut(), || {
+        let algorithm = algorithm_from_java(&env, algorithm)?;
+        let sk = PrivateKey(bytes_from_java(&env, private_key)?);
+        let peer = AnyPublicKey::new(
+            algorithm,
+            PublicKey(bytes_from_java(&env, peer_public_key)?),
+        );
+        let secret = algorithm
+            .keypair(Some(KeyGenOption::FromSecretKey(sk)))?
+            .key_exchange(&peer)?;
+        bytes_to_java(&env, &secret[..])
+    })
+}
+
+fn algorithm_from_java(env: &JNIEnv, algorithm: JString) -> Result<Algorithm, CryptoError> {
+    let name: String = env.get_string(algorithm).map_err(jni_error)?.into();
+    name.parse()
+}
@@ -0,0 +1,97 @@
+// JNI bindings for Android and the JVM
+//
+// These are the native methods of `org.hyperledger.ursa.UrsaNative`, see
+// java/src/main/kotlin for the class and the Kotlin wrappers around it.
+// Keys, messages and ciphertexts are `byte[]` and every error is thrown as
+// an `org.hyperledger.ursa.UrsaException`. Algorithm names are the ones of
+// `registry::Algorithm`.
+
+#[cfg(any(
+    feature = "aescbc",
+    feature = "aescbc_native",
+    feature = "aesgcm",
+    feature = "aesgcm_native",
+    feature = "chacha20poly1305",
+    feature = "chacha20poly1305_native"
+))]
+pub mod encryption;
+#[cfg(any(
+    feature = "ecdh_secp256k1",
+    feature = "ecdh_secp256k1_native",
+    feature = "ecdh_secp256k1_asm",
+    feature = "ecdsa_secp256k1",
+    feature = "ecdsa_secp256k1_native",
+    feature = "ecdsa_secp256k1_asm",
+    feature = "ed25519",
+    feature = "ed25519_asm",
+    feature = "x25519",
+    feature = "x25519_asm"
+))]
+pub mod keys;
+#[cfg(any(feature = "sharing", feature = "sharing_native"))]
+pub mod sharing;
+
+use jni::objects::JObject;
+use jni::sys::{jbyteArray, jobjectArray, jsize};
+use jni::JNIEnv;
+use CryptoError;
+
+const EXCEPTION_CLASS: &str = "org/hyperledger/ursa/UrsaException";
+
+/// Run `f` and throw an `UrsaException` with its error. `default` is
+/// returned to the JVM, which ignores it while the exception is pending.
+fn throw_on_err<T, F>(env: &JNIEnv, default: T, f: F) -> T
+where
+    F: FnOnce() -> Result<T, CryptoError>,
+{
+    match f() {
+        Ok(t) => t,
+        Err(e) => {
+            // A failed JNI call can already have raised a Java exception
+            if !env.exception_check().unwrap_or(false) {
+                let _ = env.throw_new(EXCEPTION_CLASS, e.to_string());
+            }
+            default
+        }
+    }
+}
+
+fn jni_error(e: jni::errors::Error) -> CryptoError {
+    CryptoError::GeneralError(format!("JNI call failed: {}", e))
+}
+
+fn bytes_from_java(env: &JNIEnv, array: jbyteArray) -> Result<Vec<u8>, CryptoError> {
+    env.convert_byte_array(array).map_err(jni_error)
+}
+
+fn bytes_to_java(env: &JNIEnv, bytes: &[u8]) -> Result<jbyteArray, CryptoError> {
+    env.byte_array_from_slice(bytes).map_err(jni_error)
+}
+
+/// Convert to a `byte[][]`
+fn byte_arrays_to_java<B: AsRef<[u8]>>(
+    env: &JNIEnv,
+    arrays: &[B],
+) -> Result<jobjectArray, CryptoError> {
+    let output = env
+        .new_object_array(arrays.len() as jsize, "[B", JObject::null())
+        .map_err(jni_error)?;
+    for (i, bytes) in arrays.iter().enumerate() {
+        let array = bytes_to_java(env, bytes.as_ref())?;
+        env.set_object_array_element(output, i as jsize, array)
+            .map_err(jni_error)?;
+    }
+    Ok(output)
+}
+
+/// Convert from a `byte[][]`
+#[cfg(any(feature = "sharing", feature = "sharing_native"))]
+fn byte_arrays_from_java(env: &JNIEnv, arrays: jobjectArray) -> Result<Vec<Vec<u8>>, CryptoError> {
+    let len = env.get_array_length(arrays).map_err(jni_error)?;
+    (0..len)
+        .map(|i| {
+            let array = env.get_object_array_element(arrays, i).map_err(jni_error)?;
+            bytes_from_java(env, array.into_inner())
+        })
+        .collect()
+}
@@ -0,0 +1,69 @@
+use super::{
+    byte_arrays_from_java, byte_arrays_to_java, bytes_from_java, bytes_to_java, throw_on_err,
+};
+use bn::BigNumber;
+use jni::objects::JClass;
+use jni::sys::{jbyteArray, jint, jobjectArray};
+use jni::JNIEnv;
+use sharing::shamir::{self, Share};
+use std::ptr;
+use CryptoError;
+
+/// Split `secret` into `total` Shamir shares, `threshold` of which recover it,
+/// in the field of the big endian `prime`
+#[no_mangle]
+pub extern "system" fn Java_org_hyperledger_ursa_UrsaNative_splitSecret(
+    env: JNIEnv,
+    _class: JClass,
+    secret: jbyteArray,
+    threshold: jint,
+    total: jint,
+    prime: jbyteArray,
+) -> jobjectArray {
+    throw_on_err(&env, ptr::null_mut(), || {
+        let secret = bytes_from_java(&env, secret)?;
+        let field = field_from_java(&env, prime)?;
+        let threshold = to_u8(threshold, "threshold")?;
+        let total = to_u8(total, "total")?;
+        let shares = shamir::split_secret(&secret, threshold, total, &field)?;
+        let shares = shares.iter().map(Share::to_bytes).collect::<Vec<_>>();
+        byte_arrays_to_java(&env, &shares)
+    })
+}
+
+/// Recover the secret from at least `threshold` shares
+#[no_mangle]
+pub extern "system" fn Java_org_hyperledger_ursa_UrsaNative_combineShares(
+    env: JNIEnv,
+    _class: JClass,
+    shares: jobjectArray,
+    prime: jbyteArray,
+) -> jbyteArray {
+    throw_on_err(&env, ptr::null_mut(), || {
+        let field = field_from_java(&env, prime)?;
+        let shares = byte_arrays_from_java(&env, shares)?
+            .iter()
+            .map(|s| {
+                if s.len() < 2 {
+                    return Err(CryptoError::ParseError("Share is too short".to_string()));
+                }
+                Share::from_bytes(s, &field)
+            })
+            .collect::<Result<Vec<Share>, CryptoError>>()?;
+        bytes_to_java(&env, &shamir::combine_shares(shares, &field)?)
+    })
+}
+
+fn field_from_java(env: &JNIEnv, prime: jbyteArray) -> Result<BigNumber, CryptoError> {
+    Ok(BigNumber::from_bytes(&bytes_from_java(env, prime)?)?)
+}
+
+fn to_u8(n: jint, name: &str) -> Result<u8, CryptoError> {
+    if n < 0 || n > 255 {
+        return Err(CryptoError::GeneralError(format!(
+            "{} must be between 0 and 255",
+            name
+        )));
+    }
+    Ok(n as u8)
+}
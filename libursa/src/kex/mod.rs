@@ -1,6 +1,7 @@
 //! A suite of Diffie-Hellman key exchange methods.
 
-use keys::{KeyGenOption, PrivateKey, PublicKey, SessionKey};
+use keys::{KeyGenOption, PrivateKey, PublicKey, SessionKey, KEYGEN_SEED_SIZE};
+use rand::{CryptoRng, RngCore};
 use CryptoError;
 
 /// A Generic trait for key exchange schemes. Each scheme provides a way to generate keys and
@@ -18,6 +19,15 @@ pub trait KeyExchangeScheme {
         &self,
         options: Option<KeyGenOption>,
     ) -> Result<(PublicKey, PrivateKey), CryptoError>;
+    /// Create a new keypair from a seed drawn from `rng` instead of the `OsRng`
+    fn keypair_with_rng<R: CryptoRng + RngCore>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(PublicKey, PrivateKey), CryptoError> {
+        let mut seed = vec![0u8; KEYGEN_SEED_SIZE];
+        rng.fill_bytes(&mut seed);
+        self.keypair(Some(KeyGenOption::UseSeed(seed)))
+    }
     /// Compute the diffie-hellman shared secret.
     /// `local_private_key` is the key generated from calling `keypair` while
     /// `remote_public_key` is the key received from a different call to `keypair` from another party.
@@ -1,4 +1,5 @@
 use super::*;
+use encoding::pkcs8::{KeyAlgorithm, Pkcs8KeyHandler};
 use rand::rngs::OsRng;
 use rand::SeedableRng;
 use rand_chacha::ChaChaRng;
@@ -56,6 +57,13 @@ impl KeyExchangeScheme for X25519Sha256 {
         let sk = StaticSecret::from(*array_ref!(&local_private_key[..], 0, 32));
         let pk = X25519PublicKey::from(*array_ref!(&remote_public_key[..], 0, 32));
         let shared_secret = sk.diffie_hellman(&pk);
+        // A low order public key forces the all zero secret whatever the
+        // private key, so reject it as RFC 7748 section 6.1 suggests
+        if shared_secret.as_bytes().iter().fold(0u8, |acc, b| acc | b) == 0 {
+            return Err(CryptoError::InvalidPoint(
+                "The public key has low order".to_string(),
+            ));
+        }
         let hash = sha2::Sha256::digest(shared_secret.as_bytes());
         Ok(SessionKey(hash.as_slice().to_vec()))
     }
@@ -71,6 +79,40 @@ impl KeyExchangeScheme for X25519Sha256 {
     }
 }
 
+impl Pkcs8KeyHandler for X25519Sha256 {
+    fn key_algorithm(&self) -> KeyAlgorithm {
+        KeyAlgorithm::X25519
+    }
+    fn private_key_to_raw(&self, sk: &PrivateKey) -> Result<Vec<u8>, CryptoError> {
+        if sk.len() != 32 {
+            return Err(CryptoError::ParseError(
+                "Invalid private key provided".to_string(),
+            ));
+        }
+        Ok(sk[..].to_vec())
+    }
+    fn keypair_from_raw(&self, raw: &[u8]) -> Result<(PublicKey, PrivateKey), CryptoError> {
+        if raw.len() != 32 {
+            return Err(CryptoError::ParseError(
+                "Invalid private key provided".to_string(),
+            ));
+        }
+        self.keypair(Some(KeyGenOption::FromSecretKey(PrivateKey(raw.to_vec()))))
+    }
+    fn public_key_to_raw(&self, pk: &PublicKey) -> Result<Vec<u8>, CryptoError> {
+        self.public_key_from_raw(&pk[..])?;
+        Ok(pk[..].to_vec())
+    }
+    fn public_key_from_raw(&self, raw: &[u8]) -> Result<PublicKey, CryptoError> {
+        if raw.len() != 32 {
+            return Err(CryptoError::ParseError(
+                "Invalid public key provided".to_string(),
+            ));
+        }
+        Ok(PublicKey(raw.to_vec()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -111,4 +153,23 @@ mod tests {
         assert_eq!(pk1, pk);
         assert_eq!(sk1, sk);
     }
+
+    #[test]
+    fn keypair_with_rng() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let scheme = X25519Sha256::new();
+        let (pk, sk) = scheme
+            .keypair_with_rng(&mut StdRng::seed_from_u64(1))
+            .unwrap();
+        let (pk1, sk1) = scheme
+            .keypair_with_rng(&mut StdRng::seed_from_u64(1))
+            .unwrap();
+        assert_eq!(pk1, pk);
+        assert_eq!(sk1, sk);
+        let (pk2, _) = scheme
+            .keypair_with_rng(&mut StdRng::seed_from_u64(2))
+            .unwrap();
+        assert_ne!(pk2, pk);
+    }
 }
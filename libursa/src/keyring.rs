@@ -0,0 +1,455 @@
+//! Key metadata and rotation for long lived services.
+//!
+//! A [`ManagedKey`] pairs key material with an id, creation time, the
+//! operations it may be used for and its status. A [`KeyRing`] holds the
+//! active key and the public keys of the keys it replaced, so signatures made
+//! before a rotation still verify until those keys are revoked.
+//!
+//! ```
+//! # extern crate ursa;
+//! use ursa::keyring::{KeyRing, KeyUsage, ManagedKey};
+//! use ursa::signatures::{ed25519::Ed25519Sha512, SignatureScheme};
+//!
+//! let scheme = Ed25519Sha512::new();
+//! let (pk, sk) = scheme.keypair(None).unwrap();
+//! let mut ring = KeyRing::new(ManagedKey::new("key-1", pk, sk, KeyUsage::SIGN));
+//! let signature = scheme
+//!     .sign(b"message", ring.active().key(KeyUsage::SIGN).unwrap())
+//!     .unwrap();
+//!
+//! let (pk, sk) = scheme.keypair(None).unwrap();
+//! ring.rotate(ManagedKey::new("key-2", pk, sk, KeyUsage::SIGN)).unwrap();
+//! let old = ring.verification_key("key-1").unwrap();
+//! assert!(scheme.verify(b"message", &signature, old).unwrap());
+//!
+//! ring.revoke("key-1").unwrap();
+//! assert!(ring.verification_key("key-1").is_err());
+//! ```
+
+use keys::PublicKey;
+use std::fmt;
+use std::ops::BitOr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use CryptoError;
+
+/// The operations a key may be used for
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct KeyUsage(u8);
+
+impl KeyUsage {
+    pub const SIGN: KeyUsage = KeyUsage(1);
+    pub const DERIVE: KeyUsage = KeyUsage(2);
+    pub const ENCRYPT: KeyUsage = KeyUsage(4);
+
+    pub fn empty() -> Self {
+        KeyUsage(0)
+    }
+
+    pub fn all() -> Self {
+        Self::SIGN | Self::DERIVE | Self::ENCRYPT
+    }
+
+    /// True if every usage in `other` is allowed
+    pub fn contains(self, other: KeyUsage) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn from_bits(bits: u8) -> Result<Self, CryptoError> {
+        if bits & !Self::all().0 != 0 {
+            return Err(CryptoError::ParseError(format!(
+                "Unknown key usage bits {:#x}",
+                bits
+            )));
+        }
+        Ok(KeyUsage(bits))
+    }
+}
+
+impl BitOr for KeyUsage {
+    type Output = KeyUsage;
+
+    fn bitor(self, other: KeyUsage) -> KeyUsage {
+        KeyUsage(self.0 | other.0)
+    }
+}
+
+impl fmt::Debug for KeyUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = [
+            (Self::SIGN, "SIGN"),
+            (Self::DERIVE, "DERIVE"),
+            (Self::ENCRYPT, "ENCRYPT"),
+        ]
+        .iter()
+        .filter(|(usage, _)| self.contains(*usage))
+        .map(|(_, name)| *name)
+        .collect::<Vec<&str>>();
+        write!(f, "KeyUsage({})", names.join(" | "))
+    }
+}
+
+/// Where a key is in its lifecycle. Times are seconds since the Unix epoch.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyStatus {
+    /// The key may be used for everything its usage allows
+    Active,
+    /// The key was replaced and is only kept to verify old signatures
+    Rotated { at: u64 },
+    /// The key must not be used or trusted
+    Revoked { at: u64 },
+}
+
+impl KeyStatus {
+    pub fn is_revoked(self) -> bool {
+        matches!(self, KeyStatus::Revoked { .. })
+    }
+}
+
+/// Key material with its metadata. `T` is usually a `PrivateKey`, or a
+/// handle for keys held by a [`KeyBackend`](../backend/trait.KeyBackend.html).
+/// Keys that have been retired keep only their public key and have `T = ()`.
+pub struct ManagedKey<T> {
+    id: String,
+    created_at: u64,
+    usage: KeyUsage,
+    status: KeyStatus,
+    public_key: PublicKey,
+    key: T,
+}
+
+impl<T> ManagedKey<T> {
+    /// An active key created now
+    pub fn new(id: &str, public_key: PublicKey, key: T, usage: KeyUsage) -> Self {
+        Self::with_created_at(id, public_key, key, usage, now())
+    }
+
+    /// An active key created at `created_at`, e.g. when loading it from storage
+    pub fn with_created_at(
+        id: &str,
+        public_key: PublicKey,
+        key: T,
+        usage: KeyUsage,
+        created_at: u64,
+    ) -> Self {
+        ManagedKey {
+            id: id.to_string(),
+            created_at,
+            usage,
+            status: KeyStatus::Active,
+            public_key,
+            key,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    pub fn usage(&self) -> KeyUsage {
+        self.usage
+    }
+
+    pub fn status(&self) -> KeyStatus {
+        self.status
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// True if the key is active and may be used for `usage`
+    pub fn allows(&self, usage: KeyUsage) -> bool {
+        self.status == KeyStatus::Active && self.usage.contains(usage)
+    }
+
+    /// The key material, if the key may be used for `usage`
+    pub fn key(&self, usage: KeyUsage) -> Result<&T, CryptoError> {
+        if self.allows(usage) {
+            Ok(&self.key)
+        } else {
+            Err(CryptoError::GeneralError(format!(
+                "Key {} is {:?} and allows {:?}, not {:?}",
+                self.id, self.status, self.usage, usage
+            )))
+        }
+    }
+
+    /// Mark the key revoked. Revocation cannot be undone.
+    pub fn revoke(&mut self) {
+        if !self.status.is_revoked() {
+            self.status = KeyStatus::Revoked { at: now() };
+        }
+    }
+
+    /// Drop the key material and keep the public key and metadata
+    pub fn retire(self) -> ManagedKey<()> {
+        let status = match self.status {
+            KeyStatus::Active => KeyStatus::Rotated { at: now() },
+            s => s,
+        };
+        ManagedKey {
+            id: self.id,
+            created_at: self.created_at,
+            usage: self.usage,
+            status,
+            public_key: self.public_key,
+            key: (),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ManagedKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ManagedKey")
+            .field("id", &self.id)
+            .field("created_at", &self.created_at)
+            .field("usage", &self.usage)
+            .field("status", &self.status)
+            .field("public_key", &self.public_key)
+            .finish()
+    }
+}
+
+/// An active key and the retired keys it replaced
+#[derive(Debug)]
+pub struct KeyRing<T> {
+    active: ManagedKey<T>,
+    retired: Vec<ManagedKey<()>>,
+}
+
+impl<T> KeyRing<T> {
+    pub fn new(active: ManagedKey<T>) -> Self {
+        KeyRing {
+            active,
+            retired: Vec::new(),
+        }
+    }
+
+    /// Restore a ring, e.g. from storage. Fails if `active` is not active,
+    /// any retired key is, or two keys share an id.
+    pub fn from_parts(
+        active: ManagedKey<T>,
+        retired: Vec<ManagedKey<()>>,
+    ) -> Result<Self, CryptoError> {
+        if active.status != KeyStatus::Active
+            || retired.iter().any(|k| k.status == KeyStatus::Active)
+        {
+            return Err(CryptoError::GeneralError(
+                "A key ring has exactly one active key".to_string(),
+            ));
+        }
+        let mut ring = Self::new(active);
+        for key in retired {
+            ring.check_unique(&key.id)?;
+            ring.retired.push(key);
+        }
+        Ok(ring)
+    }
+
+    pub fn active(&self) -> &ManagedKey<T> {
+        &self.active
+    }
+
+    /// Keys replaced by rotation, oldest first
+    pub fn retired(&self) -> &[ManagedKey<()>] {
+        &self.retired
+    }
+
+    /// Make `next` the active key. The old key material is dropped and its
+    /// public key is kept for verification. Returns the id of the old key.
+    pub fn rotate(&mut self, next: ManagedKey<T>) -> Result<String, CryptoError> {
+        if next.status != KeyStatus::Active {
+            return Err(CryptoError::GeneralError(format!(
+                "Cannot rotate to {:?} key {}",
+                next.status, next.id
+            )));
+        }
+        self.check_unique(&next.id)?;
+        let old = ::std::mem::replace(&mut self.active, next).retire();
+        let id = old.id.clone();
+        self.retired.push(old);
+        Ok(id)
+    }
+
+    /// Revoke a retired key. The active key must be rotated out first.
+    pub fn revoke(&mut self, id: &str) -> Result<(), CryptoError> {
+        if self.active.id == id {
+            return Err(CryptoError::GeneralError(format!(
+                "Key {} is active, rotate before revoking it",
+                id
+            )));
+        }
+        self.retired
+            .iter_mut()
+            .find(|k| k.id == id)
+            .map(ManagedKey::revoke)
+            .ok_or_else(|| unknown_key(id))
+    }
+
+    /// The public key to verify signatures made by key `id`. Revoked keys and
+    /// keys not allowed to sign are rejected.
+    pub fn verification_key(&self, id: &str) -> Result<&PublicKey, CryptoError> {
+        let (status, usage, public_key) = if self.active.id == id {
+            (
+                self.active.status,
+                self.active.usage,
+                &self.active.public_key,
+            )
+        } else {
+            let key = self
+                .retired
+                .iter()
+                .find(|k| k.id == id)
+                .ok_or_else(|| unknown_key(id))?;
+            (key.status, key.usage, &key.public_key)
+        };
+        match status {
+            KeyStatus::Revoked { .. } => {
+                Err(CryptoError::GeneralError(format!("Key {} is revoked", id)))
+            }
+            _ if !usage.contains(KeyUsage::SIGN) => Err(CryptoError::GeneralError(format!(
+                "Key {} is not a signing key",
+                id
+            ))),
+            _ => Ok(public_key),
+        }
+    }
+
+    /// Ids and public keys of every key that is not revoked, newest first
+    pub fn verification_keys(&self) -> Vec<(&str, &PublicKey)> {
+        let mut keys = vec![(self.active.id(), self.active.public_key())];
+        keys.extend(
+            self.retired
+                .iter()
+                .rev()
+                .filter(|k| !k.status.is_revoked())
+                .map(|k| (k.id(), k.public_key())),
+        );
+        keys
+    }
+
+    fn check_unique(&self, id: &str) -> Result<(), CryptoError> {
+        if self.active.id == id || self.retired.iter().any(|k| k.id == id) {
+            Err(CryptoError::GeneralError(format!(
+                "Key {} is already in the key ring",
+                id
+            )))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn unknown_key(id: &str) -> CryptoError {
+    CryptoError::GeneralError(format!("Unknown key {}", id))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keys::PrivateKey;
+
+    fn key(id: &str, usage: KeyUsage) -> ManagedKey<PrivateKey> {
+        ManagedKey::with_created_at(
+            id,
+            PublicKey(id.as_bytes().to_vec()),
+            PrivateKey(vec![1u8; 32]),
+            usage,
+            1_600_000_000,
+        )
+    }
+
+    #[test]
+    fn key_usage() {
+        let usage = KeyUsage::SIGN | KeyUsage::ENCRYPT;
+        assert!(usage.contains(KeyUsage::SIGN));
+        assert!(!usage.contains(KeyUsage::DERIVE));
+        assert!(!usage.contains(KeyUsage::all()));
+        assert!(usage.contains(KeyUsage::empty()));
+        assert_eq!(usage, KeyUsage::from_bits(usage.bits()).unwrap());
+        assert!(KeyUsage::from_bits(8).is_err());
+        assert_eq!("KeyUsage(SIGN | ENCRYPT)", format!("{:?}", usage));
+    }
+
+    #[test]
+    fn managed_key() {
+        let mut k = key("a", KeyUsage::SIGN);
+        assert_eq!(1_600_000_000, k.created_at());
+        assert!(k.key(KeyUsage::SIGN).is_ok());
+        assert!(k.key(KeyUsage::DERIVE).is_err());
+        assert!(!format!("{:?}", k).contains(&"01".repeat(32)));
+        k.revoke();
+        assert!(k.key(KeyUsage::SIGN).is_err());
+        let status = k.status();
+        k.revoke();
+        assert_eq!(status, k.status());
+        assert_eq!(status, k.retire().status());
+        assert!(ManagedKey::new("b", PublicKey(vec![]), (), KeyUsage::SIGN).created_at() > 0);
+    }
+
+    #[test]
+    fn rotation() {
+        let mut ring = KeyRing::new(key("a", KeyUsage::SIGN));
+        assert!(ring.rotate(key("a", KeyUsage::SIGN)).is_err());
+        assert_eq!("a", ring.rotate(key("b", KeyUsage::SIGN)).unwrap());
+        assert_eq!("b", ring.active().id());
+        match ring.retired()[0].status() {
+            KeyStatus::Rotated { .. } => {}
+            s => panic!("{:?}", s),
+        }
+        assert_eq!(b"a", &ring.verification_key("a").unwrap().0[..]);
+        ring.rotate(key("c", KeyUsage::DERIVE)).unwrap();
+        assert!(ring.verification_key("c").is_err());
+        assert_eq!(
+            vec!["c", "b", "a"],
+            ring.verification_keys()
+                .iter()
+                .map(|(id, _)| *id)
+                .collect::<Vec<&str>>()
+        );
+
+        assert!(ring.revoke("c").is_err());
+        assert!(ring.revoke("d").is_err());
+        ring.revoke("a").unwrap();
+        assert!(ring.verification_key("a").is_err());
+        assert!(ring.verification_key("b").is_ok());
+        assert_eq!(2, ring.verification_keys().len());
+
+        let mut revoked = key("d", KeyUsage::SIGN);
+        revoked.revoke();
+        assert!(ring.rotate(revoked).is_err());
+    }
+
+    #[test]
+    fn from_parts() {
+        let retired = vec![key("a", KeyUsage::SIGN).retire()];
+        let ring = KeyRing::from_parts(key("b", KeyUsage::SIGN), retired).unwrap();
+        assert_eq!(1, ring.retired().len());
+        let retired = vec![key("b", KeyUsage::SIGN).retire()];
+        assert!(KeyRing::from_parts(key("b", KeyUsage::SIGN), retired).is_err());
+        let active = vec![key("a", KeyUsage::SIGN)]
+            .into_iter()
+            .map(|k| ManagedKey::new(k.id(), PublicKey(vec![]), (), k.usage()))
+            .collect();
+        assert!(KeyRing::from_parts(key("b", KeyUsage::SIGN), active).is_err());
+    }
+}
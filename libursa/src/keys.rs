@@ -1,21 +1,41 @@
+use alloc::vec::Vec;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::ops::Drop;
 use zeroize::Zeroize;
+#[cfg(feature = "hex")]
+use CryptoError;
+
+pub use ursa_core::Secret;
 
 // A private key instance.
 /// The underlying content is dependent on implementation.
 pub struct PrivateKey(pub Vec<u8>);
-impl_bytearray!(PrivateKey);
+impl_bytearray!(secret PrivateKey);
 
 pub struct PublicKey(pub Vec<u8>);
 impl_bytearray!(PublicKey);
 
 pub struct SessionKey(pub Vec<u8>);
-impl_bytearray!(SessionKey);
+impl_bytearray!(secret SessionKey);
 
 pub struct MacKey(pub Vec<u8>);
-impl_bytearray!(MacKey);
+impl_bytearray!(secret MacKey);
+
+/// Decodes the hex that keys and signatures display as, with or
+/// without a leading `0x`
+#[cfg(feature = "hex")]
+pub fn decode_hex(s: &str) -> Result<Vec<u8>, CryptoError> {
+    let digits = if s.starts_with("0x") || s.starts_with("0X") {
+        &s[2..]
+    } else {
+        s
+    };
+    hex::decode(digits).map_err(|e| CryptoError::ParseError(format!("Invalid hex: {}", e)))
+}
+
+/// Size of the seed drawn by the `keypair_with_rng` methods
+pub const KEYGEN_SEED_SIZE: usize = 32;
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, PartialEq, Eq)]
@@ -49,3 +69,65 @@ fn serialize_tests() {
         serde_json::to_string(&e).unwrap()
     );
 }
+
+#[test]
+fn constant_time_compare() {
+    use subtle::{Choice, ConstantTimeEq};
+
+    let mut a = PrivateKey(vec![1u8, 2, 3]);
+    let b = PrivateKey(vec![1u8, 2, 4]);
+    assert!(!bool::from(a.ct_eq(&b)));
+    assert_ne!(a, PrivateKey(vec![1u8, 2]));
+    a.conditional_assign(&b, Choice::from(0));
+    assert_eq!(PrivateKey(vec![1u8, 2, 3]), a);
+    a.conditional_assign(&b, Choice::from(1));
+    assert_eq!(b, a);
+}
+
+#[test]
+fn byte_conversions() {
+    use std::convert::TryFrom;
+
+    fn parse<'a, T: TryFrom<&'a [u8]>>(bytes: &'a [u8]) -> Option<T> {
+        T::try_from(bytes).ok()
+    }
+
+    fn length<B: AsRef<[u8]>>(bytes: B) -> usize {
+        bytes.as_ref().len()
+    }
+
+    let pk = PublicKey::from(&[1u8, 2, 3][..]);
+    assert_eq!(pk, PublicKey(vec![1u8, 2, 3]));
+    assert_eq!(parse::<PublicKey>(&[1u8, 2, 3]), Some(pk.clone()));
+    assert_eq!(length(&pk), 3);
+    let sk: PrivateKey = vec![4u8; 32].into();
+    assert_eq!(sk.as_ref(), &[4u8; 32][..]);
+}
+
+#[cfg(feature = "hex")]
+#[test]
+fn hex_strings() {
+    let pk: PublicKey = "0x0a0bFF".parse().unwrap();
+    assert_eq!(pk, PublicKey(vec![10u8, 11, 255]));
+    assert_eq!(pk.to_string(), "0a0bff");
+    assert_eq!(pk.to_string().parse::<PublicKey>().unwrap(), pk);
+    assert!("0a0".parse::<PublicKey>().is_err());
+    assert!("0xzz".parse::<PublicKey>().is_err());
+}
+
+#[cfg(feature = "hex")]
+#[test]
+fn redacted_secrets() {
+    let sk = PrivateKey(vec![10u8, 11, 255]);
+    assert_eq!(format!("{:?}", sk), "PrivateKey(..)");
+    assert_eq!(sk.to_string(), "PrivateKey(..)");
+    assert_eq!(sk.expose_bytes(), &[10u8, 11, 255][..]);
+    assert_eq!(format!("{:?}", SessionKey(vec![1u8])), "SessionKey(..)");
+    assert_eq!(
+        format!("{:?}", KeyGenOption::FromSecretKey(sk.clone())),
+        "FromSecretKey(PrivateKey(..))"
+    );
+    // Secrets still parse from hex
+    assert_eq!("0a0bff".parse::<PrivateKey>().unwrap(), sk);
+    assert_eq!(format!("{:?}", PublicKey(vec![10u8])), "PublicKey { 0a }");
+}
@@ -0,0 +1,898 @@
+//! Encrypted storage for private keys.
+//!
+//! A private key is encrypted with AES-256-GCM under a key derived from a
+//! passphrase with Argon2id. Keystores are JSON documents laid out like
+//! EIP-2335 (`crypto.kdf`, `crypto.checksum`, `crypto.cipher`, `pubkey`,
+//! `path`, `uuid`, `version`) so BLS keys stored here have the same shape as
+//! Ethereum validator keystores. [`KeystoreDir`] keeps one file per key.
+//!
+//! Keystores written by Ethereum validator tooling, with a scrypt or PBKDF2
+//! key and AES-128-CTR, are read too and written by
+//! [`Keystore::encrypt_eip2335`]. Their passphrases are NFKD normalized and
+//! stripped of control codes as EIP-2335 requires. Passphrases of the
+//! Argon2id keystores are used as given.
+
+use aead::generic_array::typenum::Unsigned;
+use aead::Aead;
+use aes::Aes128;
+use ctr::cipher::stream::{NewStreamCipher, SyncStreamCipher};
+use encoding::pkcs8::{KeyAlgorithm, Pkcs8KeyHandler};
+use encryption::symm::{aesgcm::Aes256Gcm, SymmetricEncryptor};
+use hmac::Hmac;
+use keys::{PrivateKey, PublicKey, Secret};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use subtle::ConstantTimeEq;
+use unicode_normalization::UnicodeNormalization;
+use CryptoError;
+
+/// The keystore format version written and accepted
+pub const KEYSTORE_VERSION: u32 = 4;
+
+const KDF_FUNCTION: &str = "argon2id";
+const CHECKSUM_FUNCTION: &str = "sha256";
+const CIPHER_FUNCTION: &str = "aes-256-gcm";
+const SALT_SIZE: usize = 32;
+/// The first half of the derived key encrypts, the second half is the checksum key
+const DERIVED_KEY_SIZE: usize = 64;
+const EIP2335_CIPHER_FUNCTION: &str = "aes-128-ctr";
+const EIP2335_PRF: &str = "hmac-sha256";
+/// The first 16 bytes of the derived key encrypt, the last 16 are the checksum key
+const EIP2335_KEY_SIZE: usize = 32;
+const EIP2335_IV_SIZE: usize = 16;
+
+type Aes128Ctr = ctr::Ctr128<Aes128>;
+
+/// Argon2id cost parameters
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory in KiB
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            memory_kib: 65536,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+/// The key derivation of an EIP-2335 keystore
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Eip2335Kdf {
+    /// scrypt with the cost `n`, a power of two, block size `r` and
+    /// parallelism `p`
+    Scrypt { n: u32, r: u32, p: u32 },
+    /// PBKDF2-HMAC-SHA256 with `c` iterations
+    Pbkdf2 { c: u32 },
+}
+
+/// The scrypt parameters of the EIP-2335 examples and `eth2.0-deposit-cli`
+impl Default for Eip2335Kdf {
+    fn default() -> Self {
+        Eip2335Kdf::Scrypt {
+            n: 262_144,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+/// One step of the keystore pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeystoreModule {
+    pub function: String,
+    pub params: Value,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeystoreCrypto {
+    pub kdf: KeystoreModule,
+    pub checksum: KeystoreModule,
+    pub cipher: KeystoreModule,
+}
+
+/// An encrypted private key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub crypto: KeystoreCrypto,
+    #[serde(default)]
+    pub description: String,
+    /// Hex of the public key
+    pub pubkey: String,
+    /// Derivation path of the key, empty when it was not derived
+    #[serde(default)]
+    pub path: String,
+    pub uuid: String,
+    pub version: u32,
+    /// The type of key, e.g. `ed25519`. EIP-2335 keystores omit it for BLS keys.
+    #[serde(default, rename = "keytype", skip_serializing_if = "Option::is_none")]
+    pub key_type: Option<String>,
+}
+
+impl Keystore {
+    /// Encrypt the raw bytes of a private key
+    pub fn encrypt(
+        secret: &[u8],
+        pubkey: &[u8],
+        key_type: Option<&str>,
+        passphrase: &[u8],
+        params: KdfParams,
+    ) -> Result<Self, CryptoError> {
+        Self::encrypt_with_rng(secret, pubkey, key_type, passphrase, params, &mut OsRng)
+    }
+
+    /// `encrypt` with the uuid, salt and iv drawn from `rng`
+    pub fn encrypt_with_rng<R: CryptoRng + RngCore>(
+        secret: &[u8],
+        pubkey: &[u8],
+        key_type: Option<&str>,
+        passphrase: &[u8],
+        params: KdfParams,
+        rng: &mut R,
+    ) -> Result<Self, CryptoError> {
+        Ok(Keystore {
+            crypto: KeystoreCrypto::encrypt(secret, passphrase, params, rng)?,
+            description: String::new(),
+            pubkey: hex::encode(pubkey),
+            path: String::new(),
+            uuid: new_uuid(rng),
+            version: KEYSTORE_VERSION,
+            key_type: key_type.map(str::to_string),
+        })
+    }
+
+    /// Encrypt the raw bytes of a BLS private key in the format of EIP-2335.
+    /// The passphrase must be UTF-8.
+    pub fn encrypt_eip2335(
+        secret: &[u8],
+        pubkey: &[u8],
+        passphrase: &[u8],
+        kdf: Eip2335Kdf,
+    ) -> Result<Self, CryptoError> {
+        Self::encrypt_eip2335_with_rng(secret, pubkey, passphrase, kdf, &mut OsRng)
+    }
+
+    /// `encrypt_eip2335` with the uuid, salt and iv drawn from `rng`
+    pub fn encrypt_eip2335_with_rng<R: CryptoRng + RngCore>(
+        secret: &[u8],
+        pubkey: &[u8],
+        passphrase: &[u8],
+        kdf: Eip2335Kdf,
+        rng: &mut R,
+    ) -> Result<Self, CryptoError> {
+        Ok(Keystore {
+            crypto: KeystoreCrypto::encrypt_eip2335(secret, passphrase, kdf, rng)?,
+            description: String::new(),
+            pubkey: hex::encode(pubkey),
+            path: String::new(),
+            uuid: new_uuid(rng),
+            version: KEYSTORE_VERSION,
+            key_type: None,
+        })
+    }
+
+    /// True if this keystore is in the format of EIP-2335
+    pub fn is_eip2335(&self) -> bool {
+        self.crypto.kdf.function != KDF_FUNCTION
+    }
+
+    /// Encrypt a private key of the scheme of `handler`
+    pub fn from_private_key<H: Pkcs8KeyHandler>(
+        handler: &H,
+        sk: &PrivateKey,
+        passphrase: &[u8],
+        params: KdfParams,
+    ) -> Result<Self, CryptoError> {
+        let raw = Secret::new(handler.private_key_to_raw(sk)?);
+        let (pk, _) = handler.keypair_from_raw(raw.expose_secret())?;
+        Self::encrypt(
+            raw.expose_secret(),
+            &handler.public_key_to_raw(&pk)?,
+            Some(key_type(handler.key_algorithm())),
+            passphrase,
+            params,
+        )
+    }
+
+    /// Set the description
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    /// Set the derivation path
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = path.to_string();
+        self
+    }
+
+    /// Decrypt the raw bytes of the private key
+    pub fn decrypt(&self, passphrase: &[u8]) -> Result<Secret<Vec<u8>>, CryptoError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(CryptoError::ParseError(format!(
+                "Unsupported keystore version {}",
+                self.version
+            )));
+        }
+        self.crypto.decrypt(passphrase)
+    }
+
+    /// Decrypt the keypair of the scheme of `handler`
+    pub fn to_keypair<H: Pkcs8KeyHandler>(
+        &self,
+        handler: &H,
+        passphrase: &[u8],
+    ) -> Result<(PublicKey, PrivateKey), CryptoError> {
+        let expected = key_type(handler.key_algorithm());
+        if self.key_type.as_ref().map(String::as_str) != Some(expected) {
+            return Err(CryptoError::ParseError(format!(
+                "Expected a {} keystore",
+                expected
+            )));
+        }
+        let raw = self.decrypt(passphrase)?;
+        let (pk, sk) = handler.keypair_from_raw(raw.expose_secret())?;
+        if hex::encode(handler.public_key_to_raw(&pk)?) != self.pubkey {
+            return Err(CryptoError::ParseError(
+                "Keystore public key does not match the private key".to_string(),
+            ));
+        }
+        Ok((pk, sk))
+    }
+
+    /// Re-encrypt under a new passphrase, keeping the uuid and metadata.
+    /// EIP-2335 keystores keep their key derivation and ignore `params`.
+    pub fn change_passphrase(
+        &self,
+        old: &[u8],
+        new: &[u8],
+        params: KdfParams,
+    ) -> Result<Self, CryptoError> {
+        let secret = self.decrypt(old)?;
+        let crypto = if self.is_eip2335() {
+            let kdf = Eip2335Kdf::from_module(&self.crypto.kdf)?;
+            KeystoreCrypto::encrypt_eip2335(secret.expose_secret(), new, kdf, &mut OsRng)?
+        } else {
+            KeystoreCrypto::encrypt(secret.expose_secret(), new, params, &mut OsRng)?
+        };
+        Ok(Keystore {
+            crypto,
+            ..self.clone()
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, CryptoError> {
+        serde_json::to_string_pretty(self).map_err(|e| CryptoError::GeneralError(e.to_string()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, CryptoError> {
+        serde_json::from_str(json).map_err(|e| CryptoError::ParseError(e.to_string()))
+    }
+}
+
+impl KeystoreCrypto {
+    fn encrypt<R: CryptoRng + RngCore>(
+        secret: &[u8],
+        passphrase: &[u8],
+        params: KdfParams,
+        rng: &mut R,
+    ) -> Result<Self, CryptoError> {
+        let mut salt = [0u8; SALT_SIZE];
+        rng.fill_bytes(&mut salt);
+        let mut iv = vec![0u8; <Aes256Gcm as Aead>::NonceSize::to_usize()];
+        rng.fill_bytes(&mut iv);
+
+        let dk = derive_key(passphrase, &salt, params)?;
+        let dk = dk.expose_secret();
+        let ciphertext = SymmetricEncryptor::<Aes256Gcm>::new_with_key(&dk[..32])
+            .and_then(|e| e.encrypt(&iv[..], &[], secret))
+            .map_err(|_| CryptoError::GeneralError("Keystore encryption failed".to_string()))?;
+        let checksum = checksum(&dk[32..], &ciphertext);
+
+        Ok(KeystoreCrypto {
+            kdf: KeystoreModule {
+                function: KDF_FUNCTION.to_string(),
+                params: json!({
+                    "m": params.memory_kib,
+                    "t": params.iterations,
+                    "p": params.parallelism,
+                    "salt": hex::encode(salt),
+                }),
+                message: String::new(),
+            },
+            checksum: KeystoreModule {
+                function: CHECKSUM_FUNCTION.to_string(),
+                params: json!({}),
+                message: hex::encode(checksum),
+            },
+            cipher: KeystoreModule {
+                function: CIPHER_FUNCTION.to_string(),
+                params: json!({ "iv": hex::encode(iv) }),
+                message: hex::encode(ciphertext),
+            },
+        })
+    }
+
+    fn encrypt_eip2335<R: CryptoRng + RngCore>(
+        secret: &[u8],
+        passphrase: &[u8],
+        kdf: Eip2335Kdf,
+        rng: &mut R,
+    ) -> Result<Self, CryptoError> {
+        let mut salt = [0u8; SALT_SIZE];
+        rng.fill_bytes(&mut salt);
+        let mut iv = [0u8; EIP2335_IV_SIZE];
+        rng.fill_bytes(&mut iv);
+
+        let dk = kdf.derive_key(passphrase, &salt)?;
+        let dk = dk.expose_secret();
+        let mut ciphertext = secret.to_vec();
+        Aes128Ctr::new((&dk[..16]).into(), (&iv[..]).into()).apply_keystream(&mut ciphertext);
+        let checksum = checksum(&dk[16..], &ciphertext);
+
+        let mut params = match kdf {
+            Eip2335Kdf::Scrypt { n, r, p } => json!({ "n": n, "r": r, "p": p }),
+            Eip2335Kdf::Pbkdf2 { c } => json!({ "c": c, "prf": EIP2335_PRF }),
+        };
+        params["dklen"] = json!(EIP2335_KEY_SIZE);
+        params["salt"] = json!(hex::encode(salt));
+        Ok(KeystoreCrypto {
+            kdf: KeystoreModule {
+                function: kdf.function().to_string(),
+                params,
+                message: String::new(),
+            },
+            checksum: KeystoreModule {
+                function: CHECKSUM_FUNCTION.to_string(),
+                params: json!({}),
+                message: hex::encode(checksum),
+            },
+            cipher: KeystoreModule {
+                function: EIP2335_CIPHER_FUNCTION.to_string(),
+                params: json!({ "iv": hex::encode(iv) }),
+                message: hex::encode(ciphertext),
+            },
+        })
+    }
+
+    fn decrypt_eip2335(&self, passphrase: &[u8]) -> Result<Secret<Vec<u8>>, CryptoError> {
+        let kdf = Eip2335Kdf::from_module(&self.kdf)?;
+        for (module, function) in &[
+            (&self.checksum, CHECKSUM_FUNCTION),
+            (&self.cipher, EIP2335_CIPHER_FUNCTION),
+        ] {
+            if module.function != *function {
+                return Err(CryptoError::NoSuchAlgorithm(module.function.clone()));
+            }
+        }
+        let salt = hex_param(&self.kdf.params, "salt")?;
+        let iv = hex_param(&self.cipher.params, "iv")?;
+        if iv.len() != EIP2335_IV_SIZE {
+            return Err(CryptoError::ParseError("Invalid keystore iv".to_string()));
+        }
+        let mut ciphertext = decode_hex(&self.cipher.message)?;
+        let expected = decode_hex(&self.checksum.message)?;
+
+        let dk = kdf.derive_key(passphrase, &salt)?;
+        let dk = dk.expose_secret();
+        let computed = checksum(&dk[16..], &ciphertext);
+        if !bool::from(computed[..].ct_eq(&expected[..])) {
+            return Err(CryptoError::GeneralError(
+                "Invalid keystore passphrase".to_string(),
+            ));
+        }
+        Aes128Ctr::new((&dk[..16]).into(), (&iv[..]).into()).apply_keystream(&mut ciphertext);
+        Ok(Secret::new(ciphertext))
+    }
+
+    fn decrypt(&self, passphrase: &[u8]) -> Result<Secret<Vec<u8>>, CryptoError> {
+        if self.kdf.function != KDF_FUNCTION {
+            return self.decrypt_eip2335(passphrase);
+        }
+        for (module, function) in &[
+            (&self.kdf, KDF_FUNCTION),
+            (&self.checksum, CHECKSUM_FUNCTION),
+            (&self.cipher, CIPHER_FUNCTION),
+        ] {
+            if module.function != *function {
+                return Err(CryptoError::NoSuchAlgorithm(module.function.clone()));
+            }
+        }
+        let params = KdfParams {
+            memory_kib: u32_param(&self.kdf.params, "m")?,
+            iterations: u32_param(&self.kdf.params, "t")?,
+            parallelism: u32_param(&self.kdf.params, "p")?,
+        };
+        let salt = hex_param(&self.kdf.params, "salt")?;
+        let iv = hex_param(&self.cipher.params, "iv")?;
+        if iv.len() != <Aes256Gcm as Aead>::NonceSize::to_usize() {
+            return Err(CryptoError::ParseError("Invalid keystore iv".to_string()));
+        }
+        let ciphertext = decode_hex(&self.cipher.message)?;
+        let expected = decode_hex(&self.checksum.message)?;
+
+        let dk = derive_key(passphrase, &salt, params)?;
+        let dk = dk.expose_secret();
+        let computed = checksum(&dk[32..], &ciphertext);
+        if !bool::from(computed[..].ct_eq(&expected[..])) {
+            return Err(CryptoError::GeneralError(
+                "Invalid keystore passphrase".to_string(),
+            ));
+        }
+        SymmetricEncryptor::<Aes256Gcm>::new_with_key(&dk[..32])
+            .and_then(|e| e.decrypt(&iv[..], &[], &ciphertext[..]))
+            .map(Secret::new)
+            .map_err(|_| CryptoError::GeneralError("Keystore decryption failed".to_string()))
+    }
+}
+
+impl Eip2335Kdf {
+    fn function(self) -> &'static str {
+        match self {
+            Eip2335Kdf::Scrypt { .. } => "scrypt",
+            Eip2335Kdf::Pbkdf2 { .. } => "pbkdf2",
+        }
+    }
+
+    fn from_module(kdf: &KeystoreModule) -> Result<Self, CryptoError> {
+        if u32_param(&kdf.params, "dklen")? as usize != EIP2335_KEY_SIZE {
+            return Err(CryptoError::ParseError(
+                "Invalid keystore parameter dklen".to_string(),
+            ));
+        }
+        match kdf.function.as_str() {
+            "scrypt" => Ok(Eip2335Kdf::Scrypt {
+                n: u32_param(&kdf.params, "n")?,
+                r: u32_param(&kdf.params, "r")?,
+                p: u32_param(&kdf.params, "p")?,
+            }),
+            "pbkdf2" => {
+                let prf = kdf.params.get("prf").and_then(Value::as_str);
+                if prf != Some(EIP2335_PRF) {
+                    return Err(CryptoError::NoSuchAlgorithm(format!(
+                        "PBKDF2 with {}",
+                        prf.unwrap_or("no prf")
+                    )));
+                }
+                Ok(Eip2335Kdf::Pbkdf2 {
+                    c: u32_param(&kdf.params, "c")?,
+                })
+            }
+            function => Err(CryptoError::NoSuchAlgorithm(function.to_string())),
+        }
+    }
+
+    fn derive_key(self, passphrase: &[u8], salt: &[u8]) -> Result<Secret<Vec<u8>>, CryptoError> {
+        let passphrase = normalize_passphrase(passphrase)?;
+        let passphrase = passphrase.expose_secret();
+        let mut key = vec![0u8; EIP2335_KEY_SIZE];
+        match self {
+            Eip2335Kdf::Scrypt { n, r, p } => {
+                if n < 2 || !n.is_power_of_two() {
+                    return Err(CryptoError::KeyGenError(format!(
+                        "The scrypt cost {} is not a power of two",
+                        n
+                    )));
+                }
+                let params =
+                    scrypt::ScryptParams::new(n.trailing_zeros() as u8, r, p).map_err(|_| {
+                        CryptoError::KeyGenError("Invalid scrypt parameters".to_string())
+                    })?;
+                scrypt::scrypt(passphrase, salt, &params, &mut key).map_err(|_| {
+                    CryptoError::KeyGenError("Invalid scrypt key length".to_string())
+                })?;
+            }
+            Eip2335Kdf::Pbkdf2 { c } => {
+                if c == 0 {
+                    return Err(CryptoError::KeyGenError(
+                        "PBKDF2 needs at least one iteration".to_string(),
+                    ));
+                }
+                pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase, salt, c as usize, &mut key)
+            }
+        }
+        Ok(Secret::new(key))
+    }
+}
+
+/// NFKD normalize `passphrase` and remove the C0, C1 and delete control
+/// codes, as EIP-2335 specifies
+fn normalize_passphrase(passphrase: &[u8]) -> Result<Secret<Vec<u8>>, CryptoError> {
+    let passphrase = std::str::from_utf8(passphrase)
+        .map_err(|_| CryptoError::ParseError("Keystore passphrases must be UTF-8".to_string()))?;
+    Ok(Secret::new(
+        passphrase
+            .nfkd()
+            .filter(|c| !c.is_control())
+            .collect::<String>()
+            .into_bytes(),
+    ))
+}
+
+/// A random version 4 uuid
+fn new_uuid<R: CryptoRng + RngCore>(rng: &mut R) -> String {
+    let mut uuid = [0u8; 16];
+    rng.fill_bytes(&mut uuid);
+    // Version 4, variant 1
+    uuid[6] = (uuid[6] & 0x0f) | 0x40;
+    uuid[8] = (uuid[8] & 0x3f) | 0x80;
+    let uuid = hex::encode(uuid);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &uuid[..8],
+        &uuid[8..12],
+        &uuid[12..16],
+        &uuid[16..20],
+        &uuid[20..]
+    )
+}
+
+/// A directory holding one `<uuid>.json` file per keystore
+#[derive(Debug, Clone)]
+pub struct KeystoreDir {
+    path: PathBuf,
+}
+
+impl KeystoreDir {
+    /// Open the directory at `path`, creating it if needed
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CryptoError> {
+        fs::create_dir_all(path.as_ref()).map_err(io_error)?;
+        Ok(KeystoreDir {
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    /// Write `keystore` and return the path of its file.
+    /// An existing keystore with the same uuid is replaced.
+    pub fn save(&self, keystore: &Keystore) -> Result<PathBuf, CryptoError> {
+        let path = self.file(&keystore.uuid)?;
+        let tmp = path.with_extension("json.tmp");
+        write_private(&tmp, keystore.to_json()?.as_bytes())?;
+        fs::rename(&tmp, &path).map_err(io_error)?;
+        Ok(path)
+    }
+
+    pub fn load(&self, uuid: &str) -> Result<Keystore, CryptoError> {
+        let json = fs::read_to_string(self.file(uuid)?).map_err(io_error)?;
+        Keystore::from_json(&json)
+    }
+
+    /// All keystores in the directory ordered by uuid.
+    /// Files that are not keystores are skipped.
+    pub fn list(&self) -> Result<Vec<Keystore>, CryptoError> {
+        let mut keystores = Vec::new();
+        for entry in fs::read_dir(&self.path).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            if let Ok(keystore) = fs::read_to_string(&path)
+                .map_err(io_error)
+                .and_then(|json| Keystore::from_json(&json))
+            {
+                keystores.push(keystore);
+            }
+        }
+        keystores.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+        Ok(keystores)
+    }
+
+    pub fn remove(&self, uuid: &str) -> Result<(), CryptoError> {
+        fs::remove_file(self.file(uuid)?).map_err(io_error)
+    }
+
+    /// Re-encrypt a stored keystore under a new passphrase
+    pub fn change_passphrase(
+        &self,
+        uuid: &str,
+        old: &[u8],
+        new: &[u8],
+        params: KdfParams,
+    ) -> Result<Keystore, CryptoError> {
+        let keystore = self.load(uuid)?.change_passphrase(old, new, params)?;
+        self.save(&keystore)?;
+        Ok(keystore)
+    }
+
+    fn file(&self, uuid: &str) -> Result<PathBuf, CryptoError> {
+        if uuid.is_empty() || !uuid.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+            return Err(CryptoError::ParseError(format!(
+                "Invalid keystore uuid {}",
+                uuid
+            )));
+        }
+        Ok(self.path.join(format!("{}.json", uuid)))
+    }
+}
+
+fn key_type(algorithm: KeyAlgorithm) -> &'static str {
+    match algorithm {
+        KeyAlgorithm::Ed25519 => "ed25519",
+        KeyAlgorithm::X25519 => "x25519",
+        KeyAlgorithm::EcSecp256k1 => "secp256k1",
+    }
+}
+
+fn derive_key(
+    passphrase: &[u8],
+    salt: &[u8],
+    params: KdfParams,
+) -> Result<Secret<Vec<u8>>, CryptoError> {
+    let config = argon2::Config {
+        ad: &[],
+        hash_length: DERIVED_KEY_SIZE as u32,
+        lanes: params.parallelism,
+        mem_cost: params.memory_kib,
+        secret: &[],
+        thread_mode: argon2::ThreadMode::Sequential,
+        time_cost: params.iterations,
+        variant: argon2::Variant::Argon2id,
+        version: argon2::Version::Version13,
+    };
+    argon2::hash_raw(passphrase, salt, &config)
+        .map(Secret::new)
+        .map_err(|e| CryptoError::KeyGenError(format!("Argon2id failed: {}", e)))
+}
+
+fn checksum(key: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.input(key);
+    hasher.input(ciphertext);
+    hasher.result().to_vec()
+}
+
+fn u32_param(params: &Value, name: &str) -> Result<u32, CryptoError> {
+    params
+        .get(name)
+        .and_then(Value::as_u64)
+        .filter(|v| *v <= u64::from(u32::max_value()))
+        .map(|v| v as u32)
+        .ok_or_else(|| CryptoError::ParseError(format!("Invalid keystore parameter {}", name)))
+}
+
+fn hex_param(params: &Value, name: &str) -> Result<Vec<u8>, CryptoError> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| CryptoError::ParseError(format!("Missing keystore parameter {}", name)))
+        .and_then(decode_hex)
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, CryptoError> {
+    hex::decode(s).map_err(|e| CryptoError::ParseError(e.to_string()))
+}
+
+fn io_error(e: std::io::Error) -> CryptoError {
+    CryptoError::GeneralError(format!("Keystore I/O error: {}", e))
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, data: &[u8]) -> Result<(), CryptoError> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .and_then(|mut f| f.write_all(data))
+        .map_err(io_error)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, data: &[u8]) -> Result<(), CryptoError> {
+    fs::write(path, data).map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAST: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[test]
+    fn encrypt_decrypt() {
+        let secret = [3u8; 32];
+        let keystore = Keystore::encrypt(&secret, &[4u8; 48], None, b"testpassword", FAST)
+            .unwrap()
+            .with_description("validator")
+            .with_path("m/12381/3600/0/0/0");
+        assert_eq!(36, keystore.uuid.len());
+        assert_eq!('4', keystore.uuid.chars().nth(14).unwrap());
+
+        let json = keystore.to_json().unwrap();
+        let parsed = Keystore::from_json(&json).unwrap();
+        assert_eq!(keystore, parsed);
+        assert!(!json.contains("keytype"));
+        assert_eq!(
+            &secret[..],
+            &parsed.decrypt(b"testpassword").unwrap().expose_secret()[..]
+        );
+        assert!(parsed.decrypt(b"wrong").is_err());
+
+        let rotated = parsed
+            .change_passphrase(b"testpassword", b"new", FAST)
+            .unwrap();
+        assert_eq!(keystore.uuid, rotated.uuid);
+        assert_eq!("m/12381/3600/0/0/0", rotated.path);
+        assert_eq!(
+            &secret[..],
+            &rotated.decrypt(b"new").unwrap().expose_secret()[..]
+        );
+        assert!(rotated.decrypt(b"testpassword").is_err());
+        assert!(parsed.change_passphrase(b"wrong", b"new", FAST).is_err());
+
+        let mut tampered = parsed.clone();
+        tampered.crypto.cipher.message.replace_range(..2, "00");
+        assert!(tampered.decrypt(b"testpassword").is_err());
+        let mut unsupported = parsed.clone();
+        unsupported.crypto.kdf.function = "bcrypt".to_string();
+        assert!(unsupported.decrypt(b"testpassword").is_err());
+        let mut future = parsed;
+        future.version = 5;
+        assert!(future.decrypt(b"testpassword").is_err());
+    }
+
+    /// The PBKDF2 test vector of EIP-2335
+    const EIP2335_PBKDF2: &str = r#"{
+        "crypto": {
+            "kdf": {
+                "function": "pbkdf2",
+                "params": {
+                    "dklen": 32,
+                    "c": 262144,
+                    "prf": "hmac-sha256",
+                    "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                },
+                "message": ""
+            },
+            "checksum": {
+                "function": "sha256",
+                "params": {},
+                "message": "8a9f5d9912ed7e75ea794bc5a89bca5f193721d30868ade6f73043c6ea6febf1"
+            },
+            "cipher": {
+                "function": "aes-128-ctr",
+                "params": {
+                    "iv": "264daa3f303d7259501c93d997d84fe6"
+                },
+                "message": "cee03fde2af33149775b7223e7845e4fb2c8ae1792e5f99fe9ecf474cc8c16ad"
+            }
+        },
+        "description": "This is a test keystore that uses PBKDF2 to secure the secret.",
+        "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+        "path": "m/12381/60/0/0",
+        "uuid": "64625def-3331-4eea-ab6f-782f3ed16a83",
+        "version": 4
+    }"#;
+
+    #[test]
+    fn eip2335() {
+        let secret =
+            hex::decode("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f")
+                .unwrap();
+        let passphrase = "\u{1d531}\u{1d522}\u{1d530}\u{1d531}\u{1d52d}\u{1d51e}\u{1d530}\u{1d530}\u{1d534}\u{1d52c}\u{1d52f}\u{1d521}\u{1f511}";
+        let keystore = Keystore::from_json(EIP2335_PBKDF2).unwrap();
+        assert!(keystore.is_eip2335());
+        assert_eq!(
+            &secret[..],
+            &keystore
+                .decrypt(passphrase.as_bytes())
+                .unwrap()
+                .expose_secret()[..]
+        );
+        let pubkey = hex::decode(&keystore.pubkey).unwrap();
+        for kdf in &[
+            Eip2335Kdf::Scrypt { n: 16, r: 8, p: 1 },
+            Eip2335Kdf::Pbkdf2 { c: 2 },
+        ] {
+            let keystore = Keystore::encrypt_eip2335(&secret, &pubkey, b"testpassword", *kdf)
+                .unwrap()
+                .with_path("m/12381/3600/0/0/0");
+            assert!(keystore.is_eip2335());
+            assert!(keystore.key_type.is_none());
+            let parsed = Keystore::from_json(&keystore.to_json().unwrap()).unwrap();
+            assert_eq!(
+                &secret[..],
+                &parsed.decrypt(b"testpassword").unwrap().expose_secret()[..]
+            );
+            assert!(parsed.decrypt(b"wrong").is_err());
+
+            let rotated = parsed
+                .change_passphrase(b"testpassword", b"new", FAST)
+                .unwrap();
+            assert_eq!(parsed.crypto.kdf.function, rotated.crypto.kdf.function);
+            assert_eq!(
+                parsed.crypto.kdf.params["dklen"],
+                rotated.crypto.kdf.params["dklen"]
+            );
+            assert_eq!(
+                &secret[..],
+                &rotated.decrypt(b"new").unwrap().expose_secret()[..]
+            );
+        }
+        assert!(Keystore::encrypt_eip2335(
+            &secret,
+            &pubkey,
+            b"a",
+            Eip2335Kdf::Scrypt { n: 3, r: 8, p: 1 }
+        )
+        .is_err());
+        // Normalized to the same passphrase
+        let keystore = Keystore::encrypt_eip2335(
+            &secret,
+            &pubkey,
+            passphrase.as_bytes(),
+            Eip2335Kdf::Pbkdf2 { c: 2 },
+        )
+        .unwrap();
+        assert_eq!(
+            &secret[..],
+            &keystore
+                .decrypt("test\u{7f}password\u{1f511}".as_bytes())
+                .unwrap()
+                .expose_secret()[..]
+        );
+        assert!(keystore.decrypt(b"testpassword").is_err());
+        assert!(
+            Keystore::encrypt_eip2335(&secret, &pubkey, &[0xff], Eip2335Kdf::Pbkdf2 { c: 2 })
+                .is_err()
+        );
+    }
+
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    #[test]
+    fn keystore_dir() {
+        use signatures::{ed25519::Ed25519Sha512, SignatureScheme};
+
+        let dir = std::env::temp_dir().join(format!("ursa-keystore-{}", OsRng.next_u64()));
+        let store = KeystoreDir::open(&dir).unwrap();
+        let scheme = Ed25519Sha512::new();
+        let (pk, sk) = scheme.keypair(None).unwrap();
+        let keystore = Keystore::from_private_key(&scheme, &sk, b"pass", FAST).unwrap();
+        assert_eq!(Some("ed25519".to_string()), keystore.key_type);
+        assert_eq!(hex::encode(&pk[..]), keystore.pubkey);
+        store.save(&keystore).unwrap();
+        fs::write(dir.join("notes.json"), "{}").unwrap();
+
+        let listed = store.list().unwrap();
+        assert_eq!(1, listed.len());
+        assert_eq!(keystore, listed[0]);
+        let (pk2, sk2) = store
+            .load(&keystore.uuid)
+            .unwrap()
+            .to_keypair(&scheme, b"pass")
+            .unwrap();
+        assert_eq!(pk, pk2);
+        assert_eq!(sk, sk2);
+
+        store
+            .change_passphrase(&keystore.uuid, b"pass", b"word", FAST)
+            .unwrap();
+        let loaded = store.load(&keystore.uuid).unwrap();
+        assert!(loaded.to_keypair(&scheme, b"pass").is_err());
+        assert_eq!(sk, loaded.to_keypair(&scheme, b"word").unwrap().1);
+
+        assert!(store.load("../secret").is_err());
+        store.remove(&keystore.uuid).unwrap();
+        assert!(store.list().unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,13 @@
 #![deny(unused_import_braces, trivial_numeric_casts)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std as alloc;
+#[cfg(not(feature = "std"))]
+extern crate core as std;
 
 #[cfg(all(feature = "ecdsa_secp256k1", feature = "ecdsa_secp256k1_native"))]
 compile_error!("Cannot compile both features 'ecdsa_sepc256k1' and 'ecdsa_secp256k1_native'");
@@ -25,27 +34,44 @@ extern crate block_modes;
 extern crate block_padding;
 #[cfg(feature = "hmac")]
 extern crate hmac;
+#[cfg(feature = "jni")]
+extern crate jni;
 #[cfg(any(test, feature = "libsodium-ffi"))]
 extern crate libsodium_ffi;
 #[cfg(any(test, feature = "openssl"))]
 extern crate openssl;
+#[cfg(feature = "pbkdf2")]
+extern crate pbkdf2;
 #[cfg(feature = "rand")]
 extern crate rand;
 #[cfg(feature = "rand_chacha")]
 extern crate rand_chacha;
+#[cfg(feature = "rust-argon2")]
+extern crate argon2;
+#[cfg(feature = "rustpkcs11")]
+extern crate rustpkcs11;
 #[cfg(feature = "rustchacha20poly1305")]
 extern crate rustchacha20poly1305;
-#[cfg(feature = "subtle")]
+#[cfg(feature = "scrypt")]
+extern crate scrypt;
 extern crate subtle;
 #[cfg(feature = "lazy_static")]
 #[macro_use]
 extern crate lazy_static;
 #[cfg(feature = "blake2")]
 pub extern crate blake2;
+#[cfg(feature = "blake2b_simd")]
+extern crate blake2b_simd;
+#[cfg(feature = "blst")]
+extern crate blst;
+#[cfg(feature = "memmap2")]
+extern crate memmap2;
 #[cfg(test)]
 extern crate bytebuffer;
 #[cfg(feature = "ed25519-dalek")]
 extern crate ed25519_dalek;
+#[cfg(feature = "sha-1")]
+extern crate sha1;
 #[cfg(feature = "sha2")]
 pub extern crate sha2;
 #[cfg(feature = "sha3")]
@@ -55,6 +81,9 @@ extern crate zeroize;
 #[cfg(feature = "arrayref")]
 #[macro_use]
 extern crate arrayref;
+extern crate ursa_core;
+#[cfg(feature = "ursa_sharing")]
+extern crate ursa_sharing;
 #[cfg(feature = "amcl_wrapper")]
 extern crate amcl_wrapper;
 #[cfg(feature = "failure")]
@@ -87,12 +116,15 @@ extern crate rustlibsecp256k1;
 extern crate secp256k1 as libsecp256k1;
 #[cfg(feature = "serde")]
 extern crate serde;
-#[cfg(any(test, feature = "ffi"))]
+#[cfg(feature = "serde_cbor")]
+extern crate serde_cbor;
+#[cfg(any(test, feature = "ffi", feature = "jose", feature = "keystore"))]
 #[cfg_attr(
     any(
         feature = "cl",
         feature = "cl_native",
         feature = "ffi",
+        feature = "keystore",
         feature = "wasm"
     ),
     macro_use
@@ -103,6 +135,8 @@ extern crate serde_json;
 extern crate ffi_support;
 #[cfg(feature = "console_error_panic_hook")]
 extern crate console_error_panic_hook;
+#[cfg(feature = "ctr")]
+extern crate ctr;
 #[cfg(feature = "curve25519-dalek")]
 extern crate curve25519_dalek;
 #[cfg(feature = "hex")]
@@ -111,6 +145,8 @@ extern crate hex;
 extern crate hkdf;
 #[cfg(feature = "js-sys")]
 extern crate js_sys;
+#[cfg(feature = "unicode-normalization")]
+extern crate unicode_normalization;
 #[cfg(feature = "wasm-bindgen")]
 extern crate wasm_bindgen;
 #[cfg(feature = "x25519-dalek")]
@@ -136,6 +172,24 @@ extern crate x25519_dalek;
 #[macro_use]
 pub mod utils;
 
+#[cfg(all(
+    feature = "std",
+    any(
+        feature = "bls_bls12381",
+        feature = "ecdh_secp256k1",
+        feature = "ecdh_secp256k1_native",
+        feature = "ecdh_secp256k1_asm",
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm",
+        feature = "ed25519",
+        feature = "ed25519_asm",
+        feature = "x25519",
+        feature = "x25519_asm",
+        feature = "wasm"
+    )
+))]
+pub mod backend;
 #[cfg(any(feature = "bls_bn254", feature = "bls_bn254_asm"))]
 pub mod bls;
 #[cfg(any(feature = "cl_native", feature = "sharing_native"))]
@@ -146,6 +200,8 @@ pub mod bn;
 pub mod bn;
 #[cfg(any(feature = "cl", feature = "cl_native"))]
 pub mod cl;
+#[cfg(feature = "drbg")]
+pub mod drbg;
 #[cfg(any(
     feature = "aescbc",
     feature = "aescbc_native",
@@ -155,6 +211,21 @@ pub mod cl;
     feature = "chacha20poly1305_native"
 ))]
 pub mod encryption;
+#[cfg(any(
+    feature = "bls_bls12381",
+    feature = "ecdh_secp256k1",
+    feature = "ecdh_secp256k1_native",
+    feature = "ecdh_secp256k1_asm",
+    feature = "ecdsa_secp256k1",
+    feature = "ecdsa_secp256k1_native",
+    feature = "ecdsa_secp256k1_asm",
+    feature = "ed25519",
+    feature = "ed25519_asm",
+    feature = "x25519",
+    feature = "x25519_asm",
+    feature = "wasm"
+))]
+pub mod encoding;
 #[cfg(any(
     feature = "bls_bn254",
     feature = "bls_bn254_asm",
@@ -168,10 +239,30 @@ pub mod encryption;
     feature = "wasm"
 ))]
 pub mod errors;
+#[cfg(feature = "eth2")]
+pub mod eth2;
 #[cfg(feature = "ffi")]
 pub mod ffi;
 #[cfg(any(feature = "blake2", feature = "sha2", feature = "sha3"))]
 pub mod hash;
+#[cfg(all(
+    feature = "hd",
+    any(
+        feature = "ecdh_secp256k1",
+        feature = "ecdh_secp256k1_native",
+        feature = "ecdh_secp256k1_asm",
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm",
+        feature = "ed25519",
+        feature = "ed25519_asm",
+        feature = "x25519",
+        feature = "x25519_asm"
+    )
+))]
+pub mod hd;
+#[cfg(feature = "java")]
+pub mod java;
 #[cfg(any(
     feature = "ecdh_secp256k1",
     feature = "ecdh_secp256k1_native",
@@ -182,6 +273,7 @@ pub mod hash;
 pub mod kex;
 #[cfg(any(
     feature = "bls_bls12381",
+    feature = "bls_ietf",
     feature = "ecdh_secp256k1",
     feature = "ecdh_secp256k1_native",
     feature = "ecdh_secp256k1_asm",
@@ -195,6 +287,42 @@ pub mod kex;
     feature = "wasm"
 ))]
 pub mod keys;
+#[cfg(all(
+    feature = "std",
+    any(
+        feature = "bls_bls12381",
+        feature = "ecdh_secp256k1",
+        feature = "ecdh_secp256k1_native",
+        feature = "ecdh_secp256k1_asm",
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm",
+        feature = "ed25519",
+        feature = "ed25519_asm",
+        feature = "x25519",
+        feature = "x25519_asm",
+        feature = "wasm"
+    )
+))]
+pub mod keyring;
+#[cfg(all(
+    feature = "keystore",
+    any(
+        feature = "ecdh_secp256k1",
+        feature = "ecdh_secp256k1_native",
+        feature = "ecdh_secp256k1_asm",
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm",
+        feature = "ed25519",
+        feature = "ed25519_asm",
+        feature = "x25519",
+        feature = "x25519_asm"
+    )
+))]
+pub mod keystore;
+#[cfg(feature = "otp")]
+pub mod otp;
 #[cfg(any(
     feature = "bls_bn254",
     feature = "bls_bn254_asm",
@@ -203,6 +331,24 @@ pub mod keys;
 ))]
 #[path = "pair/amcl.rs"]
 pub mod pair;
+#[cfg(all(
+    feature = "std",
+    any(
+        feature = "ecdh_secp256k1",
+        feature = "ecdh_secp256k1_native",
+        feature = "ecdh_secp256k1_asm",
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm",
+        feature = "ed25519",
+        feature = "ed25519_asm",
+        feature = "x25519",
+        feature = "x25519_asm"
+    )
+))]
+pub mod registry;
+#[cfg(feature = "secure_channel")]
+pub mod secure_channel;
 #[cfg(any(feature = "sharing", feature = "sharing_native"))]
 pub mod sharing;
 #[cfg(any(
@@ -211,12 +357,15 @@ pub mod sharing;
     feature = "ecdsa_secp256k1",
     feature = "ecdsa_secp256k1_native",
     feature = "ecdsa_secp256k1_asm",
-    feature = "bls_bls12381"
+    feature = "bls_bls12381",
+    feature = "bls_ietf"
 ))]
 pub mod signatures;
 #[cfg(feature = "wasm")]
 pub mod wasm;
 
+use alloc::string::{String, ToString};
+
 pub type CryptoResult<T> = Result<T, CryptoError>;
 
 #[derive(Debug)]
@@ -234,6 +383,9 @@ pub enum CryptoError {
     DigestGenError(String),
     /// A General purpose error message that doesn't fit in any category
     GeneralError(String),
+    /// Returned when a decoded curve point is the identity or outside the
+    /// prime order subgroup
+    InvalidPoint(String),
 }
 
 impl std::fmt::Display for CryptoError {
@@ -245,10 +397,48 @@ impl std::fmt::Display for CryptoError {
             CryptoError::KeyGenError(s) => write!(f, "KeyGenError({})", s),
             CryptoError::DigestGenError(s) => write!(f, "DigestGenError({})", s),
             CryptoError::GeneralError(m) => write!(f, "GeneralError({})", m),
+            CryptoError::InvalidPoint(s) => write!(f, "InvalidPoint({})", s),
+        }
+    }
+}
+
+impl CryptoError {
+    /// The stable code of this kind of error from `ursa_core::error::codes`
+    pub fn code(&self) -> i32 {
+        use ursa_core::error::codes;
+
+        match self {
+            CryptoError::NoSuchAlgorithm(_) => codes::NO_SUCH_ALGORITHM,
+            CryptoError::ParseError(_) => codes::PARSE_ERROR,
+            CryptoError::SigningError(_) => codes::SIGNING_ERROR,
+            CryptoError::KeyGenError(_) => codes::KEY_GEN_ERROR,
+            CryptoError::DigestGenError(_) => codes::DIGEST_GEN_ERROR,
+            CryptoError::GeneralError(_) => codes::GENERAL_ERROR,
+            CryptoError::InvalidPoint(_) => codes::INVALID_GROUP_ELEMENT,
         }
     }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for CryptoError {}
+
+impl From<CryptoError> for ursa_core::UrsaError {
+    fn from(error: CryptoError) -> Self {
+        use ursa_core::ErrorKind;
+
+        let (kind, message) = match &error {
+            CryptoError::NoSuchAlgorithm(s) => (ErrorKind::NoSuchAlgorithm, s.clone()),
+            CryptoError::ParseError(s) => (ErrorKind::Parse, s.clone()),
+            CryptoError::SigningError(s) => (ErrorKind::Signing, s.clone()),
+            CryptoError::KeyGenError(s) => (ErrorKind::KeyGen, s.clone()),
+            CryptoError::DigestGenError(s) => (ErrorKind::Digest, s.clone()),
+            CryptoError::GeneralError(s) => (ErrorKind::General, s.clone()),
+            CryptoError::InvalidPoint(s) => (ErrorKind::Parse, s.clone()),
+        };
+        ursa_core::UrsaError::with_source(kind, message, error)
+    }
+}
+
 #[cfg(feature = "secp256k1")]
 impl From<libsecp256k1::Error> for CryptoError {
     fn from(error: libsecp256k1::Error) -> CryptoError {
@@ -281,6 +471,12 @@ impl From<libsecp256k1::Error> for CryptoError {
     }
 }
 
+impl From<ursa_core::CborError> for CryptoError {
+    fn from(error: ursa_core::CborError) -> CryptoError {
+        CryptoError::ParseError(error.to_string())
+    }
+}
+
 #[cfg(any(
     feature = "bls_bn254",
     feature = "bls_bn254_asm",
@@ -0,0 +1,304 @@
+//! One-time passwords for two factor authentication.
+//!
+//! `Hotp` implements the counter based passwords of RFC 4226 and `Totp` the
+//! time based passwords of RFC 6238, which authenticator apps generate from
+//! a shared secret. Codes are compared in constant time.
+//!
+//! # Example
+//! ```
+//! use ursa::otp::Totp;
+//!
+//! let totp = Totp::new(b"12345678901234567890").digits(8).skew(1);
+//! let code = totp.generate_at(59).unwrap();
+//! assert_eq!(code, "94287082");
+//! // Accepted one period later, the step it matched guards against replays
+//! assert_eq!(totp.verify_at(&code, 89).unwrap(), Some(1));
+//! ```
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::digest::generic_array::ArrayLength;
+use sha2::digest::{BlockInput, FixedOutput, Input, Reset};
+use sha2::{Sha256, Sha512};
+use std::fmt;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+use CryptoError;
+
+/// Fewest digits in a code
+pub const MIN_DIGITS: u32 = 6;
+/// Most digits in a code, the truncated MAC is 31 bits
+pub const MAX_DIGITS: u32 = 9;
+
+/// The hash function of the HMAC
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OtpAlgorithm {
+    /// The default of RFC 4226 and most authenticator apps
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+fn hmac<D>(key: &[u8], counter: u64) -> Result<Vec<u8>, CryptoError>
+where
+    D: Input + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+    D::OutputSize: ArrayLength<u8>,
+{
+    let mut mac = Hmac::<D>::new_varkey(key)
+        .map_err(|_| CryptoError::KeyGenError("Invalid HMAC key".to_string()))?;
+    mac.input(&counter.to_be_bytes());
+    Ok(mac.result().code().to_vec())
+}
+
+/// Counter based one-time passwords from RFC 4226.
+///
+/// The secret is zeroed when dropped.
+#[derive(Clone)]
+pub struct Hotp {
+    secret: Vec<u8>,
+    algorithm: OtpAlgorithm,
+    digits: u32,
+}
+
+impl Hotp {
+    /// Six digit codes with HMAC-SHA-1
+    pub fn new(secret: &[u8]) -> Self {
+        Hotp {
+            secret: secret.to_vec(),
+            algorithm: OtpAlgorithm::Sha1,
+            digits: MIN_DIGITS,
+        }
+    }
+
+    pub fn algorithm(mut self, algorithm: OtpAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// `MIN_DIGITS` to `MAX_DIGITS`, checked when a code is generated
+    pub fn digits(mut self, digits: u32) -> Self {
+        self.digits = digits;
+        self
+    }
+
+    /// The code for `counter`
+    pub fn generate(&self, counter: u64) -> Result<String, CryptoError> {
+        if self.digits < MIN_DIGITS || self.digits > MAX_DIGITS {
+            return Err(CryptoError::GeneralError(format!(
+                "Codes have {} to {} digits, found {}",
+                MIN_DIGITS, MAX_DIGITS, self.digits
+            )));
+        }
+        if self.secret.is_empty() {
+            return Err(CryptoError::KeyGenError("The secret is empty".to_string()));
+        }
+        let mac = match self.algorithm {
+            OtpAlgorithm::Sha1 => hmac::<Sha1>(&self.secret, counter)?,
+            OtpAlgorithm::Sha256 => hmac::<Sha256>(&self.secret, counter)?,
+            OtpAlgorithm::Sha512 => hmac::<Sha512>(&self.secret, counter)?,
+        };
+        // Dynamic truncation, section 5.3
+        let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+        let mut binary = [0u8; 4];
+        binary.copy_from_slice(&mac[offset..offset + 4]);
+        let value = u32::from_be_bytes(binary) & 0x7fff_ffff;
+        Ok(format!(
+            "{:0width$}",
+            value % 10u32.pow(self.digits),
+            width = self.digits as usize
+        ))
+    }
+
+    /// The counter in `counter..=counter + look_ahead` that `code` was
+    /// generated for. Every counter is checked so the time taken doesn't
+    /// reveal which one matched.
+    pub fn verify(
+        &self,
+        code: &str,
+        counter: u64,
+        look_ahead: u64,
+    ) -> Result<Option<u64>, CryptoError> {
+        let mut matched = None;
+        for c in counter..=counter.saturating_add(look_ahead) {
+            let expected = self.generate(c)?;
+            if bool::from(expected.as_bytes().ct_eq(code.as_bytes())) && matched.is_none() {
+                matched = Some(c);
+            }
+        }
+        Ok(matched)
+    }
+}
+
+impl Drop for Hotp {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+/// Leaves out the secret
+impl fmt::Debug for Hotp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hotp")
+            .field("algorithm", &self.algorithm)
+            .field("digits", &self.digits)
+            .finish()
+    }
+}
+
+/// Time based one-time passwords from RFC 6238.
+///
+/// The counter is the number of `period` second steps since the Unix epoch.
+/// `skew` steps either side of the current one are accepted to allow for
+/// clock drift and the time taken to enter a code.
+#[derive(Clone, Debug)]
+pub struct Totp {
+    hotp: Hotp,
+    period: u64,
+    skew: u64,
+}
+
+impl Totp {
+    /// Six digit codes with HMAC-SHA-1 that change every 30 seconds and are
+    /// only accepted in their own period
+    pub fn new(secret: &[u8]) -> Self {
+        Totp {
+            hotp: Hotp::new(secret),
+            period: 30,
+            skew: 0,
+        }
+    }
+
+    pub fn algorithm(mut self, algorithm: OtpAlgorithm) -> Self {
+        self.hotp = self.hotp.algorithm(algorithm);
+        self
+    }
+
+    /// `MIN_DIGITS` to `MAX_DIGITS`, checked when a code is generated
+    pub fn digits(mut self, digits: u32) -> Self {
+        self.hotp = self.hotp.digits(digits);
+        self
+    }
+
+    /// Seconds a code is valid for, must not be 0
+    pub fn period(mut self, period: u64) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Number of periods before and after the current one that are accepted
+    pub fn skew(mut self, skew: u64) -> Self {
+        self.skew = skew;
+        self
+    }
+
+    /// The time step of `unix_time`
+    pub fn step(&self, unix_time: u64) -> Result<u64, CryptoError> {
+        unix_time
+            .checked_div(self.period)
+            .ok_or_else(|| CryptoError::GeneralError("The period must not be 0".to_string()))
+    }
+
+    /// The code at `unix_time` seconds
+    pub fn generate_at(&self, unix_time: u64) -> Result<String, CryptoError> {
+        self.hotp.generate(self.step(unix_time)?)
+    }
+
+    /// The time step `code` was generated in if it is accepted at
+    /// `unix_time`. Servers should reject a second code from the same or
+    /// an earlier step.
+    pub fn verify_at(&self, code: &str, unix_time: u64) -> Result<Option<u64>, CryptoError> {
+        let step = self.step(unix_time)?;
+        let first = step.saturating_sub(self.skew);
+        let look_ahead = step - first + self.skew;
+        self.hotp.verify(code, first, look_ahead)
+    }
+
+    /// The current code
+    #[cfg(feature = "std")]
+    pub fn generate(&self) -> Result<String, CryptoError> {
+        self.generate_at(unix_time()?)
+    }
+
+    /// `verify_at` the current time
+    #[cfg(feature = "std")]
+    pub fn verify(&self, code: &str) -> Result<Option<u64>, CryptoError> {
+        self.verify_at(code, unix_time()?)
+    }
+}
+
+#[cfg(feature = "std")]
+fn unix_time() -> Result<u64, CryptoError> {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .map_err(|e| CryptoError::GeneralError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET_SHA1: &[u8] = b"12345678901234567890";
+    const SECRET_SHA256: &[u8] = b"12345678901234567890123456789012";
+    const SECRET_SHA512: &[u8] =
+        b"1234567890123456789012345678901234567890123456789012345678901234";
+
+    #[test]
+    fn hotp_rfc4226() {
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        let hotp = Hotp::new(SECRET_SHA1);
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(hotp.generate(counter as u64).unwrap(), *code);
+        }
+        assert_eq!(hotp.verify("969429", 1, 5).unwrap(), Some(3));
+        assert_eq!(hotp.verify("969429", 4, 5).unwrap(), None);
+        assert_eq!(hotp.verify("96942", 1, 5).unwrap(), None);
+    }
+
+    #[test]
+    fn totp_rfc6238() {
+        let vectors = [
+            (59u64, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+        let sha1 = Totp::new(SECRET_SHA1).digits(8);
+        let sha256 = Totp::new(SECRET_SHA256)
+            .algorithm(OtpAlgorithm::Sha256)
+            .digits(8);
+        let sha512 = Totp::new(SECRET_SHA512)
+            .algorithm(OtpAlgorithm::Sha512)
+            .digits(8);
+        for (time, code1, code256, code512) in vectors.iter() {
+            assert_eq!(sha1.generate_at(*time).unwrap(), *code1);
+            assert_eq!(sha256.generate_at(*time).unwrap(), *code256);
+            assert_eq!(sha512.generate_at(*time).unwrap(), *code512);
+        }
+    }
+
+    #[test]
+    fn totp_skew() {
+        let totp = Totp::new(SECRET_SHA1).skew(1);
+        let code = totp.generate_at(300).unwrap();
+        assert_eq!(totp.verify_at(&code, 300).unwrap(), Some(10));
+        assert_eq!(totp.verify_at(&code, 270).unwrap(), Some(10));
+        assert_eq!(totp.verify_at(&code, 339).unwrap(), Some(10));
+        assert_eq!(totp.verify_at(&code, 360).unwrap(), None);
+        assert_eq!(totp.clone().skew(0).verify_at(&code, 330).unwrap(), None);
+        assert_eq!(totp.verify_at(&code, 0).unwrap(), None);
+
+        assert!(totp.clone().period(0).generate_at(300).is_err());
+        assert!(totp.clone().digits(5).generate_at(300).is_err());
+        assert!(Totp::new(b"").generate_at(300).is_err());
+    }
+}
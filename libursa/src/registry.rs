@@ -0,0 +1,569 @@
+//! Runtime dispatch over the supported key algorithms.
+//!
+//! [`AnyKeyPair`] and [`AnyPublicKey`] carry their [`Algorithm`], so storage
+//! layers and RPC APIs can handle keys of every scheme with one type. With
+//! the `serde` feature they serialize with an `algorithm` tag:
+//!
+//! ```json
+//! {"algorithm":"ed25519","public_key":"...","private_key":"..."}
+//! {"algorithm":"ed25519","public_key":"..."}
+//! ```
+//!
+//! With the `protobuf` feature they convert to and from the messages of
+//! [`encoding::proto`], tagged with [`Algorithm::name`].
+//!
+//! Only the algorithms enabled by the crate features are available.
+
+#[cfg(feature = "protobuf")]
+use encoding::proto;
+#[cfg(any(
+    feature = "ecdh_secp256k1",
+    feature = "ecdh_secp256k1_native",
+    feature = "ecdh_secp256k1_asm"
+))]
+use kex::secp256k1::EcdhSecp256k1Sha256;
+#[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+use kex::x25519::X25519Sha256;
+#[cfg(any(
+    feature = "ecdh_secp256k1",
+    feature = "ecdh_secp256k1_native",
+    feature = "ecdh_secp256k1_asm",
+    feature = "x25519",
+    feature = "x25519_asm"
+))]
+use kex::KeyExchangeScheme;
+use keys::{KeyGenOption, PrivateKey, PublicKey, SessionKey};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+use signatures::ed25519::Ed25519Sha512;
+#[cfg(any(
+    feature = "ecdsa_secp256k1",
+    feature = "ecdsa_secp256k1_native",
+    feature = "ecdsa_secp256k1_asm"
+))]
+use signatures::secp256k1::EcdsaSecp256k1Sha256;
+#[cfg(any(
+    feature = "ed25519",
+    feature = "ed25519_asm",
+    feature = "ecdsa_secp256k1",
+    feature = "ecdsa_secp256k1_native",
+    feature = "ecdsa_secp256k1_asm"
+))]
+use signatures::SignatureScheme;
+#[cfg(feature = "protobuf")]
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use CryptoError;
+
+/// The key algorithms that can be chosen at runtime
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    Ed25519,
+    #[cfg(any(
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    ))]
+    EcdsaSecp256k1,
+    #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+    X25519,
+    #[cfg(any(
+        feature = "ecdh_secp256k1",
+        feature = "ecdh_secp256k1_native",
+        feature = "ecdh_secp256k1_asm"
+    ))]
+    EcdhSecp256k1,
+}
+
+impl Algorithm {
+    /// Every algorithm enabled in this build
+    pub fn all() -> Vec<Algorithm> {
+        vec![
+            #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+            Algorithm::Ed25519,
+            #[cfg(any(
+                feature = "ecdsa_secp256k1",
+                feature = "ecdsa_secp256k1_native",
+                feature = "ecdsa_secp256k1_asm"
+            ))]
+            Algorithm::EcdsaSecp256k1,
+            #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+            Algorithm::X25519,
+            #[cfg(any(
+                feature = "ecdh_secp256k1",
+                feature = "ecdh_secp256k1_native",
+                feature = "ecdh_secp256k1_asm"
+            ))]
+            Algorithm::EcdhSecp256k1,
+        ]
+    }
+
+    /// The name used by `Display`, `FromStr` and serde
+    pub fn name(self) -> &'static str {
+        match self {
+            #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+            Algorithm::Ed25519 => "ed25519",
+            #[cfg(any(
+                feature = "ecdsa_secp256k1",
+                feature = "ecdsa_secp256k1_native",
+                feature = "ecdsa_secp256k1_asm"
+            ))]
+            Algorithm::EcdsaSecp256k1 => "ecdsa_secp256k1",
+            #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+            Algorithm::X25519 => "x25519",
+            #[cfg(any(
+                feature = "ecdh_secp256k1",
+                feature = "ecdh_secp256k1_native",
+                feature = "ecdh_secp256k1_asm"
+            ))]
+            Algorithm::EcdhSecp256k1 => "ecdh_secp256k1",
+        }
+    }
+
+    /// Create a keypair, see `SignatureScheme::keypair` and
+    /// `KeyExchangeScheme::keypair` for the `options`
+    pub fn keypair(self, options: Option<KeyGenOption>) -> Result<AnyKeyPair, CryptoError> {
+        let (public_key, private_key) = match self {
+            #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+            Algorithm::Ed25519 => Ed25519Sha512::new().keypair(options)?,
+            #[cfg(any(
+                feature = "ecdsa_secp256k1",
+                feature = "ecdsa_secp256k1_native",
+                feature = "ecdsa_secp256k1_asm"
+            ))]
+            Algorithm::EcdsaSecp256k1 => EcdsaSecp256k1Sha256::new().keypair(options)?,
+            #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+            Algorithm::X25519 => X25519Sha256::new().keypair(options)?,
+            #[cfg(any(
+                feature = "ecdh_secp256k1",
+                feature = "ecdh_secp256k1_native",
+                feature = "ecdh_secp256k1_asm"
+            ))]
+            Algorithm::EcdhSecp256k1 => EcdhSecp256k1Sha256::new().keypair(options)?,
+        };
+        Ok(AnyKeyPair::new(
+            self,
+            KeyPair {
+                public_key,
+                private_key,
+            },
+        ))
+    }
+
+    #[allow(unreachable_patterns)]
+    fn sign(self, message: &[u8], sk: &PrivateKey) -> Result<Vec<u8>, CryptoError> {
+        match self {
+            #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+            Algorithm::Ed25519 => Ed25519Sha512::new().sign(message, sk),
+            #[cfg(any(
+                feature = "ecdsa_secp256k1",
+                feature = "ecdsa_secp256k1_native",
+                feature = "ecdsa_secp256k1_asm"
+            ))]
+            Algorithm::EcdsaSecp256k1 => EcdsaSecp256k1Sha256::new().sign(message, sk),
+            a => Err(a.unsupported("signing")),
+        }
+    }
+
+    #[allow(unreachable_patterns)]
+    fn verify(self, message: &[u8], signature: &[u8], pk: &PublicKey) -> Result<bool, CryptoError> {
+        match self {
+            #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+            Algorithm::Ed25519 => Ed25519Sha512::new().verify(message, signature, pk),
+            #[cfg(any(
+                feature = "ecdsa_secp256k1",
+                feature = "ecdsa_secp256k1_native",
+                feature = "ecdsa_secp256k1_asm"
+            ))]
+            Algorithm::EcdsaSecp256k1 => EcdsaSecp256k1Sha256::new().verify(message, signature, pk),
+            a => Err(a.unsupported("signing")),
+        }
+    }
+
+    #[allow(unreachable_patterns)]
+    fn key_exchange(self, sk: &PrivateKey, pk: &PublicKey) -> Result<SessionKey, CryptoError> {
+        match self {
+            #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+            Algorithm::X25519 => X25519Sha256::new().compute_shared_secret(sk, pk),
+            #[cfg(any(
+                feature = "ecdh_secp256k1",
+                feature = "ecdh_secp256k1_native",
+                feature = "ecdh_secp256k1_asm"
+            ))]
+            Algorithm::EcdhSecp256k1 => EcdhSecp256k1Sha256::new().compute_shared_secret(sk, pk),
+            a => Err(a.unsupported("key exchange")),
+        }
+    }
+
+    fn unsupported(self, operation: &str) -> CryptoError {
+        CryptoError::NoSuchAlgorithm(format!("{} does not support {}", self, operation))
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Algorithm::all()
+            .into_iter()
+            .find(|a| a.name() == s)
+            .ok_or_else(|| CryptoError::NoSuchAlgorithm(s.to_string()))
+    }
+}
+
+/// A public key and its private key
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPair {
+    pub public_key: PublicKey,
+    pub private_key: PrivateKey,
+}
+
+/// A keypair of any supported algorithm
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "algorithm", rename_all = "snake_case"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnyKeyPair {
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    Ed25519(KeyPair),
+    #[cfg(any(
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    ))]
+    EcdsaSecp256k1(KeyPair),
+    #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+    X25519(KeyPair),
+    #[cfg(any(
+        feature = "ecdh_secp256k1",
+        feature = "ecdh_secp256k1_native",
+        feature = "ecdh_secp256k1_asm"
+    ))]
+    EcdhSecp256k1(KeyPair),
+}
+
+impl AnyKeyPair {
+    /// Wrap the keys of `algorithm`
+    pub fn new(algorithm: Algorithm, keys: KeyPair) -> Self {
+        match algorithm {
+            #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+            Algorithm::Ed25519 => AnyKeyPair::Ed25519(keys),
+            #[cfg(any(
+                feature = "ecdsa_secp256k1",
+                feature = "ecdsa_secp256k1_native",
+                feature = "ecdsa_secp256k1_asm"
+            ))]
+            Algorithm::EcdsaSecp256k1 => AnyKeyPair::EcdsaSecp256k1(keys),
+            #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+            Algorithm::X25519 => AnyKeyPair::X25519(keys),
+            #[cfg(any(
+                feature = "ecdh_secp256k1",
+                feature = "ecdh_secp256k1_native",
+                feature = "ecdh_secp256k1_asm"
+            ))]
+            Algorithm::EcdhSecp256k1 => AnyKeyPair::EcdhSecp256k1(keys),
+        }
+    }
+
+    /// Create a random keypair
+    pub fn generate(algorithm: Algorithm) -> Result<Self, CryptoError> {
+        algorithm.keypair(None)
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.parts().0
+    }
+
+    pub fn keys(&self) -> &KeyPair {
+        self.parts().1
+    }
+
+    pub fn public_key(&self) -> AnyPublicKey {
+        AnyPublicKey::new(self.algorithm(), self.keys().public_key.clone())
+    }
+
+    pub fn private_key(&self) -> &PrivateKey {
+        &self.keys().private_key
+    }
+
+    /// Sign `message`. Fails for key exchange algorithms.
+    pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let (algorithm, keys) = self.parts();
+        algorithm.sign(message, &keys.private_key)
+    }
+
+    /// Verify a signature with the public key
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<bool, CryptoError> {
+        let (algorithm, keys) = self.parts();
+        algorithm.verify(message, signature, &keys.public_key)
+    }
+
+    /// Compute the shared secret with `peer`, which must be of the same
+    /// algorithm. Fails for signature algorithms.
+    pub fn key_exchange(&self, peer: &AnyPublicKey) -> Result<SessionKey, CryptoError> {
+        let (algorithm, keys) = self.parts();
+        if peer.algorithm() != algorithm {
+            return Err(CryptoError::GeneralError(format!(
+                "Can't exchange a {} key with a {} key",
+                algorithm,
+                peer.algorithm()
+            )));
+        }
+        algorithm.key_exchange(&keys.private_key, peer.key())
+    }
+
+    fn parts(&self) -> (Algorithm, &KeyPair) {
+        match self {
+            #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+            AnyKeyPair::Ed25519(k) => (Algorithm::Ed25519, k),
+            #[cfg(any(
+                feature = "ecdsa_secp256k1",
+                feature = "ecdsa_secp256k1_native",
+                feature = "ecdsa_secp256k1_asm"
+            ))]
+            AnyKeyPair::EcdsaSecp256k1(k) => (Algorithm::EcdsaSecp256k1, k),
+            #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+            AnyKeyPair::X25519(k) => (Algorithm::X25519, k),
+            #[cfg(any(
+                feature = "ecdh_secp256k1",
+                feature = "ecdh_secp256k1_native",
+                feature = "ecdh_secp256k1_asm"
+            ))]
+            AnyKeyPair::EcdhSecp256k1(k) => (Algorithm::EcdhSecp256k1, k),
+        }
+    }
+}
+
+/// A public key of any supported algorithm
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "algorithm", content = "public_key", rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnyPublicKey {
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    Ed25519(PublicKey),
+    #[cfg(any(
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    ))]
+    EcdsaSecp256k1(PublicKey),
+    #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+    X25519(PublicKey),
+    #[cfg(any(
+        feature = "ecdh_secp256k1",
+        feature = "ecdh_secp256k1_native",
+        feature = "ecdh_secp256k1_asm"
+    ))]
+    EcdhSecp256k1(PublicKey),
+}
+
+impl AnyPublicKey {
+    /// Wrap a public key of `algorithm`
+    pub fn new(algorithm: Algorithm, key: PublicKey) -> Self {
+        match algorithm {
+            #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+            Algorithm::Ed25519 => AnyPublicKey::Ed25519(key),
+            #[cfg(any(
+                feature = "ecdsa_secp256k1",
+                feature = "ecdsa_secp256k1_native",
+                feature = "ecdsa_secp256k1_asm"
+            ))]
+            Algorithm::EcdsaSecp256k1 => AnyPublicKey::EcdsaSecp256k1(key),
+            #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+            Algorithm::X25519 => AnyPublicKey::X25519(key),
+            #[cfg(any(
+                feature = "ecdh_secp256k1",
+                feature = "ecdh_secp256k1_native",
+                feature = "ecdh_secp256k1_asm"
+            ))]
+            Algorithm::EcdhSecp256k1 => AnyPublicKey::EcdhSecp256k1(key),
+        }
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.parts().0
+    }
+
+    pub fn key(&self) -> &PublicKey {
+        self.parts().1
+    }
+
+    /// Verify `signature`. Fails for key exchange algorithms.
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<bool, CryptoError> {
+        let (algorithm, key) = self.parts();
+        algorithm.verify(message, signature, key)
+    }
+
+    fn parts(&self) -> (Algorithm, &PublicKey) {
+        match self {
+            #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+            AnyPublicKey::Ed25519(k) => (Algorithm::Ed25519, k),
+            #[cfg(any(
+                feature = "ecdsa_secp256k1",
+                feature = "ecdsa_secp256k1_native",
+                feature = "ecdsa_secp256k1_asm"
+            ))]
+            AnyPublicKey::EcdsaSecp256k1(k) => (Algorithm::EcdsaSecp256k1, k),
+            #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
+            AnyPublicKey::X25519(k) => (Algorithm::X25519, k),
+            #[cfg(any(
+                feature = "ecdh_secp256k1",
+                feature = "ecdh_secp256k1_native",
+                feature = "ecdh_secp256k1_asm"
+            ))]
+            AnyPublicKey::EcdhSecp256k1(k) => (Algorithm::EcdhSecp256k1, k),
+        }
+    }
+}
+
+#[cfg(feature = "protobuf")]
+impl AnyKeyPair {
+    /// Sign `message` into a signature message tagged with the algorithm
+    pub fn sign_proto(&self, message: &[u8]) -> Result<proto::Signature, CryptoError> {
+        Ok(proto::Signature {
+            algorithm: self.algorithm().name().to_string(),
+            signature: self.sign(message)?,
+        })
+    }
+}
+
+#[cfg(feature = "protobuf")]
+impl AnyPublicKey {
+    /// Verify a signature message, which must be of the algorithm of this key
+    pub fn verify_proto(
+        &self,
+        message: &[u8],
+        signature: &proto::Signature,
+    ) -> Result<bool, CryptoError> {
+        if signature.algorithm != self.algorithm().name() {
+            return Err(CryptoError::ParseError(format!(
+                "Expected a {} signature, found {}",
+                self.algorithm(),
+                signature.algorithm
+            )));
+        }
+        self.verify(message, &signature.signature)
+    }
+}
+
+#[cfg(feature = "protobuf")]
+impl<'a> From<&'a AnyPublicKey> for proto::PublicKey {
+    fn from(key: &'a AnyPublicKey) -> Self {
+        proto::PublicKey {
+            algorithm: key.algorithm().name().to_string(),
+            key: key.key().0.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "protobuf")]
+impl<'a> TryFrom<&'a proto::PublicKey> for AnyPublicKey {
+    type Error = CryptoError;
+
+    fn try_from(key: &'a proto::PublicKey) -> Result<Self, CryptoError> {
+        let algorithm = key.algorithm.parse()?;
+        Ok(AnyPublicKey::new(algorithm, PublicKey(key.key.clone())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        for algorithm in Algorithm::all() {
+            assert_eq!(algorithm, algorithm.to_string().parse().unwrap());
+        }
+        assert!("rsa".parse::<Algorithm>().is_err());
+    }
+
+    #[test]
+    fn dispatch() {
+        for algorithm in Algorithm::all() {
+            let keys = AnyKeyPair::generate(algorithm).unwrap();
+            assert_eq!(algorithm, keys.algorithm());
+            assert_eq!(algorithm, keys.public_key().algorithm());
+            match keys.sign(b"message") {
+                Ok(signature) => {
+                    assert!(keys.public_key().verify(b"message", &signature).unwrap());
+                    assert!(keys.key_exchange(&keys.public_key()).is_err());
+                }
+                Err(_) => {
+                    let peer = AnyKeyPair::generate(algorithm).unwrap();
+                    assert_eq!(
+                        keys.key_exchange(&peer.public_key()).unwrap(),
+                        peer.key_exchange(&keys.public_key()).unwrap()
+                    );
+                    assert!(keys.verify(b"message", &[0u8; 64]).is_err());
+                }
+            }
+        }
+    }
+
+    #[cfg(all(
+        feature = "protobuf",
+        any(feature = "ed25519", feature = "ed25519_asm"),
+        any(feature = "x25519", feature = "x25519_asm")
+    ))]
+    #[test]
+    fn protobuf_messages() {
+        let keys = AnyKeyPair::generate(Algorithm::Ed25519).unwrap();
+        let pk = proto::PublicKey::from(&keys.public_key());
+        assert_eq!("ed25519", pk.algorithm);
+        let pk = AnyPublicKey::try_from(&pk).unwrap();
+        assert_eq!(keys.public_key(), pk);
+
+        let mut signature = keys.sign_proto(b"message").unwrap();
+        assert!(pk.verify_proto(b"message", &signature).unwrap());
+        signature.algorithm = Algorithm::X25519.name().to_string();
+        assert!(pk.verify_proto(b"message", &signature).is_err());
+
+        let unknown = proto::PublicKey {
+            algorithm: "rsa".to_string(),
+            key: vec![1u8; 32],
+        };
+        assert!(AnyPublicKey::try_from(&unknown).is_err());
+    }
+
+    #[cfg(all(
+        feature = "serde",
+        any(feature = "ed25519", feature = "ed25519_asm"),
+        any(feature = "x25519", feature = "x25519_asm")
+    ))]
+    #[test]
+    fn tagged_serde() {
+        let keys = AnyKeyPair::generate(Algorithm::Ed25519).unwrap();
+        let json = serde_json::to_value(&keys).unwrap();
+        assert_eq!("ed25519", json["algorithm"]);
+        assert!(json["public_key"].is_string());
+        assert!(json["private_key"].is_string());
+        assert_eq!(keys, serde_json::from_value(json).unwrap());
+
+        let pk = keys.public_key();
+        let json = serde_json::to_string(&pk).unwrap();
+        assert!(json.starts_with(r#"{"algorithm":"ed25519","public_key":""#));
+        assert_eq!(pk, serde_json::from_str(&json).unwrap());
+
+        let other = AnyKeyPair::generate(Algorithm::X25519).unwrap();
+        assert!(keys.key_exchange(&other.public_key()).is_err());
+        assert_ne!(
+            serde_json::to_string(&AnyPublicKey::new(Algorithm::X25519, pk.key().clone())).unwrap(),
+            json
+        );
+    }
+}
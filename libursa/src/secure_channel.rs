@@ -0,0 +1,492 @@
+//! Encrypted channels between two parties built from a key exchange and an AEAD.
+//!
+//! The initiator sends an ephemeral public key, the responder answers with its
+//! own, and both derive one ChaCha20-Poly1305 key per direction with HKDF-SHA256
+//! over the shared secret. Messages are then sealed into frames of a 4 byte big
+//! endian length followed by the ciphertext.
+//!
+//! Frames carry no nonce: each side counts the frames it has sent or accepted,
+//! so a replayed, reordered or dropped frame fails to decrypt and the channel
+//! only moves forward on frames in order. Keys are replaced every
+//! [`REKEY_INTERVAL`] frames so a compromised key does not expose earlier traffic.
+//!
+//! The handshake itself is not authenticated. Peers with long term keys should
+//! sign [`Channel::handshake_hash`] and check each other's signature, or mix
+//! something only they share into the prologue.
+//!
+//! ```
+//! use ursa::kex::x25519::X25519Sha256;
+//! use ursa::secure_channel::{respond, Initiator};
+//!
+//! let (initiator, hello) = Initiator::<X25519Sha256>::new(b"my app v1").unwrap();
+//! let (mut server, reply) = respond::<X25519Sha256>(b"my app v1", &hello).unwrap();
+//! let mut client = initiator.finish(&reply).unwrap();
+//!
+//! let frame = client.writer().seal(b"hello").unwrap();
+//! assert_eq!(server.reader().open(&frame).unwrap(), b"hello");
+//! ```
+
+use encryption::symm::chacha20poly1305::ChaCha20Poly1305;
+use hkdf::Hkdf;
+use kex::KeyExchangeScheme;
+use keys::{PrivateKey, PublicKey};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use zeroize::Zeroize;
+
+use aead::{generic_array::GenericArray, Aead, NewAead, Payload};
+use CryptoError;
+
+/// Bound into every handshake hash
+pub const PROTOCOL_NAME: &[u8] = b"ursa secure channel v1";
+/// The largest message a frame can carry
+pub const MAX_MESSAGE_SIZE: usize = 1 << 24;
+/// Frames sent under each key before it is replaced
+pub const REKEY_INTERVAL: u64 = 1 << 20;
+
+const LENGTH_SIZE: usize = 4;
+const TAG_SIZE: usize = 16;
+const KEY_SIZE: usize = 32;
+const INITIATOR_INFO: &[u8] = b"initiator to responder";
+const RESPONDER_INFO: &[u8] = b"responder to initiator";
+
+/// The side of the handshake that speaks first
+pub struct Initiator<K: KeyExchangeScheme> {
+    scheme: K,
+    prologue: Vec<u8>,
+    public_key: PublicKey,
+    private_key: PrivateKey,
+}
+
+impl<K: KeyExchangeScheme> Initiator<K> {
+    /// Start a handshake and return the message for the responder.
+    /// `prologue` is any context both sides must agree on, like a protocol
+    /// name and version.
+    pub fn new(prologue: &[u8]) -> Result<(Self, Vec<u8>), CryptoError> {
+        Self::new_with_rng(prologue, &mut OsRng)
+    }
+
+    pub fn new_with_rng<R: CryptoRng + RngCore>(
+        prologue: &[u8],
+        rng: &mut R,
+    ) -> Result<(Self, Vec<u8>), CryptoError> {
+        let scheme = K::new();
+        let (public_key, private_key) = scheme.keypair_with_rng(rng)?;
+        let message = public_key.0.clone();
+        Ok((
+            Initiator {
+                scheme,
+                prologue: prologue.to_vec(),
+                public_key,
+                private_key,
+            },
+            message,
+        ))
+    }
+
+    /// Complete the handshake with the responder's reply
+    pub fn finish(self, reply: &[u8]) -> Result<Channel, CryptoError> {
+        let remote = read_public_key::<K>(reply)?;
+        let mut keys = derive_keys(
+            &self.scheme,
+            &self.prologue,
+            &self.private_key,
+            &remote,
+            (&self.public_key, &remote),
+        )?;
+        let channel = Channel::new(&keys.initiator, &keys.responder, keys.handshake_hash);
+        keys.zeroize();
+        Ok(channel)
+    }
+}
+
+/// Answer an initiator's `message`, returning the channel and the reply to
+/// send back
+pub fn respond<K: KeyExchangeScheme>(
+    prologue: &[u8],
+    message: &[u8],
+) -> Result<(Channel, Vec<u8>), CryptoError> {
+    respond_with_rng::<K, _>(prologue, message, &mut OsRng)
+}
+
+pub fn respond_with_rng<K: KeyExchangeScheme, R: CryptoRng + RngCore>(
+    prologue: &[u8],
+    message: &[u8],
+    rng: &mut R,
+) -> Result<(Channel, Vec<u8>), CryptoError> {
+    let remote = read_public_key::<K>(message)?;
+    let scheme = K::new();
+    let (public_key, private_key) = scheme.keypair_with_rng(rng)?;
+    let mut keys = derive_keys(
+        &scheme,
+        prologue,
+        &private_key,
+        &remote,
+        (&remote, &public_key),
+    )?;
+    let channel = Channel::new(&keys.responder, &keys.initiator, keys.handshake_hash);
+    keys.zeroize();
+    Ok((channel, public_key.0.clone()))
+}
+
+/// Both halves of an established channel
+#[derive(Debug)]
+pub struct Channel {
+    writer: ChannelWriter,
+    reader: ChannelReader,
+    handshake_hash: [u8; 32],
+}
+
+impl Channel {
+    fn new(send_key: &[u8], receive_key: &[u8], handshake_hash: [u8; 32]) -> Self {
+        Channel {
+            writer: ChannelWriter(CipherState::new(send_key)),
+            reader: ChannelReader(CipherState::new(receive_key)),
+            handshake_hash,
+        }
+    }
+
+    /// A hash of the prologue and both handshake messages, the same on both
+    /// sides. Signing it with a long term key authenticates the channel.
+    pub fn handshake_hash(&self) -> &[u8; 32] {
+        &self.handshake_hash
+    }
+
+    pub fn writer(&mut self) -> &mut ChannelWriter {
+        &mut self.writer
+    }
+
+    pub fn reader(&mut self) -> &mut ChannelReader {
+        &mut self.reader
+    }
+
+    /// Separate the halves, e.g. to send and receive on different threads
+    pub fn split(self) -> (ChannelWriter, ChannelReader) {
+        (self.writer, self.reader)
+    }
+}
+
+/// Seals messages into frames for the peer
+#[derive(Debug)]
+pub struct ChannelWriter(CipherState);
+
+impl ChannelWriter {
+    /// Encrypt `message` into the next frame
+    pub fn seal(&mut self, message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if message.len() > MAX_MESSAGE_SIZE {
+            return Err(CryptoError::GeneralError(format!(
+                "Message of {} bytes is larger than a frame",
+                message.len()
+            )));
+        }
+        let header = ((message.len() + TAG_SIZE) as u32).to_be_bytes();
+        let ciphertext = self.0.apply(|cipher, nonce| {
+            cipher.encrypt(
+                nonce,
+                Payload {
+                    msg: message,
+                    aad: &header,
+                },
+            )
+        })?;
+        let mut frame = Vec::with_capacity(LENGTH_SIZE + ciphertext.len());
+        frame.extend_from_slice(&header);
+        frame.extend_from_slice(&ciphertext);
+        Ok(frame)
+    }
+
+    /// Seal `message` and write the frame to `output`
+    pub fn write<W: Write>(&mut self, output: &mut W, message: &[u8]) -> Result<(), CryptoError> {
+        let frame = self.seal(message)?;
+        output
+            .write_all(&frame)
+            .map_err(|e| CryptoError::GeneralError(format!("Unable to write frame: {}", e)))
+    }
+
+    /// Replace the key now instead of waiting for [`REKEY_INTERVAL`] frames.
+    /// The reader must call [`ChannelReader::rekey`] at the same frame.
+    pub fn rekey(&mut self) {
+        self.0.rekey()
+    }
+}
+
+/// Opens the peer's frames in the order they were sealed
+#[derive(Debug)]
+pub struct ChannelReader(CipherState);
+
+impl ChannelReader {
+    /// Decrypt the next frame. A frame that fails is not counted, so the
+    /// reader still expects the same frame afterwards.
+    pub fn open(&mut self, frame: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if frame.len() < LENGTH_SIZE + TAG_SIZE {
+            return Err(CryptoError::ParseError("Frame is too short".to_string()));
+        }
+        let (header, ciphertext) = frame.split_at(LENGTH_SIZE);
+        if frame_length(header)? != ciphertext.len() {
+            return Err(CryptoError::ParseError(
+                "Frame length does not match its header".to_string(),
+            ));
+        }
+        self.0.apply(|cipher, nonce| {
+            cipher.decrypt(
+                nonce,
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+        })
+    }
+
+    /// Read the next frame from `input` and decrypt it
+    pub fn read<R: Read>(&mut self, input: &mut R) -> Result<Vec<u8>, CryptoError> {
+        let mut frame = vec![0u8; LENGTH_SIZE];
+        read_exact(input, &mut frame)?;
+        let length = frame_length(&frame)?;
+        frame.resize(LENGTH_SIZE + length, 0);
+        read_exact(input, &mut frame[LENGTH_SIZE..])?;
+        self.open(&frame)
+    }
+
+    /// Replace the key now, matching [`ChannelWriter::rekey`] on the peer
+    pub fn rekey(&mut self) {
+        self.0.rekey()
+    }
+}
+
+/// One direction's key and frame counter
+struct CipherState {
+    key: [u8; KEY_SIZE],
+    counter: u64,
+}
+
+impl CipherState {
+    fn new(key: &[u8]) -> Self {
+        let mut state = CipherState {
+            key: [0u8; KEY_SIZE],
+            counter: 0,
+        };
+        state.key.copy_from_slice(key);
+        state
+    }
+
+    /// Run `f` with the nonce of the next frame and count the frame if it
+    /// succeeds
+    fn apply<F>(&mut self, f: F) -> Result<Vec<u8>, CryptoError>
+    where
+        F: FnOnce(
+            &ChaCha20Poly1305,
+            &GenericArray<u8, <ChaCha20Poly1305 as Aead>::NonceSize>,
+        ) -> Result<Vec<u8>, aead::Error>,
+    {
+        if self.counter == u64::MAX {
+            return Err(CryptoError::GeneralError(
+                "Channel has sent all the frames it can".to_string(),
+            ));
+        }
+        let cipher = ChaCha20Poly1305::new(GenericArray::from_slice(&self.key));
+        let output = f(&cipher, &nonce(self.counter))
+            .map_err(|_| CryptoError::GeneralError("Invalid frame".to_string()))?;
+        self.counter += 1;
+        if self.counter % REKEY_INTERVAL == 0 {
+            self.rekey();
+        }
+        Ok(output)
+    }
+
+    /// The next key is the encryption of zeros under the maximum nonce,
+    /// which no frame uses
+    fn rekey(&mut self) {
+        let cipher = ChaCha20Poly1305::new(GenericArray::from_slice(&self.key));
+        let mut next = cipher
+            .encrypt(&nonce(u64::MAX), &[0u8; KEY_SIZE][..])
+            .expect("ChaCha20-Poly1305 encrypts any short message");
+        self.key.copy_from_slice(&next[..KEY_SIZE]);
+        next.zeroize();
+    }
+}
+
+impl std::fmt::Debug for CipherState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("CipherState")
+            .field("counter", &self.counter)
+            .finish()
+    }
+}
+
+impl Drop for CipherState {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+struct SessionKeys {
+    initiator: [u8; KEY_SIZE],
+    responder: [u8; KEY_SIZE],
+    handshake_hash: [u8; 32],
+}
+
+impl Zeroize for SessionKeys {
+    fn zeroize(&mut self) {
+        self.initiator.zeroize();
+        self.responder.zeroize();
+    }
+}
+
+fn derive_keys<K: KeyExchangeScheme>(
+    scheme: &K,
+    prologue: &[u8],
+    private_key: &PrivateKey,
+    remote: &PublicKey,
+    (initiator, responder): (&PublicKey, &PublicKey),
+) -> Result<SessionKeys, CryptoError> {
+    let shared = scheme.compute_shared_secret(private_key, remote)?;
+
+    let mut handshake_hash = [0u8; 32];
+    handshake_hash.copy_from_slice(
+        &Sha256::new()
+            .chain(PROTOCOL_NAME)
+            .chain((prologue.len() as u64).to_be_bytes())
+            .chain(prologue)
+            .chain(&initiator[..])
+            .chain(&responder[..])
+            .result(),
+    );
+
+    let hkdf = Hkdf::<Sha256>::new(Some(&handshake_hash), &shared[..]);
+    let mut keys = SessionKeys {
+        initiator: [0u8; KEY_SIZE],
+        responder: [0u8; KEY_SIZE],
+        handshake_hash,
+    };
+    hkdf.expand(INITIATOR_INFO, &mut keys.initiator)
+        .and_then(|_| hkdf.expand(RESPONDER_INFO, &mut keys.responder))
+        .map_err(|_| CryptoError::KeyGenError("Unable to derive channel keys".to_string()))?;
+    Ok(keys)
+}
+
+fn read_public_key<K: KeyExchangeScheme>(message: &[u8]) -> Result<PublicKey, CryptoError> {
+    if message.len() != K::public_key_size() {
+        return Err(CryptoError::ParseError(format!(
+            "Expected a {} byte handshake message, found {}",
+            K::public_key_size(),
+            message.len()
+        )));
+    }
+    Ok(PublicKey(message.to_vec()))
+}
+
+fn frame_length(header: &[u8]) -> Result<usize, CryptoError> {
+    let mut length = [0u8; LENGTH_SIZE];
+    length.copy_from_slice(header);
+    let length = u32::from_be_bytes(length) as usize;
+    if !(TAG_SIZE..=MAX_MESSAGE_SIZE + TAG_SIZE).contains(&length) {
+        return Err(CryptoError::ParseError(format!(
+            "Invalid frame length {}",
+            length
+        )));
+    }
+    Ok(length)
+}
+
+fn read_exact<R: Read>(input: &mut R, buffer: &mut [u8]) -> Result<(), CryptoError> {
+    input
+        .read_exact(buffer)
+        .map_err(|e| CryptoError::GeneralError(format!("Unable to read frame: {}", e)))
+}
+
+/// ChaCha20-Poly1305 nonce for frame `counter`
+fn nonce(counter: u64) -> GenericArray<u8, <ChaCha20Poly1305 as Aead>::NonceSize> {
+    let mut nonce = GenericArray::default();
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    nonce
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kex::x25519::X25519Sha256;
+
+    fn connect() -> (Channel, Channel) {
+        let (initiator, hello) = Initiator::<X25519Sha256>::new(b"test").unwrap();
+        let (server, reply) = respond::<X25519Sha256>(b"test", &hello).unwrap();
+        (initiator.finish(&reply).unwrap(), server)
+    }
+
+    #[test]
+    fn handshake() {
+        let (client, server) = connect();
+        assert_eq!(client.handshake_hash(), server.handshake_hash());
+
+        let (initiator, hello) = Initiator::<X25519Sha256>::new(b"test").unwrap();
+        let (mut server, reply) = respond::<X25519Sha256>(b"other", &hello).unwrap();
+        let mut client = initiator.finish(&reply).unwrap();
+        assert_ne!(client.handshake_hash(), server.handshake_hash());
+        let frame = client.writer().seal(b"hello").unwrap();
+        assert!(server.reader().open(&frame).is_err());
+
+        assert!(respond::<X25519Sha256>(b"test", &hello[1..]).is_err());
+    }
+
+    #[test]
+    fn low_order_keys() {
+        // Both points give the all zero shared secret for any private key
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        for key in &[[0u8; 32], one] {
+            assert!(respond::<X25519Sha256>(b"test", key).is_err());
+            let (initiator, _) = Initiator::<X25519Sha256>::new(b"test").unwrap();
+            assert!(initiator.finish(key).is_err());
+        }
+    }
+
+    #[test]
+    fn frames() {
+        let (mut client, mut server) = connect();
+        let first = client.writer().seal(b"first").unwrap();
+        let second = client.writer().seal(b"").unwrap();
+        assert_eq!(first.len(), LENGTH_SIZE + 5 + TAG_SIZE);
+
+        // Out of order and replayed frames are rejected
+        assert!(server.reader().open(&second).is_err());
+        assert_eq!(server.reader().open(&first).unwrap(), b"first");
+        assert!(server.reader().open(&first).is_err());
+        assert_eq!(server.reader().open(&second).unwrap(), b"");
+
+        let mut tampered = client.writer().seal(b"third").unwrap();
+        tampered[LENGTH_SIZE] ^= 1;
+        assert!(server.reader().open(&tampered).is_err());
+        assert!(server.reader().open(&tampered[..LENGTH_SIZE + 3]).is_err());
+
+        // The directions use different keys
+        let reply = server.writer().seal(b"reply").unwrap();
+        assert!(server.reader().open(&reply).is_err());
+        assert_eq!(client.reader().open(&reply).unwrap(), b"reply");
+    }
+
+    #[test]
+    fn streams_and_rekeying() {
+        let (client, server) = connect();
+        let (mut writer, _) = client.split();
+        let (_, mut reader) = server.split();
+
+        let mut wire = Vec::new();
+        writer.write(&mut wire, b"one").unwrap();
+        writer.rekey();
+        writer.write(&mut wire, b"two").unwrap();
+        writer.write(&mut wire, &[7u8; 1000]).unwrap();
+
+        let mut input = wire.as_slice();
+        assert_eq!(reader.read(&mut input).unwrap(), b"one");
+        let mut stale = input;
+        assert!(reader.read(&mut stale).is_err());
+        reader.rekey();
+        assert_eq!(reader.read(&mut input).unwrap(), b"two");
+        assert_eq!(reader.read(&mut input).unwrap(), vec![7u8; 1000]);
+        assert!(reader.read(&mut input).is_err());
+
+        let mut huge = &[0xffu8, 0xff, 0xff, 0xff][..];
+        assert!(reader.read(&mut huge).is_err());
+    }
+}
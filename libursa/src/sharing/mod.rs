@@ -1 +1,2 @@
 pub mod shamir;
+pub mod threshold_rsa;
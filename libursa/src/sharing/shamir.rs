@@ -214,7 +214,6 @@ impl Polynomial {
 }
 
 /// Represents a share created from a split
-#[derive(Debug)]
 pub struct Share {
     /// x-coordinate
     pub identifier: u8,
@@ -239,6 +238,14 @@ impl Share {
     }
 }
 
+impl std::fmt::Debug for Share {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Share")
+            .field("identifier", &self.identifier)
+            .finish()
+    }
+}
+
 impl Clone for Share {
     fn clone(&self) -> Self {
         Self {
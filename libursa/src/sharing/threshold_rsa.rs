@@ -0,0 +1,668 @@
+//! Threshold RSA signatures as described by Victor Shoup in
+//! "Practical Threshold Signatures"
+//! (see <https://www.iacr.org/archive/eurocrypt2000/1807/18070209-new.pdf>).
+//!
+//! A dealer splits the private exponent of an RSA key into `total` shares,
+//! any `threshold` of which can sign. Each share holder raises the encoded
+//! message to its share and proves in zero knowledge that it used the share
+//! behind its public verification key. A combiner checks the proofs and
+//! turns the partial signatures into an ordinary PKCS#1 v1.5 or PSS
+//! signature with SHA-256, which verifies under the RSA public key like any
+//! other, so relying parties don't know the key is shared.
+//!
+//! The proofs are only sound when the modulus is the product of two safe
+//! primes, as made by `generate`. `split_key` also accepts an existing key
+//! so it can stay in use, but the partial signatures of shares split from a
+//! key with other primes must be trusted.
+
+use bn::BigNumber;
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeSet, fmt};
+
+use {CryptoError, CryptoResult};
+
+/// The public exponent of keys made by `generate`
+pub const PUBLIC_EXPONENT: usize = 65537;
+
+const HASH_SIZE: usize = 32;
+const PSS_SALT_SIZE: usize = HASH_SIZE;
+/// The DER encoded `DigestInfo` of SHA-256 before the hash, RFC 8017 section 9.2
+const SHA256_DIGEST_INFO: [u8; 19] = [
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05,
+    0x00, 0x04, 0x20,
+];
+
+/// An RSA public key
+#[derive(Debug, PartialEq, Eq)]
+pub struct RsaPublicKey {
+    pub modulus: BigNumber,
+    pub exponent: BigNumber,
+}
+
+impl RsaPublicKey {
+    /// The length of the modulus and of signatures in bytes
+    pub fn size(&self) -> CryptoResult<usize> {
+        Ok((self.modulus.num_bits()? as usize).div_ceil(8))
+    }
+
+    /// Verify a PKCS#1 v1.5 signature of the SHA-256 hash of `message`
+    pub fn verify_pkcs1v15(&self, message: &[u8], signature: &[u8]) -> CryptoResult<bool> {
+        let expected = encode_pkcs1v15(message, self)?;
+        Ok(match self.open(signature)? {
+            Some(representative) => representative == expected.0,
+            None => false,
+        })
+    }
+
+    /// Verify a PSS signature of `message` with SHA-256, MGF1 and a 32 byte salt
+    pub fn verify_pss(&self, message: &[u8], signature: &[u8]) -> CryptoResult<bool> {
+        let representative = match self.open(signature)? {
+            Some(representative) => representative,
+            None => return Ok(false),
+        };
+        let em_bits = self.modulus.num_bits()? as usize - 1;
+        let em_len = em_bits.div_ceil(8);
+        let em = match i2osp(&representative, em_len) {
+            Ok(em) => em,
+            Err(_) => return Ok(false),
+        };
+        if em_len < HASH_SIZE + PSS_SALT_SIZE + 2 || em[em_len - 1] != 0xbc {
+            return Ok(false);
+        }
+        let (masked_db, h) = em[..em_len - 1].split_at(em_len - HASH_SIZE - 1);
+        let top_bits = 8 * em_len - em_bits;
+        if masked_db[0] & !(0xff >> top_bits) != 0 {
+            return Ok(false);
+        }
+        let mut db = mgf1(h, masked_db.len());
+        db.iter_mut().zip(masked_db).for_each(|(d, m)| *d ^= m);
+        db[0] &= 0xff >> top_bits;
+        let (padding, salt) = db.split_at(db.len() - PSS_SALT_SIZE);
+        let (zeros, one) = padding.split_at(padding.len() - 1);
+        if zeros.iter().any(|b| *b != 0) || one != [1] {
+            return Ok(false);
+        }
+        Ok(pss_hash(&Sha256::digest(message), salt)[..] == *h)
+    }
+
+    /// The representative of `signature` or `None` if it isn't one
+    fn open(&self, signature: &[u8]) -> CryptoResult<Option<BigNumber>> {
+        if signature.len() != self.size()? {
+            return Ok(None);
+        }
+        let signature = BigNumber::from_bytes(signature)?;
+        if signature >= self.modulus {
+            return Ok(None);
+        }
+        Ok(Some(signature.mod_exp(
+            &self.exponent,
+            &self.modulus,
+            None,
+        )?))
+    }
+}
+
+/// A message encoded for signing with an RSA key.
+/// The combiner encodes the message and sends it to the share holders.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EncodedMessage(BigNumber);
+
+impl EncodedMessage {
+    pub fn to_bytes(&self, public_key: &RsaPublicKey) -> CryptoResult<Vec<u8>> {
+        i2osp(&self.0, public_key.size()?)
+    }
+
+    /// Fails unless `bytes` is less than the modulus
+    pub fn from_bytes(bytes: &[u8], public_key: &RsaPublicKey) -> CryptoResult<Self> {
+        let value = BigNumber::from_bytes(bytes)?;
+        if value >= public_key.modulus {
+            return Err(CryptoError::ParseError(
+                "Encoded message is larger than the modulus".to_string(),
+            ));
+        }
+        Ok(EncodedMessage(value))
+    }
+}
+
+/// Encode the SHA-256 hash of `message` for a PKCS#1 v1.5 signature
+pub fn encode_pkcs1v15(message: &[u8], public_key: &RsaPublicKey) -> CryptoResult<EncodedMessage> {
+    let size = public_key.size()?;
+    let t_len = SHA256_DIGEST_INFO.len() + HASH_SIZE;
+    if size < t_len + 11 {
+        return Err(CryptoError::SigningError(
+            "The modulus is too small for a PKCS#1 v1.5 signature".to_string(),
+        ));
+    }
+    let mut em = vec![0xffu8; size];
+    em[0] = 0;
+    em[1] = 1;
+    em[size - t_len - 1] = 0;
+    em[size - t_len..size - HASH_SIZE].copy_from_slice(&SHA256_DIGEST_INFO);
+    em[size - HASH_SIZE..].copy_from_slice(&Sha256::digest(message));
+    Ok(EncodedMessage(BigNumber::from_bytes(&em)?))
+}
+
+/// Encode `message` for a PSS signature with SHA-256, MGF1 and a random 32 byte salt
+pub fn encode_pss(message: &[u8], public_key: &RsaPublicKey) -> CryptoResult<EncodedMessage> {
+    let em_bits = public_key.modulus.num_bits()? as usize - 1;
+    let em_len = em_bits.div_ceil(8);
+    if em_len < HASH_SIZE + PSS_SALT_SIZE + 2 {
+        return Err(CryptoError::SigningError(
+            "The modulus is too small for a PSS signature".to_string(),
+        ));
+    }
+    let mut salt = [0u8; PSS_SALT_SIZE];
+    OsRng.fill_bytes(&mut salt);
+    let h = pss_hash(&Sha256::digest(message), &salt);
+    let mut db = vec![0u8; em_len - HASH_SIZE - 1];
+    let salt_start = db.len() - PSS_SALT_SIZE;
+    db[salt_start - 1] = 1;
+    db[salt_start..].copy_from_slice(&salt);
+    db.iter_mut()
+        .zip(mgf1(&h, em_len - HASH_SIZE - 1))
+        .for_each(|(d, m)| *d ^= m);
+    db[0] &= 0xff >> (8 * em_len - em_bits);
+    db.extend_from_slice(&h);
+    db.push(0xbc);
+    Ok(EncodedMessage(BigNumber::from_bytes(&db)?))
+}
+
+/// `H(0x00 * 8 || mHash || salt)` from EMSA-PSS
+fn pss_hash(m_hash: &[u8], salt: &[u8]) -> Vec<u8> {
+    Sha256::new()
+        .chain([0u8; 8])
+        .chain(m_hash)
+        .chain(salt)
+        .result()
+        .to_vec()
+}
+
+fn mgf1(seed: &[u8], len: usize) -> Vec<u8> {
+    let mut mask = Vec::with_capacity(len + HASH_SIZE);
+    let mut counter = 0u32;
+    while mask.len() < len {
+        mask.extend_from_slice(
+            &Sha256::new()
+                .chain(seed)
+                .chain(counter.to_be_bytes())
+                .result(),
+        );
+        counter += 1;
+    }
+    mask.truncate(len);
+    mask
+}
+
+/// `value` as `len` big endian bytes
+fn i2osp(value: &BigNumber, len: usize) -> CryptoResult<Vec<u8>> {
+    let bytes = value.to_bytes()?;
+    let skip = bytes.iter().take_while(|b| **b == 0).count();
+    let bytes = &bytes[skip..];
+    if bytes.len() > len {
+        return Err(CryptoError::GeneralError("Integer too large".to_string()));
+    }
+    let mut out = vec![0u8; len - bytes.len()];
+    out.extend_from_slice(bytes);
+    Ok(out)
+}
+
+/// `total!`, which clears the denominators of the Lagrange coefficients
+fn delta(total: u8) -> CryptoResult<BigNumber> {
+    let mut delta = BigNumber::from_u32(1)?;
+    for i in 2..=total as u32 {
+        delta.mul_word(i)?;
+    }
+    Ok(delta)
+}
+
+/// The public key of a shared RSA key and what is needed to check the
+/// partial signatures of its shares
+#[derive(Debug)]
+pub struct ThresholdPublicKey {
+    pub public_key: RsaPublicKey,
+    pub threshold: u8,
+    pub total: u8,
+    /// A random square, the base of the verification keys
+    pub verification_base: BigNumber,
+    /// `verification_base` raised to each share, the first is share 1's
+    pub verification_keys: Vec<BigNumber>,
+}
+
+impl ThresholdPublicKey {
+    fn verification_key(&self, identifier: u8) -> CryptoResult<&BigNumber> {
+        if identifier == 0 {
+            return Err(CryptoError::GeneralError(
+                "Share must have a non-zero identifier".to_string(),
+            ));
+        }
+        self.verification_keys
+            .get(identifier as usize - 1)
+            .ok_or_else(|| CryptoError::GeneralError(format!("Unknown share {}", identifier)))
+    }
+
+    /// Check the proof that `partial` was made for `message` with the share
+    /// behind its verification key
+    pub fn verify_partial(
+        &self,
+        message: &EncodedMessage,
+        partial: &PartialSignature,
+    ) -> CryptoResult<bool> {
+        let n = &self.public_key.modulus;
+        let zero = BigNumber::new()?;
+        if partial.value <= zero || &partial.value >= n || partial.challenge.is_negative() {
+            return Ok(false);
+        }
+        let verification_key = self.verification_key(partial.identifier)?;
+        let x_tilde = message.0.mod_exp(&self.exponent(4)?, n, None)?;
+        let x_i_squared = partial.value.mod_mul(&partial.value, n, None)?;
+        let minus_c = partial.challenge.set_negative(true)?;
+        // v^z * v_i^-c and x~^z * x_i^-2c
+        let v_commitment = self
+            .verification_base
+            .mod_exp(&partial.response, n, None)?
+            .mod_mul(&verification_key.mod_exp(&minus_c, n, None)?, n, None)?;
+        let x_commitment = x_tilde.mod_exp(&partial.response, n, None)?.mod_mul(
+            &x_i_squared.mod_exp(&minus_c, n, None)?,
+            n,
+            None,
+        )?;
+        let challenge = challenge(&[
+            &self.verification_base,
+            &x_tilde,
+            verification_key,
+            &x_i_squared,
+            &v_commitment,
+            &x_commitment,
+        ])?;
+        Ok(challenge == partial.challenge)
+    }
+
+    /// Combine the partial signatures of `threshold` different shares into
+    /// a signature of `message`. Each proof is checked and the first invalid
+    /// partial signature is reported.
+    pub fn combine(
+        &self,
+        message: &EncodedMessage,
+        partials: &[PartialSignature],
+    ) -> CryptoResult<Vec<u8>> {
+        if partials.len() < self.threshold as usize {
+            return Err(CryptoError::SigningError(format!(
+                "{} partial signatures are needed, found {}",
+                self.threshold,
+                partials.len()
+            )));
+        }
+        let partials = &partials[..self.threshold as usize];
+        let mut identifiers = BTreeSet::new();
+        for partial in partials {
+            if !identifiers.insert(partial.identifier) {
+                return Err(CryptoError::SigningError(
+                    "Duplicate partial signatures cannot be combined".to_string(),
+                ));
+            }
+            if !self.verify_partial(message, partial)? {
+                return Err(CryptoError::SigningError(format!(
+                    "Partial signature of share {} is invalid",
+                    partial.identifier
+                )));
+            }
+        }
+
+        let n = &self.public_key.modulus;
+        let delta = delta(self.total)?;
+        // w = prod x_j^(2 * lambda_j) where lambda_j = delta * prod j' / (j' - j)
+        let mut w = BigNumber::from_u32(1)?;
+        for partial in partials {
+            let mut numerator = delta.try_clone()?;
+            let mut denominator = BigNumber::from_u32(1)?;
+            let j = BigNumber::from_u32(partial.identifier as usize)?;
+            for other in partials {
+                if other.identifier == partial.identifier {
+                    continue;
+                }
+                let other = BigNumber::from_u32(other.identifier as usize)?;
+                numerator = numerator.mul(&other, None)?;
+                denominator = denominator.mul(&other.sub(&j)?, None)?;
+            }
+            let lambda = numerator.div(&denominator, None)?;
+            let exponent = lambda.add(&lambda)?;
+            w = w.mod_mul(&partial.value.mod_exp(&exponent, n, None)?, n, None)?;
+        }
+
+        // w^e = x^e' where e' = 4 * delta^2, so y = w^a * x^b with a*e' + b*e = 1
+        let e = &self.public_key.exponent;
+        let e_prime = self.exponent(4)?.mul(&delta, None)?;
+        let a = e_prime.inverse(e, None)?;
+        let b = BigNumber::from_u32(1)?
+            .sub(&a.mul(&e_prime, None)?)?
+            .div(e, None)?;
+        let signature =
+            w.mod_exp(&a, n, None)?
+                .mod_mul(&message.0.mod_exp(&b, n, None)?, n, None)?;
+        if signature.mod_exp(e, n, None)? != message.0 {
+            return Err(CryptoError::SigningError(
+                "Combined signature is invalid".to_string(),
+            ));
+        }
+        i2osp(&signature, self.public_key.size()?)
+    }
+
+    /// `multiple * delta`
+    fn exponent(&self, multiple: u32) -> CryptoResult<BigNumber> {
+        let mut exponent = delta(self.total)?;
+        exponent.mul_word(multiple)?;
+        Ok(exponent)
+    }
+}
+
+/// A share of an RSA private exponent
+pub struct KeyShare {
+    pub identifier: u8,
+    pub value: BigNumber,
+}
+
+impl KeyShare {
+    /// Sign `message` with this share and prove it was done correctly
+    pub fn sign(
+        &self,
+        public_key: &ThresholdPublicKey,
+        message: &EncodedMessage,
+    ) -> CryptoResult<PartialSignature> {
+        let n = &public_key.public_key.modulus;
+        let verification_key = public_key.verification_key(self.identifier)?;
+        // x_i = x^(2 * delta * s_i)
+        let value = message
+            .0
+            .mod_exp(&public_key.exponent(2)?.mul(&self.value, None)?, n, None)?;
+
+        // Prove log_v(v_i) = log_x~(x_i^2) where x~ = x^(4 * delta)
+        let x_tilde = message.0.mod_exp(&public_key.exponent(4)?, n, None)?;
+        let x_i_squared = value.mod_mul(&value, n, None)?;
+        let r = BigNumber::rand(n.num_bits()? as usize + 2 * 8 * HASH_SIZE)?;
+        let challenge = challenge(&[
+            &public_key.verification_base,
+            &x_tilde,
+            verification_key,
+            &x_i_squared,
+            &public_key.verification_base.mod_exp(&r, n, None)?,
+            &x_tilde.mod_exp(&r, n, None)?,
+        ])?;
+        let response = self.value.mul(&challenge, None)?.add(&r)?;
+        Ok(PartialSignature {
+            identifier: self.identifier,
+            value,
+            challenge,
+            response,
+        })
+    }
+}
+
+/// Leaves out the share
+impl fmt::Debug for KeyShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyShare")
+            .field("identifier", &self.identifier)
+            .finish()
+    }
+}
+
+/// A share's signature of an encoded message with the proof that it is correct
+#[derive(Debug)]
+pub struct PartialSignature {
+    pub identifier: u8,
+    pub value: BigNumber,
+    pub challenge: BigNumber,
+    pub response: BigNumber,
+}
+
+/// The Fiat-Shamir challenge of the values of a proof
+fn challenge(values: &[&BigNumber]) -> CryptoResult<BigNumber> {
+    let mut hasher = Sha256::new();
+    for value in values {
+        let bytes = value.to_bytes()?;
+        hasher.input((bytes.len() as u32).to_be_bytes());
+        hasher.input(&bytes);
+    }
+    Ok(BigNumber::from_bytes(&hasher.result())?)
+}
+
+/// Generate an RSA key of `bits` from two safe primes and split it into
+/// `total` shares, any `threshold` of which can sign
+pub fn generate(
+    bits: usize,
+    threshold: u8,
+    total: u8,
+) -> CryptoResult<(ThresholdPublicKey, Vec<KeyShare>)> {
+    let p = BigNumber::generate_safe_prime(bits / 2)?;
+    let mut q = BigNumber::generate_safe_prime(bits - bits / 2)?;
+    while q == p {
+        q = BigNumber::generate_safe_prime(bits - bits / 2)?;
+    }
+    split_key(
+        &p,
+        &q,
+        &BigNumber::from_u32(PUBLIC_EXPONENT)?,
+        threshold,
+        total,
+    )
+}
+
+/// Split the RSA key with primes `p` and `q` and public exponent `e` into
+/// `total` shares, any `threshold` of which can sign. `e` must be a prime
+/// greater than `total`.
+pub fn split_key(
+    p: &BigNumber,
+    q: &BigNumber,
+    e: &BigNumber,
+    threshold: u8,
+    total: u8,
+) -> CryptoResult<(ThresholdPublicKey, Vec<KeyShare>)> {
+    if total < threshold {
+        return Err(CryptoError::KeyGenError(
+            "total cannot be less than the threshold".to_string(),
+        ));
+    }
+    if threshold < 2 {
+        return Err(CryptoError::KeyGenError(
+            "threshold must be at least 2".to_string(),
+        ));
+    }
+    if e <= &BigNumber::from_u32(total as usize)? || !e.is_prime(None)? {
+        return Err(CryptoError::KeyGenError(
+            "The public exponent must be a prime greater than total".to_string(),
+        ));
+    }
+    if p == q || !p.is_prime(None)? || !q.is_prime(None)? {
+        return Err(CryptoError::KeyGenError(
+            "p and q must be different primes".to_string(),
+        ));
+    }
+    let modulus = p.mul(q, None)?;
+    // The order of the squares mod n, which is p'q' for safe primes
+    let order = p.decrement()?.mul(&q.decrement()?, None)?.rshift(2)?;
+    let d = e.inverse(&order, None).map_err(|_| {
+        CryptoError::KeyGenError("The public exponent is not coprime to the order".to_string())
+    })?;
+
+    // Shares are a random polynomial over the order with d as its intercept
+    let mut coefficients = vec![d];
+    for _ in 1..threshold {
+        coefficients.push(order.rand_range()?);
+    }
+    let verification_base = BigNumber::random_qr(&modulus)?;
+    let mut shares = Vec::with_capacity(total as usize);
+    let mut verification_keys = Vec::with_capacity(total as usize);
+    for identifier in 1..=total {
+        let x = BigNumber::from_u32(identifier as usize)?;
+        let mut value = BigNumber::new()?;
+        for coefficient in coefficients.iter().rev() {
+            value = value
+                .mul(&x, None)?
+                .add(coefficient)?
+                .modulus(&order, None)?;
+        }
+        verification_keys.push(verification_base.mod_exp(&value, &modulus, None)?);
+        shares.push(KeyShare { identifier, value });
+    }
+    let public_key = ThresholdPublicKey {
+        public_key: RsaPublicKey {
+            modulus,
+            exponent: e.try_clone()?,
+        },
+        threshold,
+        total,
+        verification_base,
+        verification_keys,
+    };
+    Ok((public_key, shares))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Safe primes so the modulus is 640 bits, large enough for PSS with SHA-256
+    const P: &str =
+        "B3FCA276B7689EE14FB930A496DF9F3C8B295DD674A1DBC2342153526169759D5B2CBEF4FF5E259B";
+    const Q: &str =
+        "944A82B932872757D04942E2748E1ABDF3E9EC7B048DEF1F22B1DD58ABCB0C2A5432EEFFA26F36CB";
+
+    fn split(threshold: u8, total: u8) -> (ThresholdPublicKey, Vec<KeyShare>) {
+        split_key(
+            &BigNumber::from_hex(P).unwrap(),
+            &BigNumber::from_hex(Q).unwrap(),
+            &BigNumber::from_u32(PUBLIC_EXPONENT).unwrap(),
+            threshold,
+            total,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn pkcs1v15() {
+        let (public_key, shares) = split(3, 5);
+        let message = encode_pkcs1v15(b"wire transfer", &public_key.public_key).unwrap();
+        let partials = shares
+            .iter()
+            .map(|share| share.sign(&public_key, &message).unwrap())
+            .collect::<Vec<_>>();
+        for partial in &partials {
+            assert!(public_key.verify_partial(&message, partial).unwrap());
+        }
+
+        for subset in &[[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let subset = subset
+                .iter()
+                .map(|i| shares[*i].sign(&public_key, &message).unwrap())
+                .collect::<Vec<_>>();
+            let signature = public_key.combine(&message, &subset).unwrap();
+            assert_eq!(signature.len(), 80);
+            assert!(public_key
+                .public_key
+                .verify_pkcs1v15(b"wire transfer", &signature)
+                .unwrap());
+            assert!(!public_key
+                .public_key
+                .verify_pkcs1v15(b"wire transfers", &signature)
+                .unwrap());
+            // PKCS#1 v1.5 is deterministic
+            assert_eq!(
+                signature,
+                public_key.combine(&message, &partials[2..]).unwrap()
+            );
+        }
+
+        assert!(public_key.combine(&message, &partials[..2]).is_err());
+        let duplicates = [
+            shares[0].sign(&public_key, &message).unwrap(),
+            shares[0].sign(&public_key, &message).unwrap(),
+            shares[1].sign(&public_key, &message).unwrap(),
+        ];
+        assert!(public_key.combine(&message, &duplicates).is_err());
+    }
+
+    #[test]
+    fn pss() {
+        let (public_key, shares) = split(2, 3);
+        let message = encode_pss(b"wire transfer", &public_key.public_key).unwrap();
+        // Share holders receive the encoded message
+        let bytes = message.to_bytes(&public_key.public_key).unwrap();
+        let received = EncodedMessage::from_bytes(&bytes, &public_key.public_key).unwrap();
+        assert_eq!(received, message);
+
+        let partials = [
+            shares[2].sign(&public_key, &received).unwrap(),
+            shares[0].sign(&public_key, &received).unwrap(),
+        ];
+        let signature = public_key.combine(&message, &partials).unwrap();
+        assert!(public_key
+            .public_key
+            .verify_pss(b"wire transfer", &signature)
+            .unwrap());
+        assert!(!public_key
+            .public_key
+            .verify_pss(b"wire transfers", &signature)
+            .unwrap());
+        assert!(!public_key
+            .public_key
+            .verify_pkcs1v15(b"wire transfer", &signature)
+            .unwrap());
+        assert!(!public_key
+            .public_key
+            .verify_pss(b"wire transfer", &signature[1..])
+            .unwrap());
+        // Salted, so signing again gives another signature
+        let again = encode_pss(b"wire transfer", &public_key.public_key).unwrap();
+        assert_ne!(again, message);
+    }
+
+    #[test]
+    fn invalid_partials() {
+        let (public_key, shares) = split(2, 3);
+        let message = encode_pkcs1v15(b"wire transfer", &public_key.public_key).unwrap();
+        let other = encode_pkcs1v15(b"other", &public_key.public_key).unwrap();
+
+        // Signed with another share than the one claimed
+        let mut wrong_share = shares[1].sign(&public_key, &message).unwrap();
+        wrong_share.identifier = 3;
+        assert!(!public_key.verify_partial(&message, &wrong_share).unwrap());
+        // Signed for another message
+        let wrong_message = shares[1].sign(&public_key, &other).unwrap();
+        assert!(!public_key.verify_partial(&message, &wrong_message).unwrap());
+        // A forged value with the honest proof
+        let mut forged = shares[0].sign(&public_key, &message).unwrap();
+        forged.value = forged
+            .value
+            .mod_mul(
+                &BigNumber::from_u32(2).unwrap(),
+                &public_key.public_key.modulus,
+                None,
+            )
+            .unwrap();
+        assert!(!public_key.verify_partial(&message, &forged).unwrap());
+        let honest = shares[1].sign(&public_key, &message).unwrap();
+        assert!(public_key.combine(&message, &[forged, honest]).is_err());
+
+        let mut unknown = shares[0].sign(&public_key, &message).unwrap();
+        unknown.identifier = 4;
+        assert!(public_key.verify_partial(&message, &unknown).is_err());
+    }
+
+    #[test]
+    fn split_invalid_args() {
+        let p = BigNumber::from_hex(P).unwrap();
+        let q = BigNumber::from_hex(Q).unwrap();
+        let e = BigNumber::from_u32(PUBLIC_EXPONENT).unwrap();
+        assert!(split_key(&p, &q, &e, 3, 2).is_err());
+        assert!(split_key(&p, &q, &e, 1, 3).is_err());
+        assert!(split_key(&p, &p, &e, 2, 3).is_err());
+        assert!(split_key(&p, &q, &BigNumber::from_u32(3).unwrap(), 2, 3).is_err());
+        assert!(split_key(&p, &q, &BigNumber::from_u32(65536).unwrap(), 2, 3).is_err());
+        assert!(split_key(&p.increment().unwrap(), &q, &e, 2, 3).is_err());
+        assert_eq!(
+            format!("{:?}", split(2, 3).1[0]),
+            "KeyShare { identifier: 1 }"
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//! A service that collects signatures to verify from many callers and
+//! checks them in batches.
+//!
+//! Each `submit` queues one signature and returns a future for its result.
+//! A dispatcher thread groups the queued signatures and hands a batch to the
+//! worker threads once it holds `max_batch_size` signatures or its oldest one
+//! has waited `max_delay`. A batch that passes `BatchVerify::batch_verify`
+//! resolves every request in it as valid. A batch that fails is verified one
+//! signature at a time, so each caller still learns whether its own
+//! signature is valid.
+
+use super::BatchVerify;
+use keys::PublicKey;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use ursa_core::blocking::{self, Blocking, Completer};
+
+use CryptoError;
+
+/// Settings for a `BatchVerifier`
+#[derive(Clone, Debug)]
+pub struct BatchVerifierConfig {
+    /// Flush a batch as soon as it holds this many signatures
+    pub max_batch_size: usize,
+    /// Flush a batch when its oldest signature has waited this long
+    pub max_delay: Duration,
+    /// Number of threads verifying batches
+    pub workers: usize,
+}
+
+impl Default for BatchVerifierConfig {
+    fn default() -> Self {
+        BatchVerifierConfig {
+            max_batch_size: 64,
+            max_delay: Duration::from_millis(10),
+            workers: 2,
+        }
+    }
+}
+
+struct Request {
+    message: Vec<u8>,
+    signature: Vec<u8>,
+    pk: PublicKey,
+    result: Completer<Result<bool, CryptoError>>,
+}
+
+/// Verifies signatures submitted from many tasks in shared batches.
+///
+/// Dropping it verifies the signatures that are still queued and then stops
+/// its threads.
+pub struct BatchVerifier {
+    requests: Mutex<Option<Sender<Request>>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl BatchVerifier {
+    /// Start a verifier with the default settings
+    pub fn new<S: BatchVerify + Send + Sync + 'static>(scheme: S) -> Self {
+        Self::with_config(scheme, BatchVerifierConfig::default())
+    }
+
+    /// Start a verifier, panics if `max_batch_size` or `workers` is 0
+    pub fn with_config<S: BatchVerify + Send + Sync + 'static>(
+        scheme: S,
+        config: BatchVerifierConfig,
+    ) -> Self {
+        assert!(config.max_batch_size > 0, "max_batch_size must not be 0");
+        assert!(config.workers > 0, "workers must not be 0");
+
+        let scheme = Arc::new(scheme);
+        let (requests, queue) = mpsc::channel();
+        let (batches, work) = mpsc::channel();
+        let work = Arc::new(Mutex::new(work));
+
+        let mut threads = (0..config.workers)
+            .map(|_| {
+                let scheme = scheme.clone();
+                let work = work.clone();
+                thread::spawn(move || run_worker(&*scheme, &work))
+            })
+            .collect::<Vec<_>>();
+        threads.push(thread::spawn(move || {
+            run_dispatcher(&queue, &batches, &config)
+        }));
+
+        BatchVerifier {
+            requests: Mutex::new(Some(requests)),
+            threads,
+        }
+    }
+
+    /// Queue a signature, the future resolves to what verifying it alone
+    /// would return
+    pub fn submit(
+        &self,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+        pk: PublicKey,
+    ) -> Blocking<Result<bool, CryptoError>> {
+        let (future, result) = blocking::pending();
+        let request = Request {
+            message,
+            signature,
+            pk,
+            result,
+        };
+        let requests = self.requests.lock().unwrap();
+        if let Err(mpsc::SendError(request)) = requests.as_ref().unwrap().send(request) {
+            request.result.complete(Err(CryptoError::GeneralError(
+                "The batch verifier has stopped".to_string(),
+            )));
+        }
+        future
+    }
+}
+
+impl Drop for BatchVerifier {
+    fn drop(&mut self) {
+        // Closing the queue makes the dispatcher flush and exit, which in
+        // turn stops the workers once they have verified everything
+        self.requests.lock().unwrap().take();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run_dispatcher(
+    queue: &Receiver<Request>,
+    batches: &Sender<Vec<Request>>,
+    config: &BatchVerifierConfig,
+) {
+    let mut batch = Vec::with_capacity(config.max_batch_size);
+    let mut deadline = Instant::now();
+    loop {
+        let next = if batch.is_empty() {
+            queue.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            let now = Instant::now();
+            if now >= deadline {
+                Err(RecvTimeoutError::Timeout)
+            } else {
+                queue.recv_timeout(deadline - now)
+            }
+        };
+        match next {
+            Ok(request) => {
+                if batch.is_empty() {
+                    deadline = Instant::now() + config.max_delay;
+                }
+                batch.push(request);
+                if batch.len() < config.max_batch_size {
+                    continue;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                flush(&mut batch, batches);
+                return;
+            }
+        }
+        flush(&mut batch, batches);
+    }
+}
+
+fn flush(batch: &mut Vec<Request>, batches: &Sender<Vec<Request>>) {
+    // Nobody is waiting for the result of a cancelled request
+    batch.retain(|request| !request.result.is_cancelled());
+    if !batch.is_empty() {
+        let _ = batches.send(batch.split_off(0));
+    }
+}
+
+fn run_worker<S: BatchVerify>(scheme: &S, work: &Mutex<Receiver<Vec<Request>>>) {
+    loop {
+        let batch = match work.lock().unwrap().recv() {
+            Ok(batch) => batch,
+            Err(_) => return,
+        };
+        let inputs = batch
+            .iter()
+            .map(|r| (r.message.as_slice(), r.signature.as_slice(), &r.pk))
+            .collect::<Vec<_>>();
+        if scheme.batch_verify(&inputs) {
+            for request in batch {
+                request.result.complete(Ok(true));
+            }
+        } else {
+            for request in batch {
+                let result = scheme.verify(&request.message, &request.signature, &request.pk);
+                request.result.complete(result);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keys::{KeyGenOption, PrivateKey};
+    use signatures::SignatureScheme;
+
+    /// Accepts a signature equal to the message and records the batch sizes
+    #[derive(Default)]
+    struct Echo(Arc<Mutex<Vec<usize>>>);
+
+    impl SignatureScheme for Echo {
+        fn new() -> Self {
+            Echo::default()
+        }
+        fn keypair(&self, _: Option<KeyGenOption>) -> Result<(PublicKey, PrivateKey), CryptoError> {
+            Ok((PublicKey(vec![]), PrivateKey(vec![])))
+        }
+        fn sign(&self, message: &[u8], _: &PrivateKey) -> Result<Vec<u8>, CryptoError> {
+            Ok(message.to_vec())
+        }
+        fn verify(
+            &self,
+            message: &[u8],
+            signature: &[u8],
+            _: &PublicKey,
+        ) -> Result<bool, CryptoError> {
+            Ok(message == signature)
+        }
+        fn signature_size() -> usize {
+            0
+        }
+        fn private_key_size() -> usize {
+            0
+        }
+        fn public_key_size() -> usize {
+            0
+        }
+    }
+
+    impl BatchVerify for Echo {
+        fn batch_verify(&self, inputs: &[(&[u8], &[u8], &PublicKey)]) -> bool {
+            self.0.lock().unwrap().push(inputs.len());
+            inputs.iter().all(|(m, s, _)| m == s)
+        }
+    }
+
+    fn submit(
+        verifier: &BatchVerifier,
+        message: u8,
+        signature: u8,
+    ) -> Blocking<Result<bool, CryptoError>> {
+        verifier.submit(vec![message], vec![signature], PublicKey(vec![]))
+    }
+
+    #[test]
+    fn batches_by_size() {
+        let echo = Echo::default();
+        let batches = echo.0.clone();
+        let verifier = BatchVerifier::with_config(
+            echo,
+            BatchVerifierConfig {
+                max_batch_size: 4,
+                max_delay: Duration::from_secs(60),
+                workers: 2,
+            },
+        );
+        let results = (0..8u8)
+            .map(|i| submit(&verifier, i, if i == 6 { 0 } else { i }))
+            .collect::<Vec<_>>();
+        let results = results
+            .into_iter()
+            .map(|r| r.wait().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(results, [true, true, true, true, true, true, false, true]);
+        assert_eq!(*batches.lock().unwrap(), [4, 4]);
+    }
+
+    #[test]
+    fn batches_by_deadline() {
+        let echo = Echo::default();
+        let batches = echo.0.clone();
+        let verifier = BatchVerifier::with_config(
+            echo,
+            BatchVerifierConfig {
+                max_batch_size: 1000,
+                max_delay: Duration::from_millis(5),
+                workers: 1,
+            },
+        );
+        assert!(submit(&verifier, 1, 1).wait().unwrap());
+        assert!(!submit(&verifier, 1, 2).wait().unwrap());
+        assert_eq!(*batches.lock().unwrap(), [1, 1]);
+    }
+
+    #[test]
+    fn flushes_on_drop() {
+        let verifier = BatchVerifier::with_config(
+            Echo::default(),
+            BatchVerifierConfig {
+                max_delay: Duration::from_secs(60),
+                ..BatchVerifierConfig::default()
+            },
+        );
+        let result = submit(&verifier, 3, 3);
+        drop(verifier);
+        assert!(result.wait().unwrap());
+    }
+}
@@ -1,529 +1,3860 @@
-use super::SignatureScheme;
+use super::{BatchVerify, SignatureScheme, VerificationError};
 /// Implements
 /// https://eprint.iacr.org/2018/483 and
 /// https://crypto.stanford.edu/~dabo/pubs/papers/BLSmultisig.html
 use amcl_wrapper::{
-    constants::{GroupG1_SIZE, MODBYTES},
+    constants::{FieldElement_SIZE, GroupG1_SIZE, MODBYTES},
     extension_field_gt::GT,
     field_elem::FieldElement,
     group_elem::GroupElement,
     group_elem_g1::G1,
     group_elem_g2::G2,
-    types_g2::GroupG2_SIZE,
+    types::GroupG1,
+    types_g2::{GroupG2, GroupG2_SIZE},
 };
-use keys::{KeyGenOption, PrivateKey as UrsaPrivateKey, PublicKey as UrsaPublicKey};
-#[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
-use sha2::Sha256;
+#[cfg(feature = "protobuf")]
+use encoding::proto;
+use encoding::{
+    cbor::{CborError, CborSerializable, Reader, Writer},
+    did_key::{DidKey, DID_KEY_PREFIX},
+    fingerprint::Fingerprint,
+    multibase::{self, Multicodec},
+};
+use keys::{decode_hex, KeyGenOption, PrivateKey as UrsaPrivateKey, PublicKey as UrsaPublicKey};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use sha3::Shake256;
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use subtle::{Choice, ConstantTimeEq};
+#[cfg(feature = "async")]
+use ursa_core::blocking::{self, Blocking};
+use ursa_core::parallel;
+#[cfg(feature = "bls_threshold")]
+use ursa_sharing::{
+    error::{SharingError, SharingResult},
+    generic_array::{typenum::U48, GenericArray},
+    shamir, Field, Group,
+};
+use zeroize::Zeroize;
 
 use CryptoError;
 
 pub const PRIVATE_KEY_SIZE: usize = MODBYTES;
-/// This is a simple alias so the consumer can just use PrivateKey::random() to generate a new one
-/// instead of wrapping it as a private field
-pub type PrivateKey = FieldElement;
 
-macro_rules! bls_impl {
-    ($pk_size:expr, $sig_size:expr, $pk_group:ident, $sig_group:ident, $ate_2_pairing_is_one:ident, $set_pairs:ident) => {
-        pub const PUBLIC_KEY_SIZE: usize = $pk_size;
-        pub const SIGNATURE_SIZE: usize = $sig_size;
+/// A BLS private key.
+///
+/// The scalar is zeroed when the key is dropped and `Debug` does not print
+/// it. It is only used to sign, to make a `PublicKey`, to derive other keys
+/// and, with `to_bytes`, to be stored.
+#[derive(Clone)]
+pub struct PrivateKey(FieldElement);
 
-        pub const MESSAGE_CONTEXT: &[u8; 20] = b"for signing messages";
-        pub const PUBLICKEY_CONTEXT: &[u8; 47] = b"for signing public keys for proof of possession";
+impl PrivateKey {
+    pub fn random() -> Self {
+        Self::random_using_rng(&mut OsRng)
+    }
 
-        pub type Generator = $pk_group;
-        pub type SignatureGroup = $sig_group;
+    pub fn random_using_rng<R: CryptoRng + RngCore>(rng: &mut R) -> Self {
+        PrivateKey(FieldElement::random_using_rng(rng))
+    }
 
-        /// Creates a new BLS key pair
-        pub fn generate(g: &Generator) -> (PublicKey, PrivateKey) {
-            let sk = PrivateKey::random();
-            let pk = PublicKey::new(&sk, g);
-            (pk, sk)
-        }
+    /// The `PRIVATE_KEY_SIZE` byte big endian encoding
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
 
-        fn hash_msg<A: AsRef<[u8]>>(message: A, context: Option<&'static [u8]>) -> SignatureGroup {
-            let ctx: &[u8] = context.unwrap_or(MESSAGE_CONTEXT);
-            hash_to_point(message, ctx)
+    /// Decode a key, reducing it modulo the group order. Zero is rejected.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let sk = FieldElement::from_bytes(bytes)
+            .map_err(|e| CryptoError::ParseError(format!("{:?}", e)))?;
+        if sk.is_zero() {
+            return Err(CryptoError::ParseError(
+                "The private key is zero".to_string(),
+            ));
         }
+        Ok(PrivateKey(sk))
+    }
+}
 
-        fn hash_key(pk: &PublicKey, context: Option<&'static [u8]>) -> SignatureGroup {
-            let ctx: &[u8] = context.unwrap_or(PUBLICKEY_CONTEXT);
-            hash_to_point(pk.to_bytes(), ctx)
-        }
+impl PartialEq for PrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes().ct_eq(&other.to_bytes()).into()
+    }
+}
 
-        fn hash_to_point<A: AsRef<[u8]>>(v: A, ctx: &[u8]) -> SignatureGroup {
-            let mut value = Vec::new();
-            value.extend_from_slice(ctx);
-            value.extend_from_slice(v.as_ref());
-            SignatureGroup::from_msg_hash(value.as_slice())
-        }
+impl Eq for PrivateKey {}
 
-        pub struct Bls;
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("PrivateKey(..)")
+    }
+}
 
-        impl SignatureScheme for Bls {
-            fn new() -> Self {
-                Bls
-            }
+impl Drop for PrivateKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
 
-            fn keypair(
-                &self,
-                options: Option<KeyGenOption>,
-            ) -> Result<(UrsaPublicKey, UrsaPrivateKey), CryptoError> {
-                let (public_key, private_key) = match options {
-                    Some(option) => match option {
-                        // Follows https://datatracker.ietf.org/doc/draft-irtf-cfrg-bls-signature/?include_text=1
-                        KeyGenOption::UseSeed(ref seed) => {
-                            let salt = b"BLS-SIG-KEYGEN-SALT-";
-                            let info = [0u8, PRIVATE_KEY_SIZE as u8]; // key_info || I2OSP(L, 2)
-                            let mut ikm = vec![0u8; seed.len() + 1];
-                            ikm[..seed.len()].copy_from_slice(seed); // IKM || I2OSP(0, 1)
-                            let mut okm = [0u8; PRIVATE_KEY_SIZE];
-                            let h = hkdf::Hkdf::<Sha256>::new(Some(&salt[..]), &ikm);
-                            h.expand(&info[..], &mut okm).map_err(|err| {
-                                CryptoError::KeyGenError(format!(
-                                    "Failed to generate keypair: {}",
-                                    err
-                                ))
-                            })?;
-                            let private_key: PrivateKey = PrivateKey::from(&okm);
-                            (
-                                PublicKey::new(&private_key, &Generator::generator()),
-                                private_key,
-                            )
-                        }
-                        KeyGenOption::FromSecretKey(ref key) => {
-                            let private_key =
-                                PrivateKey::from_bytes(key.as_ref()).map_err(|_| {
-                                    CryptoError::ParseError(
-                                        "Failed to parse private key.".to_string(),
-                                    )
-                                })?;
-                            (
-                                PublicKey::new(&private_key, &Generator::generator()),
-                                private_key,
-                            )
-                        }
-                    },
-                    None => generate(&Generator::generator()),
-                };
-                Ok((
-                    UrsaPublicKey(public_key.to_bytes()),
-                    UrsaPrivateKey(private_key.to_bytes()),
-                ))
-            }
+/// CBOR envelopes for a BLS type, using its uncompressed byte encoding
+macro_rules! bls_cbor_impl {
+    ($scheme:expr, $kind:expr, $name:ident) => {
+        impl CborSerializable for $name {
+            const CBOR_ID: &'static str = concat!($scheme, "/", $kind);
 
-            fn sign(&self, message: &[u8], sk: &UrsaPrivateKey) -> Result<Vec<u8>, CryptoError> {
-                Ok(Signature::new(
-                    message,
-                    None,
-                    &PrivateKey::from_bytes(sk.as_ref()).map_err(|_| {
-                        CryptoError::ParseError("Failed to parse private key.".to_string())
-                    })?,
-                )
-                .to_bytes())
+            fn to_cbor_payload(&self) -> Vec<u8> {
+                self.to_bytes()
             }
 
-            fn verify(
-                &self,
-                message: &[u8],
-                signature: &[u8],
-                pk: &UrsaPublicKey,
-            ) -> Result<bool, CryptoError> {
-                Ok(Signature::from_bytes(signature)
-                    .map_err(|_| CryptoError::ParseError("Failed to parse signature.".to_string()))?
-                    .verify(
-                        message,
-                        None,
-                        &PublicKey::from_bytes(pk.as_ref()).map_err(|_| {
-                            CryptoError::ParseError("Failed to parse public key.".to_string())
-                        })?,
-                        &Generator::generator(),
-                    ))
+            fn from_cbor_payload(_version: u32, payload: &[u8]) -> Result<Self, CborError> {
+                Self::from_bytes(payload).map_err(|e| CborError::InvalidPayload(e.to_string()))
             }
+        }
+    };
+}
 
-            fn signature_size() -> usize {
-                SIGNATURE_SIZE
+/// Protobuf conversions for a BLS type, tagged with the scheme name
+#[cfg(feature = "protobuf")]
+macro_rules! bls_proto_impl {
+    ($scheme:expr, $name:ident, $message:ident, $field:ident) => {
+        impl<'a> From<&'a $name> for proto::$message {
+            fn from(value: &'a $name) -> Self {
+                proto::$message {
+                    algorithm: $scheme.to_string(),
+                    $field: value.to_bytes(),
+                }
             }
+        }
 
-            fn private_key_size() -> usize {
-                PRIVATE_KEY_SIZE
-            }
+        impl<'a> TryFrom<&'a proto::$message> for $name {
+            type Error = CryptoError;
 
-            fn public_key_size() -> usize {
-                PUBLIC_KEY_SIZE
+            fn try_from(message: &'a proto::$message) -> Result<Self, CryptoError> {
+                if message.algorithm != $scheme {
+                    return Err(CryptoError::ParseError(format!(
+                        "Expected {}, found {}",
+                        $scheme, message.algorithm
+                    )));
+                }
+                Self::from_bytes(&message.$field)
             }
         }
+    };
+}
 
-        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-        #[derive(Debug, Clone)]
-        pub struct PublicKey(Generator);
+/// Conversions between a BLS type and its encoding. Types encoded in a
+/// fixed `$size` also convert to and from byte arrays.
+macro_rules! bls_bytes_impl {
+    ($name:ident) => {
+        impl<'a> TryFrom<&'a [u8]> for $name {
+            type Error = CryptoError;
 
-        impl PublicKey {
-            pub fn new(sk: &PrivateKey, g: &Generator) -> Self {
-                PublicKey(g * sk)
+            fn try_from(bytes: &'a [u8]) -> Result<Self, CryptoError> {
+                Self::from_bytes(bytes)
             }
+        }
 
-            // Create an combined public key without rogue key mitigation
-            pub fn combine(&mut self, pks: &[PublicKey]) {
-                for pk in pks {
-                    self.0 += &pk.0;
-                }
+        impl<'a> From<&'a $name> for Vec<u8> {
+            fn from(value: &'a $name) -> Self {
+                value.to_bytes()
             }
+        }
 
-            pub fn to_bytes(&self) -> Vec<u8> {
-                self.0.to_bytes(false)
+        /// Lowercase hex of `to_bytes`
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", hex::encode(self.to_bytes()))
             }
+        }
 
-            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
-                Ok(PublicKey(Generator::from_bytes(bytes).map_err(|e| {
-                    CryptoError::ParseError(format!("{:?}", e))
-                })?))
+        /// Hex, optionally prefixed by `0x`
+        impl FromStr for $name {
+            type Err = CryptoError;
+
+            fn from_str(s: &str) -> Result<Self, CryptoError> {
+                Self::from_bytes(&decode_hex(s)?)
             }
         }
+    };
+    ($name:ident, $size:ident) => {
+        bls_bytes_impl!($name);
 
-        /// Represents an aggregated BLS public key that mitigates the rogue key attack
-        /// for verifying aggregated signatures.
-        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-        #[derive(Debug, Clone)]
-        pub struct AggregatedPublicKey(Generator);
+        impl TryFrom<[u8; $size]> for $name {
+            type Error = CryptoError;
 
-        impl From<&[PublicKey]> for AggregatedPublicKey {
-            fn from(keys: &[PublicKey]) -> Self {
-                // To combat the rogue key attack,
-                // compute (t_1,…,t_n)←H1(pk_1,…,pk_n) ∈ R_n
-                // output the aggregated public key
-                // as described in section 3.1 from https://eprint.iacr.org/2018/483
-                let mut bytes = Vec::new();
-                for k in keys {
-                    bytes.extend_from_slice(k.to_bytes().as_slice());
-                }
-                AggregatedPublicKey(keys.iter().fold(Generator::identity(), |apk, k| {
-                    // The position of the ith public key in the byte array
-                    // of the hash doesn't matter as much as its included twice.
-                    // For convenience, its appended to the end
-                    let mut h = bytes.clone();
-                    h.extend_from_slice(k.0.to_bytes(false).as_slice());
-                    apk + &k.0 * &FieldElement::from_msg_hash(h.as_slice())
-                }))
+            fn try_from(bytes: [u8; $size]) -> Result<Self, CryptoError> {
+                Self::from_bytes(&bytes[..])
             }
         }
 
-        impl AggregatedPublicKey {
-            pub fn new(keys: &[PublicKey]) -> Self {
-                keys.into()
+        impl<'a> From<&'a $name> for [u8; $size] {
+            fn from(value: &'a $name) -> Self {
+                let mut bytes = [0u8; $size];
+                bytes.copy_from_slice(&value.to_bytes());
+                bytes
             }
+        }
+    };
+}
 
-            pub fn to_bytes(&self) -> Vec<u8> {
-                self.0.to_bytes(false)
+/// Serde through `to_bytes` and `from_bytes` instead of the curve library's
+/// own representation: a hex string in human readable formats like JSON and
+/// a byte string in binary ones. Deserializing checks the point like
+/// `from_bytes` does.
+#[cfg(feature = "serde")]
+macro_rules! bls_serde_impl {
+    ($($name:ident),*) => {
+        $(
+            impl ::serde::Serialize for $name {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: ::serde::Serializer,
+                {
+                    if serializer.is_human_readable() {
+                        serializer.serialize_str(&hex::encode(self.to_bytes()))
+                    } else {
+                        serializer.serialize_bytes(&self.to_bytes())
+                    }
+                }
             }
 
-            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
-                Ok(AggregatedPublicKey(Generator::from_bytes(bytes).map_err(
-                    |e| CryptoError::ParseError(format!("{:?}", e)),
-                )?))
-            }
-        }
+            impl<'de> ::serde::Deserialize<'de> for $name {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: ::serde::Deserializer<'de>,
+                {
+                    struct BytesVisitor;
 
-        /// Signature over a message. One gotcha for BLS signatures
-        /// is the need to mitigate rogue key attacks. There are two methods to achieve
-        /// this: compute additional work to make each message distinct
-        /// in a signature for each `PublicKey` or
-        /// use `ProofOfPossession`. `Signature` and `ProofOfPossession` MUST
-        /// use domain separation values that are different
-        /// to avoid certain types of attacks and make `Signature`
-        /// distinct from `ProofOfPossession`. If `ProofOfPossession`
-        /// and `Signature` use the same value for `context` they are effectively the same.
-        /// Don't do this. You have been warned.
-        ///
-        /// To make messages distinct, use `new_with_rk_mitigation`. If using
-        /// proof of possession mitigation, use `new`.
-        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-        #[derive(Debug, Clone)]
-        pub struct Signature(SignatureGroup);
+                    impl<'de> ::serde::de::Visitor<'de> for BytesVisitor {
+                        type Value = $name;
 
-        impl Signature {
-            pub fn new<A: AsRef<[u8]>>(
-                message: A,
-                context: Option<&'static [u8]>,
-                sk: &PrivateKey,
-            ) -> Self {
-                Signature(hash_msg(message, context) * sk)
-            }
+                        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                            write!(f, "the hex or bytes of a {}", stringify!($name))
+                        }
 
-            pub fn new_with_rk_mitigation<A: AsRef<[u8]>>(
-                message: A,
-                context: Option<&'static [u8]>,
-                sk: &PrivateKey,
-                pk_index: usize,
-                pks: &[PublicKey],
-            ) -> Self {
-                let hash = hash_msg(message, context);
-                // To combat the rogue key attack,
-                // compute (t_1,…,t_n)←H1(pk_1,…,pk_n) ∈ R_n
-                // output the aggregated public key
-                // as described in section 3.1 from https://eprint.iacr.org/2018/483
-                let mut bytes = Vec::new();
-                for k in pks {
-                    bytes.extend_from_slice(k.to_bytes().as_slice());
-                }
-                bytes.extend_from_slice(pks[pk_index].to_bytes().as_slice());
-                let a = FieldElement::from_msg_hash(bytes.as_slice());
-                Signature(hash * sk * &a)
-            }
+                        fn visit_str<E>(self, value: &str) -> Result<$name, E>
+                        where
+                            E: ::serde::de::Error,
+                        {
+                            decode_hex(value)
+                                .and_then(|bytes| $name::from_bytes(&bytes))
+                                .map_err(E::custom)
+                        }
 
-            // Collects multiple signatures into a single signature
-            // Verified by using `verify_multi`. This method does not
-            // directly mitigate the rogue key attack. It is expected the caller
-            // handles this using other techniques like proof of possession
-            pub fn combine(&mut self, signatures: &[Signature]) {
-                for sig in signatures {
-                    self.0 += &sig.0;
+                        fn visit_bytes<E>(self, value: &[u8]) -> Result<$name, E>
+                        where
+                            E: ::serde::de::Error,
+                        {
+                            $name::from_bytes(value).map_err(E::custom)
+                        }
+
+                        fn visit_seq<A>(self, mut seq: A) -> Result<$name, A::Error>
+                        where
+                            A: ::serde::de::SeqAccess<'de>,
+                        {
+                            let mut bytes = Vec::new();
+                            while let Some(b) = seq.next_element()? {
+                                bytes.push(b);
+                            }
+                            $name::from_bytes(&bytes).map_err(::serde::de::Error::custom)
+                        }
+                    }
+
+                    if deserializer.is_human_readable() {
+                        deserializer.deserialize_str(BytesVisitor)
+                    } else {
+                        deserializer.deserialize_bytes(BytesVisitor)
+                    }
                 }
             }
+        )*
+    };
+}
 
-            // Verify a signature generated by `new`
-            pub fn verify<A: AsRef<[u8]>>(
-                &self,
-                message: A,
-                context: Option<&'static [u8]>,
-                pk: &PublicKey,
-                g: &Generator,
-            ) -> bool {
-                let hash = hash_msg(message, context);
-                $ate_2_pairing_is_one(&g, &self.0, &pk.0, &hash)
+/// The number of 4 bit windows of a scalar, which is less than 2^255
+const FIXED_BASE_WINDOWS: usize = 64;
+
+/// Precomputed multiples of a fixed base point for constant time fixed-base
+/// scalar multiplication.
+///
+/// Window `i` holds `j * 16^i * base` for every 4 bit digit `j`, so the
+/// product is the sum of one entry per digit of the scalar and no doublings
+/// are needed. Every entry of a window is read to select one, so the memory
+/// accesses don't depend on the scalar. A table takes 1024 points, build it
+/// once per base and reuse it.
+macro_rules! fixed_base_table {
+    ($name:ident, $group_elem:ident, $ecp:ident) => {
+        #[derive(Clone)]
+        pub struct $name(Vec<Vec<$ecp>>);
+
+        impl $name {
+            pub fn new(base: &$group_elem) -> Self {
+                let mut windows = Vec::with_capacity(FIXED_BASE_WINDOWS);
+                let mut base = base.clone();
+                for _ in 0..FIXED_BASE_WINDOWS {
+                    let mut window = Vec::with_capacity(16);
+                    let mut multiple = $group_elem::identity();
+                    for _ in 0..16 {
+                        window.push(multiple.to_ecp());
+                        multiple = &multiple + &base;
+                    }
+                    windows.push(window);
+                    // 16 * base
+                    base = multiple;
+                }
+                $name(windows)
             }
 
-            // Caller should aggregate all signatures into `self` by using `combine`.
-            // Messages must be distinct
-            // `inputs` is a slice of message - public key tuples
-            // Multisignature verification
-            pub fn verify_multi(
-                &self,
-                inputs: &[(&[u8], &PublicKey)],
-                context: Option<&'static [u8]>,
-                g: &Generator,
-            ) -> bool {
-                let mut msg_check = ::std::collections::HashSet::new();
-                let mut pairs = Vec::new();
-                for (msg, pk) in inputs {
-                    let hash = hash_msg(msg, context);
-                    if msg_check.contains(&hash) {
-                        return false;
+            /// Compute `scalar * base`
+            pub fn mul(&self, scalar: &FieldElement) -> $group_elem {
+                // Big endian and reduced modulo the group order
+                let mut bytes = scalar.to_bytes();
+                let mut result = $group_elem::identity();
+                for (i, window) in self.0.iter().enumerate() {
+                    let byte = bytes[bytes.len() - 1 - i / 2];
+                    let digit = if i % 2 == 0 { byte & 0x0f } else { byte >> 4 };
+                    let mut entry = window[0].clone();
+                    for (j, e) in window.iter().enumerate().skip(1) {
+                        // 1 if j == digit without branching
+                        entry.cmove(e, ((((j as u8 ^ digit) as isize) - 1) >> 8) & 1);
                     }
-                    pairs.push((pk.0.clone(), hash.clone()));
-                    msg_check.insert(hash);
+                    result += $group_elem::from(entry);
                 }
+                bytes.zeroize();
+                result
+            }
+        }
+    };
+}
+
+fixed_base_table!(G1Table, G1, GroupG1);
+fixed_base_table!(G2Table, G2, GroupG2);
+
+/// Reusable buffers for the multi-scalar multiplications done when
+/// aggregating public keys and batch verifying signatures.
+///
+/// Each thread keeps one that those functions use by default, so once the
+/// first calls have grown it to the batch size no more memory is allocated
+/// for scalars and buckets. A caller that manages its own memory can pass
+/// one to the `_with_scratch` functions instead.
+#[derive(Default)]
+pub struct Scratch {
+    /// Big endian scalars, `FieldElement_SIZE` bytes each
+    scalars: Vec<u8>,
+    /// Random exponents for batch verification
+    exponents: Vec<FieldElement>,
+    g1_buckets: Vec<G1>,
+    g2_buckets: Vec<G2>,
+    /// Weighted public keys and message hashes for batch verification
+    g1_points: Vec<G1>,
+    g2_points: Vec<G2>,
+}
+
+impl Scratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `f` with the scratch space of this thread, or a new one if it is
+    /// already in use further up the stack
+    fn with_local<T, F: FnOnce(&mut Scratch) -> T>(f: F) -> T {
+        thread_local!(static SCRATCH: RefCell<Scratch> = RefCell::new(Scratch::default()));
+        SCRATCH.with(|scratch| match scratch.try_borrow_mut() {
+            Ok(mut scratch) => f(&mut scratch),
+            Err(_) => f(&mut Scratch::default()),
+        })
+    }
+}
+
+/// Selects the buffers of a `Scratch` for a group
+trait ScratchGroup: GroupElement {
+    fn buffers(scratch: &mut Scratch) -> (&mut Vec<u8>, &mut Vec<Self>);
+    fn points(scratch: &mut Scratch) -> &mut Vec<Self>;
+    /// The point amcl maps a SHAKE256 output to
+    fn map(hash: &[u8]) -> Self;
+}
+
+impl ScratchGroup for G1 {
+    fn buffers(scratch: &mut Scratch) -> (&mut Vec<u8>, &mut Vec<Self>) {
+        (&mut scratch.scalars, &mut scratch.g1_buckets)
+    }
+
+    fn points(scratch: &mut Scratch) -> &mut Vec<Self> {
+        &mut scratch.g1_points
+    }
+
+    fn map(hash: &[u8]) -> Self {
+        GroupG1::mapit(hash).into()
+    }
+}
+
+impl ScratchGroup for G2 {
+    fn buffers(scratch: &mut Scratch) -> (&mut Vec<u8>, &mut Vec<Self>) {
+        (&mut scratch.scalars, &mut scratch.g2_buckets)
+    }
+
+    fn points(scratch: &mut Scratch) -> &mut Vec<Self> {
+        &mut scratch.g2_points
+    }
+
+    fn map(hash: &[u8]) -> Self {
+        GroupG2::mapit(hash).into()
+    }
+}
+
+/// `G::from_msg_hash` of `ctx || msg`, streamed into the hash instead of
+/// copied into one buffer
+fn hash_with_context<G: ScratchGroup>(ctx: &[u8], msg: &[u8]) -> G {
+    use sha3::digest::{ExtendableOutput, Input, XofReader};
+
+    let mut hasher = Shake256::default();
+    hasher.input(ctx);
+    hasher.input(msg);
+    let mut hash = [0u8; FieldElement_SIZE];
+    hasher.xof_result().read(&mut hash);
+    G::map(&hash)
+}
+
+/// Compute `sum(scalars[i] * points[i])` with Pippenger's bucket method.
+///
+/// Each window of `c` bits sorts the points into `2^c - 1` buckets by their
+/// digit and sums the buckets with a running total, so a window costs about
+/// `n + 2^(c+1)` additions instead of a scalar multiplication per point.
+/// The run time depends on the scalars, use this only when they are public
+/// or random values that never leave the function calling it.
+fn multi_scalar_mul<'a, G, I>(points: I, scalars: &[FieldElement], scratch: &mut Scratch) -> G
+where
+    G: ScratchGroup + 'a,
+    I: Iterator<Item = &'a G> + Clone,
+{
+    debug_assert_eq!(points.clone().count(), scalars.len());
+    let (bytes, buckets) = G::buffers(scratch);
+    // Big endian and reduced modulo the group order
+    bytes.clear();
+    bytes.resize(scalars.len() * FieldElement_SIZE, 0);
+    for (s, out) in scalars.iter().zip(bytes.chunks_mut(FieldElement_SIZE)) {
+        s.write_to_slice_unchecked(out);
+    }
+    let bits = bytes
+        .chunks(FieldElement_SIZE)
+        .map(bit_length)
+        .max()
+        .unwrap_or(0);
+    let c = if scalars.len() < 32 {
+        3
+    } else {
+        (scalars.len() as f64).ln() as usize + 2
+    };
+
+    buckets.clear();
+    buckets.resize_with((1 << c) - 1, G::identity);
+    let mut result = G::identity();
+    for window in (0..(bits + c - 1) / c).rev() {
+        for _ in 0..c {
+            result.double_mut();
+        }
+        for b in buckets.iter_mut() {
+            b.set_to_identity();
+        }
+        for (p, s) in points.clone().zip(bytes.chunks(FieldElement_SIZE)) {
+            let d = digit(s, window * c, c);
+            if d != 0 {
+                buckets[d - 1].add_assign_(p);
+            }
+        }
+        // sum(j * bucket_j) as the sum of the running totals from the top
+        let mut running = G::identity();
+        for b in buckets.iter().rev() {
+            running.add_assign_(b);
+            result.add_assign_(&running);
+        }
+    }
+    result
+}
+
+/// The position of the highest set bit of a big endian number, plus one
+fn bit_length(bytes: &[u8]) -> usize {
+    match bytes.iter().position(|b| *b != 0) {
+        Some(i) => (bytes.len() - i - 1) * 8 + (8 - bytes[i].leading_zeros() as usize),
+        None => 0,
+    }
+}
+
+/// The `width` bits of a big endian number starting at bit `offset`
+fn digit(bytes: &[u8], offset: usize, width: usize) -> usize {
+    (offset..offset + width)
+        .take_while(|bit| *bit < bytes.len() * 8)
+        .fold(0, |d, bit| {
+            let b = (bytes[bytes.len() - 1 - bit / 8] >> (bit % 8)) & 1;
+            d | (b as usize) << (bit - offset)
+        })
+}
+
+/// How signatures made with a `BlsSigningContext` are protected from rogue
+/// key attacks when they are aggregated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlsScheme {
+    /// Only signatures over distinct messages are aggregated
+    Basic,
+    /// Every key comes with a `ProofOfPossession`, so signatures over the
+    /// same message can be aggregated as they are
+    Pop,
+    /// Each signature is weighted by a hash of all the signers' keys, see
+    /// `Signature::new_with_rk_mitigation`
+    KeyWeighted,
+}
+
+/// One holder's share of a `PrivateKey` for t-of-n signing.
+///
+/// `split` deals the shares with Shamir secret sharing from ursa_sharing.
+/// Each holder signs with `PartialSignature::new` and any `threshold` of
+/// the partial signatures combine into a signature that verifies under the
+/// public key of the key that was split.
+#[cfg(feature = "bls_threshold")]
+#[derive(Clone)]
+pub struct SignatureShare {
+    identifier: u32,
+    sk: PrivateKey,
+}
+
+#[cfg(feature = "bls_threshold")]
+impl SignatureShare {
+    /// Split `sk` into `limit` shares numbered from 1, any `threshold` of
+    /// which can sign for it
+    pub fn split(
+        sk: &PrivateKey,
+        threshold: usize,
+        limit: usize,
+    ) -> Result<Vec<SignatureShare>, CryptoError> {
+        Self::split_with_rng(sk, threshold, limit, &mut OsRng)
+    }
+
+    pub fn split_with_rng<R: CryptoRng + RngCore>(
+        sk: &PrivateKey,
+        threshold: usize,
+        limit: usize,
+        rng: &mut R,
+    ) -> Result<Vec<SignatureShare>, CryptoError> {
+        let scheme = shamir::Scheme::new(threshold, limit)
+            .map_err(|e| CryptoError::KeyGenError(e.to_string()))?;
+        scheme
+            .split_secret(rng, &SharedKey(sk.0.clone()))
+            .map_err(|e| CryptoError::KeyGenError(e.to_string()))?
+            .iter()
+            .map(Self::from_share)
+            .collect()
+    }
+
+    /// Fails if `identifier` is 0, which is where the polynomial hides the
+    /// key that was split
+    pub fn new(identifier: u32, sk: PrivateKey) -> Result<Self, CryptoError> {
+        if identifier == 0 {
+            return Err(CryptoError::KeyGenError(
+                "Share identifiers start at 1".to_string(),
+            ));
+        }
+        Ok(SignatureShare { identifier, sk })
+    }
+
+    /// Read a share in the format of `ursa_sharing::shamir::Share`
+    pub fn from_share(share: &shamir::Share) -> Result<Self, CryptoError> {
+        let sk = FieldElement::from_bytes(share.value())
+            .map_err(|e| CryptoError::ParseError(format!("{:?}", e)))?;
+        Self::new(share.identifier(), PrivateKey(sk))
+    }
+
+    pub fn to_share(&self) -> shamir::Share {
+        shamir::Share::new(self.identifier as usize, self.sk.to_bytes())
+    }
+
+    /// The x coordinate of the share, which partial signatures carry
+    pub fn identifier(&self) -> u32 {
+        self.identifier
+    }
+
+    /// The holder's key. Its public key verifies the holder's partial
+    /// signatures.
+    pub fn private_key(&self) -> &PrivateKey {
+        &self.sk
+    }
+
+    /// The identifier as 4 big endian bytes followed by the key
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_share().to_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let share =
+            shamir::Share::try_from(bytes).map_err(|e| CryptoError::ParseError(e.to_string()))?;
+        Self::from_share(&share)
+    }
+}
+
+#[cfg(feature = "bls_threshold")]
+impl fmt::Debug for SignatureShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SignatureShare")
+            .field("identifier", &self.identifier)
+            .finish()
+    }
+}
+
+/// The Lagrange coefficient at 0 of the share `i` among the shares with
+/// `indices`. Multiplying each share by its coefficient and adding them up
+/// interpolates the shared secret, or the signature or public key when the
+/// shares are in a group.
+///
+/// Fails if `i` is not one of `indices` or an index is 0 or repeated.
+#[cfg(feature = "bls_threshold")]
+pub fn lagrange_coefficient(indices: &[u32], i: u32) -> Result<FieldElement, CryptoError> {
+    let position = indices.iter().position(|j| *j == i).ok_or_else(|| {
+        CryptoError::GeneralError(format!("Share identifier {} is not one of the indices", i))
+    })?;
+    Ok(lagrange_coefficients(indices)?.swap_remove(position))
+}
+
+/// The Lagrange coefficients at 0 for the shares with `identifiers`
+#[cfg(feature = "bls_threshold")]
+fn lagrange_coefficients(identifiers: &[u32]) -> Result<Vec<FieldElement>, CryptoError> {
+    if identifiers.is_empty() {
+        return Err(CryptoError::GeneralError(
+            "No partial signatures to combine".to_string(),
+        ));
+    }
+    let mut seen = ::std::collections::BTreeSet::new();
+    for i in identifiers {
+        if *i == 0 || !seen.insert(*i) {
+            return Err(CryptoError::GeneralError(format!(
+                "Invalid or repeated share identifier {}",
+                i
+            )));
+        }
+    }
+    let xs = identifiers
+        .iter()
+        .map(|i| FieldElement::from(*i))
+        .collect::<Vec<_>>();
+    Ok(xs
+        .iter()
+        .map(|xi| {
+            let mut num = FieldElement::one();
+            let mut den = FieldElement::one();
+            for xj in xs.iter().filter(|xj| *xj != xi) {
+                num = &num * xj;
+                den = &den * &(xj - xi);
+            }
+            &num * &den.inverse()
+        })
+        .collect())
+}
+
+/// A `PrivateKey` as ursa_sharing's field
+#[cfg(feature = "bls_threshold")]
+#[derive(Clone)]
+struct SharedKey(FieldElement);
+
+#[cfg(feature = "bls_threshold")]
+impl fmt::Debug for SharedKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SharedKey(..)")
+    }
+}
+
+#[cfg(feature = "bls_threshold")]
+impl Group for SharedKey {
+    type Size = U48;
+
+    fn zero() -> Self {
+        SharedKey(FieldElement::zero())
+    }
+
+    fn from_bytes<B: AsRef<[u8]>>(value: B) -> SharingResult<Self> {
+        FieldElement::from_bytes(value.as_ref())
+            .map(SharedKey)
+            .map_err(|_| SharingError::ShareInvalidValue)
+    }
+
+    fn random(rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        SharedKey(FieldElement::random_using_rng(rng))
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    fn is_valid(&self) -> bool {
+        // Field elements are always reduced
+        !self.0.is_zero()
+    }
+
+    fn negate(&mut self) {
+        self.0.negate();
+    }
+
+    fn add_assign(&mut self, rhs: &Self) {
+        self.0 += &rhs.0;
+    }
+
+    fn sub_assign(&mut self, rhs: &Self) {
+        self.0 -= &rhs.0;
+    }
+
+    fn scalar_mul_assign(&mut self, rhs: &Self) {
+        self.0 = &self.0 * &rhs.0;
+    }
+
+    fn to_bytes(&self) -> GenericArray<u8, U48> {
+        GenericArray::clone_from_slice(&self.0.to_bytes())
+    }
+}
+
+#[cfg(feature = "bls_threshold")]
+impl Field for SharedKey {
+    fn one() -> Self {
+        SharedKey(FieldElement::one())
+    }
+
+    fn from_usize(value: usize) -> Self {
+        SharedKey(FieldElement::from(value as u64))
+    }
+
+    fn scalar_div_assign(&mut self, rhs: &Self) {
+        self.0 = &self.0 * &rhs.0.inverse();
+    }
+}
+
+/// The EIP-2334 purpose of BLS12-381 keys
+pub const EIP2334_PURPOSE: u32 = 12381;
+/// The EIP-2334 coin type of Ethereum
+pub const EIP2334_COIN_TYPE: u32 = 3600;
+
+/// KeyGen from EIP-2333 and draft-irtf-cfrg-bls-signature
+fn key_gen(ikm: &[u8]) -> Result<PrivateKey, CryptoError> {
+    if ikm.len() < 32 {
+        return Err(CryptoError::KeyGenError(format!(
+            "Expected at least 32 bytes of seed, found {}",
+            ikm.len()
+        )));
+    }
+    Ok(hkdf_mod_r(ikm))
+}
+
+/// HKDF-SHA256 of `ikm` to 48 bytes reduced modulo the group order,
+/// rehashing the salt in the unlikely case the result is zero
+fn hkdf_mod_r(ikm: &[u8]) -> PrivateKey {
+    let mut salt = Sha256::digest(b"BLS-SIG-KEYGEN-SALT-");
+    // IKM || I2OSP(0, 1)
+    let mut input = ikm.to_vec();
+    input.push(0u8);
+    loop {
+        let mut okm = [0u8; FieldElement_SIZE];
+        // key_info is empty, followed by I2OSP(L, 2)
+        hkdf::Hkdf::<Sha256>::new(Some(&salt[..]), &input)
+            .expand(&[0u8, FieldElement_SIZE as u8], &mut okm)
+            .expect("48 bytes is a valid HKDF output length");
+        let sk = FieldElement::from_bytes(&okm[..]).expect("okm is the size of a field element");
+        okm.zeroize();
+        if !sk.is_zero() {
+            input.zeroize();
+            return PrivateKey(sk);
+        }
+        salt = Sha256::digest(&salt);
+    }
+}
+
+/// The EIP-2333 child of `parent_sk` at `index`. Every child is hardened
+/// by the Lamport step, so knowing a child key and the parent's public key
+/// does not reveal the parent.
+pub fn derive_child(parent_sk: &PrivateKey, index: u32) -> PrivateKey {
+    let salt = index.to_be_bytes();
+    // I2OSP(parent_SK, 32)
+    let mut parent = parent_sk.to_bytes().split_off(FieldElement_SIZE - 32);
+    let mut not_parent = parent.iter().map(|b| !b).collect::<Vec<u8>>();
+    let mut hasher = Sha256::new();
+    for ikm in &[&parent[..], &not_parent[..]] {
+        let mut lamport = vec![0u8; 32 * 255];
+        hkdf::Hkdf::<Sha256>::new(Some(&salt[..]), ikm)
+            .expand(&[], &mut lamport)
+            .expect("255 hashes is the longest valid HKDF output");
+        for chunk in lamport.chunks(32) {
+            hasher.input(Sha256::digest(chunk));
+        }
+        lamport.zeroize();
+    }
+    parent.zeroize();
+    not_parent.zeroize();
+    hkdf_mod_r(&hasher.result())
+}
+
+/// The indices of an EIP-2334 path such as `m/12381/3600/0/0/0`. `m` is
+/// the master key and there are no hardened indices.
+pub fn parse_path(path: &str) -> Result<Vec<u32>, CryptoError> {
+    let mut parts = path.split('/');
+    if parts.next() != Some("m") {
+        return Err(CryptoError::ParseError(format!(
+            "Derivation path {} does not start with m",
+            path
+        )));
+    }
+    parts
+        .map(|p| {
+            p.parse::<u32>()
+                .map_err(|_| CryptoError::ParseError(format!("Invalid path index {}", p)))
+        })
+        .collect()
+}
+
+/// The key at `path` below the master key of `seed`
+pub fn derive_from_path(seed: &[u8], path: &str) -> Result<PrivateKey, CryptoError> {
+    let indices = parse_path(path)?;
+    Ok(indices
+        .iter()
+        .fold(key_gen(seed)?, |sk, index| derive_child(&sk, *index)))
+}
+
+/// The EIP-2334 path of the withdrawal key of validator `index`
+pub fn withdrawal_key_path(index: u32) -> String {
+    format!("m/{}/{}/{}/0", EIP2334_PURPOSE, EIP2334_COIN_TYPE, index)
+}
+
+/// The EIP-2334 path of the signing key of validator `index`
+pub fn signing_key_path(index: u32) -> String {
+    format!("{}/0", withdrawal_key_path(index))
+}
+
+/// Decode a point of a key or signature. The identity and points outside
+/// the prime order subgroup are rejected, since in an aggregate they can
+/// cancel out or forge the contributions of other signers.
+fn decode_point<G: GroupElement>(bytes: &[u8], what: &str) -> Result<G, CryptoError> {
+    let point = G::from_bytes(bytes).map_err(|e| CryptoError::ParseError(format!("{:?}", e)))?;
+    check_point(point, what)
+}
+
+/// The checks of `decode_point` for a point that is already decoded
+fn check_point<G: GroupElement>(point: G, what: &str) -> Result<G, CryptoError> {
+    if point.is_identity() {
+        return Err(CryptoError::InvalidPoint(format!(
+            "The {} is the identity",
+            what
+        )));
+    }
+    if !point.has_correct_order() {
+        return Err(CryptoError::InvalidPoint(format!(
+            "The {} is not in the prime order subgroup",
+            what
+        )));
+    }
+    Ok(point)
+}
+
+/// Length check for the `*Ref` types, which defer decoding
+fn check_encoding_length(bytes: &[u8], expected: usize) -> Result<(), CryptoError> {
+    if bytes.len() != expected {
+        return Err(CryptoError::ParseError(format!(
+            "Expected {} bytes, found {}",
+            expected,
+            bytes.len()
+        )));
+    }
+    Ok(())
+}
+
+macro_rules! bls_impl {
+    ($pk_size:expr, $sig_size:expr, $pk_group:ident, $sig_group:ident, $pk_table:ident, $sig_table:ident, $pk_codec:ident, $scheme:expr, $suite:tt, $ate_2_pairing_is_one:ident, $set_pairs:ident, $pair:ident) => {
+        pub const PUBLIC_KEY_SIZE: usize = $pk_size;
+        pub const SIGNATURE_SIZE: usize = $sig_size;
+
+        pub const MESSAGE_CONTEXT: &[u8; 20] = b"for signing messages";
+        pub const PUBLICKEY_CONTEXT: &[u8; 47] = b"for signing public keys for proof of possession";
+
+        /// The ciphersuite ids of draft-irtf-cfrg-bls-signature, which
+        /// `BlsSigningContext` uses as `hash_to_curve` tags by default
+        pub const BASIC_DST: &[u8] =
+            concat!("BLS_SIG_", $suite, "_XMD:SHA-256_SSWU_RO_NUL_").as_bytes();
+        pub const POP_DST: &[u8] =
+            concat!("BLS_SIG_", $suite, "_XMD:SHA-256_SSWU_RO_POP_").as_bytes();
+        /// Not from the draft, which has no key weighted scheme
+        pub const KEY_WEIGHTED_DST: &[u8] =
+            concat!("BLS_SIG_", $suite, "_XMD:SHA-256_SSWU_RO_URSA_KW_").as_bytes();
+
+        pub type Generator = $pk_group;
+        pub type SignatureGroup = $sig_group;
+        /// A fixed-base table for multiplying a `Generator`
+        pub type GeneratorTable = $pk_table;
+
+        lazy_static! {
+            static ref GENERATOR: Generator = Generator::generator();
+            static ref GENERATOR_TABLE: GeneratorTable = GeneratorTable::new(&GENERATOR);
+            static ref SIGNATURE_GENERATOR_TABLE: $sig_table =
+                $sig_table::new(&SignatureGroup::generator());
+        }
+
+        /// The standard generator of the public key group, `Generator::generator()`
+        pub fn generator() -> &'static Generator {
+            &GENERATOR
+        }
+
+        /// The table of `generator()`, built on first use
+        pub fn generator_table() -> &'static GeneratorTable {
+            &GENERATOR_TABLE
+        }
+
+        /// Creates a new BLS key pair
+        pub fn generate() -> (PublicKey, PrivateKey) {
+            generate_with_rng(&mut OsRng)
+        }
+
+        /// `generate` for keys over a generator other than the standard one,
+        /// e.g. one picked in a custom setup ceremony
+        pub fn generate_with_generator(g: &Generator) -> (PublicKey, PrivateKey) {
+            let sk = PrivateKey::random_using_rng(&mut OsRng);
+            let pk = PublicKey::new_with_generator(&sk, g);
+            (pk, sk)
+        }
+
+        /// Creates a new BLS key pair with the private key drawn from `rng`
+        pub fn generate_with_rng<R: CryptoRng + RngCore>(rng: &mut R) -> (PublicKey, PrivateKey) {
+            let sk = PrivateKey::random_using_rng(rng);
+            let pk = PublicKey::new(&sk);
+            (pk, sk)
+        }
+
+        /// Creates the BLS key pair for `ikm`, which must be at least 32
+        /// bytes of secret seed, with the KeyGen of EIP-2333 and
+        /// draft-irtf-cfrg-bls-signature. The same seed always gives the
+        /// same keys. These are not the keys of `KeyGenOption::UseSeed`,
+        /// which keeps its older derivation.
+        pub fn generate_from_seed(ikm: &[u8]) -> Result<(PublicKey, PrivateKey), CryptoError> {
+            let sk = key_gen(ikm)?;
+            let pk = PublicKey::new(&sk);
+            Ok((pk, sk))
+        }
+
+        fn hash_msg<A: AsRef<[u8]>>(message: A, context: Option<&'static [u8]>) -> SignatureGroup {
+            let ctx: &[u8] = context.unwrap_or(MESSAGE_CONTEXT);
+            hash_to_point(message, ctx)
+        }
+
+        /// The identity accepts the identity signature on any message
+        fn check_key(pk: &Generator) -> Result<(), VerificationError> {
+            if pk.is_identity() {
+                return Err(VerificationError::InvalidKey(
+                    "The public key is the identity".to_string(),
+                ));
+            }
+            Ok(())
+        }
+
+        /// Bytes that aren't a point on the curve decode to the identity
+        fn check_signature(signature: &SignatureGroup) -> Result<(), VerificationError> {
+            if signature.is_identity() {
+                return Err(VerificationError::Malformed(
+                    "The signature is the identity".to_string(),
+                ));
+            }
+            Ok(())
+        }
+
+        fn check_pairing(valid: bool) -> Result<(), VerificationError> {
+            if valid {
+                Ok(())
+            } else {
+                Err(VerificationError::Mismatch)
+            }
+        }
+
+        fn hash_key(pk: &PublicKey, context: Option<&'static [u8]>) -> SignatureGroup {
+            let ctx: &[u8] = context.unwrap_or(PUBLICKEY_CONTEXT);
+            hash_to_point(pk.to_bytes(), ctx)
+        }
+
+        fn hash_to_point<A: AsRef<[u8]>>(v: A, ctx: &[u8]) -> SignatureGroup {
+            hash_with_context(ctx, v.as_ref())
+        }
+
+        /// Hashes `message` to the signature group with the RFC 9380
+        /// `XMD:SHA-256_SSWU_RO_` suite under the domain separation tag `dst`.
+        /// Unlike the hashing behind the `context` arguments, the result is
+        /// the same as in other BLS12-381 implementations.
+        pub fn hash_to_curve<A: AsRef<[u8]>>(message: A, dst: &[u8]) -> SignatureGroup {
+            // Section 5.3.3: tags over 255 bytes are replaced by their hash
+            if dst.len() > 255 {
+                let dst = Sha256::new()
+                    .chain(b"H2C-OVERSIZE-DST-")
+                    .chain(dst)
+                    .result();
+                return SignatureGroup::hash_to_curve(&dst, message.as_ref());
+            }
+            SignatureGroup::hash_to_curve(dst, message.as_ref())
+        }
+
+        /// The weight `H1(pk_1, ..., pk_n, pk_i)` the `KeyWeighted` scheme
+        /// gives the signer at `pk_index`, as in section 3.1 of
+        /// https://eprint.iacr.org/2018/483
+        fn rk_weight(pk_index: usize, pks: &[PublicKey]) -> Result<FieldElement, CryptoError> {
+            if pk_index >= pks.len() {
+                return Err(CryptoError::SigningError(format!(
+                    "Signer {} is not among the {} public keys",
+                    pk_index,
+                    pks.len()
+                )));
+            }
+            let mut bytes = Vec::new();
+            for k in pks {
+                bytes.extend_from_slice(k.to_bytes().as_slice());
+            }
+            bytes.extend_from_slice(pks[pk_index].to_bytes().as_slice());
+            Ok(FieldElement::from_msg_hash(bytes.as_slice()))
+        }
+
+        /// The weights of all of `pks`, see `rk_weight`
+        fn rk_weights(pks: &[PublicKey]) -> Vec<FieldElement> {
+            // To combat the rogue key attack,
+            // compute (t_1,…,t_n)←H1(pk_1,…,pk_n) ∈ R_n
+            // as described in section 3.1 from https://eprint.iacr.org/2018/483
+            let mut bytes = Vec::new();
+            for k in pks {
+                bytes.extend_from_slice(k.to_bytes().as_slice());
+            }
+            parallel::map(pks, |k| {
+                // The position of the ith public key in the byte array
+                // of the hash doesn't matter as much as its included twice.
+                // For convenience, its appended to the end
+                let mut h = bytes.clone();
+                h.extend_from_slice(k.0.to_bytes(false).as_slice());
+                FieldElement::from_msg_hash(h.as_slice())
+            })
+        }
+
+        /// Checks a signature over distinct messages, each hashed and paired
+        /// with its signer's key.
+        ///
+        /// A repeated message does not stop the check early. The pairings
+        /// are always computed so the time taken does not tell an attacker
+        /// whether, or where, the inputs had a repeated message rather than
+        /// a bad signature.
+        fn verify_hashes(
+            signature: &SignatureGroup,
+            hashes: Vec<(Generator, SignatureGroup)>,
+            g: &Generator,
+        ) -> bool {
+            check_hashes(signature, hashes, g).is_ok()
+        }
+
+        /// `verify_hashes` that reports why the signature was rejected
+        fn check_hashes(
+            signature: &SignatureGroup,
+            hashes: Vec<(Generator, SignatureGroup)>,
+            g: &Generator,
+        ) -> Result<(), VerificationError> {
+            // The identity signature would verify against no messages
+            if hashes.is_empty() {
+                return Err(VerificationError::EmptyInput);
+            }
+            let mut msg_check = ::std::collections::HashSet::new();
+            let mut distinct = Choice::from(1);
+            let mut pairs = Vec::with_capacity(hashes.len() + 1);
+            for (pk, hash) in hashes {
+                distinct &= Choice::from(msg_check.insert(hash.clone()) as u8);
+                pairs.push((pk, hash));
+            }
+
+            pairs.push((-g, signature.clone()));
+            let refs = pairs.iter().map(|(p, q)| $set_pairs((p, q))).collect();
+            let valid = multi_pairing_is_one(refs);
+            if bool::from(distinct) {
+                check_pairing(valid)
+            } else {
+                Err(VerificationError::DuplicateMessage)
+            }
+        }
+
+        /// Batch verification as described in the end of section 3.1 from
+        /// https://eprint.iacr.org/2018/483: checks
+        /// `prod(e(r_i * pk_i, H(m_i))) = e(g, sum(r_i * sig_i))` for random `r_i`.
+        /// `parts` gives the signature and public key of an input and `hash_into`
+        /// fills a buffer with the `H(m_i)`. The buffers are taken from `scratch`,
+        /// so once it has grown to the batch size only the list of pairs handed
+        /// to `multi_pairing_is_one` is allocated.
+        fn batch_check<T, P, H, R>(
+            inputs: &[T],
+            parts: P,
+            hash_into: H,
+            g: &Generator,
+            rng: &mut R,
+            scratch: &mut Scratch,
+        ) -> bool
+        where
+            T: parallel::MaybeSync,
+            P: Fn(&T) -> (&SignatureGroup, &Generator)
+                + parallel::MaybeSend
+                + parallel::MaybeSync,
+            H: FnOnce(&mut Vec<SignatureGroup>) + parallel::MaybeSend,
+            R: CryptoRng + RngCore,
+        {
+            let mut exponents = ::std::mem::take(&mut scratch.exponents);
+            exponents.clear();
+            exponents.extend(inputs.iter().map(|_| FieldElement::random_using_rng(rng)));
+            let mut keys = ::std::mem::take(Generator::points(scratch));
+            let mut hashes = ::std::mem::take(SignatureGroup::points(scratch));
+            // The public keys are paired with different messages so only the
+            // signatures can be combined in one multi-scalar multiplication.
+            // Hashing and the other multiplications are independent per input
+            let (sig, _) = parallel::join(
+                || multi_scalar_mul(inputs.iter().map(|i| parts(i).0), &exponents, scratch),
+                || {
+                    parallel::zip_map_into(&exponents, inputs, &mut keys, |r, i| parts(i).1 * r);
+                    hash_into(&mut hashes);
+                },
+            );
+            scratch.exponents = exponents;
+
+            let minus_g = -g;
+            let mut pairs = Vec::with_capacity(inputs.len() + 1);
+            pairs.extend(keys.iter().zip(hashes.iter()).map($set_pairs));
+            pairs.push($set_pairs((&minus_g, &sig)));
+            let valid = multi_pairing_is_one(pairs);
+            *Generator::points(scratch) = keys;
+            *SignatureGroup::points(scratch) = hashes;
+            valid
+        }
+
+        pub struct Bls;
+
+        impl SignatureScheme for Bls {
+            fn new() -> Self {
+                Bls
+            }
+
+            fn keypair(
+                &self,
+                options: Option<KeyGenOption>,
+            ) -> Result<(UrsaPublicKey, UrsaPrivateKey), CryptoError> {
+                let (public_key, private_key) = match options {
+                    Some(option) => match option {
+                        // Follows https://datatracker.ietf.org/doc/draft-irtf-cfrg-bls-signature/?include_text=1
+                        KeyGenOption::UseSeed(ref seed) => {
+                            let salt = b"BLS-SIG-KEYGEN-SALT-";
+                            let info = [0u8, PRIVATE_KEY_SIZE as u8]; // key_info || I2OSP(L, 2)
+                            let mut ikm = vec![0u8; seed.len() + 1];
+                            ikm[..seed.len()].copy_from_slice(seed); // IKM || I2OSP(0, 1)
+                            let mut okm = [0u8; PRIVATE_KEY_SIZE];
+                            let h = hkdf::Hkdf::<Sha256>::new(Some(&salt[..]), &ikm);
+                            h.expand(&info[..], &mut okm).map_err(|err| {
+                                CryptoError::KeyGenError(format!(
+                                    "Failed to generate keypair: {}",
+                                    err
+                                ))
+                            })?;
+                            let private_key = PrivateKey(FieldElement::from(&okm));
+                            (
+                                PublicKey::from_table(&private_key, &GENERATOR_TABLE),
+                                private_key,
+                            )
+                        }
+                        KeyGenOption::FromSecretKey(ref key) => {
+                            let private_key =
+                                PrivateKey::from_bytes(key.as_ref()).map_err(|_| {
+                                    CryptoError::ParseError(
+                                        "Failed to parse private key.".to_string(),
+                                    )
+                                })?;
+                            (
+                                PublicKey::from_table(&private_key, &GENERATOR_TABLE),
+                                private_key,
+                            )
+                        }
+                    },
+                    None => {
+                        let private_key = PrivateKey::random_using_rng(&mut OsRng);
+                        (
+                            PublicKey::from_table(&private_key, &GENERATOR_TABLE),
+                            private_key,
+                        )
+                    }
+                };
+                Ok((
+                    UrsaPublicKey(public_key.to_bytes()),
+                    UrsaPrivateKey(private_key.to_bytes()),
+                ))
+            }
+
+            fn sign(&self, message: &[u8], sk: &UrsaPrivateKey) -> Result<Vec<u8>, CryptoError> {
+                Ok(Signature::new(
+                    message,
+                    None,
+                    &PrivateKey::from_bytes(sk.as_ref()).map_err(|_| {
+                        CryptoError::ParseError("Failed to parse private key.".to_string())
+                    })?,
+                )
+                .to_bytes())
+            }
+
+            fn verify(
+                &self,
+                message: &[u8],
+                signature: &[u8],
+                pk: &UrsaPublicKey,
+            ) -> Result<bool, CryptoError> {
+                Ok(Signature::from_bytes(signature)
+                    .map_err(|_| CryptoError::ParseError("Failed to parse signature.".to_string()))?
+                    .verify(
+                        message,
+                        None,
+                        &PublicKey::from_bytes(pk.as_ref()).map_err(|_| {
+                            CryptoError::ParseError("Failed to parse public key.".to_string())
+                        })?,
+                    ))
+            }
+
+            fn verify_strict(
+                &self,
+                message: &[u8],
+                signature: &[u8],
+                pk: &UrsaPublicKey,
+            ) -> Result<(), VerificationError> {
+                SignatureRef::from_bytes(signature)
+                    .map_err(|e| VerificationError::Malformed(e.to_string()))?
+                    .verify_strict(
+                        message,
+                        None,
+                        &PublicKeyRef::from_bytes(pk.as_ref())
+                            .map_err(|e| VerificationError::InvalidKey(e.to_string()))?,
+                    )
+            }
+
+            fn signature_size() -> usize {
+                SIGNATURE_SIZE
+            }
+
+            fn private_key_size() -> usize {
+                PRIVATE_KEY_SIZE
+            }
+
+            fn public_key_size() -> usize {
+                PUBLIC_KEY_SIZE
+            }
+        }
+
+        impl BatchVerify for Bls {
+            /// Checks the batch with `Signature::batch_verify`, which needs one
+            /// pairing per signature plus one instead of two per signature
+            fn batch_verify(&self, inputs: &[(&[u8], &[u8], &UrsaPublicKey)]) -> bool {
+                let decoded = inputs
+                    .iter()
+                    .map(|&(message, signature, pk)| -> Result<_, CryptoError> {
+                        Ok((
+                            message,
+                            Signature::from_bytes(signature)?,
+                            PublicKey::from_bytes(pk.as_ref())?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, _>>();
+                match decoded {
+                    Ok(decoded) => {
+                        let inputs = decoded
+                            .iter()
+                            .map(|(message, signature, pk)| (*message, signature, pk))
+                            .collect::<Vec<_>>();
+                        Signature::batch_verify(&inputs, None)
+                    }
+                    Err(_) => false,
+                }
+            }
+        }
+
+        #[derive(Debug, Clone)]
+        pub struct PublicKey(Generator);
+
+        impl PublicKey {
+            pub fn new(sk: &PrivateKey) -> Self {
+                Self::from_table(sk, &GENERATOR_TABLE)
+            }
+
+            /// `new` over a generator other than the standard one
+            pub fn new_with_generator(sk: &PrivateKey, g: &Generator) -> Self {
+                PublicKey(g * &sk.0)
+            }
+
+            /// `new` with the precomputed table of the generator
+            pub fn from_table(sk: &PrivateKey, table: &GeneratorTable) -> Self {
+                PublicKey(table.mul(&sk.0))
+            }
+
+            // Create an combined public key without rogue key mitigation
+            pub fn combine(&mut self, pks: &[PublicKey]) {
+                for pk in pks {
+                    self.0 += &pk.0;
+                }
+            }
+
+            pub fn to_bytes(&self) -> Vec<u8> {
+                self.0.to_bytes(false)
+            }
+
+            /// Fails with `CryptoError::InvalidPoint` for the identity and
+            /// points outside the prime order subgroup
+            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+                decode_point(bytes, "public key").map(PublicKey)
+            }
+
+            /// The SHA-256 digest of the uncompressed key
+            pub fn fingerprint(&self) -> Fingerprint {
+                Fingerprint::digest(&self.to_bytes())
+            }
+
+            /// The `did:key` of this public key
+            pub fn to_did_key(&self) -> String {
+                format!(
+                    "{}{}",
+                    DID_KEY_PREFIX,
+                    multibase::encode_key(Multicodec::$pk_codec, &self.to_bytes())
+                )
+            }
+
+            /// Resolve a `did:key` of this key type
+            pub fn from_did_key(did: &str) -> Result<Self, CryptoError> {
+                let did = did.parse::<DidKey>()?;
+                if did.codec() != Multicodec::$pk_codec {
+                    return Err(CryptoError::ParseError(format!(
+                        "Expected a {:?} did:key, found {:?}",
+                        Multicodec::$pk_codec,
+                        did.codec()
+                    )));
+                }
+                Self::from_bytes(did.public_key())
+            }
+        }
+
+        /// A `PublicKey` that passed the identity and subgroup checks.
+        ///
+        /// The subgroup check costs about as much as a pairing and runs
+        /// every time a `PublicKeyRef` is decoded. Validate a key once with
+        /// `from_bytes` or `PublicKeyRef::validate` and keep it to verify
+        /// its signatures with `verify_validated`, which skips the key checks.
+        #[derive(Debug, Clone)]
+        pub struct ValidatedPublicKey(PublicKey);
+
+        impl TryFrom<PublicKey> for ValidatedPublicKey {
+            type Error = CryptoError;
+
+            /// Keys from `combine` or made with a zero private key aren't
+            /// checked when they are created
+            fn try_from(pk: PublicKey) -> Result<Self, CryptoError> {
+                check_point(pk.0, "public key").map(|p| ValidatedPublicKey(PublicKey(p)))
+            }
+        }
+
+        impl From<ValidatedPublicKey> for PublicKey {
+            fn from(pk: ValidatedPublicKey) -> Self {
+                pk.0
+            }
+        }
+
+        impl AsRef<PublicKey> for ValidatedPublicKey {
+            fn as_ref(&self) -> &PublicKey {
+                &self.0
+            }
+        }
+
+        impl ValidatedPublicKey {
+            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+                PublicKey::from_bytes(bytes).map(ValidatedPublicKey)
+            }
+
+            pub fn to_bytes(&self) -> Vec<u8> {
+                self.0.to_bytes()
+            }
+
+            pub fn public_key(&self) -> &PublicKey {
+                &self.0
+            }
+        }
+
+        /// Represents an aggregated BLS public key that mitigates the rogue key attack
+        /// for verifying aggregated signatures.
+        #[derive(Debug, Clone)]
+        pub struct AggregatedPublicKey(Generator);
+
+        impl<'a> TryFrom<&'a [PublicKey]> for AggregatedPublicKey {
+            type Error = CryptoError;
+
+            fn try_from(keys: &'a [PublicKey]) -> Result<Self, CryptoError> {
+                Scratch::with_local(|scratch| Self::new_with_scratch(keys, scratch))
+            }
+        }
+
+        impl AggregatedPublicKey {
+            /// Fails if `keys` is empty
+            pub fn new(keys: &[PublicKey]) -> Result<Self, CryptoError> {
+                Self::try_from(keys)
+            }
+
+            /// `new` with the buffers for combining the keys taken from `scratch`
+            pub fn new_with_scratch(
+                keys: &[PublicKey],
+                scratch: &mut Scratch,
+            ) -> Result<Self, CryptoError> {
+                if keys.is_empty() {
+                    return Err(CryptoError::GeneralError(
+                        "Can't aggregate an empty set of public keys".to_string(),
+                    ));
+                }
+                let weights = rk_weights(keys);
+                let points = keys.iter().map(|k| &k.0);
+                Ok(AggregatedPublicKey(multi_scalar_mul(
+                    points, &weights, scratch,
+                )))
+            }
+
+            /// Take out `pk`, weighted as in the aggregate of `signer_set`,
+            /// the keys the aggregate and its signatures were made with.
+            /// The other keys keep the weights `signer_set` gave them, so the
+            /// result verifies the remaining signatures but differs from
+            /// `new` of the remaining keys. Fails if `pk` is not in `signer_set`.
+            pub fn remove(
+                &mut self,
+                pk: &PublicKey,
+                signer_set: &[PublicKey],
+            ) -> Result<(), CryptoError> {
+                let index = signer_set
+                    .iter()
+                    .position(|k| k.0 == pk.0)
+                    .ok_or_else(|| {
+                        CryptoError::GeneralError("The key is not in the signer set".to_string())
+                    })?;
+                self.0 -= &pk.0 * &rk_weight(index, signer_set)?;
+                Ok(())
+            }
+
+            pub fn to_bytes(&self) -> Vec<u8> {
+                self.0.to_bytes(false)
+            }
+
+            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+                decode_point(bytes, "aggregated public key").map(AggregatedPublicKey)
+            }
+        }
+
+        /// Signature over a message. One gotcha for BLS signatures
+        /// is the need to mitigate rogue key attacks. There are two methods to achieve
+        /// this: compute additional work to make each message distinct
+        /// in a signature for each `PublicKey` or
+        /// use `ProofOfPossession`. `Signature` and `ProofOfPossession` MUST
+        /// use domain separation values that are different
+        /// to avoid certain types of attacks and make `Signature`
+        /// distinct from `ProofOfPossession`. If `ProofOfPossession`
+        /// and `Signature` use the same value for `context` they are effectively the same.
+        /// Don't do this. You have been warned.
+        ///
+        /// To make messages distinct, use `new_with_rk_mitigation`. If using
+        /// proof of possession mitigation, use `new`.
+        #[derive(Debug, Clone)]
+        pub struct Signature(SignatureGroup);
+
+        impl Signature {
+            pub fn new<A: AsRef<[u8]>>(
+                message: A,
+                context: Option<&'static [u8]>,
+                sk: &PrivateKey,
+            ) -> Self {
+                Signature(hash_msg(message, context) * &sk.0)
+            }
+
+            /// Fails if `pk_index` is not an index of `pks`
+            pub fn new_with_rk_mitigation<A: AsRef<[u8]>>(
+                message: A,
+                context: Option<&'static [u8]>,
+                sk: &PrivateKey,
+                pk_index: usize,
+                pks: &[PublicKey],
+            ) -> Result<Self, CryptoError> {
+                // To combat the rogue key attack,
+                // compute (t_1,…,t_n)←H1(pk_1,…,pk_n) ∈ R_n
+                // output the aggregated public key
+                // as described in section 3.1 from https://eprint.iacr.org/2018/483
+                let a = rk_weight(pk_index, pks)?;
+                Ok(Signature(hash_msg(message, context) * &sk.0 * &a))
+            }
+
+            // Collects multiple signatures into a single signature
+            // Verified by using `verify_multi`. This method does not
+            // directly mitigate the rogue key attack. It is expected the caller
+            // handles this using other techniques like proof of possession
+            pub fn combine(&mut self, signatures: &[Signature]) {
+                for sig in signatures {
+                    self.0 += &sig.0;
+                }
+            }
+
+            // Verify a signature generated by `new`
+            pub fn verify<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &PublicKey,
+            ) -> bool {
+                self.verify_with_generator(message, context, pk, &GENERATOR)
+            }
+
+            /// `verify` for a key made with `PublicKey::new_with_generator`
+            pub fn verify_with_generator<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &PublicKey,
+                g: &Generator,
+            ) -> bool {
+                let hash = hash_msg(message, context);
+                $ate_2_pairing_is_one(&g, &self.0, &pk.0, &hash)
+            }
+
+            /// `verify` for a message hashed with `MessagePoint::hash`
+            pub fn verify_prehashed(&self, message: &MessagePoint, pk: &PublicKey) -> bool {
+                $ate_2_pairing_is_one(generator(), &self.0, &pk.0, &message.0)
+            }
+
+            /// `verify` that reports why the signature was rejected
+            pub fn verify_strict<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &PublicKey,
+            ) -> Result<(), VerificationError> {
+                check_key(&pk.0)?;
+                check_signature(&self.0)?;
+                check_pairing(self.verify(message, context, pk))
+            }
+
+            /// `verify_strict` for a key that was already checked
+            pub fn verify_validated<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &ValidatedPublicKey,
+            ) -> Result<(), VerificationError> {
+                check_signature(&self.0)?;
+                check_pairing(self.verify(message, context, &pk.0))
+            }
+
+            // Caller should aggregate all signatures into `self` by using `combine`.
+            // Messages must be distinct. A repeated message is rejected only
+            // after the pairings, like a bad signature.
+            // `inputs` is a slice of message - public key tuples
+            // Multisignature verification
+            pub fn verify_multi(
+                &self,
+                inputs: &[(&[u8], &PublicKey)],
+                context: Option<&'static [u8]>,
+            ) -> bool {
+                self.verify_multi_with_generator(inputs, context, &GENERATOR)
+            }
+
+            /// `verify_multi` for keys made with `PublicKey::new_with_generator`
+            pub fn verify_multi_with_generator(
+                &self,
+                inputs: &[(&[u8], &PublicKey)],
+                context: Option<&'static [u8]>,
+                g: &Generator,
+            ) -> bool {
+                // Hashing to the curve dominates for many signers and is
+                // independent per input, only the multi-pairing is not
+                let hashes = parallel::map(inputs, |&(msg, pk)| {
+                    (pk.0.clone(), hash_msg(msg, context))
+                });
+                verify_hashes(&self.0, hashes, g)
+            }
+
+            /// `verify_multi` that reports why the signature was rejected
+            pub fn verify_multi_strict(
+                &self,
+                inputs: &[(&[u8], &PublicKey)],
+                context: Option<&'static [u8]>,
+            ) -> Result<(), VerificationError> {
+                let g = generator();
+                for (_, pk) in inputs {
+                    check_key(&pk.0)?;
+                }
+                check_signature(&self.0)?;
+                let hashes = parallel::map(inputs, |&(msg, pk)| {
+                    (pk.0.clone(), hash_msg(msg, context))
+                });
+                check_hashes(&self.0, hashes, g)
+            }
+
+            pub fn batch_verify(
+                inputs: &[(&[u8], &Signature, &PublicKey)],
+                context: Option<&'static [u8]>,
+            ) -> bool {
+                Self::batch_verify_with_rng(inputs, context, &mut OsRng)
+            }
+
+            /// `batch_verify` with the random exponents drawn from `rng`
+            pub fn batch_verify_with_rng<R: CryptoRng + RngCore>(
+                inputs: &[(&[u8], &Signature, &PublicKey)],
+                context: Option<&'static [u8]>,
+                rng: &mut R,
+            ) -> bool {
+                Scratch::with_local(|scratch| {
+                    Self::batch_verify_with_scratch(inputs, context, rng, scratch)
+                })
+            }
+
+            /// `batch_verify_with_rng` with the buffers for combining the
+            /// signatures taken from `scratch`
+            pub fn batch_verify_with_scratch<R: CryptoRng + RngCore>(
+                inputs: &[(&[u8], &Signature, &PublicKey)],
+                context: Option<&'static [u8]>,
+                rng: &mut R,
+                scratch: &mut Scratch,
+            ) -> bool {
+                Self::batch_check_messages(inputs, context, &GENERATOR, rng, scratch)
+            }
+
+            /// `batch_verify` for keys made with `PublicKey::new_with_generator`
+            pub fn batch_verify_with_generator(
+                inputs: &[(&[u8], &Signature, &PublicKey)],
+                context: Option<&'static [u8]>,
+                g: &Generator,
+            ) -> bool {
+                Scratch::with_local(|scratch| {
+                    Self::batch_check_messages(inputs, context, g, &mut OsRng, scratch)
+                })
+            }
+
+            fn batch_check_messages<R: CryptoRng + RngCore>(
+                inputs: &[(&[u8], &Signature, &PublicKey)],
+                context: Option<&'static [u8]>,
+                g: &Generator,
+                rng: &mut R,
+                scratch: &mut Scratch,
+            ) -> bool {
+                // To avoid rogue key attacks, you must use proof of possession or `AggregateSignature::batch_verify`
+                // This function just avoids checking for distinct messages
+                batch_check(
+                    inputs,
+                    |&(_, sig, pk)| (&sig.0, &pk.0),
+                    |hashes| {
+                        parallel::map_into(inputs, hashes, |&(msg, _, _)| hash_msg(msg, context))
+                    },
+                    g,
+                    rng,
+                    scratch,
+                )
+            }
+
+            /// `batch_verify` for messages hashed with `MessagePoint::hash`
+            pub fn batch_verify_prehashed(
+                inputs: &[(&MessagePoint, &Signature, &PublicKey)],
+            ) -> bool {
+                Self::batch_verify_prehashed_with_rng(inputs, &mut OsRng)
+            }
+
+            /// `batch_verify_prehashed` with the random exponents drawn from `rng`
+            pub fn batch_verify_prehashed_with_rng<R: CryptoRng + RngCore>(
+                inputs: &[(&MessagePoint, &Signature, &PublicKey)],
+                rng: &mut R,
+            ) -> bool {
+                Scratch::with_local(|scratch| {
+                    batch_check(
+                        inputs,
+                        |&(_, sig, pk)| (&sig.0, &pk.0),
+                        |hashes| parallel::map_into(inputs, hashes, |&(msg, _, _)| msg.0.clone()),
+                        generator(),
+                        rng,
+                        scratch,
+                    )
+                })
+            }
+
+            /// `batch_verify` on another thread, for callers on an async executor
+            #[cfg(feature = "async")]
+            pub fn batch_verify_async(
+                inputs: Vec<(Vec<u8>, Signature, PublicKey)>,
+                context: Option<&'static [u8]>,
+            ) -> Blocking<bool> {
+                blocking::spawn_blocking(move || {
+                    let inputs = inputs
+                        .iter()
+                        .map(|(msg, sig, pk)| (msg.as_slice(), sig, pk))
+                        .collect::<Vec<_>>();
+                    Self::batch_verify(&inputs, context)
+                })
+            }
+
+            pub fn to_bytes(&self) -> Vec<u8> {
+                self.0.to_bytes(false)
+            }
+
+            /// Fails with `CryptoError::InvalidPoint` for the identity and
+            /// points outside the prime order subgroup
+            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+                decode_point(bytes, "signature").map(Signature)
+            }
+        }
+
+        /// A signature by one holder of a `SignatureShare`, tagged with the
+        /// share's identifier
+        #[cfg(feature = "bls_threshold")]
+        #[derive(Debug, Clone)]
+        pub struct PartialSignature {
+            identifier: u32,
+            signature: Signature,
+        }
+
+        #[cfg(feature = "bls_threshold")]
+        impl PartialSignature {
+            pub fn new<A: AsRef<[u8]>>(
+                message: A,
+                context: Option<&'static [u8]>,
+                share: &SignatureShare,
+            ) -> Self {
+                PartialSignature {
+                    identifier: share.identifier(),
+                    signature: Signature::new(message, context, share.private_key()),
+                }
+            }
+
+            pub fn identifier(&self) -> u32 {
+                self.identifier
+            }
+
+            pub fn signature(&self) -> &Signature {
+                &self.signature
+            }
+
+            /// Verify under the public key of the holder's share, so a bad
+            /// partial signature can be told apart before combining
+            pub fn verify<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &PublicKey,
+            ) -> bool {
+                self.signature.verify(message, context, pk)
+            }
+
+            /// Interpolate the signature of the key that was split. Fails if
+            /// there are no partial signatures or an identifier is 0 or
+            /// repeated. The result only verifies if at least the threshold
+            /// of valid partial signatures were given.
+            pub fn combine_with_lagrange(
+                partials: &[PartialSignature],
+            ) -> Result<Signature, CryptoError> {
+                let shares = partials
+                    .iter()
+                    .map(|p| (p.identifier, p.signature.clone()))
+                    .collect::<Vec<_>>();
+                combine_signature_shares(&shares)
+            }
+
+            /// The identifier as 4 big endian bytes followed by the signature
+            pub fn to_bytes(&self) -> Vec<u8> {
+                let mut bytes = self.identifier.to_be_bytes().to_vec();
+                bytes.extend_from_slice(&self.signature.to_bytes());
+                bytes
+            }
+
+            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+                check_encoding_length(bytes, 4 + SIGNATURE_SIZE)?;
+                let mut identifier = [0u8; 4];
+                identifier.copy_from_slice(&bytes[..4]);
+                Ok(PartialSignature {
+                    identifier: u32::from_be_bytes(identifier),
+                    signature: Signature::from_bytes(&bytes[4..])?,
+                })
+            }
+        }
+
+        /// Interpolate the signature of the key that was split from
+        /// signatures by its shares, each with the share's identifier.
+        /// `PartialSignature::combine_with_lagrange` for signatures that
+        /// did not come as `PartialSignature`s.
+        #[cfg(feature = "bls_threshold")]
+        pub fn combine_signature_shares(
+            shares: &[(u32, Signature)],
+        ) -> Result<Signature, CryptoError> {
+            let identifiers = shares.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+            let coefficients = lagrange_coefficients(&identifiers)?;
+            let mut signature = SignatureGroup::identity();
+            for ((_, s), l) in shares.iter().zip(coefficients.iter()) {
+                signature += &s.0 * l;
+            }
+            Ok(Signature(signature))
+        }
+
+        /// The public key of one `SignatureShare`, which verifies the
+        /// holder's partial signatures. Any threshold of them combine into
+        /// the public key of the key that was split, so a committee can be
+        /// checked against it without the dealer.
+        #[cfg(feature = "bls_threshold")]
+        #[derive(Debug, Clone)]
+        pub struct PublicKeyShare {
+            identifier: u32,
+            pk: PublicKey,
+        }
+
+        #[cfg(feature = "bls_threshold")]
+        impl PublicKeyShare {
+            pub fn new(share: &SignatureShare) -> Self {
+                PublicKeyShare {
+                    identifier: share.identifier(),
+                    pk: PublicKey::new(share.private_key()),
+                }
+            }
+
+            /// Fails if `identifier` is 0
+            pub fn from_public_key(identifier: u32, pk: PublicKey) -> Result<Self, CryptoError> {
+                if identifier == 0 {
+                    return Err(CryptoError::ParseError(
+                        "Share identifiers start at 1".to_string(),
+                    ));
+                }
+                Ok(PublicKeyShare { identifier, pk })
+            }
+
+            pub fn identifier(&self) -> u32 {
+                self.identifier
+            }
+
+            pub fn public_key(&self) -> &PublicKey {
+                &self.pk
+            }
+
+            /// Verify a partial signature by the same share
+            pub fn verify<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                partial: &PartialSignature,
+            ) -> bool {
+                partial.identifier() == self.identifier
+                    && partial.verify(message, context, &self.pk)
+            }
+
+            /// Interpolate the public key of the key that was split, with
+            /// the same rules as `combine_signature_shares`
+            pub fn combine(shares: &[PublicKeyShare]) -> Result<PublicKey, CryptoError> {
+                let identifiers = shares.iter().map(|s| s.identifier).collect::<Vec<_>>();
+                let coefficients = lagrange_coefficients(&identifiers)?;
+                let mut pk = Generator::identity();
+                for (s, l) in shares.iter().zip(coefficients.iter()) {
+                    pk += &s.pk.0 * l;
+                }
+                Ok(PublicKey(pk))
+            }
+
+            /// The identifier as 4 big endian bytes followed by the public key
+            pub fn to_bytes(&self) -> Vec<u8> {
+                let mut bytes = self.identifier.to_be_bytes().to_vec();
+                bytes.extend_from_slice(&self.pk.to_bytes());
+                bytes
+            }
+
+            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+                check_encoding_length(bytes, 4 + PUBLIC_KEY_SIZE)?;
+                let mut identifier = [0u8; 4];
+                identifier.copy_from_slice(&bytes[..4]);
+                Self::from_public_key(
+                    u32::from_be_bytes(identifier),
+                    PublicKey::from_bytes(&bytes[4..])?,
+                )
+            }
+        }
+
+        /// Options for signing and verifying, in place of picking among the
+        /// `Signature::new*` and `verify*` functions.
+        ///
+        /// The default is the `Pop` scheme with `MESSAGE_CONTEXT` as the
+        /// domain separation tag. `prehashed` signs the SHA-256 hash of the
+        /// message instead of the message itself. `hash_to_curve` switches
+        /// to the standard hash, see the function of the same name.
+        #[derive(Clone, Debug)]
+        pub struct BlsSigningContext {
+            scheme: BlsScheme,
+            dst: Option<&'static [u8]>,
+            prehashed: bool,
+            hash_to_curve: bool,
+        }
+
+        impl Default for BlsSigningContext {
+            fn default() -> Self {
+                BlsSigningContext {
+                    scheme: BlsScheme::Pop,
+                    dst: None,
+                    prehashed: false,
+                    hash_to_curve: false,
+                }
+            }
+        }
+
+        impl BlsSigningContext {
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            pub fn scheme(mut self, scheme: BlsScheme) -> Self {
+                self.scheme = scheme;
+                self
+            }
+
+            /// The domain separation tag messages are hashed with
+            pub fn dst(mut self, dst: &'static [u8]) -> Self {
+                self.dst = Some(dst);
+                self
+            }
+
+            pub fn prehashed(mut self, prehashed: bool) -> Self {
+                self.prehashed = prehashed;
+                self
+            }
+
+            /// Hash messages with RFC 9380. Without a `dst` the tag is the
+            /// scheme's, e.g. `POP_DST`. Signatures made this way only verify
+            /// through a context that also sets this.
+            pub fn hash_to_curve(mut self, hash_to_curve: bool) -> Self {
+                self.hash_to_curve = hash_to_curve;
+                self
+            }
+
+            /// Sign with the `Basic` or `Pop` scheme
+            pub fn sign<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                sk: &PrivateKey,
+            ) -> Result<Signature, CryptoError> {
+                if self.scheme == BlsScheme::KeyWeighted {
+                    return Err(CryptoError::SigningError(
+                        "KeyWeighted signatures need the signers' keys, use sign_weighted"
+                            .to_string(),
+                    ));
+                }
+                Ok(Signature(self.hash(message) * &sk.0))
+            }
+
+            /// Sign with the `KeyWeighted` scheme as the signer at `pk_index`
+            /// in `pks`
+            pub fn sign_weighted<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                sk: &PrivateKey,
+                pk_index: usize,
+                pks: &[PublicKey],
+            ) -> Result<Signature, CryptoError> {
+                if self.scheme != BlsScheme::KeyWeighted {
+                    return Err(CryptoError::SigningError(format!(
+                        "The {:?} scheme doesn't weight signatures",
+                        self.scheme
+                    )));
+                }
+                let a = rk_weight(pk_index, pks)?;
+                Ok(Signature(self.hash(message) * &sk.0 * &a))
+            }
+
+            /// Verify a signature from `sign`
+            pub fn verify<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                signature: &Signature,
+                pk: &PublicKey,
+            ) -> bool {
+                let g = generator();
+                $ate_2_pairing_is_one(&g, &signature.0, &pk.0, &self.hash(message))
+            }
+
+            /// Verify signatures over the same message aggregated from the
+            /// signers `pks`. `Basic` never aggregates such signatures.
+            pub fn verify_aggregate<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                signature: &AggregatedSignature,
+                pks: &[PublicKey],
+            ) -> bool {
+                let g = generator();
+                let apk = match self.scheme {
+                    BlsScheme::Basic => return false,
+                    // The identity key would accept the identity signature
+                    BlsScheme::Pop if pks.is_empty() => return false,
+                    BlsScheme::Pop => pks.iter().fold(Generator::identity(), |a, p| a + &p.0),
+                    BlsScheme::KeyWeighted => match AggregatedPublicKey::new(pks) {
+                        Ok(apk) => apk.0,
+                        Err(_) => return false,
+                    },
+                };
+                $ate_2_pairing_is_one(&g, &signature.0, &apk, &self.hash(message))
+            }
+
+            /// Verify a signature combined from signatures over distinct
+            /// messages with `Signature::combine`
+            pub fn verify_multi(
+                &self,
+                inputs: &[(&[u8], &PublicKey)],
+                signature: &Signature,
+            ) -> bool {
+                let g = generator();
+                let hashes = parallel::map(inputs, |&(m, pk)| (pk.0.clone(), self.hash(m)));
+                verify_hashes(&signature.0, hashes, g)
+            }
+
+            /// `verify` that reports why the signature was rejected
+            pub fn verify_strict<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                signature: &Signature,
+                pk: &PublicKey,
+            ) -> Result<(), VerificationError> {
+                check_key(&pk.0)?;
+                check_signature(&signature.0)?;
+                check_pairing(self.verify(message, signature, pk))
+            }
+
+            /// `verify_multi` that reports why the signature was rejected
+            pub fn verify_multi_strict(
+                &self,
+                inputs: &[(&[u8], &PublicKey)],
+                signature: &Signature,
+            ) -> Result<(), VerificationError> {
+                let g = generator();
+                for (_, pk) in inputs {
+                    check_key(&pk.0)?;
+                }
+                check_signature(&signature.0)?;
+                let hashes = parallel::map(inputs, |&(m, pk)| (pk.0.clone(), self.hash(m)));
+                check_hashes(&signature.0, hashes, g)
+            }
+
+            fn hash<A: AsRef<[u8]>>(&self, message: A) -> SignatureGroup {
+                let message = if self.prehashed {
+                    Sha256::digest(message.as_ref()).to_vec()
+                } else {
+                    message.as_ref().to_vec()
+                };
+                if !self.hash_to_curve {
+                    return hash_msg(message, self.dst);
+                }
+                let dst = self.dst.unwrap_or(match self.scheme {
+                    BlsScheme::Basic => BASIC_DST,
+                    BlsScheme::Pop => POP_DST,
+                    BlsScheme::KeyWeighted => KEY_WEIGHTED_DST,
+                });
+                hash_to_curve(message, dst)
+            }
+        }
+
+        /// A `PublicKey` borrowed from its encoding, for ingesting keys
+        /// that may only be compared, hashed or forwarded. Creating one checks
+        /// the length, the point is decompressed and validated by `decode`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct PublicKeyRef<'a>(&'a [u8]);
+
+        impl<'a> TryFrom<&'a [u8]> for PublicKeyRef<'a> {
+            type Error = CryptoError;
+
+            fn try_from(bytes: &'a [u8]) -> Result<Self, CryptoError> {
+                Self::from_bytes(bytes)
+            }
+        }
+
+        impl<'a> fmt::Display for PublicKeyRef<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", hex::encode(self.0))
+            }
+        }
+
+        impl<'a> AsRef<[u8]> for PublicKeyRef<'a> {
+            fn as_ref(&self) -> &[u8] {
+                self.0
+            }
+        }
+
+        impl<'a> PublicKeyRef<'a> {
+            pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, CryptoError> {
+                check_encoding_length(bytes, PUBLIC_KEY_SIZE)?;
+                Ok(PublicKeyRef(bytes))
+            }
+
+            pub fn as_bytes(&self) -> &'a [u8] {
+                self.0
+            }
+
+            pub fn decode(&self) -> Result<PublicKey, CryptoError> {
+                PublicKey::from_bytes(self.0)
+            }
+
+            /// `decode` to a key that later verifications don't check again
+            pub fn validate(&self) -> Result<ValidatedPublicKey, CryptoError> {
+                ValidatedPublicKey::from_bytes(self.0)
+            }
+        }
+
+        /// A `Signature` borrowed from its encoding, decoded on `decode`
+        /// or `verify`. Signatures seen twice can be dropped by their bytes
+        /// before any curve arithmetic.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct SignatureRef<'a>(&'a [u8]);
+
+        impl<'a> TryFrom<&'a [u8]> for SignatureRef<'a> {
+            type Error = CryptoError;
+
+            fn try_from(bytes: &'a [u8]) -> Result<Self, CryptoError> {
+                Self::from_bytes(bytes)
+            }
+        }
+
+        impl<'a> fmt::Display for SignatureRef<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", hex::encode(self.0))
+            }
+        }
+
+        impl<'a> AsRef<[u8]> for SignatureRef<'a> {
+            fn as_ref(&self) -> &[u8] {
+                self.0
+            }
+        }
+
+        impl<'a> SignatureRef<'a> {
+            pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, CryptoError> {
+                check_encoding_length(bytes, SIGNATURE_SIZE)?;
+                Ok(SignatureRef(bytes))
+            }
+
+            pub fn as_bytes(&self) -> &'a [u8] {
+                self.0
+            }
+
+            pub fn decode(&self) -> Result<Signature, CryptoError> {
+                Signature::from_bytes(self.0)
+            }
+
+            /// Decodes the signature and key and checks it with `Signature::verify`
+            pub fn verify<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &PublicKeyRef,
+            ) -> Result<bool, CryptoError> {
+                Ok(self.decode()?.verify(message, context, &pk.decode()?))
+            }
+
+            /// Decodes the signature and key and checks it with
+            /// `Signature::verify_strict`
+            pub fn verify_strict<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &PublicKeyRef,
+            ) -> Result<(), VerificationError> {
+                let signature = self
+                    .decode()
+                    .map_err(|e| VerificationError::Malformed(e.to_string()))?;
+                let pk = pk
+                    .decode()
+                    .map_err(|e| VerificationError::InvalidKey(e.to_string()))?;
+                signature.verify_strict(message, context, &pk)
+            }
+
+            /// Decodes the signature and checks it with
+            /// `Signature::verify_validated`
+            pub fn verify_validated<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &ValidatedPublicKey,
+            ) -> Result<(), VerificationError> {
+                let signature = self
+                    .decode()
+                    .map_err(|e| VerificationError::Malformed(e.to_string()))?;
+                signature.verify_validated(message, context, pk)
+            }
+        }
+
+        /// Proof of possession for BLS verification key.
+        /// Used as another form of rogue key mitigation
+        /// where signers are known entities in a group.
+        /// Virtually identical to a signature but should
+        /// use a different domain separation than `Signature`.
+        #[derive(Debug, Clone)]
+        pub struct ProofOfPossession(SignatureGroup);
+
+        impl ProofOfPossession {
+            pub fn new(pk: &PublicKey, context: Option<&'static [u8]>, sk: &PrivateKey) -> Self {
+                ProofOfPossession(hash_key(pk, context) * &sk.0)
+            }
+
+            pub fn to_bytes(&self) -> Vec<u8> {
+                self.0.to_bytes(false)
+            }
+
+            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+                decode_point(bytes, "proof of possession").map(ProofOfPossession)
+            }
+
+            pub fn verify(&self, context: Option<&'static [u8]>, pk: &PublicKey) -> bool {
+                self.verify_with_generator(context, pk, &GENERATOR)
+            }
+
+            /// `verify` for a key made with `PublicKey::new_with_generator`
+            pub fn verify_with_generator(
+                &self,
+                context: Option<&'static [u8]>,
+                pk: &PublicKey,
+                g: &Generator,
+            ) -> bool {
+                let hash = hash_key(pk, context);
+                $ate_2_pairing_is_one(&g, &self.0, &pk.0, &hash)
+            }
+        }
+
+        /// A message hashed to the signature group. Hashing to the curve is
+        /// most of the cost of a verification, so checking many signatures
+        /// on the same message can hash it once and use the `_prehashed`
+        /// functions.
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct MessagePoint(SignatureGroup);
+
+        impl MessagePoint {
+            /// The point `verify` computes for `message` and `context`
+            pub fn hash<A: AsRef<[u8]>>(message: A, context: Option<&'static [u8]>) -> Self {
+                MessagePoint(hash_msg(message, context))
+            }
+        }
+
+        /// Zero-knowledge proof of knowledge of a `Signature`.
+        /// The signature itself is never revealed so presentations of the
+        /// same signature can't be linked to each other.
+        ///
+        /// The signature is blinded as `sig * q^r` for the generator `q` of the signature group,
+        /// then `e(g, sig * q^r) / e(pk, H(m)) = e(g, q)^r` and the prover shows it knows `r`.
+        /// The message is revealed. Hiding it would need a proof that the hidden point is a hash
+        /// of some message, which BLS can't give, so use BBS+ to sign hidden messages.
+        /// The verifier should choose a fresh `nonce` for each proof to prevent replays.
+        #[derive(Debug, Clone)]
+        pub struct SignatureProof {
+            blinded_signature: SignatureGroup,
+            challenge: FieldElement,
+            signature_response: FieldElement,
+        }
+
+        impl SignatureProof {
+            /// Prove knowledge of a signature on a revealed message
+            pub fn new<A: AsRef<[u8]>>(
+                signature: &Signature,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &PublicKey,
+                nonce: &[u8],
+            ) -> Self {
+                Self::new_with_rng(signature, message, context, pk, nonce, &mut OsRng)
+            }
+
+            /// `new` with the blinding and nonces drawn from `rng`
+            pub fn new_with_rng<A: AsRef<[u8]>, R: CryptoRng + RngCore>(
+                signature: &Signature,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &PublicKey,
+                nonce: &[u8],
+                rng: &mut R,
+            ) -> Self {
+                let g = generator();
+                let message = hash_msg(message, context);
+                Self::prove(signature, &message, pk, g, nonce, rng)
+            }
+
+            /// `new` for a key made with `PublicKey::new_with_generator`.
+            /// The proof only verifies with `verify_with_generator` and the
+            /// same generator.
+            pub fn new_with_generator<A: AsRef<[u8]>>(
+                signature: &Signature,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &PublicKey,
+                nonce: &[u8],
+                g: &Generator,
+            ) -> Self {
+                let message = hash_msg(message, context);
+                Self::prove(signature, &message, pk, g, nonce, &mut OsRng)
+            }
+
+            /// Verify a proof generated by `new`
+            pub fn verify<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &PublicKey,
+                nonce: &[u8],
+            ) -> bool {
+                self.verify_with_generator(message, context, pk, nonce, &GENERATOR)
+            }
+
+            /// `verify` for a proof made with `new_with_generator`
+            pub fn verify_with_generator<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &PublicKey,
+                nonce: &[u8],
+                g: &Generator,
+            ) -> bool {
+                self.check(&hash_msg(message, context), pk, g, nonce)
+            }
+
+            fn prove<R: CryptoRng + RngCore>(
+                signature: &Signature,
+                message: &SignatureGroup,
+                pk: &PublicKey,
+                g: &Generator,
+                nonce: &[u8],
+                rng: &mut R,
+            ) -> Self {
+                let r = FieldElement::random_using_rng(rng);
+                let blinded_signature = &signature.0 + &SIGNATURE_GENERATOR_TABLE.mul(&r);
+
+                // e(g, sig * q^r) / e(pk, H(m)) = e(g, q)^r
+                let r_nonce = FieldElement::random_using_rng(rng);
+                let t = $pair(g, &SignatureGroup::generator()).pow(&r_nonce);
+
+                let challenge = proof_challenge(g, pk, message, &blinded_signature, &t, nonce);
+                let signature_response = r_nonce - &challenge * &r;
+                SignatureProof {
+                    blinded_signature,
+                    challenge,
+                    signature_response,
+                }
+            }
+
+            fn check(
+                &self,
+                message: &SignatureGroup,
+                pk: &PublicKey,
+                g: &Generator,
+                nonce: &[u8],
+            ) -> bool {
+                let y = $pair(g, &self.blinded_signature) * $pair(&pk.0, message).inverse();
+                let t = $pair(g, &SignatureGroup::generator()).pow(&self.signature_response)
+                    * y.pow(&self.challenge);
+                proof_challenge(g, pk, message, &self.blinded_signature, &t, nonce)
+                    == self.challenge
+            }
+
+            pub fn to_bytes(&self) -> Vec<u8> {
+                let mut bytes = self.blinded_signature.to_bytes(false);
+                bytes.extend_from_slice(self.challenge.to_bytes().as_slice());
+                bytes.extend_from_slice(self.signature_response.to_bytes().as_slice());
+                bytes
+            }
+
+            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+                if bytes.len() != SIGNATURE_SIZE + 2 * PRIVATE_KEY_SIZE {
+                    return Err(CryptoError::ParseError(format!(
+                        "Invalid signature proof length {}",
+                        bytes.len()
+                    )));
+                }
+                let field = |i: usize| {
+                    let start = SIGNATURE_SIZE + i * PRIVATE_KEY_SIZE;
+                    FieldElement::from_bytes(&bytes[start..start + PRIVATE_KEY_SIZE])
+                        .map_err(|e| CryptoError::ParseError(format!("{:?}", e)))
+                };
+                Ok(SignatureProof {
+                    blinded_signature: decode_point(
+                        &bytes[..SIGNATURE_SIZE],
+                        "blinded signature",
+                    )?,
+                    challenge: field(0)?,
+                    signature_response: field(1)?,
+                })
+            }
+        }
+
+        fn proof_challenge(
+            g: &Generator,
+            pk: &PublicKey,
+            message: &SignatureGroup,
+            blinded_signature: &SignatureGroup,
+            t: &GT,
+            nonce: &[u8],
+        ) -> FieldElement {
+            let mut bytes = g.to_bytes(false);
+            bytes.extend_from_slice(pk.to_bytes().as_slice());
+            bytes.extend_from_slice(message.to_bytes(false).as_slice());
+            bytes.extend_from_slice(blinded_signature.to_bytes(false).as_slice());
+            bytes.extend_from_slice(t.to_bytes().as_slice());
+            bytes.extend_from_slice(nonce);
+            FieldElement::from_msg_hash(bytes.as_slice())
+        }
+
+        #[derive(Debug, Clone)]
+        pub struct AggregatedSignature(SignatureGroup);
+
+        impl AggregatedSignature {
+            // `Signature` should be generated by calling `Signature::new_with_rk_mitigation`
+            // to avoid rogue key attacks. If using proof of possession mitigation
+            // then `Signature` can be generated by calling `Signature::new`
+            // Fails if `signatures` is empty
+            pub fn new(signatures: &[Signature]) -> Result<Self, CryptoError> {
+                if signatures.is_empty() {
+                    return Err(CryptoError::GeneralError(
+                        "Can't aggregate an empty set of signatures".to_string(),
+                    ));
+                }
+                Ok(AggregatedSignature(
+                    signatures
+                        .iter()
+                        .fold(SignatureGroup::identity(), |sig, s| sig + &s.0),
+                ))
+            }
+
+            /// Take out the contribution of a signer, e.g. one that misbehaved.
+            /// `signature` must be exactly what the signer added, and the key
+            /// of an aggregate with rogue key mitigation must be taken out
+            /// with `AggregatedPublicKey::remove`.
+            pub fn remove(&mut self, signature: &Signature) {
+                self.0 -= &signature.0;
+            }
+
+            // Verify with rogue key attack mitigation.
+            pub fn verify<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &AggregatedPublicKey,
+            ) -> bool {
+                self.verify_with_generator(message, context, pk, &GENERATOR)
+            }
+
+            /// `verify` for keys made with `PublicKey::new_with_generator`
+            pub fn verify_with_generator<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &AggregatedPublicKey,
+                g: &Generator,
+            ) -> bool {
+                let hash = hash_msg(message, context);
+                $ate_2_pairing_is_one(&g, &self.0, &pk.0, &hash)
+            }
+
+            /// `verify` for a message hashed with `MessagePoint::hash`
+            pub fn verify_prehashed(
+                &self,
+                message: &MessagePoint,
+                pk: &AggregatedPublicKey,
+            ) -> bool {
+                $ate_2_pairing_is_one(generator(), &self.0, &pk.0, &message.0)
+            }
+
+            // Verify without rogue key mitigation. Assumes caller has handled
+            // rogue key mitigation some other way like proof of possession.
+            // This practice is discouraged in favor of the other method
+            // but there are use cases where proof of possession is better suited
+            pub fn verify_no_rk<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pks: &[PublicKey],
+            ) -> bool {
+                self.verify_no_rk_with_generator(message, context, pks, &GENERATOR)
+            }
+
+            /// `verify_no_rk` for keys made with `PublicKey::new_with_generator`
+            pub fn verify_no_rk_with_generator<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pks: &[PublicKey],
+                g: &Generator,
+            ) -> bool {
+                // The identity key would accept the identity signature
+                if pks.is_empty() {
+                    return false;
+                }
+                let apk = pks.iter().fold(Generator::identity(), |a, p| a + &p.0);
+                let hash = hash_msg(message, context);
+                $ate_2_pairing_is_one(&g, &self.0, &apk, &hash)
+            }
+
+            /// `verify` that reports why the signature was rejected
+            pub fn verify_strict<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pk: &AggregatedPublicKey,
+            ) -> Result<(), VerificationError> {
+                check_key(&pk.0)?;
+                check_signature(&self.0)?;
+                check_pairing(self.verify(message, context, pk))
+            }
+
+            /// Verify signatures over distinct messages aggregated with `new`,
+            /// with one multi-pairing. This is AggregateVerify from
+            /// draft-irtf-cfrg-bls-signature. `inputs` are the messages with
+            /// the keys that signed them, and fail if a message repeats.
+            pub fn verify_multi(
+                &self,
+                inputs: &[(&[u8], &PublicKey)],
+                context: Option<&'static [u8]>,
+            ) -> bool {
+                self.verify_multi_with_generator(inputs, context, &GENERATOR)
+            }
+
+            /// `verify_multi` for keys made with `PublicKey::new_with_generator`
+            pub fn verify_multi_with_generator(
+                &self,
+                inputs: &[(&[u8], &PublicKey)],
+                context: Option<&'static [u8]>,
+                g: &Generator,
+            ) -> bool {
+                let hashes = parallel::map(inputs, |&(msg, pk)| {
+                    (pk.0.clone(), hash_msg(msg, context))
+                });
+                verify_hashes(&self.0, hashes, g)
+            }
+
+            /// `verify_multi` that reports why the signature was rejected
+            pub fn verify_multi_strict(
+                &self,
+                inputs: &[(&[u8], &PublicKey)],
+                context: Option<&'static [u8]>,
+            ) -> Result<(), VerificationError> {
+                let g = generator();
+                for (_, pk) in inputs {
+                    check_key(&pk.0)?;
+                }
+                check_signature(&self.0)?;
+                let hashes = parallel::map(inputs, |&(msg, pk)| {
+                    (pk.0.clone(), hash_msg(msg, context))
+                });
+                check_hashes(&self.0, hashes, g)
+            }
+
+            /// `verify_no_rk` that reports why the signature was rejected.
+            /// Every key must be valid on its own, not only their sum.
+            pub fn verify_no_rk_strict<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                pks: &[PublicKey],
+            ) -> Result<(), VerificationError> {
+                if pks.is_empty() {
+                    return Err(VerificationError::EmptyInput);
+                }
+                for pk in pks {
+                    check_key(&pk.0)?;
+                }
+                check_signature(&self.0)?;
+                check_pairing(self.verify_no_rk(message, context, pks))
+            }
+
+            /// This should be used to verify quickly multiple BLS aggregated signatures by batching
+            /// versus verifying them one by one as it reduces the number of computed pairings
+            pub fn batch_verify(
+                inputs: &[(
+                    &[u8], /* message */
+                    &AggregatedSignature,
+                    &AggregatedPublicKey,
+                )],
+                context: Option<&'static [u8]>,
+            ) -> bool {
+                Self::batch_verify_with_rng(inputs, context, &mut OsRng)
+            }
+
+            /// `batch_verify` with the random exponents drawn from `rng`
+            pub fn batch_verify_with_rng<R: CryptoRng + RngCore>(
+                inputs: &[(
+                    &[u8], /* message */
+                    &AggregatedSignature,
+                    &AggregatedPublicKey,
+                )],
+                context: Option<&'static [u8]>,
+                rng: &mut R,
+            ) -> bool {
+                Scratch::with_local(|scratch| {
+                    Self::batch_verify_with_scratch(inputs, context, rng, scratch)
+                })
+            }
+
+            /// `batch_verify_with_rng` with the buffers for combining the
+            /// signatures taken from `scratch`
+            pub fn batch_verify_with_scratch<R: CryptoRng + RngCore>(
+                inputs: &[(
+                    &[u8], /* message */
+                    &AggregatedSignature,
+                    &AggregatedPublicKey,
+                )],
+                context: Option<&'static [u8]>,
+                rng: &mut R,
+                scratch: &mut Scratch,
+            ) -> bool {
+                Self::batch_check_messages(inputs, context, &GENERATOR, rng, scratch)
+            }
+
+            /// `batch_verify` for keys made with `PublicKey::new_with_generator`
+            pub fn batch_verify_with_generator(
+                inputs: &[(
+                    &[u8], /* message */
+                    &AggregatedSignature,
+                    &AggregatedPublicKey,
+                )],
+                context: Option<&'static [u8]>,
+                g: &Generator,
+            ) -> bool {
+                Scratch::with_local(|scratch| {
+                    Self::batch_check_messages(inputs, context, g, &mut OsRng, scratch)
+                })
+            }
+
+            fn batch_check_messages<R: CryptoRng + RngCore>(
+                inputs: &[(
+                    &[u8], /* message */
+                    &AggregatedSignature,
+                    &AggregatedPublicKey,
+                )],
+                context: Option<&'static [u8]>,
+                g: &Generator,
+                rng: &mut R,
+                scratch: &mut Scratch,
+            ) -> bool {
+                // To combat the rogue key attack and avoid checking for distinct messages
+                batch_check(
+                    inputs,
+                    |&(_, sig, apk)| (&sig.0, &apk.0),
+                    |hashes| {
+                        parallel::map_into(inputs, hashes, |&(msg, _, _)| hash_msg(msg, context))
+                    },
+                    g,
+                    rng,
+                    scratch,
+                )
+            }
+
+            pub fn to_bytes(&self) -> Vec<u8> {
+                self.0.to_bytes(false)
+            }
+
+            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+                decode_point(bytes, "aggregated signature").map(AggregatedSignature)
+            }
+        }
+
+        /// A fixed set of signers using rogue key mitigation.
+        ///
+        /// The weight of each key and the `AggregatedPublicKey` are
+        /// computed once when the set is made, where
+        /// `Signature::new_with_rk_mitigation` and `AggregatedPublicKey::new`
+        /// hash all the keys again on every call. Keep one per committee
+        /// and reuse it for every round.
+        #[derive(Debug, Clone)]
+        pub struct SignerSet {
+            keys: Vec<PublicKey>,
+            weights: Vec<FieldElement>,
+            apk: AggregatedPublicKey,
+        }
+
+        impl SignerSet {
+            /// Fails if `keys` is empty
+            pub fn new(keys: &[PublicKey]) -> Result<Self, CryptoError> {
+                if keys.is_empty() {
+                    return Err(CryptoError::GeneralError(
+                        "Can't make a signer set without public keys".to_string(),
+                    ));
+                }
+                let weights = rk_weights(keys);
+                let apk = Scratch::with_local(|scratch| {
+                    multi_scalar_mul(keys.iter().map(|k| &k.0), &weights, scratch)
+                });
+                Ok(SignerSet {
+                    keys: keys.to_vec(),
+                    weights,
+                    apk: AggregatedPublicKey(apk),
+                })
+            }
+
+            pub fn keys(&self) -> &[PublicKey] {
+                &self.keys
+            }
+
+            pub fn len(&self) -> usize {
+                self.keys.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.keys.is_empty()
+            }
+
+            /// Sign as the `i`th signer, the same signature as
+            /// `Signature::new_with_rk_mitigation`. Fails if there is no
+            /// `i`th signer.
+            pub fn sign<A: AsRef<[u8]>>(
+                &self,
+                i: usize,
+                message: A,
+                context: Option<&'static [u8]>,
+                sk: &PrivateKey,
+            ) -> Result<Signature, CryptoError> {
+                let weight = self.weights.get(i).ok_or_else(|| {
+                    CryptoError::SigningError(format!(
+                        "Signer {} is not among the {} public keys",
+                        i,
+                        self.keys.len()
+                    ))
+                })?;
+                Ok(Signature(hash_msg(message, context) * &sk.0 * weight))
+            }
+
+            /// The key that verifies the aggregate of every signer
+            pub fn aggregate_pk(&self) -> &AggregatedPublicKey {
+                &self.apk
+            }
+
+            /// Aggregate the signatures of every signer, made with `sign`
+            pub fn aggregate(
+                &self,
+                signatures: &[Signature],
+            ) -> Result<AggregatedSignature, CryptoError> {
+                if signatures.len() != self.keys.len() {
+                    return Err(CryptoError::GeneralError(format!(
+                        "Expected {} signatures, found {}",
+                        self.keys.len(),
+                        signatures.len()
+                    )));
+                }
+                AggregatedSignature::new(signatures)
+            }
+        }
+
+        /// Builds an `AggregatedSignature` one signature at a time, so
+        /// signatures can be folded in as they arrive instead of collected
+        /// for `AggregatedSignature::new`
+        #[derive(Debug, Clone)]
+        pub struct Aggregator {
+            sum: SignatureGroup,
+            count: usize,
+        }
+
+        impl Default for Aggregator {
+            fn default() -> Self {
+                Aggregator {
+                    sum: SignatureGroup::identity(),
+                    count: 0,
+                }
+            }
+        }
+
+        impl Aggregator {
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            pub fn add(&mut self, signature: &Signature) {
+                self.sum += &signature.0;
+                self.count += 1;
+            }
+
+            /// Decode and add a signature. Nothing is added if the bytes
+            /// are not a valid signature.
+            pub fn add_bytes(&mut self, bytes: &[u8]) -> Result<(), CryptoError> {
+                self.add(&Signature::from_bytes(bytes)?);
+                Ok(())
+            }
+
+            /// The number of signatures added so far
+            pub fn len(&self) -> usize {
+                self.count
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.count == 0
+            }
+
+            /// Fails if no signatures were added, like `AggregatedSignature::new`
+            pub fn finish(self) -> Result<AggregatedSignature, CryptoError> {
+                if self.is_empty() {
+                    return Err(CryptoError::GeneralError(
+                        "Can't aggregate an empty set of signatures".to_string(),
+                    ));
+                }
+                Ok(AggregatedSignature(self.sum))
+            }
+        }
+
+        /// The signers of an `Aggregate`
+        #[derive(Debug, Clone)]
+        pub enum Signers {
+            /// The public keys of the signers
+            Keys(Vec<PublicKey>),
+            /// Bit `i`, least significant first, is set if the `i`th key of
+            /// a key set known to the verifier signed
+            Bitmap(Vec<u8>),
+        }
+
+        impl Signers {
+            /// The bitmap of the positions of the signers in a key set
+            pub fn bitmap(indices: &[usize]) -> Self {
+                let len = indices.iter().max().map_or(0, |i| i / 8 + 1);
+                let mut bitmap = vec![0u8; len];
+                for i in indices {
+                    bitmap[i / 8] |= 1 << (i % 8);
+                }
+                Signers::Bitmap(bitmap)
+            }
+
+            /// The keys of the signers. A bitmap selects them from `key_set`.
+            pub fn public_keys(
+                &self,
+                key_set: &[PublicKey],
+            ) -> Result<Vec<PublicKey>, CryptoError> {
+                let keys = match self {
+                    Signers::Keys(keys) => keys.clone(),
+                    Signers::Bitmap(bitmap) => {
+                        let signed = |i: usize| {
+                            matches!(bitmap.get(i / 8), Some(b) if (b >> (i % 8)) & 1 == 1)
+                        };
+                        if (key_set.len()..bitmap.len() * 8).any(signed) {
+                            return Err(CryptoError::ParseError(format!(
+                                "The bitmap has signers beyond the {} keys of the key set",
+                                key_set.len()
+                            )));
+                        }
+                        key_set
+                            .iter()
+                            .enumerate()
+                            .filter(|(i, _)| signed(*i))
+                            .map(|(_, k)| k.clone())
+                            .collect()
+                    }
+                };
+                if keys.is_empty() {
+                    return Err(CryptoError::ParseError("There are no signers".to_string()));
+                }
+                Ok(keys)
+            }
+        }
+
+        /// An aggregated signature and the keys that contributed to it.
+        ///
+        /// The CBOR payload is the array `[signers, signature]`, where
+        /// `signers` is a byte string for a bitmap or an array of byte
+        /// strings for keys, and the envelope id tags the scheme.
+        #[derive(Debug, Clone)]
+        pub struct Aggregate {
+            pub signers: Signers,
+            pub signature: AggregatedSignature,
+        }
+
+        impl Aggregate {
+            pub fn new(signers: Signers, signature: AggregatedSignature) -> Self {
+                Aggregate { signers, signature }
+            }
+
+            /// Verify with rogue key mitigation. `key_set` is only used
+            /// when the signers are a bitmap.
+            pub fn verify<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                key_set: &[PublicKey],
+            ) -> bool {
+                match self
+                    .signers
+                    .public_keys(key_set)
+                    .and_then(|keys| AggregatedPublicKey::new(&keys))
+                {
+                    Ok(apk) => self.signature.verify(message, context, &apk),
+                    Err(_) => false,
+                }
+            }
+
+            /// Verify without rogue key mitigation, see `AggregatedSignature::verify_no_rk`
+            pub fn verify_no_rk<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                key_set: &[PublicKey],
+            ) -> bool {
+                match self.signers.public_keys(key_set) {
+                    Ok(keys) => self.signature.verify_no_rk(message, context, &keys),
+                    Err(_) => false,
+                }
+            }
+
+            /// `verify` that reports why the aggregate was rejected
+            pub fn verify_strict<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                key_set: &[PublicKey],
+            ) -> Result<(), VerificationError> {
+                let keys = self
+                    .signers
+                    .public_keys(key_set)
+                    .map_err(|e| VerificationError::Malformed(e.to_string()))?;
+                if keys.is_empty() {
+                    return Err(VerificationError::EmptyInput);
+                }
+                let apk = AggregatedPublicKey::new(&keys)
+                    .map_err(|e| VerificationError::InvalidKey(e.to_string()))?;
+                self.signature.verify_strict(message, context, &apk)
+            }
+
+            /// `verify_no_rk` that reports why the aggregate was rejected
+            pub fn verify_no_rk_strict<A: AsRef<[u8]>>(
+                &self,
+                message: A,
+                context: Option<&'static [u8]>,
+                key_set: &[PublicKey],
+            ) -> Result<(), VerificationError> {
+                let keys = self
+                    .signers
+                    .public_keys(key_set)
+                    .map_err(|e| VerificationError::Malformed(e.to_string()))?;
+                self.signature.verify_no_rk_strict(message, context, &keys)
+            }
+        }
+
+        impl CborSerializable for Aggregate {
+            const CBOR_ID: &'static str = concat!($scheme, "/aggregate");
+
+            fn to_cbor_payload(&self) -> Vec<u8> {
+                let mut writer = Writer::new();
+                writer.array(2);
+                match &self.signers {
+                    Signers::Keys(keys) => {
+                        writer.array(keys.len());
+                        for k in keys {
+                            writer.bytes(&k.to_bytes());
+                        }
+                    }
+                    Signers::Bitmap(bitmap) => writer.bytes(bitmap),
+                }
+                writer.bytes(&self.signature.to_bytes());
+                writer.into_bytes()
+            }
+
+            fn from_cbor_payload(_version: u32, payload: &[u8]) -> Result<Self, CborError> {
+                let invalid = |e: CryptoError| CborError::InvalidPayload(e.to_string());
+                let mut reader = Reader::new(payload);
+                if reader.array()? != 2 {
+                    return Err(CborError::InvalidPayload(
+                        "expected [signers, signature]".to_string(),
+                    ));
+                }
+                let signers = if reader.is_array() {
+                    let len = reader.array()?;
+                    let mut keys = Vec::with_capacity(len);
+                    for _ in 0..len {
+                        keys.push(PublicKey::from_bytes(reader.bytes()?).map_err(invalid)?);
+                    }
+                    Signers::Keys(keys)
+                } else {
+                    Signers::Bitmap(reader.bytes()?.to_vec())
+                };
+                let signature =
+                    AggregatedSignature::from_bytes(reader.bytes()?).map_err(invalid)?;
+                reader.finish()?;
+                Ok(Aggregate { signers, signature })
+            }
+        }
+
+        bls_cbor_impl!($scheme, "public-key", PublicKey);
+        bls_cbor_impl!($scheme, "aggregated-public-key", AggregatedPublicKey);
+        bls_cbor_impl!($scheme, "signature", Signature);
+        bls_cbor_impl!($scheme, "aggregated-signature", AggregatedSignature);
+        bls_cbor_impl!($scheme, "proof-of-possession", ProofOfPossession);
+        bls_cbor_impl!($scheme, "signature-proof", SignatureProof);
+
+        bls_bytes_impl!(PublicKey, PUBLIC_KEY_SIZE);
+        bls_bytes_impl!(AggregatedPublicKey, PUBLIC_KEY_SIZE);
+        bls_bytes_impl!(Signature, SIGNATURE_SIZE);
+        bls_bytes_impl!(AggregatedSignature, SIGNATURE_SIZE);
+        bls_bytes_impl!(ProofOfPossession, SIGNATURE_SIZE);
+        bls_bytes_impl!(SignatureProof);
+
+        #[cfg(feature = "serde")]
+        bls_serde_impl!(
+            PublicKey,
+            AggregatedPublicKey,
+            Signature,
+            AggregatedSignature,
+            ProofOfPossession,
+            SignatureProof
+        );
+        #[cfg(all(feature = "serde", feature = "bls_threshold"))]
+        bls_serde_impl!(PartialSignature, PublicKeyShare);
+
+        #[cfg(feature = "protobuf")]
+        bls_proto_impl!($scheme, PublicKey, PublicKey, key);
+        #[cfg(feature = "protobuf")]
+        bls_proto_impl!($scheme, Signature, Signature, signature);
+        #[cfg(feature = "protobuf")]
+        bls_proto_impl!($scheme, AggregatedSignature, AggregatedSignature, signature);
+    };
+}
+
+macro_rules! bls_tests_impl {
+    () => {
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            const MESSAGE_1: &[u8; 22] = b"This is a test message";
+            const MESSAGE_2: &[u8; 20] = b"Another test message";
+            const SEED: &[u8; 10] = &[1u8; 10];
+
+            #[test]
+            fn signature_generation_from_seed() {
+                let keypair_1 = Bls
+                    .keypair(Some(KeyGenOption::UseSeed(SEED.to_vec())))
+                    .unwrap();
+                let keypair_2 = Bls
+                    .keypair(Some(KeyGenOption::UseSeed(SEED.to_vec())))
+                    .unwrap();
+                assert_eq!(keypair_1, keypair_2);
+            }
+
+            #[test]
+            fn private_key_encoding() {
+                let (_, sk) = generate();
+                let bytes = sk.to_bytes();
+                assert_eq!(bytes.len(), PRIVATE_KEY_SIZE);
+                assert_eq!(PrivateKey::from_bytes(&bytes).unwrap(), sk);
+                assert_ne!(PrivateKey::random(), sk);
+                assert!(PrivateKey::from_bytes(&[0u8; PRIVATE_KEY_SIZE]).is_err());
+                assert!(PrivateKey::from_bytes(&bytes[1..]).is_err());
+                assert_eq!(format!("{:?}", sk), "PrivateKey(..)");
+            }
+
+            #[test]
+            fn point_validation() {
+                let (pk, sk) = generate();
+                let signature = Signature::new(MESSAGE_1, None, &sk);
+                assert!(PublicKey::from_bytes(&pk.to_bytes()).is_ok());
+                assert!(Signature::from_bytes(&signature.to_bytes()).is_ok());
+
+                let identity = Generator::identity().to_bytes(false);
+                match PublicKey::from_bytes(&identity) {
+                    Err(CryptoError::InvalidPoint(_)) => (),
+                    r => panic!("Expected InvalidPoint, found {:?}", r),
+                }
+                assert!(AggregatedPublicKey::from_bytes(&identity).is_err());
+                let identity = SignatureGroup::identity().to_bytes(false);
+                match Signature::from_bytes(&identity) {
+                    Err(CryptoError::InvalidPoint(_)) => (),
+                    r => panic!("Expected InvalidPoint, found {:?}", r),
+                }
+                assert!(AggregatedSignature::from_bytes(&identity).is_err());
+                assert!(ProofOfPossession::from_bytes(&identity).is_err());
+
+                // Bytes off the curve are rejected too
+                let mut bytes = pk.to_bytes();
+                let last = bytes.len() - 1;
+                bytes[last] ^= 1;
+                assert!(PublicKey::from_bytes(&bytes).is_err());
+            }
+
+            #[test]
+            fn generate_from_seed_vectors() {
+                // The master keys of the EIP-2333 test cases
+                for (seed, master) in &[
+                    (
+                        "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e5349553\
+                         1f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+                        "0d7359d57963ab8fbbde1852dcf553fedbc31f464d80ee7d40ae683122b45070",
+                    ),
+                    (
+                        "3141592653589793238462643383279502884197169399375105820974944592",
+                        "41c9e07822b092a93fd6797396338c3ada4170cc81829fdfce6b5d34bd5e7ec7",
+                    ),
+                ] {
+                    let seed = hex::decode(seed).unwrap();
+                    let (pk, sk) = generate_from_seed(&seed).unwrap();
+                    let bytes = sk.to_bytes();
+                    assert_eq!(bytes[..PRIVATE_KEY_SIZE - 32], [0u8; PRIVATE_KEY_SIZE - 32]);
+                    assert_eq!(hex::encode(&bytes[PRIVATE_KEY_SIZE - 32..]), *master);
+                    assert_eq!(pk.to_bytes(), PublicKey::new(&sk).to_bytes());
+                    let (_, again) = generate_from_seed(&seed).unwrap();
+                    assert_eq!(again, sk);
+                }
+                assert!(generate_from_seed(&[0u8; 31]).is_err());
+            }
+
+            #[test]
+            fn fixed_base_tables() {
+                let g = Generator::generator();
+                let base = &g * &FieldElement::random();
+                let table = GeneratorTable::new(&base);
+                let mut minus_one = FieldElement::zero();
+                minus_one -= FieldElement::one();
+                for sk in &[
+                    FieldElement::zero(),
+                    FieldElement::one(),
+                    FieldElement::from(15u64),
+                    FieldElement::from(16u64),
+                    minus_one,
+                    FieldElement::random(),
+                ] {
+                    assert_eq!(&base * sk, table.mul(sk));
+                    let sk = PrivateKey(sk.clone());
+                    assert_eq!(
+                        PublicKey::new_with_generator(&sk, &g).0,
+                        PublicKey::from_table(&sk, generator_table()).0
+                    );
+                }
+                let sk = FieldElement::random();
+                let sg = SignatureGroup::generator();
+                assert_eq!(&sg * &sk, SIGNATURE_GENERATOR_TABLE.mul(&sk));
+            }
+
+            #[test]
+            fn multi_scalar_multiplication() {
+                // The buffers grow and shrink between the sizes
+                let mut scratch = Scratch::new();
+                for n in &[0usize, 1, 2, 40] {
+                    let points = (0..*n).map(|_| Generator::random()).collect::<Vec<_>>();
+                    let mut scalars = (0..*n).map(|_| FieldElement::random()).collect::<Vec<_>>();
+                    if *n > 1 {
+                        scalars[0] = FieldElement::zero();
+                        scalars[1] = FieldElement::one();
+                    }
+                    let expected = points
+                        .iter()
+                        .zip(scalars.iter())
+                        .fold(Generator::identity(), |sum, (p, s)| sum + p * s);
+                    assert_eq!(
+                        expected,
+                        multi_scalar_mul(points.iter(), &scalars, &mut scratch)
+                    );
+                }
+            }
+
+            #[test]
+            fn fingerprints() {
+                let (pk, _) = generate();
+                let (other, _) = generate();
+                let parsed = PublicKey::from_bytes(&pk.to_bytes()).unwrap();
+                assert_eq!(pk.fingerprint(), parsed.fingerprint());
+                assert_ne!(pk.fingerprint(), other.fingerprint());
+            }
+
+            #[test]
+            fn cbor_envelopes() {
+                let (pk, sk) = generate();
+                let cbor = pk.to_cbor();
+                assert_eq!(
+                    pk.to_bytes(),
+                    PublicKey::from_cbor(&cbor).unwrap().to_bytes()
+                );
+                assert!(AggregatedPublicKey::from_cbor(&cbor).is_err());
+
+                let signature = Signature::new(&MESSAGE_1[..], None, &sk);
+                let signature = Signature::from_cbor(&signature.to_cbor()).unwrap();
+                assert!(signature.verify(&MESSAGE_1[..], None, &pk));
+
+                let pop = ProofOfPossession::new(&pk, None, &sk);
+                let pop = ProofOfPossession::from_cbor(&pop.to_cbor()).unwrap();
+                assert!(pop.verify(None, &pk));
+                assert!(Signature::from_cbor(&pop.to_cbor()).is_err());
+            }
+
+            #[cfg(feature = "protobuf")]
+            #[test]
+            fn protobuf_messages() {
+                let (pk, sk) = generate();
+                let message = proto::PublicKey::from(&pk);
+                assert_eq!(
+                    pk.to_bytes(),
+                    PublicKey::try_from(&message).unwrap().to_bytes()
+                );
+
+                let signature = Signature::new(&MESSAGE_1[..], None, &sk);
+                let message = proto::Signature::from(&signature);
+                let signature = Signature::try_from(&message).unwrap();
+                assert!(signature.verify(&MESSAGE_1[..], None, &pk));
+
+                let asg = AggregatedSignature::new(&[signature]).unwrap();
+                let mut message = proto::AggregatedSignature::from(&asg);
+                assert_eq!(
+                    asg.to_bytes(),
+                    AggregatedSignature::try_from(&message).unwrap().to_bytes()
+                );
+                message.algorithm = "ed25519".to_string();
+                assert!(AggregatedSignature::try_from(&message).is_err());
+            }
+
+            #[test]
+            fn aggregates() {
+                let keys = (0..10).map(|_| generate()).collect::<Vec<_>>();
+                let key_set = keys.iter().map(|(pk, _)| pk.clone()).collect::<Vec<_>>();
+                let signers = [1usize, 4, 9];
+                let signer_keys = signers
+                    .iter()
+                    .map(|i| key_set[*i].clone())
+                    .collect::<Vec<_>>();
+                let signatures = signers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, s)| {
+                        Signature::new_with_rk_mitigation(
+                            &MESSAGE_1[..],
+                            None,
+                            &keys[*s].1,
+                            i,
+                            &signer_keys,
+                        )
+                        .unwrap()
+                    })
+                    .collect::<Vec<_>>();
+                let signature = AggregatedSignature::new(&signatures).unwrap();
+
+                let bitmap = Aggregate::new(Signers::bitmap(&signers), signature.clone());
+                let cbor = bitmap.to_cbor();
+                let bitmap = Aggregate::from_cbor(&cbor).unwrap();
+                match &bitmap.signers {
+                    Signers::Bitmap(b) => assert_eq!(&[0x12, 0x02][..], &b[..]),
+                    _ => panic!("expected a bitmap"),
+                }
+                assert!(bitmap.verify(&MESSAGE_1[..], None, &key_set));
+                assert!(!bitmap.verify(&MESSAGE_2[..], None, &key_set));
+                assert!(!bitmap.verify(&MESSAGE_1[..], None, &key_set[..9]));
+
+                let explicit = Aggregate::new(Signers::Keys(signer_keys), signature);
+                let explicit = Aggregate::from_cbor(&explicit.to_cbor()).unwrap();
+                assert!(explicit.verify(&MESSAGE_1[..], None, &[]));
+
+                let empty = Aggregate::new(Signers::bitmap(&[]), explicit.signature.clone());
+                assert!(!empty.verify(&MESSAGE_1[..], None, &key_set));
+                let mut truncated = cbor.clone();
+                truncated.pop();
+                assert!(Aggregate::from_cbor(&truncated).is_err());
+                assert!(AggregatedSignature::from_cbor(&cbor).is_err());
+            }
+
+            #[test]
+            fn generation_with_rng() {
+                use rand::{rngs::StdRng, SeedableRng};
+
+                let (pk, sk) = generate_with_rng(&mut StdRng::seed_from_u64(1));
+                let (pk_2, sk_2) = generate_with_rng(&mut StdRng::seed_from_u64(1));
+                assert_eq!(pk.to_bytes(), pk_2.to_bytes());
+                assert_eq!(sk, sk_2);
+
+                let signature = Signature::new(&MESSAGE_1[..], None, &sk);
+                assert!(Signature::batch_verify_with_rng(
+                    &[(&MESSAGE_1[..], &signature, &pk)],
+                    None,
+                    &mut StdRng::seed_from_u64(2)
+                ));
+                let nonce = b"verifier nonce";
+                let proof = SignatureProof::new_with_rng(
+                    &signature,
+                    &MESSAGE_1[..],
+                    None,
+                    &pk,
+                    nonce,
+                    &mut StdRng::seed_from_u64(3),
+                );
+                assert!(proof.verify(&MESSAGE_1[..], None, &pk, nonce));
+            }
+
+            #[test]
+            fn signature_verification() {
+                let (pk, sk) = generate();
+
+                let signature_1 = Signature::new(&MESSAGE_1[..], None, &sk);
+                assert!(signature_1.verify(&MESSAGE_1[..], None, &pk));
+
+                let signature_2 = Signature::new(&MESSAGE_2[..], Some(MESSAGE_CONTEXT), &sk);
+                assert!(signature_2.verify(&MESSAGE_2[..], Some(MESSAGE_CONTEXT), &pk));
+
+                // Should fail for different messages
+                assert!(!signature_1.verify(&MESSAGE_2[..], Some(MESSAGE_CONTEXT), &pk));
+                assert!(!signature_2.verify(&MESSAGE_1[..], None, &pk));
+            }
+
+            #[test]
+            fn borrowed_encodings() {
+                let (pk, sk) = generate();
+                let pk_bytes = pk.to_bytes();
+                let sig_bytes = Signature::new(MESSAGE_1, None, &sk).to_bytes();
+
+                let pk_ref = PublicKeyRef::from_bytes(&pk_bytes).unwrap();
+                let sig_ref = SignatureRef::from_bytes(&sig_bytes).unwrap();
+                assert_eq!(pk_ref.as_bytes(), pk_bytes.as_slice());
+                assert_eq!(pk_ref.decode().unwrap().0, pk.0);
+                assert_eq!(sig_ref, SignatureRef::from_bytes(&sig_bytes).unwrap());
+                assert!(sig_ref.verify(MESSAGE_1, None, &pk_ref).unwrap());
+                assert!(!sig_ref.verify(MESSAGE_2, None, &pk_ref).unwrap());
+
+                assert!(PublicKeyRef::from_bytes(&pk_bytes[1..]).is_err());
+                assert!(SignatureRef::from_bytes(&[]).is_err());
+            }
+
+            #[test]
+            fn did_key() {
+                let (pk, _) = generate();
+                let did = pk.to_did_key();
+                assert!(did.starts_with("did:key:z"));
+                assert_eq!(
+                    pk.to_bytes(),
+                    PublicKey::from_did_key(&did).unwrap().to_bytes()
+                );
+                assert!(PublicKey::from_did_key(
+                    "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp"
+                )
+                .is_err());
+            }
+
+            #[test]
+            fn proof_of_possession() {
+                let (pk, sk) = generate();
+
+                let proof_of_possession_1 = ProofOfPossession::new(&pk, None, &sk);
+                assert!(proof_of_possession_1.verify(None, &pk));
+
+                let ctx = b"another domain separator";
+                let proof_of_possession_2 = ProofOfPossession::new(&pk, Some(ctx), &sk);
+                assert!(!proof_of_possession_2.verify(None, &pk));
+                assert!(proof_of_possession_2.verify(Some(ctx), &pk));
+            }
+
+            #[test]
+            fn signature_proof() {
+                let (pk, sk) = generate();
+                let (other_pk, _) = generate();
+                let signature = Signature::new(&MESSAGE_1[..], None, &sk);
+                let nonce = b"verifier nonce";
+
+                let proof = SignatureProof::new(&signature, &MESSAGE_1[..], None, &pk, nonce);
+                assert!(proof.verify(&MESSAGE_1[..], None, &pk, nonce));
+                assert!(!proof.verify(&MESSAGE_2[..], None, &pk, nonce));
+                assert!(!proof.verify(&MESSAGE_1[..], None, &other_pk, nonce));
+                assert!(!proof.verify(&MESSAGE_1[..], None, &pk, b"another nonce"));
+
+                let proof = SignatureProof::from_bytes(proof.to_bytes().as_slice()).unwrap();
+                assert!(proof.verify(&MESSAGE_1[..], None, &pk, nonce));
+
+                // Two proofs of the same signature don't share the blinded signature
+                let proof_2 = SignatureProof::new(&signature, &MESSAGE_1[..], None, &pk, nonce);
+                assert_ne!(proof.blinded_signature, proof_2.blinded_signature);
+
+                // Can't prove a signature on a different message
+                let proof = SignatureProof::new(&signature, &MESSAGE_2[..], None, &pk, nonce);
+                assert!(!proof.verify(&MESSAGE_2[..], None, &pk, nonce));
+
+                // Without a signature a prover can still blind the identity into `q^r` and prove
+                // it knows `r`, which must not verify
+                let forged = SignatureProof::prove(
+                    &Signature(SignatureGroup::identity()),
+                    &hash_msg(&MESSAGE_1[..], None),
+                    &pk,
+                    generator(),
+                    nonce,
+                    &mut OsRng,
+                );
+                assert!(!forged.verify(&MESSAGE_1[..], None, &pk, nonce));
+            }
+
+            #[test]
+            fn strict_verification() {
+                let (pk, sk) = generate();
+                let signature = Signature::new(&MESSAGE_1[..], None, &sk);
+                assert_eq!(signature.verify_strict(&MESSAGE_1[..], None, &pk), Ok(()));
+                assert_eq!(
+                    signature.verify_strict(&MESSAGE_2[..], None, &pk),
+                    Err(VerificationError::Mismatch)
+                );
+
+                let identity = PublicKey(Generator::identity());
+                let forged = Signature(SignatureGroup::identity());
+                assert!(matches!(
+                    forged.verify_strict(&MESSAGE_2[..], None, &identity),
+                    Err(VerificationError::InvalidKey(_))
+                ));
+
+                let sig_bytes = signature.to_bytes();
+                let pk_bytes = pk.to_bytes();
+                let sig_ref = SignatureRef::from_bytes(&sig_bytes).unwrap();
+                let pk_ref = PublicKeyRef::from_bytes(&pk_bytes).unwrap();
+                assert_eq!(sig_ref.verify_strict(&MESSAGE_1[..], None, &pk_ref), Ok(()));
+                // Not a point on the curve
+                let mut bad_bytes = sig_bytes.clone();
+                bad_bytes[1] ^= 1;
+                let bad_ref = SignatureRef::from_bytes(&bad_bytes).unwrap();
+                assert!(matches!(
+                    bad_ref.verify_strict(&MESSAGE_1[..], None, &pk_ref),
+                    Err(VerificationError::Malformed(_))
+                ));
+
+                let aggregated = AggregatedSignature::new(&[signature]).unwrap();
+                assert_eq!(
+                    aggregated.verify_no_rk_strict(&MESSAGE_1[..], None, &[pk.clone()]),
+                    Ok(())
+                );
+                assert_eq!(
+                    aggregated.verify_no_rk_strict(&MESSAGE_1[..], None, &[]),
+                    Err(VerificationError::EmptyInput)
+                );
+                let aggregate = Aggregate::new(Signers::bitmap(&[3]), aggregated);
+                assert!(matches!(
+                    aggregate.verify_no_rk_strict(&MESSAGE_1[..], None, &[pk]),
+                    Err(VerificationError::Malformed(_))
+                ));
+            }
+
+            #[test]
+            fn validated_public_key() {
+                let (pk, sk) = generate();
+                let signature = Signature::new(&MESSAGE_1[..], None, &sk);
+                let pk_bytes = pk.to_bytes();
+                let validated = PublicKeyRef::from_bytes(&pk_bytes)
+                    .unwrap()
+                    .validate()
+                    .unwrap();
+                assert_eq!(validated.to_bytes(), pk_bytes);
+                assert_eq!(
+                    signature.verify_validated(&MESSAGE_1[..], None, &validated),
+                    Ok(())
+                );
+                assert_eq!(
+                    signature.verify_validated(&MESSAGE_2[..], None, &validated),
+                    Err(VerificationError::Mismatch)
+                );
+                let sig_bytes = signature.to_bytes();
+                let sig_ref = SignatureRef::from_bytes(&sig_bytes).unwrap();
+                assert_eq!(
+                    sig_ref.verify_validated(&MESSAGE_1[..], None, &validated),
+                    Ok(())
+                );
+                assert!(signature.verify(&MESSAGE_1[..], None, validated.public_key()));
+                assert_eq!(PublicKey::from(validated).0, pk.0);
+
+                assert!(ValidatedPublicKey::try_from(pk).is_ok());
+                assert!(ValidatedPublicKey::try_from(PublicKey(Generator::identity())).is_err());
+                assert!(ValidatedPublicKey::from_bytes(&pk_bytes[1..]).is_err());
+            }
+
+            #[test]
+            fn signing_context() {
+                const DST: &[u8] = b"ursa signing context test";
+                let keys = (0..3).map(|_| generate()).collect::<Vec<_>>();
+                let pks = keys.iter().map(|(pk, _)| pk.clone()).collect::<Vec<_>>();
+                let (pk, sk) = &keys[0];
+
+                let context = BlsSigningContext::new().dst(DST).prehashed(true);
+                let signature = context.sign(&MESSAGE_1[..], sk).unwrap();
+                assert!(context.verify(&MESSAGE_1[..], &signature, pk));
+                assert!(!context.verify(&MESSAGE_2[..], &signature, pk));
+                assert!(!signature.verify(&MESSAGE_1[..], Some(DST), pk));
+                assert!(!BlsSigningContext::new().verify(&MESSAGE_1[..], &signature, pk));
+
+                let signatures = keys
+                    .iter()
+                    .map(|(_, sk)| context.sign(&MESSAGE_1[..], sk).unwrap())
+                    .collect::<Vec<_>>();
+                let aggregated = AggregatedSignature::new(&signatures).unwrap();
+                assert!(context.verify_aggregate(&MESSAGE_1[..], &aggregated, &pks));
+                assert!(!context.clone().scheme(BlsScheme::Basic).verify_aggregate(
+                    &MESSAGE_1[..],
+                    &aggregated,
+                    &pks,
+                ));
+
+                let weighted = BlsSigningContext::new().scheme(BlsScheme::KeyWeighted);
+                assert!(weighted.sign(&MESSAGE_1[..], sk).is_err());
+                assert!(context.sign_weighted(&MESSAGE_1[..], sk, 0, &pks).is_err());
+                assert!(weighted.sign_weighted(&MESSAGE_1[..], sk, 3, &pks).is_err());
+                let signatures = keys
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (_, sk))| {
+                        weighted.sign_weighted(&MESSAGE_1[..], sk, i, &pks).unwrap()
+                    })
+                    .collect::<Vec<_>>();
+                let aggregated = AggregatedSignature::new(&signatures).unwrap();
+                assert!(weighted.verify_aggregate(&MESSAGE_1[..], &aggregated, &pks));
+
+                let mut combined = context.sign(&MESSAGE_1[..], sk).unwrap();
+                combined.combine(&[context.sign(&MESSAGE_2[..], &keys[1].1).unwrap()]);
+                assert!(context.verify_multi(
+                    &[(&MESSAGE_1[..], pk), (&MESSAGE_2[..], &pks[1])],
+                    &combined,
+                ));
+            }
+
+            #[test]
+            fn hash_to_curve_context() {
+                let (pk, sk) = generate();
+
+                let context = BlsSigningContext::new().hash_to_curve(true);
+                let signature = context.sign(&MESSAGE_1[..], &sk).unwrap();
+                assert!(context.verify(&MESSAGE_1[..], &signature, &pk));
+                assert!(!context.verify(&MESSAGE_2[..], &signature, &pk));
+                assert_eq!(
+                    signature.to_bytes(),
+                    (hash_to_curve(&MESSAGE_1[..], POP_DST) * &sk.0).to_bytes(false)
+                );
+                // Neither the legacy hash nor another tag accepts it
+                assert!(!BlsSigningContext::new().verify(&MESSAGE_1[..], &signature, &pk));
+                let basic = context.clone().scheme(BlsScheme::Basic);
+                assert!(!basic.verify(&MESSAGE_1[..], &signature, &pk));
+                let tagged = context.clone().dst(b"another protocol");
+                assert!(!tagged.verify(&MESSAGE_1[..], &signature, &pk));
+                assert!(tagged.verify(
+                    &MESSAGE_1[..],
+                    &tagged.sign(&MESSAGE_1[..], &sk).unwrap(),
+                    &pk,
+                ));
 
-                pairs.push((-g, self.0.clone()));
-                let ate_pairs = pairs.iter().map($set_pairs).collect();
-                GT::ate_multi_pairing(ate_pairs).is_one()
+                // Oversized tags are hashed first
+                let long = [7u8; 300];
+                let hashed = Sha256::new()
+                    .chain(b"H2C-OVERSIZE-DST-")
+                    .chain(&long[..])
+                    .result();
+                assert_eq!(
+                    hash_to_curve(&MESSAGE_1[..], &long),
+                    hash_to_curve(&MESSAGE_1[..], &hashed)
+                );
+                assert_ne!(
+                    hash_to_curve(&MESSAGE_1[..], BASIC_DST),
+                    hash_to_curve(&MESSAGE_1[..], POP_DST)
+                );
             }
 
-            pub fn batch_verify(
-                inputs: &[(&[u8], &Signature, &PublicKey)],
-                context: Option<&'static [u8]>,
-                g: &Generator,
-            ) -> bool {
-                // To avoid rogue key attacks, you must use proof of possession or `AggregateSignature::batch_verify`
-                // This function just avoids checking for distinct messages and
-                // uses batch verification as described in the end of section 3.1 from https://eprint.iacr.org/2018/483
-                let mut pairs = Vec::new();
-                let mut sig = SignatureGroup::identity();
-                for (msg, asg, apk) in inputs {
-                    let random_exponent = FieldElement::random();
-                    let hash = hash_msg(msg, context);
-                    sig += &asg.0 * &random_exponent;
-                    pairs.push((&apk.0 * &random_exponent, hash));
+            #[cfg(feature = "bls_threshold")]
+            #[test]
+            fn threshold_signing() {
+                let (pk, sk) = generate();
+                let shares = SignatureShare::split(&sk, 3, 5).unwrap();
+                assert_eq!(
+                    shares.iter().map(|s| s.identifier()).collect::<Vec<_>>(),
+                    vec![1, 2, 3, 4, 5]
+                );
+                let partials = shares
+                    .iter()
+                    .map(|s| PartialSignature::new(MESSAGE_1, None, s))
+                    .collect::<Vec<_>>();
+                for (share, partial) in shares.iter().zip(partials.iter()) {
+                    let share_pk = PublicKey::new(share.private_key());
+                    assert!(partial.verify(MESSAGE_1, None, &share_pk));
+                    assert!(!partial.verify(MESSAGE_1, None, &pk));
                 }
 
-                pairs.push((-g, sig));
+                // Any three partial signatures in any order
+                let signature = PartialSignature::combine_with_lagrange(&partials[1..4]).unwrap();
+                assert!(signature.verify(MESSAGE_1, None, &pk));
+                let some = [
+                    partials[4].clone(),
+                    partials[0].clone(),
+                    partials[2].clone(),
+                ];
+                let signature = PartialSignature::combine_with_lagrange(&some).unwrap();
+                assert!(signature.verify(MESSAGE_1, None, &pk));
+                assert!(!signature.verify(MESSAGE_2, None, &pk));
+                assert_eq!(
+                    signature.to_bytes(),
+                    Signature::new(MESSAGE_1, None, &sk).to_bytes()
+                );
 
-                let ate_pairs = pairs.iter().map($set_pairs).collect();
-                GT::ate_multi_pairing(ate_pairs).is_one()
-            }
+                // Two are not enough
+                let signature = PartialSignature::combine_with_lagrange(&partials[..2]).unwrap();
+                assert!(!signature.verify(MESSAGE_1, None, &pk));
+                assert!(PartialSignature::combine_with_lagrange(&[]).is_err());
+                let repeated = [partials[0].clone(), partials[0].clone()];
+                assert!(PartialSignature::combine_with_lagrange(&repeated).is_err());
 
-            pub fn to_bytes(&self) -> Vec<u8> {
-                self.0.to_bytes(false)
-            }
+                let bytes = partials[2].to_bytes();
+                let decoded = PartialSignature::from_bytes(&bytes).unwrap();
+                assert_eq!(decoded.identifier(), 3);
+                assert_eq!(decoded.to_bytes(), bytes);
+                assert!(PartialSignature::from_bytes(&bytes[1..]).is_err());
+                let share = SignatureShare::from_bytes(&shares[2].to_bytes()).unwrap();
+                assert_eq!(share.identifier(), 3);
+                assert_eq!(share.private_key(), shares[2].private_key());
+                assert_eq!(format!("{:?}", share), "SignatureShare { identifier: 3 }");
 
-            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
-                Ok(Signature(SignatureGroup::from_bytes(bytes).map_err(
-                    |e| CryptoError::ParseError(format!("{:?}", e)),
-                )?))
+                assert!(SignatureShare::new(0, sk.clone()).is_err());
+                assert!(SignatureShare::split(&sk, 1, 5).is_err());
+                assert!(SignatureShare::split(&sk, 4, 3).is_err());
             }
-        }
 
-        /// Proof of possession for BLS verification key.
-        /// Used as another form of rogue key mitigation
-        /// where signers are known entities in a group.
-        /// Virtually identical to a signature but should
-        /// use a different domain separation than `Signature`.
-        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-        #[derive(Debug, Clone)]
-        pub struct ProofOfPossession(SignatureGroup);
+            #[cfg(feature = "bls_threshold")]
+            #[test]
+            fn public_key_shares() {
+                let (pk, sk) = generate();
+                let shares = SignatureShare::split(&sk, 2, 3).unwrap();
+                let pk_shares = shares.iter().map(PublicKeyShare::new).collect::<Vec<_>>();
+                let combined = PublicKeyShare::combine(&pk_shares[1..]).unwrap();
+                assert_eq!(combined.to_bytes(), pk.to_bytes());
 
-        impl ProofOfPossession {
-            pub fn new(pk: &PublicKey, context: Option<&'static [u8]>, sk: &PrivateKey) -> Self {
-                ProofOfPossession(hash_key(pk, context) * sk)
-            }
+                let partial = PartialSignature::new(MESSAGE_1, None, &shares[0]);
+                assert!(pk_shares[0].verify(MESSAGE_1, None, &partial));
+                assert!(!pk_shares[1].verify(MESSAGE_1, None, &partial));
+                let moved =
+                    PublicKeyShare::from_public_key(2, pk_shares[0].public_key().clone()).unwrap();
+                assert!(!moved.verify(MESSAGE_1, None, &partial));
+                assert!(PublicKeyShare::from_public_key(0, pk.clone()).is_err());
 
-            pub fn to_bytes(&self) -> Vec<u8> {
-                self.0.to_bytes(false)
-            }
+                let bytes = pk_shares[2].to_bytes();
+                let decoded = PublicKeyShare::from_bytes(&bytes).unwrap();
+                assert_eq!(decoded.identifier(), 3);
+                assert_eq!(decoded.to_bytes(), bytes);
+                assert!(PublicKeyShare::from_bytes(&bytes[1..]).is_err());
 
-            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
-                Ok(ProofOfPossession(
-                    SignatureGroup::from_bytes(bytes)
-                        .map_err(|e| CryptoError::ParseError(format!("{:?}", e)))?,
-                ))
-            }
+                // Signatures combine the same way without PartialSignature
+                let signatures = [
+                    (3, Signature::new(MESSAGE_1, None, shares[2].private_key())),
+                    (1, Signature::new(MESSAGE_1, None, shares[0].private_key())),
+                ];
+                let signature = combine_signature_shares(&signatures).unwrap();
+                assert!(signature.verify(MESSAGE_1, None, &pk));
+                assert!(combine_signature_shares(&[]).is_err());
+                assert!(combine_signature_shares(&[(0, signature)]).is_err());
 
-            pub fn verify(
-                &self,
-                context: Option<&'static [u8]>,
-                pk: &PublicKey,
-                g: &Generator,
-            ) -> bool {
-                let hash = hash_key(pk, context);
-                $ate_2_pairing_is_one(&g, &self.0, &pk.0, &hash)
+                let indices = [3, 1];
+                let mut secret = FieldElement::zero();
+                for (i, share) in [(3, &shares[2]), (1, &shares[0])].iter() {
+                    let l = lagrange_coefficient(&indices, *i).unwrap();
+                    secret += &share.private_key().0 * &l;
+                }
+                assert_eq!(PrivateKey(secret), sk);
+                assert!(lagrange_coefficient(&indices, 2).is_err());
+                assert!(lagrange_coefficient(&[1, 1], 1).is_err());
             }
-        }
 
-        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-        #[derive(Debug, Clone)]
-        pub struct AggregatedSignature(SignatureGroup);
+            #[test]
+            fn byte_conversions() {
+                let (pk, sk) = generate();
+                let signature = Signature::new(&MESSAGE_1[..], None, &sk);
 
-        impl AggregatedSignature {
-            // `Signature` should be generated by calling `Signature::new_with_rk_mitigation`
-            // to avoid rogue key attacks. If using proof of possession mitigation
-            // then `Signature` can be generated by calling `Signature::new`
-            pub fn new(signatures: &[Signature]) -> Self {
-                AggregatedSignature(
-                    signatures
-                        .iter()
-                        .fold(SignatureGroup::identity(), |sig, s| sig + &s.0),
-                )
-            }
+                let pk_bytes: [u8; PUBLIC_KEY_SIZE] = (&pk).into();
+                let sig_bytes: [u8; SIGNATURE_SIZE] = (&signature).into();
+                assert_eq!(&pk_bytes[..], pk.to_bytes().as_slice());
+                let pk = PublicKey::try_from(pk_bytes).unwrap();
+                let signature = Signature::try_from(&sig_bytes[..]).unwrap();
+                assert!(signature.verify(&MESSAGE_1[..], None, &pk));
+                assert!(Signature::try_from(&sig_bytes[1..]).is_err());
 
-            // Verify with rogue key attack mitigation.
-            pub fn verify<A: AsRef<[u8]>>(
-                &self,
-                message: A,
-                context: Option<&'static [u8]>,
-                pk: &AggregatedPublicKey,
-                g: &Generator,
-            ) -> bool {
-                let hash = hash_msg(message, context);
-                $ate_2_pairing_is_one(&g, &self.0, &pk.0, &hash)
-            }
+                let sig_ref = SignatureRef::try_from(&sig_bytes[..]).unwrap();
+                assert_eq!(sig_ref.as_ref(), Vec::<u8>::from(&signature).as_slice());
 
-            // Verify without rogue key mitigation. Assumes caller has handled
-            // rogue key mitigation some other way like proof of possession.
-            // This practice is discouraged in favor of the other method
-            // but there are use cases where proof of possession is better suited
-            pub fn verify_no_rk<A: AsRef<[u8]>>(
-                &self,
-                message: A,
-                context: Option<&'static [u8]>,
-                pks: &[PublicKey],
-                g: &Generator,
-            ) -> bool {
-                let apk = pks.iter().fold(Generator::identity(), |a, p| a + &p.0);
-                let hash = hash_msg(message, context);
-                $ate_2_pairing_is_one(&g, &self.0, &apk, &hash)
+                let hex = signature.to_string();
+                assert_eq!(hex, sig_ref.to_string());
+                assert_eq!(hex, hex.to_lowercase());
+                let parsed: Signature = format!("0x{}", hex).parse().unwrap();
+                assert_eq!(parsed.to_bytes(), signature.to_bytes());
+                assert_eq!(
+                    pk.to_string().parse::<PublicKey>().unwrap().to_bytes(),
+                    pk.to_bytes()
+                );
+                assert!(hex[2..].parse::<Signature>().is_err());
             }
 
-            /// This should be used to verify quickly multiple BLS aggregated signatures by batching
-            /// versus verifying them one by one as it reduces the number of computed pairings
-            pub fn batch_verify(
-                inputs: &[(
-                    &[u8], /* message */
-                    &AggregatedSignature,
-                    &AggregatedPublicKey,
-                )],
-                context: Option<&'static [u8]>,
-                g: &Generator,
-            ) -> bool {
-                // To combat the rogue key attack and avoid checking for distinct messages
-                // use batch verification as described in the end of section 3.1 from https://eprint.iacr.org/2018/483
-                let mut pairs = Vec::new();
-                let mut sig = SignatureGroup::identity();
-                for (msg, asg, apk) in inputs {
-                    let random_exponent = FieldElement::random();
-                    let hash = hash_msg(msg, context);
-                    sig += &asg.0 * &random_exponent;
-                    pairs.push((&apk.0 * &random_exponent, hash));
-                }
-
-                pairs.push((-g, sig));
+            #[test]
+            fn degenerate_inputs() {
+                let (pk, sk) = generate();
+                let identity = Signature(SignatureGroup::identity());
 
-                let ate_pairs = pairs.iter().map($set_pairs).collect();
-                GT::ate_multi_pairing(ate_pairs).is_one()
-            }
+                assert!(AggregatedSignature::new(&[]).is_err());
+                assert!(AggregatedPublicKey::new(&[]).is_err());
+                assert!(AggregatedPublicKey::try_from(&[pk.clone()][..]).is_ok());
+                assert!(Signature::new_with_rk_mitigation(
+                    &MESSAGE_1[..],
+                    None,
+                    &sk,
+                    1,
+                    &[pk.clone()]
+                )
+                .is_err());
+                assert!(
+                    Signature::new_with_rk_mitigation(&MESSAGE_1[..], None, &sk, 0, &[]).is_err()
+                );
 
-            pub fn to_bytes(&self) -> Vec<u8> {
-                self.0.to_bytes(false)
+                assert!(!identity.verify_multi(&[], None));
+                let aggregated = AggregatedSignature::new(&[identity]).unwrap();
+                assert!(!aggregated.verify_no_rk(&MESSAGE_1[..], None, &[]));
+                let aggregate = Aggregate::new(Signers::Keys(vec![]), aggregated);
+                assert!(!aggregate.verify(&MESSAGE_1[..], None, &[pk]));
             }
 
-            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
-                Ok(AggregatedSignature(
-                    SignatureGroup::from_bytes(bytes)
-                        .map_err(|e| CryptoError::ParseError(format!("{:?}", e)))?,
-                ))
-            }
-        }
-    };
-}
+            #[test]
+            fn streaming_aggregator() {
+                let keys = (0..3).map(|_| generate()).collect::<Vec<_>>();
+                let signatures = keys
+                    .iter()
+                    .map(|(_, sk)| Signature::new(&MESSAGE_1[..], None, sk))
+                    .collect::<Vec<_>>();
 
-macro_rules! bls_tests_impl {
-    () => {
-        #[cfg(test)]
-        mod tests {
-            use super::*;
+                let mut aggregator = Aggregator::new();
+                assert!(aggregator.is_empty());
+                aggregator.add(&signatures[0]);
+                aggregator.add_bytes(&signatures[1].to_bytes()).unwrap();
+                assert!(aggregator
+                    .add_bytes(&signatures[2].to_bytes()[1..])
+                    .is_err());
+                assert_eq!(aggregator.len(), 2);
+                aggregator.add(&signatures[2]);
 
-            const MESSAGE_1: &[u8; 22] = b"This is a test message";
-            const MESSAGE_2: &[u8; 20] = b"Another test message";
-            const SEED: &[u8; 10] = &[1u8; 10];
+                let aggregated = aggregator.finish().unwrap();
+                assert_eq!(
+                    aggregated.to_bytes(),
+                    AggregatedSignature::new(&signatures).unwrap().to_bytes()
+                );
+                let pks = keys.into_iter().map(|(pk, _)| pk).collect::<Vec<_>>();
+                assert!(aggregated.verify_no_rk(&MESSAGE_1[..], None, &pks));
+                assert!(Aggregator::new().finish().is_err());
+            }
 
             #[test]
-            fn signature_generation_from_seed() {
-                let keypair_1 = Bls
-                    .keypair(Some(KeyGenOption::UseSeed(SEED.to_vec())))
-                    .unwrap();
-                let keypair_2 = Bls
-                    .keypair(Some(KeyGenOption::UseSeed(SEED.to_vec())))
-                    .unwrap();
-                assert_eq!(keypair_1, keypair_2);
+            fn signer_set() {
+                let keys = (0..4).map(|_| generate()).collect::<Vec<_>>();
+                let pks = keys.iter().map(|(pk, _)| pk.clone()).collect::<Vec<_>>();
+                let set = SignerSet::new(&pks).unwrap();
+                assert_eq!(set.len(), 4);
+                assert_eq!(
+                    set.aggregate_pk().to_bytes(),
+                    AggregatedPublicKey::new(&pks).unwrap().to_bytes()
+                );
+
+                for _ in 0..2 {
+                    let signatures = keys
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (_, sk))| set.sign(i, &MESSAGE_1[..], None, sk).unwrap())
+                        .collect::<Vec<_>>();
+                    assert_eq!(
+                        signatures[1].to_bytes(),
+                        Signature::new_with_rk_mitigation(
+                            &MESSAGE_1[..],
+                            None,
+                            &keys[1].1,
+                            1,
+                            &pks
+                        )
+                        .unwrap()
+                        .to_bytes()
+                    );
+                    let aggregated = set.aggregate(&signatures).unwrap();
+                    assert!(aggregated.verify(&MESSAGE_1[..], None, set.aggregate_pk()));
+                    assert!(!aggregated.verify(&MESSAGE_2[..], None, set.aggregate_pk()));
+                    assert!(set.aggregate(&signatures[1..]).is_err());
+                }
+                assert!(set.sign(4, &MESSAGE_1[..], None, &keys[0].1).is_err());
+                assert!(SignerSet::new(&[]).is_err());
             }
 
             #[test]
-            fn signature_verification() {
-                let g = Generator::generator();
-                let (pk, sk) = generate(&g);
+            fn prehashed_verification() {
+                let keys = (0..3).map(|_| generate()).collect::<Vec<_>>();
+                let point = MessagePoint::hash(&MESSAGE_1[..], None);
+                let other = MessagePoint::hash(&MESSAGE_2[..], None);
+                let signatures = keys
+                    .iter()
+                    .map(|(_, sk)| Signature::new(&MESSAGE_1[..], None, sk))
+                    .collect::<Vec<_>>();
+                for ((pk, _), signature) in keys.iter().zip(&signatures) {
+                    assert!(signature.verify_prehashed(&point, pk));
+                    assert!(!signature.verify_prehashed(&other, pk));
+                }
+                let in_context = MessagePoint::hash(&MESSAGE_1[..], Some(MESSAGE_CONTEXT));
+                assert!(!signatures[0].verify_prehashed(&in_context, &keys[0].0));
 
-                let signature_1 = Signature::new(&MESSAGE_1[..], None, &sk);
-                assert!(signature_1.verify(&MESSAGE_1[..], None, &pk, &g));
+                let mut inputs = keys
+                    .iter()
+                    .zip(&signatures)
+                    .map(|((pk, _), signature)| (&point, signature, pk))
+                    .collect::<Vec<_>>();
+                assert!(Signature::batch_verify_prehashed(&inputs));
+                inputs[1].0 = &other;
+                assert!(!Signature::batch_verify_prehashed(&inputs));
 
-                let signature_2 = Signature::new(&MESSAGE_2[..], Some(MESSAGE_CONTEXT), &sk);
-                assert!(signature_2.verify(&MESSAGE_2[..], Some(MESSAGE_CONTEXT), &pk, &g));
+                let pks = keys.iter().map(|(pk, _)| pk.clone()).collect::<Vec<_>>();
+                let signatures = keys
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (_, sk))| {
+                        Signature::new_with_rk_mitigation(&MESSAGE_1[..], None, sk, i, &pks)
+                            .unwrap()
+                    })
+                    .collect::<Vec<_>>();
+                let aggregated = AggregatedSignature::new(&signatures).unwrap();
+                let apk = AggregatedPublicKey::new(&pks).unwrap();
+                assert!(aggregated.verify_prehashed(&point, &apk));
+                assert!(!aggregated.verify_prehashed(&other, &apk));
+            }
 
-                // Should fail for different messages
-                assert!(!signature_1.verify(&MESSAGE_2[..], Some(MESSAGE_CONTEXT), &pk, &g));
-                assert!(!signature_2.verify(&MESSAGE_1[..], None, &pk, &g));
+            #[cfg(feature = "serde")]
+            #[test]
+            fn serde_encoding() {
+                let (pk, sk) = generate();
+                let signature = Signature::new(&MESSAGE_1[..], None, &sk);
+                let json = serde_json::to_string(&pk).unwrap();
+                assert_eq!(json, format!("\"{}\"", hex::encode(pk.to_bytes())));
+                let decoded: PublicKey = serde_json::from_str(&json).unwrap();
+                assert_eq!(decoded.to_bytes(), pk.to_bytes());
+                let json = serde_json::to_string(&signature).unwrap();
+                let decoded: Signature = serde_json::from_str(&json).unwrap();
+                assert!(decoded.verify(&MESSAGE_1[..], None, &pk));
+
+                // The identity and bytes that aren't a point are rejected
+                let identity = hex::encode(Generator::identity().to_bytes(false));
+                assert!(serde_json::from_str::<PublicKey>(&format!("\"{}\"", identity)).is_err());
+                let mut bytes = pk.to_bytes();
+                bytes[1] ^= 1;
+                let json = format!("\"{}\"", hex::encode(&bytes));
+                assert!(serde_json::from_str::<PublicKey>(&json).is_err());
+                assert!(serde_json::from_str::<PublicKey>("\"not hex\"").is_err());
             }
 
             #[test]
-            fn proof_of_possession() {
-                let g = Generator::generator();
-                let (pk, sk) = generate(&g);
+            fn remove_signer() {
+                let keys = (0..4).map(|_| generate()).collect::<Vec<_>>();
+                let pks = keys.iter().map(|(pk, _)| pk.clone()).collect::<Vec<_>>();
+                let signatures = keys
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (_, sk))| {
+                        Signature::new_with_rk_mitigation(&MESSAGE_1[..], None, sk, i, &pks)
+                            .unwrap()
+                    })
+                    .collect::<Vec<_>>();
+                let mut aggregated = AggregatedSignature::new(&signatures).unwrap();
+                let mut apk = AggregatedPublicKey::new(&pks).unwrap();
+                assert!(aggregated.verify(&MESSAGE_1[..], None, &apk));
 
-                let proof_of_possession_1 = ProofOfPossession::new(&pk, None, &sk);
-                assert!(proof_of_possession_1.verify(None, &pk, &g));
+                aggregated.remove(&signatures[2]);
+                assert!(!aggregated.verify(&MESSAGE_1[..], None, &apk));
+                apk.remove(&pks[2], &pks).unwrap();
+                assert!(aggregated.verify(&MESSAGE_1[..], None, &apk));
+                let rest = [pks[0].clone(), pks[1].clone(), pks[3].clone()];
+                let expected = AggregatedSignature::new(&[
+                    signatures[0].clone(),
+                    signatures[1].clone(),
+                    signatures[3].clone(),
+                ])
+                .unwrap();
+                assert_eq!(aggregated.to_bytes(), expected.to_bytes());
+                assert_ne!(
+                    apk.to_bytes(),
+                    AggregatedPublicKey::new(&rest).unwrap().to_bytes()
+                );
 
-                let ctx = b"another domain separator";
-                let proof_of_possession_2 = ProofOfPossession::new(&pk, Some(ctx), &sk);
-                assert!(!proof_of_possession_2.verify(None, &pk, &g));
-                assert!(proof_of_possession_2.verify(Some(ctx), &pk, &g));
+                // A key outside the signer set can't be removed
+                let (other, _) = generate();
+                assert!(apk.remove(&other, &pks).is_err());
+
+                // Without rogue key mitigation the keys are just added up
+                let signatures = keys
+                    .iter()
+                    .map(|(_, sk)| Signature::new(&MESSAGE_1[..], None, sk))
+                    .collect::<Vec<_>>();
+                let mut aggregated = AggregatedSignature::new(&signatures).unwrap();
+                aggregated.remove(&signatures[0]);
+                assert!(aggregated.verify_no_rk(&MESSAGE_1[..], None, &pks[1..]));
             }
 
             #[test]
@@ -535,7 +3866,7 @@ macro_rules! bls_tests_impl {
                 let mut sks = Vec::new();
                 let mut asigs = Vec::new();
                 for _ in 0..KEY_COUNT {
-                    let (pk, sk) = generate(&g);
+                    let (pk, sk) = generate_with_generator(&g);
 
                     pks.push(pk);
                     sks.push(sk);
@@ -548,30 +3879,104 @@ macro_rules! bls_tests_impl {
                         &sks[i],
                         i,
                         pks.as_slice(),
-                    );
+                    )
+                    .unwrap();
                     asigs.push(sig);
                 }
 
-                let apk = AggregatedPublicKey::new(pks.as_slice());
-                let asg = AggregatedSignature::new(asigs.as_slice());
-                assert!(asg.verify(&MESSAGE_1[..], Some(MESSAGE_CONTEXT), &apk, &g));
+                let apk = AggregatedPublicKey::new(pks.as_slice()).unwrap();
+                let asg = AggregatedSignature::new(asigs.as_slice()).unwrap();
+                assert!(asg.verify_with_generator(&MESSAGE_1[..], Some(MESSAGE_CONTEXT), &apk, &g));
 
                 // Can't verify individually because of rogue key mitigation
                 for i in 0..KEY_COUNT {
-                    assert!(!asigs[i].verify(&MESSAGE_1[..], Some(MESSAGE_CONTEXT), &pks[i], &g));
+                    assert!(!asigs[i].verify_with_generator(
+                        &MESSAGE_1[..],
+                        Some(MESSAGE_CONTEXT),
+                        &pks[i],
+                        &g
+                    ));
                 }
             }
 
+            #[test]
+            fn custom_generator_verification() {
+                let g = Generator::from_msg_hash(b"nothing up my sleeve for this generator");
+                let keys = (0..3)
+                    .map(|_| generate_with_generator(&g))
+                    .collect::<Vec<_>>();
+                let (pk, sk) = &keys[0];
+
+                let sigs = keys
+                    .iter()
+                    .map(|(_, sk)| Signature::new(&MESSAGE_1[..], None, sk))
+                    .collect::<Vec<_>>();
+                let batch = keys
+                    .iter()
+                    .zip(sigs.iter())
+                    .map(|((pk, _), sig)| (&MESSAGE_1[..], sig, pk))
+                    .collect::<Vec<_>>();
+                assert!(Signature::batch_verify_with_generator(&batch, None, &g));
+                assert!(!Signature::batch_verify(&batch, None));
+
+                let parts = [
+                    Signature::new(&MESSAGE_1[..], None, sk),
+                    Signature::new(&MESSAGE_2[..], None, &keys[1].1),
+                ];
+                let mut multi = parts[0].clone();
+                multi.combine(&parts[1..]);
+                let inputs = [(&MESSAGE_1[..], pk), (&MESSAGE_2[..], &keys[1].0)];
+                assert!(multi.verify_multi_with_generator(&inputs, None, &g));
+                assert!(!multi.verify_multi(&inputs, None));
+                let asg = AggregatedSignature::new(&parts).unwrap();
+                assert!(asg.verify_multi_with_generator(&inputs, None, &g));
+                assert!(!asg.verify_multi(&inputs, None));
+
+                let pks = keys.iter().map(|(pk, _)| pk.clone()).collect::<Vec<_>>();
+                let apk = AggregatedPublicKey::new(&pks).unwrap();
+                let asigs = keys
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (_, sk))| {
+                        Signature::new_with_rk_mitigation(&MESSAGE_1[..], None, sk, i, &pks)
+                            .unwrap()
+                    })
+                    .collect::<Vec<_>>();
+                let asg = AggregatedSignature::new(&asigs).unwrap();
+                let batch = [(&MESSAGE_1[..], &asg, &apk)];
+                assert!(AggregatedSignature::batch_verify_with_generator(
+                    &batch, None, &g
+                ));
+                assert!(!AggregatedSignature::batch_verify(&batch, None));
+
+                let pop = ProofOfPossession::new(pk, None, sk);
+                assert!(pop.verify_with_generator(None, pk, &g));
+                assert!(!pop.verify(None, pk));
+
+                let nonce = b"custom generator nonce";
+                let proof = SignatureProof::new_with_generator(
+                    &sigs[0],
+                    &MESSAGE_1[..],
+                    None,
+                    pk,
+                    nonce,
+                    &g,
+                );
+                assert!(proof.verify_with_generator(&MESSAGE_1[..], None, pk, nonce, &g));
+                assert!(!proof.verify(&MESSAGE_1[..], None, pk, nonce));
+                let proof = SignatureProof::new(&sigs[0], &MESSAGE_1[..], None, pk, nonce);
+                assert!(!proof.verify_with_generator(&MESSAGE_1[..], None, pk, nonce, &g));
+            }
+
             #[test]
             fn aggregate_signature_verification_no_rk() {
                 const KEY_COUNT: usize = 10;
 
-                let g = Generator::generator();
                 let mut pks = Vec::new();
                 let mut sks = Vec::new();
                 let mut sigs = Vec::new();
                 for _ in 0..KEY_COUNT {
-                    let (pk, sk) = generate(&g);
+                    let (pk, sk) = generate();
 
                     pks.push(pk);
                     sks.push(sk);
@@ -582,31 +3987,27 @@ macro_rules! bls_tests_impl {
                     sigs.push(sig);
                 }
 
-                let asg = AggregatedSignature::new(sigs.as_slice());
-                assert!(asg.verify_no_rk(
-                    &MESSAGE_1[..],
-                    Some(MESSAGE_CONTEXT),
-                    pks.as_slice(),
-                    &g
-                ));
+                let asg = AggregatedSignature::new(sigs.as_slice()).unwrap();
+                assert!(asg.verify_no_rk(&MESSAGE_1[..], Some(MESSAGE_CONTEXT), pks.as_slice()));
 
                 // Check that simple aggregation without rogue key mitigation fails
-                let apk = AggregatedPublicKey::new(pks.as_slice());
-                assert!(!asg.verify(&MESSAGE_1[..], Some(MESSAGE_CONTEXT), &apk, &g));
+                let apk = AggregatedPublicKey::new(pks.as_slice()).unwrap();
+                assert!(!asg.verify(&MESSAGE_1[..], Some(MESSAGE_CONTEXT), &apk));
 
                 // Can verify individually because of no rogue key mitigation
                 for i in 0..KEY_COUNT {
-                    assert!(sigs[i].verify(&MESSAGE_1[..], Some(MESSAGE_CONTEXT), &pks[i], &g));
+                    assert!(sigs[i].verify(&MESSAGE_1[..], Some(MESSAGE_CONTEXT), &pks[i]));
                 }
             }
 
             #[test]
             fn batch_signature_verification() {
+                use rand::{rngs::StdRng, SeedableRng};
+
                 const KEY_COUNT: usize = 10;
                 const SIG_COUNT: usize = 5;
 
                 // First batch verification with rogue key mitigation
-                let g = Generator::generator();
                 let mut groups_1 = Vec::new();
                 for _ in 0..SIG_COUNT {
                     let mut sks = Vec::new();
@@ -614,7 +4015,7 @@ macro_rules! bls_tests_impl {
                     let mut sigs = Vec::new();
                     let msg = FieldElement::random();
                     for _ in 0..KEY_COUNT {
-                        let (pk, sk) = generate(&g);
+                        let (pk, sk) = generate();
                         pks.push(pk);
                         sks.push(sk);
                     }
@@ -626,14 +4027,15 @@ macro_rules! bls_tests_impl {
                             &sks[i],
                             i,
                             pks.as_slice(),
-                        );
+                        )
+                        .unwrap();
                         sigs.push(sig);
                     }
 
-                    let asg = AggregatedSignature::new(sigs.as_slice());
-                    let apk = AggregatedPublicKey::new(pks.as_slice());
+                    let asg = AggregatedSignature::new(sigs.as_slice()).unwrap();
+                    let apk = AggregatedPublicKey::new(pks.as_slice()).unwrap();
                     //sanity check
-                    assert!(asg.verify(msg.to_bytes().as_slice(), Some(MESSAGE_CONTEXT), &apk, &g));
+                    assert!(asg.verify(msg.to_bytes().as_slice(), Some(MESSAGE_CONTEXT), &apk));
                     groups_1.push((msg.to_bytes(), asg, apk));
                 }
 
@@ -644,8 +4046,26 @@ macro_rules! bls_tests_impl {
                 assert!(AggregatedSignature::batch_verify(
                     refs.as_slice(),
                     Some(MESSAGE_CONTEXT),
-                    &g
                 ));
+                // One scratch space reused by the batches of both groups
+                let mut rng = StdRng::seed_from_u64(4);
+                let mut scratch = Scratch::new();
+                assert!(AggregatedSignature::batch_verify_with_scratch(
+                    refs.as_slice(),
+                    Some(MESSAGE_CONTEXT),
+                    &mut rng,
+                    &mut scratch
+                ));
+                // The buffers stay in the scratch space for the next batch
+                assert!(Generator::points(&mut scratch).capacity() >= SIG_COUNT);
+                assert!(SignatureGroup::points(&mut scratch).capacity() >= SIG_COUNT);
+                // The hash is streamed but matches hashing the context and message joined
+                let mut joined = MESSAGE_CONTEXT.to_vec();
+                joined.extend_from_slice(&groups_1[0].0);
+                assert_eq!(
+                    hash_msg(&groups_1[0].0, Some(MESSAGE_CONTEXT)),
+                    SignatureGroup::from_msg_hash(&joined)
+                );
 
                 // Second batch verification without rogue key mitigation
                 let mut groups_2 = Vec::new();
@@ -655,7 +4075,7 @@ macro_rules! bls_tests_impl {
                     let mut sigs = Vec::new();
                     let msg = FieldElement::random();
                     for _ in 0..KEY_COUNT {
-                        let (pk, sk) = generate(&g);
+                        let (pk, sk) = generate();
                         pks.push(pk);
                         sks.push(sk);
                     }
@@ -676,7 +4096,7 @@ macro_rules! bls_tests_impl {
                     apk.combine(&pks[1..]);
 
                     //sanity check
-                    assert!(asg.verify(msg.to_bytes().as_slice(), Some(MESSAGE_CONTEXT), &apk, &g));
+                    assert!(asg.verify(msg.to_bytes().as_slice(), Some(MESSAGE_CONTEXT), &apk));
                     groups_2.push((msg.to_bytes(), asg, apk));
                 }
 
@@ -687,21 +4107,32 @@ macro_rules! bls_tests_impl {
                 assert!(Signature::batch_verify(
                     refs.as_slice(),
                     Some(MESSAGE_CONTEXT),
-                    &g
                 ));
+                for (context, valid) in &[(None, false), (Some(MESSAGE_CONTEXT), true)] {
+                    assert_eq!(
+                        *valid,
+                        Signature::batch_verify_with_scratch(
+                            refs.as_slice(),
+                            *context,
+                            &mut rng,
+                            &mut scratch
+                        )
+                    );
+                }
+                #[cfg(feature = "async")]
+                assert!(Signature::batch_verify_async(groups_2, Some(MESSAGE_CONTEXT)).wait());
             }
 
             #[test]
             fn multi_signature_verification() {
                 const KEY_COUNT: usize = 10;
 
-                let g = Generator::generator();
                 let mut pks = Vec::new();
                 let mut sks = Vec::new();
                 let mut sigs = Vec::new();
                 let mut msgs = Vec::new();
                 for _ in 0..KEY_COUNT {
-                    let (pk, sk) = generate(&g);
+                    let (pk, sk) = generate();
 
                     let msg = FieldElement::random();
                     let sig = Signature::new(msg.to_bytes().as_slice(), None, &sk);
@@ -719,29 +4150,102 @@ macro_rules! bls_tests_impl {
                     .map(|(msg, pk)| (msg.as_slice(), pk))
                     .collect::<Vec<(&[u8], &PublicKey)>>();
 
-                assert!(sig.verify_multi(inputs.as_slice(), None, &g));
+                assert!(sig.verify_multi(inputs.as_slice(), None));
+                assert_eq!(sig.verify_multi_strict(inputs.as_slice(), None), Ok(()));
+                assert_eq!(
+                    sig.verify_multi_strict(&inputs[1..], None),
+                    Err(VerificationError::Mismatch)
+                );
                 msgs[0] = msgs[1].clone();
                 let inputs = msgs
                     .iter()
                     .zip(pks.iter())
                     .map(|(msg, pk)| (msg.as_slice(), pk))
                     .collect::<Vec<(&[u8], &PublicKey)>>();
-                assert!(!sig.verify_multi(inputs.as_slice(), None, &g));
+                assert!(!sig.verify_multi(inputs.as_slice(), None));
+                assert_eq!(
+                    sig.verify_multi_strict(inputs.as_slice(), None),
+                    Err(VerificationError::DuplicateMessage)
+                );
+            }
+
+            #[test]
+            fn aggregate_verify_distinct_messages() {
+                let keys = (0..5).map(|_| generate()).collect::<Vec<_>>();
+                let msgs = (0..5u8).map(|i| vec![i; 8]).collect::<Vec<_>>();
+                let sigs = keys
+                    .iter()
+                    .zip(msgs.iter())
+                    .map(|((_, sk), msg)| Signature::new(msg, None, sk))
+                    .collect::<Vec<_>>();
+                let asig = AggregatedSignature::new(&sigs).unwrap();
+                let mut inputs = msgs
+                    .iter()
+                    .zip(keys.iter())
+                    .map(|(msg, (pk, _))| (msg.as_slice(), pk))
+                    .collect::<Vec<(&[u8], &PublicKey)>>();
+                assert!(asig.verify_multi(&inputs, None));
+                assert!(asig.verify_multi_strict(&inputs, None).is_ok());
+                assert!(!asig.verify_multi(&inputs, Some(MESSAGE_CONTEXT)));
+                assert!(!asig.verify_multi(&inputs[1..], None));
+                assert!(!asig.verify_multi(&[], None));
+                assert_eq!(
+                    asig.verify_multi_strict(&[], None),
+                    Err(VerificationError::EmptyInput)
+                );
+
+                // Keys swapped between messages
+                inputs[0].1 = &keys[1].0;
+                inputs[1].1 = &keys[0].0;
+                assert_eq!(
+                    asig.verify_multi_strict(&inputs, None),
+                    Err(VerificationError::Mismatch)
+                );
+
+                // The same message twice is rejected even with valid signatures
+                let repeated = [
+                    Signature::new(&msgs[0], None, &keys[0].1),
+                    Signature::new(&msgs[0], None, &keys[1].1),
+                ];
+                let asig = AggregatedSignature::new(&repeated).unwrap();
+                let inputs = [
+                    (msgs[0].as_slice(), &keys[0].0),
+                    (msgs[0].as_slice(), &keys[1].0),
+                ];
+                assert!(!asig.verify_multi(&inputs, None));
+                assert_eq!(
+                    asig.verify_multi_strict(&inputs, None),
+                    Err(VerificationError::DuplicateMessage)
+                );
             }
         }
     };
 }
 
 pub mod prelude {
+    #[cfg(feature = "bls_threshold")]
+    pub use super::{
+        lagrange_coefficient,
+        small::{
+            combine_signature_shares as small_combine_signature_shares,
+            PartialSignature as SmallPartialSignature, PublicKeyShare as SmallPublicKeyShare,
+        },
+        SignatureShare,
+    };
     pub use super::{
         normal::*,
         small::{
-            generate as small_generate, AggregatedPublicKey as SmallAggregatedPublicKey,
-            AggregatedSignature as SmallAggregatedSignature, Generator as SmallGenerator,
-            ProofOfPossession as SmallProofOfPossession, PublicKey as SmallPublicKey,
+            generate as small_generate, generate_with_generator as small_generate_with_generator,
+            generator as small_generator, AggregatedPublicKey as SmallAggregatedPublicKey,
+            AggregatedSignature as SmallAggregatedSignature, Aggregator as SmallAggregator,
+            BlsSigningContext as SmallBlsSigningContext, Generator as SmallGenerator,
+            MessagePoint as SmallMessagePoint, ProofOfPossession as SmallProofOfPossession,
+            PublicKey as SmallPublicKey, PublicKeyRef as SmallPublicKeyRef,
             Signature as SmallSignature, SignatureGroup as SmallSignatureGroup,
+            SignatureProof as SmallSignatureProof, SignatureRef as SmallSignatureRef,
+            SignerSet as SmallSignerSet, ValidatedPublicKey as SmallValidatedPublicKey,
         },
-        PrivateKey,
+        BlsScheme, PrivateKey,
     };
 }
 
@@ -756,8 +4260,14 @@ pub mod normal {
         GroupG2_SIZE,
         G1,
         G2,
+        G1Table,
+        G2Table,
+        Bls12381G1Pub,
+        "bls12381-g1",
+        "BLS12381G2",
         ate_2_pairing_g1_g2_is_one,
-        set_pairs_g1_g2
+        set_pairs_g1_g2,
+        pair_g1_g2
     );
 
     bls_tests_impl!();
@@ -777,8 +4287,14 @@ pub mod small {
         GroupG1_SIZE,
         G2,
         G1,
+        G2Table,
+        G1Table,
+        Bls12381G2Pub,
+        "bls12381-g2",
+        "BLS12381G1",
         ate_2_pairing_g2_g1_is_one,
-        set_pairs_g2_g1
+        set_pairs_g2_g1,
+        pair_g2_g1
     );
 
     bls_tests_impl!();
@@ -786,54 +4302,449 @@ pub mod small {
 
 #[inline(always)]
 fn ate_2_pairing_g1_g2_is_one(p1: &G1, g1: &G2, p2: &G1, g2: &G2) -> bool {
-    GT::ate_2_pairing(&-p1, g1, p2, g2).is_one()
+    multi_pairing_is_one(vec![(&-p1, g1), (p2, g2)])
+}
+
+#[inline(always)]
+fn set_pairs_g1_g2<'a>(t: (&'a G1, &'a G2)) -> (&'a G1, &'a G2) {
+    t
 }
 
 #[inline(always)]
-fn set_pairs_g1_g2(t: &(G1, G2)) -> (&G1, &G2) {
-    (&t.0, &t.1)
+fn pair_g1_g2(pk: &G1, sig: &G2) -> GT {
+    GT::ate_pairing(pk, sig)
 }
 
 #[inline(always)]
 fn ate_2_pairing_g2_g1_is_one(p1: &G2, g1: &G1, p2: &G2, g2: &G1) -> bool {
-    GT::ate_2_pairing(g1, &-p1, g2, p2).is_one()
+    multi_pairing_is_one(vec![(g1, &-p1), (g2, p2)])
 }
 
 #[inline(always)]
-fn set_pairs_g2_g1(t: &(G2, G1)) -> (&G1, &G2) {
-    (&t.1, &t.0)
+fn set_pairs_g2_g1<'a>(t: (&'a G2, &'a G1)) -> (&'a G1, &'a G2) {
+    (t.1, t.0)
+}
+
+#[inline(always)]
+fn pair_g2_g1(pk: &G2, sig: &G1) -> GT {
+    GT::ate_pairing(sig, pk)
+}
+
+/// True if the product of the pairings is one. This is the check behind
+/// every verification in this module.
+#[cfg(not(feature = "bls_blst_pairing"))]
+fn multi_pairing_is_one(pairs: Vec<(&G1, &G2)>) -> bool {
+    GT::ate_multi_pairing(pairs).is_one()
+}
+
+/// True if the product of the pairings is one, computed by blst
+#[cfg(feature = "bls_blst_pairing")]
+fn multi_pairing_is_one(pairs: Vec<(&G1, &G2)>) -> bool {
+    blst_pairing::multi_pairing_is_one(&pairs)
+}
+
+/// Pairings with blst.
+///
+/// This only accelerates pairings. Keys, signatures, hashing to the curve and
+/// scalar multiplication stay on amcl so encodings and results do not change.
+/// Only the final pairing product is handed to blst, which is where
+/// verification spends its time. Points cross over through their
+/// uncompressed encoding. A point that does not convert fails the check
+/// rather than quietly verifying with amcl.
+#[cfg(feature = "bls_blst_pairing")]
+mod blst_pairing {
+    use super::*;
+    use blst::{
+        blst_fp12, blst_fp12_is_one, blst_p1_affine, blst_p1_affine_is_equal, blst_p1_deserialize,
+        blst_p2_affine, blst_p2_affine_is_equal, blst_p2_deserialize, BLS12_381_G1, BLS12_381_G2,
+        BLST_ERROR,
+    };
+
+    lazy_static! {
+        /// How amcl orders the two halves of an Fp2 coordinate relative to
+        /// blst, found by converting the generators. `None` if the
+        /// generators do not match, in which case every check fails.
+        static ref G2_HALVES_SWAPPED: Option<bool> = {
+            let same_g1 = g1_affine(&G1::generator())
+                .map(|g1| unsafe { blst_p1_affine_is_equal(&g1, &BLS12_381_G1) })
+                .unwrap_or(false);
+            let g2 = G2::generator();
+            if same_g1 {
+                [false, true].iter().cloned().find(|&swapped| {
+                    g2_affine(&g2, swapped)
+                        .map(|p| unsafe { blst_p2_affine_is_equal(&p, &BLS12_381_G2) })
+                        .unwrap_or(false)
+                })
+            } else {
+                None
+            }
+        };
+    }
+
+    pub(super) fn multi_pairing_is_one(pairs: &[(&G1, &G2)]) -> bool {
+        let swapped = match *G2_HALVES_SWAPPED {
+            Some(swapped) => swapped,
+            None => return false,
+        };
+        let mut ps = Vec::with_capacity(pairs.len());
+        let mut qs = Vec::with_capacity(pairs.len());
+        for (p, q) in pairs {
+            // e(O, Q) = e(P, O) = 1, and blst does not take the identity
+            if p.is_identity() || q.is_identity() {
+                continue;
+            }
+            match (g1_affine(p), g2_affine(q, swapped)) {
+                (Some(p), Some(q)) => {
+                    ps.push(p);
+                    qs.push(q);
+                }
+                _ => return false,
+            }
+        }
+        if ps.is_empty() {
+            return true;
+        }
+        let product = blst_fp12::miller_loop_n(&qs, &ps).final_exp();
+        unsafe { blst_fp12_is_one(&product) }
+    }
+
+    /// amcl writes 0x04 || x || y, blst reads x || y
+    fn g1_affine(p: &G1) -> Option<blst_p1_affine> {
+        let bytes = p.to_bytes(false);
+        if bytes.len() != GroupG1_SIZE || bytes[0] != 0x04 {
+            return None;
+        }
+        let mut out = blst_p1_affine::default();
+        match unsafe { blst_p1_deserialize(&mut out, bytes[1..].as_ptr()) } {
+            BLST_ERROR::BLST_SUCCESS => Some(out),
+            _ => None,
+        }
+    }
+
+    fn g2_affine(q: &G2, swapped: bool) -> Option<blst_p2_affine> {
+        let bytes = q.to_bytes(false);
+        if bytes.len() != GroupG2_SIZE || bytes[0] != 0x04 {
+            return None;
+        }
+        let mut coords = bytes[1..].to_vec();
+        if swapped {
+            for c in coords.chunks_mut(2 * MODBYTES) {
+                let (a, b) = c.split_at_mut(MODBYTES);
+                a.swap_with_slice(b);
+            }
+        }
+        let mut out = blst_p2_affine::default();
+        match unsafe { blst_p2_deserialize(&mut out, coords.as_ptr()) } {
+            BLST_ERROR::BLST_SUCCESS => Some(out),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::normal::{
-        generate as normal_generate, Generator as NormalGenerator, Signature as NormalSignature,
-    };
-    use super::small::{
-        generate as small_generate, Generator as SmallGenerator, Signature as SmallSignature,
+    use super::normal::{generate as normal_generate, Signature as NormalSignature};
+    use super::small::{generate as small_generate, Signature as SmallSignature};
+    use super::{
+        derive_child, derive_from_path, parse_path, signing_key_path, withdrawal_key_path,
     };
     use amcl_wrapper::{
         constants::{GroupG1_SIZE, MODBYTES},
         field_elem::FieldElement,
-        group_elem::GroupElement,
         types_g2::GroupG2_SIZE,
     };
 
+    #[test]
+    fn eip2333_child_keys() {
+        for (seed, index, child) in &[
+            (
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e5349553\
+                 1f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+                0u32,
+                "2d18bd6c14e6d15bf8b5085c9b74f3daae3b03cc2014770a599d8c1539e50f8e",
+            ),
+            (
+                "3141592653589793238462643383279502884197169399375105820974944592",
+                3141592653u32,
+                "384843fad5f3d777ea39de3e47a8f999ae91f89e42bffa993d91d9782d152a0f",
+            ),
+        ] {
+            let seed = hex::decode(seed).unwrap();
+            let master = derive_from_path(&seed, "m").unwrap();
+            let sk = derive_child(&master, *index);
+            assert_eq!(hex::encode(&sk.to_bytes()[MODBYTES - 32..]), *child);
+            let path = format!("m/{}", index);
+            assert_eq!(derive_from_path(&seed, &path).unwrap(), sk);
+        }
+
+        let seed = [1u8; 32];
+        assert_eq!(signing_key_path(3), "m/12381/3600/3/0/0");
+        assert_eq!(
+            parse_path(&signing_key_path(3)).unwrap(),
+            vec![12381, 3600, 3, 0, 0]
+        );
+        let withdrawal = derive_from_path(&seed, &withdrawal_key_path(3)).unwrap();
+        assert_eq!(
+            derive_from_path(&seed, &signing_key_path(3)).unwrap(),
+            derive_child(&withdrawal, 0)
+        );
+        assert!(parse_path("12381/3600").is_err());
+        assert!(parse_path("m/12381'/3600").is_err());
+        assert!(parse_path("m/").is_err());
+        assert!(derive_from_path(&[1u8; 31], "m/0").is_err());
+    }
+
+    /// The messages of the RFC 9380 test vectors
+    fn quux_messages() -> Vec<Vec<u8>> {
+        vec![
+            b"".to_vec(),
+            b"abc".to_vec(),
+            b"abcdef0123456789".to_vec(),
+            [&b"q128_"[..], &[b'q'; 128][..]].concat(),
+            [&b"a512_"[..], &[b'a'; 512][..]].concat(),
+        ]
+    }
+
+    #[test]
+    fn expand_message_xmd_vectors() {
+        use amcl_wrapper::amcl::hmac::{xmd_expand, MC_SHA2};
+        use sha2::{Digest, Sha256};
+
+        // RFC 9380 appendix K.1
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128".to_vec();
+        let long = [
+            &b"QUUX-V01-CS02-with-expander-SHA256-128-long-DST-"[..],
+            &[b'1'; 208][..],
+        ]
+        .concat();
+        // Tags over 255 bytes are hashed first, as in `hash_to_curve`
+        let hashed = Sha256::new()
+            .chain(b"H2C-OVERSIZE-DST-")
+            .chain(&long)
+            .result()
+            .to_vec();
+        let mut vectors = Vec::new();
+        for (tag, outputs) in vec![
+            (
+                &dst,
+                vec![
+                    "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235",
+                    "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615",
+                    "eff31487c770a893cfb36f912fbfcbff40d5661771ca4b2cb4eafe524333f5c1",
+                    "b23a1d2b4d97b2ef7785562a7e8bac7eed54ed6e97e29aa51bfe3f12ddad1ff9",
+                    "4623227bcc01293b8c130bf771da8c298dede7383243dc0993d2d94823958c4c",
+                    "af84c27ccfd45d41914fdff5df25293e221afc53d8ad2ac06d5e3e29485dadbe\
+                     e0d121587713a3e0dd4d5e69e93eb7cd4f5df4cd103e188cf60cb02edc3edf18\
+                     eda8576c412b18ffb658e3dd6ec849469b979d444cf7b26911a08e63cf31f9dc\
+                     c541708d3491184472c2c29bb749d4286b004ceb5ee6b9a7fa5b646c993f0ced",
+                    "abba86a6129e366fc877aab32fc4ffc70120d8996c88aee2fe4b32d6c7b6437a\
+                     647e6c3163d40b76a73cf6a5674ef1d890f95b664ee0afa5359a5c4e07985635\
+                     bbecbac65d747d3d2da7ec2b8221b17b0ca9dc8a1ac1c07ea6a1e60583e2cb00\
+                     058e77b7b72a298425cd1b941ad4ec65e8afc50303a22c0f99b0509b4c895f40",
+                    "ef904a29bffc4cf9ee82832451c946ac3c8f8058ae97d8d629831a74c6572bd9\
+                     ebd0df635cd1f208e2038e760c4994984ce73f0d55ea9f22af83ba4734569d4b\
+                     c95e18350f740c07eef653cbb9f87910d833751825f0ebefa1abe5420bb52be1\
+                     4cf489b37fe1a72f7de2d10be453b2c9d9eb20c7e3f6edc5a60629178d9478df",
+                    "80be107d0884f0d881bb460322f0443d38bd222db8bd0b0a5312a6fedb49c1bb\
+                     d88fd75d8b9a09486c60123dfa1d73c1cc3169761b17476d3c6b7cbbd727acd0\
+                     e2c942f4dd96ae3da5de368d26b32286e32de7e5a8cb2949f866a0b80c58116b\
+                     29fa7fabb3ea7d520ee603e0c25bcaf0b9a5e92ec6a1fe4e0391d1cdbce8c68a",
+                    "546aff5444b5b79aa6148bd81728704c32decb73a3ba76e9e75885cad9def1d0\
+                     6d6792f8a7d12794e90efed817d96920d728896a4510864370c207f99bd4a608\
+                     ea121700ef01ed879745ee3e4ceef777eda6d9e5e38b90c86ea6fb0b36504ba4\
+                     a45d22e86f6db5dd43d98a294bebb9125d5b794e9d2a81181066eb954966a487",
+                ],
+            ),
+            (
+                &hashed,
+                vec![
+                    "e8dc0c8b686b7ef2074086fbdd2f30e3f8bfbd3bdf177f73f04b97ce618a3ed3",
+                    "52dbf4f36cf560fca57dedec2ad924ee9c266341d8f3d6afe5171733b16bbb12",
+                    "35387dcf22618f3728e6c686490f8b431f76550b0b2c61cbc1ce7001536f4521",
+                    "01b637612bb18e840028be900a833a74414140dde0c4754c198532c3a0ba42bc",
+                    "20cce7033cabc5460743180be6fa8aac5a103f56d481cf369a8accc0c374431b",
+                ],
+            ),
+        ] {
+            for (msg, uniform_bytes) in quux_messages().iter().cycle().zip(outputs) {
+                vectors.push((tag, msg.clone(), uniform_bytes));
+            }
+        }
+        for (tag, msg, uniform_bytes) in vectors {
+            let expected = hex::decode(uniform_bytes).unwrap();
+            let mut okm = vec![0u8; expected.len()];
+            let len = okm.len();
+            xmd_expand(MC_SHA2, 32, &mut okm, len, tag, &msg);
+            assert_eq!(okm, expected);
+        }
+    }
+
+    #[test]
+    fn hash_to_curve_vectors() {
+        use super::{normal, small};
+        use amcl_wrapper::group_elem_g2::G2;
+
+        // RFC 9380 appendix J.9.1
+        let dst = b"QUUX-V01-CS02-with-BLS12381G1_XMD:SHA-256_SSWU_RO_";
+        for (msg, (x, y)) in quux_messages().iter().zip(&[
+            (
+                "052926add2207b76ca4fa57a8734416c8dc95e24501772c8\
+                 14278700eed6d1e4e8cf62d9c09db0fac349612b759e79a1",
+                "08ba738453bfed09cb546dbb0783dbb3a5f1f566ed67bb6b\
+                 e0e8c67e2e81a4cc68ee29813bb7994998f3eae0c9c6a265",
+            ),
+            (
+                "03567bc5ef9c690c2ab2ecdf6a96ef1c139cc0b2f284dca0\
+                 a9a7943388a49a3aee664ba5379a7655d3c68900be2f6903",
+                "0b9c15f3fe6e5cf4211f346271d7b01c8f3b28be689c8429\
+                 c85b67af215533311f0b8dfaaa154fa6b88176c229f2885d",
+            ),
+            (
+                "11e0b079dea29a68f0383ee94fed1b940995272407e3bb91\
+                 6bbf268c263ddd57a6a27200a784cbc248e84f357ce82d98",
+                "03a87ae2caf14e8ee52e51fa2ed8eefe80f02457004ba4d4\
+                 86d6aa1f517c0889501dc7413753f9599b099ebcbbd2d709",
+            ),
+            (
+                "15f68eaa693b95ccb85215dc65fa81038d69629f70aeee0d\
+                 0f677cf22285e7bf58d7cb86eefe8f2e9bc3f8cb84fac488",
+                "1807a1d50c29f430b8cafc4f8638dfeeadf51211e1602a5f\
+                 184443076715f91bb90a48ba1e370edce6ae1062f5e6dd38",
+            ),
+            (
+                "082aabae8b7dedb0e78aeb619ad3bfd9277a2f77ba7fad20\
+                 ef6aabdc6c31d19ba5a6d12283553294c1825c4b3ca2dcfe",
+                "05b84ae5a942248eea39e1d91030458c40153f3b654ab787\
+                 2d779ad1e942856a20c438e8d99bc8abfbf74729ce1f7ac8",
+            ),
+        ]) {
+            let expected = [vec![0x04], hex::decode(x).unwrap(), hex::decode(y).unwrap()].concat();
+            assert_eq!(small::hash_to_curve(msg, dst).to_bytes(false), expected);
+        }
+
+        // RFC 9380 appendix J.10.1, with each coordinate as (c0, c1)
+        let dst = b"QUUX-V01-CS02-with-BLS12381G2_XMD:SHA-256_SSWU_RO_";
+        let g2 = |coords: &[&str; 4], swapped: bool| {
+            let mut bytes = vec![0x04];
+            for c in coords.chunks(2) {
+                let (c0, c1) = if swapped { (c[1], c[0]) } else { (c[0], c[1]) };
+                bytes.extend(hex::decode(c0).unwrap());
+                bytes.extend(hex::decode(c1).unwrap());
+            }
+            bytes
+        };
+        // amcl may put either half of an Fp2 first, so take its order from
+        // the generator
+        let generator = [
+            "024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02\
+             b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8",
+            "13e02b6052719f607dacd3a088274f65596bd0d09920b61a\
+             b5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e",
+            "0ce5d527727d6e118cc9cdc6da2e351aadfd9baa8cbdd3a7\
+             6d429a695160d12c923ac9cc3baca289e193548608b82801",
+            "0606c4a02ea734cc32acd2b02bc28b99cb3e287e85a763af\
+             267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be",
+        ];
+        let generator_bytes = G2::generator().to_bytes(false);
+        let swapped = generator_bytes == g2(&generator, true);
+        assert_eq!(generator_bytes, g2(&generator, swapped));
+        for (msg, coords) in quux_messages().iter().zip(&[
+            [
+                "0141ebfbdca40eb85b87142e130ab689c673cf60f1a3e98d\
+                 69335266f30d9b8d4ac44c1038e9dcdd5393faf5c41fb78a",
+                "05cb8437535e20ecffaef7752baddf98034139c38452458b\
+                 aeefab379ba13dff5bf5dd71b72418717047f5b0f37da03d",
+                "0503921d7f6a12805e72940b963c0cf3471c7b2a524950ca\
+                 195d11062ee75ec076daf2d4bc358c4b190c0c98064fdd92",
+                "12424ac32561493f3fe3c260708a12b7c620e7be00099a97\
+                 4e259ddc7d1f6395c3c811cdd19f1e8dbf3e9ecfdcbab8d6",
+            ],
+            [
+                "02c2d18e033b960562aae3cab37a27ce00d80ccd5ba4b7fe\
+                 0e7a210245129dbec7780ccc7954725f4168aff2787776e6",
+                "139cddbccdc5e91b9623efd38c49f81a6f83f175e80b06fc\
+                 374de9eb4b41dfe4ca3a230ed250fbe3a2acf73a41177fd8",
+                "1787327b68159716a37440985269cf584bcb1e621d3a7202\
+                 be6ea05c4cfe244aeb197642555a0645fb87bf7466b2ba48",
+                "00aa65dae3c8d732d10ecd2c50f8a1baf3001578f71c694e\
+                 03866e9f3d49ac1e1ce70dd94a733534f106d4cec0eddd16",
+            ],
+            [
+                "121982811d2491fde9ba7ed31ef9ca474f0e1501297f68c2\
+                 98e9f4c0028add35aea8bb83d53c08cfc007c1e005723cd0",
+                "190d119345b94fbd15497bcba94ecf7db2cbfd1e1fe7da03\
+                 4d26cbba169fb3968288b3fafb265f9ebd380512a71c3f2c",
+                "05571a0f8d3c08d094576981f4a3b8eda0a8e771fcdcc8ec\
+                 ceaf1356a6acf17574518acb506e435b639353c2e14827c8",
+                "0bb5e7572275c567462d91807de765611490205a941a5a6a\
+                 f3b1691bfe596c31225d3aabdf15faff860cb4ef17c7c3be",
+            ],
+            [
+                "19a84dd7248a1066f737cc34502ee5555bd3c19f2ecdb3c7\
+                 d9e24dc65d4e25e50d83f0f77105e955d78f4762d33c17da",
+                "0934aba516a52d8ae479939a91998299c76d39cc0c035cd1\
+                 8813bec433f587e2d7a4fef038260eef0cef4d02aae3eb91",
+                "14f81cd421617428bc3b9fe25afbb751d934a00493524bc4\
+                 e065635b0555084dd54679df1536101b2c979c0152d09192",
+                "09bcccfa036b4847c9950780733633f13619994394c23ff0\
+                 b32fa6b795844f4a0673e20282d07bc69641cee04f5e5662",
+            ],
+            [
+                "01a6ba2f9a11fa5598b2d8ace0fbe0a0eacb65deceb476fb\
+                 bcb64fd24557c2f4b18ecfc5663e54ae16a84f5ab7f62534",
+                "11fca2ff525572795a801eed17eb12785887c7b63fb77a42\
+                 be46ce4a34131d71f7a73e95fee3f812aea3de78b4d01569",
+                "0b6798718c8aed24bc19cb27f866f1c9effcdbf92397ad64\
+                 48b5c9db90d2b9da6cbabf48adc1adf59a1a28344e79d57e",
+                "03a47f8e6d1763ba0cad63d6114c0accbef65707825a511b\
+                 251a660a9b3994249ae4e63fac38b23da0c398689ee2ab52",
+            ],
+        ]) {
+            assert_eq!(
+                normal::hash_to_curve(msg, dst).to_bytes(false),
+                g2(coords, swapped)
+            );
+        }
+    }
+
     #[test]
     fn size_check() {
         let msg = FieldElement::random();
-        let g = NormalGenerator::generator();
-        let (pk, sk) = normal_generate(&g);
+        let (pk, sk) = normal_generate();
         assert_eq!(sk.to_bytes().len(), MODBYTES);
         assert_eq!(pk.to_bytes().len(), GroupG1_SIZE);
         let sig = NormalSignature::new(msg.to_bytes().as_slice(), None, &sk);
         assert_eq!(sig.to_bytes().len(), GroupG2_SIZE);
 
-        let g = SmallGenerator::generator();
-        let (pk, sk) = small_generate(&g);
+        let (pk, sk) = small_generate();
         assert_eq!(sk.to_bytes().len(), MODBYTES);
         assert_eq!(pk.to_bytes().len(), GroupG2_SIZE);
         let sig = SmallSignature::new(msg.to_bytes().as_slice(), None, &sk);
         assert_eq!(sig.to_bytes().len(), GroupG1_SIZE);
     }
+
+    #[cfg(feature = "bls_blst_pairing")]
+    #[test]
+    fn blst_pairing() {
+        use super::blst_pairing;
+        use amcl_wrapper::{
+            extension_field_gt::GT, group_elem::GroupElement, group_elem_g1::G1, group_elem_g2::G2,
+        };
+
+        let (p, q) = (G1::random(), G2::random());
+        let r = FieldElement::random();
+        let identity = G1::identity();
+        for pairs in vec![
+            vec![(-(&p * &r), q.clone()), (p.clone(), &q * &r)],
+            vec![(p.clone(), q.clone()), (p.clone(), &q * &r)],
+            vec![(identity.clone(), q.clone())],
+            vec![(identity, q.clone()), (p.clone(), q.clone())],
+        ] {
+            let pairs = pairs.iter().map(|t| (&t.0, &t.1)).collect::<Vec<_>>();
+            assert_eq!(
+                blst_pairing::multi_pairing_is_one(&pairs),
+                GT::ate_multi_pairing(pairs).is_one()
+            );
+        }
+    }
 }
@@ -0,0 +1,591 @@
+//! BLS signatures over BLS12-381 as specified by draft-irtf-cfrg-bls-signature-05
+//! (<https://tools.ietf.org/html/draft-irtf-cfrg-bls-signature-05>).
+//!
+//! Unlike `signatures::bls`, keys, signatures and messages here are exactly the
+//! ones produced by other implementations of the draft such as py_ecc and the
+//! Ethereum 2.0 clients: private keys are 32 byte big endian scalars, points use
+//! the 48 and 96 byte compressed encodings from the zcash serialization format
+//! and messages are hashed with the `XMD:SHA-256_SSWU_RO_` hash to curve suites.
+//! The arithmetic is done by blst.
+//!
+//! [`min_pk`] puts public keys in G1 and signatures in G2, [`min_sig`] the other
+//! way around. Both offer the three schemes from the draft, see [`Scheme`].
+//! [`eth2`] fixes the choices Ethereum 2.0 made and adds its variants of the
+//! aggregate functions.
+//!
+//! ```
+//! use ursa::signatures::bls_ietf::{min_pk::*, PrivateKey, Scheme};
+//!
+//! let sk = PrivateKey::key_gen(&[7u8; 32], b"").unwrap();
+//! let pk = PublicKey::new(&sk);
+//! let signature = sign(Scheme::ProofOfPossession, &sk, b"message");
+//! assert!(verify(Scheme::ProofOfPossession, &pk, b"message", &signature));
+//! ```
+
+use blst::min_pk::SecretKey;
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use std::fmt;
+use zeroize::Zeroize;
+
+use CryptoError;
+
+/// Bytes in an encoded private key
+pub const PRIVATE_KEY_SIZE: usize = 32;
+
+/// The three ways the draft protects aggregate signatures from rogue key attacks.
+/// Each one signs with its own domain separation tag, so a signature made under
+/// one scheme does not verify under another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    /// Aggregates must cover distinct messages
+    Basic,
+    /// Every message is prefixed with the signer's public key
+    MessageAugmentation,
+    /// Every public key comes with a proof of possession of its private key,
+    /// see `pop_prove`
+    ProofOfPossession,
+}
+
+impl Scheme {
+    fn suffix(self) -> &'static str {
+        match self {
+            Scheme::Basic => "NUL_",
+            Scheme::MessageAugmentation => "AUG_",
+            Scheme::ProofOfPossession => "POP_",
+        }
+    }
+}
+
+/// A BLS12-381 scalar, shared by both variants
+#[derive(Clone, PartialEq, Eq)]
+pub struct PrivateKey([u8; PRIVATE_KEY_SIZE]);
+
+impl PrivateKey {
+    /// A key from 32 random bytes
+    pub fn random() -> Self {
+        Self::random_with_rng(&mut OsRng)
+    }
+
+    pub fn random_with_rng<R: CryptoRng + RngCore>(rng: &mut R) -> Self {
+        let mut ikm = [0u8; 32];
+        rng.fill_bytes(&mut ikm);
+        let sk = Self::key_gen(&ikm, b"").expect("32 bytes is enough key material");
+        ikm.zeroize();
+        sk
+    }
+
+    /// The draft's KeyGen: derive a key from at least 32 bytes of secret
+    /// key material `ikm` and optional `key_info`
+    pub fn key_gen(ikm: &[u8], key_info: &[u8]) -> Result<Self, CryptoError> {
+        if ikm.len() < 32 {
+            return Err(CryptoError::KeyGenError(format!(
+                "Expected at least 32 bytes of key material, found {}",
+                ikm.len()
+            )));
+        }
+        SecretKey::key_gen(ikm, key_info)
+            .map(|sk| PrivateKey(sk.to_bytes()))
+            .map_err(|e| CryptoError::KeyGenError(format!("{:?}", e)))
+    }
+
+    /// The 32 byte big endian encoding
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    /// Decode a key, rejecting zero and values not less than the group order
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        if bytes.len() != PRIVATE_KEY_SIZE {
+            return Err(CryptoError::ParseError(format!(
+                "Expected {} bytes, found {}",
+                PRIVATE_KEY_SIZE,
+                bytes.len()
+            )));
+        }
+        SecretKey::from_bytes(bytes)
+            .map(|sk| PrivateKey(sk.to_bytes()))
+            .map_err(|_| CryptoError::ParseError("Invalid private key".to_string()))
+    }
+}
+
+impl Drop for PrivateKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("PrivateKey(..)")
+    }
+}
+
+macro_rules! bls_ietf_impl {
+    ($variant:ident, $pk_size:expr, $sig_size:expr, $suite:expr) => {
+        use super::{PrivateKey, Scheme};
+        use blst::{$variant as backend, BLST_ERROR};
+        use std::collections::BTreeSet;
+
+        use CryptoError;
+
+        pub const PUBLIC_KEY_SIZE: usize = $pk_size;
+        pub const SIGNATURE_SIZE: usize = $sig_size;
+
+        /// The domain separation tag signatures use under `scheme`
+        pub fn dst(scheme: Scheme) -> Vec<u8> {
+            format!("BLS_SIG_{}_XMD:SHA-256_SSWU_RO_{}", $suite, scheme.suffix()).into_bytes()
+        }
+
+        /// The domain separation tag of proofs of possession
+        pub fn pop_dst() -> Vec<u8> {
+            format!("BLS_POP_{}_XMD:SHA-256_SSWU_RO_POP_", $suite).into_bytes()
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct PublicKey(backend::PublicKey);
+
+        impl PublicKey {
+            /// SkToPk
+            pub fn new(sk: &PrivateKey) -> Self {
+                PublicKey(secret_key(sk).sk_to_pk())
+            }
+
+            /// The compressed encoding
+            pub fn to_bytes(&self) -> Vec<u8> {
+                self.0.compress().to_vec()
+            }
+
+            /// Decode a compressed public key and run KeyValidate on it, so
+            /// the identity and points outside the prime order subgroup are
+            /// rejected
+            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+                check_length(bytes, PUBLIC_KEY_SIZE)?;
+                backend::PublicKey::key_validate(bytes)
+                    .map(PublicKey)
+                    .map_err(parse_error)
+            }
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct Signature(backend::Signature);
+
+        impl Signature {
+            /// The compressed encoding
+            pub fn to_bytes(&self) -> Vec<u8> {
+                self.0.compress().to_vec()
+            }
+
+            /// Decode a compressed signature, rejecting points outside the
+            /// prime order subgroup
+            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+                check_length(bytes, SIGNATURE_SIZE)?;
+                backend::Signature::sig_validate(bytes, false)
+                    .map(Signature)
+                    .map_err(parse_error)
+            }
+        }
+
+        fn secret_key(sk: &PrivateKey) -> backend::SecretKey {
+            backend::SecretKey::from_bytes(&sk.0).expect("Private keys are always in range")
+        }
+
+        fn check_length(bytes: &[u8], size: usize) -> Result<(), CryptoError> {
+            if bytes.len() != size {
+                return Err(CryptoError::ParseError(format!(
+                    "Expected {} bytes, found {}",
+                    size,
+                    bytes.len()
+                )));
+            }
+            Ok(())
+        }
+
+        fn parse_error(e: BLST_ERROR) -> CryptoError {
+            CryptoError::ParseError(format!("{:?}", e))
+        }
+
+        /// CoreSign
+        pub fn core_sign(sk: &PrivateKey, msg: &[u8], dst: &[u8]) -> Signature {
+            Signature(secret_key(sk).sign(msg, dst, &[]))
+        }
+
+        /// CoreVerify
+        pub fn core_verify(pk: &PublicKey, msg: &[u8], signature: &Signature, dst: &[u8]) -> bool {
+            core_aggregate_verify(&[*pk], &[msg], signature, dst)
+        }
+
+        /// Aggregate. Fails if there are no signatures.
+        pub fn aggregate(signatures: &[Signature]) -> Result<Signature, CryptoError> {
+            if signatures.is_empty() {
+                return Err(CryptoError::GeneralError(
+                    "No signatures to aggregate".to_string(),
+                ));
+            }
+            let signatures = signatures.iter().map(|s| &s.0).collect::<Vec<_>>();
+            backend::AggregateSignature::aggregate(&signatures, false)
+                .map(|a| Signature(a.to_signature()))
+                .map_err(|e| CryptoError::GeneralError(format!("{:?}", e)))
+        }
+
+        /// CoreAggregateVerify: `signature` aggregates a signature by each
+        /// key over the message in the same position
+        pub fn core_aggregate_verify(
+            pks: &[PublicKey],
+            msgs: &[&[u8]],
+            signature: &Signature,
+            dst: &[u8],
+        ) -> bool {
+            // Keys and signatures were validated when they were decoded
+            let pks = pks.iter().map(|pk| &pk.0).collect::<Vec<_>>();
+            signature.0.aggregate_verify(false, msgs, dst, &pks, false) == BLST_ERROR::BLST_SUCCESS
+        }
+
+        /// Sign `msg` under `scheme`
+        pub fn sign(scheme: Scheme, sk: &PrivateKey, msg: &[u8]) -> Signature {
+            match scheme {
+                Scheme::MessageAugmentation => {
+                    let augmented = augment(&PublicKey::new(sk), msg);
+                    core_sign(sk, &augmented, &dst(scheme))
+                }
+                _ => core_sign(sk, msg, &dst(scheme)),
+            }
+        }
+
+        /// Verify a signature made by `sign`
+        pub fn verify(scheme: Scheme, pk: &PublicKey, msg: &[u8], signature: &Signature) -> bool {
+            aggregate_verify(scheme, &[*pk], &[msg], signature)
+        }
+
+        /// AggregateVerify for `scheme`. The basic scheme rejects repeated
+        /// messages.
+        pub fn aggregate_verify(
+            scheme: Scheme,
+            pks: &[PublicKey],
+            msgs: &[&[u8]],
+            signature: &Signature,
+        ) -> bool {
+            match scheme {
+                Scheme::Basic => {
+                    let distinct = msgs.iter().collect::<BTreeSet<_>>();
+                    distinct.len() == msgs.len()
+                        && core_aggregate_verify(pks, msgs, signature, &dst(scheme))
+                }
+                Scheme::MessageAugmentation => {
+                    let augmented = pks
+                        .iter()
+                        .zip(msgs.iter())
+                        .map(|(pk, msg)| augment(pk, msg))
+                        .collect::<Vec<_>>();
+                    let augmented = augmented.iter().map(|m| m.as_slice()).collect::<Vec<_>>();
+                    pks.len() == msgs.len()
+                        && core_aggregate_verify(pks, &augmented, signature, &dst(scheme))
+                }
+                Scheme::ProofOfPossession => {
+                    core_aggregate_verify(pks, msgs, signature, &dst(scheme))
+                }
+            }
+        }
+
+        /// PopProve
+        pub fn pop_prove(sk: &PrivateKey) -> Signature {
+            core_sign(sk, &PublicKey::new(sk).to_bytes(), &pop_dst())
+        }
+
+        /// PopVerify
+        pub fn pop_verify(pk: &PublicKey, proof: &Signature) -> bool {
+            core_verify(pk, &pk.to_bytes(), proof, &pop_dst())
+        }
+
+        /// Add up the keys of the signers of an aggregate over one message.
+        /// Fails if there are no keys.
+        pub fn aggregate_public_keys(pks: &[PublicKey]) -> Result<PublicKey, CryptoError> {
+            if pks.is_empty() {
+                return Err(CryptoError::GeneralError(
+                    "No public keys to aggregate".to_string(),
+                ));
+            }
+            let pks = pks.iter().map(|pk| &pk.0).collect::<Vec<_>>();
+            backend::AggregatePublicKey::aggregate(&pks, false)
+                .map(|a| PublicKey(a.to_public_key()))
+                .map_err(|e| CryptoError::GeneralError(format!("{:?}", e)))
+        }
+
+        /// FastAggregateVerify: `signature` aggregates a signature over `msg`
+        /// by each key. Only sound when every key's proof of possession was
+        /// checked with `pop_verify`.
+        pub fn fast_aggregate_verify(pks: &[PublicKey], msg: &[u8], signature: &Signature) -> bool {
+            let pks = pks.iter().map(|pk| &pk.0).collect::<Vec<_>>();
+            let dst = dst(Scheme::ProofOfPossession);
+            signature.0.fast_aggregate_verify(false, msg, &dst, &pks) == BLST_ERROR::BLST_SUCCESS
+        }
+
+        fn augment(pk: &PublicKey, msg: &[u8]) -> Vec<u8> {
+            let mut augmented = pk.to_bytes();
+            augmented.extend_from_slice(msg);
+            augmented
+        }
+    };
+}
+
+/// Public keys in G1, signatures in G2
+pub mod min_pk {
+    bls_ietf_impl!(min_pk, 48, 96, "BLS12381G2");
+}
+
+/// Public keys in G2, signatures in G1
+pub mod min_sig {
+    bls_ietf_impl!(min_sig, 96, 48, "BLS12381G1");
+}
+
+/// The BLS profile of the Ethereum 2.0 consensus specs: public keys in G1,
+/// the proof of possession scheme and compressed encodings, so signatures
+/// and keys are interchangeable with those of the Eth2 clients. The spec's
+/// functions are here under their snake case names.
+pub mod eth2 {
+    pub use super::min_pk::{PublicKey, Signature, PUBLIC_KEY_SIZE, SIGNATURE_SIZE};
+    pub use super::PrivateKey;
+    use super::{min_pk, Scheme};
+
+    use CryptoError;
+
+    /// The domain separation tag of every Eth2 signature
+    pub const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+    /// The encoding of the identity of G2, the aggregate of no signatures
+    pub const G2_POINT_AT_INFINITY: [u8; SIGNATURE_SIZE] = {
+        let mut point = [0u8; SIGNATURE_SIZE];
+        point[0] = 0xc0;
+        point
+    };
+
+    pub fn sign(sk: &PrivateKey, msg: &[u8]) -> Signature {
+        min_pk::sign(Scheme::ProofOfPossession, sk, msg)
+    }
+
+    pub fn verify(pk: &PublicKey, msg: &[u8], signature: &Signature) -> bool {
+        min_pk::verify(Scheme::ProofOfPossession, pk, msg, signature)
+    }
+
+    /// Fails if there are no signatures
+    pub fn aggregate(signatures: &[Signature]) -> Result<Signature, CryptoError> {
+        min_pk::aggregate(signatures)
+    }
+
+    /// Messages may repeat, since every validator key comes with a proof of
+    /// possession in its deposit
+    pub fn aggregate_verify(pks: &[PublicKey], msgs: &[&[u8]], signature: &Signature) -> bool {
+        min_pk::aggregate_verify(Scheme::ProofOfPossession, pks, msgs, signature)
+    }
+
+    /// False when there are no keys
+    pub fn fast_aggregate_verify(pks: &[PublicKey], msg: &[u8], signature: &Signature) -> bool {
+        min_pk::fast_aggregate_verify(pks, msg, signature)
+    }
+
+    /// The sum of `pks`, as sync committees publish it. Fails if there are
+    /// no keys.
+    pub fn eth_aggregate_pubkeys(pks: &[PublicKey]) -> Result<PublicKey, CryptoError> {
+        min_pk::aggregate_public_keys(pks)
+    }
+
+    /// `fast_aggregate_verify` that also accepts no keys with the identity
+    /// signature, for sync aggregates no member of the committee took part in
+    pub fn eth_fast_aggregate_verify(pks: &[PublicKey], msg: &[u8], signature: &Signature) -> bool {
+        if pks.is_empty() {
+            return signature.to_bytes()[..] == G2_POINT_AT_INFINITY[..];
+        }
+        fast_aggregate_verify(pks, msg, signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! scheme_tests {
+        ($name:ident, $variant:ident) => {
+            mod $name {
+                use super::super::$variant::*;
+                use super::super::{PrivateKey, Scheme};
+
+                const SCHEMES: [Scheme; 3] = [
+                    Scheme::Basic,
+                    Scheme::MessageAugmentation,
+                    Scheme::ProofOfPossession,
+                ];
+
+                #[test]
+                fn sign_verify() {
+                    let sk = PrivateKey::random();
+                    let pk = PublicKey::new(&sk);
+                    for scheme in SCHEMES.iter() {
+                        let signature = sign(*scheme, &sk, b"message");
+                        assert!(verify(*scheme, &pk, b"message", &signature));
+                        assert!(!verify(*scheme, &pk, b"other message", &signature));
+                        let other = PublicKey::new(&PrivateKey::random());
+                        assert!(!verify(*scheme, &other, b"message", &signature));
+                        for other_scheme in SCHEMES.iter().filter(|s| *s != scheme) {
+                            assert!(!verify(*other_scheme, &pk, b"message", &signature));
+                        }
+                    }
+                }
+
+                #[test]
+                fn aggregates() {
+                    let sks = (0..3).map(|_| PrivateKey::random()).collect::<Vec<_>>();
+                    let pks = sks.iter().map(PublicKey::new).collect::<Vec<_>>();
+                    let msgs: [&[u8]; 3] = [b"one", b"two", b"three"];
+                    for scheme in SCHEMES.iter() {
+                        let signatures = sks
+                            .iter()
+                            .zip(msgs.iter())
+                            .map(|(sk, msg)| sign(*scheme, sk, msg))
+                            .collect::<Vec<_>>();
+                        let signature = aggregate(&signatures).unwrap();
+                        assert!(aggregate_verify(*scheme, &pks, &msgs, &signature));
+                        assert!(!aggregate_verify(
+                            *scheme,
+                            &pks[1..],
+                            &msgs[1..],
+                            &signature
+                        ));
+                        assert!(!aggregate_verify(*scheme, &[], &[], &signature));
+                    }
+                    assert!(aggregate(&[]).is_err());
+
+                    // The basic scheme refuses repeated messages
+                    let same: [&[u8]; 2] = [b"same", b"same"];
+                    let signatures = sks[..2]
+                        .iter()
+                        .map(|sk| sign(Scheme::Basic, sk, b"same"))
+                        .collect::<Vec<_>>();
+                    let signature = aggregate(&signatures).unwrap();
+                    assert!(!aggregate_verify(
+                        Scheme::Basic,
+                        &pks[..2],
+                        &same,
+                        &signature
+                    ));
+
+                    // Proofs of possession allow fast verification
+                    let signatures = sks
+                        .iter()
+                        .map(|sk| sign(Scheme::ProofOfPossession, sk, b"same"))
+                        .collect::<Vec<_>>();
+                    let signature = aggregate(&signatures).unwrap();
+                    assert!(fast_aggregate_verify(&pks, b"same", &signature));
+                    assert!(!fast_aggregate_verify(&pks[1..], b"same", &signature));
+                    assert!(!fast_aggregate_verify(&[], b"same", &signature));
+                    let proof = pop_prove(&sks[0]);
+                    assert!(pop_verify(&pks[0], &proof));
+                    assert!(!pop_verify(&pks[1], &proof));
+                    // A signature over the key's encoding is not a proof
+                    let signature = sign(Scheme::ProofOfPossession, &sks[0], &pks[0].to_bytes());
+                    assert!(!pop_verify(&pks[0], &signature));
+                }
+
+                #[test]
+                fn encodings() {
+                    let sk = PrivateKey::random();
+                    let pk = PublicKey::new(&sk);
+                    let signature = sign(Scheme::Basic, &sk, b"message");
+                    let pk_bytes = pk.to_bytes();
+                    let sig_bytes = signature.to_bytes();
+                    assert_eq!(pk_bytes.len(), PUBLIC_KEY_SIZE);
+                    assert_eq!(sig_bytes.len(), SIGNATURE_SIZE);
+                    assert_eq!(PublicKey::from_bytes(&pk_bytes).unwrap(), pk);
+                    assert_eq!(Signature::from_bytes(&sig_bytes).unwrap(), signature);
+                    assert!(PublicKey::from_bytes(&pk_bytes[1..]).is_err());
+                    assert!(Signature::from_bytes(&pk_bytes).is_err());
+
+                    // The identity is not a valid key
+                    let mut identity = vec![0u8; PUBLIC_KEY_SIZE];
+                    identity[0] = 0xc0;
+                    assert!(PublicKey::from_bytes(&identity).is_err());
+                }
+            }
+        };
+    }
+
+    scheme_tests!(min_pk_schemes, min_pk);
+    scheme_tests!(min_sig_schemes, min_sig);
+
+    #[test]
+    fn key_gen() {
+        // EIP-2333 test case 0 derives its master key with the draft's KeyGen
+        let seed = hex::decode(
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e5349553\
+             1f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        )
+        .unwrap();
+        let sk = PrivateKey::key_gen(&seed, b"").unwrap();
+        assert_eq!(
+            hex::encode(sk.to_bytes()),
+            "0d7359d57963ab8fbbde1852dcf553fedbc31f464d80ee7d40ae683122b45070"
+        );
+        assert_eq!(PrivateKey::from_bytes(&sk.to_bytes()).unwrap(), sk);
+        assert_ne!(PrivateKey::key_gen(&seed, b"info").unwrap(), sk);
+        assert!(PrivateKey::key_gen(&seed[..31], b"").is_err());
+        assert!(PrivateKey::from_bytes(&[0u8; 32]).is_err());
+        assert!(PrivateKey::from_bytes(&[0xffu8; 32]).is_err());
+        assert_eq!(format!("{:?}", sk), "PrivateKey(..)");
+    }
+
+    #[test]
+    fn eth2_vector() {
+        // The first sign case from the Ethereum 2.0 BLS test suite
+        let sk = PrivateKey::from_bytes(
+            &hex::decode("263dbd792f5b1be47ed85f8938c0f29586af0d3ac7b977f21c278fe1462040e3")
+                .unwrap(),
+        )
+        .unwrap();
+        let pk = eth2::PublicKey::new(&sk);
+        assert_eq!(
+            hex::encode(pk.to_bytes()),
+            "a491d1b0ecd9bb917989f0e74f0dea0422eac4a873e5e2644f368dffb9a6e20f\
+             d6e10c1b77654d067c0618f6e5a7f79a"
+        );
+        let signature = eth2::sign(&sk, &[0u8; 32]);
+        assert_eq!(
+            hex::encode(signature.to_bytes()),
+            "b6ed936746e01f8ecf281f020953fbf1f01debd5657c4a383940b020b26507f6\
+             076334f91e2366c96e9ab279fb5158090352ea1c5b0c9274504f4f0e7053af24\
+             802e51e4568d164fe986834f41e55c8e850ce1f98458c0cfc9ab380b55285a55"
+        );
+        assert_eq!(
+            signature,
+            min_pk::sign(Scheme::ProofOfPossession, &sk, &[0u8; 32])
+        );
+        let signature = eth2::Signature::from_bytes(&signature.to_bytes()).unwrap();
+        assert!(eth2::verify(&pk, &[0u8; 32], &signature));
+        assert_eq!(eth2::DST, &min_pk::dst(Scheme::ProofOfPossession)[..]);
+    }
+
+    #[test]
+    fn eth2_aggregates() {
+        let sks = (0..3).map(|_| PrivateKey::random()).collect::<Vec<_>>();
+        let pks = sks.iter().map(eth2::PublicKey::new).collect::<Vec<_>>();
+        let signatures = sks
+            .iter()
+            .map(|sk| eth2::sign(sk, b"root"))
+            .collect::<Vec<_>>();
+        let signature = eth2::aggregate(&signatures).unwrap();
+        assert!(eth2::fast_aggregate_verify(&pks, b"root", &signature));
+        assert!(eth2::eth_fast_aggregate_verify(&pks, b"root", &signature));
+        assert!(eth2::aggregate_verify(
+            &pks,
+            &[b"root", b"root", b"root"],
+            &signature
+        ));
+        let apk = eth2::eth_aggregate_pubkeys(&pks).unwrap();
+        assert!(eth2::verify(&apk, b"root", &signature));
+        assert!(eth2::eth_aggregate_pubkeys(&[]).is_err());
+
+        // Only the eth_ variant accepts an empty committee, and only with
+        // the identity signature
+        let identity = eth2::Signature::from_bytes(&eth2::G2_POINT_AT_INFINITY).unwrap();
+        assert!(!eth2::fast_aggregate_verify(&[], b"root", &identity));
+        assert!(eth2::eth_fast_aggregate_verify(&[], b"root", &identity));
+        assert!(!eth2::eth_fast_aggregate_verify(&[], b"root", &signature));
+        assert!(!eth2::aggregate_verify(&[], &[], &identity));
+    }
+}
@@ -0,0 +1,269 @@
+//! Remembers the result of verifying a signature so it isn't verified twice.
+//!
+//! Gossip networks deliver the same signed message from many peers. Putting
+//! a `CachedVerifier` in front of the signature scheme verifies each
+//! `(scheme, public key, message, signature)` once and answers the repeats
+//! from the cache. Messages are stored as their SHA-256 hash.
+//!
+//! The cache is bounded, `LruCache` drops the entry used least recently once
+//! it is full. Another eviction policy or a cache shared with other services
+//! can be used by implementing `VerificationCache`.
+
+use super::SignatureScheme;
+use keys::PublicKey;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use CryptoError;
+
+/// Identifies one verification
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    /// The type name of the signature scheme
+    pub scheme: &'static str,
+    pub public_key: Vec<u8>,
+    /// SHA-256 of the message
+    pub message_hash: [u8; 32],
+    pub signature: Vec<u8>,
+}
+
+impl CacheKey {
+    pub fn new<S: SignatureScheme>(message: &[u8], signature: &[u8], pk: &PublicKey) -> Self {
+        let mut message_hash = [0u8; 32];
+        message_hash.copy_from_slice(Sha256::digest(message).as_slice());
+        CacheKey {
+            scheme: std::any::type_name::<S>(),
+            public_key: pk[..].to_vec(),
+            message_hash,
+            signature: signature.to_vec(),
+        }
+    }
+}
+
+/// Storage for verification results
+pub trait VerificationCache: Send + Sync {
+    /// The result stored for `key`, if any
+    fn get(&self, key: &CacheKey) -> Option<bool>;
+    /// Store the result of verifying `key`
+    fn insert(&self, key: CacheKey, valid: bool);
+}
+
+/// A `VerificationCache` holding up to `capacity` results, evicting the one
+/// used least recently
+pub struct LruCache {
+    capacity: usize,
+    entries: Mutex<LruEntries>,
+}
+
+#[derive(Default)]
+struct LruEntries {
+    /// Result and last use of each key
+    results: HashMap<CacheKey, (bool, u64)>,
+    /// Keys by last use, the oldest first
+    uses: BTreeMap<u64, CacheKey>,
+    clock: u64,
+}
+
+impl LruCache {
+    /// Panics if `capacity` is 0
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must not be 0");
+        LruCache {
+            capacity,
+            entries: Mutex::new(LruEntries::default()),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of results stored
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl VerificationCache for LruCache {
+    fn get(&self, key: &CacheKey) -> Option<bool> {
+        let mut entries = self.entries.lock().unwrap();
+        let entries = &mut *entries;
+        entries.clock += 1;
+        let (valid, used) = entries.results.get_mut(key)?;
+        let key = entries.uses.remove(used).unwrap();
+        *used = entries.clock;
+        entries.uses.insert(entries.clock, key);
+        Some(*valid)
+    }
+
+    fn insert(&self, key: CacheKey, valid: bool) {
+        let mut entries = self.entries.lock().unwrap();
+        let entries = &mut *entries;
+        entries.clock += 1;
+        if let Some((_, used)) = entries.results.insert(key.clone(), (valid, entries.clock)) {
+            entries.uses.remove(&used);
+        } else if entries.results.len() > self.capacity {
+            let oldest = *entries.uses.keys().next().unwrap();
+            let evicted = entries.uses.remove(&oldest).unwrap();
+            entries.results.remove(&evicted);
+        }
+        entries.uses.insert(entries.clock, key);
+    }
+}
+
+/// Hit and miss counts of a `CachedVerifier`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Verifies signatures with a scheme, answering from a cache when the same
+/// signature was verified before.
+///
+/// Only results are cached, so a signature that failed to parse is checked
+/// again every time.
+pub struct CachedVerifier<S: SignatureScheme, C: VerificationCache = LruCache> {
+    scheme: S,
+    cache: C,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<S: SignatureScheme> CachedVerifier<S> {
+    /// Cache up to `capacity` results in an `LruCache`
+    pub fn new(scheme: S, capacity: usize) -> Self {
+        Self::with_cache(scheme, LruCache::new(capacity))
+    }
+}
+
+impl<S: SignatureScheme, C: VerificationCache> CachedVerifier<S, C> {
+    pub fn with_cache(scheme: S, cache: C) -> Self {
+        CachedVerifier {
+            scheme,
+            cache,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Same as `SignatureScheme::verify`
+    pub fn verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<bool, CryptoError> {
+        let key = CacheKey::new::<S>(message, signature, pk);
+        if let Some(valid) = self.cache.get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(valid);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let valid = self.scheme.verify(message, signature, pk)?;
+        self.cache.insert(key, valid);
+        Ok(valid)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn scheme(&self) -> &S {
+        &self.scheme
+    }
+
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keys::{KeyGenOption, PrivateKey};
+
+    /// Accepts a signature equal to the message and counts the calls
+    #[derive(Default)]
+    struct Echo(AtomicU64);
+
+    impl SignatureScheme for Echo {
+        fn new() -> Self {
+            Echo::default()
+        }
+        fn keypair(&self, _: Option<KeyGenOption>) -> Result<(PublicKey, PrivateKey), CryptoError> {
+            Ok((PublicKey(vec![]), PrivateKey(vec![])))
+        }
+        fn sign(&self, message: &[u8], _: &PrivateKey) -> Result<Vec<u8>, CryptoError> {
+            Ok(message.to_vec())
+        }
+        fn verify(
+            &self,
+            message: &[u8],
+            signature: &[u8],
+            _: &PublicKey,
+        ) -> Result<bool, CryptoError> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            if signature.is_empty() {
+                return Err(CryptoError::ParseError("Empty signature".to_string()));
+            }
+            Ok(message == signature)
+        }
+        fn signature_size() -> usize {
+            0
+        }
+        fn private_key_size() -> usize {
+            0
+        }
+        fn public_key_size() -> usize {
+            0
+        }
+    }
+
+    fn key(message: u8) -> CacheKey {
+        CacheKey::new::<Echo>(&[message], &[message], &PublicKey(vec![]))
+    }
+
+    #[test]
+    fn cached_results() {
+        let verifier = CachedVerifier::new(Echo::default(), 16);
+        let pk = PublicKey(vec![1]);
+        for _ in 0..3 {
+            assert!(verifier.verify(b"a", b"a", &pk).unwrap());
+            assert!(!verifier.verify(b"a", b"b", &pk).unwrap());
+            assert!(verifier.verify(b"a", b"", &pk).is_err());
+        }
+        assert!(verifier.verify(b"a", b"a", &PublicKey(vec![2])).unwrap());
+
+        assert_eq!(verifier.scheme().0.load(Ordering::Relaxed), 6);
+        assert_eq!(verifier.stats(), CacheStats { hits: 4, misses: 6 });
+        assert_eq!(verifier.cache().len(), 3);
+    }
+
+    #[test]
+    fn lru_eviction() {
+        let cache = LruCache::new(2);
+        cache.insert(key(1), true);
+        cache.insert(key(2), false);
+        assert_eq!(cache.get(&key(1)), Some(true));
+        cache.insert(key(3), true);
+        assert_eq!(cache.get(&key(2)), None);
+        assert_eq!(cache.get(&key(1)), Some(true));
+        assert_eq!(cache.get(&key(3)), Some(true));
+
+        cache.insert(key(1), false);
+        cache.insert(key(4), true);
+        assert_eq!(cache.get(&key(3)), None);
+        assert_eq!(cache.get(&key(1)), Some(false));
+        assert_eq!(cache.len(), 2);
+    }
+}
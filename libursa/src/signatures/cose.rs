@@ -0,0 +1,229 @@
+//! COSE_Sign1 messages (RFC 8152 section 4.2) with `EdDSA` and
+//! `ES256K` (RFC 8812) signatures.
+
+use super::SignatureScheme;
+use keys::{PrivateKey, PublicKey};
+use serde_cbor::Value;
+use std::collections::BTreeMap;
+use CryptoError;
+
+/// CBOR tag of a COSE_Sign1 message
+pub const COSE_SIGN1_TAG: u64 = 18;
+
+const HEADER_ALG: i128 = 1;
+const HEADER_CRIT: i128 = 2;
+const HEADER_KID: i128 = 4;
+
+/// A signature scheme usable as a COSE algorithm
+pub trait CoseAlgorithm: SignatureScheme {
+    /// The COSE algorithm identifier
+    fn cose_algorithm(&self) -> i128;
+}
+
+#[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+impl CoseAlgorithm for super::ed25519::Ed25519Sha512 {
+    fn cose_algorithm(&self) -> i128 {
+        -8
+    }
+}
+
+#[cfg(any(
+    feature = "ecdsa_secp256k1",
+    feature = "ecdsa_secp256k1_native",
+    feature = "ecdsa_secp256k1_asm"
+))]
+impl CoseAlgorithm for super::secp256k1::EcdsaSecp256k1Sha256 {
+    fn cose_algorithm(&self) -> i128 {
+        -47
+    }
+}
+
+/// A verified COSE_Sign1 message
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoseSign1 {
+    pub kid: Option<Vec<u8>>,
+    pub payload: Vec<u8>,
+}
+
+/// Sign `payload` and return the tagged COSE_Sign1 message.
+/// `kid` is added to the protected header when given and
+/// `external_aad` is bound to the signature without being included.
+pub fn sign1<S: CoseAlgorithm>(
+    scheme: &S,
+    payload: &[u8],
+    external_aad: &[u8],
+    sk: &PrivateKey,
+    kid: Option<&[u8]>,
+) -> Result<Vec<u8>, CryptoError> {
+    let mut header = BTreeMap::new();
+    header.insert(
+        Value::Integer(HEADER_ALG),
+        Value::Integer(scheme.cose_algorithm()),
+    );
+    if let Some(kid) = kid {
+        header.insert(Value::Integer(HEADER_KID), Value::Bytes(kid.to_vec()));
+    }
+    let protected = to_vec(&Value::Map(header))?;
+    let signature = scheme.sign(&sig_structure(&protected, external_aad, payload)?, sk)?;
+    to_vec(&Value::Tag(
+        COSE_SIGN1_TAG,
+        Box::new(Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(BTreeMap::new()),
+            Value::Bytes(payload.to_vec()),
+            Value::Bytes(signature),
+        ])),
+    ))
+}
+
+/// Verify a COSE_Sign1 message, tagged or untagged.
+/// The protected `alg` header must name the algorithm of `scheme`.
+pub fn verify1<S: CoseAlgorithm>(
+    scheme: &S,
+    message: &[u8],
+    external_aad: &[u8],
+    pk: &PublicKey,
+) -> Result<CoseSign1, CryptoError> {
+    let value = serde_cbor::from_slice(message)
+        .map_err(|e| CryptoError::ParseError(format!("Invalid COSE_Sign1: {}", e)))?;
+    let parts = match value {
+        Value::Tag(COSE_SIGN1_TAG, inner) => *inner,
+        v => v,
+    };
+    let (protected, unprotected, payload, signature) = match parts {
+        Value::Array(ref parts) if parts.len() == 4 => {
+            match (&parts[0], &parts[1], &parts[2], &parts[3]) {
+                (Value::Bytes(p), Value::Map(u), Value::Bytes(m), Value::Bytes(s)) => (p, u, m, s),
+                _ => return Err(parse_error("unexpected field types")),
+            }
+        }
+        _ => return Err(parse_error("expected an array of four fields")),
+    };
+
+    let header = match serde_cbor::from_slice(protected) {
+        Ok(Value::Map(header)) => header,
+        _ => return Err(parse_error("protected header is not a map")),
+    };
+    match header.get(&Value::Integer(HEADER_ALG)) {
+        Some(Value::Integer(alg)) if *alg == scheme.cose_algorithm() => {}
+        _ => {
+            return Err(CryptoError::ParseError(format!(
+                "COSE algorithm must be {}",
+                scheme.cose_algorithm()
+            )))
+        }
+    }
+    if header.contains_key(&Value::Integer(HEADER_CRIT))
+        || unprotected.contains_key(&Value::Integer(HEADER_ALG))
+    {
+        return Err(parse_error("unsupported header parameters"));
+    }
+    let kid = match header
+        .get(&Value::Integer(HEADER_KID))
+        .or_else(|| unprotected.get(&Value::Integer(HEADER_KID)))
+    {
+        None => None,
+        Some(Value::Bytes(kid)) => Some(kid.clone()),
+        Some(_) => return Err(parse_error("kid is not a byte string")),
+    };
+
+    match scheme.verify(
+        &sig_structure(protected, external_aad, payload)?,
+        signature,
+        pk,
+    ) {
+        Ok(true) => Ok(CoseSign1 {
+            kid,
+            payload: payload.clone(),
+        }),
+        _ => Err(CryptoError::SigningError(
+            "Invalid COSE_Sign1 signature".to_string(),
+        )),
+    }
+}
+
+fn sig_structure(
+    protected: &[u8],
+    external_aad: &[u8],
+    payload: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    to_vec(&Value::Array(vec![
+        Value::Text("Signature1".to_string()),
+        Value::Bytes(protected.to_vec()),
+        Value::Bytes(external_aad.to_vec()),
+        Value::Bytes(payload.to_vec()),
+    ]))
+}
+
+fn to_vec(value: &Value) -> Result<Vec<u8>, CryptoError> {
+    serde_cbor::to_vec(value).map_err(|e| CryptoError::GeneralError(e.to_string()))
+}
+
+fn parse_error(msg: &str) -> CryptoError {
+    CryptoError::ParseError(format!("Invalid COSE_Sign1: {}", msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    #[test]
+    fn eddsa_sign1() {
+        use signatures::ed25519::Ed25519Sha512;
+
+        // RFC 8152 Appendix C.2.1 structure with an Ed25519 key from RFC 8032
+        let scheme = Ed25519Sha512::new();
+        let sk = PrivateKey(
+            hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+                .unwrap(),
+        );
+        let pk = PublicKey(sk[32..].to_vec());
+        let message = sign1(&scheme, b"This is the content.", b"", &sk, Some(b"11")).unwrap();
+        // 18([h'a2012704423131', {}, h'...', h'...'])
+        assert_eq!("d28447a2012704423131a054", hex::encode(&message[..12]));
+
+        let verified = verify1(&scheme, &message, b"", &pk).unwrap();
+        assert_eq!(b"This is the content.", &verified.payload[..]);
+        assert_eq!(Some(b"11".to_vec()), verified.kid);
+
+        assert!(verify1(&scheme, &message, b"aad", &pk).is_err());
+        let mut tampered = message.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(verify1(&scheme, &tampered, b"", &pk).is_err());
+
+        // The tag is optional
+        let untagged = sign1(&scheme, b"payload", b"aad", &sk, None).unwrap();
+        let verified = verify1(&scheme, &untagged[1..], b"aad", &pk).unwrap();
+        assert_eq!(None, verified.kid);
+    }
+
+    #[cfg(any(
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    ))]
+    #[test]
+    fn es256k_sign1() {
+        use signatures::secp256k1::EcdsaSecp256k1Sha256;
+
+        let scheme = EcdsaSecp256k1Sha256::new();
+        let (pk, sk) = scheme.keypair(None).unwrap();
+        let message = sign1(&scheme, b"payload", b"", &sk, None).unwrap();
+        assert_eq!(
+            b"payload",
+            &verify1(&scheme, &message, b"", &pk).unwrap().payload[..]
+        );
+
+        let (other, _) = scheme.keypair(None).unwrap();
+        assert!(verify1(&scheme, &message, b"", &other).is_err());
+
+        #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+        {
+            use signatures::ed25519::Ed25519Sha512;
+            let (ed_pk, _) = Ed25519Sha512::new().keypair(None).unwrap();
+            assert!(verify1(&Ed25519Sha512::new(), &message, b"", &ed_pk).is_err());
+        }
+    }
+}
@@ -1,6 +1,12 @@
 pub const ALGORITHM_NAME: &str = "ED25519_SHA2_512";
 
-use super::{KeyGenOption, SignatureScheme};
+use super::{
+    BatchVerify, KeyGenOption, PrehashSignatureScheme, SignatureScheme, VerificationError,
+};
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use curve25519_dalek::digest::generic_array::{typenum::U64, GenericArray};
+use curve25519_dalek::digest::{FixedOutput, Reset, Update};
 #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
 use ed25519_dalek::SecretKey as SK;
 use ed25519_dalek::{Keypair, PublicKey as PK, Signature, Signer, Verifier};
@@ -8,7 +14,9 @@ pub use ed25519_dalek::{
     EXPANDED_SECRET_KEY_LENGTH as PRIVATE_KEY_SIZE, PUBLIC_KEY_LENGTH as PUBLIC_KEY_SIZE,
     SIGNATURE_LENGTH as SIGNATURE_SIZE,
 };
+use encoding::pkcs8::{KeyAlgorithm, Pkcs8KeyHandler};
 use keys::{PrivateKey, PublicKey};
+#[cfg(feature = "std")]
 use rand::rngs::OsRng;
 use rand::SeedableRng;
 use rand_chacha::ChaChaRng;
@@ -18,6 +26,8 @@ use zeroize::Zeroize;
 
 use CryptoError;
 
+/// Ed25519 with ed25519-dalek. Key generation and signing multiply by the
+/// basepoint with the precomputed table of the curve25519-dalek backend.
 pub struct Ed25519Sha512;
 
 #[cfg(any(feature = "x25519", feature = "x25519_asm"))]
@@ -106,6 +116,42 @@ impl Ed25519Sha512 {
     }
 }
 
+impl Pkcs8KeyHandler for Ed25519Sha512 {
+    fn key_algorithm(&self) -> KeyAlgorithm {
+        KeyAlgorithm::Ed25519
+    }
+    fn private_key_to_raw(&self, sk: &PrivateKey) -> Result<Vec<u8>, CryptoError> {
+        if sk.len() != PRIVATE_KEY_SIZE {
+            return Err(CryptoError::ParseError(
+                "Invalid private key provided".to_string(),
+            ));
+        }
+        // The seed is the first half, the second half is the public key
+        Ok(sk[..32].to_vec())
+    }
+    fn keypair_from_raw(&self, raw: &[u8]) -> Result<(PublicKey, PrivateKey), CryptoError> {
+        let sk = ed25519_dalek::SecretKey::from_bytes(raw)
+            .map_err(|e| CryptoError::ParseError(e.to_string()))?;
+        let pk = PK::from(&sk);
+        let kp = Keypair {
+            secret: sk,
+            public: pk,
+        };
+        Ok((
+            PublicKey(pk.to_bytes().to_vec()),
+            PrivateKey(kp.to_bytes().to_vec()),
+        ))
+    }
+    fn public_key_to_raw(&self, pk: &PublicKey) -> Result<Vec<u8>, CryptoError> {
+        self.public_key_from_raw(&pk[..])?;
+        Ok(pk[..].to_vec())
+    }
+    fn public_key_from_raw(&self, raw: &[u8]) -> Result<PublicKey, CryptoError> {
+        PK::from_bytes(raw).map_err(|e| CryptoError::ParseError(e.to_string()))?;
+        Ok(PublicKey(raw.to_vec()))
+    }
+}
+
 impl SignatureScheme for Ed25519Sha512 {
     fn new() -> Self {
         Self
@@ -125,10 +171,17 @@ impl SignatureScheme for Ed25519Sha512 {
                 KeyGenOption::FromSecretKey(ref s) => Keypair::from_bytes(&s[..])
                     .map_err(|e| CryptoError::KeyGenError(e.to_string()))?,
             },
+            #[cfg(feature = "std")]
             None => {
                 let mut rng = OsRng::default();
                 Keypair::generate(&mut rng)
             }
+            #[cfg(not(feature = "std"))]
+            None => {
+                return Err(CryptoError::KeyGenError(
+                    "No OS random number generator without std, use keypair_with_rng".to_string(),
+                ))
+            }
         };
         Ok((
             PublicKey(kp.public.to_bytes().to_vec()),
@@ -153,6 +206,19 @@ impl SignatureScheme for Ed25519Sha512 {
             .map_err(|e| CryptoError::SigningError(e.to_string()))?;
         Ok(true)
     }
+    fn verify_strict(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<(), VerificationError> {
+        let p =
+            PK::from_bytes(&pk[..]).map_err(|e| VerificationError::InvalidKey(e.to_string()))?;
+        let s = Signature::try_from(signature)
+            .map_err(|e| VerificationError::Malformed(e.to_string()))?;
+        p.verify(message, &s)
+            .map_err(|_| VerificationError::Mismatch)
+    }
     fn signature_size() -> usize {
         SIGNATURE_SIZE
     }
@@ -164,10 +230,168 @@ impl SignatureScheme for Ed25519Sha512 {
     }
 }
 
+impl BatchVerify for Ed25519Sha512 {}
+
+/// Ed25519ph from RFC 8032, the SHA-512 hash of the message is signed instead
+/// of the message itself. The signatures differ from those made by `sign` and
+/// only verify with `verify_prehashed` or `verify_reader`.
+impl PrehashSignatureScheme for Ed25519Sha512 {
+    type Hasher = sha2::Sha512;
+
+    fn sign_prehashed(
+        &self,
+        hasher: Self::Hasher,
+        sk: &PrivateKey,
+    ) -> Result<Vec<u8>, CryptoError> {
+        sign_ph(hasher, None, sk)
+    }
+    fn verify_prehashed(
+        &self,
+        hasher: Self::Hasher,
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<bool, CryptoError> {
+        verify_ph(hasher, None, signature, pk)
+    }
+}
+
+fn sign_ph(
+    hasher: sha2::Sha512,
+    context: Option<&[u8]>,
+    sk: &PrivateKey,
+) -> Result<Vec<u8>, CryptoError> {
+    let kp = Keypair::from_bytes(&sk[..]).map_err(|e| CryptoError::KeyGenError(e.to_string()))?;
+    let s = kp
+        .sign_prehashed(Sha512Prehash(hasher), context)
+        .map_err(|e| CryptoError::SigningError(e.to_string()))?;
+    Ok(s.to_bytes().to_vec())
+}
+
+fn verify_ph(
+    hasher: sha2::Sha512,
+    context: Option<&[u8]>,
+    signature: &[u8],
+    pk: &PublicKey,
+) -> Result<bool, CryptoError> {
+    let p = PK::from_bytes(&pk[..]).map_err(|e| CryptoError::ParseError(e.to_string()))?;
+    let s = Signature::try_from(signature).map_err(|e| CryptoError::ParseError(e.to_string()))?;
+    p.verify_prehashed(Sha512Prehash(hasher), context, &s)
+        .map_err(|e| CryptoError::SigningError(e.to_string()))?;
+    Ok(true)
+}
+
+/// Options for signing with Ed25519.
+///
+/// Messages are signed with plain Ed25519 unless `prehashed` selects
+/// Ed25519ph, which can also bind the signature to a `context` string of up
+/// to 255 bytes. A signature only verifies with the options it was made with.
+///
+/// # Example
+/// ```
+/// use ursa::signatures::ed25519::{Ed25519Sha512, Ed25519SigningContext};
+/// use ursa::signatures::SignatureScheme;
+///
+/// let (pk, sk) = Ed25519Sha512::new().keypair(None).unwrap();
+/// let context = Ed25519SigningContext::new()
+///     .prehashed(true)
+///     .context(b"payments");
+/// let signature = context.sign(b"message", &sk).unwrap();
+/// assert!(context.verify(b"message", &signature, &pk).unwrap());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Ed25519SigningContext {
+    prehashed: bool,
+    context: Option<Vec<u8>>,
+}
+
+impl Ed25519SigningContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sign the SHA-512 hash of the message with Ed25519ph
+    pub fn prehashed(mut self, prehashed: bool) -> Self {
+        self.prehashed = prehashed;
+        self
+    }
+
+    /// The Ed25519ph context string
+    pub fn context(mut self, context: &[u8]) -> Self {
+        self.context = Some(context.to_vec());
+        self
+    }
+
+    pub fn sign(&self, message: &[u8], sk: &PrivateKey) -> Result<Vec<u8>, CryptoError> {
+        if self.prehashed {
+            sign_ph(self.hash(message), self.context.as_deref(), sk)
+        } else {
+            self.check_pure()?;
+            Ed25519Sha512.sign(message, sk)
+        }
+    }
+
+    pub fn verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<bool, CryptoError> {
+        if self.prehashed {
+            verify_ph(self.hash(message), self.context.as_deref(), signature, pk)
+        } else {
+            self.check_pure()?;
+            Ed25519Sha512.verify(message, signature, pk)
+        }
+    }
+
+    fn hash(&self, message: &[u8]) -> sha2::Sha512 {
+        let mut hasher = sha2::Sha512::default();
+        hasher.input(message);
+        hasher
+    }
+
+    fn check_pure(&self) -> Result<(), CryptoError> {
+        if self.context.is_some() {
+            return Err(CryptoError::SigningError(
+                "Only Ed25519ph takes a context, set prehashed".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// ed25519-dalek takes the prehash through a newer version of the `digest`
+/// traits than the sha2 crate used here, this forwards between the two
+#[derive(Clone, Default)]
+struct Sha512Prehash(sha2::Sha512);
+
+impl Update for Sha512Prehash {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.0.input(data)
+    }
+}
+
+impl FixedOutput for Sha512Prehash {
+    type OutputSize = U64;
+
+    fn finalize_into(self, out: &mut GenericArray<u8, U64>) {
+        out.copy_from_slice(self.0.result().as_slice())
+    }
+    fn finalize_into_reset(&mut self, out: &mut GenericArray<u8, U64>) {
+        out.copy_from_slice(self.0.result_reset().as_slice())
+    }
+}
+
+impl Reset for Sha512Prehash {
+    fn reset(&mut self) {
+        sha2::Digest::reset(&mut self.0)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use self::Ed25519Sha512;
-    use super::super::{SignatureScheme, Signer};
+    use super::super::{verify_batch, SignatureScheme, Signer};
     use super::*;
     use keys::{KeyGenOption, PrivateKey, PublicKey};
     use libsodium_ffi as ffi;
@@ -204,6 +428,78 @@ mod test {
         assert_eq!(p1, PublicKey(hex::decode(PUBLIC_KEY).unwrap()));
     }
 
+    #[test]
+    fn ed25519ph_rfc8032() {
+        // Test vector for Ed25519ph from RFC 8032 section 7.3
+        let scheme = Ed25519Sha512::new();
+        let secret = PrivateKey(
+            hex::decode("833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf").unwrap(),
+        );
+        let (p, s) = scheme
+            .keypair(Some(KeyGenOption::FromSecretKey(secret)))
+            .unwrap();
+        let expected = hex::decode("98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae4131f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406").unwrap();
+
+        let sig = scheme.sign_reader(&b"abc"[..], &s).unwrap();
+        assert_eq!(sig, expected);
+        assert!(scheme.verify_reader(&b"abc"[..], &sig, &p).unwrap());
+        assert!(scheme.verify_reader(&b"abd"[..], &sig, &p).is_err());
+        assert!(scheme.verify(b"abc", &sig, &p).is_err());
+    }
+
+    #[test]
+    fn ed25519_signing_context() {
+        let scheme = Ed25519Sha512::new();
+        let secret = PrivateKey(
+            hex::decode("833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf").unwrap(),
+        );
+        let (p, s) = scheme
+            .keypair(Some(KeyGenOption::FromSecretKey(secret)))
+            .unwrap();
+
+        let pure = Ed25519SigningContext::new();
+        let sig = pure.sign(b"abc", &s).unwrap();
+        assert_eq!(sig, scheme.sign(b"abc", &s).unwrap());
+        assert!(pure.verify(b"abc", &sig, &p).unwrap());
+
+        let ph = Ed25519SigningContext::new().prehashed(true);
+        let sig = ph.sign(b"abc", &s).unwrap();
+        assert_eq!(sig, scheme.sign_reader(&b"abc"[..], &s).unwrap());
+        assert!(ph.verify(b"abc", &sig, &p).unwrap());
+
+        let ctx = ph.clone().context(b"foo");
+        let ctx_sig = ctx.sign(b"abc", &s).unwrap();
+        assert_ne!(ctx_sig, sig);
+        assert!(ctx.verify(b"abc", &ctx_sig, &p).unwrap());
+        assert!(ph.verify(b"abc", &ctx_sig, &p).is_err());
+        assert!(ph.clone().context(&[0u8; 256]).sign(b"abc", &s).is_err());
+        assert!(pure.context(b"foo").sign(b"abc", &s).is_err());
+    }
+
+    #[test]
+    fn ed25519_verify_strict() {
+        let scheme = Ed25519Sha512::new();
+        let secret = PrivateKey(hex::decode(PRIVATE_KEY).unwrap());
+        let (p, _) = scheme
+            .keypair(Some(KeyGenOption::FromSecretKey(secret)))
+            .unwrap();
+        let signature = hex::decode(SIGNATURE_1).unwrap();
+
+        assert_eq!(scheme.verify_strict(&MESSAGE_1, &signature, &p), Ok(()));
+        assert_eq!(
+            scheme.verify_strict(b"another message", &signature, &p),
+            Err(VerificationError::Mismatch)
+        );
+        assert!(matches!(
+            scheme.verify_strict(&MESSAGE_1, &signature[1..], &p),
+            Err(VerificationError::Malformed(_))
+        ));
+        assert!(matches!(
+            scheme.verify_strict(&MESSAGE_1, &signature, &PublicKey(vec![1u8; 3])),
+            Err(VerificationError::InvalidKey(_))
+        ));
+    }
+
     #[test]
     fn ed25519_verify() {
         let scheme = Ed25519Sha512::new();
@@ -229,6 +525,55 @@ mod test {
         assert_eq!(res, 0);
     }
 
+    #[test]
+    fn ed25519_verify_batch() {
+        let scheme = Ed25519Sha512::new();
+        let keys = (0u8..8)
+            .map(|i| {
+                scheme
+                    .keypair(Some(KeyGenOption::UseSeed(vec![i; 32])))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let signatures = keys
+            .iter()
+            .map(|(_, sk)| scheme.sign(MESSAGE_1, sk).unwrap())
+            .collect::<Vec<_>>();
+        let mut inputs = keys
+            .iter()
+            .zip(signatures.iter())
+            .map(|((pk, _), sig)| (MESSAGE_1, sig.as_slice(), pk))
+            .collect::<Vec<_>>();
+        assert!(verify_batch(&scheme, &inputs).unwrap());
+        assert!(verify_batch(&scheme, &[]).unwrap());
+
+        inputs[5].2 = &keys[4].0;
+        assert!(verify_batch(&scheme, &inputs).is_err());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn ed25519_verify_batch_async() {
+        use super::super::verify_batch_async;
+
+        let scheme = Ed25519Sha512::new();
+        let mut inputs = (0u8..8)
+            .map(|i| {
+                let (pk, sk) = scheme
+                    .keypair(Some(KeyGenOption::UseSeed(vec![i; 32])))
+                    .unwrap();
+                let sig = scheme.sign(MESSAGE_1, &sk).unwrap();
+                (MESSAGE_1.to_vec(), sig, pk)
+            })
+            .collect::<Vec<_>>();
+        assert!(verify_batch_async(Ed25519Sha512, inputs.clone())
+            .wait()
+            .unwrap());
+
+        inputs[5].0 = b"Another message".to_vec();
+        assert!(verify_batch_async(Ed25519Sha512, inputs).wait().is_err());
+    }
+
     #[test]
     fn ed25519_sign() {
         let scheme = Ed25519Sha512::new();
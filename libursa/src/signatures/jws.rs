@@ -0,0 +1,185 @@
+//! JWS compact serialization (RFC 7515) with `EdDSA` (RFC 8037)
+//! and `ES256K` (RFC 8812) signatures.
+
+use super::SignatureScheme;
+use encoding::base64;
+use keys::{PrivateKey, PublicKey};
+use serde_json::{Map, Value};
+use CryptoError;
+
+/// A signature scheme usable as a JWS algorithm
+pub trait JwsAlgorithm: SignatureScheme {
+    /// The `alg` header value
+    fn jws_algorithm(&self) -> &'static str;
+}
+
+#[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+impl JwsAlgorithm for super::ed25519::Ed25519Sha512 {
+    fn jws_algorithm(&self) -> &'static str {
+        "EdDSA"
+    }
+}
+
+/// ES256K signatures are `r || s` over the SHA-256 digest, which is what
+/// `EcdsaSecp256k1Sha256::sign` returns
+#[cfg(any(
+    feature = "ecdsa_secp256k1",
+    feature = "ecdsa_secp256k1_native",
+    feature = "ecdsa_secp256k1_asm"
+))]
+impl JwsAlgorithm for super::secp256k1::EcdsaSecp256k1Sha256 {
+    fn jws_algorithm(&self) -> &'static str {
+        "ES256K"
+    }
+}
+
+/// Sign `payload` and return the compact JWS.
+/// `kid` is added to the protected header when given.
+pub fn sign_compact<S: JwsAlgorithm>(
+    scheme: &S,
+    payload: &[u8],
+    sk: &PrivateKey,
+    kid: Option<&str>,
+) -> Result<String, CryptoError> {
+    let mut header = Map::new();
+    header.insert(
+        "alg".to_string(),
+        Value::String(scheme.jws_algorithm().to_string()),
+    );
+    if let Some(kid) = kid {
+        header.insert("kid".to_string(), Value::String(kid.to_string()));
+    }
+    let header = serde_json::to_vec(&Value::Object(header))
+        .map_err(|e| CryptoError::GeneralError(e.to_string()))?;
+
+    let signing_input = format!(
+        "{}.{}",
+        base64::encode_url(&header),
+        base64::encode_url(payload)
+    );
+    let signature = scheme.sign(signing_input.as_bytes(), sk)?;
+    Ok(format!(
+        "{}.{}",
+        signing_input,
+        base64::encode_url(&signature)
+    ))
+}
+
+/// Verify a compact JWS and return its protected header and payload.
+/// The `alg` header must name the algorithm of `scheme`.
+pub fn verify_compact<S: JwsAlgorithm>(
+    scheme: &S,
+    jws: &str,
+    pk: &PublicKey,
+) -> Result<(Map<String, Value>, Vec<u8>), CryptoError> {
+    let parts = jws.split('.').collect::<Vec<&str>>();
+    if parts.len() != 3 {
+        return Err(CryptoError::ParseError(
+            "JWS must have three parts".to_string(),
+        ));
+    }
+    let header = match serde_json::from_slice(&base64::decode_url(parts[0])?) {
+        Ok(Value::Object(header)) => header,
+        _ => {
+            return Err(CryptoError::ParseError(
+                "JWS header is not a JSON object".to_string(),
+            ))
+        }
+    };
+    match header.get("alg") {
+        Some(Value::String(ref alg)) if alg == scheme.jws_algorithm() => {}
+        _ => {
+            return Err(CryptoError::ParseError(format!(
+                "JWS algorithm must be {}",
+                scheme.jws_algorithm()
+            )))
+        }
+    }
+    if header.contains_key("crit") {
+        return Err(CryptoError::ParseError(
+            "Critical JWS header parameters are not supported".to_string(),
+        ));
+    }
+
+    let payload = base64::decode_url(parts[1])?;
+    let signature = base64::decode_url(parts[2])?;
+    let signing_input = &jws[..parts[0].len() + 1 + parts[1].len()];
+    match scheme.verify(signing_input.as_bytes(), &signature, pk) {
+        Ok(true) => Ok((header, payload)),
+        _ => Err(CryptoError::SigningError(
+            "Invalid JWS signature".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+    #[test]
+    fn eddsa_jws() {
+        use encoding::jwk::Jwk;
+        use signatures::ed25519::Ed25519Sha512;
+
+        // RFC 8037 Appendix A.4
+        let jwk: Jwk = serde_json::from_str(
+            r#"{"kty":"OKP","crv":"Ed25519",
+                "d":"nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A",
+                "x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}"#,
+        )
+        .unwrap();
+        let scheme = Ed25519Sha512::new();
+        let (pk, sk) = jwk.to_keypair(&scheme).unwrap();
+        let jws = sign_compact(&scheme, b"Example of Ed25519 signing", &sk, None).unwrap();
+        assert_eq!(
+            "eyJhbGciOiJFZERTQSJ9.RXhhbXBsZSBvZiBFZDI1NTE5IHNpZ25pbmc.hgyY0il_MGCjP0JzlnLWG1PPOt7-09PGcvMg3AIbQR6dWbhijcNR4ki4iylGjg5BhVsPt9g7sVvpAr_MuM0KAg",
+            jws
+        );
+        let (header, payload) = verify_compact(&scheme, &jws, &pk).unwrap();
+        assert_eq!(b"Example of Ed25519 signing", &payload[..]);
+        assert_eq!(Some(&Value::String("EdDSA".to_string())), header.get("alg"));
+
+        let with_kid = sign_compact(&scheme, b"payload", &sk, Some("key-1")).unwrap();
+        let (header, _) = verify_compact(&scheme, &with_kid, &pk).unwrap();
+        assert_eq!(Some(&Value::String("key-1".to_string())), header.get("kid"));
+
+        let tampered = jws.replacen(".RX", ".Rx", 1);
+        assert!(verify_compact(&scheme, &tampered, &pk).is_err());
+        // alg none
+        let unsigned = format!(
+            "{}.{}.",
+            base64::encode_url(br#"{"alg":"none"}"#),
+            base64::encode_url(b"payload")
+        );
+        assert!(verify_compact(&scheme, &unsigned, &pk).is_err());
+        assert!(verify_compact(&scheme, "a.b", &pk).is_err());
+    }
+
+    #[cfg(any(
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    ))]
+    #[test]
+    fn es256k_jws() {
+        use signatures::secp256k1::EcdsaSecp256k1Sha256;
+
+        let scheme = EcdsaSecp256k1Sha256::new();
+        let (pk, sk) = scheme.keypair(None).unwrap();
+        let jws = sign_compact(&scheme, b"{\"iss\":\"ursa\"}", &sk, None).unwrap();
+        assert!(jws.starts_with("eyJhbGciOiJFUzI1NksifQ."));
+        let (_, payload) = verify_compact(&scheme, &jws, &pk).unwrap();
+        assert_eq!(b"{\"iss\":\"ursa\"}", &payload[..]);
+
+        let (other, _) = scheme.keypair(None).unwrap();
+        assert!(verify_compact(&scheme, &jws, &other).is_err());
+
+        #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
+        {
+            use signatures::ed25519::Ed25519Sha512;
+            let (ed_pk, _) = Ed25519Sha512::new().keypair(None).unwrap();
+            assert!(verify_compact(&Ed25519Sha512::new(), &jws, &ed_pk).is_err());
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Messages that say what their bytes are.
+//!
+//! `SignatureScheme::sign` takes any bytes, so nothing stops a signature
+//! made for one protocol from being replayed in another that happens to
+//! accept the same bytes. Signing a `DomainSeparated` message binds the
+//! signature to a domain tag instead, and a `Prehashed` message can only be
+//! passed to the prehash variant of a scheme, never mistaken for a raw
+//! message.
+
+use alloc::vec::Vec;
+#[cfg(feature = "sha2")]
+use sha2::Digest;
+
+use CryptoError;
+
+/// The longest domain tag of a `DomainSeparated` message
+pub const MAX_DOMAIN_SIZE: usize = 255;
+
+/// A message tagged with the domain, such as a protocol name and version,
+/// it is signed for.
+///
+/// The signed bytes are the domain length as a byte, the domain and then
+/// the message, so no two domains produce the same input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DomainSeparated<'a> {
+    domain: &'a [u8],
+    message: &'a [u8],
+}
+
+impl<'a> DomainSeparated<'a> {
+    /// Fails if `domain` is empty or longer than `MAX_DOMAIN_SIZE`
+    pub fn new(domain: &'a [u8], message: &'a [u8]) -> Result<Self, CryptoError> {
+        if domain.is_empty() || domain.len() > MAX_DOMAIN_SIZE {
+            return Err(CryptoError::GeneralError(format!(
+                "The domain must be 1 to {} bytes, found {}",
+                MAX_DOMAIN_SIZE,
+                domain.len()
+            )));
+        }
+        Ok(DomainSeparated { domain, message })
+    }
+
+    pub fn domain(&self) -> &'a [u8] {
+        self.domain
+    }
+
+    pub fn message(&self) -> &'a [u8] {
+        self.message
+    }
+
+    /// The bytes that are signed
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + self.domain.len() + self.message.len());
+        bytes.push(self.domain.len() as u8);
+        bytes.extend_from_slice(self.domain);
+        bytes.extend_from_slice(self.message);
+        bytes
+    }
+
+    /// Hash the message with its domain for a prehash scheme
+    #[cfg(feature = "sha2")]
+    pub fn prehash<H: Digest>(&self) -> Prehashed<H> {
+        let mut hasher = H::new();
+        hasher.input([self.domain.len() as u8]);
+        hasher.input(self.domain);
+        hasher.input(self.message);
+        Prehashed(hasher)
+    }
+}
+
+/// A message already input to the hash function of a prehash scheme,
+/// see `PrehashSignatureScheme::sign_digest`
+#[cfg(feature = "sha2")]
+#[derive(Clone, Debug)]
+pub struct Prehashed<H: Digest>(H);
+
+#[cfg(feature = "sha2")]
+impl<H: Digest> Prehashed<H> {
+    /// A message that was input to `hasher`
+    pub fn new(hasher: H) -> Self {
+        Prehashed(hasher)
+    }
+
+    pub fn into_hasher(self) -> H {
+        self.0
+    }
+}
+
+#[cfg(all(test, any(feature = "ed25519", feature = "ed25519_asm")))]
+mod tests {
+    use super::super::{ed25519::Ed25519Sha512, PrehashSignatureScheme, SignatureScheme};
+    use super::*;
+
+    #[test]
+    fn domain_separation() {
+        let scheme = Ed25519Sha512::new();
+        let (pk, sk) = scheme.keypair(None).unwrap();
+
+        let transfer = DomainSeparated::new(b"transfer/v1", b"pay 10").unwrap();
+        let signature = scheme.sign_domain_separated(transfer, &sk).unwrap();
+        assert!(scheme
+            .verify_domain_separated(transfer, &signature, &pk)
+            .unwrap());
+        assert!(scheme
+            .verify(&transfer.to_bytes(), &signature, &pk)
+            .unwrap());
+
+        let vote = DomainSeparated::new(b"vote/v1", b"pay 10").unwrap();
+        assert!(scheme
+            .verify_domain_separated(vote, &signature, &pk)
+            .is_err());
+        assert!(scheme.verify(b"pay 10", &signature, &pk).is_err());
+        // The length prefix keeps the domain and message apart
+        let shifted = DomainSeparated::new(b"transfer/v", b"1pay 10").unwrap();
+        assert_ne!(shifted.to_bytes(), transfer.to_bytes());
+
+        assert!(DomainSeparated::new(b"", b"pay 10").is_err());
+        assert!(DomainSeparated::new(&[0u8; 256], b"pay 10").is_err());
+
+        let signature = scheme.sign_digest(transfer.prehash(), &sk).unwrap();
+        assert!(scheme
+            .verify_digest(transfer.prehash(), &signature, &pk)
+            .unwrap());
+        assert!(scheme
+            .verify_digest(vote.prehash(), &signature, &pk)
+            .is_err());
+    }
+}
@@ -1,27 +1,97 @@
+#[cfg(feature = "async")]
+pub mod batch;
 #[cfg(feature = "bls_bls12381")]
 pub mod bls;
+#[cfg(feature = "bls_ietf")]
+pub mod bls_ietf;
+#[cfg(all(feature = "std", feature = "sha2"))]
+pub mod cache;
+#[cfg(all(
+    feature = "cose",
+    any(
+        feature = "ed25519",
+        feature = "ed25519_asm",
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    )
+))]
+pub mod cose;
 #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
 pub mod ed25519;
+#[cfg(all(
+    feature = "jose",
+    any(
+        feature = "ed25519",
+        feature = "ed25519_asm",
+        feature = "ecdsa_secp256k1",
+        feature = "ecdsa_secp256k1_native",
+        feature = "ecdsa_secp256k1_asm"
+    )
+))]
+pub mod jws;
+pub mod message;
 #[cfg(any(
     feature = "ecdsa_secp256k1",
     feature = "ecdsa_secp256k1_native",
     feature = "ecdsa_secp256k1_asm"
 ))]
 pub mod secp256k1;
+#[cfg(feature = "timelock")]
+pub mod timelock;
 
 pub mod prelude {
+    #[cfg(all(feature = "std", feature = "sha2"))]
+    pub use super::cache::{CachedVerifier, LruCache, VerificationCache};
     #[cfg(any(feature = "ed25519", feature = "ed25519_asm"))]
-    pub use super::ed25519::Ed25519Sha512;
+    pub use super::ed25519::{Ed25519Sha512, Ed25519SigningContext};
+    #[cfg(feature = "async")]
+    pub use super::{batch::BatchVerifier, verify_batch_async};
+    pub use super::{
+        message::DomainSeparated, verify_batch, BatchVerify, SignatureScheme, Signer,
+        VerificationError,
+    };
+    #[cfg(feature = "sha2")]
+    pub use super::{message::Prehashed, PrehashSignatureScheme};
     #[cfg(any(
         feature = "ecdsa_secp256k1",
         feature = "ecdsa_secp256k1_native",
         feature = "ecdsa_secp256k1_asm"
     ))]
-    pub use super::{secp256k1::EcdsaSecp256k1Sha256, EcdsaPublicKeyHandler};
-    pub use super::{SignatureScheme, Signer};
+    pub use super::{
+        secp256k1::{EcdsaSecp256k1Sha256, EcdsaSigningContext},
+        EcdsaPublicKeyHandler,
+    };
+    #[cfg(feature = "mmap")]
+    pub use super::{sign_file, verify_file};
+    #[cfg(feature = "jose")]
+    pub use super::{sign_json, verify_json};
 }
 
-use keys::{KeyGenOption, PrivateKey, PublicKey};
+use self::message::DomainSeparated;
+#[cfg(feature = "sha2")]
+use self::message::Prehashed;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "jose")]
+use encoding::jcs;
+use keys::{KeyGenOption, PrivateKey, PublicKey, KEYGEN_SEED_SIZE};
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+use rand::{CryptoRng, RngCore};
+#[cfg(feature = "sha2")]
+use sha2::Digest;
+use std::fmt;
+#[cfg(feature = "mmap")]
+use std::fs::File;
+#[cfg(all(feature = "sha2", feature = "std"))]
+use std::io::{ErrorKind, Read};
+#[cfg(feature = "mmap")]
+use std::path::Path;
+#[cfg(feature = "async")]
+use ursa_core::blocking::{self, Blocking};
+use ursa_core::error::codes;
+use ursa_core::parallel::{self, MaybeSync};
 use CryptoError;
 
 pub trait SignatureScheme {
@@ -30,14 +100,232 @@ pub trait SignatureScheme {
         &self,
         options: Option<KeyGenOption>,
     ) -> Result<(PublicKey, PrivateKey), CryptoError>;
+    /// Generate a keypair from a seed drawn from `rng` instead of the `OsRng`
+    fn keypair_with_rng<R: CryptoRng + RngCore>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(PublicKey, PrivateKey), CryptoError> {
+        let mut seed = vec![0u8; KEYGEN_SEED_SIZE];
+        rng.fill_bytes(&mut seed);
+        self.keypair(Some(KeyGenOption::UseSeed(seed)))
+    }
     fn sign(&self, message: &[u8], sk: &PrivateKey) -> Result<Vec<u8>, CryptoError>;
     fn verify(&self, message: &[u8], signature: &[u8], pk: &PublicKey)
         -> Result<bool, CryptoError>;
+    /// `verify` that tells a malformed signature or an invalid key apart
+    /// from a signature that doesn't match. Schemes whose `verify` can't
+    /// tell them apart report every error as `Malformed`.
+    fn verify_strict(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<(), VerificationError> {
+        match self.verify(message, signature, pk) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(VerificationError::Mismatch),
+            Err(e) => Err(VerificationError::Malformed(e.to_string())),
+        }
+    }
+    /// Sign `message` bound to its domain
+    fn sign_domain_separated(
+        &self,
+        message: DomainSeparated,
+        sk: &PrivateKey,
+    ) -> Result<Vec<u8>, CryptoError> {
+        self.sign(&message.to_bytes(), sk)
+    }
+    /// Verify a signature from `sign_domain_separated`, which only
+    /// verifies in the domain it was made for
+    fn verify_domain_separated(
+        &self,
+        message: DomainSeparated,
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<bool, CryptoError> {
+        self.verify(&message.to_bytes(), signature, pk)
+    }
     fn signature_size() -> usize;
     fn private_key_size() -> usize;
     fn public_key_size() -> usize;
 }
 
+/// Why `verify_strict` rejected a signature
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerificationError {
+    /// The signature or message couldn't be decoded
+    Malformed(String),
+    /// The public key couldn't be decoded or can't be used for verifying
+    InvalidKey(String),
+    /// The signature is well formed but wasn't made over the message with
+    /// the key
+    Mismatch,
+    /// The same message was given twice to a check that needs distinct
+    /// messages
+    DuplicateMessage,
+    /// No messages or keys were given, which nothing should verify against
+    EmptyInput,
+}
+
+impl VerificationError {
+    /// The stable code of this kind of error from `ursa_core::error::codes`
+    pub fn code(&self) -> i32 {
+        match self {
+            VerificationError::Malformed(_) => codes::MALFORMED_SIGNATURE,
+            VerificationError::InvalidKey(_) => codes::INVALID_PUBLIC_KEY,
+            VerificationError::Mismatch => codes::SIGNATURE_MISMATCH,
+            VerificationError::DuplicateMessage => codes::DUPLICATE_MESSAGE,
+            VerificationError::EmptyInput => codes::EMPTY_INPUT,
+        }
+    }
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerificationError::Malformed(s) => write!(f, "Malformed signature: {}", s),
+            VerificationError::InvalidKey(s) => write!(f, "Invalid public key: {}", s),
+            VerificationError::Mismatch => write!(f, "Signature mismatch"),
+            VerificationError::DuplicateMessage => write!(f, "A message was repeated"),
+            VerificationError::EmptyInput => write!(f, "Nothing to verify against"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerificationError {}
+
+impl From<VerificationError> for ursa_core::UrsaError {
+    fn from(error: VerificationError) -> Self {
+        let kind = match error {
+            VerificationError::Malformed(_) | VerificationError::InvalidKey(_) => {
+                ursa_core::ErrorKind::Parse
+            }
+            VerificationError::Mismatch
+            | VerificationError::DuplicateMessage
+            | VerificationError::EmptyInput => ursa_core::ErrorKind::Verification,
+        };
+        ursa_core::UrsaError::with_source(kind, error.to_string(), error)
+    }
+}
+
+/// A signature scheme that signs a hash of the message, so a message can be
+/// hashed while it is read instead of being loaded into memory first.
+///
+/// The `_reader` methods read until the end of the input in fixed size
+/// chunks, which suits multi-gigabyte files and network streams.
+#[cfg(feature = "sha2")]
+pub trait PrehashSignatureScheme: SignatureScheme {
+    /// The hash function the message is input to
+    type Hasher: Digest;
+
+    /// Sign the message that was input to `hasher`
+    fn sign_prehashed(&self, hasher: Self::Hasher, sk: &PrivateKey)
+        -> Result<Vec<u8>, CryptoError>;
+    /// Verify a signature from `sign_prehashed` over the message that was
+    /// input to `hasher`
+    fn verify_prehashed(
+        &self,
+        hasher: Self::Hasher,
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<bool, CryptoError>;
+
+    /// `sign_prehashed` for a message that is known to be hashed already
+    fn sign_digest(
+        &self,
+        message: Prehashed<Self::Hasher>,
+        sk: &PrivateKey,
+    ) -> Result<Vec<u8>, CryptoError> {
+        self.sign_prehashed(message.into_hasher(), sk)
+    }
+
+    /// Verify a signature from `sign_digest`
+    fn verify_digest(
+        &self,
+        message: Prehashed<Self::Hasher>,
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<bool, CryptoError> {
+        self.verify_prehashed(message.into_hasher(), signature, pk)
+    }
+
+    /// Sign everything read from `reader`
+    #[cfg(feature = "std")]
+    fn sign_reader<R: Read>(&self, reader: R, sk: &PrivateKey) -> Result<Vec<u8>, CryptoError> {
+        self.sign_prehashed(hash_reader(reader)?, sk)
+    }
+
+    /// Verify a signature over everything read from `reader`
+    #[cfg(feature = "std")]
+    fn verify_reader<R: Read>(
+        &self,
+        reader: R,
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<bool, CryptoError> {
+        self.verify_prehashed(hash_reader(reader)?, signature, pk)
+    }
+}
+
+/// Size of the chunks `PrehashSignatureScheme` reads messages in
+#[cfg(all(feature = "sha2", feature = "std"))]
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+#[cfg(all(feature = "sha2", feature = "std"))]
+fn hash_reader<D: Digest, R: Read>(mut reader: R) -> Result<D, CryptoError> {
+    let mut hasher = D::new();
+    let mut chunk = vec![0u8; READ_CHUNK_SIZE];
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => return Ok(hasher),
+            Ok(n) => hasher.input(&chunk[..n]),
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(CryptoError::DigestGenError(format!("Read error: {}", e))),
+        }
+    }
+}
+
+/// Sign the contents of the file at `path` by memory mapping it.
+///
+/// The file must not be modified while it is signed, or the signature may
+/// cover a mix of old and new contents.
+#[cfg(feature = "mmap")]
+pub fn sign_file<S: PrehashSignatureScheme, P: AsRef<Path>>(
+    scheme: &S,
+    path: P,
+    sk: &PrivateKey,
+) -> Result<Vec<u8>, CryptoError> {
+    scheme.sign_prehashed(hash_file(path.as_ref())?, sk)
+}
+
+/// Verify a signature over the contents of the file at `path`
+/// by memory mapping it
+#[cfg(feature = "mmap")]
+pub fn verify_file<S: PrehashSignatureScheme, P: AsRef<Path>>(
+    scheme: &S,
+    path: P,
+    signature: &[u8],
+    pk: &PublicKey,
+) -> Result<bool, CryptoError> {
+    scheme.verify_prehashed(hash_file(path.as_ref())?, signature, pk)
+}
+
+#[cfg(feature = "mmap")]
+fn hash_file<D: Digest>(path: &Path) -> Result<D, CryptoError> {
+    let io_error = |e: std::io::Error| {
+        CryptoError::DigestGenError(format!("Can't read {}: {}", path.display(), e))
+    };
+    let file = File::open(path).map_err(io_error)?;
+    // Empty files can't be mapped
+    if file.metadata().map_err(io_error)?.len() == 0 {
+        return Ok(D::new());
+    }
+    // The map is only read, and the caller must not modify the file meanwhile
+    let map = unsafe { Mmap::map(&file) }.map_err(io_error)?;
+    Ok(D::new().chain(&map[..]))
+}
+
 pub struct Signer<'a, 'b, T: 'a + SignatureScheme> {
     scheme: &'a T,
     key: &'b PrivateKey,
@@ -67,6 +355,12 @@ impl<'a, 'b, T: 'a + SignatureScheme> Signer<'a, 'b, T> {
         self.scheme.sign(message, self.key)
     }
 
+    /// Signs the canonical form of a JSON value, see [`sign_json`]
+    #[cfg(feature = "jose")]
+    pub fn sign_json(&self, value: &serde_json::Value) -> Result<Vec<u8>, CryptoError> {
+        sign_json(self.scheme, value, self.key)
+    }
+
     /// Return the public key for this Signer instance.
     ///
     /// # Returns
@@ -82,6 +376,79 @@ impl<'a, 'b, T: 'a + SignatureScheme> Signer<'a, 'b, T> {
     }
 }
 
+/// Sign the RFC 8785 canonical form of `value`.
+///
+/// The signature covers the data rather than its formatting, so it still
+/// verifies after the JSON has been reserialized with a different key order,
+/// whitespace or number format.
+#[cfg(feature = "jose")]
+pub fn sign_json<S: SignatureScheme>(
+    scheme: &S,
+    value: &serde_json::Value,
+    sk: &PrivateKey,
+) -> Result<Vec<u8>, CryptoError> {
+    scheme.sign(jcs::canonicalize(value)?.as_bytes(), sk)
+}
+
+/// Verify a signature from [`sign_json`]
+#[cfg(feature = "jose")]
+pub fn verify_json<S: SignatureScheme>(
+    scheme: &S,
+    value: &serde_json::Value,
+    signature: &[u8],
+    pk: &PublicKey,
+) -> Result<bool, CryptoError> {
+    scheme.verify(jcs::canonicalize(value)?.as_bytes(), signature, pk)
+}
+
+/// Verify many `(message, signature, public key)` triples.
+///
+/// Returns `Ok(true)` if every signature verifies, otherwise what `verify`
+/// returned for one that doesn't. The signatures are checked in parallel
+/// with the `parallel` feature.
+pub fn verify_batch<S: SignatureScheme + MaybeSync>(
+    scheme: &S,
+    inputs: &[(&[u8], &[u8], &PublicKey)],
+) -> Result<bool, CryptoError> {
+    let results = parallel::try_map(inputs, |&(message, signature, pk)| {
+        scheme.verify(message, signature, pk)
+    })?;
+    Ok(results.into_iter().all(|valid| valid))
+}
+
+/// `verify_batch` on another thread, for callers on an async executor.
+///
+/// Dropping the returned future stops the verification between signatures,
+/// when it hasn't finished yet.
+#[cfg(feature = "async")]
+pub fn verify_batch_async<S: SignatureScheme + Send + Sync + 'static>(
+    scheme: S,
+    inputs: Vec<(Vec<u8>, Vec<u8>, PublicKey)>,
+) -> Blocking<Result<bool, CryptoError>> {
+    blocking::spawn_cancellable(move |token| {
+        let results = parallel::try_map(&inputs, |(message, signature, pk)| {
+            if token.is_cancelled() {
+                return Err(CryptoError::GeneralError(
+                    "Batch verification cancelled".to_string(),
+                ));
+            }
+            scheme.verify(message, signature, pk)
+        })?;
+        Ok(results.into_iter().all(|valid| valid))
+    })
+}
+
+/// A signature scheme that can check many signatures together.
+///
+/// Schemes with a real batch check, like BLS, override `batch_verify`.
+/// The default verifies each signature with `verify_batch`.
+pub trait BatchVerify: SignatureScheme + MaybeSync + Sized {
+    /// True if every signature verifies. False doesn't tell which failed.
+    fn batch_verify(&self, inputs: &[(&[u8], &[u8], &PublicKey)]) -> bool {
+        verify_batch(self, inputs).unwrap_or(false)
+    }
+}
+
 #[cfg(any(
     feature = "ecdsa_secp256k1",
     feature = "ecdsa_secp256k1_native",
@@ -1,4 +1,6 @@
 use super::*;
+use encoding::der::{self, DerReader};
+use encoding::pkcs8::{KeyAlgorithm, Pkcs8KeyHandler};
 use sha2::digest::generic_array::typenum::U32;
 use CryptoError;
 
@@ -43,6 +45,28 @@ impl SignatureScheme for EcdsaSecp256k1Sha256 {
     ) -> Result<bool, CryptoError> {
         self.0.verify::<sha2::Sha256>(message, signature, pk)
     }
+    fn verify_strict(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<(), VerificationError> {
+        if signature.len() != SIGNATURE_SIZE {
+            return Err(VerificationError::Malformed(format!(
+                "Expected a {} byte signature, found {}",
+                SIGNATURE_SIZE,
+                signature.len()
+            )));
+        }
+        self.0
+            .check_public_key(pk)
+            .map_err(|e| VerificationError::InvalidKey(e.to_string()))?;
+        match self.verify(message, signature, pk) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(VerificationError::Mismatch),
+            Err(e) => Err(VerificationError::Malformed(e.to_string())),
+        }
+    }
     fn signature_size() -> usize {
         SIGNATURE_SIZE
     }
@@ -54,6 +78,287 @@ impl SignatureScheme for EcdsaSecp256k1Sha256 {
     }
 }
 
+impl BatchVerify for EcdsaSecp256k1Sha256 {}
+
+/// Options for signing with `EcdsaSecp256k1Sha256`.
+///
+/// Nonces are derived with RFC 6979. `extra_entropy` mixes 32 bytes into the
+/// derivation as its additional data, which keeps signing deterministic for
+/// a given input but protects against fault attacks when the bytes are
+/// random. Signatures are always made with a low `s`, `low_s(false)` also
+/// accepts signatures with a high `s` when verifying.
+///
+/// # Example
+/// ```
+/// use ursa::signatures::secp256k1::{EcdsaSecp256k1Sha256, EcdsaSigningContext};
+/// use ursa::signatures::SignatureScheme;
+///
+/// let (pk, sk) = EcdsaSecp256k1Sha256::new().keypair(None).unwrap();
+/// let context = EcdsaSigningContext::new().extra_entropy([7u8; 32]);
+/// let signature = context.sign(b"message", &sk).unwrap();
+/// assert!(context.verify(b"message", &signature, &pk).unwrap());
+/// ```
+pub struct EcdsaSigningContext {
+    scheme: EcdsaSecp256k1Sha256,
+    low_s: bool,
+    extra_entropy: Option<[u8; 32]>,
+}
+
+impl EcdsaSigningContext {
+    pub fn new() -> Self {
+        EcdsaSigningContext {
+            scheme: EcdsaSecp256k1Sha256::new(),
+            low_s: true,
+            extra_entropy: None,
+        }
+    }
+
+    /// Reject signatures with a high `s` when verifying, the default
+    pub fn low_s(mut self, low_s: bool) -> Self {
+        self.low_s = low_s;
+        self
+    }
+
+    /// Additional data for the RFC 6979 nonce derivation
+    pub fn extra_entropy(mut self, extra_entropy: [u8; 32]) -> Self {
+        self.extra_entropy = Some(extra_entropy);
+        self
+    }
+
+    pub fn sign(&self, message: &[u8], sk: &PrivateKey) -> Result<Vec<u8>, CryptoError> {
+        let hash = sha2::Sha256::digest(message);
+        match &self.extra_entropy {
+            Some(extra_entropy) => {
+                self.scheme
+                    .0
+                    .sign_hash_with_entropy(hash.as_slice(), sk, extra_entropy)
+            }
+            None => self.scheme.0.sign_hash(hash.as_slice(), sk),
+        }
+    }
+
+    pub fn verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<bool, CryptoError> {
+        if signature.len() != SIGNATURE_SIZE {
+            return Err(CryptoError::ParseError(format!(
+                "Expected a {} byte signature, found {}",
+                SIGNATURE_SIZE,
+                signature.len()
+            )));
+        }
+        let mut normalized = signature.to_vec();
+        self.scheme.normalize_s(&mut normalized)?;
+        if self.low_s && normalized != signature {
+            return Ok(false);
+        }
+        self.scheme.verify(message, &normalized, pk)
+    }
+}
+
+impl Default for EcdsaSigningContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The order of the secp256k1 group
+const ORDER: [u8; SIGNATURE_POINT_SIZE] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+/// The largest low `s`, half the group order rounded down
+const HALF_ORDER: [u8; SIGNATURE_POINT_SIZE] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// The checks made when parsing a signature.
+///
+/// `STRICT` follows BIP-66 and X.509, which need canonical DER. Signatures
+/// from older Bitcoin transactions and some TLS stacks can only be read with
+/// `LAX`, which accepts the same encodings as libsecp256k1's lax DER parser.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SignatureParseFlags {
+    /// Require DER as strict as BIP-66: minimal lengths and integers, no
+    /// negative integers and no trailing data
+    pub strict_der: bool,
+    /// Reject signatures with `s` above half the group order, which BIP-146
+    /// requires to stop signatures being altered
+    pub low_s: bool,
+}
+
+impl SignatureParseFlags {
+    pub const STRICT: SignatureParseFlags = SignatureParseFlags {
+        strict_der: true,
+        low_s: false,
+    };
+    pub const LAX: SignatureParseFlags = SignatureParseFlags {
+        strict_der: false,
+        low_s: false,
+    };
+}
+
+impl Default for SignatureParseFlags {
+    fn default() -> Self {
+        Self::STRICT
+    }
+}
+
+/// Check a 64 byte compact signature, `r` followed by `s`, for the range
+/// of its scalars and `flags.low_s`
+pub fn check_compact_signature(
+    signature: &[u8],
+    flags: SignatureParseFlags,
+) -> Result<(), CryptoError> {
+    if signature.len() != SIGNATURE_SIZE {
+        return Err(CryptoError::ParseError(format!(
+            "Expected a {} byte signature, found {}",
+            SIGNATURE_SIZE,
+            signature.len()
+        )));
+    }
+    for scalar in signature.chunks(SIGNATURE_POINT_SIZE) {
+        if scalar.iter().all(|b| *b == 0) || scalar >= &ORDER[..] {
+            return Err(CryptoError::ParseError(
+                "Signature scalars must be between 1 and the group order".to_string(),
+            ));
+        }
+    }
+    if flags.low_s && signature[SIGNATURE_POINT_SIZE..] > HALF_ORDER[..] {
+        return Err(CryptoError::ParseError(
+            "Signature does not have a low s".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// DER encode a compact signature as an `Ecdsa-Sig-Value`
+pub fn compact_signature_to_der(signature: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    check_compact_signature(signature, SignatureParseFlags::LAX)?;
+    Ok(der::sequence(&[
+        &der::integer(&signature[..SIGNATURE_POINT_SIZE]),
+        &der::integer(&signature[SIGNATURE_POINT_SIZE..]),
+    ]))
+}
+
+/// Parse a DER encoded `Ecdsa-Sig-Value` into a compact signature
+pub fn der_signature_to_compact(
+    signature: &[u8],
+    flags: SignatureParseFlags,
+) -> Result<Vec<u8>, CryptoError> {
+    let (r, s) = if flags.strict_der {
+        let mut reader = DerReader::new(signature);
+        let mut fields = reader.read_sequence()?;
+        reader.finish()?;
+        let r = fields.read_integer()?;
+        let s = fields.read_integer()?;
+        fields.finish()?;
+        (r, s)
+    } else {
+        lax_der_signature(signature)?
+    };
+    let mut compact = vec![0u8; SIGNATURE_SIZE];
+    for (half, value) in compact.chunks_mut(SIGNATURE_POINT_SIZE).zip(&[r, s]) {
+        let skip = value.iter().take_while(|b| **b == 0).count();
+        let value = &value[skip..];
+        if value.len() > SIGNATURE_POINT_SIZE {
+            return Err(CryptoError::ParseError(
+                "Signature scalar is too large".to_string(),
+            ));
+        }
+        half[SIGNATURE_POINT_SIZE - value.len()..].copy_from_slice(value);
+    }
+    check_compact_signature(&compact, flags)?;
+    Ok(compact)
+}
+
+/// The integers of a DER signature read like libsecp256k1's
+/// `ecdsa_signature_parse_der_lax`: lengths need not be minimal, integers
+/// may be padded or have the sign bit set and trailing data is ignored
+fn lax_der_signature(signature: &[u8]) -> Result<(&[u8], &[u8]), CryptoError> {
+    fn length(data: &mut &[u8]) -> Result<Option<usize>, CryptoError> {
+        let first = *data.first().ok_or_else(truncated)?;
+        *data = &data[1..];
+        if first & 0x80 == 0 {
+            return Ok(Some(first as usize));
+        }
+        let n = (first & 0x7f) as usize;
+        if data.len() < n {
+            return Err(truncated());
+        }
+        let bytes = &data[..n];
+        *data = &data[n..];
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        if n - skip > std::mem::size_of::<usize>() {
+            return Ok(None);
+        }
+        Ok(Some(
+            bytes[skip..]
+                .iter()
+                .fold(0usize, |acc, b| (acc << 8) | *b as usize),
+        ))
+    }
+    fn integer<'a>(data: &mut &'a [u8]) -> Result<&'a [u8], CryptoError> {
+        if data.first() != Some(&der::TAG_INTEGER) {
+            return Err(CryptoError::ParseError(
+                "Expected a DER integer".to_string(),
+            ));
+        }
+        *data = &data[1..];
+        match length(data)? {
+            Some(len) if len <= data.len() => {
+                let value = &data[..len];
+                *data = &data[len..];
+                Ok(value)
+            }
+            _ => Err(truncated()),
+        }
+    }
+    fn truncated() -> CryptoError {
+        CryptoError::ParseError("Truncated DER signature".to_string())
+    }
+
+    let mut data = signature;
+    if data.first() != Some(&der::TAG_SEQUENCE) {
+        return Err(CryptoError::ParseError(
+            "Expected a DER sequence".to_string(),
+        ));
+    }
+    data = &data[1..];
+    // The sequence length is not checked
+    length(&mut data)?;
+    let r = integer(&mut data)?;
+    let s = integer(&mut data)?;
+    Ok((r, s))
+}
+
+/// Signs the SHA-256 hash of the message, so `sign_reader` and `sign`
+/// produce the same signatures
+impl PrehashSignatureScheme for EcdsaSecp256k1Sha256 {
+    type Hasher = sha2::Sha256;
+
+    fn sign_prehashed(
+        &self,
+        hasher: Self::Hasher,
+        sk: &PrivateKey,
+    ) -> Result<Vec<u8>, CryptoError> {
+        self.0.sign_hash(hasher.result().as_slice(), sk)
+    }
+    fn verify_prehashed(
+        &self,
+        hasher: Self::Hasher,
+        signature: &[u8],
+        pk: &PublicKey,
+    ) -> Result<bool, CryptoError> {
+        self.0
+            .verify_hash(hasher.result().as_slice(), signature, pk)
+    }
+}
+
 impl EcdsaPublicKeyHandler for EcdsaSecp256k1Sha256 {
     /// Returns the compressed bytes
     fn public_key_compressed(&self, pk: &PublicKey) -> Vec<u8> {
@@ -72,6 +377,35 @@ impl EcdsaPublicKeyHandler for EcdsaSecp256k1Sha256 {
     }
 }
 
+impl Pkcs8KeyHandler for EcdsaSecp256k1Sha256 {
+    fn key_algorithm(&self) -> KeyAlgorithm {
+        KeyAlgorithm::EcSecp256k1
+    }
+    fn private_key_to_raw(&self, sk: &PrivateKey) -> Result<Vec<u8>, CryptoError> {
+        if sk.len() != PRIVATE_KEY_SIZE {
+            return Err(CryptoError::ParseError(
+                "Invalid private key provided".to_string(),
+            ));
+        }
+        Ok(sk[..].to_vec())
+    }
+    fn keypair_from_raw(&self, raw: &[u8]) -> Result<(PublicKey, PrivateKey), CryptoError> {
+        if raw.len() != PRIVATE_KEY_SIZE {
+            return Err(CryptoError::ParseError(
+                "Invalid private key provided".to_string(),
+            ));
+        }
+        self.keypair(Some(KeyGenOption::FromSecretKey(PrivateKey(raw.to_vec()))))
+    }
+    /// SubjectPublicKeyInfo holds the uncompressed point
+    fn public_key_to_raw(&self, pk: &PublicKey) -> Result<Vec<u8>, CryptoError> {
+        Ok(self.public_key_uncompressed(&self.parse(&pk[..])?))
+    }
+    fn public_key_from_raw(&self, raw: &[u8]) -> Result<PublicKey, CryptoError> {
+        self.parse(raw)
+    }
+}
+
 #[cfg(feature = "serde")]
 impl Serialize for EcdsaSecp256k1Sha256 {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -178,12 +512,46 @@ mod ecdsa_secp256k1 {
         where
             D: Digest<OutputSize = U32>,
         {
-            let h = D::digest(message);
-            let msg = libsecp256k1::Message::from_slice(h.as_slice())?;
+            self.sign_hash(D::digest(message).as_slice(), sk)
+        }
+        pub fn sign_hash(&self, h: &[u8], sk: &PrivateKey) -> Result<Vec<u8>, CryptoError> {
+            let msg = libsecp256k1::Message::from_slice(h)?;
             let s = libsecp256k1::key::SecretKey::from_slice(&sk[..])?;
             let sig = self.0.sign(&msg, &s);
             Ok(sig.serialize_compact().to_vec())
         }
+        pub fn sign_hash_with_entropy(
+            &self,
+            h: &[u8],
+            sk: &PrivateKey,
+            extra_entropy: &[u8; 32],
+        ) -> Result<Vec<u8>, CryptoError> {
+            use libsecp256k1::ffi::{self, CPtr};
+
+            let msg = libsecp256k1::Message::from_slice(h)?;
+            let s = libsecp256k1::key::SecretKey::from_slice(&sk[..])?;
+            let mut sig = ffi::Signature::new();
+            // The RFC 6979 nonce function takes the extra entropy as its
+            // additional data
+            let signed = unsafe {
+                ffi::secp256k1_ecdsa_sign(
+                    *self.0.ctx(),
+                    &mut sig,
+                    msg.as_c_ptr(),
+                    s.as_c_ptr(),
+                    ffi::secp256k1_nonce_function_rfc6979,
+                    extra_entropy.as_ptr() as *const ffi::types::c_void,
+                )
+            };
+            if signed != 1 {
+                return Err(CryptoError::SigningError(
+                    "Failed to sign the message".to_string(),
+                ));
+            }
+            Ok(libsecp256k1::Signature::from(sig)
+                .serialize_compact()
+                .to_vec())
+        }
         pub fn verify<D>(
             &self,
             message: &[u8],
@@ -193,8 +561,15 @@ mod ecdsa_secp256k1 {
         where
             D: Digest<OutputSize = U32>,
         {
-            let h = D::digest(message);
-            let msg = libsecp256k1::Message::from_slice(h.as_slice())?;
+            self.verify_hash(D::digest(message).as_slice(), signature, pk)
+        }
+        pub fn verify_hash(
+            &self,
+            h: &[u8],
+            signature: &[u8],
+            pk: &PublicKey,
+        ) -> Result<bool, CryptoError> {
+            let msg = libsecp256k1::Message::from_slice(h)?;
             let p = libsecp256k1::PublicKey::from_slice(&pk[..])?;
             let sig = libsecp256k1::Signature::from_compact(signature)?;
             let res = self.0.verify(&msg, &sig, &p);
@@ -204,6 +579,11 @@ mod ecdsa_secp256k1 {
                 Err(err) => Err(CryptoError::from(err)),
             }
         }
+        /// Fails unless `pk` is an encoded point on the curve
+        pub fn check_public_key(&self, pk: &PublicKey) -> Result<(), CryptoError> {
+            libsecp256k1::PublicKey::from_slice(&pk[..])?;
+            Ok(())
+        }
         pub fn normalize_s(&self, signature: &mut [u8]) -> Result<(), CryptoError> {
             let mut sig = libsecp256k1::Signature::from_compact(signature)?;
             sig.normalize_s();
@@ -292,15 +672,48 @@ mod ecdsa_secp256k1 {
         where
             D: Digest<OutputSize = U32>,
         {
-            let h = D::digest(message);
-            let msg =
-                rustlibsecp256k1::Message::parse(array_ref!(h.as_slice(), 0, SIGNATURE_POINT_SIZE));
+            self.sign_hash(D::digest(message).as_slice(), sk)
+        }
+        pub fn sign_hash(&self, h: &[u8], sk: &PrivateKey) -> Result<Vec<u8>, CryptoError> {
+            let msg = rustlibsecp256k1::Message::parse(array_ref!(h, 0, SIGNATURE_POINT_SIZE));
             let secret =
                 rustlibsecp256k1::SecretKey::parse(array_ref!(sk[..], 0, PRIVATE_KEY_SIZE))
                     .map_err(|e| CryptoError::SigningError(format!("{:?}", e)))?;
             let (sig, _) = rustlibsecp256k1::sign(&msg, &secret);
             Ok(sig.serialize().to_vec())
         }
+        pub fn sign_hash_with_entropy(
+            &self,
+            h: &[u8],
+            sk: &PrivateKey,
+            extra_entropy: &[u8; 32],
+        ) -> Result<Vec<u8>, CryptoError> {
+            use drbg::HmacSha256Drbg;
+            use rustlibsecp256k1::curve::{Scalar, ECMULT_GEN_CONTEXT};
+
+            let msg = rustlibsecp256k1::Message::parse(array_ref!(h, 0, SIGNATURE_POINT_SIZE));
+            let secret: Scalar =
+                rustlibsecp256k1::SecretKey::parse(array_ref!(sk[..], 0, PRIVATE_KEY_SIZE))
+                    .map_err(|e| CryptoError::SigningError(format!("{:?}", e)))?
+                    .into();
+            // RFC 6979 seeds HMAC-DRBG with the key, the hash and the
+            // additional data, like libsecp256k1 does
+            let mut drbg = HmacSha256Drbg::new(&secret.b32(), &msg.0.b32(), extra_entropy)?;
+            let mut k = [0u8; 32];
+            loop {
+                drbg.generate(&mut k, &[])?;
+                let mut nonce = Scalar::default();
+                let overflow = bool::from(nonce.set_b32(&k));
+                if !overflow && !nonce.is_zero() {
+                    if let Ok((r, s, _)) = ECMULT_GEN_CONTEXT.sign_raw(&secret, &msg.0, &nonce) {
+                        k.zeroize();
+                        nonce.clear();
+                        let sig = rustlibsecp256k1::Signature { r, s };
+                        return Ok(sig.serialize().to_vec());
+                    }
+                }
+            }
+        }
         pub fn verify<D>(
             &self,
             message: &[u8],
@@ -310,11 +723,17 @@ mod ecdsa_secp256k1 {
         where
             D: Digest<OutputSize = U32>,
         {
-            let h = D::digest(message);
+            self.verify_hash(D::digest(message).as_slice(), signature, pk)
+        }
+        pub fn verify_hash(
+            &self,
+            h: &[u8],
+            signature: &[u8],
+            pk: &PublicKey,
+        ) -> Result<bool, CryptoError> {
             let uncompressed_pk = self.public_key_uncompressed(&pk);
 
-            let msg =
-                rustlibsecp256k1::Message::parse(array_ref!(h.as_slice(), 0, SIGNATURE_POINT_SIZE));
+            let msg = rustlibsecp256k1::Message::parse(array_ref!(h, 0, SIGNATURE_POINT_SIZE));
             let sig = rustlibsecp256k1::Signature::parse(array_ref!(signature, 0, SIGNATURE_SIZE));
             let pk = rustlibsecp256k1::PublicKey::parse(array_ref!(
                 uncompressed_pk.as_slice(),
@@ -324,6 +743,12 @@ mod ecdsa_secp256k1 {
             .map_err(|e| CryptoError::SigningError(format!("{:?}", e)))?;
             Ok(rustlibsecp256k1::verify(&msg, &sig, &pk))
         }
+        /// Fails unless `pk` is an encoded point on the curve
+        pub fn check_public_key(&self, pk: &PublicKey) -> Result<(), CryptoError> {
+            rustlibsecp256k1::PublicKey::parse_slice(&pk[..], None)
+                .map_err(|e| CryptoError::ParseError(format!("{:?}", e)))?;
+            Ok(())
+        }
         pub fn normalize_s(&self, signature: &mut [u8]) -> Result<(), CryptoError> {
             let mut sig =
                 rustlibsecp256k1::Signature::parse(array_ref!(signature, 0, SIGNATURE_SIZE));
@@ -402,6 +827,50 @@ mod test {
         assert!(openssl_point.is_ok());
     }
 
+    #[test]
+    fn secp256k1_signing_context() {
+        let scheme = EcdsaSecp256k1Sha256::new();
+        let (p, s) = scheme
+            .keypair(Some(KeyGenOption::UseSeed(vec![3u8; 32])))
+            .unwrap();
+
+        let plain = EcdsaSigningContext::new();
+        let signature = plain.sign(&MESSAGE_1, &s).unwrap();
+        assert_eq!(signature, scheme.sign(&MESSAGE_1, &s).unwrap());
+        assert!(plain.verify(&MESSAGE_1, &signature, &p).unwrap());
+
+        let extra = EcdsaSigningContext::new().extra_entropy([9u8; 32]);
+        let extra_signature = extra.sign(&MESSAGE_1, &s).unwrap();
+        assert_ne!(extra_signature, signature);
+        assert_eq!(extra_signature, extra.sign(&MESSAGE_1, &s).unwrap());
+        assert!(plain.verify(&MESSAGE_1, &extra_signature, &p).unwrap());
+        assert!(!plain
+            .verify(b"another message", &extra_signature, &p)
+            .unwrap());
+        assert!(plain.verify(&MESSAGE_1, &signature[1..], &p).is_err());
+    }
+
+    #[test]
+    fn secp256k1_verify_strict() {
+        let scheme = EcdsaSecp256k1Sha256::new();
+        let p = PublicKey(hex::decode(PUBLIC_KEY).unwrap());
+        let signature = hex::decode(SIGNATURE_1).unwrap();
+
+        assert_eq!(scheme.verify_strict(&MESSAGE_1, &signature, &p), Ok(()));
+        assert_eq!(
+            scheme.verify_strict(b"another message", &signature, &p),
+            Err(VerificationError::Mismatch)
+        );
+        assert!(matches!(
+            scheme.verify_strict(&MESSAGE_1, &signature[1..], &p),
+            Err(VerificationError::Malformed(_))
+        ));
+        assert!(matches!(
+            scheme.verify_strict(&MESSAGE_1, &signature, &PublicKey(vec![1u8; 3])),
+            Err(VerificationError::InvalidKey(_))
+        ));
+    }
+
     #[test]
     fn secp256k1_verify() {
         let scheme = EcdsaSecp256k1Sha256::new();
@@ -442,6 +911,81 @@ mod test {
         assert!(openssl_result.unwrap());
     }
 
+    #[test]
+    fn secp256k1_der_signatures() {
+        let compact = hex::decode(SIGNATURE_1).unwrap();
+        let der = compact_signature_to_der(&compact).unwrap();
+        let (r, s) = SIGNATURE_1.split_at(SIGNATURE_1.len() / 2);
+        let openssl_sig = EcdsaSig::from_private_components(
+            BigNum::from_hex_str(r).unwrap(),
+            BigNum::from_hex_str(s).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(der, openssl_sig.to_der().unwrap());
+        // r has its high bit set so is padded with a zero
+        assert_eq!(der[..5], [0x30, 0x45, 0x02, 0x21, 0x00]);
+        let low_s = SignatureParseFlags {
+            low_s: true,
+            ..SignatureParseFlags::STRICT
+        };
+        for flags in &[SignatureParseFlags::STRICT, SignatureParseFlags::LAX, low_s] {
+            assert_eq!(der_signature_to_compact(&der, *flags).unwrap(), compact);
+        }
+
+        // Encodings BIP-66 forbids that the lax parser accepts
+        let (r, s) = (&der[5..37], &der[39..]);
+        let padded = [
+            &[0x30, 0x46, 0x02, 0x21, 0x00][..],
+            r,
+            &[0x02, 0x21, 0x00],
+            s,
+        ]
+        .concat();
+        let negative = [&[0x30, 0x44, 0x02, 0x20][..], r, &[0x02, 0x20], s].concat();
+        let long_length = [&[0x30, 0x81, 0x45][..], &der[2..]].concat();
+        let trailing = [&der[..], &[0][..]].concat();
+        for encoding in &[padded, negative, long_length, trailing] {
+            assert!(der_signature_to_compact(encoding, SignatureParseFlags::STRICT).is_err());
+            assert_eq!(
+                der_signature_to_compact(encoding, SignatureParseFlags::LAX).unwrap(),
+                compact
+            );
+        }
+        assert!(der_signature_to_compact(&der[..der.len() - 1], SignatureParseFlags::LAX).is_err());
+        let oversized = [&[0x30, 0x46, 0x02, 0x21, 0x01][..], r, &[0x02, 0x20], s].concat();
+        assert!(der_signature_to_compact(&oversized, SignatureParseFlags::LAX).is_err());
+
+        // s is the group order minus one
+        let mut high = [0u8; SIGNATURE_SIZE];
+        high[31] = 1;
+        high[32..].copy_from_slice(&ORDER);
+        high[63] -= 1;
+        assert!(check_compact_signature(&high, SignatureParseFlags::STRICT).is_ok());
+        assert!(check_compact_signature(&high, low_s).is_err());
+        let der_high = compact_signature_to_der(&high).unwrap();
+        assert!(der_signature_to_compact(&der_high, SignatureParseFlags::STRICT).is_ok());
+        assert!(der_signature_to_compact(&der_high, low_s).is_err());
+        high[32..].copy_from_slice(&HALF_ORDER);
+        assert!(check_compact_signature(&high, low_s).is_ok());
+        high[63] += 1;
+        assert!(check_compact_signature(&high, low_s).is_err());
+
+        high[32..].copy_from_slice(&ORDER);
+        assert!(compact_signature_to_der(&high).is_err());
+        high[32..].copy_from_slice(&HALF_ORDER);
+        high[31] = 0;
+        assert!(compact_signature_to_der(&high).is_err());
+        assert!(compact_signature_to_der(&compact[1..]).is_err());
+
+        let scheme = EcdsaSecp256k1Sha256::new();
+        let (pk, sk) = scheme.keypair(None).unwrap();
+        let signature = scheme.sign(MESSAGE_1, &sk).unwrap();
+        let parsed =
+            der_signature_to_compact(&compact_signature_to_der(&signature).unwrap(), low_s)
+                .unwrap();
+        assert!(scheme.verify(MESSAGE_1, &parsed, &pk).unwrap());
+    }
+
     #[test]
     fn secp256k1_sign() {
         let scheme = EcdsaSecp256k1Sha256::new();
@@ -530,6 +1074,21 @@ mod test {
         }
     }
 
+    #[test]
+    fn secp256k1_sign_reader() {
+        let scheme = EcdsaSecp256k1Sha256::new();
+        let secret = PrivateKey(hex::decode(PRIVATE_KEY).unwrap());
+        let (p, s) = scheme
+            .keypair(Some(KeyGenOption::FromSecretKey(secret)))
+            .unwrap();
+
+        let sig = scheme.sign_reader(MESSAGE_1, &s).unwrap();
+        assert_eq!(sig, scheme.sign(MESSAGE_1, &s).unwrap());
+        assert!(scheme.verify_reader(MESSAGE_1, &sig, &p).unwrap());
+        assert!(scheme.verify(MESSAGE_1, &sig, &p).unwrap());
+        assert!(!scheme.verify_reader(&b"wrong"[..], &sig, &p).unwrap());
+    }
+
     #[test]
     fn secp256k1_publickey_compression() {
         let scheme = EcdsaSecp256k1Sha256::new();
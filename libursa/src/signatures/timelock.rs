@@ -0,0 +1,577 @@
+//! Timelock encryption to future rounds of a drand beacon
+//! (<https://drand.love>).
+//!
+//! A drand network signs every round with a threshold BLS key. Unchained
+//! networks sign the SHA-256 hash of the round number alone, so nobody knows
+//! the signature of a round before it comes and anyone can fetch it after.
+//! Boneh-Franklin identity based encryption with that hash as the identity
+//! makes the round's signature the decryption key, and the Fujisaki-Okamoto
+//! transform keeps ciphertexts from being altered. This is the construction
+//! of drand's tlock.
+//!
+//! Rounds are hashed to the curve with the RFC 9380 suites and the domain
+//! separation tags drand signs with, and points use the compressed encodings,
+//! so the public key and signatures a drand network publishes are used as
+//! they are. They are verified with [`bls_ietf`](../bls_ietf/index.html).
+//!
+//! At most `MAX_MESSAGE_SIZE` bytes can be encrypted, enough for a key to
+//! encrypt the rest with an AEAD.
+//!
+//! ```
+//! use ursa::signatures::bls_ietf::{min_sig, PrivateKey, Scheme as BlsScheme};
+//! use ursa::signatures::timelock::{round_message, Chain, Scheme};
+//!
+//! // A stand in for the quicknet key and its signature of round 1000
+//! let sk = PrivateKey::key_gen(&[7u8; 32], b"").unwrap();
+//! let pk = min_sig::PublicKey::new(&sk).to_bytes();
+//! let chain = Chain::new(Scheme::BlsUnchainedG1Rfc9380, &pk).unwrap();
+//! let ciphertext = chain.encrypt(1000, b"a 16 byte secret").unwrap();
+//!
+//! let signature = min_sig::sign(BlsScheme::Basic, &sk, &round_message(1000));
+//! let message = chain.decrypt(&ciphertext, &signature.to_bytes()).unwrap();
+//! assert_eq!(message, b"a 16 byte secret");
+//! ```
+
+use blst::{
+    blst_bendian_from_fp, blst_fp12, blst_hash_to_g1, blst_hash_to_g2, blst_p1, blst_p1_affine,
+    blst_p1_affine_compress, blst_p1_generator, blst_p1_mult, blst_p1_to_affine,
+    blst_p1_uncompress, blst_p2, blst_p2_affine, blst_p2_affine_compress, blst_p2_generator,
+    blst_p2_mult, blst_p2_to_affine, blst_p2_uncompress, blst_scalar, blst_scalar_fr_check,
+    blst_scalar_from_bendian, BLST_ERROR,
+};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use signatures::bls_ietf::{min_pk, min_sig};
+use zeroize::Zeroize;
+
+use CryptoError;
+
+/// The most bytes that can be encrypted to a round
+pub const MAX_MESSAGE_SIZE: usize = 32;
+
+const H2_DST: &[u8] = b"IBE-H2";
+const H3_DST: &[u8] = b"IBE-H3";
+const H4_DST: &[u8] = b"IBE-H4";
+
+/// How a drand network signs its rounds, named after the `schemeID` of its
+/// chain info
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    /// `pedersen-bls-chained`: keys in G1 and signatures in G2 over the
+    /// previous round's signature and the round. These rounds cannot be
+    /// encrypted to.
+    PedersenBlsChained,
+    /// `pedersen-bls-unchained`: keys in G1 and signatures in G2 over the
+    /// round
+    PedersenBlsUnchained,
+    /// `bls-unchained-g1-rfc9380`, used by quicknet: keys in G2 and
+    /// signatures in G1 over the round
+    BlsUnchainedG1Rfc9380,
+}
+
+impl Scheme {
+    /// The `schemeID` of the scheme's chains
+    pub fn id(self) -> &'static str {
+        match self {
+            Scheme::PedersenBlsChained => "pedersen-bls-chained",
+            Scheme::PedersenBlsUnchained => "pedersen-bls-unchained",
+            Scheme::BlsUnchainedG1Rfc9380 => "bls-unchained-g1-rfc9380",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Result<Self, CryptoError> {
+        [
+            Scheme::PedersenBlsChained,
+            Scheme::PedersenBlsUnchained,
+            Scheme::BlsUnchainedG1Rfc9380,
+        ]
+        .iter()
+        .cloned()
+        .find(|scheme| scheme.id() == id)
+        .ok_or_else(|| CryptoError::ParseError(format!("Unknown drand scheme {}", id)))
+    }
+
+    /// The domain separation tag rounds are signed with
+    pub fn dst(self) -> &'static [u8] {
+        match self {
+            Scheme::BlsUnchainedG1Rfc9380 => b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_",
+            _ => b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_",
+        }
+    }
+}
+
+/// The message unchained beacons sign for `round`, the SHA-256 hash of the
+/// round as 8 big endian bytes
+pub fn round_message(round: u64) -> [u8; 32] {
+    sha256(&[&round.to_be_bytes()])
+}
+
+/// The message chained beacons sign for `round`, which also covers the
+/// signature of the round before
+pub fn chained_round_message(previous_signature: &[u8], round: u64) -> [u8; 32] {
+    sha256(&[previous_signature, &round.to_be_bytes()])
+}
+
+/// The round of a beacon started at `genesis_time` with a round every
+/// `period` seconds that is current at `time`, 0 before the first round
+pub fn round_at(genesis_time: u64, period: u64, time: u64) -> u64 {
+    if time < genesis_time || period == 0 {
+        return 0;
+    }
+    (time - genesis_time) / period + 1
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PublicKey {
+    G1(min_pk::PublicKey),
+    G2(min_sig::PublicKey),
+}
+
+/// The public key of a drand network
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chain {
+    scheme: Scheme,
+    public_key: PublicKey,
+}
+
+impl Chain {
+    /// Fails if `public_key` is not the compressed encoding of a valid key
+    /// in the scheme's key group
+    pub fn new(scheme: Scheme, public_key: &[u8]) -> Result<Self, CryptoError> {
+        let public_key = match scheme {
+            Scheme::BlsUnchainedG1Rfc9380 => {
+                PublicKey::G2(min_sig::PublicKey::from_bytes(public_key)?)
+            }
+            _ => PublicKey::G1(min_pk::PublicKey::from_bytes(public_key)?),
+        };
+        Ok(Chain { scheme, public_key })
+    }
+
+    pub fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    /// The compressed public key
+    pub fn public_key(&self) -> Vec<u8> {
+        match self.public_key {
+            PublicKey::G1(pk) => pk.to_bytes(),
+            PublicKey::G2(pk) => pk.to_bytes(),
+        }
+    }
+
+    /// Verify the beacon's compressed `signature` of `message`, which is
+    /// `round_message` or `chained_round_message` of the round
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        let dst = self.scheme.dst();
+        match self.public_key {
+            PublicKey::G1(ref pk) => min_pk::Signature::from_bytes(signature)
+                .map(|signature| min_pk::core_verify(pk, message, &signature, dst))
+                .unwrap_or(false),
+            PublicKey::G2(ref pk) => min_sig::Signature::from_bytes(signature)
+                .map(|signature| min_sig::core_verify(pk, message, &signature, dst))
+                .unwrap_or(false),
+        }
+    }
+
+    /// Encrypt `message` so it can be read once the beacon signs `round`.
+    /// Fails for chained beacons and messages over `MAX_MESSAGE_SIZE` bytes.
+    pub fn encrypt(&self, round: u64, message: &[u8]) -> Result<Ciphertext, CryptoError> {
+        self.encrypt_with_rng(round, message, &mut OsRng)
+    }
+
+    pub fn encrypt_with_rng<R: CryptoRng + RngCore>(
+        &self,
+        round: u64,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<Ciphertext, CryptoError> {
+        if self.scheme == Scheme::PedersenBlsChained {
+            return Err(CryptoError::GeneralError(
+                "Rounds of chained beacons cannot be encrypted to".to_string(),
+            ));
+        }
+        if message.len() > MAX_MESSAGE_SIZE {
+            return Err(CryptoError::GeneralError(format!(
+                "Expected at most {} bytes to encrypt, found {}",
+                MAX_MESSAGE_SIZE,
+                message.len()
+            )));
+        }
+        let mut sigma = vec![0u8; message.len()];
+        rng.fill_bytes(&mut sigma);
+        let r = h3(&sigma, message);
+        let identity = round_message(round);
+        let dst = self.scheme.dst();
+        // e(r * H(identity), pk), which is e(signature, U) for the beacon
+        let (u, gid) = match self.public_key {
+            PublicKey::G1(pk) => (
+                compress_g1(&mul_g1(unsafe { &*blst_p1_generator() }, &r)),
+                pairing(
+                    &decode_g1(&pk.to_bytes())?,
+                    &g2_affine(&mul_g2(&hash_to_g2(&identity, dst), &r)),
+                ),
+            ),
+            PublicKey::G2(pk) => (
+                compress_g2(&mul_g2(unsafe { &*blst_p2_generator() }, &r)),
+                pairing(
+                    &g1_affine(&mul_g1(&hash_to_g1(&identity, dst), &r)),
+                    &decode_g2(&pk.to_bytes())?,
+                ),
+            ),
+        };
+        let mut v = h2(&gid, sigma.len());
+        xor(&mut v, &sigma);
+        let mut w = h4(&sigma, message.len());
+        xor(&mut w, message);
+        sigma.zeroize();
+        Ok(Ciphertext { round, u, v, w })
+    }
+
+    /// Decrypt with the beacon's compressed signature of the ciphertext's
+    /// round, which is verified first
+    pub fn decrypt(
+        &self,
+        ciphertext: &Ciphertext,
+        signature: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        if !self.verify(&round_message(ciphertext.round), signature) {
+            return Err(CryptoError::GeneralError(format!(
+                "Not the beacon's signature of round {}",
+                ciphertext.round
+            )));
+        }
+        let gid = match self.public_key {
+            PublicKey::G1(_) => pairing(&decode_g1(&ciphertext.u)?, &decode_g2(signature)?),
+            PublicKey::G2(_) => pairing(&decode_g1(signature)?, &decode_g2(&ciphertext.u)?),
+        };
+        let mut sigma = h2(&gid, ciphertext.v.len());
+        xor(&mut sigma, &ciphertext.v);
+        let mut message = h4(&sigma, ciphertext.w.len());
+        xor(&mut message, &ciphertext.w);
+        let r = h3(&sigma, &message);
+        let u = match self.public_key {
+            PublicKey::G1(_) => compress_g1(&mul_g1(unsafe { &*blst_p1_generator() }, &r)),
+            PublicKey::G2(_) => compress_g2(&mul_g2(unsafe { &*blst_p2_generator() }, &r)),
+        };
+        sigma.zeroize();
+        if u != ciphertext.u {
+            message.zeroize();
+            return Err(CryptoError::GeneralError(
+                "Invalid timelock ciphertext".to_string(),
+            ));
+        }
+        Ok(message)
+    }
+}
+
+/// A message that only the beacon's signature of a round decrypts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ciphertext {
+    round: u64,
+    u: Vec<u8>,
+    v: Vec<u8>,
+    w: Vec<u8>,
+}
+
+impl Ciphertext {
+    pub fn round(&self) -> u64 {
+        self.round
+    }
+
+    /// The round, the compressed `U`, then `V` and `W`, which are as long as
+    /// the message
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.round.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.u);
+        bytes.extend_from_slice(&self.v);
+        bytes.extend_from_slice(&self.w);
+        bytes
+    }
+
+    /// `U` is in the key group of `scheme`
+    pub fn from_bytes(scheme: Scheme, bytes: &[u8]) -> Result<Self, CryptoError> {
+        let u_size = match scheme {
+            Scheme::BlsUnchainedG1Rfc9380 => min_sig::PUBLIC_KEY_SIZE,
+            _ => min_pk::PUBLIC_KEY_SIZE,
+        };
+        let invalid = || {
+            CryptoError::ParseError(format!(
+                "Invalid timelock ciphertext length {}",
+                bytes.len()
+            ))
+        };
+        if bytes.len() < 8 + u_size {
+            return Err(invalid());
+        }
+        let mut round = [0u8; 8];
+        round.copy_from_slice(&bytes[..8]);
+        let (u, rest) = bytes[8..].split_at(u_size);
+        let (v, w) = rest.split_at(rest.len() / 2);
+        if v.len() != w.len() || v.len() > MAX_MESSAGE_SIZE {
+            return Err(invalid());
+        }
+        Ok(Ciphertext {
+            round: u64::from_be_bytes(round),
+            u: u.to_vec(),
+            v: v.to_vec(),
+            w: w.to_vec(),
+        })
+    }
+}
+
+fn sha256(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.input(part);
+    }
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&hasher.result());
+    out
+}
+
+fn xor(out: &mut [u8], other: &[u8]) {
+    for (a, b) in out.iter_mut().zip(other.iter()) {
+        *a ^= b;
+    }
+}
+
+/// The pairing value hashed with its coefficients big endian, highest first
+fn h2(gt: &blst_fp12, len: usize) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.input(H2_DST);
+    for fp6 in gt.fp6.iter().rev() {
+        for fp2 in fp6.fp2.iter().rev() {
+            for fp in fp2.fp.iter().rev() {
+                let mut bytes = [0u8; 48];
+                unsafe { blst_bendian_from_fp(bytes.as_mut_ptr(), fp) };
+                hasher.input(&bytes[..]);
+            }
+        }
+    }
+    hasher.result()[..len].to_vec()
+}
+
+/// The hash of `sigma` and the message rehashed with a counter until its
+/// low 255 bits are a scalar
+fn h3(sigma: &[u8], message: &[u8]) -> blst_scalar {
+    let seed = sha256(&[H3_DST, sigma, message]);
+    let mut scalar = blst_scalar::default();
+    for i in 1..u16::MAX {
+        let mut bytes = sha256(&[&i.to_le_bytes(), &seed]);
+        bytes[0] >>= 1;
+        unsafe { blst_scalar_from_bendian(&mut scalar, bytes.as_ptr()) };
+        if unsafe { blst_scalar_fr_check(&scalar) } {
+            return scalar;
+        }
+    }
+    unreachable!("Half of all 255 bit values are scalars")
+}
+
+fn h4(sigma: &[u8], len: usize) -> Vec<u8> {
+    sha256(&[H4_DST, sigma])[..len].to_vec()
+}
+
+fn hash_to_g1(message: &[u8], dst: &[u8]) -> blst_p1 {
+    let mut out = blst_p1::default();
+    unsafe {
+        blst_hash_to_g1(
+            &mut out,
+            message.as_ptr(),
+            message.len(),
+            dst.as_ptr(),
+            dst.len(),
+            [].as_ptr(),
+            0,
+        )
+    };
+    out
+}
+
+fn hash_to_g2(message: &[u8], dst: &[u8]) -> blst_p2 {
+    let mut out = blst_p2::default();
+    unsafe {
+        blst_hash_to_g2(
+            &mut out,
+            message.as_ptr(),
+            message.len(),
+            dst.as_ptr(),
+            dst.len(),
+            [].as_ptr(),
+            0,
+        )
+    };
+    out
+}
+
+fn mul_g1(p: &blst_p1, scalar: &blst_scalar) -> blst_p1 {
+    let mut out = blst_p1::default();
+    unsafe { blst_p1_mult(&mut out, p, scalar.b.as_ptr(), 255) };
+    out
+}
+
+fn mul_g2(p: &blst_p2, scalar: &blst_scalar) -> blst_p2 {
+    let mut out = blst_p2::default();
+    unsafe { blst_p2_mult(&mut out, p, scalar.b.as_ptr(), 255) };
+    out
+}
+
+fn g1_affine(p: &blst_p1) -> blst_p1_affine {
+    let mut out = blst_p1_affine::default();
+    unsafe { blst_p1_to_affine(&mut out, p) };
+    out
+}
+
+fn g2_affine(p: &blst_p2) -> blst_p2_affine {
+    let mut out = blst_p2_affine::default();
+    unsafe { blst_p2_to_affine(&mut out, p) };
+    out
+}
+
+fn compress_g1(p: &blst_p1) -> Vec<u8> {
+    let mut out = [0u8; 48];
+    unsafe { blst_p1_affine_compress(out.as_mut_ptr(), &g1_affine(p)) };
+    out.to_vec()
+}
+
+fn compress_g2(p: &blst_p2) -> Vec<u8> {
+    let mut out = [0u8; 96];
+    unsafe { blst_p2_affine_compress(out.as_mut_ptr(), &g2_affine(p)) };
+    out.to_vec()
+}
+
+/// Keys and signatures were validated by `Chain` and a bad `U` fails the
+/// comparison with the recomputed one, so only the encoding is checked
+fn decode_g1(bytes: &[u8]) -> Result<blst_p1_affine, CryptoError> {
+    if bytes.len() != 48 {
+        return Err(CryptoError::InvalidPoint(format!(
+            "Expected 48 bytes, found {}",
+            bytes.len()
+        )));
+    }
+    let mut out = blst_p1_affine::default();
+    match unsafe { blst_p1_uncompress(&mut out, bytes.as_ptr()) } {
+        BLST_ERROR::BLST_SUCCESS => Ok(out),
+        e => Err(CryptoError::InvalidPoint(format!("{:?}", e))),
+    }
+}
+
+fn decode_g2(bytes: &[u8]) -> Result<blst_p2_affine, CryptoError> {
+    if bytes.len() != 96 {
+        return Err(CryptoError::InvalidPoint(format!(
+            "Expected 96 bytes, found {}",
+            bytes.len()
+        )));
+    }
+    let mut out = blst_p2_affine::default();
+    match unsafe { blst_p2_uncompress(&mut out, bytes.as_ptr()) } {
+        BLST_ERROR::BLST_SUCCESS => Ok(out),
+        e => Err(CryptoError::InvalidPoint(format!("{:?}", e))),
+    }
+}
+
+fn pairing(p: &blst_p1_affine, q: &blst_p2_affine) -> blst_fp12 {
+    blst_fp12::miller_loop(q, p).final_exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use signatures::bls_ietf::{PrivateKey, Scheme as BlsScheme};
+
+    const MESSAGE: &[u8] = b"a 16 byte secret";
+
+    // The public key of quicknet and its signature of round 1000
+    const QUICKNET_PUBLIC_KEY: &str = "\
+        83cf0f2896adee7eb8b5f01fcad3912212c437e0073e911fb90022d3e760183c\
+        8c4b450b6a0a6c3ac6a5776a2d1064510d1fec758c921cc22b0e17e63aaf4bcb\
+        5ed66304de9cf809bd274ca73bab4af5a6e9c76a4bc09e76eae8991ef5ece45a";
+    const QUICKNET_ROUND_1000: &str = "\
+        b44679b9a59af2ec876b1a6b1ad52ea9b1615fc3982b19576350f93447cb1125\
+        e342b73a8dd2bacbe47e4b6b63ed5e39";
+    // The public key of the default network and its signatures of rounds
+    // 72784 and 72785
+    const MAINNET_PUBLIC_KEY: &str = "\
+        868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a5699\
+        37c529eeda66c7293784a9402801af31";
+    const MAINNET_ROUND_72784: &str = "\
+        a609e19a03c2fcc559e8dae14900aaefe517cb55c840f6e69bc8e4f66c8d18e8\
+        a609685d9917efbfb0c37f058c2de88f13d297c7e19e0ab24813079efe57a182\
+        554ff054c7638153f9b26a60e7111f71a0ff63d9571704905d3ca6df0b031747";
+    const MAINNET_ROUND_72785: &str = "\
+        82f5d3d2de4db19d40a6980e8aa37842a0e55d1df06bd68bddc8d60002e8e959\
+        eb9cfa368b3c1b77d18f02a54fe047b80f0989315f83b12a74fd8679c4f12aae\
+        86eaf6ab5690b34f1fddd50ee3cc6f6cdf59e95526d5a5d82aaa84fa6f181e42";
+
+    #[test]
+    fn quicknet() {
+        let pk = hex::decode(QUICKNET_PUBLIC_KEY).unwrap();
+        let signature = hex::decode(QUICKNET_ROUND_1000).unwrap();
+        let chain = Chain::new(Scheme::BlsUnchainedG1Rfc9380, &pk).unwrap();
+        assert_eq!(chain.public_key(), pk);
+        assert!(chain.verify(&round_message(1000), &signature));
+        assert!(!chain.verify(&round_message(1001), &signature));
+
+        let ciphertext = chain.encrypt(1000, MESSAGE).unwrap();
+        assert_eq!(chain.decrypt(&ciphertext, &signature).unwrap(), MESSAGE);
+        let later = chain.encrypt(1001, MESSAGE).unwrap();
+        assert!(chain.decrypt(&later, &signature).is_err());
+        assert!(Chain::new(Scheme::PedersenBlsUnchained, &pk).is_err());
+    }
+
+    #[test]
+    fn mainnet() {
+        let pk = hex::decode(MAINNET_PUBLIC_KEY).unwrap();
+        let previous = hex::decode(MAINNET_ROUND_72784).unwrap();
+        let signature = hex::decode(MAINNET_ROUND_72785).unwrap();
+        let chain = Chain::new(Scheme::from_id("pedersen-bls-chained").unwrap(), &pk).unwrap();
+        assert!(chain.verify(&chained_round_message(&previous, 72785), &signature));
+        assert!(!chain.verify(&chained_round_message(&previous, 72786), &signature));
+        assert!(!chain.verify(&round_message(72785), &signature));
+        assert!(chain.encrypt(72786, MESSAGE).is_err());
+    }
+
+    #[test]
+    fn unchained_g2_signatures() {
+        let sk = PrivateKey::key_gen(&[1u8; 32], b"").unwrap();
+        let pk = min_pk::PublicKey::new(&sk).to_bytes();
+        let chain = Chain::new(Scheme::PedersenBlsUnchained, &pk).unwrap();
+        let beacon = min_pk::sign(BlsScheme::Basic, &sk, &round_message(42)).to_bytes();
+
+        for message in &[&b""[..], MESSAGE, &[7u8; MAX_MESSAGE_SIZE]] {
+            let bytes = chain.encrypt(42, message).unwrap().to_bytes();
+            let ciphertext = Ciphertext::from_bytes(chain.scheme(), &bytes).unwrap();
+            assert_eq!(ciphertext.round(), 42);
+            assert_eq!(ciphertext.to_bytes(), bytes);
+            assert_eq!(chain.decrypt(&ciphertext, &beacon).unwrap(), *message);
+        }
+
+        let earlier = min_pk::sign(BlsScheme::Basic, &sk, &round_message(41)).to_bytes();
+        let ciphertext = chain.encrypt(42, MESSAGE).unwrap();
+        assert!(chain.decrypt(&ciphertext, &earlier).is_err());
+        let mut tampered = ciphertext.to_bytes();
+        *tampered.last_mut().unwrap() ^= 1;
+        let tampered = Ciphertext::from_bytes(chain.scheme(), &tampered).unwrap();
+        assert!(chain.decrypt(&tampered, &beacon).is_err());
+
+        assert!(chain.encrypt(42, &[0u8; MAX_MESSAGE_SIZE + 1]).is_err());
+        let bytes = ciphertext.to_bytes();
+        assert!(Ciphertext::from_bytes(chain.scheme(), &bytes[..40]).is_err());
+        assert!(Ciphertext::from_bytes(chain.scheme(), &bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn schemes_and_rounds() {
+        for scheme in &[
+            Scheme::PedersenBlsChained,
+            Scheme::PedersenBlsUnchained,
+            Scheme::BlsUnchainedG1Rfc9380,
+        ] {
+            assert_eq!(Scheme::from_id(scheme.id()).unwrap(), *scheme);
+        }
+        assert!(Scheme::from_id("bls-unchained-on-g1").is_err());
+
+        assert_eq!(round_at(1000, 3, 999), 0);
+        assert_eq!(round_at(1000, 3, 1000), 1);
+        assert_eq!(round_at(1000, 3, 1005), 2);
+    }
+}
@@ -33,6 +33,52 @@ macro_rules! array_copy {
 ))]
 macro_rules! impl_bytearray {
     ($thing:ident) => {
+        impl_bytearray!(@common $thing);
+
+        /// Lowercase hex
+        impl ::std::fmt::Display for $thing {
+            fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                write!(formatter, "{}", hex::encode(&self.0[..]))
+            }
+        }
+
+        impl ::std::fmt::Debug for $thing {
+            fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                write!(
+                    formatter,
+                    "{} {{ {} }}",
+                    stringify!($thing),
+                    hex::encode(&self.0[..])
+                )
+            }
+        }
+    };
+    // Secret bytes are not printed by `Debug` or `Display`, so they don't
+    // end up in logs
+    (secret $thing:ident) => {
+        impl_bytearray!(@common $thing);
+
+        impl $thing {
+            /// The secret bytes, for storing or exporting them. Prefer
+            /// passing the key itself to the functions that use it.
+            pub fn expose_bytes(&self) -> &[u8] {
+                self.0.as_slice()
+            }
+        }
+
+        impl ::std::fmt::Display for $thing {
+            fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                write!(formatter, "{}(..)", stringify!($thing))
+            }
+        }
+
+        impl ::std::fmt::Debug for $thing {
+            fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                write!(formatter, "{}(..)", stringify!($thing))
+            }
+        }
+    };
+    (@common $thing:ident) => {
         impl $thing {
             #[inline]
             /// Converts the object to a raw pointer for FFI interfacing
@@ -56,12 +102,32 @@ macro_rules! impl_bytearray {
             pub fn is_empty(&self) -> bool {
                 self.0.is_empty()
             }
+
+            /// Replaces the contents with `other` if `choice` is set,
+            /// without branching on `choice`.
+            /// Panics if the lengths differ
+            pub fn conditional_assign(&mut self, other: &$thing, choice: ::subtle::Choice) {
+                use subtle::ConditionallySelectable;
+
+                assert_eq!(self.0.len(), other.0.len());
+                for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+                    a.conditional_assign(b, choice);
+                }
+            }
+        }
+
+        impl ::subtle::ConstantTimeEq for $thing {
+            #[inline]
+            fn ct_eq(&self, other: &$thing) -> ::subtle::Choice {
+                self.0.as_slice().ct_eq(other.0.as_slice())
+            }
         }
 
+        /// Compares in constant time, the contents may be secret
         impl PartialEq for $thing {
             #[inline]
             fn eq(&self, other: &$thing) -> bool {
-                self.0 == other.0
+                ::subtle::ConstantTimeEq::ct_eq(self, other).into()
             }
         }
 
@@ -118,25 +184,12 @@ macro_rules! impl_bytearray {
                 self.0.as_slice()
             }
         }
-        impl ::std::fmt::Display for $thing {
-            fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-                write!(
-                    formatter,
-                    "{} {{ {} }}",
-                    stringify!($thing),
-                    hex::encode(&self.0[..])
-                )
-            }
-        }
+        /// Hex, optionally prefixed by `0x`
+        impl ::std::str::FromStr for $thing {
+            type Err = $crate::CryptoError;
 
-        impl ::std::fmt::Debug for $thing {
-            fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-                write!(
-                    formatter,
-                    "{} {{ {} }}",
-                    stringify!($thing),
-                    hex::encode(&self.0[..])
-                )
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $crate::keys::decode_hex(s).map($thing)
             }
         }
 
@@ -159,6 +212,18 @@ macro_rules! impl_bytearray {
             }
         }
 
+        impl From<Vec<u8>> for $thing {
+            fn from(bytes: Vec<u8>) -> Self {
+                $thing(bytes)
+            }
+        }
+
+        impl<'a> From<&'a [u8]> for $thing {
+            fn from(bytes: &'a [u8]) -> Self {
+                $thing(bytes.to_vec())
+            }
+        }
+
         #[cfg(feature = "serde")]
         impl serde::Serialize for $thing {
             fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
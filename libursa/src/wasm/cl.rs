@@ -336,6 +336,10 @@ impl SubProofRequest {
     pub fn addPredicate(&mut self, attribute: &str, p_type: &str, value: i32) {
         self.0.add_predicate(attribute, p_type, value).unwrap();
     }
+
+    pub fn addRangePredicate(&mut self, attribute: &str, lower: i32, upper: i32) {
+        self.0.add_range_predicate(attribute, lower, upper).unwrap();
+    }
 }
 
 #[wasm_bindgen]
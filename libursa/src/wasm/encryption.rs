@@ -6,12 +6,20 @@ use std::str::FromStr;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct WasmCipherKey {
     cipher: EncryptorType,
     key: String,
 }
 
+impl std::fmt::Debug for WasmCipherKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("WasmCipherKey")
+            .field("cipher", &self.cipher)
+            .finish()
+    }
+}
+
 macro_rules! operation_impl {
     ($name:ident) => {
         fn $name(cipher_key: WasmCipherKey, aad: &[u8], input: &[u8]) -> Result<Vec<u8>, JsValue> {
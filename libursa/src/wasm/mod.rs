@@ -16,17 +16,18 @@ use keys::{PrivateKey, PublicKey, SessionKey};
 
 use errors::{UrsaCryptoError, UrsaCryptoErrorKind};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct WasmPrivateKey(String);
 #[wasm_bindgen]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WasmPublicKey(String);
 
 #[wasm_bindgen]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct WasmSessionKey(String);
 
 #[wasm_bindgen]
@@ -36,6 +37,18 @@ pub struct KeyPair {
     sk: WasmPrivateKey,
 }
 
+impl fmt::Debug for WasmPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("WasmPrivateKey(..)")
+    }
+}
+
+impl fmt::Debug for WasmSessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("WasmSessionKey(..)")
+    }
+}
+
 impl From<&PublicKey> for WasmPublicKey {
     fn from(pk: &PublicKey) -> WasmPublicKey {
         WasmPublicKey(hex::encode(&pk[..]))
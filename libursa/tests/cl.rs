@@ -22,7 +22,10 @@ mod cl_tests {
 
     mod test {
         use super::*;
-        use ursa::cl::NonCredentialSchemaBuilder;
+        use ursa::cl::{
+            CredentialRequest, CredentialRequestMetadata, CredentialResponse,
+            NonCredentialSchemaBuilder,
+        };
         use ursa::errors::prelude::*;
 
         #[test]
@@ -542,6 +545,206 @@ mod cl_tests {
             assert!(proof_verifier.verify(&proof, &nonce).unwrap());
         }
 
+        #[test]
+        fn anoncreds_works_for_serialized_credential_request_and_response() {
+            HLCryptoDefaultLogger::init(None).ok();
+
+            // 1. Issuer creates credential schema and definition
+            let credential_schema = helpers::gvt_credential_schema();
+            let non_credential_schema = helpers::non_credential_schema();
+            let (credential_pub_key, credential_priv_key, credential_key_correctness_proof) =
+                Issuer::new_credential_def(&credential_schema, &non_credential_schema, false)
+                    .unwrap();
+
+            // 2. Issuer creates nonce for the credential offer
+            let credential_nonce = new_nonce().unwrap();
+
+            // 3. Prover creates credential request over all values including the hidden master secret
+            let credential_values =
+                helpers::gvt_credential_values(&Prover::new_master_secret().unwrap());
+            let (credential_request, credential_request_metadata) = Prover::new_credential_request(
+                PROVER_ID,
+                &credential_pub_key,
+                &credential_key_correctness_proof,
+                &credential_values,
+                &credential_nonce,
+            )
+            .unwrap();
+
+            // 4. Credential request is sent to Issuer
+            let credential_request: CredentialRequest =
+                serde_json::from_str(&serde_json::to_string(&credential_request).unwrap()).unwrap();
+            assert_eq!(PROVER_ID, credential_request.prover_id());
+
+            // 5. Issuer signs over the known values and the committed hidden ones
+            let mut known_values_builder = Issuer::new_credential_values_builder().unwrap();
+            known_values_builder
+                .add_dec_known("name", "1139481716457488690172217916278103335")
+                .unwrap();
+            known_values_builder
+                .add_dec_known(
+                    "sex",
+                    "5944657099558967239210949258394887428692050081607692519917050011144233115103",
+                )
+                .unwrap();
+            known_values_builder.add_dec_known("age", "28").unwrap();
+            known_values_builder.add_dec_known("height", "175").unwrap();
+            let known_values = known_values_builder.finalize().unwrap();
+
+            let credential_response = Issuer::sign_credential_request(
+                &credential_request,
+                &credential_nonce,
+                &known_values,
+                &credential_pub_key,
+                &credential_priv_key,
+            )
+            .unwrap();
+
+            // 6. Credential response is sent to Prover who unblinds the signature
+            let credential_response: CredentialResponse =
+                serde_json::from_str(&serde_json::to_string(&credential_response).unwrap())
+                    .unwrap();
+            let credential_request_metadata: CredentialRequestMetadata =
+                serde_json::from_str(&serde_json::to_string(&credential_request_metadata).unwrap())
+                    .unwrap();
+
+            let credential_signature = Prover::process_credential_response(
+                credential_response,
+                &credential_request_metadata,
+                &credential_values,
+                &credential_pub_key,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+            // 7. Prover creates proof
+            let sub_proof_request = helpers::gvt_sub_proof_request();
+            let nonce = new_nonce().unwrap();
+
+            let mut proof_builder = Prover::new_proof_builder().unwrap();
+            proof_builder.add_common_attribute(LINK_SECRET).unwrap();
+            proof_builder
+                .add_sub_proof_request(
+                    &sub_proof_request,
+                    &credential_schema,
+                    &non_credential_schema,
+                    &credential_signature,
+                    &credential_values,
+                    &credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            let proof = proof_builder.finalize(&nonce).unwrap();
+
+            // 8. Verifier verifies proof
+            let mut proof_verifier = Verifier::new_proof_verifier().unwrap();
+            proof_verifier
+                .add_sub_proof_request(
+                    &sub_proof_request,
+                    &credential_schema,
+                    &non_credential_schema,
+                    &credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            assert!(proof_verifier.verify(&proof, &nonce).unwrap());
+        }
+
+        #[test]
+        fn anoncreds_works_for_batch_credential_issuance() {
+            HLCryptoDefaultLogger::init(None).ok();
+
+            // 1. Issuer creates credential schema and definition
+            let credential_schema = helpers::gvt_credential_schema();
+            let non_credential_schema = helpers::non_credential_schema();
+            let (credential_pub_key, credential_priv_key, credential_key_correctness_proof) =
+                Issuer::new_credential_def(&credential_schema, &non_credential_schema, false)
+                    .unwrap();
+
+            // 2. Each prover creates a credential request for its own offer
+            let provers = (0..3)
+                .map(|_| {
+                    let credential_nonce = new_nonce().unwrap();
+                    let credential_values =
+                        helpers::gvt_credential_values(&Prover::new_master_secret().unwrap());
+                    let (credential_request, credential_request_metadata) =
+                        Prover::new_credential_request(
+                            PROVER_ID,
+                            &credential_pub_key,
+                            &credential_key_correctness_proof,
+                            &credential_values,
+                            &credential_nonce,
+                        )
+                        .unwrap();
+                    (
+                        credential_nonce,
+                        credential_values,
+                        credential_request,
+                        credential_request_metadata,
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            // 3. Issuer signs all the requests over the same known values
+            let mut known_values_builder = Issuer::new_credential_values_builder().unwrap();
+            known_values_builder
+                .add_dec_known("name", "1139481716457488690172217916278103335")
+                .unwrap();
+            known_values_builder
+                .add_dec_known(
+                    "sex",
+                    "5944657099558967239210949258394887428692050081607692519917050011144233115103",
+                )
+                .unwrap();
+            known_values_builder.add_dec_known("age", "28").unwrap();
+            known_values_builder.add_dec_known("height", "175").unwrap();
+            let known_values = known_values_builder.finalize().unwrap();
+
+            let requests = provers
+                .iter()
+                .map(|(nonce, _, request, _)| (request, nonce, &known_values))
+                .collect::<Vec<_>>();
+            let credential_responses = Issuer::sign_credential_requests(
+                &requests,
+                &credential_pub_key,
+                &credential_priv_key,
+            )
+            .unwrap();
+            assert_eq!(provers.len(), credential_responses.len());
+
+            // 4. Every prover unblinds its own signature
+            for ((_, credential_values, _, metadata), response) in
+                provers.iter().zip(credential_responses)
+            {
+                Prover::process_credential_response(
+                    response,
+                    metadata,
+                    credential_values,
+                    &credential_pub_key,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap();
+            }
+
+            // 5. A request signed with another prover's nonce is rejected
+            let requests = [
+                (&provers[0].2, &provers[0].0, &known_values),
+                (&provers[1].2, &provers[0].0, &known_values),
+            ];
+            assert!(Issuer::sign_credential_requests(
+                &requests,
+                &credential_pub_key,
+                &credential_priv_key
+            )
+            .is_err());
+        }
+
         #[test]
         fn anoncreds_works_for_revocation_proof_issuance_on_demand() {
             HLCryptoDefaultLogger::init(None).ok();
@@ -3758,6 +3961,165 @@ mod cl_tests {
             assert!(proof_verifier.verify(&proof, &nonce).unwrap());
         }
 
+        #[test]
+        fn anoncreds_works_for_witness_catch_up_from_merged_deltas() {
+            HLCryptoDefaultLogger::init(None).ok();
+
+            // 1. Issuer creates credential schema
+            let credential_schema = helpers::gvt_credential_schema();
+            let non_credential_schema = helpers::non_credential_schema();
+
+            // 2. Issuer creates credential definition(with revocation keys)
+            let (credential_pub_key, credential_priv_key, credential_key_correctness_proof) =
+                Issuer::new_credential_def(&credential_schema, &non_credential_schema, true)
+                    .unwrap();
+
+            // 3. Issuer creates revocation registry with IssuanceByDefault type
+            let max_cred_num = 5;
+            let issuance_by_default = true;
+            let (rev_key_pub, rev_key_priv, mut rev_reg, mut rev_tails_generator) =
+                Issuer::new_revocation_registry_def(
+                    &credential_pub_key,
+                    max_cred_num,
+                    issuance_by_default,
+                )
+                .unwrap();
+
+            let simple_tail_accessor = SimpleTailsAccessor::new(&mut rev_tails_generator).unwrap();
+
+            // 4. Prover creates master secret with credential values
+            let credential_values =
+                helpers::gvt_credential_values(&Prover::new_master_secret().unwrap());
+
+            // 5. Issuer creates nonce used by Prover to create correctness proof for blinded secrets
+            let credential_nonce = new_nonce().unwrap();
+
+            // 6. Prover blinds master secret
+            let (
+                blinded_credential_secrets,
+                credential_secrets_blinding_factors,
+                blinded_credential_secrets_correctness_proof,
+            ) = Prover::blind_credential_secrets(
+                &credential_pub_key,
+                &credential_key_correctness_proof,
+                &credential_values,
+                &credential_nonce,
+            )
+            .unwrap();
+
+            // 7. Prover creates nonce used by Issuer to create correctness proof for signature
+            let credential_issuance_nonce = new_nonce().unwrap();
+
+            // 8. Issuer creates and sign credential values
+            let rev_idx = 1;
+            let (mut credential_signature, signature_correctness_proof, _) =
+                Issuer::sign_credential_with_revoc(
+                    PROVER_ID,
+                    &blinded_credential_secrets,
+                    &blinded_credential_secrets_correctness_proof,
+                    &credential_nonce,
+                    &credential_issuance_nonce,
+                    &credential_values,
+                    &credential_pub_key,
+                    &credential_priv_key,
+                    rev_idx,
+                    max_cred_num,
+                    issuance_by_default,
+                    &mut rev_reg,
+                    &rev_key_priv,
+                    &simple_tail_accessor,
+                )
+                .unwrap();
+
+            // 9. Prover creates witness and keeps its own copy of the registry
+            let mut prover_rev_reg = rev_reg.clone();
+            let mut witness = Witness::new(
+                rev_idx,
+                max_cred_num,
+                issuance_by_default,
+                &RegistryDelta::from_rev_reg(&rev_reg).to_delta(),
+                &simple_tail_accessor,
+            )
+            .unwrap();
+
+            // 10. Prover processes credential signature
+            Prover::process_credential_signature(
+                &mut credential_signature,
+                &credential_values,
+                &signature_correctness_proof,
+                &credential_secrets_blinding_factors,
+                &credential_pub_key,
+                &credential_issuance_nonce,
+                Some(&rev_key_pub),
+                Some(&rev_reg),
+                Some(&witness),
+            )
+            .unwrap();
+
+            // 11. Issuer revokes two other credentials and publishes a delta for each
+            let deltas = vec![
+                Issuer::revoke_credential(&mut rev_reg, max_cred_num, 2, &simple_tail_accessor)
+                    .unwrap(),
+                Issuer::revoke_credential(&mut rev_reg, max_cred_num, 3, &simple_tail_accessor)
+                    .unwrap(),
+            ];
+
+            // 12. Prover catches up with a single merged delta
+            let delta = RevocationRegistryDelta::merge_all(&deltas).unwrap();
+            assert_eq!(2, delta.revoked().len());
+            assert!(delta.issued().is_empty());
+
+            delta.apply(&mut prover_rev_reg).unwrap();
+            delta
+                .update_witness(&mut witness, rev_idx, max_cred_num, &simple_tail_accessor)
+                .unwrap();
+
+            // The delta no longer starts from the prover's registry state
+            assert!(delta.apply(&mut prover_rev_reg).is_err());
+            // A witness can not be updated for a revoked credential
+            let mut revoked_witness = witness.clone();
+            let res =
+                delta.update_witness(&mut revoked_witness, 2, max_cred_num, &simple_tail_accessor);
+            assert_eq!(
+                UrsaCryptoErrorKind::CredentialRevoked,
+                res.unwrap_err().kind()
+            );
+
+            // 13. Prover creates proof against the updated registry
+            let nonce = new_nonce().unwrap();
+            let sub_proof_request = helpers::gvt_sub_proof_request();
+
+            let mut proof_builder = Prover::new_proof_builder().unwrap();
+            proof_builder.add_common_attribute(LINK_SECRET).unwrap();
+            proof_builder
+                .add_sub_proof_request(
+                    &sub_proof_request,
+                    &credential_schema,
+                    &non_credential_schema,
+                    &credential_signature,
+                    &credential_values,
+                    &credential_pub_key,
+                    Some(&prover_rev_reg),
+                    Some(&witness),
+                )
+                .unwrap();
+            let proof = proof_builder.finalize(&nonce).unwrap();
+
+            // 14. Verifier verifies proof against the issuer's registry
+            let mut proof_verifier = Verifier::new_proof_verifier().unwrap();
+            proof_verifier
+                .add_sub_proof_request(
+                    &sub_proof_request,
+                    &credential_schema,
+                    &non_credential_schema,
+                    &credential_pub_key,
+                    Some(&rev_key_pub),
+                    Some(&rev_reg),
+                )
+                .unwrap();
+            assert!(proof_verifier.verify(&proof, &nonce).unwrap());
+        }
+
         #[test]
         #[ignore]
         fn anoncreds_works_for_full_accumulator() {
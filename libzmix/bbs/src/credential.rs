@@ -0,0 +1,578 @@
+use crate::errors::prelude::*;
+use crate::keys::prelude::*;
+use crate::messages::*;
+use crate::pok_sig::prelude::*;
+use crate::prover::Prover;
+use crate::schema::CredentialSchema;
+use crate::signature::prelude::*;
+use crate::verifier::Verifier;
+use crate::{ProofNonce, ProofRequest, RandomElem, SignatureMessage, SignatureProof};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{
+        CredentialPrivateKey, CredentialProof, CredentialProofBuilder, CredentialProofVerifier,
+        CredentialPublicKey, CredentialValue, CredentialValues, CredentialValuesBuilder,
+        SubProofRequest, SubProofRequestBuilder,
+    };
+}
+
+/// An issuer's public key for one credential schema.
+/// Plays the part of the CL `CredentialPublicKey` at a fraction of the size.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CredentialPublicKey {
+    schema: CredentialSchema,
+    public_key: PublicKey,
+}
+
+impl CredentialPublicKey {
+    /// Pair a schema with a public key that has one generator per attribute
+    pub fn new(schema: CredentialSchema, public_key: PublicKey) -> Result<Self, BBSError> {
+        if schema.len() != public_key.message_count() {
+            return Err(BBSErrorKind::PublicKeyGeneratorMessageCountMismatch(
+                schema.len(),
+                public_key.message_count(),
+            )
+            .into());
+        }
+        public_key.validate()?;
+        Ok(Self { schema, public_key })
+    }
+
+    /// The attributes signed with this key
+    pub fn schema(&self) -> &CredentialSchema {
+        &self.schema
+    }
+
+    /// The underlying BBS+ public key
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+}
+
+/// An issuer's private key for one credential schema
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CredentialPrivateKey(pub(crate) SecretKey);
+
+impl CredentialPrivateKey {
+    /// The underlying BBS+ secret key
+    pub fn secret_key(&self) -> &SecretKey {
+        &self.0
+    }
+}
+
+/// A credential attribute value as seen by its owner
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub enum CredentialValue {
+    /// Known to the issuer
+    Known(SignatureMessage),
+    /// Only known to the prover, committed to during issuance
+    Hidden(SignatureMessage),
+}
+
+impl CredentialValue {
+    /// The message signed for this attribute
+    pub fn value(&self) -> &SignatureMessage {
+        match self {
+            CredentialValue::Known(m) | CredentialValue::Hidden(m) => m,
+        }
+    }
+
+    /// Whether the value is hidden from the issuer
+    pub fn is_hidden(&self) -> bool {
+        matches!(self, CredentialValue::Hidden(_))
+    }
+}
+
+/// The attribute values of a credential
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct CredentialValues {
+    attrs_values: BTreeMap<String, CredentialValue>,
+}
+
+impl CredentialValues {
+    /// The values by attribute name
+    pub fn attrs_values(&self) -> &BTreeMap<String, CredentialValue> {
+        &self.attrs_values
+    }
+
+    /// All values in schema order
+    pub(crate) fn messages(
+        &self,
+        schema: &CredentialSchema,
+    ) -> Result<Vec<SignatureMessage>, BBSError> {
+        let values = self
+            .attrs_values
+            .iter()
+            .map(|(k, v)| (k.clone(), *v.value()))
+            .collect();
+        schema.messages(&values)
+    }
+
+    /// The known or hidden values by message index
+    pub(crate) fn by_index(
+        &self,
+        schema: &CredentialSchema,
+        hidden: bool,
+    ) -> Result<BTreeMap<usize, SignatureMessage>, BBSError> {
+        let mut messages = BTreeMap::new();
+        for (name, value) in self
+            .attrs_values
+            .iter()
+            .filter(|(_, v)| v.is_hidden() == hidden)
+        {
+            let i = schema.indices(&[name])?[0];
+            messages.insert(i, *value.value());
+        }
+        Ok(messages)
+    }
+}
+
+/// Builds credential values
+#[derive(Debug, Default)]
+pub struct CredentialValuesBuilder {
+    attrs_values: BTreeMap<String, CredentialValue>,
+}
+
+impl CredentialValuesBuilder {
+    /// Start with no values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a value known to the issuer
+    pub fn add_known(&mut self, attr: &str, value: SignatureMessage) {
+        self.attrs_values
+            .insert(attr.to_string(), CredentialValue::Known(value));
+    }
+
+    /// Add a value only known to the prover such as a link secret
+    pub fn add_hidden(&mut self, attr: &str, value: SignatureMessage) {
+        self.attrs_values
+            .insert(attr.to_string(), CredentialValue::Hidden(value));
+    }
+
+    /// Finish building the values
+    pub fn finalize(self) -> CredentialValues {
+        CredentialValues {
+            attrs_values: self.attrs_values,
+        }
+    }
+}
+
+/// The attributes a verifier asks to see from one credential
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct SubProofRequest {
+    revealed_attrs: BTreeSet<String>,
+}
+
+impl SubProofRequest {
+    /// The attribute names to reveal
+    pub fn revealed_attrs(&self) -> &BTreeSet<String> {
+        &self.revealed_attrs
+    }
+
+    fn to_proof_request(
+        &self,
+        pub_key: &CredentialPublicKey,
+        common_attributes: &BTreeSet<String>,
+    ) -> Result<ProofRequest, BBSError> {
+        if let Some(attr) = self.revealed_attrs.intersection(common_attributes).next() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!("Common attribute {} can not be revealed", attr),
+            }
+            .into());
+        }
+        let revealed = pub_key
+            .schema
+            .indices(&self.revealed_attrs.iter().collect::<Vec<&String>>())?;
+        Verifier::new_proof_request(revealed.as_slice(), &pub_key.public_key)
+    }
+}
+
+/// Builds a sub proof request
+#[derive(Debug, Default)]
+pub struct SubProofRequestBuilder {
+    revealed_attrs: BTreeSet<String>,
+}
+
+impl SubProofRequestBuilder {
+    /// Start with no revealed attributes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask for `attr` to be revealed
+    pub fn add_revealed_attr(&mut self, attr: &str) {
+        self.revealed_attrs.insert(attr.to_string());
+    }
+
+    /// Finish building the request
+    pub fn finalize(self) -> SubProofRequest {
+        SubProofRequest {
+            revealed_attrs: self.revealed_attrs,
+        }
+    }
+}
+
+/// Position of `attr` among the hidden messages of `request`
+fn hidden_index(
+    request: &ProofRequest,
+    schema: &CredentialSchema,
+    attr: &str,
+) -> Result<usize, BBSError> {
+    let i = schema.indices(&[attr])?[0];
+    Ok(i - request.revealed_messages.range(..i).count())
+}
+
+/// Creates a proof over one or more credentials.
+/// Attributes added with `add_common_attribute`, e.g. a link secret, stay hidden
+/// and are proven equal across all credentials.
+#[derive(Debug, Default)]
+pub struct CredentialProofBuilder {
+    common_attributes: BTreeMap<String, (Option<SignatureMessage>, ProofNonce)>,
+    pok_sigs: Vec<PoKOfSignature>,
+}
+
+impl CredentialProofBuilder {
+    /// Start a proof with no credentials
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require `attr` to have the same hidden value in every credential
+    pub fn add_common_attribute(&mut self, attr: &str) {
+        self.common_attributes
+            .insert(attr.to_string(), (None, ProofNonce::random()));
+    }
+
+    /// Add a credential revealing the attributes in `sub_proof_request`
+    pub fn add_sub_proof_request(
+        &mut self,
+        sub_proof_request: &SubProofRequest,
+        signature: &Signature,
+        values: &CredentialValues,
+        pub_key: &CredentialPublicKey,
+    ) -> Result<(), BBSError> {
+        let common = self.common_attributes.keys().cloned().collect();
+        let request = sub_proof_request.to_proof_request(pub_key, &common)?;
+        let messages = values.messages(&pub_key.schema)?;
+
+        let mut blindings = BTreeMap::new();
+        for (attr, (value, blinding)) in self.common_attributes.iter_mut() {
+            let i = match pub_key.schema.index_of(attr) {
+                Some(i) => i,
+                None => continue,
+            };
+            match value {
+                Some(v) if *v != messages[i] => {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!("Common attribute {} has different values", attr),
+                    }
+                    .into())
+                }
+                Some(_) => {}
+                None => *value = Some(messages[i]),
+            }
+            blindings.insert(i, *blinding);
+        }
+
+        let proof_messages = messages
+            .iter()
+            .enumerate()
+            .map(|(i, m)| match blindings.get(&i) {
+                Some(b) => pm_hidden_raw!(*m, *b),
+                None if request.revealed_messages.contains(&i) => pm_revealed_raw!(*m),
+                None => pm_hidden_raw!(*m),
+            })
+            .collect::<Vec<ProofMessage>>();
+
+        self.pok_sigs.push(Prover::commit_signature_pok(
+            &request,
+            proof_messages.as_slice(),
+            signature,
+        )?);
+        Ok(())
+    }
+
+    /// Compute the challenge over all credentials and generate the proof
+    pub fn finalize(self, nonce: &ProofNonce) -> Result<CredentialProof, BBSError> {
+        let challenge = Prover::create_challenge_hash(self.pok_sigs.as_slice(), None, nonce)?;
+        let proofs = self
+            .pok_sigs
+            .into_iter()
+            .map(|p| Prover::generate_signature_pok(p, &challenge))
+            .collect::<Result<Vec<SignatureProof>, BBSError>>()?;
+        Ok(CredentialProof { proofs })
+    }
+}
+
+/// A proof over one or more credentials
+#[derive(Debug, Clone)]
+pub struct CredentialProof {
+    /// One proof per credential, in the order they were added
+    pub proofs: Vec<SignatureProof>,
+}
+
+/// Checks a proof over one or more credentials
+#[derive(Debug, Default)]
+pub struct CredentialProofVerifier {
+    common_attributes: BTreeSet<String>,
+    sub_proof_requests: Vec<(SubProofRequest, CredentialPublicKey)>,
+}
+
+impl CredentialProofVerifier {
+    /// Start a verifier expecting no credentials
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require `attr` to have the same hidden value in every credential
+    pub fn add_common_attribute(&mut self, attr: &str) {
+        self.common_attributes.insert(attr.to_string());
+    }
+
+    /// Expect a credential issued under `pub_key` revealing `sub_proof_request`
+    pub fn add_sub_proof_request(
+        &mut self,
+        sub_proof_request: &SubProofRequest,
+        pub_key: &CredentialPublicKey,
+    ) {
+        self.sub_proof_requests
+            .push((sub_proof_request.clone(), pub_key.clone()));
+    }
+
+    /// Check the proof and return the revealed attributes of each credential
+    pub fn verify(
+        &self,
+        proof: &CredentialProof,
+        nonce: &ProofNonce,
+    ) -> Result<Vec<BTreeMap<String, SignatureMessage>>, BBSError> {
+        if self.sub_proof_requests.len() != proof.proofs.len() || proof.proofs.is_empty() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Expected {} proofs, found {}",
+                    self.sub_proof_requests.len(),
+                    proof.proofs.len()
+                ),
+            }
+            .into());
+        }
+
+        let requests = self
+            .sub_proof_requests
+            .iter()
+            .map(|(r, pk)| r.to_proof_request(pk, &self.common_attributes))
+            .collect::<Result<Vec<ProofRequest>, BBSError>>()?;
+        let challenge = Verifier::create_challenge_hash(
+            proof.proofs.as_slice(),
+            requests.as_slice(),
+            nonce,
+            None,
+        )?;
+
+        let mut common_responses = BTreeMap::new();
+        let mut revealed = Vec::with_capacity(proof.proofs.len());
+        for ((p, request), (_, pub_key)) in proof
+            .proofs
+            .iter()
+            .zip(requests.iter())
+            .zip(self.sub_proof_requests.iter())
+        {
+            if p.revealed_messages
+                .keys()
+                .ne(request.revealed_messages.iter())
+            {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: "Revealed attributes do not match the request".to_string(),
+                }
+                .into());
+            }
+            let status =
+                p.proof
+                    .verify(&request.verification_key, &p.revealed_messages, &challenge)?;
+            if !status.is_valid() {
+                return Err(BBSErrorKind::InvalidProof { status }.into());
+            }
+
+            for attr in &self.common_attributes {
+                if pub_key.schema.index_of(attr).is_none() {
+                    continue;
+                }
+                let response =
+                    p.proof
+                        .get_resp_for_message(hidden_index(request, &pub_key.schema, attr)?)?;
+                if *common_responses.entry(attr).or_insert(response) != response {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!("Common attribute {} differs between credentials", attr),
+                    }
+                    .into());
+                }
+            }
+
+            let attrs = pub_key.schema.attributes();
+            revealed.push(
+                p.revealed_messages
+                    .iter()
+                    .map(|(i, m)| (attrs[*i].clone(), *m))
+                    .collect(),
+            );
+        }
+        Ok(revealed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issuer::Issuer;
+    use crate::HashElem;
+
+    fn issue(
+        schema: Vec<&str>,
+        known: &[(&str, &[u8])],
+        link_secret: SignatureMessage,
+    ) -> (CredentialPublicKey, Signature, CredentialValues) {
+        let schema = CredentialSchema::new(schema).unwrap();
+        let (pub_key, priv_key) = Issuer::new_credential_def(&schema).unwrap();
+
+        let mut builder = CredentialValuesBuilder::new();
+        builder.add_hidden("link_secret", link_secret);
+        let prover_values = builder.finalize();
+        let nonce = Issuer::generate_signing_nonce();
+        let (blinded, blinding) =
+            Prover::blind_credential_secrets(&pub_key, &prover_values, &nonce).unwrap();
+
+        let mut builder = CredentialValuesBuilder::new();
+        for (attr, value) in known {
+            builder.add_known(attr, SignatureMessage::hash(value));
+        }
+        let issuer_values = builder.finalize();
+        let blind_signature =
+            Issuer::sign_credential(&blinded, &issuer_values, &nonce, &pub_key, &priv_key).unwrap();
+
+        let mut values = issuer_values.attrs_values.clone();
+        values.extend(prover_values.attrs_values);
+        let values = CredentialValues {
+            attrs_values: values,
+        };
+        let signature =
+            Prover::process_credential_signature(&blind_signature, &values, &blinding, &pub_key)
+                .unwrap();
+        (pub_key, signature, values)
+    }
+
+    #[test]
+    fn credential_issuance_and_proof_work() {
+        let link_secret = Prover::new_link_secret();
+        let (pk1, sig1, values1) = issue(
+            vec!["name", "age", "link_secret"],
+            &[("name", b"Alice"), ("age", b"28")],
+            link_secret,
+        );
+        let (pk2, sig2, values2) = issue(
+            vec!["degree", "link_secret"],
+            &[("degree", b"PhD")],
+            link_secret,
+        );
+
+        let mut builder = SubProofRequestBuilder::new();
+        builder.add_revealed_attr("name");
+        let request1 = builder.finalize();
+        let request2 = SubProofRequestBuilder::new().finalize();
+
+        let mut proof_builder = CredentialProofBuilder::new();
+        proof_builder.add_common_attribute("link_secret");
+        proof_builder
+            .add_sub_proof_request(&request1, &sig1, &values1, &pk1)
+            .unwrap();
+        proof_builder
+            .add_sub_proof_request(&request2, &sig2, &values2, &pk2)
+            .unwrap();
+        let nonce = Verifier::generate_proof_nonce();
+        let proof = proof_builder.finalize(&nonce).unwrap();
+
+        let mut verifier = CredentialProofVerifier::new();
+        verifier.add_common_attribute("link_secret");
+        verifier.add_sub_proof_request(&request1, &pk1);
+        verifier.add_sub_proof_request(&request2, &pk2);
+        let revealed = verifier.verify(&proof, &nonce).unwrap();
+        assert_eq!(SignatureMessage::hash(b"Alice"), revealed[0]["name"]);
+        assert!(revealed[1].is_empty());
+        assert!(verifier
+            .verify(&proof, &Verifier::generate_proof_nonce())
+            .is_err());
+
+        // Verifier asking for more than was proven
+        let mut builder = SubProofRequestBuilder::new();
+        builder.add_revealed_attr("age");
+        let mut verifier = CredentialProofVerifier::new();
+        verifier.add_sub_proof_request(&builder.finalize(), &pk1);
+        verifier.add_sub_proof_request(&request2, &pk2);
+        assert!(verifier.verify(&proof, &nonce).is_err());
+    }
+
+    #[test]
+    fn credential_proof_with_different_link_secrets_fails() {
+        let (pk1, sig1, values1) = issue(
+            vec!["name", "link_secret"],
+            &[("name", b"Alice")],
+            Prover::new_link_secret(),
+        );
+        let (pk2, sig2, values2) = issue(
+            vec!["name", "link_secret"],
+            &[("name", b"Bob")],
+            Prover::new_link_secret(),
+        );
+        let request = SubProofRequestBuilder::new().finalize();
+
+        let mut proof_builder = CredentialProofBuilder::new();
+        proof_builder.add_common_attribute("link_secret");
+        proof_builder
+            .add_sub_proof_request(&request, &sig1, &values1, &pk1)
+            .unwrap();
+        assert!(proof_builder
+            .add_sub_proof_request(&request, &sig2, &values2, &pk2)
+            .is_err());
+
+        // Without the prover side check the verifier still catches it
+        let mut proof_builder = CredentialProofBuilder::new();
+        proof_builder
+            .add_sub_proof_request(&request, &sig1, &values1, &pk1)
+            .unwrap();
+        proof_builder
+            .add_sub_proof_request(&request, &sig2, &values2, &pk2)
+            .unwrap();
+        let nonce = Verifier::generate_proof_nonce();
+        let proof = proof_builder.finalize(&nonce).unwrap();
+        let mut verifier = CredentialProofVerifier::new();
+        verifier.add_common_attribute("link_secret");
+        verifier.add_sub_proof_request(&request, &pk1);
+        verifier.add_sub_proof_request(&request, &pk2);
+        assert!(verifier.verify(&proof, &nonce).is_err());
+    }
+
+    #[test]
+    fn sign_credential_checks_values() {
+        let schema = CredentialSchema::new(vec!["name", "link_secret"]).unwrap();
+        let (pub_key, priv_key) = Issuer::new_credential_def(&schema).unwrap();
+        let nonce = Issuer::generate_signing_nonce();
+
+        let mut builder = CredentialValuesBuilder::new();
+        builder.add_hidden("link_secret", Prover::new_link_secret());
+        let (blinded, _) =
+            Prover::blind_credential_secrets(&pub_key, &builder.finalize(), &nonce).unwrap();
+
+        // The issuer must provide every attribute the prover did not commit to
+        let values = CredentialValuesBuilder::new().finalize();
+        assert!(Issuer::sign_credential(&blinded, &values, &nonce, &pub_key, &priv_key).is_err());
+
+        let mut builder = CredentialValuesBuilder::new();
+        builder.add_known("name", SignatureMessage::hash(b"Alice"));
+        builder.add_known("link_secret", SignatureMessage::hash(b"chosen"));
+        let values = builder.finalize();
+        assert!(Issuer::sign_credential(&blinded, &values, &nonce, &pub_key, &priv_key).is_err());
+    }
+}
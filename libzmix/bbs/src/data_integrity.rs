@@ -0,0 +1,568 @@
+use crate::errors::prelude::*;
+use crate::keys::prelude::*;
+use crate::messages::*;
+use crate::pok_sig::prelude::*;
+use crate::prover::Prover;
+use crate::signature::prelude::*;
+use crate::verifier::Verifier;
+use crate::{
+    HashElem, ProofNonce, ProofRequest, SignatureMessage, SignatureProof, ToVariableLengthBytes,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{
+        canonicalize, derive, sign, verify, verify_derived, DataIntegrityProof, CRYPTOSUITE,
+        PROOF_TYPE,
+    };
+}
+
+/// The `type` of the emitted proofs
+pub const PROOF_TYPE: &str = "DataIntegrityProof";
+/// The `cryptosuite` of the emitted proofs
+pub const CRYPTOSUITE: &str = "bbs-2023";
+
+const BASE_PROOF_HEADER: [u8; 3] = [0xd9, 0x5d, 0x02];
+const DERIVED_PROOF_HEADER: [u8; 3] = [0xd9, 0x5d, 0x03];
+
+/// A Data Integrity proof as attached to the `proof` member of a credential.
+/// `proof_value` is a base64url multibase string holding either a base proof
+/// created by the issuer or a proof derived from it by the holder.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataIntegrityProof {
+    /// Always `DataIntegrityProof`
+    #[serde(rename = "type")]
+    pub proof_type: String,
+    /// Always `bbs-2023`
+    pub cryptosuite: String,
+    /// Identifies the issuer's public key
+    pub verification_method: String,
+    /// Why the proof was created e.g. `assertionMethod`
+    pub proof_purpose: String,
+    /// The encoded proof
+    pub proof_value: String,
+}
+
+/// Split a JSON document into statements, one per leaf value, keyed by its JSON pointer.
+///
+/// Objects are descended into; every other value including arrays is a single statement
+/// whose value is its JSON serialization with sorted keys.
+/// The top level `proof` member is skipped.
+pub fn canonicalize(document: &Value) -> Result<BTreeMap<String, String>, BBSError> {
+    let object = document
+        .as_object()
+        .ok_or_else(|| general_error("Document must be a JSON object"))?;
+    let mut statements = BTreeMap::new();
+    for (key, value) in object {
+        if key != "proof" {
+            flatten(&format!("/{}", escape(key)), value, &mut statements);
+        }
+    }
+    if statements.is_empty() {
+        return Err(general_error("Document has no statements"));
+    }
+    Ok(statements)
+}
+
+/// Create a base proof over every statement in `document`.
+/// Statements selected by `mandatory_pointers` will be revealed in every derived proof.
+pub fn sign(
+    document: &Value,
+    mandatory_pointers: &[String],
+    verification_method: &str,
+    proof_purpose: &str,
+    dpk: &DeterministicPublicKey,
+    signkey: &SecretKey,
+) -> Result<DataIntegrityProof, BBSError> {
+    let statements = canonicalize(document)?;
+    selected(&statements, mandatory_pointers)?;
+
+    let messages = messages(
+        &statements,
+        mandatory_pointers,
+        verification_method,
+        proof_purpose,
+    );
+    let verkey = dpk.to_public_key(messages.len())?;
+    let signature = Signature::new(messages.as_slice(), signkey, &verkey)?;
+
+    let mut bytes = BASE_PROOF_HEADER.to_vec();
+    bytes.extend_from_slice(&signature.to_bytes_compressed_form()[..]);
+    bytes.extend_from_slice(&to_json_bytes(mandatory_pointers));
+
+    Ok(new_proof(verification_method, proof_purpose, &bytes))
+}
+
+/// Check a base proof created by `sign`
+pub fn verify(
+    document: &Value,
+    proof: &DataIntegrityProof,
+    dpk: &DeterministicPublicKey,
+) -> Result<bool, BBSError> {
+    let (signature, mandatory_pointers) = decode_base_proof(proof)?;
+    let statements = canonicalize(document)?;
+    let messages = messages(
+        &statements,
+        &mandatory_pointers,
+        &proof.verification_method,
+        &proof.proof_purpose,
+    );
+    let verkey = dpk.to_public_key(messages.len())?;
+    signature.verify(messages.as_slice(), &verkey)
+}
+
+/// Derive a proof from a base proof that reveals only the statements selected by
+/// the mandatory pointers and `selective_pointers`.
+/// Returns the document containing just the revealed statements and the derived proof.
+pub fn derive(
+    document: &Value,
+    proof: &DataIntegrityProof,
+    selective_pointers: &[String],
+    dpk: &DeterministicPublicKey,
+    nonce: &ProofNonce,
+) -> Result<(Value, DataIntegrityProof), BBSError> {
+    if !verify(document, proof, dpk)? {
+        return Err(general_error("Invalid base proof"));
+    }
+    let (signature, mandatory_pointers) = decode_base_proof(proof)?;
+    let statements = canonicalize(document)?;
+
+    let mut revealed = selected(&statements, &mandatory_pointers)?;
+    revealed.extend(selected(&statements, selective_pointers)?);
+
+    let messages = messages(
+        &statements,
+        &mandatory_pointers,
+        &proof.verification_method,
+        &proof.proof_purpose,
+    );
+    let verkey = dpk.to_public_key(messages.len())?;
+    // The proof options are always revealed
+    let proof_messages = messages
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            if i == 0 || revealed.contains(&(i - 1)) {
+                pm_revealed_raw!(*m)
+            } else {
+                pm_hidden_raw!(*m)
+            }
+        })
+        .collect::<Vec<ProofMessage>>();
+
+    let pok = PoKOfSignature::init(&signature, &verkey, proof_messages.as_slice())?;
+    let challenge = Prover::create_challenge_hash(std::slice::from_ref(&pok), None, nonce)?;
+    let pok_proof = pok.gen_proof(&challenge)?;
+
+    let mut bytes = DERIVED_PROOF_HEADER.to_vec();
+    bytes.extend_from_slice(&(messages.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&(revealed.len() as u32).to_be_bytes());
+    for i in &revealed {
+        bytes.extend_from_slice(&(*i as u32).to_be_bytes());
+    }
+    let pointers = to_json_bytes(&mandatory_pointers);
+    bytes.extend_from_slice(&(pointers.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&pointers);
+    bytes.extend_from_slice(&pok_proof.to_bytes_compressed_form());
+
+    let mut revealed_document = Value::Object(Map::new());
+    for (i, (pointer, value)) in statements.iter().enumerate() {
+        if revealed.contains(&i) {
+            insert(&mut revealed_document, pointer, value)?;
+        }
+    }
+
+    Ok((
+        revealed_document,
+        new_proof(&proof.verification_method, &proof.proof_purpose, &bytes),
+    ))
+}
+
+/// Check a derived proof created by `derive` for the verifier's `nonce`
+pub fn verify_derived(
+    document: &Value,
+    proof: &DataIntegrityProof,
+    dpk: &DeterministicPublicKey,
+    nonce: &ProofNonce,
+) -> Result<bool, BBSError> {
+    let bytes = decode_proof_value(proof, DERIVED_PROOF_HEADER)?;
+    let mut reader = Reader(bytes.as_slice());
+    let message_count = reader.read_u32()? as usize;
+    let revealed_count = reader.read_u32()? as usize;
+    let mut revealed = Vec::new();
+    for _ in 0..revealed_count {
+        revealed.push(reader.read_u32()? as usize);
+    }
+    let pointers_len = reader.read_u32()? as usize;
+    let mandatory_pointers = from_json_bytes(reader.read(pointers_len)?)?;
+    let pok_proof = PoKOfSignatureProof::from_bytes_compressed_form(reader.0)?;
+
+    let statements = canonicalize(document)?;
+    if statements.len() != revealed.len()
+        || revealed.windows(2).any(|w| w[0] >= w[1])
+        || revealed.iter().any(|i| i + 1 >= message_count)
+    {
+        return Ok(false);
+    }
+    // Every mandatory statement has to be in the revealed document
+    let disclosed = statements.keys().collect::<Vec<&String>>();
+    for pointer in &mandatory_pointers {
+        if !disclosed.iter().any(|p| selects(pointer, p)) {
+            return Ok(false);
+        }
+    }
+
+    let statement_messages = messages(
+        &statements,
+        &mandatory_pointers,
+        &proof.verification_method,
+        &proof.proof_purpose,
+    );
+    let mut revealed_messages = BTreeMap::new();
+    revealed_messages.insert(0, statement_messages[0]);
+    for (i, m) in revealed.iter().zip(statement_messages[1..].iter()) {
+        revealed_messages.insert(i + 1, *m);
+    }
+
+    let proof_request = ProofRequest {
+        revealed_messages: revealed_messages.keys().copied().collect(),
+        verification_key: dpk.to_public_key(message_count)?,
+    };
+    let signature_proof = SignatureProof {
+        revealed_messages,
+        proof: pok_proof,
+    };
+    match Verifier::verify_signature_pok(&proof_request, &signature_proof, nonce) {
+        Ok(_) => Ok(true),
+        Err(e) => match e.kind() {
+            BBSErrorKind::InvalidProof { .. } => Ok(false),
+            _ => Err(e),
+        },
+    }
+}
+
+/// The messages signed for a document. The first binds the proof options,
+/// the rest are the statements in pointer order.
+fn messages(
+    statements: &BTreeMap<String, String>,
+    mandatory_pointers: &[String],
+    verification_method: &str,
+    proof_purpose: &str,
+) -> Vec<SignatureMessage> {
+    let options = json!({
+        "type": PROOF_TYPE,
+        "cryptosuite": CRYPTOSUITE,
+        "verificationMethod": verification_method,
+        "proofPurpose": proof_purpose,
+        "mandatoryPointers": mandatory_pointers,
+    });
+    let mut messages = Vec::with_capacity(statements.len() + 1);
+    messages.push(SignatureMessage::hash(options.to_string()));
+    for (pointer, value) in statements {
+        messages.push(SignatureMessage::hash(format!("{} {}", pointer, value)));
+    }
+    messages
+}
+
+/// Indices of the statements selected by `pointers`
+fn selected(
+    statements: &BTreeMap<String, String>,
+    pointers: &[String],
+) -> Result<BTreeSet<usize>, BBSError> {
+    let mut indices = BTreeSet::new();
+    for pointer in pointers {
+        let mut found = false;
+        for (i, p) in statements.keys().enumerate() {
+            if selects(pointer, p) {
+                indices.insert(i);
+                found = true;
+            }
+        }
+        if !found {
+            return Err(general_error(&format!(
+                "Pointer {} does not select any statement",
+                pointer
+            )));
+        }
+    }
+    Ok(indices)
+}
+
+/// Whether `pointer` selects the statement at `statement` or one of its parents
+fn selects(pointer: &str, statement: &str) -> bool {
+    statement == pointer
+        || (statement.starts_with(pointer) && statement[pointer.len()..].starts_with('/'))
+}
+
+fn flatten(pointer: &str, value: &Value, statements: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object {
+                flatten(&format!("{}/{}", pointer, escape(key)), value, statements);
+            }
+        }
+        _ => {
+            statements.insert(pointer.to_string(), value.to_string());
+        }
+    }
+}
+
+fn insert(document: &mut Value, pointer: &str, value: &str) -> Result<(), BBSError> {
+    let value: Value = serde_json::from_str(value).map_err(|e| general_error(&e.to_string()))?;
+    let keys = pointer
+        .split('/')
+        .skip(1)
+        .map(unescape)
+        .collect::<Vec<String>>();
+    let (last, parents) = keys
+        .split_last()
+        .ok_or_else(|| general_error("Empty pointer"))?;
+    let mut current = document;
+    for key in parents {
+        current = current
+            .as_object_mut()
+            .ok_or_else(|| general_error("Conflicting pointers"))?
+            .entry(key.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+    current
+        .as_object_mut()
+        .ok_or_else(|| general_error("Conflicting pointers"))?
+        .insert(last.clone(), value);
+    Ok(())
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(key: &str) -> String {
+    key.replace("~1", "/").replace("~0", "~")
+}
+
+fn new_proof(verification_method: &str, proof_purpose: &str, bytes: &[u8]) -> DataIntegrityProof {
+    DataIntegrityProof {
+        proof_type: PROOF_TYPE.to_string(),
+        cryptosuite: CRYPTOSUITE.to_string(),
+        verification_method: verification_method.to_string(),
+        proof_purpose: proof_purpose.to_string(),
+        proof_value: format!("u{}", base64url_encode(bytes)),
+    }
+}
+
+fn decode_base_proof(proof: &DataIntegrityProof) -> Result<(Signature, Vec<String>), BBSError> {
+    let bytes = decode_proof_value(proof, BASE_PROOF_HEADER)?;
+    if bytes.len() < SIGNATURE_COMPRESSED_SIZE {
+        return Err(BBSErrorKind::SignatureIncorrectSize(bytes.len()).into());
+    }
+    let signature = Signature::try_from(&bytes[..SIGNATURE_COMPRESSED_SIZE])?;
+    let mandatory_pointers = from_json_bytes(&bytes[SIGNATURE_COMPRESSED_SIZE..])?;
+    Ok((signature, mandatory_pointers))
+}
+
+fn decode_proof_value(proof: &DataIntegrityProof, header: [u8; 3]) -> Result<Vec<u8>, BBSError> {
+    if proof.proof_type != PROOF_TYPE || proof.cryptosuite != CRYPTOSUITE {
+        return Err(general_error("Unsupported proof type"));
+    }
+    if !proof.proof_value.starts_with('u') {
+        return Err(general_error("Proof value must be base64url multibase"));
+    }
+    let bytes = base64url_decode(&proof.proof_value[1..])?;
+    if bytes.len() < header.len() || bytes[..header.len()] != header {
+        return Err(general_error("Unexpected proof value header"));
+    }
+    Ok(bytes[header.len()..].to_vec())
+}
+
+fn to_json_bytes(pointers: &[String]) -> Vec<u8> {
+    serde_json::to_vec(pointers).unwrap()
+}
+
+fn from_json_bytes(bytes: &[u8]) -> Result<Vec<String>, BBSError> {
+    serde_json::from_slice(bytes).map_err(|e| general_error(&e.to_string()))
+}
+
+fn general_error(msg: &str) -> BBSError {
+    BBSErrorKind::GeneralError {
+        msg: msg.to_string(),
+    }
+    .into()
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, len: usize) -> Result<&'a [u8], BBSError> {
+        if self.0.len() < len {
+            return Err(BBSErrorKind::InvalidNumberOfBytes(len, self.0.len()).into());
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, BBSError> {
+        Ok(u32::from_be_bytes(*array_ref![self.read(4)?, 0, 4]))
+    }
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn base64url_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() / 3 * 4 + 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..=chunk.len() {
+            out.push(BASE64URL[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+fn base64url_decode(data: &str) -> Result<Vec<u8>, BBSError> {
+    let invalid = || general_error("Invalid base64url");
+    if data.len() % 4 == 1 {
+        return Err(invalid());
+    }
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let v = BASE64URL.iter().position(|b| b == c).ok_or_else(invalid)? as u32;
+            n |= v << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RandomElem;
+
+    fn credential() -> Value {
+        json!({
+            "@context": ["https://www.w3.org/ns/credentials/v2"],
+            "type": ["VerifiableCredential"],
+            "issuer": "did:example:issuer",
+            "credentialSubject": {
+                "id": "did:example:holder",
+                "name": "Alice",
+                "birthDate": "1990-06-15",
+                "address": {
+                    "city": "Springfield",
+                    "street/number": "Main 1"
+                }
+            }
+        })
+    }
+
+    fn pointers(p: &[&str]) -> Vec<String> {
+        p.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn base64url_round_trip() {
+        for len in 0..10 {
+            let data = (0..len).map(|i| (i * 37) as u8).collect::<Vec<u8>>();
+            let encoded = base64url_encode(&data);
+            assert!(!encoded.contains('='));
+            assert_eq!(data, base64url_decode(&encoded).unwrap());
+        }
+        assert_eq!("Zm9vYg", base64url_encode(b"foob"));
+        assert!(base64url_decode("Zm9v+").is_err());
+    }
+
+    #[test]
+    fn canonicalize_documents() {
+        let statements = canonicalize(&credential()).unwrap();
+        assert_eq!(8, statements.len());
+        assert_eq!(
+            "\"Main 1\"",
+            statements["/credentialSubject/address/street~1number"]
+        );
+        assert_eq!("[\"VerifiableCredential\"]", statements["/type"]);
+
+        let mut with_proof = credential();
+        with_proof["proof"] = json!({"type": "DataIntegrityProof"});
+        assert_eq!(statements, canonicalize(&with_proof).unwrap());
+        assert!(canonicalize(&json!([1, 2])).is_err());
+    }
+
+    #[test]
+    fn sign_derive_and_verify() {
+        let (dpk, sk) = DeterministicPublicKey::new(None).unwrap();
+        let document = credential();
+        let mandatory = pointers(&["/issuer", "/type"]);
+        let proof = sign(
+            &document,
+            &mandatory,
+            "did:example:issuer#key-1",
+            "assertionMethod",
+            &dpk,
+            &sk,
+        )
+        .unwrap();
+        assert!(verify(&document, &proof, &dpk).unwrap());
+
+        let serialized = serde_json::to_string(&proof).unwrap();
+        assert!(serialized.contains("\"cryptosuite\":\"bbs-2023\""));
+        assert!(serialized.contains("\"proofValue\":\"u"));
+        let proof: DataIntegrityProof = serde_json::from_str(&serialized).unwrap();
+
+        let mut tampered = document.clone();
+        tampered["credentialSubject"]["name"] = json!("Mallory");
+        assert!(!verify(&tampered, &proof, &dpk).unwrap());
+
+        let nonce = ProofNonce::random();
+        let (revealed, derived) = derive(
+            &document,
+            &proof,
+            &pointers(&["/credentialSubject/address"]),
+            &dpk,
+            &nonce,
+        )
+        .unwrap();
+        assert_eq!(
+            json!({
+                "type": ["VerifiableCredential"],
+                "issuer": "did:example:issuer",
+                "credentialSubject": {
+                    "address": {
+                        "city": "Springfield",
+                        "street/number": "Main 1"
+                    }
+                }
+            }),
+            revealed
+        );
+        assert!(verify_derived(&revealed, &derived, &dpk, &nonce).unwrap());
+        assert!(!verify_derived(&revealed, &derived, &dpk, &ProofNonce::random()).unwrap());
+
+        // Changing a revealed value or leaving out a mandatory one is detected
+        let mut tampered = revealed.clone();
+        tampered["credentialSubject"]["address"]["city"] = json!("Shelbyville");
+        assert!(!verify_derived(&tampered, &derived, &dpk, &nonce).unwrap());
+        let mut missing = revealed.clone();
+        missing.as_object_mut().unwrap().remove("issuer");
+        missing["credentialSubject"]["name"] = json!("Alice");
+        assert!(!verify_derived(&missing, &derived, &dpk, &nonce).unwrap());
+
+        // A derived proof is not a base proof and selections must exist
+        assert!(verify(&revealed, &derived, &dpk).is_err());
+        assert!(derive(&document, &proof, &pointers(&["/missing"]), &dpk, &nonce).is_err());
+    }
+}
@@ -1,5 +1,7 @@
+use crate::credential::prelude::*;
 use crate::errors::prelude::*;
 use crate::keys::prelude::*;
+use crate::schema::CredentialSchema;
 use crate::signature::prelude::*;
 /// The issuer generates keys and uses those to sign
 /// credentials. There are two types of public keys:
@@ -9,6 +11,8 @@ use crate::signature::prelude::*;
 /// `PublicKey` later. The latter is primarily used for storing a shorter
 /// key and looks just like a regular ECC key.
 use crate::{BlindSignatureContext, ProofNonce, RandomElem, SignatureMessage};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 use std::collections::{BTreeMap, BTreeSet};
 
 /// This struct represents an Issuer of signatures or Signer.
@@ -30,6 +34,17 @@ impl Issuer {
         DeterministicPublicKey::new(option)
     }
 
+    /// Create the keys for issuing credentials over `schema`
+    pub fn new_credential_def(
+        schema: &CredentialSchema,
+    ) -> Result<(CredentialPublicKey, CredentialPrivateKey), BBSError> {
+        let (public_key, secret_key) = generate(schema.len())?;
+        Ok((
+            CredentialPublicKey::new(schema.clone(), public_key)?,
+            CredentialPrivateKey(secret_key),
+        ))
+    }
+
     /// Create a signature with no hidden messages
     pub fn sign(
         messages: &[SignatureMessage],
@@ -39,6 +54,25 @@ impl Issuer {
         Signature::new(messages, signkey, verkey)
     }
 
+    /// Create signatures for many credentials with the same keys.
+    /// The signatures are computed in parallel when the `rayon` feature is enabled.
+    pub fn sign_batch(
+        messages: &[Vec<SignatureMessage>],
+        signkey: &SecretKey,
+        verkey: &PublicKey,
+    ) -> Result<Vec<Signature>, BBSError> {
+        signkey.validate()?;
+        verkey.validate()?;
+
+        #[cfg(feature = "rayon")]
+        let iter = messages.par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let iter = messages.iter();
+
+        iter.map(|m| Signature::new(m.as_slice(), signkey, verkey))
+            .collect()
+    }
+
     /// Verify a proof of committed messages and generate a blind signature
     pub fn blind_sign(
         ctx: &BlindSignatureContext,
@@ -58,6 +92,56 @@ impl Issuer {
         }
     }
 
+    /// Generate blind signatures for many requests with the same keys.
+    /// Each request is the holder's context, the known messages and the nonce
+    /// given to that holder. Fails if any proof of committed messages is invalid.
+    /// The signatures are computed in parallel when the `rayon` feature is enabled.
+    pub fn blind_sign_batch(
+        requests: &[(
+            &BlindSignatureContext,
+            &BTreeMap<usize, SignatureMessage>,
+            &ProofNonce,
+        )],
+        signkey: &SecretKey,
+        verkey: &PublicKey,
+    ) -> Result<Vec<BlindSignature>, BBSError> {
+        signkey.validate()?;
+        verkey.validate()?;
+
+        #[cfg(feature = "rayon")]
+        let iter = requests.par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let iter = requests.iter();
+
+        iter.map(|(ctx, messages, nonce)| Self::blind_sign(ctx, messages, signkey, verkey, nonce))
+            .collect()
+    }
+
+    /// Sign a credential. `values` holds the attributes known to the issuer,
+    /// `blinded_credential_secrets` must commit to all the others.
+    pub fn sign_credential(
+        blinded_credential_secrets: &BlindSignatureContext,
+        values: &CredentialValues,
+        nonce: &ProofNonce,
+        pub_key: &CredentialPublicKey,
+        priv_key: &CredentialPrivateKey,
+    ) -> Result<BlindSignature, BBSError> {
+        if values.attrs_values().values().any(|v| v.is_hidden()) {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Issuer can not sign hidden values".to_string(),
+            }
+            .into());
+        }
+        let known = values.by_index(pub_key.schema(), false)?;
+        Self::blind_sign(
+            blinded_credential_secrets,
+            &known,
+            &priv_key.0,
+            pub_key.public_key(),
+            nonce,
+        )
+    }
+
     /// Create a nonce used for the blind signing context
     pub fn generate_signing_nonce() -> ProofNonce {
         ProofNonce::random()
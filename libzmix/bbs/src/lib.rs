@@ -84,6 +84,11 @@ pub mod messages;
 /// Macros and classes used for creating proofs of knowledge
 #[macro_use]
 pub mod pok_vc;
+/// Anonymous credentials with the same shape as CL credentials
+pub mod credential;
+/// W3C Data Integrity `bbs-2023` proofs over JSON credentials
+#[cfg(feature = "data-integrity")]
+pub mod data_integrity;
 /// The errors that BBS+ throws
 pub mod errors;
 /// Represents steps taken by the issuer to create a BBS+ signature
@@ -91,11 +96,23 @@ pub mod errors;
 pub mod issuer;
 /// BBS+ key classes
 pub mod keys;
+/// Proofs linking hidden messages to external Pedersen commitments
+pub mod pedersen;
 /// Methods and structs for creating signature proofs of knowledge
 pub mod pok_sig;
+/// Presentations over several credentials bound to the same link secret
+pub mod presentation;
+/// Versioned messages exchanged during issuance and presentation
+pub mod protocol;
 /// Represents steps taken by the prover to receive a BBS+ signature
 /// and generate ZKPs
 pub mod prover;
+/// Domain specific pseudonyms derived from the link secret
+pub mod pseudonym;
+/// Accumulator based revocation registries and non-revocation proofs
+pub mod revocation;
+/// Canonical attribute ordering for credentials
+pub mod schema;
 /// Methods and structs for creating signatures
 pub mod signature;
 /// Represents steps taken by the verifier to request signature proofs of knowledge
@@ -764,12 +781,14 @@ fn rand_non_zero_fr() -> Fr {
 /// Convenience importer
 pub mod prelude {
     pub use super::{
-        errors::prelude::*, issuer::Issuer, keys::prelude::*, messages::*, pok_sig::prelude::*,
-        pok_vc::prelude::*, prover::Prover, signature::prelude::*, verifier::Verifier,
-        BlindSignatureContext, Commitment, CommitmentBuilder, GeneratorG1, GeneratorG2, HashElem,
-        ProofChallenge, ProofNonce, ProofRequest, RandomElem, SignatureBlinding, SignatureMessage,
-        SignatureProof, ToVariableLengthBytes, FR_COMPRESSED_SIZE, G1_COMPRESSED_SIZE,
-        G1_UNCOMPRESSED_SIZE, G2_COMPRESSED_SIZE, G2_UNCOMPRESSED_SIZE,
+        credential::prelude::*, errors::prelude::*, issuer::Issuer, keys::prelude::*, messages::*,
+        pedersen::prelude::*, pok_sig::prelude::*, pok_vc::prelude::*, presentation::prelude::*,
+        prover::Prover, pseudonym::prelude::*, revocation::prelude::*, schema::prelude::*,
+        signature::prelude::*, verifier::Verifier, BlindSignatureContext, Commitment,
+        CommitmentBuilder, GeneratorG1, GeneratorG2, HashElem, ProofChallenge, ProofNonce,
+        ProofRequest, RandomElem, SignatureBlinding, SignatureMessage, SignatureProof,
+        ToVariableLengthBytes, FR_COMPRESSED_SIZE, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE,
+        G2_COMPRESSED_SIZE, G2_UNCOMPRESSED_SIZE,
     };
 }
 
@@ -0,0 +1,283 @@
+use crate::errors::prelude::*;
+use crate::pok_sig::prelude::*;
+use crate::pok_vc::prelude::*;
+use crate::{
+    Commitment, CommitmentBuilder, GeneratorG1, HashElem, ProofChallenge, ProofNonce,
+    SignatureBlinding, SignatureMessage,
+};
+use pairing_plus::serdes::SerDes;
+use serde::{Deserialize, Serialize};
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{PedersenGenerators, PoKOfPedersenCommitment, PoKOfPedersenCommitmentProof};
+}
+
+/// Generators for a Pedersen commitment to a single message `C = g^m * h^r`.
+/// These commitments are what other proof systems like bulletproof range proofs
+/// take as input, so a message hidden in a signature proof can be handed to them.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct PedersenGenerators {
+    /// Base for the message
+    pub g: GeneratorG1,
+    /// Base for the blinding factor
+    pub h: GeneratorG1,
+}
+
+impl PedersenGenerators {
+    /// Use generators from another proof system
+    pub fn new(g: GeneratorG1, h: GeneratorG1) -> Self {
+        Self { g, h }
+    }
+
+    /// Derive both generators by hashing `label` so that no one knows the
+    /// discrete log of `h` with respect to `g`
+    pub fn hash<I: AsRef<[u8]>>(label: I) -> Self {
+        let label = label.as_ref();
+        Self {
+            g: GeneratorG1::hash([label, b" : g"].concat()),
+            h: GeneratorG1::hash([label, b" : h"].concat()),
+        }
+    }
+
+    /// Compute the commitment `g^message * h^blinding`
+    pub fn commit(&self, message: &SignatureMessage, blinding: &SignatureBlinding) -> Commitment {
+        let mut builder = CommitmentBuilder::new();
+        builder.add(self.g, message);
+        builder.add(self.h, blinding);
+        builder.finalize()
+    }
+}
+
+/// Proof of knowledge of the opening of a Pedersen commitment whose message is also
+/// hidden in a signature proof of knowledge.
+///
+/// The link works by sharing the blinding factor used for the message in both Schnorr proofs.
+/// The same `ProofNonce` must be given here and as the external blinding for the message
+/// in the signature proof i.e. `pm_hidden_raw!(message, message_blinding)`.
+/// Both proofs must then be created with the same challenge and their responses for the
+/// message will be equal if and only if the committed message is the signed one.
+#[derive(Debug, Clone)]
+pub struct PoKOfPedersenCommitment {
+    commitment: Commitment,
+    pok_vc: ProverCommittedG1,
+    secrets: Vec<SignatureMessage>,
+}
+
+impl PoKOfPedersenCommitment {
+    /// Creates the initial proof data before a Fiat-Shamir calculation
+    pub fn init(
+        generators: &PedersenGenerators,
+        commitment: &Commitment,
+        message: &SignatureMessage,
+        blinding: &SignatureBlinding,
+        message_blinding: &ProofNonce,
+    ) -> Self {
+        let mut committing = ProverCommittingG1::new();
+        committing.commit_with(generators.g, message_blinding);
+        committing.commit(generators.h);
+        Self {
+            commitment: *commitment,
+            pok_vc: committing.finish(),
+            secrets: vec![*message, SignatureMessage(blinding.0)],
+        }
+    }
+
+    /// Return byte representation of public elements so they can be used for challenge computation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.pok_vc.to_bytes();
+        self.commitment.0.serialize(&mut bytes, false).unwrap();
+        bytes
+    }
+
+    /// Given the challenge value, compute the s values for Fiat-Shamir and return the actual
+    /// proof to be sent to the verifier
+    pub fn gen_proof(
+        self,
+        challenge_hash: &ProofChallenge,
+    ) -> Result<PoKOfPedersenCommitmentProof, BBSError> {
+        let proof = self
+            .pok_vc
+            .gen_proof(challenge_hash, self.secrets.as_slice())?;
+        Ok(PoKOfPedersenCommitmentProof {
+            commitment: self.commitment,
+            proof,
+        })
+    }
+}
+
+/// The proof of knowledge of a Pedersen commitment opening sent to the verifier
+#[derive(Debug, Clone)]
+pub struct PoKOfPedersenCommitmentProof {
+    /// The commitment whose opening is proved
+    pub(crate) commitment: Commitment,
+    /// Proof of relation C == g^m * h^r
+    pub(crate) proof: ProofG1,
+}
+
+impl PoKOfPedersenCommitmentProof {
+    /// The commitment this proof is about
+    pub fn commitment(&self) -> Commitment {
+        self.commitment
+    }
+
+    /// Return bytes that need to be hashed for generating challenge.
+    /// These are the same as `PoKOfPedersenCommitment::to_bytes`
+    pub fn get_bytes_for_challenge(&self, generators: &PedersenGenerators) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        generators.g.0.serialize(&mut bytes, false).unwrap();
+        generators.h.0.serialize(&mut bytes, false).unwrap();
+        self.proof.commitment.serialize(&mut bytes, false).unwrap();
+        self.commitment.0.serialize(&mut bytes, false).unwrap();
+        bytes
+    }
+
+    /// Get the response for the committed message.
+    /// Used when comparing message equality
+    pub fn get_resp_for_message(&self) -> SignatureMessage {
+        SignatureMessage(self.proof.responses[0])
+    }
+
+    /// Validate the proof of knowledge of the commitment opening
+    pub fn verify(
+        &self,
+        generators: &PedersenGenerators,
+        challenge: &ProofChallenge,
+    ) -> Result<bool, BBSError> {
+        Ok(self
+            .proof
+            .verify(&[generators.g, generators.h], &self.commitment, challenge)?)
+    }
+
+    /// Validate the proof and check the committed message equals a hidden message in
+    /// `signature_proof` which was verified with the same `challenge`.
+    /// `hidden_msg_idx` is the position of the message among the hidden messages,
+    /// as with `PoKOfSignatureProof::get_resp_for_message`.
+    pub fn verify_linked(
+        &self,
+        generators: &PedersenGenerators,
+        challenge: &ProofChallenge,
+        signature_proof: &PoKOfSignatureProof,
+        hidden_msg_idx: usize,
+    ) -> Result<bool, BBSError> {
+        if !self.verify(generators, challenge)? {
+            return Ok(false);
+        }
+        Ok(signature_proof.get_resp_for_message(hidden_msg_idx)? == self.get_resp_for_message())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::generate;
+    use crate::messages::*;
+    use crate::signature::Signature;
+    use crate::RandomElem;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn pok_commitment_linked_to_signature() {
+        let message_count = 4;
+        let messages: Vec<SignatureMessage> = (0..message_count)
+            .map(|_| SignatureMessage::random())
+            .collect();
+        let (verkey, signkey) = generate(message_count).unwrap();
+        let sig = Signature::new(messages.as_slice(), &signkey, &verkey).unwrap();
+
+        let generators = PedersenGenerators::hash(b"range proof generators");
+        let blinding = SignatureBlinding::random();
+        let commitment = generators.commit(&messages[2], &blinding);
+
+        let message_blinding = ProofNonce::random();
+        let proof_messages = vec![
+            pm_revealed_raw!(messages[0]),
+            pm_hidden_raw!(messages[1]),
+            pm_hidden_raw!(messages[2], message_blinding),
+            pm_revealed_raw!(messages[3]),
+        ];
+        let mut revealed_msgs = BTreeMap::new();
+        revealed_msgs.insert(0, messages[0]);
+        revealed_msgs.insert(3, messages[3]);
+
+        let pok_sig = PoKOfSignature::init(&sig, &verkey, proof_messages.as_slice()).unwrap();
+        let pok_com = PoKOfPedersenCommitment::init(
+            &generators,
+            &commitment,
+            &messages[2],
+            &blinding,
+            &message_blinding,
+        );
+
+        let mut challenge_bytes = pok_sig.to_bytes();
+        challenge_bytes.extend_from_slice(pok_com.to_bytes().as_slice());
+        let challenge = ProofChallenge::hash(&challenge_bytes);
+
+        let sig_proof = pok_sig.gen_proof(&challenge).unwrap();
+        let com_proof = pok_com.gen_proof(&challenge).unwrap();
+
+        // Verifier recomputes the challenge from the proofs
+        let mut challenge_bytes =
+            sig_proof.get_bytes_for_challenge(revealed_msgs.keys().cloned().collect(), &verkey);
+        challenge_bytes
+            .extend_from_slice(com_proof.get_bytes_for_challenge(&generators).as_slice());
+        let challenge_verifier = ProofChallenge::hash(&challenge_bytes);
+
+        assert!(sig_proof
+            .verify(&verkey, &revealed_msgs, &challenge_verifier)
+            .unwrap()
+            .is_valid());
+        assert!(com_proof.verify(&generators, &challenge_verifier).unwrap());
+        // messages[2] is the second hidden message
+        assert!(com_proof
+            .verify_linked(&generators, &challenge_verifier, &sig_proof, 1)
+            .unwrap());
+        assert!(!com_proof
+            .verify_linked(&generators, &challenge_verifier, &sig_proof, 0)
+            .unwrap());
+        assert!(com_proof
+            .verify_linked(&generators, &challenge_verifier, &sig_proof, 2)
+            .is_err());
+        assert!(!com_proof
+            .verify(&generators, &ProofChallenge::random())
+            .unwrap());
+    }
+
+    #[test]
+    fn pok_commitment_to_different_message_not_linked() {
+        let messages: Vec<SignatureMessage> = (0..2).map(|_| SignatureMessage::random()).collect();
+        let (verkey, signkey) = generate(2).unwrap();
+        let sig = Signature::new(messages.as_slice(), &signkey, &verkey).unwrap();
+
+        let generators = PedersenGenerators::hash(b"range proof generators");
+        let blinding = SignatureBlinding::random();
+        let other = SignatureMessage::random();
+        let commitment = generators.commit(&other, &blinding);
+
+        let message_blinding = ProofNonce::random();
+        let proof_messages = vec![
+            pm_hidden_raw!(messages[0], message_blinding),
+            pm_hidden_raw!(messages[1]),
+        ];
+        let pok_sig = PoKOfSignature::init(&sig, &verkey, proof_messages.as_slice()).unwrap();
+        let pok_com = PoKOfPedersenCommitment::init(
+            &generators,
+            &commitment,
+            &other,
+            &blinding,
+            &message_blinding,
+        );
+
+        let mut challenge_bytes = pok_sig.to_bytes();
+        challenge_bytes.extend_from_slice(pok_com.to_bytes().as_slice());
+        let challenge = ProofChallenge::hash(&challenge_bytes);
+        let sig_proof = pok_sig.gen_proof(&challenge).unwrap();
+        let com_proof = pok_com.gen_proof(&challenge).unwrap();
+
+        // Both proofs are valid on their own but the messages differ
+        assert!(com_proof.verify(&generators, &challenge).unwrap());
+        assert!(!com_proof
+            .verify_linked(&generators, &challenge, &sig_proof, 0)
+            .unwrap());
+    }
+}
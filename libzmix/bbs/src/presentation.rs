@@ -0,0 +1,267 @@
+use crate::errors::prelude::*;
+use crate::messages::*;
+use crate::pok_sig::prelude::*;
+use crate::prover::Prover;
+use crate::signature::prelude::*;
+use crate::verifier::Verifier;
+use crate::{ProofNonce, ProofRequest, RandomElem, SignatureMessage, SignatureProof};
+use std::collections::BTreeMap;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{Presentation, PresentationBuilder, PresentationRequest};
+}
+
+/// What a verifier asks for from one credential in a presentation
+#[derive(Debug, Clone)]
+pub struct PresentationRequest {
+    /// The messages to reveal and the issuer's public key
+    pub proof_request: ProofRequest,
+    /// The index of the link secret among the signed messages
+    pub link_secret_index: usize,
+}
+
+impl PresentationRequest {
+    /// Position of the link secret among the hidden messages
+    fn hidden_link_secret_index(&self) -> Result<usize, BBSError> {
+        if self
+            .proof_request
+            .revealed_messages
+            .contains(&self.link_secret_index)
+        {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Link secret at index {} can not be revealed",
+                    self.link_secret_index
+                ),
+            }
+            .into());
+        }
+        Ok(self.link_secret_index
+            - self
+                .proof_request
+                .revealed_messages
+                .range(..self.link_secret_index)
+                .count())
+    }
+}
+
+/// Builds a presentation over several credentials which proves they were all
+/// issued to the same hidden link secret.
+///
+/// Every signature proof uses the same blinding for the link secret and all of them
+/// share one challenge, so the responses for the link secret are equal if and only if
+/// the signed link secrets are.
+#[derive(Debug)]
+pub struct PresentationBuilder {
+    link_secret: SignatureMessage,
+    link_secret_blinding: ProofNonce,
+    pok_sigs: Vec<PoKOfSignature>,
+}
+
+impl PresentationBuilder {
+    /// Start a presentation for credentials signed over `link_secret`
+    pub fn new(link_secret: SignatureMessage) -> Self {
+        Self {
+            link_secret,
+            link_secret_blinding: ProofNonce::random(),
+            pok_sigs: Vec::new(),
+        }
+    }
+
+    /// Add a credential to the presentation. Messages in `request` are revealed,
+    /// all others stay hidden.
+    pub fn add(
+        &mut self,
+        request: &PresentationRequest,
+        signature: &Signature,
+        messages: &[SignatureMessage],
+    ) -> Result<(), BBSError> {
+        request.hidden_link_secret_index()?;
+        if messages.get(request.link_secret_index) != Some(&self.link_secret) {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Message at index {} is not the link secret",
+                    request.link_secret_index
+                ),
+            }
+            .into());
+        }
+
+        let proof_messages = messages
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                if i == request.link_secret_index {
+                    pm_hidden_raw!(*m, self.link_secret_blinding)
+                } else if request.proof_request.revealed_messages.contains(&i) {
+                    pm_revealed_raw!(*m)
+                } else {
+                    pm_hidden_raw!(*m)
+                }
+            })
+            .collect::<Vec<ProofMessage>>();
+
+        self.pok_sigs.push(Prover::commit_signature_pok(
+            &request.proof_request,
+            proof_messages.as_slice(),
+            signature,
+        )?);
+        Ok(())
+    }
+
+    /// Compute the shared challenge and generate the proofs
+    pub fn finalize(self, nonce: &ProofNonce) -> Result<Presentation, BBSError> {
+        let challenge = Prover::create_challenge_hash(self.pok_sigs.as_slice(), None, nonce)?;
+        let proofs = self
+            .pok_sigs
+            .into_iter()
+            .map(|p| Prover::generate_signature_pok(p, &challenge))
+            .collect::<Result<Vec<SignatureProof>, BBSError>>()?;
+        Ok(Presentation { proofs })
+    }
+}
+
+/// Signature proofs over several credentials bound to the same link secret
+#[derive(Debug, Clone)]
+pub struct Presentation {
+    /// One proof per credential, in the order they were added
+    pub proofs: Vec<SignatureProof>,
+}
+
+impl Presentation {
+    /// Check every signature proof against the shared challenge and that all
+    /// credentials hide the same link secret.
+    /// Returns the revealed messages for each credential.
+    pub fn verify(
+        &self,
+        requests: &[PresentationRequest],
+        nonce: &ProofNonce,
+    ) -> Result<Vec<BTreeMap<usize, SignatureMessage>>, BBSError> {
+        if requests.len() != self.proofs.len() || requests.is_empty() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Expected {} proofs, found {}",
+                    requests.len(),
+                    self.proofs.len()
+                ),
+            }
+            .into());
+        }
+
+        let proof_requests = requests
+            .iter()
+            .map(|r| r.proof_request.clone())
+            .collect::<Vec<ProofRequest>>();
+        let challenge = Verifier::create_challenge_hash(
+            self.proofs.as_slice(),
+            proof_requests.as_slice(),
+            nonce,
+            None,
+        )?;
+
+        let mut link_secret_response = None;
+        for (proof, request) in self.proofs.iter().zip(requests.iter()) {
+            let status = proof.proof.verify(
+                &request.proof_request.verification_key,
+                &proof.revealed_messages,
+                &challenge,
+            )?;
+            if !status.is_valid() {
+                return Err(BBSErrorKind::InvalidProof { status }.into());
+            }
+
+            let response = proof
+                .proof
+                .get_resp_for_message(request.hidden_link_secret_index()?)?;
+            match link_secret_response {
+                None => link_secret_response = Some(response),
+                Some(r) if r == response => {}
+                Some(_) => {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: "Credentials are not bound to the same link secret".to_string(),
+                    }
+                    .into())
+                }
+            }
+        }
+
+        Ok(self
+            .proofs
+            .iter()
+            .map(|p| p.revealed_messages.clone())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::generate;
+    use crate::keys::PublicKey;
+
+    fn issue(
+        link_secret: SignatureMessage,
+        message_count: usize,
+    ) -> (PublicKey, Signature, Vec<SignatureMessage>) {
+        let (verkey, signkey) = generate(message_count).unwrap();
+        let mut messages = vec![link_secret];
+        messages.extend((1..message_count).map(|_| SignatureMessage::random()));
+        let signature = Signature::new(messages.as_slice(), &signkey, &verkey).unwrap();
+        (verkey, signature, messages)
+    }
+
+    fn request(verkey: &PublicKey, revealed: &[usize]) -> PresentationRequest {
+        PresentationRequest {
+            proof_request: Verifier::new_proof_request(revealed, verkey).unwrap(),
+            link_secret_index: 0,
+        }
+    }
+
+    #[test]
+    fn presentation_with_shared_link_secret() {
+        let link_secret = Prover::new_link_secret();
+        let (pk1, sig1, msgs1) = issue(link_secret, 4);
+        let (pk2, sig2, msgs2) = issue(link_secret, 3);
+        let requests = vec![request(&pk1, &[1, 3]), request(&pk2, &[2])];
+
+        let mut builder = PresentationBuilder::new(link_secret);
+        builder.add(&requests[0], &sig1, msgs1.as_slice()).unwrap();
+        builder.add(&requests[1], &sig2, msgs2.as_slice()).unwrap();
+        let nonce = Verifier::generate_proof_nonce();
+        let presentation = builder.finalize(&nonce).unwrap();
+
+        let revealed = presentation.verify(requests.as_slice(), &nonce).unwrap();
+        assert_eq!(msgs1[3], revealed[0][&3]);
+        assert_eq!(msgs2[2], revealed[1][&2]);
+        assert!(presentation
+            .verify(requests.as_slice(), &Verifier::generate_proof_nonce())
+            .is_err());
+        assert!(presentation.verify(&requests[..1], &nonce).is_err());
+    }
+
+    #[test]
+    fn presentation_with_different_link_secrets_fails() {
+        let link_secret = Prover::new_link_secret();
+        let (pk1, sig1, msgs1) = issue(link_secret, 3);
+        let (pk2, sig2, msgs2) = issue(Prover::new_link_secret(), 3);
+        let requests = vec![request(&pk1, &[1]), request(&pk2, &[1])];
+
+        let mut builder = PresentationBuilder::new(link_secret);
+        builder.add(&requests[0], &sig1, msgs1.as_slice()).unwrap();
+        assert!(builder.add(&requests[1], &sig2, msgs2.as_slice()).is_err());
+        // The link secret can not be revealed
+        assert!(builder
+            .add(&request(&pk1, &[0]), &sig1, msgs1.as_slice())
+            .is_err());
+
+        // A dishonest prover claiming the second link secret is the first one
+        let mut builder = PresentationBuilder::new(link_secret);
+        builder.add(&requests[0], &sig1, msgs1.as_slice()).unwrap();
+        builder.link_secret = msgs2[0];
+        builder.add(&requests[1], &sig2, msgs2.as_slice()).unwrap();
+        let nonce = Verifier::generate_proof_nonce();
+        let presentation = builder.finalize(&nonce).unwrap();
+        assert!(presentation.verify(requests.as_slice(), &nonce).is_err());
+    }
+}
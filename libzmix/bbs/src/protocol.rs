@@ -0,0 +1,372 @@
+use crate::credential::prelude::*;
+use crate::errors::prelude::*;
+use crate::keys::prelude::*;
+use crate::schema::CredentialSchema;
+use crate::signature::prelude::*;
+use crate::{
+    BlindSignatureContext, ProofNonce, SignatureMessage, SignatureProof, ToVariableLengthBytes,
+};
+use serde::{
+    de::{Error as DError, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::Formatter;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{
+        CredentialOffer, CredentialPresentation, CredentialRequest, IssuedCredential,
+        PROTOCOL_VERSION,
+    };
+}
+
+/// The version written in every protocol message
+pub const PROTOCOL_VERSION: u16 = 1;
+
+fn check_version(version: u16) -> Result<(), BBSError> {
+    if version != PROTOCOL_VERSION {
+        return Err(BBSErrorKind::GeneralError {
+            msg: format!(
+                "Unsupported protocol version {}, expected {}",
+                version, PROTOCOL_VERSION
+            ),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Raw bytes written as hex in human readable formats like JSON
+/// and as a byte string in binary formats like CBOR
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Bytes(Vec<u8>);
+
+impl Serialize for Bytes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&hex::encode(&self.0))
+        } else {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+}
+
+impl<'a> Deserialize<'a> for Bytes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        struct BytesVisitor;
+
+        impl<'a> Visitor<'a> for BytesVisitor {
+            type Value = Bytes;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("expected hex string or byte array")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Bytes, E>
+            where
+                E: DError,
+            {
+                hex::decode(value)
+                    .map(Bytes)
+                    .map_err(|_| DError::invalid_value(serde::de::Unexpected::Str(value), &self))
+            }
+
+            fn visit_bytes<E>(self, value: &[u8]) -> Result<Bytes, E>
+            where
+                E: DError,
+            {
+                Ok(Bytes(value.to_vec()))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Bytes, A::Error>
+            where
+                A: SeqAccess<'a>,
+            {
+                let mut bytes = Vec::new();
+                while let Some(b) = seq.next_element()? {
+                    bytes.push(b);
+                }
+                Ok(Bytes(bytes))
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(BytesVisitor)
+        } else {
+            deserializer.deserialize_bytes(BytesVisitor)
+        }
+    }
+}
+
+fn message_from_bytes(bytes: &Bytes) -> Result<SignatureMessage, BBSError> {
+    SignatureMessage::try_from(bytes.0.as_slice())
+}
+
+/// Sent by an issuer to offer a credential over `schema`
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialOffer {
+    version: u16,
+    schema: CredentialSchema,
+    public_key: Bytes,
+    nonce: Bytes,
+}
+
+impl CredentialOffer {
+    /// Offer a credential signed with `pub_key`. `nonce` must be fresh for each offer.
+    pub fn new(pub_key: &CredentialPublicKey, nonce: &ProofNonce) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            schema: pub_key.schema().clone(),
+            public_key: Bytes(pub_key.public_key().to_bytes_compressed_form()),
+            nonce: Bytes(nonce.to_bytes_compressed_form().to_vec()),
+        }
+    }
+
+    /// The issuer's public key for the offered credential
+    pub fn public_key(&self) -> Result<CredentialPublicKey, BBSError> {
+        check_version(self.version)?;
+        let public_key = PublicKey::from_bytes_compressed_form(&self.public_key.0)?;
+        CredentialPublicKey::new(self.schema.clone(), public_key)
+    }
+
+    /// The nonce the holder must use to blind their secrets
+    pub fn nonce(&self) -> Result<ProofNonce, BBSError> {
+        check_version(self.version)?;
+        ProofNonce::try_from(self.nonce.0.as_slice())
+    }
+}
+
+/// Sent by a holder in reply to an offer with commitments to their hidden attributes
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialRequest {
+    version: u16,
+    schema_hash: Bytes,
+    blinded_credential_secrets: Bytes,
+}
+
+impl CredentialRequest {
+    /// Request a credential over the schema of `pub_key`
+    pub fn new(
+        pub_key: &CredentialPublicKey,
+        blinded_credential_secrets: &BlindSignatureContext,
+    ) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            schema_hash: Bytes(pub_key.schema().hash().to_vec()),
+            blinded_credential_secrets: Bytes(
+                blinded_credential_secrets.to_bytes_compressed_form(),
+            ),
+        }
+    }
+
+    /// The commitments to the hidden attributes, checking the request
+    /// is for the schema of `pub_key`
+    pub fn blinded_credential_secrets(
+        &self,
+        pub_key: &CredentialPublicKey,
+    ) -> Result<BlindSignatureContext, BBSError> {
+        check_version(self.version)?;
+        if self.schema_hash.0[..] != pub_key.schema().hash()[..] {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Credential request is for a different schema".to_string(),
+            }
+            .into());
+        }
+        BlindSignatureContext::from_bytes_compressed_form(&self.blinded_credential_secrets.0)
+    }
+}
+
+/// Sent by an issuer with the blind signature and the attribute values it signed
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssuedCredential {
+    version: u16,
+    values: BTreeMap<String, Bytes>,
+    blind_signature: Bytes,
+}
+
+impl IssuedCredential {
+    /// Wrap a blind signature over the known `values`
+    pub fn new(
+        values: &CredentialValues,
+        blind_signature: &BlindSignature,
+    ) -> Result<Self, BBSError> {
+        let mut known = BTreeMap::new();
+        for (name, value) in values.attrs_values() {
+            if let CredentialValue::Known(m) = value {
+                known.insert(name.clone(), Bytes(m.to_bytes_compressed_form().to_vec()));
+            } else {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: format!("Attribute {} is hidden from the issuer", name),
+                }
+                .into());
+            }
+        }
+        Ok(Self {
+            version: PROTOCOL_VERSION,
+            values: known,
+            blind_signature: Bytes(blind_signature.to_bytes_compressed_form().to_vec()),
+        })
+    }
+
+    /// The attribute values signed by the issuer
+    pub fn values(&self) -> Result<CredentialValues, BBSError> {
+        check_version(self.version)?;
+        let mut builder = CredentialValuesBuilder::new();
+        for (name, value) in &self.values {
+            builder.add_known(name, message_from_bytes(value)?);
+        }
+        Ok(builder.finalize())
+    }
+
+    /// The blind signature to complete with the holder's blinding factor
+    pub fn blind_signature(&self) -> Result<BlindSignature, BBSError> {
+        check_version(self.version)?;
+        BlindSignature::try_from(self.blind_signature.0.as_slice())
+    }
+}
+
+/// Sent by a holder to a verifier with a proof over one or more credentials
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialPresentation {
+    version: u16,
+    proofs: Vec<Bytes>,
+}
+
+impl CredentialPresentation {
+    /// Wrap a credential proof
+    pub fn new(proof: &CredentialProof) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            proofs: proof
+                .proofs
+                .iter()
+                .map(|p| Bytes(p.to_bytes_compressed_form()))
+                .collect(),
+        }
+    }
+
+    /// The proof to check with a `CredentialProofVerifier`
+    pub fn proof(&self) -> Result<CredentialProof, BBSError> {
+        check_version(self.version)?;
+        let proofs = self
+            .proofs
+            .iter()
+            .map(|p| SignatureProof::from_bytes_compressed_form(&p.0))
+            .collect::<Result<Vec<SignatureProof>, BBSError>>()?;
+        Ok(CredentialProof { proofs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issuer::Issuer;
+    use crate::prover::Prover;
+    use crate::verifier::Verifier;
+    use crate::HashElem;
+
+    fn json<T: Serialize + for<'a> Deserialize<'a>>(message: &T) -> T {
+        serde_json::from_str(&serde_json::to_string(message).unwrap()).unwrap()
+    }
+
+    fn cbor<T: Serialize + for<'a> Deserialize<'a>>(message: &T) -> T {
+        serde_cbor::from_slice(&serde_cbor::to_vec(message).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn issuance_and_presentation_over_the_wire() {
+        let schema = CredentialSchema::new(vec!["name", "link_secret"]).unwrap();
+        let (pub_key, priv_key) = Issuer::new_credential_def(&schema).unwrap();
+        let issuer_nonce = Issuer::generate_signing_nonce();
+        let offer = json(&CredentialOffer::new(&pub_key, &issuer_nonce));
+
+        let holder_pub_key = offer.public_key().unwrap();
+        let mut builder = CredentialValuesBuilder::new();
+        builder.add_hidden("link_secret", Prover::new_link_secret());
+        let hidden = builder.finalize();
+        let (blinded, blinding) =
+            Prover::blind_credential_secrets(&holder_pub_key, &hidden, &offer.nonce().unwrap())
+                .unwrap();
+        let request = cbor(&CredentialRequest::new(&holder_pub_key, &blinded));
+
+        let mut builder = CredentialValuesBuilder::new();
+        builder.add_known("name", SignatureMessage::hash(b"Alice"));
+        let known = builder.finalize();
+        let blind_signature = Issuer::sign_credential(
+            &request.blinded_credential_secrets(&pub_key).unwrap(),
+            &known,
+            &issuer_nonce,
+            &pub_key,
+            &priv_key,
+        )
+        .unwrap();
+        let credential = json(&IssuedCredential::new(&known, &blind_signature).unwrap());
+        assert!(IssuedCredential::new(&hidden, &blind_signature).is_err());
+
+        let mut builder = CredentialValuesBuilder::new();
+        builder.add_known(
+            "name",
+            *credential.values().unwrap().attrs_values()["name"].value(),
+        );
+        builder.add_hidden("link_secret", *hidden.attrs_values()["link_secret"].value());
+        let values = builder.finalize();
+        let signature = Prover::process_credential_signature(
+            &credential.blind_signature().unwrap(),
+            &values,
+            &blinding,
+            &holder_pub_key,
+        )
+        .unwrap();
+
+        let mut builder = SubProofRequestBuilder::new();
+        builder.add_revealed_attr("name");
+        let sub_proof_request = builder.finalize();
+        let mut proof_builder = CredentialProofBuilder::new();
+        proof_builder
+            .add_sub_proof_request(&sub_proof_request, &signature, &values, &holder_pub_key)
+            .unwrap();
+        let nonce = Verifier::generate_proof_nonce();
+        let presentation = CredentialPresentation::new(&proof_builder.finalize(&nonce).unwrap());
+        assert_eq!(presentation, cbor(&presentation));
+        let presentation = json(&presentation);
+
+        let mut verifier = CredentialProofVerifier::new();
+        verifier.add_sub_proof_request(&sub_proof_request, &pub_key);
+        let revealed = verifier
+            .verify(&presentation.proof().unwrap(), &nonce)
+            .unwrap();
+        assert_eq!(SignatureMessage::hash(b"Alice"), revealed[0]["name"]);
+    }
+
+    #[test]
+    fn messages_are_versioned() {
+        let schema = CredentialSchema::new(vec!["name"]).unwrap();
+        let (pub_key, _) = Issuer::new_credential_def(&schema).unwrap();
+        let offer = CredentialOffer::new(&pub_key, &Issuer::generate_signing_nonce());
+
+        let mut value = serde_json::to_value(&offer).unwrap();
+        assert_eq!(PROTOCOL_VERSION, value["version"].as_u64().unwrap() as u16);
+        assert!(value["publicKey"].is_string());
+        value["version"] = serde_json::Value::from(PROTOCOL_VERSION + 1);
+        let future: CredentialOffer = serde_json::from_value(value).unwrap();
+        assert!(future.public_key().is_err());
+        assert!(future.nonce().is_err());
+
+        let other = CredentialSchema::new(vec!["age"]).unwrap();
+        let (other_pub_key, _) = Issuer::new_credential_def(&other).unwrap();
+        let request = CredentialRequest::new(&pub_key, &BlindSignatureContext::default());
+        assert!(request.blinded_credential_secrets(&other_pub_key).is_err());
+    }
+}
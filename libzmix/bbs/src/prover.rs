@@ -1,3 +1,4 @@
+use crate::credential::prelude::*;
 use crate::errors::prelude::*;
 use crate::keys::prelude::*;
 use crate::messages::*;
@@ -100,6 +101,32 @@ impl Prover {
         }
     }
 
+    /// Commit to the hidden credential `values` for an issuer
+    pub fn blind_credential_secrets(
+        pub_key: &CredentialPublicKey,
+        values: &CredentialValues,
+        nonce: &ProofNonce,
+    ) -> Result<(BlindSignatureContext, SignatureBlinding), BBSError> {
+        let hidden = values.by_index(pub_key.schema(), true)?;
+        Self::new_blind_signature_context(pub_key.public_key(), &hidden, nonce)
+    }
+
+    /// Unblinds and verifies a credential signature over all of `values`
+    pub fn process_credential_signature(
+        blind_signature: &BlindSignature,
+        values: &CredentialValues,
+        blinding_factor: &SignatureBlinding,
+        pub_key: &CredentialPublicKey,
+    ) -> Result<Signature, BBSError> {
+        let messages = values.messages(pub_key.schema())?;
+        Self::complete_signature(
+            pub_key.public_key(),
+            messages.as_slice(),
+            blind_signature,
+            blinding_factor,
+        )
+    }
+
     /// Create a new signature proof of knowledge and selective disclosure proof
     /// from a verifier's request
     ///
@@ -0,0 +1,324 @@
+use crate::errors::prelude::*;
+use crate::pok_sig::prelude::*;
+use crate::pok_vc::prelude::*;
+use crate::{
+    Commitment, GeneratorG1, HashElem, ProofChallenge, ProofNonce, SignatureMessage,
+    ToVariableLengthBytes, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE,
+};
+use pairing_plus::{bls12_381::G1, serdes::SerDes, CurveProjective};
+use serde::{
+    de::{Error as DError, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::JsValue;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{PoKOfPseudonym, PoKOfPseudonymProof, Pseudonym};
+}
+
+const DOMAIN_DST: &[u8] = b"BBS_PSEUDONYM_DOMAIN_";
+
+/// A domain specific pseudonym `H(domain)^link_secret`.
+///
+/// A holder presenting to the same domain always shows the same pseudonym so
+/// the verifier can recognize returning users or rate-limit them.
+/// Pseudonyms for different domains can't be linked without knowing the link secret.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Pseudonym(pub(crate) G1);
+
+impl Pseudonym {
+    to_fixed_length_bytes_impl!(Pseudonym, G1, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE);
+
+    /// Compute the pseudonym of `link_secret` for `domain`
+    pub fn new<I: AsRef<[u8]>>(domain: I, link_secret: &SignatureMessage) -> Self {
+        let mut nym = domain_generator(domain.as_ref()).0;
+        nym.mul_assign(link_secret.0);
+        Self(nym)
+    }
+}
+
+default_zero_impl!(Pseudonym, G1);
+as_ref_impl!(Pseudonym, G1);
+from_impl!(Pseudonym, G1, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE);
+display_impl!(Pseudonym);
+serdes_impl!(Pseudonym);
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(Pseudonym);
+
+fn domain_generator(domain: &[u8]) -> GeneratorG1 {
+    GeneratorG1::hash([DOMAIN_DST, domain].concat())
+}
+
+/// Proof of knowledge of the link secret behind a pseudonym.
+///
+/// As with `PoKOfPedersenCommitment`, the proof is linked to the link secret hidden
+/// in a signature proof of knowledge by using the same `ProofNonce` as its blinding
+/// in both proofs and the same challenge.
+#[derive(Debug, Clone)]
+pub struct PoKOfPseudonym {
+    pseudonym: Pseudonym,
+    pok_vc: ProverCommittedG1,
+    secrets: Vec<SignatureMessage>,
+}
+
+impl PoKOfPseudonym {
+    /// Creates the initial proof data before a Fiat-Shamir calculation
+    pub fn init<I: AsRef<[u8]>>(
+        domain: I,
+        link_secret: &SignatureMessage,
+        link_secret_blinding: &ProofNonce,
+    ) -> Self {
+        let domain = domain.as_ref();
+        let mut committing = ProverCommittingG1::new();
+        committing.commit_with(domain_generator(domain), link_secret_blinding);
+        Self {
+            pseudonym: Pseudonym::new(domain, link_secret),
+            pok_vc: committing.finish(),
+            secrets: vec![*link_secret],
+        }
+    }
+
+    /// The pseudonym this proof is about
+    pub fn pseudonym(&self) -> Pseudonym {
+        self.pseudonym
+    }
+
+    /// Return byte representation of public elements so they can be used for challenge computation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.pok_vc.to_bytes();
+        self.pseudonym.0.serialize(&mut bytes, false).unwrap();
+        bytes
+    }
+
+    /// Given the challenge value, compute the s values for Fiat-Shamir and return the actual
+    /// proof to be sent to the verifier
+    pub fn gen_proof(
+        self,
+        challenge_hash: &ProofChallenge,
+    ) -> Result<PoKOfPseudonymProof, BBSError> {
+        let proof = self
+            .pok_vc
+            .gen_proof(challenge_hash, self.secrets.as_slice())?;
+        Ok(PoKOfPseudonymProof {
+            pseudonym: self.pseudonym,
+            proof,
+        })
+    }
+}
+
+/// The pseudonym proof sent to the verifier
+#[derive(Debug, Clone)]
+pub struct PoKOfPseudonymProof {
+    /// The pseudonym
+    pub(crate) pseudonym: Pseudonym,
+    /// Proof of relation nym == H(domain)^link_secret
+    pub(crate) proof: ProofG1,
+}
+
+impl PoKOfPseudonymProof {
+    /// The pseudonym this proof is about
+    pub fn pseudonym(&self) -> Pseudonym {
+        self.pseudonym
+    }
+
+    /// Return bytes that need to be hashed for generating challenge.
+    /// These are the same as `PoKOfPseudonym::to_bytes`
+    pub fn get_bytes_for_challenge<I: AsRef<[u8]>>(&self, domain: I) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        domain_generator(domain.as_ref())
+            .0
+            .serialize(&mut bytes, false)
+            .unwrap();
+        self.proof.commitment.serialize(&mut bytes, false).unwrap();
+        self.pseudonym.0.serialize(&mut bytes, false).unwrap();
+        bytes
+    }
+
+    /// Get the response for the link secret.
+    /// Used when comparing message equality
+    pub fn get_resp_for_link_secret(&self) -> SignatureMessage {
+        SignatureMessage(self.proof.responses[0])
+    }
+
+    /// Validate the proof that the pseudonym was derived for `domain`
+    pub fn verify<I: AsRef<[u8]>>(
+        &self,
+        domain: I,
+        challenge: &ProofChallenge,
+    ) -> Result<bool, BBSError> {
+        if self.pseudonym.0.is_zero() {
+            return Ok(false);
+        }
+        Ok(self.proof.verify(
+            &[domain_generator(domain.as_ref())],
+            &Commitment(self.pseudonym.0),
+            challenge,
+        )?)
+    }
+
+    /// Validate the proof and check the link secret equals a hidden message in
+    /// `signature_proof` which was verified with the same `challenge`.
+    /// `hidden_msg_idx` is the position of the message among the hidden messages,
+    /// as with `PoKOfSignatureProof::get_resp_for_message`.
+    pub fn verify_linked<I: AsRef<[u8]>>(
+        &self,
+        domain: I,
+        challenge: &ProofChallenge,
+        signature_proof: &PoKOfSignatureProof,
+        hidden_msg_idx: usize,
+    ) -> Result<bool, BBSError> {
+        if !self.verify(domain, challenge)? {
+            return Ok(false);
+        }
+        Ok(
+            signature_proof.get_resp_for_message(hidden_msg_idx)?
+                == self.get_resp_for_link_secret(),
+        )
+    }
+}
+
+impl ToVariableLengthBytes for PoKOfPseudonymProof {
+    type Output = Self;
+    type Error = BBSError;
+
+    fn to_bytes_compressed_form(&self) -> Vec<u8> {
+        let mut bytes = self.pseudonym.to_bytes_compressed_form().to_vec();
+        bytes.extend_from_slice(self.proof.to_bytes_compressed_form().as_slice());
+        bytes
+    }
+
+    fn from_bytes_compressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        Self::from_bytes(data.as_ref(), G1_COMPRESSED_SIZE, true)
+    }
+
+    fn to_bytes_uncompressed_form(&self) -> Vec<u8> {
+        let mut bytes = self.pseudonym.to_bytes_uncompressed_form().to_vec();
+        bytes.extend_from_slice(self.proof.to_bytes_uncompressed_form().as_slice());
+        bytes
+    }
+
+    fn from_bytes_uncompressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        Self::from_bytes(data.as_ref(), G1_UNCOMPRESSED_SIZE, false)
+    }
+}
+
+impl PoKOfPseudonymProof {
+    fn from_bytes(data: &[u8], g1_size: usize, compressed: bool) -> Result<Self, BBSError> {
+        if data.len() < g1_size {
+            return Err(BBSErrorKind::InvalidNumberOfBytes(g1_size, data.len()).into());
+        }
+        let pseudonym = Pseudonym(G1::deserialize(&mut &data[..g1_size], compressed)?);
+        let proof = if compressed {
+            ProofG1::from_bytes_compressed_form(&data[g1_size..])
+        } else {
+            ProofG1::from_bytes_uncompressed_form(&data[g1_size..])
+        }
+        .map_err(|e| BBSErrorKind::GeneralError {
+            msg: format!("{:?}", e),
+        })?;
+        Ok(Self { pseudonym, proof })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::generate;
+    use crate::messages::*;
+    use crate::prover::Prover;
+    use crate::signature::Signature;
+    use crate::RandomElem;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn pseudonyms_are_domain_specific() {
+        let link_secret = Prover::new_link_secret();
+        let nym = Pseudonym::new(b"example.com", &link_secret);
+        assert_eq!(nym, Pseudonym::new(b"example.com", &link_secret));
+        assert_ne!(nym, Pseudonym::new(b"example.org", &link_secret));
+        assert_ne!(
+            nym,
+            Pseudonym::new(b"example.com", &Prover::new_link_secret())
+        );
+
+        let bytes = nym.to_bytes_compressed_form();
+        assert_eq!(nym, Pseudonym::try_from(&bytes[..]).unwrap());
+    }
+
+    #[test]
+    fn pok_pseudonym_linked_to_signature() {
+        let domain = b"example.com";
+        let link_secret = Prover::new_link_secret();
+        let messages = vec![link_secret, SignatureMessage::random()];
+        let (verkey, signkey) = generate(2).unwrap();
+        let sig = Signature::new(messages.as_slice(), &signkey, &verkey).unwrap();
+
+        let link_secret_blinding = ProofNonce::random();
+        let proof_messages = vec![
+            pm_hidden_raw!(messages[0], link_secret_blinding),
+            pm_revealed_raw!(messages[1]),
+        ];
+        let mut revealed_msgs = BTreeMap::new();
+        revealed_msgs.insert(1, messages[1]);
+
+        let pok_sig = PoKOfSignature::init(&sig, &verkey, proof_messages.as_slice()).unwrap();
+        let pok_nym = PoKOfPseudonym::init(domain, &link_secret, &link_secret_blinding);
+
+        let mut challenge_bytes = pok_sig.to_bytes();
+        challenge_bytes.extend_from_slice(pok_nym.to_bytes().as_slice());
+        let challenge = ProofChallenge::hash(&challenge_bytes);
+
+        let sig_proof = pok_sig.gen_proof(&challenge).unwrap();
+        let nym_proof = pok_nym.gen_proof(&challenge).unwrap();
+        let nym_proof =
+            PoKOfPseudonymProof::from_bytes_compressed_form(nym_proof.to_bytes_compressed_form())
+                .unwrap();
+        assert_eq!(Pseudonym::new(domain, &link_secret), nym_proof.pseudonym());
+
+        let mut challenge_bytes =
+            sig_proof.get_bytes_for_challenge(revealed_msgs.keys().cloned().collect(), &verkey);
+        challenge_bytes.extend_from_slice(nym_proof.get_bytes_for_challenge(domain).as_slice());
+        let challenge_verifier = ProofChallenge::hash(&challenge_bytes);
+
+        assert!(sig_proof
+            .verify(&verkey, &revealed_msgs, &challenge_verifier)
+            .unwrap()
+            .is_valid());
+        assert!(nym_proof
+            .verify_linked(domain, &challenge_verifier, &sig_proof, 0)
+            .unwrap());
+        // The pseudonym is not valid for another domain
+        assert!(!nym_proof
+            .verify(b"example.org", &challenge_verifier)
+            .unwrap());
+    }
+
+    #[test]
+    fn pok_pseudonym_of_other_link_secret_not_linked() {
+        let domain = b"example.com";
+        let messages = vec![Prover::new_link_secret()];
+        let (verkey, signkey) = generate(1).unwrap();
+        let sig = Signature::new(messages.as_slice(), &signkey, &verkey).unwrap();
+
+        let link_secret_blinding = ProofNonce::random();
+        let proof_messages = vec![pm_hidden_raw!(messages[0], link_secret_blinding)];
+        let pok_sig = PoKOfSignature::init(&sig, &verkey, proof_messages.as_slice()).unwrap();
+        let pok_nym =
+            PoKOfPseudonym::init(domain, &Prover::new_link_secret(), &link_secret_blinding);
+
+        let mut challenge_bytes = pok_sig.to_bytes();
+        challenge_bytes.extend_from_slice(pok_nym.to_bytes().as_slice());
+        let challenge = ProofChallenge::hash(&challenge_bytes);
+        let sig_proof = pok_sig.gen_proof(&challenge).unwrap();
+        let nym_proof = pok_nym.gen_proof(&challenge).unwrap();
+
+        assert!(nym_proof.verify(domain, &challenge).unwrap());
+        assert!(!nym_proof
+            .verify_linked(domain, &challenge, &sig_proof, 0)
+            .unwrap());
+    }
+}
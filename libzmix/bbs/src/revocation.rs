@@ -0,0 +1,521 @@
+use crate::errors::prelude::*;
+use crate::keys::prelude::*;
+use crate::pok_sig::prelude::*;
+use crate::pok_vc::prelude::*;
+use crate::{
+    rand_non_zero_fr, Commitment, GeneratorG1, ProofChallenge, ProofNonce, SignatureMessage,
+    ToVariableLengthBytes, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE,
+};
+use ff_zeroize::Field;
+use pairing_plus::{
+    bls12_381::{Bls12, Fq12, Fr, G1, G2},
+    serdes::SerDes,
+    CurveAffine, CurveProjective, Engine,
+};
+use serde::{
+    de::{Error as DError, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::JsValue;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{
+        Accumulator, MembershipWitness, PoKOfMembership, PoKOfMembershipProof, RevocationDelta,
+    };
+}
+
+/// A revocation registry is an accumulator of the elements, usually credential indices,
+/// that have not been revoked. The accumulator is `g1^((y_1 + a)(y_2 + a)...)`
+/// for the registry manager's secret key `a` as described by Nguyen
+/// (see <https://eprint.iacr.org/2005/123>) with the witness updates from
+/// Vitto and Biryukov (see <https://eprint.iacr.org/2020/777>).
+///
+/// The manager's keys are created like BBS+ keys with `DeterministicPublicKey::new`
+/// but must never be the same keys used to sign credentials.
+/// The element is signed as one of the credential's messages so a presentation
+/// can show the hidden element is still in the registry with `PoKOfMembership`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Accumulator(pub(crate) G1);
+
+impl Accumulator {
+    to_fixed_length_bytes_impl!(Accumulator, G1, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE);
+
+    /// Create a registry where all `elements` are not revoked.
+    /// Issuing credentials for these elements doesn't require any updates.
+    pub fn new(secret_key: &SecretKey, elements: &[SignatureMessage]) -> Result<Self, BBSError> {
+        secret_key.validate()?;
+        let mut exponent = Fr::one();
+        for y in elements {
+            let mut t = y.0;
+            t.add_assign(&secret_key.0);
+            exponent.mul_assign(&t);
+        }
+        let mut v = G1::one();
+        v.mul_assign(exponent);
+        Ok(Self(v))
+    }
+
+    /// Revoke `elements`. The returned delta is published along with
+    /// the new value so holders can update their witnesses.
+    pub fn remove(
+        &self,
+        secret_key: &SecretKey,
+        elements: &[SignatureMessage],
+    ) -> Result<(Self, RevocationDelta), BBSError> {
+        secret_key.validate()?;
+        let mut v = self.0;
+        let mut values = Vec::with_capacity(elements.len());
+        for y in elements {
+            let mut t = y.0;
+            t.add_assign(&secret_key.0);
+            let t = t.inverse().ok_or_else(|| BBSErrorKind::GeneralError {
+                msg: "Element can't be removed".to_string(),
+            })?;
+            v.mul_assign(t);
+            values.push(Self(v));
+        }
+        Ok((
+            Self(v),
+            RevocationDelta {
+                removed: elements.to_vec(),
+                values,
+            },
+        ))
+    }
+}
+
+default_zero_impl!(Accumulator, G1);
+as_ref_impl!(Accumulator, G1);
+from_impl!(Accumulator, G1, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE);
+display_impl!(Accumulator);
+serdes_impl!(Accumulator);
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(Accumulator);
+
+/// The elements revoked between two published registry values
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct RevocationDelta {
+    /// The elements in the order they were removed
+    removed: Vec<SignatureMessage>,
+    /// The accumulator after each removal
+    values: Vec<Accumulator>,
+}
+
+impl RevocationDelta {
+    /// The revoked elements
+    pub fn removed(&self) -> &[SignatureMessage] {
+        self.removed.as_slice()
+    }
+
+    /// The registry value after applying this delta
+    pub fn accumulator(&self) -> Option<Accumulator> {
+        self.values.last().copied()
+    }
+}
+
+/// Proves an element is in the registry i.e. `witness^(y + a) == accumulator`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MembershipWitness(pub(crate) G1);
+
+impl MembershipWitness {
+    to_fixed_length_bytes_impl!(
+        MembershipWitness,
+        G1,
+        G1_COMPRESSED_SIZE,
+        G1_UNCOMPRESSED_SIZE
+    );
+
+    /// Create a witness for `element`. Only the registry manager can do this
+    pub fn new(
+        secret_key: &SecretKey,
+        accumulator: &Accumulator,
+        element: &SignatureMessage,
+    ) -> Result<Self, BBSError> {
+        let (_, delta) = accumulator.remove(secret_key, &[*element])?;
+        Ok(Self(delta.values[0].0))
+    }
+
+    /// Check the witness for `element` against the published registry value
+    pub fn verify(
+        &self,
+        public_key: &DeterministicPublicKey,
+        accumulator: &Accumulator,
+        element: &SignatureMessage,
+    ) -> bool {
+        // e(C, g2^y * w) == e(V, g2)
+        let mut a = G2::one();
+        a.mul_assign(element.0);
+        a.add_assign(&public_key.0);
+        let mut v = accumulator.0;
+        v.negate();
+        pairing_product_is_one(&self.0, &a, &v, &G2::one())
+    }
+
+    /// Update the witness after the manager published `delta`.
+    /// Fails if `element` was revoked.
+    pub fn update(
+        &self,
+        element: &SignatureMessage,
+        delta: &RevocationDelta,
+    ) -> Result<Self, BBSError> {
+        let mut c = self.0;
+        for (y, v) in delta.removed.iter().zip(delta.values.iter()) {
+            // C' = (C / V')^(1/(y' - y))
+            let mut d = y.0;
+            d.sub_assign(&element.0);
+            let d = d.inverse().ok_or_else(|| BBSErrorKind::GeneralError {
+                msg: "Element has been revoked".to_string(),
+            })?;
+            c.sub_assign(&v.0);
+            c.mul_assign(d);
+        }
+        Ok(Self(c))
+    }
+}
+
+default_zero_impl!(MembershipWitness, G1);
+as_ref_impl!(MembershipWitness, G1);
+from_impl!(
+    MembershipWitness,
+    G1,
+    G1_COMPRESSED_SIZE,
+    G1_UNCOMPRESSED_SIZE
+);
+display_impl!(MembershipWitness);
+serdes_impl!(MembershipWitness);
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(MembershipWitness);
+
+/// Proof of knowledge of a membership witness for a hidden element.
+///
+/// The witness is randomized as `C' = C^r` and `C_bar = V^r * C'^-y` which equals `C'^a`.
+/// The verifier checks `e(C', w) == e(C_bar, g2)` and the prover shows it knows `r` and `y`.
+/// Like `PoKOfPedersenCommitment`, the proof is linked to a hidden message in a signature
+/// proof of knowledge by using the same `ProofNonce` as the message blinding in both
+/// and the same challenge.
+#[derive(Debug, Clone)]
+pub struct PoKOfMembership {
+    c_prime: G1,
+    c_bar: G1,
+    pok_vc: ProverCommittedG1,
+    secrets: Vec<SignatureMessage>,
+}
+
+impl PoKOfMembership {
+    /// Creates the initial proof data before a Fiat-Shamir calculation
+    pub fn init(
+        witness: &MembershipWitness,
+        accumulator: &Accumulator,
+        element: &SignatureMessage,
+        element_blinding: &ProofNonce,
+    ) -> Self {
+        let r = rand_non_zero_fr();
+        let mut c_prime = witness.0;
+        c_prime.mul_assign(r);
+        let mut neg_c_prime = c_prime;
+        neg_c_prime.negate();
+
+        // C_bar = V^r * C'^-y
+        let mut c_bar = accumulator.0;
+        c_bar.mul_assign(r);
+        let mut t = neg_c_prime;
+        t.mul_assign(element.0);
+        c_bar.add_assign(&t);
+
+        let mut committing = ProverCommittingG1::new();
+        committing.commit(accumulator);
+        committing.commit_with(GeneratorG1(neg_c_prime), element_blinding);
+        Self {
+            c_prime,
+            c_bar,
+            pok_vc: committing.finish(),
+            secrets: vec![SignatureMessage(r), *element],
+        }
+    }
+
+    /// Return byte representation of public elements so they can be used for challenge computation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.pok_vc.to_bytes();
+        self.c_prime.serialize(&mut bytes, false).unwrap();
+        self.c_bar.serialize(&mut bytes, false).unwrap();
+        bytes
+    }
+
+    /// Given the challenge value, compute the s values for Fiat-Shamir and return the actual
+    /// proof to be sent to the verifier
+    pub fn gen_proof(
+        self,
+        challenge_hash: &ProofChallenge,
+    ) -> Result<PoKOfMembershipProof, BBSError> {
+        let proof = self
+            .pok_vc
+            .gen_proof(challenge_hash, self.secrets.as_slice())?;
+        Ok(PoKOfMembershipProof {
+            c_prime: self.c_prime,
+            c_bar: self.c_bar,
+            proof,
+        })
+    }
+}
+
+/// The non-revocation proof sent to the verifier
+#[derive(Debug, Clone)]
+pub struct PoKOfMembershipProof {
+    /// The randomized witness
+    pub(crate) c_prime: G1,
+    /// The randomized witness raised to the registry secret key
+    pub(crate) c_bar: G1,
+    /// Proof of relation C_bar == V^r * C'^-y
+    pub(crate) proof: ProofG1,
+}
+
+impl PoKOfMembershipProof {
+    /// Return bytes that need to be hashed for generating challenge.
+    /// These are the same as `PoKOfMembership::to_bytes`
+    pub fn get_bytes_for_challenge(&self, accumulator: &Accumulator) -> Vec<u8> {
+        let mut neg_c_prime = self.c_prime;
+        neg_c_prime.negate();
+        let mut bytes = Vec::new();
+        accumulator.0.serialize(&mut bytes, false).unwrap();
+        neg_c_prime.serialize(&mut bytes, false).unwrap();
+        self.proof.commitment.serialize(&mut bytes, false).unwrap();
+        self.c_prime.serialize(&mut bytes, false).unwrap();
+        self.c_bar.serialize(&mut bytes, false).unwrap();
+        bytes
+    }
+
+    /// Get the response for the hidden element.
+    /// Used when comparing message equality
+    pub fn get_resp_for_element(&self) -> SignatureMessage {
+        SignatureMessage(self.proof.responses[1])
+    }
+
+    /// Validate the proof that the hidden element is in the registry
+    pub fn verify(
+        &self,
+        public_key: &DeterministicPublicKey,
+        accumulator: &Accumulator,
+        challenge: &ProofChallenge,
+    ) -> Result<bool, BBSError> {
+        if self.c_prime.is_zero() {
+            return Ok(false);
+        }
+        // e(C', w) == e(C_bar, g2)
+        let mut c_bar = self.c_bar;
+        c_bar.negate();
+        if !pairing_product_is_one(&self.c_prime, &public_key.0, &c_bar, &G2::one()) {
+            return Ok(false);
+        }
+        let mut neg_c_prime = self.c_prime;
+        neg_c_prime.negate();
+        Ok(self.proof.verify(
+            &[GeneratorG1(accumulator.0), GeneratorG1(neg_c_prime)],
+            &Commitment(self.c_bar),
+            challenge,
+        )?)
+    }
+
+    /// Validate the proof and check the element equals a hidden message in
+    /// `signature_proof` which was verified with the same `challenge`.
+    /// `hidden_msg_idx` is the position of the message among the hidden messages,
+    /// as with `PoKOfSignatureProof::get_resp_for_message`.
+    pub fn verify_linked(
+        &self,
+        public_key: &DeterministicPublicKey,
+        accumulator: &Accumulator,
+        challenge: &ProofChallenge,
+        signature_proof: &PoKOfSignatureProof,
+        hidden_msg_idx: usize,
+    ) -> Result<bool, BBSError> {
+        if !self.verify(public_key, accumulator, challenge)? {
+            return Ok(false);
+        }
+        Ok(signature_proof.get_resp_for_message(hidden_msg_idx)? == self.get_resp_for_element())
+    }
+}
+
+impl ToVariableLengthBytes for PoKOfMembershipProof {
+    type Output = Self;
+    type Error = BBSError;
+
+    fn to_bytes_compressed_form(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.c_prime.serialize(&mut bytes, true).unwrap();
+        self.c_bar.serialize(&mut bytes, true).unwrap();
+        bytes.extend_from_slice(self.proof.to_bytes_compressed_form().as_slice());
+        bytes
+    }
+
+    fn from_bytes_compressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        Self::from_bytes(data.as_ref(), G1_COMPRESSED_SIZE, true)
+    }
+
+    fn to_bytes_uncompressed_form(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.c_prime.serialize(&mut bytes, false).unwrap();
+        self.c_bar.serialize(&mut bytes, false).unwrap();
+        bytes.extend_from_slice(self.proof.to_bytes_uncompressed_form().as_slice());
+        bytes
+    }
+
+    fn from_bytes_uncompressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        Self::from_bytes(data.as_ref(), G1_UNCOMPRESSED_SIZE, false)
+    }
+}
+
+impl PoKOfMembershipProof {
+    fn from_bytes(data: &[u8], g1_size: usize, compressed: bool) -> Result<Self, BBSError> {
+        if data.len() < g1_size * 2 {
+            return Err(BBSErrorKind::InvalidNumberOfBytes(g1_size * 2, data.len()).into());
+        }
+        let c_prime = G1::deserialize(&mut &data[..g1_size], compressed)?;
+        let c_bar = G1::deserialize(&mut &data[g1_size..g1_size * 2], compressed)?;
+        let proof = if compressed {
+            ProofG1::from_bytes_compressed_form(&data[g1_size * 2..])
+        } else {
+            ProofG1::from_bytes_uncompressed_form(&data[g1_size * 2..])
+        }
+        .map_err(|e| BBSErrorKind::GeneralError {
+            msg: format!("{:?}", e),
+        })?;
+        Ok(Self {
+            c_prime,
+            c_bar,
+            proof,
+        })
+    }
+}
+
+/// Check e(a1, a2) * e(b1, b2) == 1
+fn pairing_product_is_one(a1: &G1, a2: &G2, b1: &G1, b2: &G2) -> bool {
+    let a1 = a1.into_affine().prepare();
+    let a2 = a2.into_affine().prepare();
+    let b1 = b1.into_affine().prepare();
+    let b2 = b2.into_affine().prepare();
+    match Bls12::final_exponentiation(&Bls12::miller_loop(&[(&a1, &a2), (&b1, &b2)])) {
+        None => false,
+        Some(product) => product == Fq12::one(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::generate;
+    use crate::messages::*;
+    use crate::signature::Signature;
+    use crate::{HashElem, RandomElem};
+    use std::collections::BTreeMap;
+
+    fn registry(
+        count: usize,
+    ) -> (
+        DeterministicPublicKey,
+        SecretKey,
+        Vec<SignatureMessage>,
+        Accumulator,
+    ) {
+        let (public_key, secret_key) = DeterministicPublicKey::new(None).unwrap();
+        let elements: Vec<SignatureMessage> = (0..count)
+            .map(|i| SignatureMessage::hash(format!("credential {}", i)))
+            .collect();
+        let accumulator = Accumulator::new(&secret_key, elements.as_slice()).unwrap();
+        (public_key, secret_key, elements, accumulator)
+    }
+
+    #[test]
+    fn witness_update_on_revocation() {
+        let (public_key, secret_key, elements, accumulator) = registry(10);
+        let witnesses: Vec<MembershipWitness> = elements
+            .iter()
+            .map(|y| MembershipWitness::new(&secret_key, &accumulator, y).unwrap())
+            .collect();
+        for (w, y) in witnesses.iter().zip(elements.iter()) {
+            assert!(w.verify(&public_key, &accumulator, y));
+        }
+        assert!(!witnesses[0].verify(&public_key, &accumulator, &elements[1]));
+
+        let (new_accumulator, delta) = accumulator.remove(&secret_key, &elements[2..4]).unwrap();
+        assert_eq!(delta.accumulator(), Some(new_accumulator));
+        assert_eq!(delta.removed(), &elements[2..4]);
+        assert!(!witnesses[0].verify(&public_key, &new_accumulator, &elements[0]));
+
+        let updated = witnesses[0].update(&elements[0], &delta).unwrap();
+        assert!(updated.verify(&public_key, &new_accumulator, &elements[0]));
+        // The revoked credential can't be updated
+        assert!(witnesses[2].update(&elements[2], &delta).is_err());
+
+        assert_eq!(
+            witnesses[5].update(&elements[5], &delta).unwrap(),
+            MembershipWitness::new(&secret_key, &new_accumulator, &elements[5]).unwrap()
+        );
+    }
+
+    #[test]
+    fn pok_membership_linked_to_signature() {
+        let (public_key, secret_key, elements, accumulator) = registry(5);
+        let witness = MembershipWitness::new(&secret_key, &accumulator, &elements[3]).unwrap();
+
+        // The credential index is the last signed message
+        let messages = vec![SignatureMessage::random(), elements[3]];
+        let (verkey, signkey) = generate(2).unwrap();
+        let sig = Signature::new(messages.as_slice(), &signkey, &verkey).unwrap();
+
+        let element_blinding = ProofNonce::random();
+        let proof_messages = vec![
+            pm_revealed_raw!(messages[0]),
+            pm_hidden_raw!(messages[1], element_blinding),
+        ];
+        let mut revealed_msgs = BTreeMap::new();
+        revealed_msgs.insert(0, messages[0]);
+
+        let pok_sig = PoKOfSignature::init(&sig, &verkey, proof_messages.as_slice()).unwrap();
+        let pok_mem =
+            PoKOfMembership::init(&witness, &accumulator, &elements[3], &element_blinding);
+
+        let mut challenge_bytes = pok_sig.to_bytes();
+        challenge_bytes.extend_from_slice(pok_mem.to_bytes().as_slice());
+        let challenge = ProofChallenge::hash(&challenge_bytes);
+
+        let sig_proof = pok_sig.gen_proof(&challenge).unwrap();
+        let mem_proof = pok_mem.gen_proof(&challenge).unwrap();
+        let mem_proof =
+            PoKOfMembershipProof::from_bytes_compressed_form(mem_proof.to_bytes_compressed_form())
+                .unwrap();
+
+        let mut challenge_bytes =
+            sig_proof.get_bytes_for_challenge(revealed_msgs.keys().cloned().collect(), &verkey);
+        challenge_bytes
+            .extend_from_slice(mem_proof.get_bytes_for_challenge(&accumulator).as_slice());
+        let challenge_verifier = ProofChallenge::hash(&challenge_bytes);
+        assert_eq!(challenge, challenge_verifier);
+
+        assert!(sig_proof
+            .verify(&verkey, &revealed_msgs, &challenge_verifier)
+            .unwrap()
+            .is_valid());
+        assert!(mem_proof
+            .verify_linked(
+                &public_key,
+                &accumulator,
+                &challenge_verifier,
+                &sig_proof,
+                0
+            )
+            .unwrap());
+
+        // Once revoked the proof no longer verifies against the new registry value
+        let (new_accumulator, _) = accumulator.remove(&secret_key, &elements[3..4]).unwrap();
+        assert!(!mem_proof
+            .verify(&public_key, &new_accumulator, &challenge_verifier)
+            .unwrap());
+        let (other_key, _) = DeterministicPublicKey::new(None).unwrap();
+        assert!(!mem_proof
+            .verify(&other_key, &accumulator, &challenge_verifier)
+            .unwrap());
+    }
+}
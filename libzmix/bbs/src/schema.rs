@@ -0,0 +1,211 @@
+use crate::errors::prelude::*;
+use crate::keys::prelude::*;
+use crate::SignatureMessage;
+use blake2::digest::{Input, VariableOutput};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{CredentialSchema, SCHEMA_HASH_SIZE};
+}
+
+/// Number of bytes in a schema hash
+pub const SCHEMA_HASH_SIZE: usize = 32;
+
+const SCHEMA_HASH_DST: &[u8] = b"BBS_CREDENTIAL_SCHEMA_V1";
+
+/// The attribute names of a credential in canonical order.
+///
+/// Names are sorted by their UTF-8 bytes so the index of every attribute,
+/// and therefore which generator signs it, is the same for issuer, holder and verifier
+/// no matter what order they list the attributes in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct CredentialSchema {
+    attributes: Vec<String>,
+}
+
+impl CredentialSchema {
+    /// Create a schema from attribute names in any order.
+    /// Names must be non-empty and unique.
+    pub fn new<I, S>(attributes: I) -> Result<Self, BBSError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut attributes = attributes
+            .into_iter()
+            .map(|a| a.into())
+            .collect::<Vec<String>>();
+        attributes.sort();
+        if attributes.is_empty() || attributes[0].is_empty() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Attribute names must not be empty".to_string(),
+            }
+            .into());
+        }
+        if let Some(w) = attributes.windows(2).find(|w| w[0] == w[1]) {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!("Duplicate attribute {}", w[0]),
+            }
+            .into());
+        }
+        Ok(Self { attributes })
+    }
+
+    /// The attribute names in canonical order
+    pub fn attributes(&self) -> &[String] {
+        self.attributes.as_slice()
+    }
+
+    /// The number of attributes i.e. messages signed
+    pub fn len(&self) -> usize {
+        self.attributes.len()
+    }
+
+    /// A schema always has at least one attribute
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+
+    /// The message index of `attribute`
+    pub fn index_of(&self, attribute: &str) -> Option<usize> {
+        self.attributes
+            .binary_search_by(|a| a.as_str().cmp(attribute))
+            .ok()
+    }
+
+    /// The message indices of `attributes` e.g. to build a proof request
+    pub fn indices<S: AsRef<str>>(&self, attributes: &[S]) -> Result<Vec<usize>, BBSError> {
+        attributes
+            .iter()
+            .map(|a| {
+                self.index_of(a.as_ref())
+                    .ok_or_else(|| unknown_attribute(a.as_ref()))
+            })
+            .collect()
+    }
+
+    /// Order attribute values as messages. Every attribute must have a value.
+    pub fn messages(
+        &self,
+        values: &BTreeMap<String, SignatureMessage>,
+    ) -> Result<Vec<SignatureMessage>, BBSError> {
+        if let Some(name) = values.keys().find(|n| self.index_of(n).is_none()) {
+            return Err(unknown_attribute(name));
+        }
+        self.attributes
+            .iter()
+            .map(|a| {
+                values.get(a).copied().ok_or_else(|| {
+                    BBSError::from(BBSErrorKind::GeneralError {
+                        msg: format!("Missing value for attribute {}", a),
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Derive the public key with one generator per attribute
+    pub fn public_key(&self, dpk: &DeterministicPublicKey) -> Result<PublicKey, BBSError> {
+        dpk.to_public_key(self.len())
+    }
+
+    /// A hash identifying the schema. Equal for schemas with the same attributes
+    /// regardless of the order they were given in.
+    pub fn hash(&self) -> [u8; SCHEMA_HASH_SIZE] {
+        let mut hasher = blake2::VarBlake2b::new(SCHEMA_HASH_SIZE).unwrap();
+        hasher.input(SCHEMA_HASH_DST);
+        hasher.input((self.attributes.len() as u32).to_be_bytes());
+        for a in &self.attributes {
+            hasher.input((a.len() as u32).to_be_bytes());
+            hasher.input(a.as_bytes());
+        }
+        let mut out = [0u8; SCHEMA_HASH_SIZE];
+        hasher.variable_result(|r| out.copy_from_slice(r));
+        out
+    }
+}
+
+impl TryFrom<Vec<String>> for CredentialSchema {
+    type Error = BBSError;
+
+    fn try_from(value: Vec<String>) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl From<CredentialSchema> for Vec<String> {
+    fn from(schema: CredentialSchema) -> Self {
+        schema.attributes
+    }
+}
+
+fn unknown_attribute(name: &str) -> BBSError {
+    BBSErrorKind::GeneralError {
+        msg: format!("Unknown attribute {}", name),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::Signature;
+    use crate::HashElem;
+
+    #[test]
+    fn schema_is_order_independent() {
+        let a = CredentialSchema::new(vec!["name", "age", "address"]).unwrap();
+        let b = CredentialSchema::new(vec!["address", "name", "age"]).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.hash(), b.hash());
+        assert_eq!(&["address", "age", "name"], a.attributes());
+        assert_eq!(Some(1), a.index_of("age"));
+        assert_eq!(None, a.index_of("height"));
+        assert_eq!(vec![2, 0], a.indices(&["name", "address"]).unwrap());
+        assert!(a.indices(&["height"]).is_err());
+
+        let c = CredentialSchema::new(vec!["name", "age"]).unwrap();
+        assert_ne!(a.hash(), c.hash());
+        // Length prefixes keep concatenations apart
+        let d = CredentialSchema::new(vec!["ab", "c"]).unwrap();
+        let e = CredentialSchema::new(vec!["a", "bc"]).unwrap();
+        assert_ne!(d.hash(), e.hash());
+
+        assert!(CredentialSchema::new(vec!["name", "name"]).is_err());
+        assert!(CredentialSchema::new(vec!["", "name"]).is_err());
+        assert!(CredentialSchema::new(Vec::<String>::new()).is_err());
+    }
+
+    #[test]
+    fn schema_orders_messages() {
+        let schema = CredentialSchema::new(vec!["name", "age"]).unwrap();
+        let (dpk, sk) = DeterministicPublicKey::new(None).unwrap();
+        let pk = schema.public_key(&dpk).unwrap();
+
+        let mut values = BTreeMap::new();
+        values.insert("name".to_string(), SignatureMessage::hash(b"Alice"));
+        values.insert("age".to_string(), SignatureMessage::hash(b"28"));
+        let messages = schema.messages(&values).unwrap();
+        assert_eq!(values["age"], messages[0]);
+        let signature = Signature::new(messages.as_slice(), &sk, &pk).unwrap();
+        assert!(signature.verify(messages.as_slice(), &pk).unwrap());
+
+        values.insert("height".to_string(), SignatureMessage::hash(b"175"));
+        assert!(schema.messages(&values).is_err());
+        values.remove("height");
+        values.remove("age");
+        assert!(schema.messages(&values).is_err());
+    }
+
+    #[test]
+    fn schema_deserialization_is_validated() {
+        let schema = CredentialSchema::try_from(vec!["b".to_string(), "a".to_string()]).unwrap();
+        let names: Vec<String> = schema.clone().into();
+        assert_eq!(vec!["a", "b"], names);
+        assert!(CredentialSchema::try_from(vec!["a".to_string(), "a".to_string()]).is_err());
+    }
+}
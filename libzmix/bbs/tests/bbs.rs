@@ -129,6 +129,67 @@ fn blind_sign_simple() {
     assert!(res.is_ok());
 }
 
+#[test]
+fn sign_batch() {
+    let (pk, sk) = Issuer::new_keys(3).unwrap();
+    let batch = (0..8)
+        .map(|_| {
+            (0..3)
+                .map(|_| SignatureMessage::random())
+                .collect::<Vec<SignatureMessage>>()
+        })
+        .collect::<Vec<Vec<SignatureMessage>>>();
+
+    let signatures = Issuer::sign_batch(batch.as_slice(), &sk, &pk).unwrap();
+    assert_eq!(batch.len(), signatures.len());
+    for (signature, messages) in signatures.iter().zip(batch.iter()) {
+        assert!(signature.verify(messages.as_slice(), &pk).unwrap());
+    }
+
+    let mut too_long = batch.clone();
+    too_long[5].push(SignatureMessage::random());
+    assert!(Issuer::sign_batch(too_long.as_slice(), &sk, &pk).is_err());
+}
+
+#[test]
+fn blind_sign_batch() {
+    let (pk, sk) = Issuer::new_keys(2).unwrap();
+    let holders = (0..4)
+        .map(|_| {
+            let nonce = Issuer::generate_signing_nonce();
+            let link_secret = Prover::new_link_secret();
+            let mut hidden = BTreeMap::new();
+            hidden.insert(0, link_secret);
+            let (ctx, blinding) =
+                Prover::new_blind_signature_context(&pk, &hidden, &nonce).unwrap();
+            let mut known = BTreeMap::new();
+            known.insert(1, SignatureMessage::random());
+            (nonce, link_secret, ctx, blinding, known)
+        })
+        .collect::<Vec<_>>();
+
+    let requests = holders
+        .iter()
+        .map(|(nonce, _, ctx, _, known)| (ctx, known, nonce))
+        .collect::<Vec<_>>();
+    let blind_signatures = Issuer::blind_sign_batch(requests.as_slice(), &sk, &pk).unwrap();
+
+    for (blind_signature, (_, link_secret, _, blinding, known)) in
+        blind_signatures.iter().zip(holders.iter())
+    {
+        let msgs = vec![*link_secret, known[&1]];
+        assert!(
+            Prover::complete_signature(&pk, msgs.as_slice(), blind_signature, blinding).is_ok()
+        );
+    }
+
+    // One holder answering with the wrong nonce fails the batch
+    let wrong_nonce = Issuer::generate_signing_nonce();
+    let mut requests = requests;
+    requests[2].2 = &wrong_nonce;
+    assert!(Issuer::blind_sign_batch(requests.as_slice(), &sk, &pk).is_err());
+}
+
 #[test]
 fn pok_sig() {
     let (pk, sk) = Issuer::new_keys(5).unwrap();
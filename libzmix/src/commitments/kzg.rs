@@ -0,0 +1,781 @@
+// KZG polynomial commitments (Kate, Zaverucha and Goldberg, ASIACRYPT 2010) over BLS12-381.
+
+// A `Setup` holds the powers of a secret `tau` in both groups: `[g1, g1*tau, g1*tau^2, ...]` and
+// `[g2, g2*tau, ...]`. The G1 powers bound the degree of polynomials that can be committed to, the G2
+// powers bound the number of points that can be opened with a single multi-point proof.
+// `tau` must be unknown to everyone, so in production the powers come from a ceremony and are loaded
+// with `Setup::load` or `Setup::from_bytes` which check that they are consistent.
+// A commitment to `p` is `[p(tau)]_1`. An opening at `z` is `y = p(z)` along with `[q(tau)]_1` where
+// `q(X) = (p(X) - y) / (X - z)` and is checked with `e(C - [y]_1, g2) == e(proof, [tau - z]_2)`.
+// A multi-point opening at points `z_i` replaces `X - z` with the vanishing polynomial `Z(X)` of the
+// points and `y` with the polynomial `I(X)` interpolating the values.
+
+use amcl_wrapper::extension_field_gt::GT;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::{GroupElement, GroupElementVector};
+use amcl_wrapper::group_elem_g1::{G1Vector, G1};
+use amcl_wrapper::group_elem_g2::{G2Vector, G2};
+use amcl_wrapper::{constants::GroupG1_SIZE, types_g2::GroupG2_SIZE};
+use failure::{Backtrace, Context, Fail};
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Clone, Eq, PartialEq, Debug, Fail)]
+pub enum KZGErrorKind {
+    #[fail(
+        display = "Setup supports polynomials of degree at most {} but given degree {}",
+        max, given
+    )]
+    DegreeTooLarge { max: usize, given: usize },
+
+    #[fail(
+        display = "Setup supports opening at most {} points at once but given {} points",
+        max, given
+    )]
+    TooManyPoints { max: usize, given: usize },
+
+    #[fail(
+        display = "Same no of points and values required. {} points and {} values",
+        points, values
+    )]
+    UnequalNoOfPointsValues { points: usize, values: usize },
+
+    #[fail(display = "Points must be distinct")]
+    DuplicatePoints,
+
+    #[fail(display = "Powers in the setup are not powers of the same secret")]
+    InconsistentSetup,
+
+    #[fail(display = "Error with message {:?}", msg)]
+    GeneralError { msg: String },
+}
+
+#[derive(Debug)]
+pub struct KZGError {
+    inner: Context<KZGErrorKind>,
+}
+
+impl KZGError {
+    pub fn kind(&self) -> KZGErrorKind {
+        self.inner.get_context().clone()
+    }
+
+    pub fn from_kind(kind: KZGErrorKind) -> Self {
+        Self {
+            inner: Context::new("").context(kind),
+        }
+    }
+}
+
+impl From<KZGErrorKind> for KZGError {
+    fn from(kind: KZGErrorKind) -> Self {
+        Self {
+            inner: Context::new(kind),
+        }
+    }
+}
+
+impl From<Context<KZGErrorKind>> for KZGError {
+    fn from(inner: Context<KZGErrorKind>) -> Self {
+        Self { inner }
+    }
+}
+
+impl Fail for KZGError {
+    fn cause(&self) -> Option<&dyn Fail> {
+        self.inner.cause()
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        self.inner.backtrace()
+    }
+}
+
+impl fmt::Display for KZGError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+/// Polynomial over the scalar field. Coefficients are stored lowest degree first and trailing zero
+/// coefficients are removed so the zero polynomial has no coefficients.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Polynomial(Vec<FieldElement>);
+
+impl Polynomial {
+    pub fn new(coefficients: Vec<FieldElement>) -> Self {
+        let mut p = Polynomial(coefficients);
+        p.trim();
+        p
+    }
+
+    pub fn zero() -> Self {
+        Polynomial(vec![])
+    }
+
+    /// Polynomial of the given degree with random coefficients
+    pub fn random(degree: usize) -> Self {
+        let mut coefficients: Vec<FieldElement> =
+            (0..degree).map(|_| FieldElement::random()).collect();
+        // Leading coefficient must be non zero for the degree to be exact
+        let mut leading = FieldElement::random();
+        while leading.is_zero() {
+            leading = FieldElement::random();
+        }
+        coefficients.push(leading);
+        Polynomial(coefficients)
+    }
+
+    /// The polynomial `(X - r_1)(X - r_2)...(X - r_n)` which is 0 exactly at the given roots.
+    pub fn vanishing(roots: &[FieldElement]) -> Self {
+        let mut coefficients = vec![FieldElement::one()];
+        for r in roots {
+            // Multiply by (X - r)
+            let mut next = vec![FieldElement::zero(); coefficients.len() + 1];
+            for (i, c) in coefficients.iter().enumerate() {
+                next[i + 1] += c;
+                next[i] -= c * r;
+            }
+            coefficients = next;
+        }
+        Polynomial(coefficients)
+    }
+
+    /// The unique polynomial of degree less than `points.len()` with `p(points[i]) == values[i]`
+    /// using Lagrange interpolation.
+    pub fn interpolate(points: &[FieldElement], values: &[FieldElement]) -> Result<Self, KZGError> {
+        if points.len() != values.len() {
+            return Err(KZGErrorKind::UnequalNoOfPointsValues {
+                points: points.len(),
+                values: values.len(),
+            }
+            .into());
+        }
+        check_distinct(points)?;
+
+        let vanishing = Self::vanishing(points);
+        let mut coefficients = vec![FieldElement::zero(); points.len()];
+        for (i, (x_i, y_i)) in points.iter().zip(values.iter()).enumerate() {
+            if y_i.is_zero() {
+                continue;
+            }
+            // l_i(X) = Z(X) / (X - x_i) / prod_{j != i}(x_i - x_j)
+            let (numerator, _) = vanishing.divide_by_linear(x_i);
+            let mut denominator = FieldElement::one();
+            for (j, x_j) in points.iter().enumerate() {
+                if i != j {
+                    denominator = denominator * (x_i - x_j);
+                }
+            }
+            let scale = y_i * denominator.inverse();
+            for (c, n) in coefficients.iter_mut().zip(numerator.0.iter()) {
+                *c += n * &scale;
+            }
+        }
+        Ok(Self::new(coefficients))
+    }
+
+    pub fn coefficients(&self) -> &[FieldElement] {
+        self.0.as_slice()
+    }
+
+    /// Degree of the polynomial. The zero polynomial is treated as having degree 0.
+    pub fn degree(&self) -> usize {
+        if self.0.is_empty() {
+            0
+        } else {
+            self.0.len() - 1
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Evaluate at `x` using Horner's rule
+    pub fn eval(&self, x: &FieldElement) -> FieldElement {
+        let mut result = FieldElement::zero();
+        for c in self.0.iter().rev() {
+            result = result * x + c;
+        }
+        result
+    }
+
+    /// Subtract the polynomial `other`
+    pub fn minus(&self, other: &Self) -> Self {
+        let len = std::cmp::max(self.0.len(), other.0.len());
+        let mut coefficients = vec![FieldElement::zero(); len];
+        for (i, c) in self.0.iter().enumerate() {
+            coefficients[i] += c;
+        }
+        for (i, c) in other.0.iter().enumerate() {
+            coefficients[i] -= c;
+        }
+        Self::new(coefficients)
+    }
+
+    /// Divide by `X - z` using synthetic division. Returns the quotient and the remainder, the
+    /// remainder is `p(z)`.
+    pub fn divide_by_linear(&self, z: &FieldElement) -> (Self, FieldElement) {
+        if self.0.is_empty() {
+            return (Self::zero(), FieldElement::zero());
+        }
+        let mut quotient = vec![FieldElement::zero(); self.0.len() - 1];
+        let mut carry = FieldElement::zero();
+        for i in (0..self.0.len()).rev() {
+            carry = &self.0[i] + carry * z;
+            if i > 0 {
+                quotient[i - 1] = carry.clone();
+            }
+        }
+        (Self::new(quotient), carry)
+    }
+
+    /// Long division by `divisor`. Returns the quotient and the remainder.
+    pub fn divide(&self, divisor: &Self) -> Result<(Self, Self), KZGError> {
+        if divisor.is_zero() {
+            return Err(KZGErrorKind::GeneralError {
+                msg: String::from("Division by zero polynomial"),
+            }
+            .into());
+        }
+        if self.0.len() < divisor.0.len() {
+            return Ok((Self::zero(), self.clone()));
+        }
+        let mut remainder = self.0.clone();
+        let mut quotient = vec![FieldElement::zero(); self.0.len() - divisor.0.len() + 1];
+        let lead_inv = divisor.0[divisor.0.len() - 1].inverse();
+        for i in (0..quotient.len()).rev() {
+            let q = &remainder[i + divisor.0.len() - 1] * &lead_inv;
+            for (j, d) in divisor.0.iter().enumerate() {
+                remainder[i + j] -= d * &q;
+            }
+            quotient[i] = q;
+        }
+        remainder.truncate(divisor.0.len() - 1);
+        Ok((Self::new(quotient), Self::new(remainder)))
+    }
+
+    fn trim(&mut self) {
+        while let Some(true) = self.0.last().map(|c| c.is_zero()) {
+            self.0.pop();
+        }
+    }
+}
+
+/// Commitment to a polynomial
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Commitment(pub G1);
+
+/// Proof that the committed polynomial evaluates to `value` at `point`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Opening {
+    pub point: FieldElement,
+    pub value: FieldElement,
+    pub proof: G1,
+}
+
+/// Proof that the committed polynomial evaluates to `values[i]` at `points[i]` for all `i`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MultiOpening {
+    pub points: Vec<FieldElement>,
+    pub values: Vec<FieldElement>,
+    pub proof: G1,
+}
+
+/// Structured reference string. Powers of the secret `tau` in G1 and G2. Deserializing checks the
+/// powers like `load` does.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "SetupPowers")]
+pub struct Setup {
+    powers_of_g1: Vec<G1>,
+    powers_of_g2: Vec<G2>,
+}
+
+/// The unchecked powers a `Setup` is deserialized from
+#[derive(Deserialize)]
+struct SetupPowers {
+    powers_of_g1: Vec<G1>,
+    powers_of_g2: Vec<G2>,
+}
+
+impl TryFrom<SetupPowers> for Setup {
+    type Error = KZGError;
+
+    fn try_from(powers: SetupPowers) -> Result<Self, KZGError> {
+        Self::load(powers.powers_of_g1, powers.powers_of_g2)
+    }
+}
+
+impl Setup {
+    /// Create a setup from a known secret. Anyone knowing `tau` can open commitments to any value,
+    /// so this is only meant for tests or when `tau` is discarded immediately after.
+    pub fn from_secret(
+        tau: &FieldElement,
+        max_degree: usize,
+        max_points: usize,
+        g1: &G1,
+        g2: &G2,
+    ) -> Self {
+        let mut powers_of_g1 = Vec::with_capacity(max_degree + 1);
+        let mut powers_of_g2 = Vec::with_capacity(max_points + 1);
+        let mut power = FieldElement::one();
+        for i in 0..=std::cmp::max(max_degree, max_points) {
+            if i <= max_degree {
+                powers_of_g1.push(g1 * &power);
+            }
+            if i <= max_points {
+                powers_of_g2.push(g2 * &power);
+            }
+            power = power * tau;
+        }
+        Self {
+            powers_of_g1,
+            powers_of_g2,
+        }
+    }
+
+    /// Create a setup with a random secret that is dropped once the powers are computed. The caller
+    /// has to trust this process, use a ceremony output with `load` otherwise.
+    pub fn new(max_degree: usize, max_points: usize) -> Self {
+        let tau = FieldElement::random();
+        Self::from_secret(
+            &tau,
+            max_degree,
+            max_points,
+            &G1::generator(),
+            &G2::generator(),
+        )
+    }
+
+    /// Load powers of tau produced by a trusted setup ceremony. Checks that the G1 and G2 powers
+    /// are of the same secret.
+    pub fn load(powers_of_g1: Vec<G1>, powers_of_g2: Vec<G2>) -> Result<Self, KZGError> {
+        if powers_of_g1.len() < 2 || powers_of_g2.len() < 2 {
+            return Err(KZGErrorKind::GeneralError {
+                msg: format!(
+                    "Setup needs at least 2 powers in each group, found {} and {}",
+                    powers_of_g1.len(),
+                    powers_of_g2.len()
+                ),
+            }
+            .into());
+        }
+        if powers_of_g1[0].is_identity() || powers_of_g2[0].is_identity() {
+            return Err(KZGErrorKind::InconsistentSetup.into());
+        }
+        let setup = Self {
+            powers_of_g1,
+            powers_of_g2,
+        };
+        if !setup.is_consistent() {
+            return Err(KZGErrorKind::InconsistentSetup.into());
+        }
+        Ok(setup)
+    }
+
+    pub fn powers_of_g1(&self) -> &[G1] {
+        &self.powers_of_g1
+    }
+
+    pub fn powers_of_g2(&self) -> &[G2] {
+        &self.powers_of_g2
+    }
+
+    /// Largest degree of a polynomial that can be committed to
+    pub fn max_degree(&self) -> usize {
+        self.powers_of_g1.len() - 1
+    }
+
+    /// Largest number of points that can be opened with a single `MultiOpening`
+    pub fn max_points(&self) -> usize {
+        self.powers_of_g2.len() - 1
+    }
+
+    pub fn commit(&self, poly: &Polynomial) -> Result<Commitment, KZGError> {
+        Ok(Commitment(self.commit_g1(poly)?))
+    }
+
+    /// Open the polynomial at `point`
+    pub fn open(&self, poly: &Polynomial, point: &FieldElement) -> Result<Opening, KZGError> {
+        self.check_degree(poly)?;
+        let (quotient, value) = poly.divide_by_linear(point);
+        Ok(Opening {
+            point: point.clone(),
+            value,
+            proof: self.commit_g1(&quotient)?,
+        })
+    }
+
+    /// Check `e(C - [y]_1, g2) == e(proof, [tau]_2 - [z]_2)`
+    pub fn verify(&self, commitment: &Commitment, opening: &Opening) -> bool {
+        let lhs = &commitment.0 - &(&self.powers_of_g1[0] * &opening.value);
+        let rhs = &self.powers_of_g2[1] - &(&self.powers_of_g2[0] * &opening.point);
+        GT::ate_2_pairing(&lhs, &self.powers_of_g2[0], &opening.proof.negation(), &rhs).is_one()
+    }
+
+    /// Verify several openings, possibly of different commitments at different points, with 2
+    /// pairings. Openings are combined with random weights so a single invalid opening fails the
+    /// batch with overwhelming probability.
+    pub fn batch_verify(&self, items: &[(&Commitment, &Opening)]) -> bool {
+        if items.is_empty() {
+            return true;
+        }
+        // For each opening, C - [y]_1 + z * proof == tau * proof so
+        // e(sum r_i (C_i - [y_i]_1 + z_i * proof_i), g2) == e(sum r_i proof_i, [tau]_2)
+        let mut lhs_bases = G1Vector::with_capacity(3 * items.len());
+        let mut lhs_scalars = Vec::with_capacity(3 * items.len());
+        let mut rhs_bases = G1Vector::with_capacity(items.len());
+        let mut rhs_scalars = Vec::with_capacity(items.len());
+        for (commitment, opening) in items {
+            let r = FieldElement::random();
+            lhs_bases.push(commitment.0.clone());
+            lhs_scalars.push(r.clone());
+            lhs_bases.push(self.powers_of_g1[0].clone());
+            lhs_scalars.push((&r * &opening.value).negation());
+            lhs_bases.push(opening.proof.clone());
+            lhs_scalars.push(&r * &opening.point);
+            rhs_bases.push(opening.proof.clone());
+            rhs_scalars.push(r);
+        }
+        let lhs = lhs_bases
+            .multi_scalar_mul_var_time(lhs_scalars.as_slice())
+            .unwrap();
+        let rhs = rhs_bases
+            .multi_scalar_mul_var_time(rhs_scalars.as_slice())
+            .unwrap();
+        GT::ate_2_pairing(
+            &lhs,
+            &self.powers_of_g2[0],
+            &rhs.negation(),
+            &self.powers_of_g2[1],
+        )
+        .is_one()
+    }
+
+    /// Open the polynomial at all `points` with a single proof
+    pub fn open_multi(
+        &self,
+        poly: &Polynomial,
+        points: &[FieldElement],
+    ) -> Result<MultiOpening, KZGError> {
+        self.check_degree(poly)?;
+        self.check_points(points)?;
+        check_distinct(points)?;
+        let values: Vec<FieldElement> = points.iter().map(|z| poly.eval(z)).collect();
+        let interpolated = Polynomial::interpolate(points, &values)?;
+        let (quotient, _) = poly
+            .minus(&interpolated)
+            .divide(&Polynomial::vanishing(points))?;
+        Ok(MultiOpening {
+            points: points.to_vec(),
+            values,
+            proof: self.commit_g1(&quotient)?,
+        })
+    }
+
+    /// Check `e(C - [I(tau)]_1, g2) == e(proof, [Z(tau)]_2)` where `I` interpolates the opened
+    /// values and `Z` vanishes on the opened points.
+    pub fn verify_multi(
+        &self,
+        commitment: &Commitment,
+        opening: &MultiOpening,
+    ) -> Result<bool, KZGError> {
+        self.check_points(&opening.points)?;
+        let interpolated = Polynomial::interpolate(&opening.points, &opening.values)?;
+        let vanishing = Polynomial::vanishing(&opening.points);
+        let lhs = &commitment.0 - &self.commit_g1(&interpolated)?;
+        let rhs = self.commit_g2(&vanishing)?;
+        Ok(
+            GT::ate_2_pairing(&lhs, &self.powers_of_g2[0], &opening.proof.negation(), &rhs)
+                .is_one(),
+        )
+    }
+
+    /// Serialize as the number of G1 and G2 powers as 4 byte big endian integers followed by the
+    /// powers
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            8 + self.powers_of_g1.len() * GroupG1_SIZE + self.powers_of_g2.len() * GroupG2_SIZE,
+        );
+        bytes.extend_from_slice(&(self.powers_of_g1.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&(self.powers_of_g2.len() as u32).to_be_bytes());
+        for p in &self.powers_of_g1 {
+            bytes.append(&mut p.to_bytes());
+        }
+        for p in &self.powers_of_g2 {
+            bytes.append(&mut p.to_bytes());
+        }
+        bytes
+    }
+
+    /// Deserialize the output of `to_bytes` and check consistency of the powers like `load`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, KZGError> {
+        if bytes.len() < 8 {
+            return Err(KZGErrorKind::GeneralError {
+                msg: format!("Setup needs at least 8 bytes, found {}", bytes.len()),
+            }
+            .into());
+        }
+        let g1_count = u32::from_be_bytes(*array_ref![bytes, 0, 4]) as usize;
+        let g2_count = u32::from_be_bytes(*array_ref![bytes, 4, 4]) as usize;
+        let expected = g1_count
+            .checked_mul(GroupG1_SIZE)
+            .and_then(|g1| g2_count.checked_mul(GroupG2_SIZE).map(|g2| (g1, g2)))
+            .and_then(|(g1, g2)| g1.checked_add(g2))
+            .and_then(|len| len.checked_add(8));
+        if expected != Some(bytes.len()) {
+            return Err(KZGErrorKind::GeneralError {
+                msg: format!("Unexpected setup length {}", bytes.len()),
+            }
+            .into());
+        }
+
+        let to_error = |e| {
+            KZGError::from_kind(KZGErrorKind::GeneralError {
+                msg: format!("{:?}", e),
+            })
+        };
+        let (g1_bytes, g2_bytes) = bytes[8..].split_at(g1_count * GroupG1_SIZE);
+        let powers_of_g1 = g1_bytes
+            .chunks(GroupG1_SIZE)
+            .map(|c| G1::from_bytes(c).map_err(to_error))
+            .collect::<Result<Vec<G1>, KZGError>>()?;
+        let powers_of_g2 = g2_bytes
+            .chunks(GroupG2_SIZE)
+            .map(|c| G2::from_bytes(c).map_err(to_error))
+            .collect::<Result<Vec<G2>, KZGError>>()?;
+        Self::load(powers_of_g1, powers_of_g2)
+    }
+
+    // Checks e(g1_{i+1}, h_0) == e(g1_i, h_1) and e(g1_0, h_{i+1}) == e(g1_1, h_i) for all i. Each
+    // check is combined with random weights so only 4 pairings are needed.
+    fn is_consistent(&self) -> bool {
+        let g1 = &self.powers_of_g1;
+        let g2 = &self.powers_of_g2;
+
+        let r: Vec<FieldElement> = (1..g1.len()).map(|_| FieldElement::random()).collect();
+        let shifted = G1Vector::from(&g1[1..])
+            .multi_scalar_mul_var_time(r.as_slice())
+            .unwrap();
+        let unshifted = G1Vector::from(&g1[..g1.len() - 1])
+            .multi_scalar_mul_var_time(r.as_slice())
+            .unwrap();
+        if !GT::ate_2_pairing(&shifted, &g2[0], &unshifted.negation(), &g2[1]).is_one() {
+            return false;
+        }
+
+        let r: Vec<FieldElement> = (1..g2.len()).map(|_| FieldElement::random()).collect();
+        let shifted = G2Vector::from(&g2[1..])
+            .multi_scalar_mul_var_time(r.as_slice())
+            .unwrap();
+        let unshifted = G2Vector::from(&g2[..g2.len() - 1])
+            .multi_scalar_mul_var_time(r.as_slice())
+            .unwrap();
+        GT::ate_2_pairing(&g1[0], &shifted, &g1[1].negation(), &unshifted).is_one()
+    }
+
+    fn commit_g1(&self, poly: &Polynomial) -> Result<G1, KZGError> {
+        self.check_degree(poly)?;
+        if poly.is_zero() {
+            return Ok(G1::identity());
+        }
+        Ok(G1Vector::from(&self.powers_of_g1[..poly.0.len()])
+            .multi_scalar_mul_var_time(poly.coefficients())
+            .unwrap())
+    }
+
+    fn commit_g2(&self, poly: &Polynomial) -> Result<G2, KZGError> {
+        if poly.degree() > self.max_points() {
+            return Err(KZGErrorKind::TooManyPoints {
+                max: self.max_points(),
+                given: poly.degree(),
+            }
+            .into());
+        }
+        if poly.is_zero() {
+            return Ok(G2::identity());
+        }
+        Ok(G2Vector::from(&self.powers_of_g2[..poly.0.len()])
+            .multi_scalar_mul_var_time(poly.coefficients())
+            .unwrap())
+    }
+
+    fn check_degree(&self, poly: &Polynomial) -> Result<(), KZGError> {
+        if poly.degree() > self.max_degree() {
+            return Err(KZGErrorKind::DegreeTooLarge {
+                max: self.max_degree(),
+                given: poly.degree(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    fn check_points(&self, points: &[FieldElement]) -> Result<(), KZGError> {
+        if points.is_empty() {
+            return Err(KZGErrorKind::GeneralError {
+                msg: String::from("No points to open at"),
+            }
+            .into());
+        }
+        if points.len() > self.max_points() {
+            return Err(KZGErrorKind::TooManyPoints {
+                max: self.max_points(),
+                given: points.len(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+fn check_distinct(points: &[FieldElement]) -> Result<(), KZGError> {
+    for i in 0..points.len() {
+        for j in (i + 1)..points.len() {
+            if points[i] == points[j] {
+                return Err(KZGErrorKind::DuplicatePoints.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polynomial_division_and_interpolation() {
+        let p = Polynomial::random(7);
+        let z = FieldElement::random();
+        let (q, r) = p.divide_by_linear(&z);
+        assert_eq!(r, p.eval(&z));
+        assert_eq!(q.degree(), 6);
+
+        let points: Vec<FieldElement> = (0..5).map(|_| FieldElement::random()).collect();
+        let values: Vec<FieldElement> = points.iter().map(|x| p.eval(x)).collect();
+        let i = Polynomial::interpolate(&points, &values).unwrap();
+        assert!(i.degree() < 5);
+        for (x, y) in points.iter().zip(values.iter()) {
+            assert_eq!(&i.eval(x), y);
+        }
+
+        let z = Polynomial::vanishing(&points);
+        let (q, r) = p.minus(&i).divide(&z).unwrap();
+        assert!(r.is_zero());
+        assert_eq!(q.degree(), 2);
+
+        let mut duplicate = points.clone();
+        duplicate.push(points[0].clone());
+        let mut more_values = values.clone();
+        more_values.push(values[0].clone());
+        assert!(Polynomial::interpolate(&duplicate, &more_values).is_err());
+    }
+
+    #[test]
+    fn test_open_and_verify() {
+        let setup = Setup::new(10, 4);
+        let p = Polynomial::random(10);
+        let c = setup.commit(&p).unwrap();
+        let opening = setup.open(&p, &FieldElement::random()).unwrap();
+        assert!(setup.verify(&c, &opening));
+
+        let mut wrong_value = opening.clone();
+        wrong_value.value = FieldElement::random();
+        assert!(!setup.verify(&c, &wrong_value));
+
+        let mut wrong_point = opening.clone();
+        wrong_point.point = FieldElement::random();
+        assert!(!setup.verify(&c, &wrong_point));
+
+        let other = setup.commit(&Polynomial::random(10)).unwrap();
+        assert!(!setup.verify(&other, &opening));
+
+        assert!(setup.commit(&Polynomial::random(11)).is_err());
+    }
+
+    #[test]
+    fn test_batch_verify() {
+        let setup = Setup::new(8, 2);
+        let polys: Vec<Polynomial> = (0..4).map(|i| Polynomial::random(5 + i)).collect();
+        let commitments: Vec<Commitment> = polys.iter().map(|p| setup.commit(p).unwrap()).collect();
+        let mut openings: Vec<Opening> = polys
+            .iter()
+            .map(|p| setup.open(p, &FieldElement::random()).unwrap())
+            .collect();
+
+        let items: Vec<(&Commitment, &Opening)> = commitments.iter().zip(openings.iter()).collect();
+        assert!(setup.batch_verify(&items));
+        assert!(setup.batch_verify(&[]));
+
+        openings[2].value = FieldElement::random();
+        let items: Vec<(&Commitment, &Opening)> = commitments.iter().zip(openings.iter()).collect();
+        assert!(!setup.batch_verify(&items));
+    }
+
+    #[test]
+    fn test_multi_point_opening() {
+        let setup = Setup::new(12, 5);
+        let p = Polynomial::random(12);
+        let c = setup.commit(&p).unwrap();
+        let points: Vec<FieldElement> = (0..5).map(|_| FieldElement::random()).collect();
+        let opening = setup.open_multi(&p, &points).unwrap();
+        assert!(setup.verify_multi(&c, &opening).unwrap());
+
+        let mut wrong = opening.clone();
+        wrong.values[3] = FieldElement::random();
+        assert!(!setup.verify_multi(&c, &wrong).unwrap());
+
+        let too_many: Vec<FieldElement> = (0..6).map(|_| FieldElement::random()).collect();
+        assert!(setup.open_multi(&p, &too_many).is_err());
+    }
+
+    #[test]
+    fn test_setup_loading() {
+        let setup = Setup::new(6, 3);
+        let bytes = setup.to_bytes();
+        let loaded = Setup::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.max_degree(), 6);
+        assert_eq!(loaded.max_points(), 3);
+
+        let p = Polynomial::random(6);
+        let c = setup.commit(&p).unwrap();
+        let opening = loaded.open(&p, &FieldElement::random()).unwrap();
+        assert!(loaded.verify(&c, &opening));
+
+        // Replacing a power breaks consistency
+        let mut g1 = setup.powers_of_g1.clone();
+        g1[4] = G1::random();
+        assert!(Setup::load(g1, setup.powers_of_g2.clone()).is_err());
+
+        let mut g2 = setup.powers_of_g2.clone();
+        g2[2] = G2::random();
+        assert!(Setup::load(setup.powers_of_g1.clone(), g2).is_err());
+
+        assert!(Setup::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_setup_serialization() {
+        let setup = Setup::new(6, 3);
+        let json = serde_json::to_string(&setup).unwrap();
+        let parsed: Setup = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.powers_of_g1(), setup.powers_of_g1());
+        assert_eq!(parsed.powers_of_g2(), setup.powers_of_g2());
+
+        // Deserializing goes through the same checks as `load`
+        let mut g1 = setup.powers_of_g1.clone();
+        g1[4] = G1::random();
+        let json = serde_json::json!({
+            "powers_of_g1": g1,
+            "powers_of_g2": setup.powers_of_g2,
+        });
+        assert!(serde_json::from_value::<Setup>(json).is_err());
+        let json = serde_json::json!({
+            "powers_of_g1": setup.powers_of_g1[..1].to_vec(),
+            "powers_of_g2": setup.powers_of_g2,
+        });
+        assert!(serde_json::from_value::<Setup>(json).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 //use hash_functions::HashError;
 
+pub mod kzg;
 #[macro_use]
 pub mod pok_vc;
 
@@ -0,0 +1,61 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! The errors that are generated by this crate
+//!
+//! Uses a kind enum for the error type
+
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// A specialized [`Result`] type for Accumulator operations.
+pub type AccumulatorResult<T> = Result<T, AccumulatorError>;
+
+/// The error type for Accumulator operations.
+#[derive(Copy, Clone, Debug)]
+pub enum AccumulatorError {
+    /// The requested prime size is too small to be secure
+    InvalidPrimeSize(usize),
+    /// Failed to generate a safe prime
+    PrimeGeneration,
+    /// The supplied factors are not distinct safe primes
+    InvalidSecretKey,
+    /// The element is already a member of the set
+    ElementIsMember,
+    /// The element is not a member of the set
+    ElementNotMember,
+    /// A value could not be inverted modulo the group modulus
+    NotInvertible,
+    /// Deserializing an empty or malformed value
+    InvalidBytes,
+}
+
+impl Display for AccumulatorError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use AccumulatorError::*;
+
+        match *self {
+            InvalidPrimeSize(bits) => write!(f, "Prime size of {} bits is too small", bits),
+            PrimeGeneration => write!(f, "Unable to generate a safe prime"),
+            InvalidSecretKey => write!(f, "Secret key factors must be distinct safe primes"),
+            ElementIsMember => write!(f, "Element is a member of the accumulator"),
+            ElementNotMember => write!(f, "Element is not a member of the accumulator"),
+            NotInvertible => write!(f, "Value has no inverse modulo the group modulus"),
+            InvalidBytes => write!(f, "Invalid byte sequence"),
+        }
+    }
+}
+
+impl Error for AccumulatorError {}
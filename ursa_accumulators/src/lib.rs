@@ -1 +1,42 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+#![deny(
+    missing_docs,
+    trivial_casts,
+    trivial_numeric_casts,
+    unconditional_recursion,
+    unused_import_braces,
+    unused_lifetimes,
+    unused_qualifications,
+    unused_extern_crates,
+    unused_parens,
+    while_true
+)]
+//! Implements cryptographic accumulators.
+//!
+//! An accumulator is a short value that represents a set. Holders of a
+//! witness can prove that an element is (or is not) a member of the set
+//! without the verifier needing the set itself.
+//!
+//! The RSA accumulator works in a group of unknown order as described by
+//! Boneh, Bünz and Fisch (see <https://eprint.iacr.org/2018/1188>)
+//! with non-membership witnesses as described by Li, Li and Xue
+//! (see <https://link.springer.com/chapter/10.1007/978-3-540-72738-5_17>).
+//! It requires no pairing friendly curve or trusted pairing setup,
+//! only an RSA modulus whose factorization is discarded or held by the manager.
 
+/// Accumulator Errors and Results
+pub mod error;
+/// RSA accumulator in a group of unknown order
+pub mod rsa;
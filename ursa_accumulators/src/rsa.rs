@@ -0,0 +1,666 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::error::{AccumulatorError, AccumulatorResult};
+use glass_pumpkin::{prime, safe_prime};
+use num_bigint::{BigInt, BigUint, RandBigInt, Sign};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::convert::TryFrom;
+
+/// The default size in bits of each safe prime factor of the modulus
+pub const DEFAULT_PRIME_BITS: usize = 1024;
+/// The smallest prime size accepted when generating a secret key.
+/// Anything less than [`DEFAULT_PRIME_BITS`] should only be used for testing.
+pub const MIN_PRIME_BITS: usize = 128;
+
+const HASH_TO_PRIME_DST: &[u8] = b"URSA_RSA_ACCUMULATOR_HASH_TO_PRIME_";
+const UPDATE_PROOF_DST: &[u8] = b"URSA_RSA_ACCUMULATOR_UPDATE_PROOF_";
+
+/// The factorization of the RSA modulus. Whoever holds this can
+/// remove elements from the accumulator and create witnesses directly.
+/// Deployments that want no trapdoor should discard it after
+/// creating the [`PublicKey`].
+#[derive(Clone)]
+pub struct SecretKey {
+    p: BigUint,
+    q: BigUint,
+}
+
+impl SecretKey {
+    /// Create a new secret key with [`DEFAULT_PRIME_BITS`] safe primes
+    pub fn new(rng: &mut (impl RngCore + CryptoRng)) -> AccumulatorResult<Self> {
+        Self::with_prime_bits(rng, DEFAULT_PRIME_BITS)
+    }
+
+    /// Create a new secret key whose safe primes are `bits` in size
+    pub fn with_prime_bits(
+        rng: &mut (impl RngCore + CryptoRng),
+        bits: usize,
+    ) -> AccumulatorResult<Self> {
+        if bits < MIN_PRIME_BITS {
+            return Err(AccumulatorError::InvalidPrimeSize(bits));
+        }
+        let p = safe_prime::from_rng(bits, rng).map_err(|_| AccumulatorError::PrimeGeneration)?;
+        let mut q =
+            safe_prime::from_rng(bits, rng).map_err(|_| AccumulatorError::PrimeGeneration)?;
+        while p == q {
+            q = safe_prime::from_rng(bits, rng).map_err(|_| AccumulatorError::PrimeGeneration)?;
+        }
+        Ok(Self { p, q })
+    }
+
+    /// Create a secret key from existing safe primes
+    pub fn from_primes(p: BigUint, q: BigUint) -> AccumulatorResult<Self> {
+        if p == q || !safe_prime::check(&p) || !safe_prime::check(&q) {
+            return Err(AccumulatorError::InvalidSecretKey);
+        }
+        Ok(Self { p, q })
+    }
+
+    /// Create the public parameters with a random quadratic residue as the generator
+    pub fn public_key(&self, rng: &mut (impl RngCore + CryptoRng)) -> PublicKey {
+        let n = self.modulus();
+        let two = BigUint::from(2u32);
+        loop {
+            let r = rng.gen_biguint_range(&two, &n);
+            if r.gcd(&n).is_one() {
+                let g = r.modpow(&two, &n);
+                return PublicKey { n, g };
+            }
+        }
+    }
+
+    /// The RSA modulus `n = pq`
+    pub fn modulus(&self) -> BigUint {
+        &self.p * &self.q
+    }
+
+    /// The order of the quadratic residues `p'q'` where `p = 2p' + 1` and `q = 2q' + 1`
+    fn group_order(&self) -> BigUint {
+        let p1: BigUint = (&self.p - 1u32) >> 1;
+        let q1: BigUint = (&self.q - 1u32) >> 1;
+        p1 * q1
+    }
+}
+
+/// The public parameters of the accumulator: the RSA modulus
+/// and a generator of the quadratic residues.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey {
+    n: BigUint,
+    g: BigUint,
+}
+
+impl PublicKey {
+    /// The RSA modulus
+    pub fn modulus(&self) -> &BigUint {
+        &self.n
+    }
+
+    /// The generator used as the empty accumulator value
+    pub fn generator(&self) -> &BigUint {
+        &self.g
+    }
+
+    /// Serialize the public key to a byte array
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut o = Vec::new();
+        write_biguint(&mut o, &self.n);
+        write_biguint(&mut o, &self.g);
+        o
+    }
+}
+
+impl TryFrom<&[u8]> for PublicKey {
+    type Error = AccumulatorError;
+
+    fn try_from(value: &[u8]) -> AccumulatorResult<Self> {
+        let mut offset = 0;
+        let n = read_biguint(value, &mut offset)?;
+        let g = read_biguint(value, &mut offset)?;
+        if offset != value.len() || n.is_zero() || g.is_zero() || g >= n {
+            return Err(AccumulatorError::InvalidBytes);
+        }
+        Ok(Self { n, g })
+    }
+}
+
+/// A value that can be added to the accumulator.
+/// Each value is represented by a prime number derived by hashing.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Element(BigUint);
+
+impl Element {
+    /// Map arbitrary bytes to a prime representative
+    pub fn hash<B: AsRef<[u8]>>(data: B) -> Self {
+        Self(hash_to_prime(HASH_TO_PRIME_DST, &[data.as_ref()]))
+    }
+
+    /// Use an existing prime as the representative
+    pub fn from_prime(value: BigUint) -> AccumulatorResult<Self> {
+        if value <= BigUint::from(2u32) || !prime::check(&value) {
+            return Err(AccumulatorError::InvalidBytes);
+        }
+        Ok(Self(value))
+    }
+
+    /// The prime representative of this element
+    pub fn value(&self) -> &BigUint {
+        &self.0
+    }
+
+    /// Serialize the element to a byte array
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes_be()
+    }
+}
+
+impl TryFrom<&[u8]> for Element {
+    type Error = AccumulatorError;
+
+    fn try_from(value: &[u8]) -> AccumulatorResult<Self> {
+        Self::from_prime(BigUint::from_bytes_be(value))
+    }
+}
+
+/// The accumulated value `g^{x_1 x_2 ... x_n} mod n`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Accumulator(BigUint);
+
+impl Accumulator {
+    /// Create an empty accumulator
+    pub fn new(pk: &PublicKey) -> Self {
+        Self(pk.g.clone())
+    }
+
+    /// Create an accumulator with an initial set of elements
+    pub fn with_elements(pk: &PublicKey, elements: &[Element]) -> Self {
+        Self::new(pk).add_elements(pk, elements)
+    }
+
+    /// Add a single element. Does not require the secret key.
+    pub fn add(&self, pk: &PublicKey, element: &Element) -> Self {
+        Self(self.0.modpow(&element.0, &pk.n))
+    }
+
+    /// Add many elements with a single exponentiation. Does not require the secret key.
+    pub fn add_elements(&self, pk: &PublicKey, elements: &[Element]) -> Self {
+        Self(self.0.modpow(&product(elements), &pk.n))
+    }
+
+    /// Add many elements and return a proof that the new value
+    /// is the old value raised to the elements. Stateless clients
+    /// can check the proof much faster than recomputing the update.
+    pub fn add_elements_with_proof(
+        &self,
+        pk: &PublicKey,
+        elements: &[Element],
+    ) -> (Self, UpdateProof) {
+        let new = self.add_elements(pk, elements);
+        let proof = UpdateProof::new(pk, self, &new, elements);
+        (new, proof)
+    }
+
+    /// Remove a single element using the secret key
+    pub fn remove(&self, sk: &SecretKey, element: &Element) -> AccumulatorResult<Self> {
+        self.remove_elements(sk, std::slice::from_ref(element))
+    }
+
+    /// Remove many elements with a single exponentiation using the secret key.
+    /// The caller is responsible for only removing current members.
+    pub fn remove_elements(&self, sk: &SecretKey, elements: &[Element]) -> AccumulatorResult<Self> {
+        let exp = mod_inverse(&product(elements), &sk.group_order())
+            .ok_or(AccumulatorError::NotInvertible)?;
+        Ok(Self(self.0.modpow(&exp, &sk.modulus())))
+    }
+
+    /// The accumulated value
+    pub fn value(&self) -> &BigUint {
+        &self.0
+    }
+
+    /// Serialize the accumulator to a byte array
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes_be()
+    }
+}
+
+impl TryFrom<&[u8]> for Accumulator {
+    type Error = AccumulatorError;
+
+    fn try_from(value: &[u8]) -> AccumulatorResult<Self> {
+        if value.is_empty() {
+            return Err(AccumulatorError::InvalidBytes);
+        }
+        Ok(Self(BigUint::from_bytes_be(value)))
+    }
+}
+
+/// A witness `w` that an element `x` is a member where `w^x = A`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MembershipWitness(BigUint);
+
+impl MembershipWitness {
+    /// Create a witness using the secret key.
+    /// The caller is responsible for checking the element is a member.
+    pub fn new(
+        sk: &SecretKey,
+        accumulator: &Accumulator,
+        element: &Element,
+    ) -> AccumulatorResult<Self> {
+        Ok(Self(accumulator.remove(sk, element)?.0))
+    }
+
+    /// Create a witness without the secret key from the current set of members
+    pub fn from_members(
+        pk: &PublicKey,
+        element: &Element,
+        members: &[Element],
+    ) -> AccumulatorResult<Self> {
+        let index = members
+            .iter()
+            .position(|m| m == element)
+            .ok_or(AccumulatorError::ElementNotMember)?;
+        let exp = members
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != index)
+            .fold(BigUint::one(), |acc, (_, m)| acc * &m.0);
+        Ok(Self(pk.g.modpow(&exp, &pk.n)))
+    }
+
+    /// Check the witness against the accumulator
+    pub fn verify(&self, pk: &PublicKey, accumulator: &Accumulator, element: &Element) -> bool {
+        self.0.modpow(&element.0, &pk.n) == accumulator.0
+    }
+
+    /// Update the witness after `added` were added to the accumulator
+    pub fn update_on_add(&self, pk: &PublicKey, added: &[Element]) -> Self {
+        Self(self.0.modpow(&product(added), &pk.n))
+    }
+
+    /// Update the witness after `removed` were removed from the accumulator
+    /// resulting in `accumulator`.
+    pub fn update_on_remove(
+        &self,
+        pk: &PublicKey,
+        element: &Element,
+        removed: &[Element],
+        accumulator: &Accumulator,
+    ) -> AccumulatorResult<Self> {
+        // Find a, b such that ax + by = 1 then
+        // w' = w^b A'^a since w'^x = A^b A'^{ax} = A'^{by + ax} = A'
+        let x = BigInt::from(element.0.clone());
+        let y = BigInt::from(product(removed));
+        let e = x.extended_gcd(&y);
+        if !e.gcd.is_one() {
+            return Err(AccumulatorError::ElementNotMember);
+        }
+        let w = pow_signed(&self.0, &e.y, &pk.n)?;
+        let a = pow_signed(&accumulator.0, &e.x, &pk.n)?;
+        Ok(Self((w * a) % &pk.n))
+    }
+
+    /// Serialize the witness to a byte array
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes_be()
+    }
+}
+
+impl TryFrom<&[u8]> for MembershipWitness {
+    type Error = AccumulatorError;
+
+    fn try_from(value: &[u8]) -> AccumulatorResult<Self> {
+        if value.is_empty() {
+            return Err(AccumulatorError::InvalidBytes);
+        }
+        Ok(Self(BigUint::from_bytes_be(value)))
+    }
+}
+
+/// A witness `(a, d)` that an element `x` is not a member where `A^a = d^x g`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NonMembershipWitness {
+    a: BigUint,
+    d: BigUint,
+}
+
+impl NonMembershipWitness {
+    /// Create a witness from the current set of members
+    pub fn new(pk: &PublicKey, element: &Element, members: &[Element]) -> AccumulatorResult<Self> {
+        let u = product(members);
+        let x = &element.0;
+        // a = u^-1 mod x so au - 1 = bx for some b and
+        // A^a = g^{au} = g^{bx + 1} = (g^b)^x g
+        let a = mod_inverse(&(&u % x), x).ok_or(AccumulatorError::ElementIsMember)?;
+        let b = (&a * &u - 1u32) / x;
+        Ok(Self {
+            a,
+            d: pk.g.modpow(&b, &pk.n),
+        })
+    }
+
+    /// Check the witness against the accumulator
+    pub fn verify(&self, pk: &PublicKey, accumulator: &Accumulator, element: &Element) -> bool {
+        if self.a.is_zero() {
+            return false;
+        }
+        let lhs = accumulator.0.modpow(&self.a, &pk.n);
+        let rhs = (self.d.modpow(&element.0, &pk.n) * &pk.g) % &pk.n;
+        lhs == rhs
+    }
+
+    /// Update the witness after `added` were added to `accumulator`
+    /// where `accumulator` is the value before the addition.
+    pub fn update_on_add(
+        &self,
+        pk: &PublicKey,
+        accumulator: &Accumulator,
+        element: &Element,
+        added: &[Element],
+    ) -> AccumulatorResult<Self> {
+        // a' = a y^-1 mod x then a'y = a + kx and
+        // A'^a' = A^{a + kx} = (d A^k)^x g
+        let x = &element.0;
+        let y = product(added);
+        let y_inv = mod_inverse(&(&y % x), x).ok_or(AccumulatorError::ElementIsMember)?;
+        let a = (&self.a * y_inv) % x;
+        let k = (BigInt::from(&a * &y) - BigInt::from(self.a.clone())) / BigInt::from(x.clone());
+        let d = (&self.d * pow_signed(&accumulator.0, &k, &pk.n)?) % &pk.n;
+        Ok(Self { a, d })
+    }
+
+    /// Update the witness after `removed` were removed from the accumulator
+    /// resulting in `accumulator`.
+    pub fn update_on_remove(
+        &self,
+        pk: &PublicKey,
+        accumulator: &Accumulator,
+        element: &Element,
+        removed: &[Element],
+    ) -> AccumulatorResult<Self> {
+        // A'^{ay} = A^a = d^x g so with a' = ay mod x and ay = a' + kx
+        // A'^a' = (d A'^-k)^x g
+        let x = &element.0;
+        let ay = &self.a * product(removed);
+        let a = &ay % x;
+        let k = BigInt::from((ay - &a) / x);
+        let d = (&self.d * pow_signed(&accumulator.0, &-k, &pk.n)?) % &pk.n;
+        Ok(Self { a, d })
+    }
+
+    /// Serialize the witness to a byte array
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut o = Vec::new();
+        write_biguint(&mut o, &self.a);
+        write_biguint(&mut o, &self.d);
+        o
+    }
+}
+
+impl TryFrom<&[u8]> for NonMembershipWitness {
+    type Error = AccumulatorError;
+
+    fn try_from(value: &[u8]) -> AccumulatorResult<Self> {
+        let mut offset = 0;
+        let a = read_biguint(value, &mut offset)?;
+        let d = read_biguint(value, &mut offset)?;
+        if offset != value.len() {
+            return Err(AccumulatorError::InvalidBytes);
+        }
+        Ok(Self { a, d })
+    }
+}
+
+/// A non-interactive proof of exponentiation (see <https://eprint.iacr.org/2018/623>)
+/// that `A' = A^{x_1 ... x_n}` for a batch of added elements.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UpdateProof(BigUint);
+
+impl UpdateProof {
+    /// Create a proof that `new` is `old` with `elements` added
+    pub fn new(pk: &PublicKey, old: &Accumulator, new: &Accumulator, elements: &[Element]) -> Self {
+        let y = product(elements);
+        let l = Self::challenge(pk, old, new, &y);
+        Self(old.0.modpow(&(y / l), &pk.n))
+    }
+
+    /// Check that `new` is `old` with `elements` added
+    pub fn verify(
+        &self,
+        pk: &PublicKey,
+        old: &Accumulator,
+        new: &Accumulator,
+        elements: &[Element],
+    ) -> bool {
+        let y = product(elements);
+        let l = Self::challenge(pk, old, new, &y);
+        let r = y % &l;
+        let lhs = (self.0.modpow(&l, &pk.n) * old.0.modpow(&r, &pk.n)) % &pk.n;
+        lhs == new.0
+    }
+
+    fn challenge(pk: &PublicKey, old: &Accumulator, new: &Accumulator, y: &BigUint) -> BigUint {
+        hash_to_prime(
+            UPDATE_PROOF_DST,
+            &[
+                &pk.to_bytes(),
+                &old.to_bytes(),
+                &new.to_bytes(),
+                &y.to_bytes_be(),
+            ],
+        )
+    }
+
+    /// Serialize the proof to a byte array
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes_be()
+    }
+}
+
+impl TryFrom<&[u8]> for UpdateProof {
+    type Error = AccumulatorError;
+
+    fn try_from(value: &[u8]) -> AccumulatorResult<Self> {
+        if value.is_empty() {
+            return Err(AccumulatorError::InvalidBytes);
+        }
+        Ok(Self(BigUint::from_bytes_be(value)))
+    }
+}
+
+/// Hash to a 256-bit prime by trying successive counters
+fn hash_to_prime(dst: &[u8], data: &[&[u8]]) -> BigUint {
+    let mut counter = 0u32;
+    loop {
+        let mut hasher = Sha256::new();
+        hasher.update(dst);
+        hasher.update(counter.to_be_bytes());
+        for d in data {
+            hasher.update((d.len() as u32).to_be_bytes());
+            hasher.update(d);
+        }
+        let mut bytes = hasher.finalize();
+        // Ensure the candidate is 256 bits and odd
+        bytes[0] |= 0x80;
+        bytes[31] |= 1;
+        let candidate = BigUint::from_bytes_be(&bytes);
+        if prime::check(&candidate) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+fn product(elements: &[Element]) -> BigUint {
+    elements.iter().fold(BigUint::one(), |acc, e| acc * &e.0)
+}
+
+fn mod_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+    let m = BigInt::from(m.clone());
+    let e = BigInt::from(a.clone()).extended_gcd(&m);
+    if !e.gcd.is_one() {
+        return None;
+    }
+    e.x.mod_floor(&m).to_biguint()
+}
+
+/// Compute `base^exp mod n` where `exp` may be negative
+fn pow_signed(base: &BigUint, exp: &BigInt, n: &BigUint) -> AccumulatorResult<BigUint> {
+    let (sign, magnitude) = (exp.sign(), exp.magnitude());
+    if sign == Sign::Minus {
+        let inv = mod_inverse(base, n).ok_or(AccumulatorError::NotInvertible)?;
+        Ok(inv.modpow(magnitude, n))
+    } else {
+        Ok(base.modpow(magnitude, n))
+    }
+}
+
+fn write_biguint(out: &mut Vec<u8>, value: &BigUint) {
+    let bytes = value.to_bytes_be();
+    out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    out.extend_from_slice(&bytes);
+}
+
+fn read_biguint(value: &[u8], offset: &mut usize) -> AccumulatorResult<BigUint> {
+    if value.len() < *offset + 4 {
+        return Err(AccumulatorError::InvalidBytes);
+    }
+    let mut size = [0u8; 4];
+    size.copy_from_slice(&value[*offset..*offset + 4]);
+    let size = u32::from_be_bytes(size) as usize;
+    *offset += 4;
+    if size == 0 || value.len() < *offset + size {
+        return Err(AccumulatorError::InvalidBytes);
+    }
+    let v = BigUint::from_bytes_be(&value[*offset..*offset + size]);
+    *offset += size;
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    fn setup() -> (SecretKey, PublicKey) {
+        let mut rng = OsRng;
+        let sk = SecretKey::with_prime_bits(&mut rng, MIN_PRIME_BITS).unwrap();
+        let pk = sk.public_key(&mut rng);
+        (sk, pk)
+    }
+
+    fn elements(count: usize) -> Vec<Element> {
+        (0..count)
+            .map(|i| Element::hash(format!("element {}", i)))
+            .collect()
+    }
+
+    #[test]
+    fn small_primes_rejected() {
+        let mut rng = OsRng;
+        assert!(SecretKey::with_prime_bits(&mut rng, 64).is_err());
+    }
+
+    #[test]
+    fn membership() {
+        let (sk, pk) = setup();
+        let members = elements(5);
+        let acc = Accumulator::with_elements(&pk, &members);
+
+        let w1 = MembershipWitness::new(&sk, &acc, &members[2]).unwrap();
+        let w2 = MembershipWitness::from_members(&pk, &members[2], &members).unwrap();
+        assert_eq!(w1, w2);
+        assert!(w1.verify(&pk, &acc, &members[2]));
+        assert!(!w1.verify(&pk, &acc, &members[3]));
+
+        let outsider = Element::hash(b"outsider");
+        assert!(MembershipWitness::from_members(&pk, &outsider, &members).is_err());
+    }
+
+    #[test]
+    fn membership_updates() {
+        let (sk, pk) = setup();
+        let members = elements(4);
+        let acc = Accumulator::with_elements(&pk, &members);
+        let w = MembershipWitness::new(&sk, &acc, &members[0]).unwrap();
+
+        let added = elements(7)[4..].to_vec();
+        let acc = acc.add_elements(&pk, &added);
+        let w = w.update_on_add(&pk, &added);
+        assert!(w.verify(&pk, &acc, &members[0]));
+
+        let acc = acc.remove_elements(&sk, &members[1..3]).unwrap();
+        let w = w
+            .update_on_remove(&pk, &members[0], &members[1..3], &acc)
+            .unwrap();
+        assert!(w.verify(&pk, &acc, &members[0]));
+    }
+
+    #[test]
+    fn non_membership() {
+        let (sk, pk) = setup();
+        let members = elements(5);
+        let acc = Accumulator::with_elements(&pk, &members);
+        let outsider = Element::hash(b"outsider");
+
+        let w = NonMembershipWitness::new(&pk, &outsider, &members).unwrap();
+        assert!(w.verify(&pk, &acc, &outsider));
+        assert!(!w.verify(&pk, &acc, &members[0]));
+        assert!(NonMembershipWitness::new(&pk, &members[0], &members).is_err());
+
+        let added = elements(8)[5..].to_vec();
+        let new_acc = acc.add_elements(&pk, &added);
+        let w = w.update_on_add(&pk, &acc, &outsider, &added).unwrap();
+        assert!(w.verify(&pk, &new_acc, &outsider));
+
+        let acc = new_acc.remove_elements(&sk, &members[..2]).unwrap();
+        let w = w
+            .update_on_remove(&pk, &acc, &outsider, &members[..2])
+            .unwrap();
+        assert!(w.verify(&pk, &acc, &outsider));
+    }
+
+    #[test]
+    fn batch_update_proof() {
+        let (_, pk) = setup();
+        let acc = Accumulator::new(&pk);
+        let added = elements(10);
+        let (new_acc, proof) = acc.add_elements_with_proof(&pk, &added);
+        assert!(proof.verify(&pk, &acc, &new_acc, &added));
+        assert!(!proof.verify(&pk, &acc, &new_acc, &added[1..]));
+        assert_eq!(new_acc, Accumulator::with_elements(&pk, &added));
+    }
+
+    #[test]
+    fn serialization() {
+        let (_, pk) = setup();
+        let members = elements(3);
+        let acc = Accumulator::with_elements(&pk, &members);
+        let outsider = Element::hash(b"outsider");
+        let w = NonMembershipWitness::new(&pk, &outsider, &members).unwrap();
+
+        let pk2 = PublicKey::try_from(pk.to_bytes().as_slice()).unwrap();
+        let acc2 = Accumulator::try_from(acc.to_bytes().as_slice()).unwrap();
+        let w2 = NonMembershipWitness::try_from(w.to_bytes().as_slice()).unwrap();
+        let e2 = Element::try_from(outsider.to_bytes().as_slice()).unwrap();
+        assert_eq!(pk, pk2);
+        assert_eq!(acc, acc2);
+        assert_eq!(w, w2);
+        assert_eq!(outsider, e2);
+        assert!(PublicKey::try_from(&[0u8; 3][..]).is_err());
+    }
+}
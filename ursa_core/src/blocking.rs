@@ -0,0 +1,259 @@
+//! Futures for expensive operations that run off the async executor.
+//!
+//! Key generation or a batch verification over thousands of signatures can
+//! take seconds of CPU time, which an async service can't spend on an
+//! executor thread without stalling every other task scheduled there.
+//! [`spawn_blocking`] runs such an operation on another thread and returns
+//! a future for its result. It doesn't depend on any async runtime: with the
+//! `parallel` feature the operation runs on the rayon pool, otherwise on a
+//! thread of its own.
+//!
+//! # Cancellation
+//!
+//! Dropping a [`Blocking`] future cancels its operation. An operation that
+//! hasn't started by then is skipped. One started with [`spawn_cancellable`]
+//! can also poll its [`CancelToken`] between steps and stop early.
+//!
+//! ```ignore
+//! let valid = blocking::spawn_cancellable(move |token| {
+//!     inputs.iter().all(|i| !token.is_cancelled() && verify(i))
+//! })
+//! .await;
+//! ```
+
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+
+/// Tells a running operation whether its result is still wanted
+#[derive(Clone, Debug)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// True once the `Blocking` future for the operation has been dropped
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+struct State<T> {
+    result: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+/// The result of an operation running on another thread.
+///
+/// Resolves to the value the operation returned, and resumes its panic if it
+/// panicked. Dropping it cancels the operation.
+pub struct Blocking<T> {
+    cancelled: Arc<AtomicBool>,
+    state: Arc<Mutex<State<T>>>,
+}
+
+/// Run `f` on another thread
+pub fn spawn_blocking<F, T>(f: F) -> Blocking<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    spawn_cancellable(|_| f())
+}
+
+/// Run `f` on another thread, passing it the token that reports when its
+/// result is no longer wanted
+pub fn spawn_cancellable<F, T>(f: F) -> Blocking<T>
+where
+    F: FnOnce(&CancelToken) -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (future, completer) = pending();
+    spawn(move || {
+        if completer.is_cancelled() {
+            return;
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(&completer.token)));
+        completer.finish(result);
+    });
+    future
+}
+
+/// A future that resolves to the value passed to its `Completer`, for
+/// operations that don't run as a single closure, like a batch shared
+/// between many callers
+pub fn pending<T>() -> (Blocking<T>, Completer<T>) {
+    let token = CancelToken(Arc::new(AtomicBool::new(false)));
+    let state = Arc::new(Mutex::new(State {
+        result: None,
+        waker: None,
+    }));
+    let future = Blocking {
+        cancelled: token.0.clone(),
+        state: state.clone(),
+    };
+    (
+        future,
+        Completer {
+            token,
+            state: Some(state),
+        },
+    )
+}
+
+/// Resolves the `Blocking` future created with it by `pending`.
+///
+/// Dropping it without calling `complete` makes the future panic
+/// instead of waiting forever.
+pub struct Completer<T> {
+    token: CancelToken,
+    state: Option<Arc<Mutex<State<T>>>>,
+}
+
+impl<T> Completer<T> {
+    /// True once the future has been dropped and the value isn't wanted
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Resolve the future with `value`
+    pub fn complete(self, value: T) {
+        self.finish(Ok(value))
+    }
+
+    fn finish(mut self, result: thread::Result<T>) {
+        if let Some(state) = self.state.take() {
+            wake(&state, result);
+        }
+    }
+}
+
+impl<T> Drop for Completer<T> {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            wake(&state, Err(Box::new("the operation was abandoned")));
+        }
+    }
+}
+
+fn wake<T>(state: &Mutex<State<T>>, result: thread::Result<T>) {
+    // The lock is never held while user code runs, so it can't be poisoned
+    let waker = {
+        let mut state = state.lock().unwrap();
+        state.result = Some(result);
+        state.waker.take()
+    };
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn spawn<F: FnOnce() + Send + 'static>(f: F) {
+    rayon::spawn(f)
+}
+
+#[cfg(not(feature = "parallel"))]
+fn spawn<F: FnOnce() + Send + 'static>(f: F) {
+    thread::spawn(f);
+}
+
+impl<T> Blocking<T> {
+    /// Block the current thread until the operation finishes, for callers
+    /// outside of an async context
+    pub fn wait(mut self) -> T {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(value) = Pin::new(&mut self).poll(&mut cx) {
+                return value;
+            }
+            thread::park();
+        }
+    }
+}
+
+struct ThreadWaker(thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+impl<T> Future for Blocking<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(Ok(value)) => Poll::Ready(value),
+            Some(Err(payload)) => {
+                drop(state);
+                panic::resume_unwind(payload)
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for Blocking<T> {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn results() {
+        assert_eq!(42, spawn_blocking(|| 6 * 7).wait());
+        assert!(!spawn_cancellable(|token| token.is_cancelled()).wait());
+
+        let panicked = panic::catch_unwind(|| spawn_blocking(|| panic!("boom")).wait());
+        assert!(panicked.is_err());
+    }
+
+    #[test]
+    fn cancellation() {
+        let (started, wait_started) = mpsc::channel();
+        let (stopped, wait_stopped) = mpsc::channel();
+        let future = spawn_cancellable(move |token| {
+            started.send(()).unwrap();
+            while !token.is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            stopped.send(()).unwrap();
+        });
+        wait_started.recv().unwrap();
+        drop(future);
+        wait_stopped
+            .recv_timeout(Duration::from_secs(10))
+            .expect("operation wasn't cancelled");
+    }
+
+    #[test]
+    fn completers() {
+        let (future, completer) = pending();
+        thread::spawn(move || completer.complete("done"));
+        assert_eq!("done", future.wait());
+
+        let (future, completer) = pending::<()>();
+        drop(completer);
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| future.wait())).is_err());
+
+        let (future, completer) = pending::<()>();
+        assert!(!completer.is_cancelled());
+        drop(future);
+        assert!(completer.is_cancelled());
+    }
+}
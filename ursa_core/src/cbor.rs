@@ -0,0 +1,454 @@
+//! A versioned CBOR envelope for serialized crypto objects.
+//!
+//! Every object is encoded as the CBOR array `[id, version, payload]`:
+//!
+//! * `id` is a text string naming the scheme and the kind of object, e.g. `ed25519/public-key`
+//! * `version` is the unsigned version of the payload format
+//! * `payload` is a byte string in the format given by `id` and `version`
+//!
+//! Payload formats are fixed by the scheme, not by the backend that produced
+//! them, so stored objects can still be read after switching backends or
+//! upgrading the crates. A new format gets a new version, and readers keep
+//! accepting the old ones.
+//!
+//! Only the canonical encoding is produced or accepted: definite lengths,
+//! the shortest form of each integer and length, and no trailing bytes.
+//! Payloads that are structured themselves can use [`Writer`] and [`Reader`]
+//! to follow the same rules.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use zeroize::Zeroize;
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+
+/// Errors from reading an envelope
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CborError {
+    /// The input isn't a canonically encoded envelope
+    Malformed(String),
+    /// The envelope holds a different kind of object
+    UnexpectedId {
+        /// The id of the type being decoded
+        expected: String,
+        /// The id found in the envelope
+        found: String,
+    },
+    /// The payload version is unknown to this release
+    UnsupportedVersion {
+        /// The id of the object
+        id: String,
+        /// The version found in the envelope
+        version: u32,
+    },
+    /// The payload couldn't be decoded
+    InvalidPayload(String),
+}
+
+impl Display for CborError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            CborError::Malformed(s) => write!(f, "Malformed CBOR envelope: {}", s),
+            CborError::UnexpectedId { expected, found } => {
+                write!(f, "Expected a {} envelope, found {}", expected, found)
+            }
+            CborError::UnsupportedVersion { id, version } => {
+                write!(f, "Unsupported {} version {}", id, version)
+            }
+            CborError::InvalidPayload(s) => write!(f, "Invalid envelope payload: {}", s),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CborError {}
+
+/// An object id, payload version and payload
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Envelope {
+    /// The scheme and kind of object, e.g. `ed25519/public-key`
+    pub id: String,
+    /// The version of the payload format
+    pub version: u32,
+    /// The serialized object
+    pub payload: Vec<u8>,
+}
+
+impl Envelope {
+    /// Wrap `payload`
+    pub fn new<S: Into<String>>(id: S, version: u32, payload: Vec<u8>) -> Self {
+        Self {
+            id: id.into(),
+            version,
+            payload,
+        }
+    }
+
+    /// Encode as canonical CBOR
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut writer = Writer::with_capacity(self.id.len() + self.payload.len() + 16);
+        writer.array(3);
+        writer.text(&self.id);
+        writer.unsigned(u64::from(self.version));
+        writer.bytes(&self.payload);
+        writer.into_bytes()
+    }
+
+    /// Decode canonical CBOR
+    pub fn from_cbor(data: &[u8]) -> Result<Self, CborError> {
+        let mut reader = Reader::new(data);
+        if reader.array()? != 3 {
+            return Err(malformed("expected an array of 3 items"));
+        }
+        let id = reader.text()?.to_string();
+        let version = reader.unsigned()?;
+        if version > u64::from(u32::MAX) {
+            return Err(malformed("version is out of range"));
+        }
+        let payload = reader.bytes()?.to_vec();
+        reader.finish()?;
+        Ok(Self {
+            id,
+            version: version as u32,
+            payload,
+        })
+    }
+
+    /// Fail unless this envelope holds an object with `id`
+    pub fn expect_id(&self, id: &str) -> Result<(), CborError> {
+        if self.id == id {
+            Ok(())
+        } else {
+            Err(CborError::UnexpectedId {
+                expected: id.to_string(),
+                found: self.id.clone(),
+            })
+        }
+    }
+}
+
+impl Zeroize for Envelope {
+    fn zeroize(&mut self) {
+        self.payload.zeroize();
+    }
+}
+
+/// Objects with a versioned CBOR encoding
+pub trait CborSerializable: Sized {
+    /// The scheme and kind of object
+    const CBOR_ID: &'static str;
+    /// The payload version written by `to_cbor`
+    const CBOR_VERSION: u32 = 1;
+
+    /// Serialize in the format of `CBOR_VERSION`
+    fn to_cbor_payload(&self) -> Vec<u8>;
+
+    /// Deserialize a payload in the format of `version`
+    fn from_cbor_payload(version: u32, payload: &[u8]) -> Result<Self, CborError>;
+
+    /// Encode in a versioned envelope
+    fn to_cbor(&self) -> Vec<u8> {
+        Envelope::new(Self::CBOR_ID, Self::CBOR_VERSION, self.to_cbor_payload()).to_cbor()
+    }
+
+    /// Decode from a versioned envelope
+    fn from_cbor(data: &[u8]) -> Result<Self, CborError> {
+        let envelope = Envelope::from_cbor(data)?;
+        envelope.expect_id(Self::CBOR_ID)?;
+        if envelope.version == 0 || envelope.version > Self::CBOR_VERSION {
+            return Err(CborError::UnsupportedVersion {
+                id: envelope.id,
+                version: envelope.version,
+            });
+        }
+        Self::from_cbor_payload(envelope.version, &envelope.payload)
+    }
+}
+
+/// Writes canonical CBOR items, for payloads that are structured themselves
+#[derive(Debug, Default)]
+pub struct Writer {
+    out: Vec<u8>,
+}
+
+impl Writer {
+    /// An empty writer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An empty writer with room for `capacity` bytes
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            out: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Start an array of `len` items, which are written next
+    pub fn array(&mut self, len: usize) {
+        self.head(MAJOR_ARRAY, len as u64);
+    }
+
+    /// Write a byte string
+    pub fn bytes(&mut self, value: &[u8]) {
+        self.head(MAJOR_BYTES, value.len() as u64);
+        self.out.extend_from_slice(value);
+    }
+
+    /// Write a text string
+    pub fn text(&mut self, value: &str) {
+        self.head(MAJOR_TEXT, value.len() as u64);
+        self.out.extend_from_slice(value.as_bytes());
+    }
+
+    /// Write an unsigned integer
+    pub fn unsigned(&mut self, value: u64) {
+        self.head(MAJOR_UNSIGNED, value);
+    }
+
+    /// The items written
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.out
+    }
+
+    fn head(&mut self, major: u8, value: u64) {
+        let major = major << 5;
+        if value < 24 {
+            self.out.push(major | value as u8);
+        } else if value <= 0xff {
+            self.out.push(major | 24);
+            self.out.push(value as u8);
+        } else if value <= 0xffff {
+            self.out.push(major | 25);
+            self.out.extend_from_slice(&(value as u16).to_be_bytes());
+        } else if value <= 0xffff_ffff {
+            self.out.push(major | 26);
+            self.out.extend_from_slice(&(value as u32).to_be_bytes());
+        } else {
+            self.out.push(major | 27);
+            self.out.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+/// Reads the canonical CBOR items written by [`Writer`]
+#[derive(Debug)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Read items from `data`
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Read the start of an array and return its number of items
+    pub fn array(&mut self) -> Result<usize, CborError> {
+        let len = self.head(MAJOR_ARRAY)?;
+        // Every item takes at least one byte
+        if len > self.data.len() as u64 {
+            return Err(malformed("truncated input"));
+        }
+        Ok(len as usize)
+    }
+
+    /// Read a byte string
+    pub fn bytes(&mut self) -> Result<&'a [u8], CborError> {
+        self.string(MAJOR_BYTES)
+    }
+
+    /// Read a text string
+    pub fn text(&mut self) -> Result<&'a str, CborError> {
+        core::str::from_utf8(self.string(MAJOR_TEXT)?).map_err(|_| malformed("invalid UTF-8"))
+    }
+
+    /// Read an unsigned integer
+    pub fn unsigned(&mut self) -> Result<u64, CborError> {
+        self.head(MAJOR_UNSIGNED)
+    }
+
+    /// True if the next item is an array
+    pub fn is_array(&self) -> bool {
+        matches!(self.data.first(), Some(b) if b >> 5 == MAJOR_ARRAY)
+    }
+
+    /// Fail unless every item has been read
+    pub fn finish(self) -> Result<(), CborError> {
+        if self.data.is_empty() {
+            Ok(())
+        } else {
+            Err(malformed("unexpected trailing data"))
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CborError> {
+        if self.data.len() < n {
+            return Err(malformed("truncated input"));
+        }
+        let (value, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(value)
+    }
+
+    /// Read an item head of type `major` and return its argument
+    fn head(&mut self, major: u8) -> Result<u64, CborError> {
+        let initial = self.take(1)?[0];
+        if initial >> 5 != major {
+            return Err(malformed(&format!(
+                "expected major type {}, found {}",
+                major,
+                initial >> 5
+            )));
+        }
+        let (value, min) = match initial & 0x1f {
+            v if v < 24 => return Ok(u64::from(v)),
+            24 => (u64::from(self.take(1)?[0]), 24),
+            25 => (self.be_bytes(2)?, 0x100),
+            26 => (self.be_bytes(4)?, 0x1_0000),
+            27 => (self.be_bytes(8)?, 0x1_0000_0000),
+            _ => return Err(malformed("indefinite lengths are not allowed")),
+        };
+        if value < min {
+            return Err(malformed("integer is not minimally encoded"));
+        }
+        Ok(value)
+    }
+
+    fn be_bytes(&mut self, n: usize) -> Result<u64, CborError> {
+        Ok(self
+            .take(n)?
+            .iter()
+            .fold(0u64, |acc, b| (acc << 8) | u64::from(*b)))
+    }
+
+    fn string(&mut self, major: u8) -> Result<&'a [u8], CborError> {
+        let len = self.head(major)?;
+        if len > self.data.len() as u64 {
+            return Err(malformed("truncated input"));
+        }
+        self.take(len as usize)
+    }
+}
+
+fn malformed(msg: &str) -> CborError {
+    CborError::Malformed(msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[derive(Debug, PartialEq)]
+    struct Point(u16, u16);
+
+    impl CborSerializable for Point {
+        const CBOR_ID: &'static str = "test/point";
+        const CBOR_VERSION: u32 = 2;
+
+        fn to_cbor_payload(&self) -> Vec<u8> {
+            [self.0.to_be_bytes(), self.1.to_be_bytes()].concat()
+        }
+
+        fn from_cbor_payload(version: u32, payload: &[u8]) -> Result<Self, CborError> {
+            match (version, payload) {
+                // Version 1 only stored one byte for each coordinate
+                (1, [x, y]) => Ok(Point(u16::from(*x), u16::from(*y))),
+                (2, [x0, x1, y0, y1]) => Ok(Point(
+                    u16::from_be_bytes([*x0, *x1]),
+                    u16::from_be_bytes([*y0, *y1]),
+                )),
+                _ => Err(CborError::InvalidPayload("bad point".to_string())),
+            }
+        }
+    }
+
+    #[test]
+    fn envelope_encoding() {
+        let envelope = Envelope::new("test/point", 1, vec![1, 2]);
+        let cbor = envelope.to_cbor();
+        // [ "test/point", 1, h'0102' ]
+        assert_eq!(
+            &[
+                0x83, 0x6a, b't', b'e', b's', b't', b'/', b'p', b'o', b'i', b'n', b't', 0x01, 0x42,
+                0x01, 0x02
+            ][..],
+            &cbor[..]
+        );
+        assert_eq!(envelope, Envelope::from_cbor(&cbor).unwrap());
+
+        let long = Envelope::new("test/long", 70_000, vec![7u8; 300]);
+        assert_eq!(long, Envelope::from_cbor(&long.to_cbor()).unwrap());
+    }
+
+    #[test]
+    fn versions() {
+        let point = Point(300, 4);
+        assert_eq!(point, Point::from_cbor(&point.to_cbor()).unwrap());
+        let v1 = Envelope::new("test/point", 1, vec![3, 4]).to_cbor();
+        assert_eq!(Point(3, 4), Point::from_cbor(&v1).unwrap());
+        let v3 = Envelope::new("test/point", 3, vec![]).to_cbor();
+        assert_eq!(
+            Err(CborError::UnsupportedVersion {
+                id: "test/point".to_string(),
+                version: 3
+            }),
+            Point::from_cbor(&v3)
+        );
+        let other = Envelope::new("test/line", 1, vec![3, 4]).to_cbor();
+        assert!(Point::from_cbor(&other).is_err());
+    }
+
+    #[test]
+    fn structured_payloads() {
+        let mut writer = Writer::new();
+        writer.array(2);
+        writer.bytes(&[1, 2]);
+        writer.array(1);
+        writer.unsigned(500);
+        let cbor = writer.into_bytes();
+        // [ h'0102', [ 500 ] ]
+        assert_eq!(
+            &[0x82, 0x42, 0x01, 0x02, 0x81, 0x19, 0x01, 0xf4][..],
+            &cbor[..]
+        );
+
+        let mut reader = Reader::new(&cbor);
+        assert_eq!(2, reader.array().unwrap());
+        assert!(!reader.is_array());
+        assert_eq!(&[1, 2][..], reader.bytes().unwrap());
+        assert!(reader.is_array());
+        assert_eq!(1, reader.array().unwrap());
+        assert_eq!(500, reader.unsigned().unwrap());
+        assert!(reader.finish().is_ok());
+
+        let mut reader = Reader::new(&cbor[..5]);
+        reader.array().unwrap();
+        reader.bytes().unwrap();
+        assert!(reader.finish().is_err());
+        // An array claiming more items than there are bytes left
+        assert!(Reader::new(&[0x9a, 0xff, 0xff, 0xff, 0xff])
+            .array()
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_non_canonical() {
+        let cbor = Envelope::new("a", 1, vec![1]).to_cbor();
+        let mut trailing = cbor.clone();
+        trailing.push(0);
+        assert!(Envelope::from_cbor(&trailing).is_err());
+        assert!(Envelope::from_cbor(&cbor[..cbor.len() - 1]).is_err());
+        // Version 1 written with a one byte argument
+        assert!(Envelope::from_cbor(&[0x83, 0x61, b'a', 0x18, 0x01, 0x41, 0x01]).is_err());
+        // Indefinite length byte string
+        assert!(Envelope::from_cbor(&[0x83, 0x61, b'a', 0x01, 0x5f, 0x41, 0x01, 0xff]).is_err());
+        assert!(Envelope::from_cbor(&[0x82, 0x61, b'a', 0x01]).is_err());
+    }
+}
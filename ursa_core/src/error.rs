@@ -0,0 +1,303 @@
+//! An error type shared by the ursa crates.
+//!
+//! Each crate keeps its own detailed error and converts it into
+//! [`UrsaError`], so code that signs, encrypts and shares secrets can use
+//! `?` on all of them and still tell failures apart by [`ErrorKind`]. With
+//! the `std` feature the original error stays reachable through
+//! [`source`](std::error::Error::source).
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use core::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// A specialized [`Result`] type for ursa operations.
+pub type UrsaResult<T> = Result<T, UrsaError>;
+
+/// The category of an [`UrsaError`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The requested algorithm doesn't exist or isn't compiled in
+    NoSuchAlgorithm,
+    /// Bytes couldn't be decoded into a key, signature or other object
+    Parse,
+    /// A parameter is out of range or inconsistent with the others
+    InvalidInput,
+    /// A signature, proof or share didn't verify
+    Verification,
+    /// Signing failed
+    Signing,
+    /// Key generation or derivation failed
+    KeyGen,
+    /// Hashing failed
+    Digest,
+    /// Anything else
+    General,
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let name = match self {
+            ErrorKind::NoSuchAlgorithm => "no such algorithm",
+            ErrorKind::Parse => "parse error",
+            ErrorKind::InvalidInput => "invalid input",
+            ErrorKind::Verification => "verification failed",
+            ErrorKind::Signing => "signing error",
+            ErrorKind::KeyGen => "key generation error",
+            ErrorKind::Digest => "digest error",
+            ErrorKind::General => "error",
+        };
+        f.write_str(name)
+    }
+}
+
+/// An error from any of the ursa crates
+pub struct UrsaError {
+    kind: ErrorKind,
+    message: String,
+    #[cfg(feature = "std")]
+    source: Option<Box<dyn std::error::Error + Send + Sync + 'static>>,
+    #[cfg(not(feature = "std"))]
+    source: Option<Box<dyn Display + Send + Sync + 'static>>,
+}
+
+impl UrsaError {
+    /// An error without an underlying cause
+    pub fn new<M: Into<String>>(kind: ErrorKind, message: M) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// An error caused by `source`
+    #[cfg(feature = "std")]
+    pub fn with_source<M, E>(kind: ErrorKind, message: M, source: E) -> Self
+    where
+        M: Into<String>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        Self {
+            kind,
+            message: message.into(),
+            source: Some(Box::new(source)),
+        }
+    }
+
+    /// An error caused by `source`
+    #[cfg(not(feature = "std"))]
+    pub fn with_source<M, E>(kind: ErrorKind, message: M, source: E) -> Self
+    where
+        M: Into<String>,
+        E: Display + Send + Sync + 'static,
+    {
+        Self {
+            kind,
+            message: message.into(),
+            source: Some(Box::new(source)),
+        }
+    }
+
+    /// The category of the error
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The description of what failed
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Debug for UrsaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let mut s = f.debug_struct("UrsaError");
+        s.field("kind", &self.kind).field("message", &self.message);
+        if let Some(source) = &self.source {
+            s.field("source", &source.to_string());
+        }
+        s.finish()
+    }
+}
+
+impl Display for UrsaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UrsaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.source {
+            Some(source) => Some(source.as_ref()),
+            None => None,
+        }
+    }
+}
+
+impl From<crate::CborError> for UrsaError {
+    fn from(error: crate::CborError) -> Self {
+        Self::with_source(ErrorKind::Parse, error.to_string(), error)
+    }
+}
+
+/// Stable numeric codes for the errors of the ursa crates, for callers that
+/// can't match on Rust enums, such as users of the C API.
+///
+/// A code is never renumbered or reused once released. Each crate has its
+/// own range: 1-99 for `ursa`, 100-199 for the legacy `ursa::ffi::ErrorCode`
+/// of the CL API and 200-299 for `ursa_sharing`.
+pub mod codes {
+    // Errors of `ursa::CryptoError`
+    /// The requested algorithm doesn't exist or isn't compiled in
+    pub const NO_SUCH_ALGORITHM: i32 = 1;
+    /// A key, signature or other input couldn't be decoded
+    pub const PARSE_ERROR: i32 = 2;
+    /// Signing failed
+    pub const SIGNING_ERROR: i32 = 3;
+    /// Key generation failed
+    pub const KEY_GEN_ERROR: i32 = 4;
+    /// Hashing failed
+    pub const DIGEST_GEN_ERROR: i32 = 5;
+    /// Any other `CryptoError`
+    pub const GENERAL_ERROR: i32 = 6;
+    /// A null or non UTF-8 string was passed through the C API
+    pub const INVALID_PARAM: i32 = 7;
+    /// A curve point is the identity or outside the prime order subgroup
+    pub const INVALID_GROUP_ELEMENT: i32 = 8;
+    // Errors of `ursa::signatures::VerificationError`
+    /// The signature couldn't be decoded
+    pub const MALFORMED_SIGNATURE: i32 = 10;
+    /// The public key couldn't be decoded or is unusable
+    pub const INVALID_PUBLIC_KEY: i32 = 11;
+    /// The signature doesn't match the message and key
+    pub const SIGNATURE_MISMATCH: i32 = 12;
+    /// A signature over distinct messages was given a message twice
+    pub const DUPLICATE_MESSAGE: i32 = 13;
+    /// There was nothing to verify the signature against
+    pub const EMPTY_INPUT: i32 = 14;
+    // Errors of `ursa_sharing::SharingError`
+    /// A share is shorter than 4 bytes
+    pub const SHARE_SECRET_MIN_SIZE: i32 = 200;
+    /// The share limit is less than the threshold
+    pub const SHARE_LIMIT_LESS_THAN_THRESHOLD: i32 = 201;
+    /// The threshold is less than 2
+    pub const SHARE_MIN_THRESHOLD: i32 = 202;
+    /// The secret isn't in the field
+    pub const SHARE_INVALID_SECRET: i32 = 203;
+    /// A share identifier is 0
+    pub const SHARE_INVALID_IDENTIFIER: i32 = 204;
+    /// Two shares have the same identifier
+    pub const SHARE_DUPLICATE_IDENTIFIER: i32 = 205;
+    /// A share value is corrupted or invalid
+    pub const SHARE_INVALID_VALUE: i32 = 206;
+    /// A Pedersen verifier is too short
+    pub const PEDERSEN_VERIFIER_MIN_SIZE: i32 = 207;
+    /// A blinding factor share is corrupted or invalid
+    pub const PEDERSEN_BLIND_SHARE_INVALID: i32 = 208;
+    /// The numbers of shares and blinding factor shares differ
+    pub const PEDERSEN_BLIND_SHARE_COUNT: i32 = 209;
+    /// A curve point couldn't be decoded
+    pub const INVALID_POINT: i32 = 210;
+    /// A DLEQ proof has the wrong length
+    pub const DLEQ_PROOF_INVALID_SIZE: i32 = 211;
+    /// A DLEQ batch is empty or its bases and values don't pair up
+    pub const DLEQ_BATCH_MISMATCH: i32 = 212;
+    /// A protobuf message is missing a required field
+    pub const MISSING_FIELD: i32 = 213;
+    /// A DKG message arrived in a round that doesn't accept it
+    pub const DKG_UNEXPECTED_ROUND: i32 = 214;
+    /// A DKG message names a dealer that isn't another participant
+    pub const DKG_UNKNOWN_DEALER: i32 = 215;
+    /// A DKG dealer sent two different messages of the same kind
+    pub const DKG_CONFLICTING_MESSAGE: i32 = 216;
+    /// Too few DKG dealers are qualified to finish
+    pub const DKG_NOT_ENOUGH_DEALERS: i32 = 217;
+    /// A backup bundle doesn't match its manifest hash
+    pub const BACKUP_MANIFEST_MISMATCH: i32 = 218;
+    /// A backup share couldn't be decrypted with the custodian's key
+    pub const BACKUP_SHARE_DECRYPTION: i32 = 219;
+
+    /// Every code with its name
+    pub const ALL: &[(i32, &str)] = &[
+        (NO_SUCH_ALGORITHM, "NO_SUCH_ALGORITHM"),
+        (PARSE_ERROR, "PARSE_ERROR"),
+        (SIGNING_ERROR, "SIGNING_ERROR"),
+        (KEY_GEN_ERROR, "KEY_GEN_ERROR"),
+        (DIGEST_GEN_ERROR, "DIGEST_GEN_ERROR"),
+        (GENERAL_ERROR, "GENERAL_ERROR"),
+        (INVALID_PARAM, "INVALID_PARAM"),
+        (INVALID_GROUP_ELEMENT, "INVALID_GROUP_ELEMENT"),
+        (MALFORMED_SIGNATURE, "MALFORMED_SIGNATURE"),
+        (INVALID_PUBLIC_KEY, "INVALID_PUBLIC_KEY"),
+        (SIGNATURE_MISMATCH, "SIGNATURE_MISMATCH"),
+        (DUPLICATE_MESSAGE, "DUPLICATE_MESSAGE"),
+        (EMPTY_INPUT, "EMPTY_INPUT"),
+        (SHARE_SECRET_MIN_SIZE, "SHARE_SECRET_MIN_SIZE"),
+        (
+            SHARE_LIMIT_LESS_THAN_THRESHOLD,
+            "SHARE_LIMIT_LESS_THAN_THRESHOLD",
+        ),
+        (SHARE_MIN_THRESHOLD, "SHARE_MIN_THRESHOLD"),
+        (SHARE_INVALID_SECRET, "SHARE_INVALID_SECRET"),
+        (SHARE_INVALID_IDENTIFIER, "SHARE_INVALID_IDENTIFIER"),
+        (SHARE_DUPLICATE_IDENTIFIER, "SHARE_DUPLICATE_IDENTIFIER"),
+        (SHARE_INVALID_VALUE, "SHARE_INVALID_VALUE"),
+        (PEDERSEN_VERIFIER_MIN_SIZE, "PEDERSEN_VERIFIER_MIN_SIZE"),
+        (PEDERSEN_BLIND_SHARE_INVALID, "PEDERSEN_BLIND_SHARE_INVALID"),
+        (PEDERSEN_BLIND_SHARE_COUNT, "PEDERSEN_BLIND_SHARE_COUNT"),
+        (INVALID_POINT, "INVALID_POINT"),
+        (DLEQ_PROOF_INVALID_SIZE, "DLEQ_PROOF_INVALID_SIZE"),
+        (DLEQ_BATCH_MISMATCH, "DLEQ_BATCH_MISMATCH"),
+        (MISSING_FIELD, "MISSING_FIELD"),
+        (DKG_UNEXPECTED_ROUND, "DKG_UNEXPECTED_ROUND"),
+        (DKG_UNKNOWN_DEALER, "DKG_UNKNOWN_DEALER"),
+        (DKG_CONFLICTING_MESSAGE, "DKG_CONFLICTING_MESSAGE"),
+        (DKG_NOT_ENOUGH_DEALERS, "DKG_NOT_ENOUGH_DEALERS"),
+        (BACKUP_MANIFEST_MISMATCH, "BACKUP_MANIFEST_MISMATCH"),
+        (BACKUP_SHARE_DECRYPTION, "BACKUP_SHARE_DECRYPTION"),
+    ];
+
+    /// The name of `code`, `None` if the code is unknown
+    pub fn name(code: i32) -> Option<&'static str> {
+        ALL.iter().find(|(c, _)| *c == code).map(|(_, name)| *name)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::CborError;
+    use std::error::Error;
+
+    #[test]
+    fn source_chain() {
+        let error = UrsaError::from(CborError::Malformed("truncated".to_string()));
+        assert_eq!(error.kind(), ErrorKind::Parse);
+        assert_eq!(
+            error.to_string(),
+            "parse error: Malformed CBOR envelope: truncated"
+        );
+        let source = error.source().unwrap();
+        assert_eq!(
+            source.downcast_ref::<CborError>(),
+            Some(&CborError::Malformed("truncated".to_string()))
+        );
+
+        let error = UrsaError::new(ErrorKind::InvalidInput, "threshold is 0");
+        assert_eq!(error.message(), "threshold is 0");
+        assert!(error.source().is_none());
+    }
+
+    #[test]
+    fn unique_codes() {
+        for (i, (code, name)) in codes::ALL.iter().enumerate() {
+            assert!(*code > 0);
+            assert_eq!(codes::name(*code), Some(*name));
+            assert!(codes::ALL[i + 1..].iter().all(|(c, _)| c != code));
+        }
+        assert_eq!(codes::name(codes::PARSE_ERROR), Some("PARSE_ERROR"));
+        assert_eq!(codes::name(0), None);
+    }
+}
@@ -1 +1,56 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+#![deny(
+    missing_docs,
+    trivial_casts,
+    trivial_numeric_casts,
+    unconditional_recursion,
+    unused_import_braces,
+    unused_lifetimes,
+    unused_qualifications,
+    unused_extern_crates,
+    unused_parens,
+    while_true
+)]
+//! Types shared by the ursa crates.
+//!
+//! Without the default `std` feature the crate is `no_std` and needs `alloc`.
+#![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std as alloc;
+
+/// Futures for running expensive operations off the async executor
+#[cfg(feature = "async")]
+pub mod blocking;
+/// Versioned CBOR serialization
+pub mod cbor;
+/// The error type shared by the ursa crates
+pub mod error;
+/// Batch operations on the rayon thread pool
+pub mod parallel;
+/// Protobuf messages for the crypto objects
+#[cfg(feature = "protobuf")]
+pub mod proto;
+/// Wrappers for secret values
+pub mod secret;
+/// Transcripts for deriving challenges in public coin protocols
+pub mod transcript;
+
+pub use cbor::{CborError, CborSerializable, Envelope};
+pub use error::{ErrorKind, UrsaError, UrsaResult};
+pub use secret::Secret;
+pub use transcript::Transcript;
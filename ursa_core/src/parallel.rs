@@ -0,0 +1,192 @@
+//! Batch helpers that run on the rayon thread pool when the `parallel`
+//! feature is enabled and sequentially otherwise.
+//!
+//! The ursa crates call these for their batch operations so that one
+//! feature switches all of them. The `MaybeSend` and `MaybeSync` bounds are
+//! `Send` and `Sync` with the feature and no bound without it, so code
+//! built without `parallel` doesn't need thread safe types.
+//!
+//! # Thread pool
+//!
+//! The work runs on the global rayon pool, which by default has one thread
+//! per CPU. Call [`init_thread_pool`] once at startup, before any batch
+//! operation, to choose the number of threads. To keep ursa off the
+//! threads of the rest of an application, build a dedicated pool and run
+//! the batch operations inside [`rayon::ThreadPool::install`] instead:
+//!
+//! ```ignore
+//! let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build()?;
+//! let valid = pool.install(|| scheme.verify_shares(&shares, &verifier));
+//! ```
+
+use alloc::vec::Vec;
+#[cfg(feature = "parallel")]
+pub use rayon;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// `Send` when the `parallel` feature is enabled
+#[cfg(feature = "parallel")]
+pub trait MaybeSend: Send {}
+#[cfg(feature = "parallel")]
+impl<T: Send + ?Sized> MaybeSend for T {}
+
+/// `Send` when the `parallel` feature is enabled
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSend {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> MaybeSend for T {}
+
+/// `Sync` when the `parallel` feature is enabled
+#[cfg(feature = "parallel")]
+pub trait MaybeSync: Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Sync + ?Sized> MaybeSync for T {}
+
+/// `Sync` when the `parallel` feature is enabled
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSync {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> MaybeSync for T {}
+
+/// Configure the global thread pool to use `num_threads` threads,
+/// 0 picks one per CPU. Fails if the pool has already been initialized,
+/// which happens on the first batch operation.
+#[cfg(feature = "parallel")]
+pub fn init_thread_pool(num_threads: usize) -> Result<(), rayon::ThreadPoolBuildError> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build_global()
+}
+
+/// Apply `f` to every item, keeping the order
+pub fn map<T, U, F>(items: &[T], f: F) -> Vec<U>
+where
+    T: MaybeSync,
+    U: MaybeSend,
+    F: Fn(&T) -> U + MaybeSend + MaybeSync,
+{
+    #[cfg(feature = "parallel")]
+    let iter = items.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let iter = items.iter();
+
+    iter.map(f).collect()
+}
+
+/// `map` into `out`, replacing its contents but reusing its allocation
+pub fn map_into<T, U, F>(items: &[T], out: &mut Vec<U>, f: F)
+where
+    T: MaybeSync,
+    U: MaybeSend,
+    F: Fn(&T) -> U + MaybeSend + MaybeSync,
+{
+    out.clear();
+    #[cfg(feature = "parallel")]
+    out.par_extend(items.par_iter().map(f));
+    #[cfg(not(feature = "parallel"))]
+    out.extend(items.iter().map(f));
+}
+
+/// `map_into` for the pairs of items at the same positions in `a` and `b`,
+/// which must have the same length
+pub fn zip_map_into<A, B, U, F>(a: &[A], b: &[B], out: &mut Vec<U>, f: F)
+where
+    A: MaybeSync,
+    B: MaybeSync,
+    U: MaybeSend,
+    F: Fn(&A, &B) -> U + MaybeSend + MaybeSync,
+{
+    debug_assert_eq!(a.len(), b.len());
+    out.clear();
+    #[cfg(feature = "parallel")]
+    out.par_extend(a.par_iter().zip(b).map(|(x, y)| f(x, y)));
+    #[cfg(not(feature = "parallel"))]
+    out.extend(a.iter().zip(b).map(|(x, y)| f(x, y)));
+}
+
+/// Apply `f` to every item, keeping the order, and return an error if any
+/// of the calls failed
+pub fn try_map<T, U, E, F>(items: &[T], f: F) -> Result<Vec<U>, E>
+where
+    T: MaybeSync,
+    U: MaybeSend,
+    E: MaybeSend,
+    F: Fn(&T) -> Result<U, E> + MaybeSend + MaybeSync,
+{
+    #[cfg(feature = "parallel")]
+    let iter = items.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let iter = items.iter();
+
+    iter.map(f).collect()
+}
+
+/// True if `f` holds for every item. Stops early on the first failure.
+pub fn all<T, F>(items: &[T], f: F) -> bool
+where
+    T: MaybeSync,
+    F: Fn(&T) -> bool + MaybeSend + MaybeSync,
+{
+    #[cfg(feature = "parallel")]
+    {
+        items.par_iter().all(f)
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.iter().all(f)
+    }
+}
+
+/// Run `a` and `b`, potentially in parallel
+pub fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + MaybeSend,
+    B: FnOnce() -> RB + MaybeSend,
+    RA: MaybeSend,
+    RB: MaybeSend,
+{
+    #[cfg(feature = "parallel")]
+    {
+        rayon::join(a, b)
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        (a(), b())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches() {
+        let items = (0u64..1000).collect::<Vec<_>>();
+        let squares = map(&items, |i| i * i);
+        assert_eq!(items.iter().map(|i| i * i).collect::<Vec<_>>(), squares);
+
+        assert_eq!(
+            Ok(squares.clone()),
+            try_map(&items, |i| Ok::<_, ()>(i * i))
+        );
+        assert_eq!(
+            Err(500),
+            try_map(&items, |i| if *i == 500 { Err(*i) } else { Ok(*i) })
+        );
+
+        let mut out = vec![7u64; 2000];
+        let capacity = out.capacity();
+        map_into(&items, &mut out, |i| i * i);
+        assert_eq!(squares, out);
+        zip_map_into(&items, &squares, &mut out, |i, s| s - i);
+        assert_eq!(items.iter().map(|i| i * i - i).collect::<Vec<_>>(), out);
+        assert_eq!(capacity, out.capacity());
+
+        assert!(all(&items, |i| *i < 1000));
+        assert!(!all(&items, |i| *i != 999));
+        assert!(all(&Vec::<u64>::new(), |_| false));
+
+        assert_eq!((1, "b"), join(|| 1, || "b"));
+    }
+}
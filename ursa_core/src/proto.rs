@@ -0,0 +1,154 @@
+//! Protobuf messages of the `ursa.v1` package.
+//!
+//! These are the messages of `proto/ursa.proto`, derived by hand with prost
+//! so that building doesn't need `protoc`. Keep both in sync: the field tags
+//! are the wire format. The conversions from and to the crypto types are
+//! implemented by the crates that own those types.
+
+use zeroize::Zeroize;
+
+/// A public key of any algorithm, e.g. `ed25519` or `bls12381-g1`
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PublicKey {
+    /// The algorithm name
+    #[prost(string, tag = "1")]
+    pub algorithm: String,
+    /// The key bytes
+    #[prost(bytes = "vec", tag = "2")]
+    pub key: Vec<u8>,
+}
+
+/// A signature of any algorithm
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Signature {
+    /// The algorithm name
+    #[prost(string, tag = "1")]
+    pub algorithm: String,
+    /// The signature bytes
+    #[prost(bytes = "vec", tag = "2")]
+    pub signature: Vec<u8>,
+}
+
+/// Several signatures combined into one
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AggregatedSignature {
+    /// The algorithm name
+    #[prost(string, tag = "1")]
+    pub algorithm: String,
+    /// The signature bytes
+    #[prost(bytes = "vec", tag = "2")]
+    pub signature: Vec<u8>,
+}
+
+/// A Shamir share. The value is zeroized on drop.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Share {
+    /// The x-coordinate
+    #[prost(uint32, tag = "1")]
+    pub identifier: u32,
+    /// The y-coordinate
+    #[prost(bytes = "vec", tag = "2")]
+    pub value: Vec<u8>,
+}
+
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+/// Commitments to the coefficients of a Feldman sharing polynomial
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FeldmanVerifier {
+    /// The generator of the commitments
+    #[prost(bytes = "vec", tag = "1")]
+    pub generator: Vec<u8>,
+    /// One commitment per coefficient
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub commitments: Vec<Vec<u8>>,
+}
+
+/// Commitments to the coefficients of a Pedersen sharing polynomial
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PedersenVerifier {
+    /// The generator for the share scalar
+    #[prost(bytes = "vec", tag = "1")]
+    pub generator: Vec<u8>,
+    /// The generator for the blinding factor
+    #[prost(bytes = "vec", tag = "2")]
+    pub blinding_generator: Vec<u8>,
+    /// One commitment per coefficient
+    #[prost(bytes = "vec", repeated, tag = "3")]
+    pub commitments: Vec<Vec<u8>>,
+}
+
+/// The commitments a DKG dealer broadcasts to every participant
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DkgCommitment {
+    /// The identifier of the dealer
+    #[prost(uint32, tag = "1")]
+    pub dealer: u32,
+    /// The commitments to the dealt polynomials
+    #[prost(message, optional, tag = "2")]
+    pub verifier: Option<PedersenVerifier>,
+}
+
+/// The shares a DKG dealer sends privately to one participant
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DkgShare {
+    /// The identifier of the dealer
+    #[prost(uint32, tag = "1")]
+    pub dealer: u32,
+    /// The identifier of the participant receiving the shares
+    #[prost(uint32, tag = "2")]
+    pub recipient: u32,
+    /// The share of the dealt secret
+    #[prost(message, optional, tag = "3")]
+    pub secret_share: Option<Share>,
+    /// The share of the blinding factor
+    #[prost(message, optional, tag = "4")]
+    pub blinding_share: Option<Share>,
+}
+
+/// Raised by a participant whose share from `dealer` doesn't verify
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DkgComplaint {
+    /// The identifier of the accused dealer
+    #[prost(uint32, tag = "1")]
+    pub dealer: u32,
+    /// The identifier of the participant complaining
+    #[prost(uint32, tag = "2")]
+    pub complainant: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn wire_format() {
+        let pk = PublicKey {
+            algorithm: "ed25519".to_string(),
+            key: vec![1, 2, 3],
+        };
+        let bytes = pk.encode_to_vec();
+        assert_eq!(b"\x0a\x07ed25519\x12\x03\x01\x02\x03".to_vec(), bytes);
+        assert_eq!(pk, PublicKey::decode(bytes.as_slice()).unwrap());
+
+        let share = DkgShare {
+            dealer: 1,
+            recipient: 2,
+            secret_share: Some(Share {
+                identifier: 2,
+                value: vec![7u8; 4],
+            }),
+            blinding_share: None,
+        };
+        assert_eq!(
+            share,
+            DkgShare::decode(share.encode_to_vec().as_slice()).unwrap()
+        );
+        assert!(PublicKey::decode(&b"\x0a\x07ed"[..]).is_err());
+    }
+}
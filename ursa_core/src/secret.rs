@@ -0,0 +1,124 @@
+use core::fmt::{Debug, Formatter, Result as FmtResult};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use zeroize::Zeroize;
+
+/// Holds a secret value such as a private key, share or seed.
+///
+/// The value is zeroized when dropped, is never printed by `Debug`, and can
+/// only be read through [`expose_secret`](Secret::expose_secret) so that
+/// every use of it is easy to find. Secrets that are bytes compare in
+/// constant time.
+#[derive(Clone, Default)]
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    /// Wrap `value`
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Borrow the secret value
+    pub fn expose_secret(&self) -> &T {
+        &self.0
+    }
+
+    /// Mutably borrow the secret value
+    pub fn expose_secret_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Zeroize + AsMut<[u8]>> Secret<T> {
+    /// Replace the value with `other` if `choice` is set, without branching
+    /// on `choice`.
+    ///
+    /// # Panics
+    ///
+    /// If the values have different lengths
+    pub fn conditional_assign(&mut self, other: &Self, choice: Choice)
+    where
+        T: AsRef<[u8]>,
+    {
+        let other = other.0.as_ref();
+        let value = self.0.as_mut();
+        assert_eq!(value.len(), other.len());
+        for (a, b) in value.iter_mut().zip(other.iter()) {
+            a.conditional_assign(b, choice);
+        }
+    }
+}
+
+impl<T: Zeroize + AsRef<[u8]>> ConstantTimeEq for Secret<T> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.as_ref().ct_eq(other.0.as_ref())
+    }
+}
+
+impl<T: Zeroize + AsRef<[u8]>> PartialEq for Secret<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<T: Zeroize + AsRef<[u8]>> Eq for Secret<T> {}
+
+impl<T: Zeroize> Debug for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "Secret([REDACTED])")
+    }
+}
+
+impl<T: Zeroize> Zeroize for Secret<T> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, vec};
+
+    #[test]
+    fn redacted_debug() {
+        let secret = Secret::new(vec![0xabu8; 4]);
+        assert_eq!("Secret([REDACTED])", format!("{:?}", secret));
+        assert_eq!(&[0xab; 4], secret.expose_secret().as_slice());
+        assert_eq!(secret, secret.clone());
+    }
+
+    #[test]
+    fn zeroize() {
+        let mut secret = Secret::from([7u8; 32]);
+        secret.expose_secret_mut()[0] = 1;
+        assert_eq!(1, secret.expose_secret()[0]);
+        secret.zeroize();
+        assert_eq!(&[0u8; 32], secret.expose_secret());
+    }
+
+    #[test]
+    fn constant_time() {
+        let mut a = Secret::new(vec![1u8, 2, 3]);
+        let b = Secret::new(vec![1u8, 2, 4]);
+        assert!(!bool::from(a.ct_eq(&b)));
+        assert!(bool::from(a.ct_eq(&a.clone())));
+        assert_ne!(a, Secret::new(vec![1u8, 2]));
+
+        a.conditional_assign(&b, Choice::from(0));
+        assert_eq!(&[1, 2, 3], a.expose_secret().as_slice());
+        a.conditional_assign(&b, Choice::from(1));
+        assert_eq!(a, b);
+    }
+}
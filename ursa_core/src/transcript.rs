@@ -0,0 +1,332 @@
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+const MERLIN_PROTOCOL_LABEL: &[u8] = b"Merlin v1.0";
+
+/// A transcript of a public coin protocol, compatible with Merlin
+/// (see <https://merlin.cool>).
+///
+/// Each party appends the same labeled messages in the same order and
+/// draws the challenges from the transcript instead of hashing the
+/// messages itself. Two protocols with different labels, or the same
+/// protocol run over different messages, get unrelated challenges, so
+/// callers can bind their own context to ursa's proofs by appending it
+/// before handing the transcript over.
+///
+/// Cloning a transcript forks it: the copies continue independently.
+#[derive(Clone)]
+pub struct Transcript {
+    strobe: Strobe128,
+}
+
+impl Transcript {
+    /// Start a transcript for the protocol named `label`
+    pub fn new(label: &'static [u8]) -> Self {
+        let mut transcript = Self {
+            strobe: Strobe128::new(MERLIN_PROTOCOL_LABEL),
+        };
+        transcript.append_message(b"dom-sep", label);
+        transcript
+    }
+
+    /// Append a message under `label`
+    pub fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.strobe.meta_ad(label, false);
+        self.strobe.meta_ad(&encode_len(message.len()), true);
+        self.strobe.ad(message, false);
+    }
+
+    /// Append a number as 8 little endian bytes
+    pub fn append_u64(&mut self, label: &'static [u8], x: u64) {
+        self.append_message(label, &x.to_le_bytes());
+    }
+
+    /// Fill `dest` with a challenge bound to everything appended so far
+    pub fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        self.strobe.meta_ad(label, false);
+        self.strobe.meta_ad(&encode_len(dest.len()), true);
+        self.strobe.prf(dest, false);
+    }
+
+    /// Fork the transcript into a random number generator for a prover's
+    /// nonces. The generator depends on the transcript, the witnesses
+    /// rekeyed into the builder and the final rng, so a weak rng alone
+    /// does not repeat nonces.
+    pub fn build_rng(&self) -> TranscriptRngBuilder {
+        TranscriptRngBuilder {
+            strobe: self.strobe.clone(),
+        }
+    }
+}
+
+impl core::fmt::Debug for Transcript {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("Transcript { .. }")
+    }
+}
+
+/// Builds a [`TranscriptRng`] from a forked transcript
+pub struct TranscriptRngBuilder {
+    strobe: Strobe128,
+}
+
+impl TranscriptRngBuilder {
+    /// Mix a prover's secret `witness` into the generator
+    pub fn rekey_with_witness_bytes(mut self, label: &'static [u8], witness: &[u8]) -> Self {
+        self.strobe.meta_ad(label, false);
+        self.strobe.meta_ad(&encode_len(witness.len()), true);
+        self.strobe.key(witness, false);
+        self
+    }
+
+    /// Mix 32 bytes from `rng` into the generator and return it
+    pub fn finalize(mut self, rng: &mut (impl RngCore + CryptoRng)) -> TranscriptRng {
+        let mut random_bytes = [0u8; 32];
+        rng.fill_bytes(&mut random_bytes);
+        self.strobe.meta_ad(b"rng", false);
+        self.strobe.key(&random_bytes, false);
+        random_bytes.zeroize();
+        TranscriptRng {
+            strobe: self.strobe,
+        }
+    }
+}
+
+/// A random number generator forked from a [`Transcript`]
+pub struct TranscriptRng {
+    strobe: Strobe128,
+}
+
+impl RngCore for TranscriptRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.strobe.meta_ad(&encode_len(dest.len()), false);
+        self.strobe.prf(dest, false);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for TranscriptRng {}
+
+fn encode_len(len: usize) -> [u8; 4] {
+    assert!(len as u64 <= u32::MAX as u64, "transcript message too long");
+    (len as u32).to_le_bytes()
+}
+
+const STROBE_R: u8 = 166;
+
+const FLAG_I: u8 = 1;
+const FLAG_A: u8 = 1 << 1;
+const FLAG_C: u8 = 1 << 2;
+const FLAG_T: u8 = 1 << 3;
+const FLAG_M: u8 = 1 << 4;
+const FLAG_K: u8 = 1 << 5;
+
+/// The subset of STROBE-128 used by Merlin
+#[derive(Clone)]
+struct Strobe128 {
+    state: [u8; 200],
+    pos: u8,
+    pos_begin: u8,
+    cur_flags: u8,
+}
+
+impl Strobe128 {
+    fn new(protocol_label: &[u8]) -> Self {
+        let mut state = [0u8; 200];
+        state[0..6].copy_from_slice(&[1, STROBE_R + 2, 1, 0, 1, 96]);
+        state[6..18].copy_from_slice(b"STROBEv1.0.2");
+        keccak_f1600(&mut state);
+        let mut strobe = Self {
+            state,
+            pos: 0,
+            pos_begin: 0,
+            cur_flags: 0,
+        };
+        strobe.meta_ad(protocol_label, false);
+        strobe
+    }
+
+    fn meta_ad(&mut self, data: &[u8], more: bool) {
+        self.begin_op(FLAG_M | FLAG_A, more);
+        self.absorb(data);
+    }
+
+    fn ad(&mut self, data: &[u8], more: bool) {
+        self.begin_op(FLAG_A, more);
+        self.absorb(data);
+    }
+
+    fn prf(&mut self, data: &mut [u8], more: bool) {
+        self.begin_op(FLAG_I | FLAG_A | FLAG_C, more);
+        self.squeeze(data);
+    }
+
+    fn key(&mut self, data: &[u8], more: bool) {
+        self.begin_op(FLAG_A | FLAG_C, more);
+        self.overwrite(data);
+    }
+
+    fn run_f(&mut self) {
+        self.state[self.pos as usize] ^= self.pos_begin;
+        self.state[(self.pos + 1) as usize] ^= 0x04;
+        self.state[(STROBE_R + 1) as usize] ^= 0x80;
+        keccak_f1600(&mut self.state);
+        self.pos = 0;
+        self.pos_begin = 0;
+    }
+
+    fn absorb(&mut self, data: &[u8]) {
+        for byte in data {
+            self.state[self.pos as usize] ^= byte;
+            self.advance();
+        }
+    }
+
+    fn overwrite(&mut self, data: &[u8]) {
+        for byte in data {
+            self.state[self.pos as usize] = *byte;
+            self.advance();
+        }
+    }
+
+    fn squeeze(&mut self, data: &mut [u8]) {
+        for byte in data {
+            *byte = self.state[self.pos as usize];
+            self.state[self.pos as usize] = 0;
+            self.advance();
+        }
+    }
+
+    fn advance(&mut self) {
+        self.pos += 1;
+        if self.pos == STROBE_R {
+            self.run_f();
+        }
+    }
+
+    fn begin_op(&mut self, flags: u8, more: bool) {
+        if more {
+            debug_assert_eq!(self.cur_flags, flags, "continued a different operation");
+            return;
+        }
+        debug_assert_eq!(flags & FLAG_T, 0, "transport operations are not supported");
+
+        let old_begin = self.pos_begin;
+        self.pos_begin = self.pos + 1;
+        self.cur_flags = flags;
+        self.absorb(&[old_begin, flags]);
+
+        // Cipher operations start on a fresh block
+        if flags & (FLAG_C | FLAG_K) != 0 && self.pos != 0 {
+            self.run_f();
+        }
+    }
+}
+
+impl Drop for Strobe128 {
+    fn drop(&mut self) {
+        self.state.zeroize();
+    }
+}
+
+fn keccak_f1600(state: &mut [u8; 200]) {
+    let mut lanes = [0u64; 25];
+    for (lane, bytes) in lanes.iter_mut().zip(state.chunks_exact(8)) {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(bytes);
+        *lane = u64::from_le_bytes(b);
+    }
+    keccak::f1600(&mut lanes);
+    for (bytes, lane) in state.chunks_exact_mut(8).zip(lanes.iter()) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    lanes.zeroize();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merlin_test_vector() {
+        let mut transcript = Transcript::new(b"test protocol");
+        transcript.append_message(b"some label", b"some data");
+        let mut challenge = [0u8; 32];
+        transcript.challenge_bytes(b"challenge", &mut challenge);
+        assert_eq!(
+            challenge,
+            [
+                0xd5, 0xa2, 0x19, 0x72, 0xd0, 0xd5, 0xfe, 0x32, 0x0c, 0x0d, 0x26, 0x3f, 0xac, 0x7f,
+                0xff, 0xb8, 0x14, 0x5a, 0xa6, 0x40, 0xaf, 0x6e, 0x9b, 0xca, 0x17, 0x7c, 0x03, 0xc7,
+                0xef, 0xcf, 0x06, 0x15
+            ]
+        );
+    }
+
+    #[test]
+    fn challenges_bind_messages() {
+        let mut a = Transcript::new(b"test protocol");
+        let mut b = a.clone();
+        a.append_u64(b"round", 1);
+        b.append_u64(b"round", 2);
+        let mut ca = [0u8; 16];
+        let mut cb = [0u8; 16];
+        a.challenge_bytes(b"c", &mut ca);
+        b.challenge_bytes(b"c", &mut cb);
+        assert_ne!(ca, cb);
+
+        // Long messages cross the permutation's rate
+        let mut a = Transcript::new(b"test protocol");
+        let mut b = a.clone();
+        a.append_message(b"data", &[7u8; 500]);
+        b.append_message(b"data", &[7u8; 500]);
+        a.challenge_bytes(b"c", &mut ca);
+        b.challenge_bytes(b"c", &mut cb);
+        assert_eq!(ca, cb);
+    }
+
+    #[test]
+    fn forked_rngs() {
+        struct Fixed;
+        impl RngCore for Fixed {
+            fn next_u32(&mut self) -> u32 {
+                0
+            }
+            fn next_u64(&mut self) -> u64 {
+                0
+            }
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                dest.iter_mut().for_each(|b| *b = 0);
+            }
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+        impl CryptoRng for Fixed {}
+
+        let transcript = Transcript::new(b"test protocol");
+        let nonce = |witness: &[u8]| {
+            let mut rng = transcript
+                .build_rng()
+                .rekey_with_witness_bytes(b"x", witness)
+                .finalize(&mut Fixed);
+            rng.next_u64()
+        };
+        // The same inputs give the same nonce, a different witness does not
+        assert_eq!(nonce(b"one"), nonce(b"one"));
+        assert_ne!(nonce(b"one"), nonce(b"two"));
+    }
+}
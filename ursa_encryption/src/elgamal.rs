@@ -0,0 +1,237 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::error::{EncryptionError, EncryptionResult};
+use curve25519_dalek::{
+    constants::{RISTRETTO_BASEPOINT_POINT, RISTRETTO_BASEPOINT_TABLE},
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+};
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    ops::{Add, Sub},
+};
+use zeroize::Zeroize;
+
+/// The number of bytes in a serialized ciphertext
+pub const CIPHERTEXT_BYTES: usize = 64;
+
+/// An ElGamal secret key
+#[derive(Clone, Debug, Zeroize)]
+#[zeroize(drop)]
+pub struct SecretKey(pub(crate) Scalar);
+
+impl SecretKey {
+    /// Generate a new random secret key
+    pub fn new(rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        Self(Scalar::random(rng))
+    }
+
+    /// Compute the public key `g^x`
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(&self.0 * &RISTRETTO_BASEPOINT_TABLE)
+    }
+
+    /// Recover the encrypted group element
+    pub fn decrypt(&self, ciphertext: &Ciphertext) -> RistrettoPoint {
+        ciphertext.a - self.0 * ciphertext.b
+    }
+
+    /// Serialize the secret key
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+}
+
+impl TryFrom<&[u8]> for SecretKey {
+    type Error = EncryptionError;
+
+    fn try_from(value: &[u8]) -> EncryptionResult<Self> {
+        Ok(Self(read_scalar(value)?))
+    }
+}
+
+/// An ElGamal public key
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct PublicKey(pub(crate) RistrettoPoint);
+
+impl PublicKey {
+    /// Encrypt `message` with fresh randomness
+    pub fn encrypt(
+        &self,
+        rng: &mut (impl RngCore + CryptoRng),
+        message: &RistrettoPoint,
+    ) -> Ciphertext {
+        self.encrypt_with(message, &Scalar::random(rng))
+    }
+
+    /// Encrypt `message` using `randomness`, i.e. `(m * y^r, g^r)`
+    pub fn encrypt_with(&self, message: &RistrettoPoint, randomness: &Scalar) -> Ciphertext {
+        Ciphertext {
+            a: message + randomness * self.0,
+            b: randomness * &RISTRETTO_BASEPOINT_TABLE,
+        }
+    }
+
+    /// Re-randomize a ciphertext so it can't be linked to the original
+    /// while still decrypting to the same message
+    pub fn rerandomize(&self, ciphertext: &Ciphertext, randomness: &Scalar) -> Ciphertext {
+        ciphertext + &self.encrypt_with(&RistrettoPoint::default(), randomness)
+    }
+
+    /// Serialize the public key
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.compress().to_bytes()
+    }
+}
+
+impl TryFrom<&[u8]> for PublicKey {
+    type Error = EncryptionError;
+
+    fn try_from(value: &[u8]) -> EncryptionResult<Self> {
+        Ok(Self(read_point(value)?))
+    }
+}
+
+/// An ElGamal ciphertext `(a, b) = (m * y^r, g^r)`.
+/// Adding ciphertexts encrypts the sum of their messages
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Ciphertext {
+    pub(crate) a: RistrettoPoint,
+    pub(crate) b: RistrettoPoint,
+}
+
+impl Ciphertext {
+    /// Serialize the ciphertext
+    pub fn to_bytes(&self) -> [u8; CIPHERTEXT_BYTES] {
+        let mut out = [0u8; CIPHERTEXT_BYTES];
+        out[..32].copy_from_slice(self.a.compress().as_bytes());
+        out[32..].copy_from_slice(self.b.compress().as_bytes());
+        out
+    }
+}
+
+impl TryFrom<&[u8]> for Ciphertext {
+    type Error = EncryptionError;
+
+    fn try_from(value: &[u8]) -> EncryptionResult<Self> {
+        if value.len() != CIPHERTEXT_BYTES {
+            return Err(EncryptionError::InvalidLength(
+                CIPHERTEXT_BYTES,
+                value.len(),
+            ));
+        }
+        Ok(Self {
+            a: read_point(&value[..32])?,
+            b: read_point(&value[32..])?,
+        })
+    }
+}
+
+impl<'b> Add<&'b Ciphertext> for &Ciphertext {
+    type Output = Ciphertext;
+
+    fn add(self, rhs: &'b Ciphertext) -> Ciphertext {
+        Ciphertext {
+            a: self.a + rhs.a,
+            b: self.b + rhs.b,
+        }
+    }
+}
+
+impl<'b> Sub<&'b Ciphertext> for &Ciphertext {
+    type Output = Ciphertext;
+
+    fn sub(self, rhs: &'b Ciphertext) -> Ciphertext {
+        Ciphertext {
+            a: self.a - rhs.a,
+            b: self.b - rhs.b,
+        }
+    }
+}
+
+/// Encode a small integer as the group element `g^value` so it can be
+/// encrypted. Sums of encrypted values are recovered with a discrete log search.
+pub fn encode_u64(value: u64) -> RistrettoPoint {
+    Scalar::from(value) * RISTRETTO_BASEPOINT_POINT
+}
+
+fn read_point(value: &[u8]) -> EncryptionResult<RistrettoPoint> {
+    if value.len() != 32 {
+        return Err(EncryptionError::InvalidLength(32, value.len()));
+    }
+    CompressedRistretto::from_slice(value)
+        .decompress()
+        .ok_or(EncryptionError::InvalidPoint)
+}
+
+fn read_scalar(value: &[u8]) -> EncryptionResult<Scalar> {
+    if value.len() != 32 {
+        return Err(EncryptionError::InvalidLength(32, value.len()));
+    }
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(value);
+    Scalar::from_canonical_bytes(bytes).ok_or(EncryptionError::InvalidScalar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn encrypt_decrypt() {
+        let mut rng = OsRng;
+        let sk = SecretKey::new(&mut rng);
+        let pk = sk.public_key();
+        let m = RistrettoPoint::random(&mut rng);
+        let c = pk.encrypt(&mut rng, &m);
+        assert_eq!(sk.decrypt(&c), m);
+
+        let c2 = pk.rerandomize(&c, &Scalar::random(&mut rng));
+        assert_ne!(c, c2);
+        assert_eq!(sk.decrypt(&c2), m);
+
+        let other = SecretKey::new(&mut rng);
+        assert_ne!(other.decrypt(&c), m);
+    }
+
+    #[test]
+    fn homomorphic() {
+        let mut rng = OsRng;
+        let sk = SecretKey::new(&mut rng);
+        let pk = sk.public_key();
+        let c1 = pk.encrypt(&mut rng, &encode_u64(5));
+        let c2 = pk.encrypt(&mut rng, &encode_u64(7));
+        assert_eq!(sk.decrypt(&(&c1 + &c2)), encode_u64(12));
+        assert_eq!(sk.decrypt(&(&c2 - &c1)), encode_u64(2));
+    }
+
+    #[test]
+    fn serialization() {
+        let mut rng = OsRng;
+        let sk = SecretKey::new(&mut rng);
+        let pk = sk.public_key();
+        let c = pk.encrypt(&mut rng, &encode_u64(1));
+
+        let sk2 = SecretKey::try_from(&sk.to_bytes()[..]).unwrap();
+        assert_eq!(sk2.public_key(), pk);
+        assert_eq!(PublicKey::try_from(&pk.to_bytes()[..]).unwrap(), pk);
+        assert_eq!(Ciphertext::try_from(&c.to_bytes()[..]).unwrap(), c);
+        assert!(Ciphertext::try_from(&c.to_bytes()[1..]).is_err());
+        assert!(PublicKey::try_from(&[0xffu8; 32][..]).is_err());
+        assert!(SecretKey::try_from(&[0xffu8; 32][..]).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! The errors that are generated by this crate
+//!
+//! Uses a kind enum for the error type
+
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// A specialized [`Result`] type for Encryption operations.
+pub type EncryptionResult<T> = Result<T, EncryptionError>;
+
+/// The error type for Encryption operations.
+#[derive(Copy, Clone, Debug)]
+pub enum EncryptionError {
+    /// Deserializing an invalid group element
+    InvalidPoint,
+    /// Deserializing an invalid scalar
+    InvalidScalar,
+    /// Deserializing a value of the wrong length: expected, found
+    InvalidLength(usize, usize),
+    /// Two lists that must be the same length are not: expected, found
+    CountMismatch(usize, usize),
+    /// The permutation does not contain every index exactly once
+    InvalidPermutation,
+}
+
+impl Display for EncryptionError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use EncryptionError::*;
+
+        match *self {
+            InvalidPoint => write!(f, "Invalid group element"),
+            InvalidScalar => write!(f, "Invalid scalar"),
+            InvalidLength(expected, found) => {
+                write!(f, "Expected {} bytes, found {}", expected, found)
+            }
+            CountMismatch(expected, found) => {
+                write!(f, "Expected {} values, found {}", expected, found)
+            }
+            InvalidPermutation => write!(f, "Invalid permutation"),
+        }
+    }
+}
+
+impl Error for EncryptionError {}
@@ -1 +1,48 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+#![deny(
+    missing_docs,
+    trivial_casts,
+    trivial_numeric_casts,
+    unconditional_recursion,
+    unused_import_braces,
+    unused_lifetimes,
+    unused_qualifications,
+    unused_extern_crates,
+    unused_parens,
+    while_true
+)]
+//! Implements public key encryption schemes with homomorphic properties.
+//!
+//! EC-ElGamal over the Ristretto group encrypts group elements and
+//! ciphertexts can be re-randomized by anyone holding the public key.
+//!
+//! A verifiable shuffle permutes and re-randomizes a list of ciphertexts
+//! and proves the output is a shuffle of the input without revealing the
+//! permutation. This is the building block for mixnets and e-voting.
 
+/// EC-ElGamal encryption
+pub mod elgamal;
+/// Encryption Errors and Results
+pub mod error;
+/// Verifiable shuffles of ElGamal ciphertexts
+///
+/// The proof is Terelius-Wikström rather than Bayer-Groth. Bayer-Groth
+/// proofs are smaller, O(sqrt(n)) group elements instead of O(n), but need
+/// the ciphertexts arranged as a padded matrix, a commitment key sized to it
+/// and separate multi-exponentiation, product and Hadamard arguments.
+/// Terelius-Wikström is a single sigma protocol that is much easier to
+/// review, and both verifiers do linear work. Its proofs cannot be checked
+/// by Bayer-Groth verifiers such as Swiss Post's.
+pub mod shuffle;
@@ -0,0 +1,440 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::{
+    elgamal::{Ciphertext, PublicKey},
+    error::{EncryptionError, EncryptionResult},
+};
+use curve25519_dalek::{
+    constants::{RISTRETTO_BASEPOINT_POINT, RISTRETTO_BASEPOINT_TABLE},
+    ristretto::RistrettoPoint,
+    scalar::Scalar,
+    traits::{MultiscalarMul, VartimeMultiscalarMul},
+};
+use rand::{CryptoRng, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+
+const GENERATOR_DST: &[u8] = b"ursa_encryption_shuffle_generator";
+const PERMUTATION_CHALLENGE_DST: &[u8] = b"ursa_encryption_shuffle_permutation_challenge";
+const CHALLENGE_DST: &[u8] = b"ursa_encryption_shuffle_challenge";
+
+/// Randomly permute and re-randomize `inputs` and prove it was done correctly.
+///
+/// Nobody learns which output corresponds to which input
+/// but anyone can check the proof against both lists.
+pub fn shuffle(
+    rng: &mut (impl RngCore + CryptoRng),
+    pk: &PublicKey,
+    inputs: &[Ciphertext],
+) -> EncryptionResult<(Vec<Ciphertext>, ShuffleProof)> {
+    // Fisher-Yates
+    let mut permutation: Vec<usize> = (0..inputs.len()).collect();
+    for i in (1..permutation.len()).rev() {
+        let j = rng.gen_range(0, i + 1);
+        permutation.swap(i, j);
+    }
+    let randomness: Vec<Scalar> = (0..inputs.len()).map(|_| Scalar::random(rng)).collect();
+    let outputs: Vec<Ciphertext> = permutation
+        .iter()
+        .zip(randomness.iter())
+        .map(|(j, r)| pk.rerandomize(&inputs[*j], r))
+        .collect();
+    let proof = ShuffleProof::new(rng, pk, inputs, &outputs, &permutation, &randomness)?;
+    Ok((outputs, proof))
+}
+
+/// A non-interactive proof that a list of ciphertexts is a permutation and
+/// re-randomization of another list.
+///
+/// This is the Terelius-Wikström proof of a shuffle
+/// (see <https://eprint.iacr.org/2010/212>) following the description in the
+/// CHVote protocol specification (see <https://eprint.iacr.org/2017/325>).
+/// The prover commits to the permutation matrix and shows the committed matrix
+/// is a permutation and was applied to the inputs. Proof size and work are linear
+/// in the number of ciphertexts.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ShuffleProof {
+    permutation_commitments: Vec<RistrettoPoint>,
+    chain_commitments: Vec<RistrettoPoint>,
+    challenge: Scalar,
+    s1: Scalar,
+    s2: Scalar,
+    s3: Scalar,
+    s4: Scalar,
+    s_hat: Vec<Scalar>,
+    s_prime: Vec<Scalar>,
+}
+
+impl ShuffleProof {
+    /// Prove that `outputs[i] = pk.rerandomize(inputs[permutation[i]], randomness[i])`
+    pub fn new(
+        rng: &mut (impl RngCore + CryptoRng),
+        pk: &PublicKey,
+        inputs: &[Ciphertext],
+        outputs: &[Ciphertext],
+        permutation: &[usize],
+        randomness: &[Scalar],
+    ) -> EncryptionResult<Self> {
+        let n = inputs.len();
+        for len in &[outputs.len(), permutation.len(), randomness.len()] {
+            if *len != n {
+                return Err(EncryptionError::CountMismatch(n, *len));
+            }
+        }
+        let mut seen = vec![false; n];
+        for j in permutation {
+            if *j >= n || seen[*j] {
+                return Err(EncryptionError::InvalidPermutation);
+            }
+            seen[*j] = true;
+        }
+
+        let (h, hs) = generators(n);
+
+        // Commit to the permutation, c_j = g^r_j * h_i where j = permutation[i]
+        let r = random_scalars(rng, n);
+        let mut permutation_commitments = vec![RistrettoPoint::default(); n];
+        for (i, j) in permutation.iter().enumerate() {
+            permutation_commitments[*j] = &r[*j] * &RISTRETTO_BASEPOINT_TABLE + hs[i];
+        }
+
+        let u = permutation_challenges(pk, inputs, outputs, &permutation_commitments);
+        let u_prime: Vec<Scalar> = permutation.iter().map(|j| u[*j]).collect();
+
+        // Commit to the permuted challenges in a chain,
+        // c_hat_i = g^r_hat_i * c_hat_{i-1}^u'_i with c_hat_0 = h
+        let r_hat = random_scalars(rng, n);
+        let mut chain_commitments = Vec::with_capacity(n);
+        let mut previous = h;
+        for (r_hat_i, u_i) in r_hat.iter().zip(u_prime.iter()) {
+            previous = r_hat_i * &RISTRETTO_BASEPOINT_TABLE + u_i * previous;
+            chain_commitments.push(previous);
+        }
+
+        // Aggregated witnesses
+        let r_bar: Scalar = r.iter().sum();
+        let mut v = vec![Scalar::one(); n];
+        for i in (0..n.saturating_sub(1)).rev() {
+            v[i] = u_prime[i + 1] * v[i + 1];
+        }
+        let r_hat_sum: Scalar = r_hat.iter().zip(v.iter()).map(|(a, b)| a * b).sum();
+        let r_tilde: Scalar = r.iter().zip(u.iter()).map(|(a, b)| a * b).sum();
+        let r_prime: Scalar = randomness
+            .iter()
+            .zip(u_prime.iter())
+            .map(|(a, b)| a * b)
+            .sum();
+
+        let w1 = Scalar::random(rng);
+        let w2 = Scalar::random(rng);
+        let w3 = Scalar::random(rng);
+        let w4 = Scalar::random(rng);
+        let w_hat = random_scalars(rng, n);
+        let w_prime = random_scalars(rng, n);
+
+        let t = [
+            &w1 * &RISTRETTO_BASEPOINT_TABLE,
+            &w2 * &RISTRETTO_BASEPOINT_TABLE,
+            &w3 * &RISTRETTO_BASEPOINT_TABLE + RistrettoPoint::multiscalar_mul(&w_prime, &hs),
+            RistrettoPoint::multiscalar_mul(&w_prime, outputs.iter().map(|e| e.a)) - w4 * pk.0,
+            RistrettoPoint::multiscalar_mul(&w_prime, outputs.iter().map(|e| e.b))
+                - &w4 * &RISTRETTO_BASEPOINT_TABLE,
+        ];
+        let mut t_hat = Vec::with_capacity(n);
+        let mut previous = h;
+        for i in 0..n {
+            t_hat.push(&w_hat[i] * &RISTRETTO_BASEPOINT_TABLE + w_prime[i] * previous);
+            previous = chain_commitments[i];
+        }
+
+        let challenge = challenge(
+            pk,
+            inputs,
+            outputs,
+            &permutation_commitments,
+            &chain_commitments,
+            &t,
+            &t_hat,
+        );
+
+        Ok(Self {
+            permutation_commitments,
+            chain_commitments,
+            challenge,
+            s1: w1 + challenge * r_bar,
+            s2: w2 + challenge * r_hat_sum,
+            s3: w3 + challenge * r_tilde,
+            s4: w4 + challenge * r_prime,
+            s_hat: w_hat
+                .iter()
+                .zip(r_hat.iter())
+                .map(|(w, r)| w + challenge * r)
+                .collect(),
+            s_prime: w_prime
+                .iter()
+                .zip(u_prime.iter())
+                .map(|(w, u)| w + challenge * u)
+                .collect(),
+        })
+    }
+
+    /// Check that `outputs` is a shuffle of `inputs` under `pk`
+    pub fn verify(&self, pk: &PublicKey, inputs: &[Ciphertext], outputs: &[Ciphertext]) -> bool {
+        let n = self.permutation_commitments.len();
+        if inputs.len() != n
+            || outputs.len() != n
+            || self.chain_commitments.len() != n
+            || self.s_hat.len() != n
+            || self.s_prime.len() != n
+        {
+            return false;
+        }
+
+        let (h, hs) = generators(n);
+        let u = permutation_challenges(pk, inputs, outputs, &self.permutation_commitments);
+        let c = self.challenge;
+
+        // g^r_bar
+        let c_bar = self.permutation_commitments.iter().sum::<RistrettoPoint>()
+            - hs.iter().sum::<RistrettoPoint>();
+        // g^r_hat
+        let u_product: Scalar = u.iter().product();
+        let c_hat = self.chain_commitments.last().unwrap_or(&h) - u_product * h;
+        // g^r_tilde * prod h_i^u'_i
+        let c_tilde = RistrettoPoint::vartime_multiscalar_mul(&u, &self.permutation_commitments);
+        let a_bar = RistrettoPoint::vartime_multiscalar_mul(&u, inputs.iter().map(|e| e.a));
+        let b_bar = RistrettoPoint::vartime_multiscalar_mul(&u, inputs.iter().map(|e| e.b));
+
+        let t = [
+            &self.s1 * &RISTRETTO_BASEPOINT_TABLE - c * c_bar,
+            &self.s2 * &RISTRETTO_BASEPOINT_TABLE - c * c_hat,
+            &self.s3 * &RISTRETTO_BASEPOINT_TABLE
+                + RistrettoPoint::vartime_multiscalar_mul(&self.s_prime, &hs)
+                - c * c_tilde,
+            RistrettoPoint::vartime_multiscalar_mul(&self.s_prime, outputs.iter().map(|e| e.a))
+                - self.s4 * pk.0
+                - c * a_bar,
+            RistrettoPoint::vartime_multiscalar_mul(&self.s_prime, outputs.iter().map(|e| e.b))
+                - &self.s4 * &RISTRETTO_BASEPOINT_TABLE
+                - c * b_bar,
+        ];
+        let mut t_hat = Vec::with_capacity(n);
+        let mut previous = h;
+        for i in 0..n {
+            t_hat.push(RistrettoPoint::vartime_multiscalar_mul(
+                &[self.s_hat[i], self.s_prime[i], -c],
+                &[
+                    RISTRETTO_BASEPOINT_POINT,
+                    previous,
+                    self.chain_commitments[i],
+                ],
+            ));
+            previous = self.chain_commitments[i];
+        }
+
+        c == challenge(
+            pk,
+            inputs,
+            outputs,
+            &self.permutation_commitments,
+            &self.chain_commitments,
+            &t,
+            &t_hat,
+        )
+    }
+}
+
+/// Independent generators `h` and `h_1..h_n` with unknown discrete logs
+fn generators(n: usize) -> (RistrettoPoint, Vec<RistrettoPoint>) {
+    let h = RistrettoPoint::hash_from_bytes::<Sha512>(GENERATOR_DST);
+    let hs = (0..n)
+        .map(|i| {
+            RistrettoPoint::from_hash(
+                Sha512::new()
+                    .chain(GENERATOR_DST)
+                    .chain((i as u32).to_be_bytes()),
+            )
+        })
+        .collect();
+    (h, hs)
+}
+
+/// The challenges `u_i` binding the shuffle to the permutation commitment
+fn permutation_challenges(
+    pk: &PublicKey,
+    inputs: &[Ciphertext],
+    outputs: &[Ciphertext],
+    permutation_commitments: &[RistrettoPoint],
+) -> Vec<Scalar> {
+    let mut hasher = Sha512::new().chain(PERMUTATION_CHALLENGE_DST);
+    hasher.update(pk.to_bytes());
+    for e in inputs.iter().chain(outputs.iter()) {
+        hasher.update(e.to_bytes());
+    }
+    for c in permutation_commitments {
+        hasher.update(c.compress().as_bytes());
+    }
+    let seed = hasher.finalize();
+    (0..inputs.len())
+        .map(|i| {
+            Scalar::from_hash(
+                Sha512::new()
+                    .chain(seed.as_slice())
+                    .chain((i as u32).to_be_bytes()),
+            )
+        })
+        .collect()
+}
+
+fn challenge(
+    pk: &PublicKey,
+    inputs: &[Ciphertext],
+    outputs: &[Ciphertext],
+    permutation_commitments: &[RistrettoPoint],
+    chain_commitments: &[RistrettoPoint],
+    t: &[RistrettoPoint],
+    t_hat: &[RistrettoPoint],
+) -> Scalar {
+    let mut hasher = Sha512::new().chain(CHALLENGE_DST);
+    hasher.update(pk.to_bytes());
+    for e in inputs.iter().chain(outputs.iter()) {
+        hasher.update(e.to_bytes());
+    }
+    for p in permutation_commitments
+        .iter()
+        .chain(chain_commitments.iter())
+        .chain(t.iter())
+        .chain(t_hat.iter())
+    {
+        hasher.update(p.compress().as_bytes());
+    }
+    Scalar::from_hash(hasher)
+}
+
+fn random_scalars(rng: &mut (impl RngCore + CryptoRng), n: usize) -> Vec<Scalar> {
+    (0..n).map(|_| Scalar::random(rng)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elgamal::{encode_u64, SecretKey};
+    use rand::rngs::OsRng;
+
+    fn setup(n: u64) -> (SecretKey, PublicKey, Vec<Ciphertext>) {
+        let mut rng = OsRng;
+        let sk = SecretKey::new(&mut rng);
+        let pk = sk.public_key();
+        let inputs = (0..n)
+            .map(|i| pk.encrypt(&mut rng, &encode_u64(i)))
+            .collect();
+        (sk, pk, inputs)
+    }
+
+    #[test]
+    fn shuffle_verifies() {
+        let mut rng = OsRng;
+        let (sk, pk, inputs) = setup(10);
+        let (outputs, proof) = shuffle(&mut rng, &pk, &inputs).unwrap();
+        assert!(proof.verify(&pk, &inputs, &outputs));
+
+        let mut decrypted: Vec<[u8; 32]> = outputs
+            .iter()
+            .map(|e| sk.decrypt(e).compress().to_bytes())
+            .collect();
+        let mut expected: Vec<[u8; 32]> = inputs
+            .iter()
+            .map(|e| sk.decrypt(e).compress().to_bytes())
+            .collect();
+        decrypted.sort();
+        expected.sort();
+        assert_eq!(decrypted, expected);
+
+        for n in 0..3 {
+            let (_, pk, inputs) = setup(n);
+            let (outputs, proof) = shuffle(&mut rng, &pk, &inputs).unwrap();
+            assert!(proof.verify(&pk, &inputs, &outputs));
+        }
+    }
+
+    #[test]
+    fn tampered_shuffle_fails() {
+        let mut rng = OsRng;
+        let (_, pk, inputs) = setup(5);
+        let (outputs, proof) = shuffle(&mut rng, &pk, &inputs).unwrap();
+
+        let mut swapped = outputs.clone();
+        swapped.swap(0, 1);
+        assert!(!proof.verify(&pk, &inputs, &swapped));
+
+        let mut replaced = outputs.clone();
+        replaced[2] = pk.encrypt(&mut rng, &encode_u64(100));
+        assert!(!proof.verify(&pk, &inputs, &replaced));
+
+        let mut rerandomized = outputs.clone();
+        rerandomized[3] = pk.rerandomize(&outputs[3], &Scalar::random(&mut rng));
+        assert!(!proof.verify(&pk, &inputs, &rerandomized));
+
+        assert!(!proof.verify(&pk, &inputs[1..], &outputs[1..]));
+        assert!(!proof.verify(&pk, &inputs, &outputs[1..]));
+        let other = SecretKey::new(&mut rng).public_key();
+        assert!(!proof.verify(&other, &inputs, &outputs));
+    }
+
+    #[test]
+    fn invalid_witness() {
+        let mut rng = OsRng;
+        let (_, pk, inputs) = setup(4);
+        let permutation = [2, 0, 3, 1];
+        let randomness = random_scalars(&mut rng, 4);
+        let outputs: Vec<Ciphertext> = permutation
+            .iter()
+            .zip(randomness.iter())
+            .map(|(j, r)| pk.rerandomize(&inputs[*j], r))
+            .collect();
+
+        let proof =
+            ShuffleProof::new(&mut rng, &pk, &inputs, &outputs, &permutation, &randomness).unwrap();
+        assert!(proof.verify(&pk, &inputs, &outputs));
+
+        // A message was replaced so no witness exists
+        let mut replaced = outputs.clone();
+        replaced[0] = pk.encrypt_with(&encode_u64(9), &randomness[0]);
+        let proof = ShuffleProof::new(&mut rng, &pk, &inputs, &replaced, &permutation, &randomness)
+            .unwrap();
+        assert!(!proof.verify(&pk, &inputs, &replaced));
+
+        assert!(
+            ShuffleProof::new(&mut rng, &pk, &inputs, &outputs, &[2, 0, 3, 3], &randomness)
+                .is_err()
+        );
+        assert!(
+            ShuffleProof::new(&mut rng, &pk, &inputs, &outputs, &[2, 0, 3, 4], &randomness)
+                .is_err()
+        );
+        assert!(
+            ShuffleProof::new(&mut rng, &pk, &inputs, &outputs, &[2, 0, 3], &randomness).is_err()
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let mut rng = OsRng;
+        let (_, pk, inputs) = setup(3);
+        let (outputs, proof) = shuffle(&mut rng, &pk, &inputs).unwrap();
+        let json = serde_json::to_string(&proof).unwrap();
+        let proof: ShuffleProof = serde_json::from_str(&json).unwrap();
+        assert!(proof.verify(&pk, &inputs, &outputs));
+    }
+}
@@ -0,0 +1,224 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::{
+    error::{OtError, OtResult},
+    OtKey,
+};
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_TABLE,
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::IsIdentity,
+};
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+const KEY_DST: &[u8] = b"ursa_ot_co15_key";
+
+/// The sender's first and only message `A = g^a`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct SenderSetup {
+    a: [u8; 32],
+}
+
+/// The receiver's reply with one point per choice bit.
+/// `B = g^b` for choice 0 and `B = A * g^b` for choice 1
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ReceiverResponse {
+    b: Vec<[u8; 32]>,
+}
+
+impl ReceiverResponse {
+    /// The number of OTs requested by the receiver
+    pub fn len(&self) -> usize {
+        self.b.len()
+    }
+
+    /// True if the receiver requested no OTs
+    pub fn is_empty(&self) -> bool {
+        self.b.is_empty()
+    }
+}
+
+/// The sender side of a batch of base OTs
+#[derive(Debug)]
+pub struct Sender {
+    a: Scalar,
+    big_a: RistrettoPoint,
+}
+
+impl Sender {
+    /// Start a batch of base OTs and return the message for the receiver
+    pub fn new(rng: &mut (impl RngCore + CryptoRng)) -> (Self, SenderSetup) {
+        let a = Scalar::random(rng);
+        let big_a = &a * &RISTRETTO_BASEPOINT_TABLE;
+        let setup = SenderSetup {
+            a: big_a.compress().to_bytes(),
+        };
+        (Self { a, big_a }, setup)
+    }
+
+    /// Compute both keys of every OT. The receiver knows exactly one key of each pair.
+    pub fn keys(&self, response: &ReceiverResponse) -> OtResult<Vec<(OtKey, OtKey)>> {
+        let big_a = self.big_a.compress();
+        response
+            .b
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                let big_b = decompress(b)?;
+                let k0 = hash_key(&big_a, b, i, &(self.a * big_b));
+                let k1 = hash_key(&big_a, b, i, &(self.a * (big_b - self.big_a)));
+                Ok((k0, k1))
+            })
+            .collect()
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        self.a.zeroize();
+    }
+}
+
+/// The receiver side of a batch of base OTs
+#[derive(Debug)]
+pub struct Receiver {
+    keys: Vec<OtKey>,
+}
+
+impl Receiver {
+    /// Choose one key from each OT according to `choices`
+    pub fn new(
+        rng: &mut (impl RngCore + CryptoRng),
+        setup: &SenderSetup,
+        choices: &[bool],
+    ) -> OtResult<(Self, ReceiverResponse)> {
+        let big_a = decompress(&setup.a)?;
+        if big_a.is_identity() {
+            return Err(OtError::InvalidPoint);
+        }
+        let compressed_a = big_a.compress();
+
+        let mut keys = Vec::with_capacity(choices.len());
+        let mut b = Vec::with_capacity(choices.len());
+        for (i, choice) in choices.iter().enumerate() {
+            let mut s = Scalar::random(rng);
+            let mut big_b = &s * &RISTRETTO_BASEPOINT_TABLE;
+            if *choice {
+                big_b += big_a;
+            }
+            let compressed_b = big_b.compress().to_bytes();
+            keys.push(hash_key(&compressed_a, &compressed_b, i, &(s * big_a)));
+            b.push(compressed_b);
+            s.zeroize();
+        }
+        Ok((Self { keys }, ReceiverResponse { b }))
+    }
+
+    /// The chosen key of every OT
+    pub fn keys(&self) -> &[OtKey] {
+        self.keys.as_slice()
+    }
+
+    /// Consume this receiver returning the chosen keys
+    pub fn into_keys(mut self) -> Vec<OtKey> {
+        std::mem::take(&mut self.keys)
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        self.keys.zeroize();
+    }
+}
+
+fn decompress(bytes: &[u8; 32]) -> OtResult<RistrettoPoint> {
+    CompressedRistretto(*bytes)
+        .decompress()
+        .ok_or(OtError::InvalidPoint)
+}
+
+fn hash_key(a: &CompressedRistretto, b: &[u8; 32], index: usize, shared: &RistrettoPoint) -> OtKey {
+    let mut key = [0u8; 32];
+    key.copy_from_slice(
+        Sha256::new()
+            .chain(KEY_DST)
+            .chain(a.as_bytes())
+            .chain(b)
+            .chain((index as u64).to_be_bytes())
+            .chain(shared.compress().as_bytes())
+            .finalize()
+            .as_slice(),
+    );
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn receiver_gets_chosen_keys() {
+        let mut rng = OsRng;
+        let choices = [true, false, false, true, true];
+        let (sender, setup) = Sender::new(&mut rng);
+        let (receiver, response) = Receiver::new(&mut rng, &setup, &choices).unwrap();
+        let pairs = sender.keys(&response).unwrap();
+        assert_eq!(pairs.len(), choices.len());
+
+        for ((k0, k1), (k, c)) in pairs.iter().zip(receiver.keys().iter().zip(choices.iter())) {
+            assert_ne!(k0, k1);
+            if *c {
+                assert_eq!(k1, k);
+                assert_ne!(k0, k);
+            } else {
+                assert_eq!(k0, k);
+                assert_ne!(k1, k);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_points_rejected() {
+        let mut rng = OsRng;
+        let setup = SenderSetup { a: [0u8; 32] };
+        assert!(Receiver::new(&mut rng, &setup, &[true]).is_err());
+        let setup = SenderSetup { a: [0xffu8; 32] };
+        assert!(Receiver::new(&mut rng, &setup, &[true]).is_err());
+
+        let (sender, _) = Sender::new(&mut rng);
+        let response = ReceiverResponse {
+            b: vec![[0xffu8; 32]],
+        };
+        assert!(sender.keys(&response).is_err());
+    }
+
+    #[test]
+    fn messages_serialize() {
+        let mut rng = OsRng;
+        let (sender, setup) = Sender::new(&mut rng);
+        let setup: SenderSetup =
+            serde_json::from_str(&serde_json::to_string(&setup).unwrap()).unwrap();
+        let (receiver, response) = Receiver::new(&mut rng, &setup, &[false, true]).unwrap();
+        let response: ReceiverResponse =
+            serde_json::from_str(&serde_json::to_string(&response).unwrap()).unwrap();
+        let pairs = sender.keys(&response).unwrap();
+        assert_eq!(pairs[0].0, receiver.keys()[0]);
+        assert_eq!(pairs[1].1, receiver.keys()[1]);
+    }
+}
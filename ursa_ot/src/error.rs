@@ -0,0 +1,59 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! The errors that are generated by this crate
+//!
+//! Uses a kind enum for the error type
+
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// A specialized [`Result`] type for OT operations.
+pub type OtResult<T> = Result<T, OtError>;
+
+/// The error type for OT operations.
+#[derive(Copy, Clone, Debug)]
+pub enum OtError {
+    /// Received bytes that are not a valid group element
+    InvalidPoint,
+    /// Received the wrong number of values: expected, found
+    CountMismatch(usize, usize),
+    /// The two messages of a pair are not the same length
+    MessageLengthMismatch,
+    /// The receiver's extension messages are inconsistent
+    ConsistencyCheckFailed,
+    /// An extension request arrived out of order: expected, found
+    UnexpectedBatch(u64, u64),
+}
+
+impl Display for OtError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use OtError::*;
+
+        match *self {
+            InvalidPoint => write!(f, "Invalid group element"),
+            CountMismatch(expected, found) => {
+                write!(f, "Expected {} values, found {}", expected, found)
+            }
+            MessageLengthMismatch => write!(f, "Messages in a pair must have the same length"),
+            ConsistencyCheckFailed => write!(f, "OT extension consistency check failed"),
+            UnexpectedBatch(expected, found) => {
+                write!(f, "Expected extension batch {}, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl Error for OtError {}
@@ -0,0 +1,498 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::{
+    base,
+    error::{OtError, OtResult},
+    OtKey,
+};
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+/// The number of base OTs, which is also the bit length of each extended OT row
+pub const SECURITY_PARAMETER: usize = 128;
+/// The statistical security of the consistency check in bits
+pub const STATISTICAL_PARAMETER: usize = 64;
+/// Extra random OTs added to each batch so the consistency check
+/// reveals nothing about the real choice bits
+const PADDING: usize = SECURITY_PARAMETER + STATISTICAL_PARAMETER;
+
+const PRG_DST: &[u8] = b"ursa_ot_iknp_prg";
+const CHI_DST: &[u8] = b"ursa_ot_kos_chi";
+const KEY_DST: &[u8] = b"ursa_ot_iknp_key";
+
+/// The extension receiver's columns for one batch of OTs along with
+/// its values for the consistency check
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ExtensionRequest {
+    batch: u64,
+    count: usize,
+    columns: Vec<Vec<u8>>,
+    check_x: u128,
+    check_t: u128,
+}
+
+impl ExtensionRequest {
+    /// The number of OTs requested
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// True if no OTs were requested
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+/// The extension receiver before the base OTs complete.
+/// In the base OTs the roles are reversed so the extension receiver is the base OT sender.
+#[derive(Debug)]
+pub struct ReceiverSetup {
+    base: base::Sender,
+}
+
+impl ReceiverSetup {
+    /// Start the base OTs and return the message for the extension sender
+    pub fn new(rng: &mut (impl RngCore + CryptoRng)) -> (Self, base::SenderSetup) {
+        let (base, setup) = base::Sender::new(rng);
+        (Self { base }, setup)
+    }
+
+    /// Complete the base OTs with the extension sender's response
+    pub fn finish(self, response: &base::ReceiverResponse) -> OtResult<Receiver> {
+        if response.len() != SECURITY_PARAMETER {
+            return Err(OtError::CountMismatch(SECURITY_PARAMETER, response.len()));
+        }
+        Ok(Receiver {
+            seeds: self.base.keys(response)?,
+            batch: 0,
+        })
+    }
+}
+
+/// The extension receiver which learns one key of each OT according to its choice bits
+#[derive(Debug)]
+pub struct Receiver {
+    seeds: Vec<(OtKey, OtKey)>,
+    batch: u64,
+}
+
+impl Receiver {
+    /// Request one batch of OTs. Returns the keys chosen by `choices` and the
+    /// request for the sender. Batches must be given to the sender in the order they are created.
+    pub fn extend(
+        &mut self,
+        rng: &mut (impl RngCore + CryptoRng),
+        choices: &[bool],
+    ) -> (Vec<OtKey>, ExtensionRequest) {
+        let total = choices.len() + PADDING;
+        let len = column_len(total);
+
+        let mut r = vec![0u8; len];
+        for (j, c) in choices.iter().enumerate() {
+            if *c {
+                set_bit(&mut r, j);
+            }
+        }
+        let mut padding = vec![0u8; column_len(PADDING)];
+        rng.fill_bytes(&mut padding);
+        for j in 0..PADDING {
+            if get_bit(&padding, j) {
+                set_bit(&mut r, choices.len() + j);
+            }
+        }
+
+        // t_i = PRG(k_i^0), u_i = t_i ^ PRG(k_i^1) ^ r
+        let mut t_columns = Vec::with_capacity(SECURITY_PARAMETER);
+        let mut u_columns = Vec::with_capacity(SECURITY_PARAMETER);
+        for (k0, k1) in &self.seeds {
+            let t = prg(k0, self.batch, len);
+            let mut u = prg(k1, self.batch, len);
+            for ((u, t), r) in u.iter_mut().zip(t.iter()).zip(r.iter()) {
+                *u ^= t ^ r;
+            }
+            t_columns.push(t);
+            u_columns.push(u);
+        }
+        let rows = transpose(&t_columns, total);
+
+        let chi = chi(self.batch, choices.len(), &u_columns, total);
+        let mut check_x = 0u128;
+        let mut check_t = 0u128;
+        for (j, (chi, row)) in chi.iter().zip(rows.iter()).enumerate() {
+            if get_bit(&r, j) {
+                check_x ^= chi;
+            }
+            check_t ^= gf128_mul(*chi, *row);
+        }
+
+        let keys = rows[..choices.len()]
+            .iter()
+            .enumerate()
+            .map(|(j, row)| hash_row(self.batch, j, *row))
+            .collect();
+        let request = ExtensionRequest {
+            batch: self.batch,
+            count: choices.len(),
+            columns: u_columns,
+            check_x,
+            check_t,
+        };
+        r.zeroize();
+        t_columns.zeroize();
+        self.batch += 1;
+        (keys, request)
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        for (k0, k1) in self.seeds.iter_mut() {
+            k0.zeroize();
+            k1.zeroize();
+        }
+    }
+}
+
+/// The extension sender which learns both keys of each OT
+#[derive(Debug)]
+pub struct Sender {
+    delta: u128,
+    seeds: Vec<OtKey>,
+    batch: u64,
+    aborted: bool,
+}
+
+impl Sender {
+    /// Complete the base OTs using the extension receiver's setup message
+    /// and return the response to send back
+    pub fn new(
+        rng: &mut (impl RngCore + CryptoRng),
+        setup: &base::SenderSetup,
+    ) -> OtResult<(Self, base::ReceiverResponse)> {
+        let mut delta_bytes = [0u8; 16];
+        rng.fill_bytes(&mut delta_bytes);
+        let delta = u128::from_le_bytes(delta_bytes);
+        delta_bytes.zeroize();
+
+        let choices: Vec<bool> = (0..SECURITY_PARAMETER)
+            .map(|i| (delta >> i) & 1 == 1)
+            .collect();
+        let (receiver, response) = base::Receiver::new(rng, setup, &choices)?;
+        Ok((
+            Self {
+                delta,
+                seeds: receiver.into_keys(),
+                batch: 0,
+                aborted: false,
+            },
+            response,
+        ))
+    }
+
+    /// Compute both keys of each requested OT after checking the request is consistent.
+    /// Once a check fails this sender refuses all further requests since
+    /// a malicious receiver learns about the base OT choices from failures.
+    pub fn extend(&mut self, request: &ExtensionRequest) -> OtResult<Vec<(OtKey, OtKey)>> {
+        if self.aborted {
+            return Err(OtError::ConsistencyCheckFailed);
+        }
+        if request.batch != self.batch {
+            return Err(OtError::UnexpectedBatch(self.batch, request.batch));
+        }
+        if request.columns.len() != SECURITY_PARAMETER {
+            return Err(OtError::CountMismatch(
+                SECURITY_PARAMETER,
+                request.columns.len(),
+            ));
+        }
+        let total = request
+            .count
+            .checked_add(PADDING)
+            .ok_or(OtError::CountMismatch(0, request.count))?;
+        let len = column_len(total);
+        for c in &request.columns {
+            if c.len() != len {
+                return Err(OtError::CountMismatch(len, c.len()));
+            }
+        }
+
+        // q_i = PRG(k_i^{s_i}) ^ s_i * u_i = t_i ^ s_i * r
+        let mut q_columns = Vec::with_capacity(SECURITY_PARAMETER);
+        for (i, (seed, u)) in self.seeds.iter().zip(request.columns.iter()).enumerate() {
+            let mut q = prg(seed, self.batch, len);
+            if (self.delta >> i) & 1 == 1 {
+                for (q, u) in q.iter_mut().zip(u.iter()) {
+                    *q ^= u;
+                }
+            }
+            q_columns.push(q);
+        }
+        let rows = transpose(&q_columns, total);
+        q_columns.zeroize();
+
+        let chi = chi(self.batch, request.count, &request.columns, total);
+        let mut check_q = 0u128;
+        for (chi, row) in chi.iter().zip(rows.iter()) {
+            check_q ^= gf128_mul(*chi, *row);
+        }
+        self.batch += 1;
+        if check_q != request.check_t ^ gf128_mul(request.check_x, self.delta) {
+            self.aborted = true;
+            return Err(OtError::ConsistencyCheckFailed);
+        }
+
+        let batch = request.batch;
+        Ok(rows[..request.count]
+            .iter()
+            .enumerate()
+            .map(|(j, row)| {
+                (
+                    hash_row(batch, j, *row),
+                    hash_row(batch, j, *row ^ self.delta),
+                )
+            })
+            .collect())
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        self.delta.zeroize();
+        self.seeds.zeroize();
+    }
+}
+
+fn column_len(bits: usize) -> usize {
+    bits.div_ceil(8)
+}
+
+fn get_bit(bytes: &[u8], i: usize) -> bool {
+    (bytes[i / 8] >> (i % 8)) & 1 == 1
+}
+
+fn set_bit(bytes: &mut [u8], i: usize) {
+    bytes[i / 8] |= 1 << (i % 8);
+}
+
+/// Expand `seed` to `len` bytes
+fn prg(seed: &OtKey, batch: u64, len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len + 32);
+    let mut counter = 0u64;
+    while out.len() < len {
+        out.extend_from_slice(
+            Sha256::new()
+                .chain(PRG_DST)
+                .chain(seed)
+                .chain(batch.to_be_bytes())
+                .chain(counter.to_be_bytes())
+                .finalize()
+                .as_slice(),
+        );
+        counter += 1;
+    }
+    out.truncate(len);
+    out
+}
+
+/// Convert 128 columns into `rows` rows of 128 bits
+fn transpose(columns: &[Vec<u8>], rows: usize) -> Vec<u128> {
+    let mut out = vec![0u128; rows];
+    for (i, column) in columns.iter().enumerate() {
+        for (j, row) in out.iter_mut().enumerate() {
+            if get_bit(column, j) {
+                *row |= 1u128 << i;
+            }
+        }
+    }
+    out
+}
+
+/// The weights for the consistency check are derived from everything the receiver
+/// committed to so it cannot choose its values after seeing them
+fn chi(batch: u64, count: usize, columns: &[Vec<u8>], total: usize) -> Vec<u128> {
+    let mut hasher = Sha256::new();
+    hasher.update(CHI_DST);
+    hasher.update(batch.to_be_bytes());
+    hasher.update((count as u64).to_be_bytes());
+    for c in columns {
+        hasher.update(c);
+    }
+    let seed = hasher.finalize();
+
+    let mut out = Vec::with_capacity(total);
+    let mut counter = 0u64;
+    while out.len() < total {
+        let block = Sha256::new()
+            .chain(seed.as_slice())
+            .chain(counter.to_be_bytes())
+            .finalize();
+        let mut lo = [0u8; 16];
+        let mut hi = [0u8; 16];
+        lo.copy_from_slice(&block[..16]);
+        hi.copy_from_slice(&block[16..]);
+        out.push(u128::from_le_bytes(lo));
+        out.push(u128::from_le_bytes(hi));
+        counter += 1;
+    }
+    out.truncate(total);
+    out
+}
+
+fn hash_row(batch: u64, index: usize, row: u128) -> OtKey {
+    let mut key = [0u8; 32];
+    key.copy_from_slice(
+        Sha256::new()
+            .chain(KEY_DST)
+            .chain(batch.to_be_bytes())
+            .chain((index as u64).to_be_bytes())
+            .chain(row.to_le_bytes())
+            .finalize()
+            .as_slice(),
+    );
+    key
+}
+
+/// Multiply in GF(2^128) with the reduction polynomial x^128 + x^7 + x^2 + x + 1
+fn gf128_mul(a: u128, b: u128) -> u128 {
+    let mut lo = 0u128;
+    let mut hi = 0u128;
+    for i in 0..128 {
+        if (b >> i) & 1 == 1 {
+            lo ^= a << i;
+            if i > 0 {
+                hi ^= a >> (128 - i);
+            }
+        }
+    }
+    // x^128 = x^7 + x^2 + x + 1. The bits shifted out of hi are reduced a second time.
+    let overflow = (hi >> 121) ^ (hi >> 126) ^ (hi >> 127);
+    lo ^= hi ^ (hi << 1) ^ (hi << 2) ^ (hi << 7);
+    lo ^ overflow ^ (overflow << 1) ^ (overflow << 2) ^ (overflow << 7)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transfer;
+    use rand::rngs::OsRng;
+
+    fn setup() -> (Sender, Receiver) {
+        let mut rng = OsRng;
+        let (receiver_setup, base_setup) = ReceiverSetup::new(&mut rng);
+        let (sender, base_response) = Sender::new(&mut rng, &base_setup).unwrap();
+        let receiver = receiver_setup.finish(&base_response).unwrap();
+        (sender, receiver)
+    }
+
+    fn random_choices(count: usize) -> Vec<bool> {
+        let mut rng = OsRng;
+        (0..count).map(|_| rng.next_u32() & 1 == 1).collect()
+    }
+
+    #[test]
+    fn gf128() {
+        let mut rng = OsRng;
+        let mut random = || {
+            let mut b = [0u8; 16];
+            rng.fill_bytes(&mut b);
+            u128::from_le_bytes(b)
+        };
+        let (a, b, c) = (random(), random(), random());
+        assert_eq!(gf128_mul(a, 1), a);
+        assert_eq!(gf128_mul(a, 0), 0);
+        assert_eq!(gf128_mul(a, b), gf128_mul(b, a));
+        assert_eq!(gf128_mul(a, b ^ c), gf128_mul(a, b) ^ gf128_mul(a, c));
+        assert_eq!(gf128_mul(gf128_mul(a, b), c), gf128_mul(a, gf128_mul(b, c)));
+        // x^127 * x = x^7 + x^2 + x + 1
+        assert_eq!(gf128_mul(1 << 127, 2), 0x87);
+    }
+
+    #[test]
+    fn receiver_gets_chosen_keys() {
+        let mut rng = OsRng;
+        let (mut sender, mut receiver) = setup();
+
+        // Several batches reuse the same base OTs
+        for count in &[1000, 1, 0] {
+            let choices = random_choices(*count);
+            let (keys, request) = receiver.extend(&mut rng, &choices);
+            let request: ExtensionRequest =
+                serde_json::from_str(&serde_json::to_string(&request).unwrap()).unwrap();
+            let pairs = sender.extend(&request).unwrap();
+            assert_eq!(pairs.len(), *count);
+            for ((k0, k1), (k, c)) in pairs.iter().zip(keys.iter().zip(choices.iter())) {
+                assert_ne!(k0, k1);
+                assert_eq!(if *c { k1 } else { k0 }, k);
+            }
+        }
+    }
+
+    #[test]
+    fn chosen_message_transfer() {
+        let mut rng = OsRng;
+        let (mut sender, mut receiver) = setup();
+        let messages: Vec<(Vec<u8>, Vec<u8>)> = (0..50u8)
+            .map(|i| (vec![i; 40], vec![i + 100; 40]))
+            .collect();
+        let choices = random_choices(messages.len());
+
+        let (keys, request) = receiver.extend(&mut rng, &choices);
+        let pairs = sender.extend(&request).unwrap();
+        let encrypted = transfer::encrypt(&pairs, &messages).unwrap();
+        let received = transfer::decrypt(&keys, &choices, &encrypted).unwrap();
+        for ((m0, m1), (r, c)) in messages.iter().zip(received.iter().zip(choices.iter())) {
+            assert_eq!(if *c { m1 } else { m0 }, r);
+        }
+    }
+
+    #[test]
+    fn out_of_order_batches_rejected() {
+        let mut rng = OsRng;
+        let (mut sender, mut receiver) = setup();
+        let (_, first) = receiver.extend(&mut rng, &random_choices(10));
+        let (_, second) = receiver.extend(&mut rng, &random_choices(10));
+        assert!(sender.extend(&second).is_err());
+        assert!(sender.extend(&first).is_ok());
+        assert!(sender.extend(&first).is_err());
+        assert!(sender.extend(&second).is_ok());
+    }
+
+    #[test]
+    fn inconsistent_request_aborts() {
+        let mut rng = OsRng;
+        let (mut sender, mut receiver) = setup();
+        let (_, mut request) = receiver.extend(&mut rng, &random_choices(100));
+        // Use a different choice bit for the first half of the columns
+        for c in request.columns.iter_mut().take(SECURITY_PARAMETER / 2) {
+            c[0] ^= 1;
+        }
+        assert!(sender.extend(&request).is_err());
+
+        // The sender refuses to continue even with honest requests
+        let (_, request) = receiver.extend(&mut rng, &random_choices(100));
+        assert!(sender.extend(&request).is_err());
+
+        let (mut sender, mut receiver) = setup();
+        let (_, mut request) = receiver.extend(&mut rng, &random_choices(100));
+        request.check_x ^= 1;
+        assert!(sender.extend(&request).is_err());
+
+        let (mut sender, mut receiver) = setup();
+        let (_, mut request) = receiver.extend(&mut rng, &random_choices(100));
+        request.columns.pop();
+        assert!(sender.extend(&request).is_err());
+    }
+}
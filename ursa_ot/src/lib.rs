@@ -0,0 +1,59 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+#![deny(
+    missing_docs,
+    trivial_casts,
+    trivial_numeric_casts,
+    unconditional_recursion,
+    unused_import_braces,
+    unused_lifetimes,
+    unused_qualifications,
+    unused_extern_crates,
+    unused_parens,
+    while_true
+)]
+//! Implements 1-out-of-2 oblivious transfer.
+//!
+//! In an oblivious transfer a sender holds pairs of messages and a receiver
+//! learns exactly one message of each pair according to its choice bits.
+//! The sender learns nothing about the choices and the receiver learns
+//! nothing about the messages it did not choose.
+//!
+//! Base OTs use public key operations and follow Chou and Orlandi
+//! (see <https://eprint.iacr.org/2015/267>) over the Ristretto group.
+//!
+//! Many OTs are then computed from 128 base OTs using only symmetric
+//! operations with the extension by Ishai, Kilian, Nissim and Petrank
+//! (see <https://www.iacr.org/archive/crypto2003/27290145/27290145.pdf>)
+//! and the consistency check by Keller, Orsini and Scholl
+//! (see <https://eprint.iacr.org/2015/546>) which protects the sender
+//! against a malicious receiver.
+//!
+//! Both protocols produce random keys. [`transfer`] uses those keys to send
+//! chosen messages. Every message exchanged between the parties is serializable.
+
+/// Chou-Orlandi base oblivious transfer
+pub mod base;
+/// OT Errors and Results
+pub mod error;
+/// IKNP oblivious transfer extension with the KOS consistency check
+pub mod extension;
+/// Transfer chosen messages using random OT keys
+pub mod transfer;
+
+/// The length of keys output by random OTs
+pub const KEY_SIZE: usize = 32;
+
+/// A key output by a random OT
+pub type OtKey = [u8; KEY_SIZE];
@@ -0,0 +1,125 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::{
+    error::{OtError, OtResult},
+    OtKey,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const PAD_DST: &[u8] = b"ursa_ot_transfer_pad";
+
+/// Message pairs encrypted by the sender under both keys of each OT.
+/// The receiver can only decrypt the message it chose from each pair.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct EncryptedPairs {
+    pairs: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl EncryptedPairs {
+    /// The number of encrypted pairs
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// True if there are no encrypted pairs
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+/// Encrypt each pair of messages with the corresponding pair of sender keys.
+/// Each key must only be used once.
+pub fn encrypt<B: AsRef<[u8]>>(
+    keys: &[(OtKey, OtKey)],
+    messages: &[(B, B)],
+) -> OtResult<EncryptedPairs> {
+    if keys.len() != messages.len() {
+        return Err(OtError::CountMismatch(keys.len(), messages.len()));
+    }
+    let mut pairs = Vec::with_capacity(messages.len());
+    for ((k0, k1), (m0, m1)) in keys.iter().zip(messages.iter()) {
+        let (m0, m1) = (m0.as_ref(), m1.as_ref());
+        if m0.len() != m1.len() {
+            return Err(OtError::MessageLengthMismatch);
+        }
+        pairs.push((apply_pad(k0, m0), apply_pad(k1, m1)));
+    }
+    Ok(EncryptedPairs { pairs })
+}
+
+/// Decrypt the chosen message of each pair with the receiver's keys
+pub fn decrypt(keys: &[OtKey], choices: &[bool], pairs: &EncryptedPairs) -> OtResult<Vec<Vec<u8>>> {
+    if keys.len() != pairs.pairs.len() {
+        return Err(OtError::CountMismatch(keys.len(), pairs.pairs.len()));
+    }
+    if choices.len() != pairs.pairs.len() {
+        return Err(OtError::CountMismatch(choices.len(), pairs.pairs.len()));
+    }
+    Ok(keys
+        .iter()
+        .zip(choices.iter())
+        .zip(pairs.pairs.iter())
+        .map(|((k, c), (e0, e1))| apply_pad(k, if *c { e1 } else { e0 }))
+        .collect())
+}
+
+/// XOR `data` with a pad expanded from `key`
+fn apply_pad(key: &OtKey, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for (i, chunk) in data.chunks(32).enumerate() {
+        let pad = Sha256::new()
+            .chain(PAD_DST)
+            .chain(key)
+            .chain((i as u64).to_be_bytes())
+            .finalize();
+        out.extend(chunk.iter().zip(pad.iter()).map(|(d, p)| d ^ p));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decrypts_only_chosen() {
+        let keys = vec![([1u8; 32], [2u8; 32]), ([3u8; 32], [4u8; 32])];
+        let messages = vec![
+            (b"zero".to_vec(), b"one!".to_vec()),
+            (vec![7u8; 70], vec![9u8; 70]),
+        ];
+        let encrypted = encrypt(&keys, &messages).unwrap();
+        let choices = [true, false];
+        let decrypted = decrypt(&[[2u8; 32], [3u8; 32]], &choices, &encrypted).unwrap();
+        assert_eq!(decrypted[0], b"one!".to_vec());
+        assert_eq!(decrypted[1], vec![7u8; 70]);
+
+        // The wrong key gives garbage
+        let decrypted = decrypt(&[[1u8; 32], [3u8; 32]], &choices, &encrypted).unwrap();
+        assert_ne!(decrypted[0], b"one!".to_vec());
+        assert_ne!(decrypted[0], b"zero".to_vec());
+    }
+
+    #[test]
+    fn invalid_lengths() {
+        let keys = vec![([1u8; 32], [2u8; 32])];
+        assert!(encrypt(&keys, &[(b"a".to_vec(), b"bc".to_vec())]).is_err());
+        let none: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+        assert!(encrypt(&keys, &none).is_err());
+        let encrypted = encrypt(&keys, &[(b"a".to_vec(), b"b".to_vec())]).unwrap();
+        assert!(decrypt(&[[1u8; 32]], &[true, false], &encrypted).is_err());
+        assert!(decrypt(&[], &[], &encrypted).is_err());
+    }
+}
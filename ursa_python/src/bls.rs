@@ -0,0 +1,110 @@
+//! BLS signatures on BLS12-381 with the public keys in G1 and the
+//! signatures in G2, the `normal` variant of `ursa::signatures::bls`.
+//!
+//! Signatures on the same message aggregate into one that verifies against
+//! all of the signers' keys. An attacker can pick a key that cancels out
+//! the others, so only aggregate keys whose owners sent a proof of
+//! possession that passed `bls_verify_possession`.
+
+use super::{bytes, to_py};
+use pyo3::prelude::*;
+use ursa::signatures::bls::normal::{
+    generate, generate_from_seed, AggregatedSignature, ProofOfPossession, PublicKey, Signature,
+};
+use ursa::signatures::bls::PrivateKey;
+
+/// Generate a keypair, derived from `seed` if one is given.
+/// Returns `(public_key, private_key)`.
+#[pyfunction]
+#[pyo3(signature = (seed = None))]
+fn bls_generate(py: Python<'_>, seed: Option<&[u8]>) -> PyResult<(PyObject, PyObject)> {
+    let (pk, sk) = match seed {
+        Some(seed) => generate_from_seed(seed).map_err(to_py)?,
+        None => generate(),
+    };
+    Ok((bytes(py, &pk.to_bytes()), bytes(py, &sk.to_bytes())))
+}
+
+/// The public key of `private_key`
+#[pyfunction]
+fn bls_public_key(py: Python<'_>, private_key: &[u8]) -> PyResult<PyObject> {
+    let sk = PrivateKey::from_bytes(private_key).map_err(to_py)?;
+    Ok(bytes(py, &PublicKey::new(&sk).to_bytes()))
+}
+
+#[pyfunction]
+fn bls_sign(py: Python<'_>, message: &[u8], private_key: &[u8]) -> PyResult<PyObject> {
+    let sk = PrivateKey::from_bytes(private_key).map_err(to_py)?;
+    Ok(bytes(py, &Signature::new(message, None, &sk).to_bytes()))
+}
+
+/// Returns false for a signature that doesn't verify and raises for
+/// malformed keys and signatures
+#[pyfunction]
+fn bls_verify(message: &[u8], signature: &[u8], public_key: &[u8]) -> PyResult<bool> {
+    let signature = Signature::from_bytes(signature).map_err(to_py)?;
+    let pk = PublicKey::from_bytes(public_key).map_err(to_py)?;
+    Ok(signature.verify(message, None, &pk))
+}
+
+/// A proof that the owner of `public_key` knows its private key, sent
+/// along with the key before it is used in an aggregate
+#[pyfunction]
+fn bls_prove_possession(
+    py: Python<'_>,
+    public_key: &[u8],
+    private_key: &[u8],
+) -> PyResult<PyObject> {
+    let pk = PublicKey::from_bytes(public_key).map_err(to_py)?;
+    let sk = PrivateKey::from_bytes(private_key).map_err(to_py)?;
+    let proof = ProofOfPossession::new(&pk, None, &sk);
+    Ok(bytes(py, &proof.to_bytes()))
+}
+
+#[pyfunction]
+fn bls_verify_possession(proof: &[u8], public_key: &[u8]) -> PyResult<bool> {
+    let proof = ProofOfPossession::from_bytes(proof).map_err(to_py)?;
+    let pk = PublicKey::from_bytes(public_key).map_err(to_py)?;
+    Ok(proof.verify(None, &pk))
+}
+
+/// Combine signatures on the same message into one
+#[pyfunction]
+fn bls_aggregate(py: Python<'_>, signatures: Vec<&[u8]>) -> PyResult<PyObject> {
+    let signatures = signatures
+        .into_iter()
+        .map(Signature::from_bytes)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(to_py)?;
+    let aggregated = AggregatedSignature::new(&signatures).map_err(to_py)?;
+    Ok(bytes(py, &aggregated.to_bytes()))
+}
+
+/// Verify a signature from `bls_aggregate` on `message` by the owners of
+/// all of `public_keys`
+#[pyfunction]
+fn bls_verify_aggregate(
+    message: &[u8],
+    signature: &[u8],
+    public_keys: Vec<&[u8]>,
+) -> PyResult<bool> {
+    let signature = AggregatedSignature::from_bytes(signature).map_err(to_py)?;
+    let pks = public_keys
+        .into_iter()
+        .map(PublicKey::from_bytes)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(to_py)?;
+    Ok(signature.verify_no_rk(message, None, &pks))
+}
+
+pub(crate) fn register(m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(bls_generate, m)?)?;
+    m.add_function(wrap_pyfunction!(bls_public_key, m)?)?;
+    m.add_function(wrap_pyfunction!(bls_sign, m)?)?;
+    m.add_function(wrap_pyfunction!(bls_verify, m)?)?;
+    m.add_function(wrap_pyfunction!(bls_prove_possession, m)?)?;
+    m.add_function(wrap_pyfunction!(bls_verify_possession, m)?)?;
+    m.add_function(wrap_pyfunction!(bls_aggregate, m)?)?;
+    m.add_function(wrap_pyfunction!(bls_verify_aggregate, m)?)?;
+    Ok(())
+}
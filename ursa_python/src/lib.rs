@@ -0,0 +1,43 @@
+//! Python bindings for the Ursa BLS signatures and Shamir secret sharing.
+//!
+//! Keys, messages, signatures and shares are `bytes` on the Python side and
+//! errors are raised as `ursa.UrsaError`. Build and install the module into
+//! the active virtualenv with `maturin develop --release`.
+//!
+//! ```python
+//! import ursa
+//!
+//! public_key, private_key = ursa.bls_generate()
+//! signature = ursa.bls_sign(b"message", private_key)
+//! ursa.bls_verify(b"message", signature, public_key)  # True
+//! ```
+#![cfg(feature = "extension-module")]
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+mod bls;
+mod sharing;
+
+create_exception!(ursa, UrsaError, PyException);
+
+#[pymodule]
+#[pyo3(name = "ursa")]
+fn ursa_python(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add("UrsaError", py.get_type::<UrsaError>())?;
+    bls::register(m)?;
+    sharing::register(m)?;
+    Ok(())
+}
+
+/// Convert any error to the exception raised in Python
+pub(crate) fn to_py<E: std::fmt::Display>(e: E) -> PyErr {
+    UrsaError::new_err(e.to_string())
+}
+
+/// Python `bytes`, since a `Vec<u8>` would become a list of ints
+pub(crate) fn bytes(py: Python<'_>, value: &[u8]) -> PyObject {
+    PyBytes::new(py, value).into()
+}
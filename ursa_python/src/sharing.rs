@@ -0,0 +1,48 @@
+//! Shamir secret sharing over a prime field.
+//!
+//! The field is given as the big endian bytes of its prime, which must be
+//! larger than the secret. Each share is `value || identifier`.
+
+use super::{bytes, to_py};
+use pyo3::prelude::*;
+use ursa::bn::BigNumber;
+use ursa::sharing::shamir::{self, Share};
+use ursa::CryptoError;
+
+/// Split `secret` into `total` shares, `threshold` of which recover it
+#[pyfunction]
+fn split_secret(
+    py: Python<'_>,
+    secret: &[u8],
+    threshold: u8,
+    total: u8,
+    prime: &[u8],
+) -> PyResult<Vec<PyObject>> {
+    let field = BigNumber::from_bytes(prime).map_err(to_py)?;
+    let shares = shamir::split_secret(secret, threshold, total, &field).map_err(to_py)?;
+    Ok(shares.iter().map(|s| bytes(py, &s.to_bytes())).collect())
+}
+
+/// Recover the secret from a list of at least `threshold` shares
+#[pyfunction]
+fn combine_shares(py: Python<'_>, shares: Vec<&[u8]>, prime: &[u8]) -> PyResult<PyObject> {
+    let field = BigNumber::from_bytes(prime).map_err(to_py)?;
+    let shares = shares
+        .into_iter()
+        .map(|s| {
+            if s.len() < 2 {
+                return Err(CryptoError::ParseError("Share is too short".to_string()));
+            }
+            Share::from_bytes(s, &field)
+        })
+        .collect::<Result<Vec<Share>, CryptoError>>()
+        .map_err(to_py)?;
+    let secret = shamir::combine_shares(shares, &field).map_err(to_py)?;
+    Ok(bytes(py, &secret))
+}
+
+pub(crate) fn register(m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(split_secret, m)?)?;
+    m.add_function(wrap_pyfunction!(combine_shares, m)?)?;
+    Ok(())
+}
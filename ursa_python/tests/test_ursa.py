@@ -0,0 +1,46 @@
+import pytest
+
+import ursa
+
+MESSAGE = b"This is a test message"
+# 2^127 - 1
+PRIME = bytes([0x7F] + [0xFF] * 15)
+
+
+def test_bls_signatures():
+    public_key, private_key = ursa.bls_generate()
+    assert ursa.bls_public_key(private_key) == public_key
+    signature = ursa.bls_sign(MESSAGE, private_key)
+    assert ursa.bls_verify(MESSAGE, signature, public_key)
+    assert not ursa.bls_verify(b"Another message", signature, public_key)
+
+    # Keys derived from the same seed are the same
+    assert ursa.bls_generate(b"seed" * 8) == ursa.bls_generate(seed=b"seed" * 8)
+
+    with pytest.raises(ursa.UrsaError):
+        ursa.bls_verify(MESSAGE, signature[1:], public_key)
+
+
+def test_bls_aggregation():
+    signers = [ursa.bls_generate() for _ in range(3)]
+    proofs = [ursa.bls_prove_possession(pk, sk) for pk, sk in signers]
+    for proof, (public_key, _) in zip(proofs, signers):
+        assert ursa.bls_verify_possession(proof, public_key)
+    assert not ursa.bls_verify_possession(proofs[0], signers[1][0])
+
+    public_keys = [pk for pk, _ in signers]
+    signature = ursa.bls_aggregate([ursa.bls_sign(MESSAGE, sk) for _, sk in signers])
+    assert ursa.bls_verify_aggregate(MESSAGE, signature, public_keys)
+    assert not ursa.bls_verify_aggregate(MESSAGE, signature, public_keys[1:])
+
+    with pytest.raises(ursa.UrsaError):
+        ursa.bls_aggregate([])
+
+
+def test_sharing():
+    shares = ursa.split_secret(b"secret", 3, 5, PRIME)
+    assert len(shares) == 5
+    assert ursa.combine_shares(shares[2:], PRIME) == b"secret"
+
+    with pytest.raises(ursa.UrsaError):
+        ursa.combine_shares([b"\x01"], PRIME)
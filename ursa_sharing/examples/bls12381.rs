@@ -24,6 +24,7 @@ use pairing_plus::{
     CurveProjective,
 };
 use rand::{CryptoRng, RngCore};
+use sha2::Sha256;
 use ursa_sharing::{error::*, tests::*, Field, Group};
 
 struct FrField(Fr);
@@ -228,6 +229,8 @@ impl Group<FrField> for G2Field {
 fn main() {
     println!("Splitting");
     split_invalid_args::<FrField>();
+    split_error_codes();
+    println!("Split error codes success");
     println!("Combine invalid fail");
     combine_invalid::<FrField>();
     println!("Combine single G1 success");
@@ -238,4 +241,48 @@ fn main() {
     combine_single::<FrField, G2Field>();
     println!("Combine combinations G2 success");
     combine_all_combinations::<FrField, G2Field>();
+    dleq_prove_verify::<FrField, G1Field, Sha256>();
+    println!("DLEQ proof G1 success");
+    cbor_envelopes::<FrField, G1Field>();
+    println!("CBOR envelopes G1 success");
+    dkg_ceremony::<FrField, G1Field>();
+    println!("DKG ceremony G1 success");
+    #[cfg(feature = "backup")]
+    {
+        backup_bundle::<FrField, G1Field, Sha256>();
+        println!("Backup bundle G1 success");
+    }
+    verify_batches::<FrField, G1Field>();
+    println!("Batch share verification G1 success");
+    #[cfg(feature = "protobuf")]
+    {
+        protobuf_messages::<FrField, G1Field>();
+        println!("Protobuf messages G1 success");
+    }
+    dleq_batch::<FrField, G1Field, Sha256>();
+    println!("DLEQ batch proof G1 success");
+    dleq_transcript::<FrField, G1Field>();
+    println!("DLEQ transcript proof G1 success");
+    dleq_prove_verify::<FrField, G2Field, Sha256>();
+    println!("DLEQ proof G2 success");
+    cbor_envelopes::<FrField, G2Field>();
+    println!("CBOR envelopes G2 success");
+    dkg_ceremony::<FrField, G2Field>();
+    println!("DKG ceremony G2 success");
+    #[cfg(feature = "backup")]
+    {
+        backup_bundle::<FrField, G2Field, Sha256>();
+        println!("Backup bundle G2 success");
+    }
+    verify_batches::<FrField, G2Field>();
+    println!("Batch share verification G2 success");
+    #[cfg(feature = "protobuf")]
+    {
+        protobuf_messages::<FrField, G2Field>();
+        println!("Protobuf messages G2 success");
+    }
+    dleq_batch::<FrField, G2Field, Sha256>();
+    println!("DLEQ batch proof G2 success");
+    dleq_transcript::<FrField, G2Field>();
+    println!("DLEQ transcript proof G2 success");
 }
@@ -11,6 +11,7 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use sha2::Sha256;
 use ursa_sharing::{error::*, tests::*, Field, Group};
 
 use generic_array::{typenum::U384, GenericArray};
@@ -114,10 +115,34 @@ impl Group for Bn3072 {
 fn main() {
     println!("Splitting");
     split_invalid_args::<Bn3072>();
+    split_error_codes();
+    println!("Split error codes success");
     println!("Combine invalid fail");
     combine_invalid::<Bn3072>();
     println!("Combine single success");
     combine_single::<Bn3072, Bn3072>();
     println!("Combine combinations success");
     combine_all_combinations::<Bn3072, Bn3072>();
+    dleq_prove_verify::<Bn3072, Bn3072, Sha256>();
+    println!("DLEQ proof success");
+    cbor_envelopes::<Bn3072, Bn3072>();
+    println!("CBOR envelopes success");
+    dkg_ceremony::<Bn3072, Bn3072>();
+    println!("DKG ceremony success");
+    #[cfg(feature = "backup")]
+    {
+        backup_bundle::<Bn3072, Bn3072, Sha256>();
+        println!("Backup bundle success");
+    }
+    verify_batches::<Bn3072, Bn3072>();
+    println!("Batch share verification success");
+    #[cfg(feature = "protobuf")]
+    {
+        protobuf_messages::<Bn3072, Bn3072>();
+        println!("Protobuf messages success");
+    }
+    dleq_batch::<Bn3072, Bn3072, Sha256>();
+    println!("DLEQ batch proof success");
+    dleq_transcript::<Bn3072, Bn3072>();
+    println!("DLEQ transcript proof success");
 }
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 use rand::{CryptoRng, RngCore};
+use sha2::Sha256;
 use ursa_sharing::{error::*, tests::*, Field, Group};
 
 use curve25519_dalek::{
@@ -155,10 +156,34 @@ impl Group<C25519Scalar> for C25519Point {
 fn main() {
     println!("Splitting");
     split_invalid_args::<C25519Scalar>();
+    split_error_codes();
+    println!("Split error codes success");
     println!("Combine invalid fail");
     combine_invalid::<C25519Scalar>();
     println!("Combine single success");
     combine_single::<C25519Scalar, C25519Point>();
     println!("Combine combinations success");
     combine_all_combinations::<C25519Scalar, C25519Point>();
+    dleq_prove_verify::<C25519Scalar, C25519Point, Sha256>();
+    println!("DLEQ proof success");
+    cbor_envelopes::<C25519Scalar, C25519Point>();
+    println!("CBOR envelopes success");
+    dkg_ceremony::<C25519Scalar, C25519Point>();
+    println!("DKG ceremony success");
+    #[cfg(feature = "backup")]
+    {
+        backup_bundle::<C25519Scalar, C25519Point, Sha256>();
+        println!("Backup bundle success");
+    }
+    verify_batches::<C25519Scalar, C25519Point>();
+    println!("Batch share verification success");
+    #[cfg(feature = "protobuf")]
+    {
+        protobuf_messages::<C25519Scalar, C25519Point>();
+        println!("Protobuf messages success");
+    }
+    dleq_batch::<C25519Scalar, C25519Point, Sha256>();
+    println!("DLEQ batch proof success");
+    dleq_transcript::<C25519Scalar, C25519Point>();
+    println!("DLEQ transcript proof success");
 }
@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 use ff::Field as FFField;
-use generic_array::{typenum::U32, GenericArray};
+use generic_array::{
+    typenum::{U32, U33},
+    GenericArray,
+};
 use k256::{
     elliptic_curve::{
         sec1::{FromEncodedPoint, ToEncodedPoint},
@@ -21,6 +24,7 @@ use k256::{
     EncodedPoint, FieldBytes, ProjectivePoint, Scalar,
 };
 use rand::{CryptoRng, RngCore};
+use sha2::Sha256;
 use ursa_sharing::{error::*, tests::*, Field, Group};
 
 struct K256Scalar(Scalar);
@@ -95,13 +99,16 @@ impl Group for K256Scalar {
 struct K256Point(ProjectivePoint);
 
 impl Group<K256Scalar> for K256Point {
-    type Size = U32;
+    type Size = U33;
 
     fn zero() -> Self {
         Self(ProjectivePoint::identity())
     }
 
     fn from_bytes<B: AsRef<[u8]>>(value: B) -> SharingResult<Self> {
+        if value.as_ref().iter().all(|b| *b == 0) {
+            return Ok(Self::zero());
+        }
         match EncodedPoint::from_bytes(value.as_ref()) {
             Ok(ept) => {
                 let ppt = ProjectivePoint::from_encoded_point(&ept);
@@ -144,25 +151,45 @@ impl Group<K256Scalar> for K256Point {
     }
 
     fn to_bytes(&self) -> GenericArray<u8, Self::Size> {
-        let mut c = [0u8; 32];
-        c.copy_from_slice(
-            self.0
-                .to_affine()
-                .to_encoded_point(true)
-                .to_bytes()
-                .as_ref(),
-        );
-        c.into()
+        // SEC1 compressed, with the identity's single zero byte padded with zeros
+        let mut c = GenericArray::default();
+        let bytes = self.0.to_affine().to_encoded_point(true).to_bytes();
+        c[..bytes.len()].copy_from_slice(bytes.as_ref());
+        c
     }
 }
 
 fn main() {
     println!("Splitting");
     split_invalid_args::<K256Scalar>();
+    split_error_codes();
+    println!("Split error codes success");
     println!("Combine invalid fail");
     combine_invalid::<K256Scalar>();
     println!("Combine single success");
     combine_single::<K256Scalar, K256Point>();
     println!("Combine combinations success");
     combine_all_combinations::<K256Scalar, K256Point>();
+    dleq_prove_verify::<K256Scalar, K256Point, Sha256>();
+    println!("DLEQ proof success");
+    cbor_envelopes::<K256Scalar, K256Point>();
+    println!("CBOR envelopes success");
+    dkg_ceremony::<K256Scalar, K256Point>();
+    println!("DKG ceremony success");
+    #[cfg(feature = "backup")]
+    {
+        backup_bundle::<K256Scalar, K256Point, Sha256>();
+        println!("Backup bundle success");
+    }
+    verify_batches::<K256Scalar, K256Point>();
+    println!("Batch share verification success");
+    #[cfg(feature = "protobuf")]
+    {
+        protobuf_messages::<K256Scalar, K256Point>();
+        println!("Protobuf messages success");
+    }
+    dleq_batch::<K256Scalar, K256Point, Sha256>();
+    println!("DLEQ batch proof success");
+    dleq_transcript::<K256Scalar, K256Point>();
+    println!("DLEQ transcript proof success");
 }
@@ -11,8 +11,12 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use generic_array::{typenum::U32, GenericArray};
+use generic_array::{
+    typenum::{U32, U33},
+    GenericArray,
+};
 use rand::{CryptoRng, RngCore};
+use sha2::Sha256;
 use ursa_sharing::{error::*, tests::*, Field, Group};
 
 use ff::Field as FFField;
@@ -97,13 +101,16 @@ impl Field for P256Scalar {
 struct P256Point(ProjectivePoint);
 
 impl Group<P256Scalar> for P256Point {
-    type Size = U32;
+    type Size = U33;
 
     fn zero() -> Self {
         Self(ProjectivePoint::identity())
     }
 
     fn from_bytes<B: AsRef<[u8]>>(value: B) -> SharingResult<Self> {
+        if value.as_ref().iter().all(|b| *b == 0) {
+            return Ok(Self::zero());
+        }
         match EncodedPoint::from_bytes(value.as_ref()) {
             Ok(ept) => {
                 let apt = AffinePoint::from_encoded_point(&ept);
@@ -146,26 +153,46 @@ impl Group<P256Scalar> for P256Point {
         self.0 *= rhs.0;
     }
 
-    fn to_bytes(&self) -> GenericArray<u8, U32> {
-        let mut c = [0u8; 32];
-        c.copy_from_slice(
-            self.0
-                .to_affine()
-                .to_encoded_point(true)
-                .to_bytes()
-                .as_ref(),
-        );
-        c.into()
+    fn to_bytes(&self) -> GenericArray<u8, U33> {
+        // SEC1 compressed, with the identity's single zero byte padded with zeros
+        let mut c = GenericArray::default();
+        let bytes = self.0.to_affine().to_encoded_point(true).to_bytes();
+        c[..bytes.len()].copy_from_slice(bytes.as_ref());
+        c
     }
 }
 
 fn main() {
     println!("Splitting");
     split_invalid_args::<P256Scalar>();
+    split_error_codes();
+    println!("Split error codes success");
     println!("Combine invalid fail");
     combine_invalid::<P256Scalar>();
     println!("Combine single success");
     combine_single::<P256Scalar, P256Point>();
     println!("Combine combinations success");
     combine_all_combinations::<P256Scalar, P256Point>();
+    dleq_prove_verify::<P256Scalar, P256Point, Sha256>();
+    println!("DLEQ proof success");
+    cbor_envelopes::<P256Scalar, P256Point>();
+    println!("CBOR envelopes success");
+    dkg_ceremony::<P256Scalar, P256Point>();
+    println!("DKG ceremony success");
+    #[cfg(feature = "backup")]
+    {
+        backup_bundle::<P256Scalar, P256Point, Sha256>();
+        println!("Backup bundle success");
+    }
+    verify_batches::<P256Scalar, P256Point>();
+    println!("Batch share verification success");
+    #[cfg(feature = "protobuf")]
+    {
+        protobuf_messages::<P256Scalar, P256Point>();
+        println!("Protobuf messages success");
+    }
+    dleq_batch::<P256Scalar, P256Point, Sha256>();
+    println!("DLEQ batch proof success");
+    dleq_transcript::<P256Scalar, P256Point>();
+    println!("DLEQ transcript proof success");
 }
@@ -1,7 +1,7 @@
 #!/bin/bash
 
-cargo run --features=impl_tests --example curve25519
-cargo run --features=impl_tests --example bn3072
-cargo run --features=impl_tests --example k256
-cargo run --features=impl_tests --example p256
-cargo run --features=impl_tests --example bls12381
+cargo run --features=impl_tests,backup --example curve25519
+cargo run --features=impl_tests,backup --example bn3072
+cargo run --features=impl_tests,backup --example k256
+cargo run --features=impl_tests,backup --example p256
+cargo run --features=impl_tests,backup --example bls12381
@@ -0,0 +1,344 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! A [`BackupBundle`] holds everything needed to back up a secret with a
+//! set of custodians: the secret is split with Feldman's scheme, each share
+//! is encrypted to the public key of one custodian and the bundle carries
+//! the Feldman commitments and a manifest hash over all of it.
+//!
+//! The bundle is public. Anyone can check its manifest with
+//! [`verify_bundle`], each custodian decrypts and checks only its own share
+//! with [`BackupBundle::unwrap_share`], and any `threshold` of those shares
+//! [`restore`] the secret.
+//!
+//! A custodian key is a secret exponent `x` and the public element `g^x`.
+//! Shares are encrypted with XChaCha20Poly1305 under a key derived from an
+//! ephemeral Diffie-Hellman exchange in the same group.
+
+use super::{
+    error::{SharingError, SharingResult},
+    feldman::{FeldmanVerifier, Scheme as FeldmanVss},
+    shamir::Share,
+    Field, Group,
+};
+use chacha20poly1305::{
+    aead::{Aead, NewAead, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use digest::Digest;
+use rand::{CryptoRng, RngCore};
+use std::{convert::TryFrom, marker::PhantomData};
+use subtle::ConstantTimeEq;
+use ursa_core::{
+    cbor::{Reader, Writer},
+    CborError, CborSerializable,
+};
+use zeroize::Zeroize;
+
+const KEY_DST: &[u8] = b"ursa_sharing_backup_key";
+const MANIFEST_DST: &[u8] = b"ursa_sharing_backup_manifest";
+const NONCE_SIZE: usize = 24;
+
+/// A share encrypted to one custodian
+#[derive(Debug)]
+pub struct WrappedShare<S: Field, R: Group<S>> {
+    /// The identifier of the share
+    pub identifier: u32,
+    /// The public key of the custodian
+    pub custodian: R,
+    /// The ephemeral public element of the key exchange
+    pub ephemeral: R,
+    /// The nonce followed by the encrypted share value
+    pub ciphertext: Vec<u8>,
+    phantom: PhantomData<S>,
+}
+
+/// A secret split among custodians, see the module documentation
+#[derive(Debug)]
+pub struct BackupBundle<S: Field, R: Group<S>> {
+    threshold: usize,
+    verifier: FeldmanVerifier<S, R>,
+    shares: Vec<WrappedShare<S, R>>,
+    manifest: Vec<u8>,
+}
+
+/// Create a custodian key pair `(x, g^x)`
+pub fn custodian_keypair<S: Field, R: Group<S>>(
+    rng: &mut (impl RngCore + CryptoRng),
+    g: &R,
+) -> (S, R) {
+    let x = S::random(rng);
+    let mut public = R::zero();
+    public.add_assign(g);
+    public.scalar_mul_assign(&x);
+    (x, public)
+}
+
+/// Split `secret` so any `threshold` of the `custodians` can restore it.
+/// Share `i` is encrypted to `custodians[i - 1]`, and `g` is both the
+/// generator of the custodian keys and of the Feldman commitments.
+/// The manifest is hashed with `D`.
+pub fn create_backup<S: Field, R: Group<S>, D: Digest>(
+    rng: &mut (impl RngCore + CryptoRng),
+    secret: &S,
+    threshold: usize,
+    g: &R,
+    custodians: &[R],
+) -> SharingResult<BackupBundle<S, R>> {
+    let (verifier, shares) = FeldmanVss::new(threshold, custodians.len())?.split_secret::<S, R>(
+        rng,
+        secret,
+        Some(R::from_bytes(g.to_bytes())?),
+    )?;
+    let context = verifier.to_bytes();
+
+    let mut wrapped = Vec::with_capacity(shares.len());
+    for (share, custodian) in shares.iter().zip(custodians) {
+        let r = S::random(rng);
+        let mut ephemeral = R::zero();
+        ephemeral.add_assign(g);
+        ephemeral.scalar_mul_assign(&r);
+        let mut shared = R::zero();
+        shared.add_assign(custodian);
+        shared.scalar_mul_assign(&r);
+
+        let mut nonce = [0u8; NONCE_SIZE];
+        rng.fill_bytes(&mut nonce);
+        let cipher = cipher::<S, R, D>(&shared, &ephemeral, custodian);
+        let mut ciphertext = nonce.to_vec();
+        ciphertext.append(
+            &mut cipher
+                .encrypt(
+                    XNonce::from_slice(&nonce),
+                    Payload {
+                        msg: share.value(),
+                        aad: &aad(share.identifier(), &context),
+                    },
+                )
+                .map_err(|_| SharingError::ShareInvalidValue)?,
+        );
+        wrapped.push(WrappedShare {
+            identifier: share.identifier(),
+            custodian: R::from_bytes(custodian.to_bytes())?,
+            ephemeral,
+            ciphertext,
+            phantom: PhantomData,
+        });
+    }
+
+    let manifest = manifest::<S, R, D>(threshold, &verifier, &wrapped);
+    Ok(BackupBundle {
+        threshold,
+        verifier,
+        shares: wrapped,
+        manifest,
+    })
+}
+
+/// Check the manifest hash and the structure of `bundle`
+pub fn verify_bundle<S: Field, R: Group<S>, D: Digest>(
+    bundle: &BackupBundle<S, R>,
+) -> SharingResult<()> {
+    FeldmanVss::new(bundle.threshold, bundle.shares.len())?;
+    if bundle.verifier.commitments.len() != bundle.threshold {
+        return Err(SharingError::ShareInvalidValue);
+    }
+    for (i, share) in bundle.shares.iter().enumerate() {
+        if share.identifier as usize != i + 1 || share.ciphertext.len() < NONCE_SIZE {
+            return Err(SharingError::ShareInvalidIdentifier);
+        }
+    }
+    let manifest = manifest::<S, R, D>(bundle.threshold, &bundle.verifier, &bundle.shares);
+    if bool::from(manifest.ct_eq(&bundle.manifest)) {
+        Ok(())
+    } else {
+        Err(SharingError::BackupManifestMismatch)
+    }
+}
+
+/// Check the shares returned by the custodians against the commitments of
+/// `bundle` and combine them into the secret
+pub fn restore<S: Field, R: Group<S>>(
+    bundle: &BackupBundle<S, R>,
+    shares: &[Share],
+) -> SharingResult<S> {
+    let scheme = FeldmanVss::new(bundle.threshold, bundle.shares.len())?;
+    for share in shares {
+        scheme.verify_share(share, &bundle.verifier)?;
+    }
+    scheme.combine_shares::<S, S>(shares)
+}
+
+impl<S: Field, R: Group<S>> BackupBundle<S, R> {
+    /// The number of shares needed to restore the secret
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// The Feldman commitments to the shares
+    pub fn verifier(&self) -> &FeldmanVerifier<S, R> {
+        &self.verifier
+    }
+
+    /// The encrypted shares, in order of identifier
+    pub fn shares(&self) -> &[WrappedShare<S, R>] {
+        &self.shares
+    }
+
+    /// The hash over the whole bundle, to compare with a copy kept elsewhere
+    pub fn manifest(&self) -> &[u8] {
+        &self.manifest
+    }
+
+    /// Decrypt share `identifier` with the custodian's secret `x` and check
+    /// it against the commitments
+    pub fn unwrap_share<D: Digest>(&self, identifier: u32, x: &S) -> SharingResult<Share> {
+        let wrapped = self
+            .shares
+            .iter()
+            .find(|s| s.identifier == identifier)
+            .ok_or(SharingError::ShareInvalidIdentifier)?;
+        if wrapped.ciphertext.len() < NONCE_SIZE {
+            return Err(SharingError::BackupShareDecryption(identifier));
+        }
+        let mut shared = R::zero();
+        shared.add_assign(&wrapped.ephemeral);
+        shared.scalar_mul_assign(x);
+        let (nonce, ciphertext) = wrapped.ciphertext.split_at(NONCE_SIZE);
+        let mut value = cipher::<S, R, D>(&shared, &wrapped.ephemeral, &wrapped.custodian)
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &aad(identifier, &self.verifier.to_bytes()),
+                },
+            )
+            .map_err(|_| SharingError::BackupShareDecryption(identifier))?;
+        let share = Share::new(identifier as usize, &value);
+        value.zeroize();
+        FeldmanVss::new(self.threshold, self.shares.len())?.verify_share(&share, &self.verifier)?;
+        Ok(share)
+    }
+}
+
+impl<S: Field, R: Group<S>> CborSerializable for BackupBundle<S, R> {
+    const CBOR_ID: &'static str = "feldman/backup-bundle";
+
+    fn to_cbor_payload(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.array(4);
+        writer.unsigned(self.threshold as u64);
+        writer.bytes(&self.verifier.to_bytes());
+        writer.array(self.shares.len());
+        for share in &self.shares {
+            writer.array(4);
+            writer.unsigned(u64::from(share.identifier));
+            writer.bytes(&share.custodian.to_bytes());
+            writer.bytes(&share.ephemeral.to_bytes());
+            writer.bytes(&share.ciphertext);
+        }
+        writer.bytes(&self.manifest);
+        writer.into_bytes()
+    }
+
+    fn from_cbor_payload(_version: u32, payload: &[u8]) -> Result<Self, CborError> {
+        let invalid = |e: SharingError| CborError::InvalidPayload(e.to_string());
+        let mut reader = Reader::new(payload);
+        if reader.array()? != 4 {
+            return Err(CborError::InvalidPayload("expected 4 fields".to_string()));
+        }
+        let threshold = reader.unsigned()? as usize;
+        let verifier = FeldmanVerifier::try_from(reader.bytes()?).map_err(invalid)?;
+        let count = reader.array()?;
+        let mut shares = Vec::with_capacity(count);
+        for _ in 0..count {
+            if reader.array()? != 4 {
+                return Err(CborError::InvalidPayload(
+                    "expected 4 fields in a share".to_string(),
+                ));
+            }
+            let identifier = u32::try_from(reader.unsigned()?)
+                .map_err(|_| invalid(SharingError::ShareInvalidIdentifier))?;
+            shares.push(WrappedShare {
+                identifier,
+                custodian: R::from_bytes(reader.bytes()?).map_err(invalid)?,
+                ephemeral: R::from_bytes(reader.bytes()?).map_err(invalid)?,
+                ciphertext: reader.bytes()?.to_vec(),
+                phantom: PhantomData,
+            });
+        }
+        let manifest = reader.bytes()?.to_vec();
+        reader.finish()?;
+        Ok(Self {
+            threshold,
+            verifier,
+            shares,
+            manifest,
+        })
+    }
+}
+
+/// The cipher keyed by the Diffie-Hellman result, expanded to 32 bytes for
+/// digests shorter than that
+fn cipher<S: Field, R: Group<S>, D: Digest>(
+    shared: &R,
+    ephemeral: &R,
+    custodian: &R,
+) -> XChaCha20Poly1305 {
+    let mut key = Vec::with_capacity(32);
+    let mut counter = 0u8;
+    while key.len() < 32 {
+        let block = D::new()
+            .chain(KEY_DST)
+            .chain([counter])
+            .chain(shared.to_bytes())
+            .chain(ephemeral.to_bytes())
+            .chain(custodian.to_bytes())
+            .result();
+        key.extend_from_slice(&block);
+        counter += 1;
+    }
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key[..32]));
+    key.zeroize();
+    cipher
+}
+
+/// Binds a ciphertext to its share identifier and to this sharing
+fn aad(identifier: u32, context: &[u8]) -> Vec<u8> {
+    let mut aad = identifier.to_be_bytes().to_vec();
+    aad.extend_from_slice(context);
+    aad
+}
+
+fn manifest<S: Field, R: Group<S>, D: Digest>(
+    threshold: usize,
+    verifier: &FeldmanVerifier<S, R>,
+    shares: &[WrappedShare<S, R>],
+) -> Vec<u8> {
+    let verifier = verifier.to_bytes();
+    let mut hasher = D::new()
+        .chain(MANIFEST_DST)
+        .chain((threshold as u32).to_be_bytes())
+        .chain((verifier.len() as u32).to_be_bytes())
+        .chain(&verifier)
+        .chain((shares.len() as u32).to_be_bytes());
+    for share in shares {
+        hasher = hasher
+            .chain(share.identifier.to_be_bytes())
+            .chain(share.custodian.to_bytes())
+            .chain(share.ephemeral.to_bytes())
+            .chain((share.ciphertext.len() as u32).to_be_bytes())
+            .chain(&share.ciphertext);
+    }
+    hasher.result().to_vec()
+}
@@ -0,0 +1,617 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Each of `limit` participants deals a random secret with Pedersen's
+//! scheme and every participant ends up with the sum of the shares dealt to
+//! it by the qualified dealers, a share of a secret no participant knows.
+//!
+//! A [`Participant`] is a state machine over the [`Round`]s of the
+//! ceremony. Its whole state, including the secret shares, round-trips
+//! through [`CborSerializable`] so a node can persist it after every
+//! message and resume after a crash. Stored states are secret and should be
+//! encrypted at rest.
+//!
+//! A finished ceremony produces a [`Transcript`] of the public commitments
+//! and the qualified dealers, which anyone can check later without having
+//! taken part.
+
+use super::{
+    error::{SharingError, SharingResult},
+    pedersen::{PedersenVerifier, Scheme as PedersenVss},
+    shamir::Share,
+    Field, Group, ShareVerifier,
+};
+use rand::{CryptoRng, RngCore};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    marker::PhantomData,
+};
+use ursa_core::{
+    cbor::{Reader, Writer},
+    CborError, CborSerializable,
+};
+
+/// The rounds of a DKG ceremony
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Round {
+    /// The participant hasn't dealt its own secret yet
+    Dealing,
+    /// Collecting the commitments and shares of the other dealers
+    Collecting,
+    /// The shares are combined and the transcript is final
+    Complete,
+}
+
+impl Round {
+    fn to_u64(self) -> u64 {
+        match self {
+            Round::Dealing => 0,
+            Round::Collecting => 1,
+            Round::Complete => 2,
+        }
+    }
+
+    fn from_u64(value: u64) -> Result<Self, CborError> {
+        match value {
+            0 => Ok(Round::Dealing),
+            1 => Ok(Round::Collecting),
+            2 => Ok(Round::Complete),
+            _ => Err(invalid("unknown round")),
+        }
+    }
+}
+
+/// The messages a dealer sends: the commitment for everyone and the
+/// secret and blinding shares for each participant
+#[derive(Debug)]
+pub struct Dealing<S: Field, R: Group<S>> {
+    /// The identifier of the dealer
+    pub dealer: u32,
+    /// Broadcast to every participant
+    pub verifier: PedersenVerifier<S, R>,
+    /// The secret and blinding shares, sent privately to the participant
+    /// with their identifier
+    pub shares: Vec<(Share, Share)>,
+}
+
+/// One participant of a Pedersen DKG.
+///
+/// Messages may arrive in any order and may be replayed, e.g. after
+/// resuming from a stored state: an identical message is ignored, a
+/// different one from the same dealer is an error.
+#[derive(Debug)]
+pub struct Participant<S: Field, R: Group<S>> {
+    id: u32,
+    threshold: usize,
+    limit: usize,
+    g: R,
+    h: R,
+    round: Round,
+    dealing: Option<Dealing<S, R>>,
+    commitments: BTreeMap<u32, PedersenVerifier<S, R>>,
+    shares: BTreeMap<u32, (Share, Share)>,
+    disqualified: BTreeSet<u32>,
+    qualified: BTreeSet<u32>,
+    output: Option<(Share, Share)>,
+}
+
+impl<S: Field, R: Group<S>> Participant<S, R> {
+    /// Participant `id` of `1..=limit`. Every participant must use the same
+    /// generators `g` and `h`.
+    pub fn new(id: u32, threshold: usize, limit: usize, g: R, h: R) -> SharingResult<Self> {
+        PedersenVss::new(threshold, limit)?;
+        if id == 0 || id as usize > limit {
+            return Err(SharingError::ShareInvalidIdentifier);
+        }
+        Ok(Self {
+            id,
+            threshold,
+            limit,
+            g,
+            h,
+            round: Round::Dealing,
+            dealing: None,
+            commitments: BTreeMap::new(),
+            shares: BTreeMap::new(),
+            disqualified: BTreeSet::new(),
+            qualified: BTreeSet::new(),
+            output: None,
+        })
+    }
+
+    /// The identifier of this participant
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// The current round
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
+    /// Deal a random secret. The returned messages are kept, see `dealing`.
+    pub fn deal(&mut self, rng: &mut (impl RngCore + CryptoRng)) -> SharingResult<&Dealing<S, R>> {
+        if self.round != Round::Dealing {
+            return Err(SharingError::DkgUnexpectedRound);
+        }
+        let secret = S::random(rng);
+        let res = PedersenVss::new(self.threshold, self.limit)?.split_secret(
+            rng,
+            &secret,
+            Some(copy_point(&self.g)?),
+            Some(copy_point(&self.h)?),
+        )?;
+        let shares = res
+            .secret_shares
+            .iter()
+            .cloned()
+            .zip(res.blinding_shares.iter().cloned())
+            .collect::<Vec<_>>();
+        // A participant is one of its own dealers
+        let own = &shares[self.id as usize - 1];
+        self.shares.insert(self.id, (own.0.clone(), own.1.clone()));
+        self.commitments
+            .insert(self.id, copy_verifier(&res.verifier)?);
+        self.dealing = Some(Dealing {
+            dealer: self.id,
+            verifier: copy_verifier(&res.verifier)?,
+            shares,
+        });
+        self.round = Round::Collecting;
+        Ok(self.dealing.as_ref().unwrap())
+    }
+
+    /// The messages dealt by this participant, to send again after resuming
+    pub fn dealing(&self) -> Option<&Dealing<S, R>> {
+        self.dealing.as_ref()
+    }
+
+    /// Accept the commitment broadcast by `dealer`
+    pub fn receive_commitment(
+        &mut self,
+        dealer: u32,
+        verifier: PedersenVerifier<S, R>,
+    ) -> SharingResult<()> {
+        self.check_dealer(dealer)?;
+        if let Some(existing) = self.commitments.get(&dealer) {
+            return if existing.to_bytes() == verifier.to_bytes() {
+                Ok(())
+            } else {
+                Err(SharingError::DkgConflictingMessage(dealer))
+            };
+        }
+        if verifier.g.to_bytes() != self.g.to_bytes()
+            || verifier.h.to_bytes() != self.h.to_bytes()
+            || verifier.commitments.len() != self.threshold
+        {
+            self.disqualified.insert(dealer);
+            return Err(SharingError::ShareInvalidValue);
+        }
+        self.commitments.insert(dealer, verifier);
+        self.check_shares(dealer)
+    }
+
+    /// Accept the shares `dealer` sent to this participant
+    pub fn receive_shares(
+        &mut self,
+        dealer: u32,
+        secret_share: Share,
+        blinding_share: Share,
+    ) -> SharingResult<()> {
+        self.check_dealer(dealer)?;
+        if secret_share.identifier != self.id || blinding_share.identifier != self.id {
+            return Err(SharingError::ShareInvalidIdentifier);
+        }
+        if let Some((s, b)) = self.shares.get(&dealer) {
+            return if *s == secret_share && *b == blinding_share {
+                Ok(())
+            } else {
+                Err(SharingError::DkgConflictingMessage(dealer))
+            };
+        }
+        self.shares.insert(dealer, (secret_share, blinding_share));
+        self.check_shares(dealer)
+    }
+
+    /// Disqualify `dealer` after another participant complained about it
+    pub fn receive_complaint(&mut self, dealer: u32) -> SharingResult<()> {
+        self.check_dealer(dealer)?;
+        self.disqualified.insert(dealer);
+        Ok(())
+    }
+
+    /// The dealers disqualified so far, by complaints or because their
+    /// messages didn't verify
+    pub fn disqualified(&self) -> impl Iterator<Item = u32> + '_ {
+        self.disqualified.iter().copied()
+    }
+
+    /// Combine the shares of every dealer that isn't disqualified and whose
+    /// messages have arrived, into this participant's secret and blinding
+    /// shares. Fails unless at least `threshold` dealers qualify.
+    pub fn finish(&mut self) -> SharingResult<(&Share, &Share)> {
+        if self.round != Round::Collecting {
+            return Err(SharingError::DkgUnexpectedRound);
+        }
+        let qualified = self
+            .commitments
+            .keys()
+            .filter(|d| self.shares.contains_key(d) && !self.disqualified.contains(d))
+            .copied()
+            .collect::<BTreeSet<_>>();
+        if qualified.len() < self.threshold {
+            return Err(SharingError::DkgNotEnoughDealers(
+                self.threshold,
+                qualified.len(),
+            ));
+        }
+
+        let mut secret = S::zero();
+        let mut blinding = S::zero();
+        for (s, b) in qualified.iter().map(|d| &self.shares[d]) {
+            secret.add_assign(&S::from_bytes(s.value())?);
+            blinding.add_assign(&S::from_bytes(b.value())?);
+        }
+        self.output = Some((
+            Share::new(self.id as usize, secret.to_bytes()),
+            Share::new(self.id as usize, blinding.to_bytes()),
+        ));
+        self.qualified = qualified;
+        self.round = Round::Complete;
+        let (s, b) = self.output.as_ref().unwrap();
+        Ok((s, b))
+    }
+
+    /// The combined secret and blinding shares once complete
+    pub fn output(&self) -> Option<(&Share, &Share)> {
+        self.output.as_ref().map(|(s, b)| (s, b))
+    }
+
+    /// The public record of the ceremony once complete
+    pub fn transcript(&self) -> SharingResult<Transcript<S, R>> {
+        if self.round != Round::Complete {
+            return Err(SharingError::DkgUnexpectedRound);
+        }
+        let mut commitments = BTreeMap::new();
+        for dealer in &self.qualified {
+            commitments.insert(*dealer, copy_verifier(&self.commitments[dealer])?);
+        }
+        Ok(Transcript {
+            threshold: self.threshold,
+            limit: self.limit,
+            commitments,
+            disqualified: self.disqualified.clone(),
+        })
+    }
+
+    fn check_dealer(&self, dealer: u32) -> SharingResult<()> {
+        if self.round == Round::Complete {
+            return Err(SharingError::DkgUnexpectedRound);
+        }
+        if dealer == 0 || dealer == self.id || dealer as usize > self.limit {
+            return Err(SharingError::DkgUnknownDealer(dealer));
+        }
+        Ok(())
+    }
+
+    /// Verify the shares of `dealer` once both of its messages are in
+    fn check_shares(&mut self, dealer: u32) -> SharingResult<()> {
+        let (verifier, (s, b)) = match (self.commitments.get(&dealer), self.shares.get(&dealer)) {
+            (Some(verifier), Some(shares)) => (verifier, shares),
+            _ => return Ok(()),
+        };
+        let result = PedersenVss::new(self.threshold, self.limit)?.verify_share(s, b, verifier);
+        if result.is_err() {
+            self.disqualified.insert(dealer);
+        }
+        result
+    }
+}
+
+impl<S: Field, R: Group<S>> CborSerializable for Participant<S, R> {
+    const CBOR_ID: &'static str = "pedersen/dkg-participant";
+
+    fn to_cbor_payload(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.array(12);
+        writer.unsigned(u64::from(self.id));
+        writer.unsigned(self.threshold as u64);
+        writer.unsigned(self.limit as u64);
+        writer.unsigned(self.round.to_u64());
+        writer.bytes(&self.g.to_bytes());
+        writer.bytes(&self.h.to_bytes());
+        match &self.dealing {
+            Some(dealing) => {
+                writer.array(2);
+                writer.bytes(&dealing.verifier.to_bytes());
+                write_share_pairs(&mut writer, dealing.shares.iter().map(|(s, b)| (s, b)));
+            }
+            None => writer.array(0),
+        }
+        writer.array(self.commitments.len());
+        for (dealer, verifier) in &self.commitments {
+            writer.array(2);
+            writer.unsigned(u64::from(*dealer));
+            writer.bytes(&verifier.to_bytes());
+        }
+        writer.array(self.shares.len());
+        for (dealer, (s, b)) in &self.shares {
+            writer.array(3);
+            writer.unsigned(u64::from(*dealer));
+            writer.bytes(&s.to_bytes());
+            writer.bytes(&b.to_bytes());
+        }
+        write_ids(&mut writer, &self.disqualified);
+        write_ids(&mut writer, &self.qualified);
+        write_share_pairs(&mut writer, self.output.iter().map(|(s, b)| (s, b)));
+        writer.into_bytes()
+    }
+
+    fn from_cbor_payload(_version: u32, payload: &[u8]) -> Result<Self, CborError> {
+        let mut reader = Reader::new(payload);
+        if reader.array()? != 12 {
+            return Err(invalid("expected 12 fields"));
+        }
+        let id = read_id(&mut reader)?;
+        let threshold = reader.unsigned()? as usize;
+        let limit = reader.unsigned()? as usize;
+        let round = Round::from_u64(reader.unsigned()?)?;
+        let mut participant = Self::new(
+            id,
+            threshold,
+            limit,
+            R::from_bytes(reader.bytes()?).map_err(invalid_payload)?,
+            R::from_bytes(reader.bytes()?).map_err(invalid_payload)?,
+        )
+        .map_err(invalid_payload)?;
+        participant.round = round;
+
+        participant.dealing = match reader.array()? {
+            0 => None,
+            2 => Some(Dealing {
+                dealer: id,
+                verifier: PedersenVerifier::try_from(reader.bytes()?).map_err(invalid_payload)?,
+                shares: read_share_pairs(&mut reader)?,
+            }),
+            _ => return Err(invalid("expected an empty or 2 field dealing")),
+        };
+        for _ in 0..reader.array()? {
+            if reader.array()? != 2 {
+                return Err(invalid("expected a dealer and commitment"));
+            }
+            let dealer = read_id(&mut reader)?;
+            let verifier = PedersenVerifier::try_from(reader.bytes()?).map_err(invalid_payload)?;
+            participant.commitments.insert(dealer, verifier);
+        }
+        for _ in 0..reader.array()? {
+            if reader.array()? != 3 {
+                return Err(invalid("expected a dealer and two shares"));
+            }
+            let dealer = read_id(&mut reader)?;
+            let pair = read_share_pair(&mut reader)?;
+            participant.shares.insert(dealer, pair);
+        }
+        participant.disqualified = read_ids(&mut reader)?;
+        participant.qualified = read_ids(&mut reader)?;
+        let mut output = read_share_pairs(&mut reader)?;
+        participant.output = output.pop();
+        reader.finish()?;
+
+        if (round == Round::Dealing) != participant.dealing.is_none()
+            || (round == Round::Complete) != participant.output.is_some()
+            || !output.is_empty()
+        {
+            return Err(invalid("the state doesn't match the round"));
+        }
+        Ok(participant)
+    }
+}
+
+/// The public record of a finished DKG: the commitments of the qualified
+/// dealers and the dealers that were disqualified.
+#[derive(Debug)]
+pub struct Transcript<S: Field, R: Group<S>> {
+    threshold: usize,
+    limit: usize,
+    commitments: BTreeMap<u32, PedersenVerifier<S, R>>,
+    disqualified: BTreeSet<u32>,
+}
+
+impl<S: Field, R: Group<S>> Transcript<S, R> {
+    /// The number of shares needed to use the secret
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// The number of participants
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// The dealers whose secrets are part of the shared secret
+    pub fn qualified(&self) -> impl Iterator<Item = u32> + '_ {
+        self.commitments.keys().copied()
+    }
+
+    /// The dealers that were left out
+    pub fn disqualified(&self) -> impl Iterator<Item = u32> + '_ {
+        self.disqualified.iter().copied()
+    }
+
+    /// Check the transcript is consistent and return the verifier of the
+    /// combined shares, the sum of the qualified dealers' commitments
+    pub fn verify(&self) -> SharingResult<PedersenVerifier<S, R>> {
+        PedersenVss::new(self.threshold, self.limit)?;
+        if self.commitments.len() < self.threshold {
+            return Err(SharingError::DkgNotEnoughDealers(
+                self.threshold,
+                self.commitments.len(),
+            ));
+        }
+        let mut verifiers = self.commitments.iter();
+        let (_, first) = verifiers.next().unwrap();
+        let mut combined = copy_verifier(first)?;
+        for (dealer, verifier) in self.commitments.iter() {
+            if *dealer == 0 || *dealer as usize > self.limit || self.disqualified.contains(dealer) {
+                return Err(SharingError::DkgUnknownDealer(*dealer));
+            }
+            if verifier.g.to_bytes() != first.g.to_bytes()
+                || verifier.h.to_bytes() != first.h.to_bytes()
+                || verifier.commitments.len() != self.threshold
+            {
+                return Err(SharingError::ShareInvalidValue);
+            }
+        }
+        for (_, verifier) in verifiers {
+            for (c, v) in combined
+                .commitments
+                .iter_mut()
+                .zip(verifier.commitments.iter())
+            {
+                c.value.add_assign(&v.value);
+            }
+        }
+        Ok(combined)
+    }
+
+    /// Check a participant's combined shares against the transcript
+    pub fn verify_share(&self, secret_share: &Share, blinding_share: &Share) -> SharingResult<()> {
+        let verifier = self.verify()?;
+        PedersenVss::new(self.threshold, self.limit)?.verify_share(
+            secret_share,
+            blinding_share,
+            &verifier,
+        )
+    }
+}
+
+impl<S: Field, R: Group<S>> CborSerializable for Transcript<S, R> {
+    const CBOR_ID: &'static str = "pedersen/dkg-transcript";
+
+    fn to_cbor_payload(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.array(4);
+        writer.unsigned(self.threshold as u64);
+        writer.unsigned(self.limit as u64);
+        writer.array(self.commitments.len());
+        for (dealer, verifier) in &self.commitments {
+            writer.array(2);
+            writer.unsigned(u64::from(*dealer));
+            writer.bytes(&verifier.to_bytes());
+        }
+        write_ids(&mut writer, &self.disqualified);
+        writer.into_bytes()
+    }
+
+    fn from_cbor_payload(_version: u32, payload: &[u8]) -> Result<Self, CborError> {
+        let mut reader = Reader::new(payload);
+        if reader.array()? != 4 {
+            return Err(invalid("expected 4 fields"));
+        }
+        let threshold = reader.unsigned()? as usize;
+        let limit = reader.unsigned()? as usize;
+        let mut commitments = BTreeMap::new();
+        for _ in 0..reader.array()? {
+            if reader.array()? != 2 {
+                return Err(invalid("expected a dealer and commitment"));
+            }
+            let dealer = read_id(&mut reader)?;
+            let verifier = PedersenVerifier::try_from(reader.bytes()?).map_err(invalid_payload)?;
+            commitments.insert(dealer, verifier);
+        }
+        let disqualified = read_ids(&mut reader)?;
+        reader.finish()?;
+        Ok(Self {
+            threshold,
+            limit,
+            commitments,
+            disqualified,
+        })
+    }
+}
+
+fn copy_point<S: Field, R: Group<S>>(point: &R) -> SharingResult<R> {
+    R::from_bytes(point.to_bytes())
+}
+
+fn copy_verifier<S: Field, R: Group<S>>(
+    verifier: &PedersenVerifier<S, R>,
+) -> SharingResult<PedersenVerifier<S, R>> {
+    Ok(PedersenVerifier {
+        g: copy_point(&verifier.g)?,
+        h: copy_point(&verifier.h)?,
+        commitments: verifier
+            .commitments
+            .iter()
+            .map(|c| {
+                Ok(ShareVerifier {
+                    value: copy_point(&c.value)?,
+                    phantom: PhantomData,
+                })
+            })
+            .collect::<SharingResult<_>>()?,
+    })
+}
+
+fn write_ids(writer: &mut Writer, ids: &BTreeSet<u32>) {
+    writer.array(ids.len());
+    for id in ids {
+        writer.unsigned(u64::from(*id));
+    }
+}
+
+fn read_ids(reader: &mut Reader) -> Result<BTreeSet<u32>, CborError> {
+    (0..reader.array()?).map(|_| read_id(reader)).collect()
+}
+
+fn read_id(reader: &mut Reader) -> Result<u32, CborError> {
+    u32::try_from(reader.unsigned()?).map_err(|_| invalid("identifier is out of range"))
+}
+
+fn write_share_pairs<'a>(
+    writer: &mut Writer,
+    pairs: impl ExactSizeIterator<Item = (&'a Share, &'a Share)>,
+) {
+    writer.array(pairs.len());
+    for (s, b) in pairs {
+        writer.array(2);
+        writer.bytes(&s.to_bytes());
+        writer.bytes(&b.to_bytes());
+    }
+}
+
+fn read_share_pairs(reader: &mut Reader) -> Result<Vec<(Share, Share)>, CborError> {
+    (0..reader.array()?)
+        .map(|_| {
+            if reader.array()? != 2 {
+                return Err(invalid("expected two shares"));
+            }
+            read_share_pair(reader)
+        })
+        .collect()
+}
+
+fn read_share_pair(reader: &mut Reader) -> Result<(Share, Share), CborError> {
+    let s = Share::try_from(reader.bytes()?).map_err(invalid_payload)?;
+    let b = Share::try_from(reader.bytes()?).map_err(invalid_payload)?;
+    Ok((s, b))
+}
+
+fn invalid(msg: &str) -> CborError {
+    CborError::InvalidPayload(msg.to_string())
+}
+
+fn invalid_payload(e: SharingError) -> CborError {
+    CborError::InvalidPayload(e.to_string())
+}
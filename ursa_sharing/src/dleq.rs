@@ -0,0 +1,288 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::{
+    error::{SharingError, SharingResult},
+    Field, Group,
+};
+use digest::Digest;
+use generic_array::typenum::Unsigned;
+use rand::{CryptoRng, RngCore};
+use std::convert::TryFrom;
+use ursa_core::Transcript;
+
+const CHALLENGE_DST: &[u8] = b"ursa_sharing_dleq_challenge";
+const WEIGHT_DST: &[u8] = b"ursa_sharing_dleq_batch_weight";
+/// Number of digest bytes used for challenges and batch weights
+const CHALLENGE_BYTES: usize = 16;
+
+/// A non-interactive Chaum-Pedersen proof that two elements share the same
+/// discrete logarithm, i.e. `a = g^x` and `b = h^x` for the same secret `x`.
+/// (see <https://link.springer.com/chapter/10.1007/3-540-48071-4_7>)
+///
+/// The challenge is derived from the statement and the prover's commitments
+/// with the digest `D` chosen by the caller. The batched variant proves
+/// `b_i = h_i^x` for many `h_i` with a single proof by folding the
+/// statements together using weights derived from all of them.
+///
+/// The `_with_transcript` variants draw the challenge from a [`Transcript`]
+/// instead, so it also covers whatever the caller appended to it first.
+#[derive(Debug)]
+pub struct Proof<S: Field> {
+    challenge: S,
+    response: S,
+}
+
+impl<S: Field> Proof<S> {
+    /// Prove that `a = g^x` and `b = h^x`
+    pub fn new<R: Group<S>, D: Digest>(
+        rng: &mut (impl RngCore + CryptoRng),
+        x: &S,
+        g: &R,
+        a: &R,
+        h: &R,
+        b: &R,
+    ) -> Self {
+        let k = S::random(rng);
+        let t1 = exp(g, &k);
+        let t2 = exp(h, &k);
+        let challenge = challenge::<S, R, D>(g, a, h, b, &t1, &t2);
+
+        // response = k - challenge * x
+        let mut cx = copy(&challenge);
+        cx.scalar_mul_assign(x);
+        let mut response = k;
+        response.sub_assign(&cx);
+
+        Self {
+            challenge,
+            response,
+        }
+    }
+
+    /// Check the proof that `a = g^x` and `b = h^x` for some `x`
+    pub fn verify<R: Group<S>, D: Digest>(&self, g: &R, a: &R, h: &R, b: &R) -> bool {
+        // t1 = g^response * a^challenge
+        let mut t1 = exp(g, &self.response);
+        t1.add_assign(&exp(a, &self.challenge));
+        // t2 = h^response * b^challenge
+        let mut t2 = exp(h, &self.response);
+        t2.add_assign(&exp(b, &self.challenge));
+
+        let challenge = challenge::<S, R, D>(g, a, h, b, &t1, &t2);
+        challenge.to_bytes() == self.challenge.to_bytes()
+    }
+
+    /// Prove that `a = g^x` and `bs[i] = hs[i]^x` for every `i`
+    pub fn new_batch<R: Group<S>, D: Digest>(
+        rng: &mut (impl RngCore + CryptoRng),
+        x: &S,
+        g: &R,
+        a: &R,
+        hs: &[R],
+        bs: &[R],
+    ) -> SharingResult<Self> {
+        let (h, b) = fold::<S, R, D>(g, a, hs, bs)?;
+        Ok(Self::new::<R, D>(rng, x, g, a, &h, &b))
+    }
+
+    /// Check the proof that `a = g^x` and `bs[i] = hs[i]^x` for every `i`
+    pub fn verify_batch<R: Group<S>, D: Digest>(&self, g: &R, a: &R, hs: &[R], bs: &[R]) -> bool {
+        match fold::<S, R, D>(g, a, hs, bs) {
+            Ok((h, b)) => self.verify::<R, D>(g, a, &h, &b),
+            Err(_) => false,
+        }
+    }
+
+    /// Prove that `a = g^x` and `b = h^x`, appending the statement to
+    /// `transcript` and drawing the challenge from it. The nonce comes from
+    /// the transcript rekeyed with `x` and `rng`.
+    pub fn new_with_transcript<R: Group<S>>(
+        rng: &mut (impl RngCore + CryptoRng),
+        transcript: &mut Transcript,
+        x: &S,
+        g: &R,
+        a: &R,
+        h: &R,
+        b: &R,
+    ) -> Self {
+        append_statement(transcript, g, a, h, b);
+        let mut nonce_rng = transcript
+            .build_rng()
+            .rekey_with_witness_bytes(b"x", x.to_bytes().as_slice())
+            .finalize(rng);
+        let k = S::random(&mut nonce_rng);
+        let t1 = exp(g, &k);
+        let t2 = exp(h, &k);
+        let challenge = transcript_challenge::<S, R>(transcript, &t1, &t2);
+
+        let mut cx = copy(&challenge);
+        cx.scalar_mul_assign(x);
+        let mut response = k;
+        response.sub_assign(&cx);
+
+        Self {
+            challenge,
+            response,
+        }
+    }
+
+    /// Check a proof made by [`new_with_transcript`](Proof::new_with_transcript)
+    /// with a transcript in the same state as the prover's
+    pub fn verify_with_transcript<R: Group<S>>(
+        &self,
+        transcript: &mut Transcript,
+        g: &R,
+        a: &R,
+        h: &R,
+        b: &R,
+    ) -> bool {
+        append_statement(transcript, g, a, h, b);
+        let mut t1 = exp(g, &self.response);
+        t1.add_assign(&exp(a, &self.challenge));
+        let mut t2 = exp(h, &self.response);
+        t2.add_assign(&exp(b, &self.challenge));
+
+        let challenge = transcript_challenge::<S, R>(transcript, &t1, &t2);
+        challenge.to_bytes() == self.challenge.to_bytes()
+    }
+
+    /// Serialize the proof as the challenge followed by the response
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut o = self.challenge.to_bytes().to_vec();
+        o.extend_from_slice(self.response.to_bytes().as_slice());
+        o
+    }
+}
+
+impl<S: Field> Clone for Proof<S> {
+    fn clone(&self) -> Self {
+        Self {
+            challenge: copy(&self.challenge),
+            response: copy(&self.response),
+        }
+    }
+}
+
+impl<S: Field> TryFrom<&[u8]> for Proof<S> {
+    type Error = SharingError;
+
+    fn try_from(value: &[u8]) -> SharingResult<Self> {
+        let size = S::Size::to_usize();
+        if value.len() != size * 2 {
+            return Err(SharingError::DleqProofInvalidSize(size * 2, value.len()));
+        }
+        Ok(Self {
+            challenge: S::from_bytes(&value[..size])?,
+            response: S::from_bytes(&value[size..])?,
+        })
+    }
+}
+
+/// Combine the statements `bs[i] = hs[i]^x` into a single statement
+/// `prod bs[i]^w_i = (prod hs[i]^w_i)^x` where the weights depend on every statement.
+fn fold<S: Field, R: Group<S>, D: Digest>(
+    g: &R,
+    a: &R,
+    hs: &[R],
+    bs: &[R],
+) -> SharingResult<(R, R)> {
+    if hs.len() != bs.len() {
+        return Err(SharingError::DleqBatchMismatch(hs.len(), bs.len()));
+    }
+    if hs.is_empty() {
+        return Err(SharingError::DleqBatchMismatch(0, 0));
+    }
+
+    let mut hasher = D::new();
+    hasher.input(WEIGHT_DST);
+    hasher.input(g.to_bytes());
+    hasher.input(a.to_bytes());
+    for (h, b) in hs.iter().zip(bs.iter()) {
+        hasher.input(h.to_bytes());
+        hasher.input(b.to_bytes());
+    }
+    let seed = hasher.result();
+
+    let mut h = R::zero();
+    let mut b = R::zero();
+    for (i, (h_i, b_i)) in hs.iter().zip(bs.iter()).enumerate() {
+        let w = to_field::<S>(
+            D::new()
+                .chain(seed.as_slice())
+                .chain((i as u32).to_be_bytes())
+                .result()
+                .as_slice(),
+        );
+        h.add_assign(&exp(h_i, &w));
+        b.add_assign(&exp(b_i, &w));
+    }
+    Ok((h, b))
+}
+
+fn challenge<S: Field, R: Group<S>, D: Digest>(g: &R, a: &R, h: &R, b: &R, t1: &R, t2: &R) -> S {
+    let mut hasher = D::new();
+    hasher.input(CHALLENGE_DST);
+    for e in &[g, a, h, b, t1, t2] {
+        hasher.input(e.to_bytes());
+    }
+    to_field::<S>(hasher.result().as_slice())
+}
+
+fn append_statement<S: Field, R: Group<S>>(
+    transcript: &mut Transcript,
+    g: &R,
+    a: &R,
+    h: &R,
+    b: &R,
+) {
+    transcript.append_message(b"dom-sep", b"ursa_sharing_dleq");
+    transcript.append_message(b"g", g.to_bytes().as_slice());
+    transcript.append_message(b"a", a.to_bytes().as_slice());
+    transcript.append_message(b"h", h.to_bytes().as_slice());
+    transcript.append_message(b"b", b.to_bytes().as_slice());
+}
+
+fn transcript_challenge<S: Field, R: Group<S>>(transcript: &mut Transcript, t1: &R, t2: &R) -> S {
+    transcript.append_message(b"t1", t1.to_bytes().as_slice());
+    transcript.append_message(b"t2", t2.to_bytes().as_slice());
+    let mut c = [0u8; CHALLENGE_BYTES];
+    transcript.challenge_bytes(b"c", &mut c);
+    to_field::<S>(&c)
+}
+
+/// Interpret the leading digest bytes as a big-endian integer.
+/// Using fewer bytes than the field size keeps the value unbiased without
+/// knowing how the field encodes its elements.
+fn to_field<S: Field>(digest: &[u8]) -> S {
+    let base = S::from_usize(256);
+    let mut out = S::zero();
+    for byte in digest.iter().take(CHALLENGE_BYTES) {
+        out.scalar_mul_assign(&base);
+        out.add_assign(&S::from_usize(*byte as usize));
+    }
+    out
+}
+
+fn exp<S: Field, R: Group<S>>(base: &R, e: &S) -> R {
+    let mut r = R::zero();
+    r.add_assign(base);
+    r.scalar_mul_assign(e);
+    r
+}
+
+fn copy<S: Field>(s: &S) -> S {
+    let mut r = S::zero();
+    r.add_assign(s);
+    r
+}
@@ -19,6 +19,7 @@ use std::{
     error::Error,
     fmt::{Display, Formatter, Result as FmtResult},
 };
+use ursa_core::{error::codes, ErrorKind, UrsaError};
 
 /// A specialized [`Result`] type for Sharing operations.
 pub type SharingResult<T> = Result<T, SharingError>;
@@ -44,8 +45,58 @@ pub enum SharingError {
     PedersenVerifierMinSize(usize, usize),
     /// The blinding factor share value is corrupted or invalid
     PedersenBlindShareInvalid,
+    /// A batch has a different number of shares and blinding factor shares
+    PedersenBlindShareCount(usize, usize),
     /// Deserializing an invalid ECC point
     InvalidPoint,
+    /// Deserializing a DLEQ proof of the wrong length
+    DleqProofInvalidSize(usize, usize),
+    /// A batched DLEQ statement is empty or has unequal numbers of bases and values
+    DleqBatchMismatch(usize, usize),
+    /// A protobuf message is missing a required field
+    MissingField(&'static str),
+    /// A DKG message arrived in a round that doesn't accept it
+    DkgUnexpectedRound,
+    /// A DKG message names a dealer that isn't another participant
+    DkgUnknownDealer(u32),
+    /// A DKG dealer sent two different messages of the same kind
+    DkgConflictingMessage(u32),
+    /// Fewer DKG dealers are qualified than the threshold
+    DkgNotEnoughDealers(usize, usize),
+    /// A backup bundle doesn't match its manifest hash
+    BackupManifestMismatch,
+    /// A backup share couldn't be decrypted with the custodian's key
+    BackupShareDecryption(u32),
+}
+
+impl SharingError {
+    /// The stable code of this kind of error from `ursa_core::error::codes`
+    pub fn code(&self) -> i32 {
+        use SharingError::*;
+
+        match self {
+            ShareSecretMinSize => codes::SHARE_SECRET_MIN_SIZE,
+            ShareLimitLessThanThreshold => codes::SHARE_LIMIT_LESS_THAN_THRESHOLD,
+            ShareMinThreshold => codes::SHARE_MIN_THRESHOLD,
+            ShareInvalidSecret => codes::SHARE_INVALID_SECRET,
+            ShareInvalidIdentifier => codes::SHARE_INVALID_IDENTIFIER,
+            ShareDuplicateIdentifier => codes::SHARE_DUPLICATE_IDENTIFIER,
+            ShareInvalidValue => codes::SHARE_INVALID_VALUE,
+            PedersenVerifierMinSize(..) => codes::PEDERSEN_VERIFIER_MIN_SIZE,
+            PedersenBlindShareInvalid => codes::PEDERSEN_BLIND_SHARE_INVALID,
+            PedersenBlindShareCount(..) => codes::PEDERSEN_BLIND_SHARE_COUNT,
+            InvalidPoint => codes::INVALID_POINT,
+            DleqProofInvalidSize(..) => codes::DLEQ_PROOF_INVALID_SIZE,
+            DleqBatchMismatch(..) => codes::DLEQ_BATCH_MISMATCH,
+            MissingField(_) => codes::MISSING_FIELD,
+            DkgUnexpectedRound => codes::DKG_UNEXPECTED_ROUND,
+            DkgUnknownDealer(_) => codes::DKG_UNKNOWN_DEALER,
+            DkgConflictingMessage(_) => codes::DKG_CONFLICTING_MESSAGE,
+            DkgNotEnoughDealers(..) => codes::DKG_NOT_ENOUGH_DEALERS,
+            BackupManifestMismatch => codes::BACKUP_MANIFEST_MISMATCH,
+            BackupShareDecryption(_) => codes::BACKUP_SHARE_DECRYPTION,
+        }
+    }
 }
 
 impl Display for SharingError {
@@ -69,9 +120,69 @@ impl Display for SharingError {
                 expected, found
             ),
             PedersenBlindShareInvalid => write!(f, "Blind share is not valid"),
+            PedersenBlindShareCount(shares, blind_shares) => write!(
+                f,
+                "Every share requires a blind share: {} shares and {} blind shares",
+                shares, blind_shares
+            ),
             InvalidPoint => write!(f, "Invalid curve point"),
+            DleqProofInvalidSize(expected, found) => write!(
+                f,
+                "Invalid DLEQ proof length: expected {}, found {}",
+                expected, found
+            ),
+            DleqBatchMismatch(bases, values) => write!(
+                f,
+                "DLEQ batch requires the same non-zero number of bases and values: {} bases and {} values",
+                bases, values
+            ),
+            MissingField(field) => write!(f, "Message is missing the {} field", field),
+            DkgUnexpectedRound => write!(f, "DKG message isn't accepted in this round"),
+            DkgUnknownDealer(dealer) => write!(f, "Unknown DKG dealer {}", dealer),
+            DkgConflictingMessage(dealer) => {
+                write!(f, "DKG dealer {} sent conflicting messages", dealer)
+            }
+            DkgNotEnoughDealers(expected, found) => write!(
+                f,
+                "Not enough qualified DKG dealers: expected {}, found {}",
+                expected, found
+            ),
+            BackupManifestMismatch => write!(f, "Backup bundle doesn't match its manifest"),
+            BackupShareDecryption(identifier) => {
+                write!(f, "Backup share {} couldn't be decrypted", identifier)
+            }
         }
     }
 }
 
 impl Error for SharingError {}
+
+impl From<SharingError> for UrsaError {
+    fn from(error: SharingError) -> Self {
+        use SharingError::*;
+
+        let kind = match error {
+            ShareSecretMinSize
+            | PedersenVerifierMinSize(..)
+            | InvalidPoint
+            | DleqProofInvalidSize(..)
+            | MissingField(_) => ErrorKind::Parse,
+            ShareLimitLessThanThreshold
+            | ShareMinThreshold
+            | ShareInvalidSecret
+            | ShareInvalidIdentifier
+            | ShareDuplicateIdentifier
+            | PedersenBlindShareCount(..)
+            | DleqBatchMismatch(..)
+            | DkgUnexpectedRound
+            | DkgUnknownDealer(_)
+            | DkgNotEnoughDealers(..) => ErrorKind::InvalidInput,
+            ShareInvalidValue
+            | PedersenBlindShareInvalid
+            | DkgConflictingMessage(_)
+            | BackupManifestMismatch
+            | BackupShareDecryption(_) => ErrorKind::Verification,
+        };
+        UrsaError::with_source(kind, error.to_string(), error)
+    }
+}
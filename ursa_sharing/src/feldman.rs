@@ -19,6 +19,10 @@ use super::{
 use generic_array::typenum::Unsigned;
 use rand::{CryptoRng, RngCore};
 use std::{convert::TryFrom, marker::PhantomData};
+use ursa_core::{
+    parallel::{self, MaybeSync},
+    CborError, CborSerializable,
+};
 
 /// Feldman's Verifiable secret sharing scheme.
 /// (see <https://www.cs.umd.edu/~gasarch/TOPICS/secretsharing/feldmanVSS.pdf>
@@ -81,7 +85,7 @@ impl Scheme {
         if verifier.commitments.len() < self.threshold {
             return Err(SharingError::ShareMinThreshold);
         }
-        let s = S::from_bytes(&share.value)?;
+        let s = S::from_bytes(share.value())?;
         if !s.is_valid() {
             return Err(SharingError::ShareInvalidValue);
         }
@@ -122,6 +126,16 @@ impl Scheme {
         }
     }
 
+    /// Checks that every share is valid according to the verifier set.
+    /// The shares are checked in parallel with the `parallel` feature.
+    pub fn verify_shares<S: Field + MaybeSync, R: Group<S> + MaybeSync>(
+        &self,
+        shares: &[ShamirShare],
+        verifier: &FeldmanVerifier<S, R>,
+    ) -> SharingResult<()> {
+        parallel::try_map(shares, |s| self.verify_share(s, verifier)).map(|_| ())
+    }
+
     /// Reconstruct a secret from shares created from `split_secret`.
     /// The shares should be verified first by calling `verify_share`.
     /// This method assumes all the shares have been verified.
@@ -155,6 +169,18 @@ impl<S: Field, R: Group<S>> FeldmanVerifier<S, R> {
     }
 }
 
+impl<S: Field, R: Group<S>> CborSerializable for FeldmanVerifier<S, R> {
+    const CBOR_ID: &'static str = "feldman/verifier";
+
+    fn to_cbor_payload(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn from_cbor_payload(_version: u32, payload: &[u8]) -> Result<Self, CborError> {
+        Self::try_from(payload).map_err(|e| CborError::InvalidPayload(e.to_string()))
+    }
+}
+
 impl<S: Field, R: Group<S>> TryFrom<&[u8]> for FeldmanVerifier<S, R> {
     type Error = SharingError;
 
@@ -177,11 +203,15 @@ impl<S: Field, R: Group<S>> TryFrom<&[u8]> for FeldmanVerifier<S, R> {
         let cs = u32::from_be_bytes(c_size) as usize;
         let mut commitments = Vec::with_capacity(cs);
         offset = end;
-        end += R::Size::to_usize();
-        for _ in 0..cs {
-            let c = R::from_bytes(&value[offset..end])?;
+        if value.len() - offset != cs * R::Size::to_usize() {
+            return Err(SharingError::PedersenVerifierMinSize(
+                offset + cs * R::Size::to_usize(),
+                value.len(),
+            ));
+        }
+        for c in value[offset..].chunks(R::Size::to_usize()) {
             commitments.push(ShareVerifier {
-                value: c,
+                value: R::from_bytes(c)?,
                 phantom: PhantomData,
             });
         }
@@ -41,7 +41,7 @@
 //! Future work would be to use reed-solomon
 //! codes to check for corrupted shares.
 
-#![cfg_attr(feature = "nightly", feature(doc_cfg))]
+#![cfg_attr(docsrs, feature(doc_cfg))]
 
 pub use generic_array::{self, typenum};
 
@@ -91,11 +91,19 @@ pub trait Field: Group {
 }
 
 /// The polynomial used for generating the shares
-#[derive(Debug)]
 pub(crate) struct Polynomial<S: Field> {
     pub(crate) coefficients: Vec<S>,
 }
 
+impl<S: Field> std::fmt::Debug for Polynomial<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The coefficients are as secret as the intercept
+        f.debug_struct("Polynomial")
+            .field("degree", &self.coefficients.len().saturating_sub(1))
+            .finish()
+    }
+}
+
 impl<S: Field> Polynomial<S> {
     /// Construct a random polynomial of the specified degree using a specified intercept
     pub fn new(rng: &mut (impl RngCore + CryptoRng), intercept: &S, degree: usize) -> Self {
@@ -131,6 +139,15 @@ impl<S: Field> Polynomial<S> {
     }
 }
 
+impl<S: Field> Drop for Polynomial<S> {
+    fn drop(&mut self) {
+        // Field elements aren't required to be `Zeroize`, so overwrite them instead
+        for c in self.coefficients.iter_mut() {
+            *c = S::zero();
+        }
+    }
+}
+
 /// A share verifier is used to provide integrity checking of shamir shares
 #[derive(Debug)]
 pub struct ShareVerifier<S: Field, R: Group<S>> {
@@ -159,18 +176,28 @@ impl<S: Field, R: Group<S>> TryFrom<&[u8]> for ShareVerifier<S, R> {
 impl<S: Field, R: Group<S>> Clone for ShareVerifier<S, R> {
     fn clone(&self) -> Self {
         Self {
-            value: R::from_bytes(&self.value.to_bytes()).unwrap(),
+            value: R::from_bytes(self.value.to_bytes()).unwrap(),
             phantom: PhantomData,
         }
     }
 }
 
+/// Secrets backed up with custodians as one verifiable bundle
+#[cfg(feature = "backup")]
+pub mod backup;
+/// Resumable Pedersen distributed key generation
+pub mod dkg;
+/// Chaum-Pedersen proofs of discrete logarithm equality
+pub mod dleq;
 /// Sharing Errors and Results
 pub mod error;
 /// Feldman's verifiable secret sharing scheme
 pub mod feldman;
 /// Pedersen's verifiable secret sharing scheme
 pub mod pedersen;
+/// Conversions to and from the protobuf messages of `ursa_core::proto`
+#[cfg(feature = "protobuf")]
+pub mod proto;
 /// Shamir secret sharing scheme
 pub mod shamir;
 
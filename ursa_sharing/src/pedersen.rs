@@ -19,6 +19,10 @@ use super::{
 use generic_array::typenum::Unsigned;
 use rand::{CryptoRng, RngCore};
 use std::{convert::TryFrom, marker::PhantomData};
+use ursa_core::{
+    parallel::{self, MaybeSync},
+    CborError, CborSerializable,
+};
 
 /// Implements Pedersen's Verifiable secret sharing scheme.
 /// (see <https://www.cs.cornell.edu/courses/cs754/2001fa/129.PDF>)
@@ -95,11 +99,11 @@ impl Scheme {
         blind_share: &ShamirShare,
         verifier: &PedersenVerifier<S, R>,
     ) -> SharingResult<()> {
-        let s = S::from_bytes(&share.value)?;
+        let s = S::from_bytes(share.value())?;
         if !s.is_valid() {
             return Err(SharingError::ShareInvalidValue);
         }
-        let t = S::from_bytes(&blind_share.value)?;
+        let t = S::from_bytes(blind_share.value())?;
         if !t.is_valid() {
             return Err(SharingError::PedersenBlindShareInvalid);
         }
@@ -146,6 +150,25 @@ impl Scheme {
         }
     }
 
+    /// Checks that every share is valid according to the verifier, where
+    /// `blind_shares[i]` is the blinding factor share of `shares[i]`.
+    /// The shares are checked in parallel with the `parallel` feature.
+    pub fn verify_shares<S: Field + MaybeSync, R: Group<S> + MaybeSync>(
+        &self,
+        shares: &[ShamirShare],
+        blind_shares: &[ShamirShare],
+        verifier: &PedersenVerifier<S, R>,
+    ) -> SharingResult<()> {
+        if shares.len() != blind_shares.len() {
+            return Err(SharingError::PedersenBlindShareCount(
+                shares.len(),
+                blind_shares.len(),
+            ));
+        }
+        let pairs = shares.iter().zip(blind_shares).collect::<Vec<_>>();
+        parallel::try_map(&pairs, |(s, b)| self.verify_share(s, b, verifier)).map(|_| ())
+    }
+
     /// Reconstruct a secret from shares created from `split_secret`.
     /// The shares should be verified first by calling `verify_share`.
     /// This method assumes all the shares have been verified.
@@ -182,6 +205,18 @@ impl<S: Field, R: Group<S>> PedersenVerifier<S, R> {
     }
 }
 
+impl<S: Field, R: Group<S>> CborSerializable for PedersenVerifier<S, R> {
+    const CBOR_ID: &'static str = "pedersen/verifier";
+
+    fn to_cbor_payload(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn from_cbor_payload(_version: u32, payload: &[u8]) -> Result<Self, CborError> {
+        Self::try_from(payload).map_err(|e| CborError::InvalidPayload(e.to_string()))
+    }
+}
+
 impl<S: Field, R: Group<S>> TryFrom<&[u8]> for PedersenVerifier<S, R> {
     type Error = SharingError;
 
@@ -209,11 +244,15 @@ impl<S: Field, R: Group<S>> TryFrom<&[u8]> for PedersenVerifier<S, R> {
         let cs = u32::from_be_bytes(c_size) as usize;
         let mut commitments = Vec::with_capacity(cs);
         offset = end;
-        end += R::Size::to_usize();
-        for _ in 0..cs {
-            let c = R::from_bytes(&value[offset..end])?;
+        if value.len() - offset != cs * R::Size::to_usize() {
+            return Err(SharingError::PedersenVerifierMinSize(
+                offset + cs * R::Size::to_usize(),
+                value.len(),
+            ));
+        }
+        for c in value[offset..].chunks(R::Size::to_usize()) {
             commitments.push(ShareVerifier {
-                value: c,
+                value: R::from_bytes(c)?,
                 phantom: PhantomData,
             });
         }
@@ -221,8 +260,10 @@ impl<S: Field, R: Group<S>> TryFrom<&[u8]> for PedersenVerifier<S, R> {
     }
 }
 
-/// A Pedersen result returned when calling `split_secret`
-#[derive(Debug, Clone)]
+/// A Pedersen result returned when calling `split_secret`.
+///
+/// `Debug` leaves out the blinding factor, the shares print redacted.
+#[derive(Clone)]
 pub struct PedersenVssResult<S: Field, R: Group<S>> {
     /// The blinding factor randomly generated
     pub blinding: S,
@@ -233,3 +274,17 @@ pub struct PedersenVssResult<S: Field, R: Group<S>> {
     /// The verifier used to check shares
     pub verifier: PedersenVerifier<S, R>,
 }
+
+impl<S, R> std::fmt::Debug for PedersenVssResult<S, R>
+where
+    S: Field + std::fmt::Debug,
+    R: Group<S> + std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PedersenVssResult")
+            .field("blinding_shares", &self.blinding_shares)
+            .field("secret_shares", &self.secret_shares)
+            .field("verifier", &self.verifier)
+            .finish()
+    }
+}
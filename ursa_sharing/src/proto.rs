@@ -0,0 +1,156 @@
+// Copyright 2020 Hyperledger Ursa Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::{
+    error::{SharingError, SharingResult},
+    feldman::FeldmanVerifier,
+    pedersen::{PedersenVerifier, PedersenVssResult},
+    shamir::Share,
+    Field, Group, ShareVerifier,
+};
+use std::{convert::TryFrom, marker::PhantomData};
+use ursa_core::{proto, Secret};
+
+impl From<&Share> for proto::Share {
+    fn from(share: &Share) -> Self {
+        Self {
+            identifier: share.identifier,
+            value: share.value().to_vec(),
+        }
+    }
+}
+
+impl From<&proto::Share> for Share {
+    fn from(share: &proto::Share) -> Self {
+        Self {
+            identifier: share.identifier,
+            value: Secret::new(share.value.clone()),
+        }
+    }
+}
+
+impl<S: Field, R: Group<S>> From<&FeldmanVerifier<S, R>> for proto::FeldmanVerifier {
+    fn from(verifier: &FeldmanVerifier<S, R>) -> Self {
+        Self {
+            generator: verifier.g.to_bytes().to_vec(),
+            commitments: commitments_to_proto(&verifier.commitments),
+        }
+    }
+}
+
+impl<S: Field, R: Group<S>> TryFrom<&proto::FeldmanVerifier> for FeldmanVerifier<S, R> {
+    type Error = SharingError;
+
+    fn try_from(verifier: &proto::FeldmanVerifier) -> SharingResult<Self> {
+        Ok(Self {
+            g: R::from_bytes(&verifier.generator)?,
+            commitments: commitments_from_proto(&verifier.commitments)?,
+        })
+    }
+}
+
+impl<S: Field, R: Group<S>> From<&PedersenVerifier<S, R>> for proto::PedersenVerifier {
+    fn from(verifier: &PedersenVerifier<S, R>) -> Self {
+        Self {
+            generator: verifier.g.to_bytes().to_vec(),
+            blinding_generator: verifier.h.to_bytes().to_vec(),
+            commitments: commitments_to_proto(&verifier.commitments),
+        }
+    }
+}
+
+impl<S: Field, R: Group<S>> TryFrom<&proto::PedersenVerifier> for PedersenVerifier<S, R> {
+    type Error = SharingError;
+
+    fn try_from(verifier: &proto::PedersenVerifier) -> SharingResult<Self> {
+        Ok(Self {
+            g: R::from_bytes(&verifier.generator)?,
+            h: R::from_bytes(&verifier.blinding_generator)?,
+            commitments: commitments_from_proto(&verifier.commitments)?,
+        })
+    }
+}
+
+impl<S: Field, R: Group<S>> TryFrom<&proto::DkgCommitment> for PedersenVerifier<S, R> {
+    type Error = SharingError;
+
+    fn try_from(commitment: &proto::DkgCommitment) -> SharingResult<Self> {
+        commitment
+            .verifier
+            .as_ref()
+            .ok_or(SharingError::MissingField("verifier"))
+            .and_then(Self::try_from)
+    }
+}
+
+impl<S: Field, R: Group<S>> PedersenVssResult<S, R> {
+    /// The message `dealer` broadcasts to every participant of a DKG
+    pub fn dkg_commitment(&self, dealer: u32) -> proto::DkgCommitment {
+        proto::DkgCommitment {
+            dealer,
+            verifier: Some((&self.verifier).into()),
+        }
+    }
+
+    /// The messages `dealer` sends privately to each participant of a DKG,
+    /// addressed to the identifier of the shares
+    pub fn dkg_shares(&self, dealer: u32) -> Vec<proto::DkgShare> {
+        self.secret_shares
+            .iter()
+            .zip(self.blinding_shares.iter())
+            .map(|(s, b)| proto::DkgShare {
+                dealer,
+                recipient: s.identifier,
+                secret_share: Some(s.into()),
+                blinding_share: Some(b.into()),
+            })
+            .collect()
+    }
+}
+
+/// The secret and blinding shares of a DKG share message,
+/// to check with `pedersen::Scheme::verify_share`
+pub fn dkg_share(message: &proto::DkgShare) -> SharingResult<(Share, Share)> {
+    let secret = message
+        .secret_share
+        .as_ref()
+        .ok_or(SharingError::MissingField("secret_share"))?;
+    let blinding = message
+        .blinding_share
+        .as_ref()
+        .ok_or(SharingError::MissingField("blinding_share"))?;
+    if secret.identifier != message.recipient || blinding.identifier != message.recipient {
+        return Err(SharingError::ShareInvalidIdentifier);
+    }
+    Ok((secret.into(), blinding.into()))
+}
+
+fn commitments_to_proto<S: Field, R: Group<S>>(
+    commitments: &[ShareVerifier<S, R>],
+) -> Vec<Vec<u8>> {
+    commitments.iter().map(|c| c.to_bytes().to_vec()).collect()
+}
+
+fn commitments_from_proto<S: Field, R: Group<S>>(
+    commitments: &[Vec<u8>],
+) -> SharingResult<Vec<ShareVerifier<S, R>>> {
+    commitments
+        .iter()
+        .map(|c| {
+            Ok(ShareVerifier {
+                value: R::from_bytes(c)?,
+                phantom: PhantomData,
+            })
+        })
+        .collect()
+}
@@ -17,17 +17,18 @@ use super::{
 };
 use rand::{CryptoRng, RngCore};
 use std::{collections::BTreeSet, convert::TryFrom};
+use subtle::{Choice, ConstantTimeEq};
+use ursa_core::{CborError, CborSerializable, Secret};
 use zeroize::Zeroize;
 
 /// A Shamir simple secret share
 /// provides no integrity checking
-#[derive(Debug, Zeroize)]
-#[zeroize(drop)]
+#[derive(Clone, Debug, Zeroize)]
 pub struct Share {
     /// X-coordinate
     pub(crate) identifier: u32,
     /// Y-coordinate
-    pub(crate) value: Vec<u8>,
+    pub(crate) value: Secret<Vec<u8>>,
 }
 
 impl Share {
@@ -35,7 +36,7 @@ impl Share {
     pub fn new<B: AsRef<[u8]>>(identifier: usize, value: B) -> Self {
         Self {
             identifier: identifier as u32,
-            value: value.as_ref().to_vec(),
+            value: Secret::new(value.as_ref().to_vec()),
         }
     }
 
@@ -43,7 +44,7 @@ impl Share {
     /// The identifier is the first 4 bytes
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut o = self.identifier.to_be_bytes().to_vec();
-        o.append(&mut self.to_bytes());
+        o.extend_from_slice(self.value());
         o
     }
 
@@ -54,10 +55,24 @@ impl Share {
 
     /// Get the current value of the share
     pub fn value(&self) -> &[u8] {
-        self.value.as_slice()
+        self.value.expose_secret().as_slice()
     }
 }
 
+impl ConstantTimeEq for Share {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.identifier.ct_eq(&other.identifier) & self.value.ct_eq(&other.value)
+    }
+}
+
+impl PartialEq for Share {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for Share {}
+
 impl TryFrom<&[u8]> for Share {
     type Error = SharingError;
 
@@ -69,17 +84,26 @@ impl TryFrom<&[u8]> for Share {
         identifier.copy_from_slice(&value[..4]);
         Ok(Self {
             identifier: u32::from_be_bytes(identifier),
-            value: value[4..].to_vec(),
+            value: Secret::new(value[4..].to_vec()),
         })
     }
 }
 
-impl Clone for Share {
-    fn clone(&self) -> Self {
-        Self {
-            identifier: self.identifier,
-            value: self.value.clone(),
-        }
+impl From<&Share> for Vec<u8> {
+    fn from(share: &Share) -> Self {
+        share.to_bytes()
+    }
+}
+
+impl CborSerializable for Share {
+    const CBOR_ID: &'static str = "shamir/share";
+
+    fn to_cbor_payload(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn from_cbor_payload(_version: u32, payload: &[u8]) -> Result<Self, CborError> {
+        Self::try_from(payload).map_err(|e| CborError::InvalidPayload(e.to_string()))
     }
 }
 
@@ -132,7 +156,7 @@ impl Scheme {
             let y = polynomial.evaluate(&x);
             shares.push(Share {
                 identifier: identifier as u32,
-                value: y.to_bytes().to_vec(),
+                value: Secret::new(y.to_bytes().to_vec()),
             });
         }
         Ok((shares, polynomial))
@@ -158,7 +182,7 @@ impl Scheme {
                 return Err(SharingError::ShareDuplicateIdentifier);
             }
 
-            let y = R::from_bytes(&share.value)?;
+            let y = R::from_bytes(share.value())?;
             if !y.is_valid() {
                 return Err(SharingError::ShareInvalidValue);
             }
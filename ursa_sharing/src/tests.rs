@@ -12,15 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 use super::{
+    dkg::{Participant, Round, Transcript},
+    dleq::Proof as DleqProof,
     error::SharingResult,
     feldman::{FeldmanVerifier, Scheme as FeldmanVss},
-    pedersen::{PedersenVssResult, Scheme as PedersenVss},
+    pedersen::{PedersenVerifier, PedersenVssResult, Scheme as PedersenVss},
     shamir::{Scheme, Share},
     Field, Group,
 };
+use digest::Digest;
 use rand::prelude::*;
+use std::convert::TryFrom;
+use ursa_core::{
+    error::codes, parallel::MaybeSync, transcript::Transcript as ProofTranscript, CborSerializable,
+};
 
 /// Test invalid split arguments
+#[allow(clippy::needless_borrows_for_generic_args)]
 pub fn split_invalid_args<S: Field>() {
     assert!(Scheme::new(0, 0).is_err());
     assert!(Scheme::new(3, 2).is_err());
@@ -31,7 +39,20 @@ pub fn split_invalid_args<S: Field>() {
     assert!(S::from_bytes(&[65u8; 1000]).is_err());
 }
 
+/// Test the stable codes of invalid split arguments
+pub fn split_error_codes() {
+    assert_eq!(
+        Scheme::new(3, 2).unwrap_err().code(),
+        codes::SHARE_LIMIT_LESS_THAN_THRESHOLD
+    );
+    assert_eq!(
+        Scheme::new(1, 10).unwrap_err().code(),
+        codes::SHARE_MIN_THRESHOLD
+    );
+}
+
 /// Test that combining should fail
+#[allow(clippy::needless_borrows_for_generic_args)]
 pub fn combine_invalid<S: Field>() {
     let scheme = Scheme::new(2, 3).unwrap();
     // No shares
@@ -68,6 +89,14 @@ pub fn combine_single<S: Field, R: Group<S>>() {
 
     assert_eq!(secret.to_bytes(), secret_1.to_bytes());
 
+    // Shares round trip through their byte encoding
+    for share in &shares {
+        let bytes = share.to_bytes();
+        assert_eq!(bytes.len(), 4 + share.value().len());
+        assert_eq!(Vec::<u8>::from(share), bytes);
+        assert_eq!(&Share::try_from(bytes.as_slice()).unwrap(), share);
+    }
+
     // Feldman test
     let scheme = FeldmanVss::new(2, 3).unwrap();
     let secret = S::from_bytes(b"hello").unwrap();
@@ -96,7 +125,7 @@ pub fn combine_single<S: Field, R: Group<S>>() {
     assert!(res.is_ok());
     let pedersen_res = res.unwrap();
 
-    for i in 0..(&pedersen_res.secret_shares).len() {
+    for i in 0..pedersen_res.secret_shares.len() {
         assert!(scheme
             .verify_share(
                 &pedersen_res.secret_shares[i],
@@ -210,3 +239,369 @@ pub fn combine_all_combinations<S: Field, R: Group<S>>() {
         }
     }
 }
+
+/// Test the versioned CBOR encodings of shares and verifiers
+pub fn cbor_envelopes<S: Field, R: Group<S>>() {
+    let mut rng = thread_rng();
+    let secret = S::random(&mut rng);
+
+    let feldman_vss = FeldmanVss::new(3, 5).unwrap();
+    let (verifier, shares) = feldman_vss
+        .split_secret::<S, R>(&mut rng, &secret, None)
+        .unwrap();
+    let verifier = FeldmanVerifier::<S, R>::from_cbor(&verifier.to_cbor()).unwrap();
+    for s in &shares {
+        let share = Share::from_cbor(&s.to_cbor()).unwrap();
+        assert_eq!(s, &share);
+        assert!(feldman_vss.verify_share(&share, &verifier).is_ok());
+    }
+    assert!(Share::from_cbor(&verifier.to_cbor()).is_err());
+
+    let pedersen_vss = PedersenVss::new(3, 5).unwrap();
+    let res = pedersen_vss
+        .split_secret::<S, R>(&mut rng, &secret, None, None)
+        .unwrap();
+    let verifier = PedersenVerifier::<S, R>::from_cbor(&res.verifier.to_cbor()).unwrap();
+    assert_eq!(res.verifier.to_bytes(), verifier.to_bytes());
+    for (s, b) in res.secret_shares.iter().zip(res.blinding_shares.iter()) {
+        assert!(pedersen_vss.verify_share(s, b, &verifier).is_ok());
+    }
+    assert!(FeldmanVerifier::<S, R>::from_cbor(&verifier.to_cbor()).is_err());
+}
+
+/// Test verifying all the shares of a dealing at once
+pub fn verify_batches<S: Field + MaybeSync, R: Group<S> + MaybeSync>() {
+    let mut rng = thread_rng();
+    let secret = S::random(&mut rng);
+
+    let feldman_vss = FeldmanVss::new(3, 5).unwrap();
+    let (verifier, mut shares) = feldman_vss
+        .split_secret::<S, R>(&mut rng, &secret, None)
+        .unwrap();
+    assert!(feldman_vss.verify_shares(&shares, &verifier).is_ok());
+    let (_, other_shares) = feldman_vss
+        .split_secret::<S, R>(&mut rng, &secret, None)
+        .unwrap();
+    shares[3] = other_shares[3].clone();
+    assert!(feldman_vss.verify_shares(&shares, &verifier).is_err());
+
+    let pedersen_vss = PedersenVss::new(3, 5).unwrap();
+    let mut res = pedersen_vss
+        .split_secret::<S, R>(&mut rng, &secret, None, None)
+        .unwrap();
+    assert!(pedersen_vss
+        .verify_shares(&res.secret_shares, &res.blinding_shares, &res.verifier)
+        .is_ok());
+    assert!(pedersen_vss
+        .verify_shares(&res.secret_shares, &res.blinding_shares[1..], &res.verifier)
+        .is_err());
+    res.blinding_shares.swap(0, 1);
+    assert!(pedersen_vss
+        .verify_shares(&res.secret_shares, &res.blinding_shares, &res.verifier)
+        .is_err());
+}
+
+/// Test a DKG ceremony with a crash, a cheating dealer and a late verifier
+pub fn dkg_ceremony<S: Field, R: Group<S>>() {
+    let mut rng = thread_rng();
+    let g = R::random(&mut rng);
+    let h = R::random(&mut rng);
+    let mut participants = (1..=4)
+        .map(|i| {
+            Participant::<S, R>::new(
+                i,
+                3,
+                4,
+                R::from_bytes(g.to_bytes()).unwrap(),
+                R::from_bytes(h.to_bytes()).unwrap(),
+            )
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert!(Participant::<S, R>::new(5, 3, 4, R::random(&mut rng), R::random(&mut rng)).is_err());
+    assert!(participants[0].transcript().is_err());
+
+    let mut dealings = Vec::new();
+    for p in participants.iter_mut() {
+        let d = p.deal(&mut rng).unwrap();
+        dealings.push((d.dealer, d.verifier.to_bytes(), d.shares.clone()));
+        assert_eq!(Round::Collecting, p.round());
+    }
+    assert!(participants[0].deal(&mut rng).is_err());
+    // Dealer 4 sends participant 2 a share of dealer 3's secret
+    dealings[3].2[1] = dealings[2].2[1].clone();
+
+    for (dealer, verifier, shares) in &dealings {
+        for p in participants.iter_mut().filter(|p| p.id() != *dealer) {
+            // Participant 1 crashes after hearing from dealer 2 and resumes
+            if p.id() == 1 && *dealer == 3 {
+                let stored = p.to_cbor();
+                *p = Participant::from_cbor(&stored).unwrap();
+                assert_eq!(stored, p.to_cbor());
+                let own = p.dealing().unwrap();
+                assert_eq!(dealings[0].1, own.verifier.to_bytes());
+                // Replayed messages are ignored
+                let (s, b) = dealings[1].2[0].clone();
+                p.receive_shares(2, s, b).unwrap();
+            }
+            let verifier = PedersenVerifier::try_from(verifier.as_slice()).unwrap();
+            let (s, b) = shares[p.id() as usize - 1].clone();
+            let received = p
+                .receive_commitment(*dealer, verifier)
+                .and_then(|_| p.receive_shares(*dealer, s, b));
+            assert_eq!(p.id() == 2 && *dealer == 4, received.is_err());
+        }
+    }
+    assert_eq!(vec![4], participants[1].disqualified().collect::<Vec<_>>());
+    let (s, b) = dealings[0].2[1].clone();
+    assert_eq!(
+        codes::DKG_CONFLICTING_MESSAGE,
+        participants[1].receive_shares(1, b, s).unwrap_err().code()
+    );
+    assert_eq!(
+        codes::DKG_UNKNOWN_DEALER,
+        participants[1]
+            .receive_complaint(2)
+            .map(|_| ())
+            .unwrap_err()
+            .code()
+    );
+    for p in participants.iter_mut() {
+        if p.id() != 4 {
+            p.receive_complaint(4).unwrap();
+        }
+    }
+
+    let mut shares = Vec::new();
+    for p in participants.iter_mut().take(3) {
+        let (s, b) = p.finish().unwrap();
+        shares.push((s.clone(), b.clone()));
+        assert_eq!(Round::Complete, p.round());
+        assert!(p.receive_complaint(1).is_err());
+    }
+    let restored = Participant::<S, R>::from_cbor(&participants[2].to_cbor()).unwrap();
+    assert_eq!(Some(&shares[2].0), restored.output().map(|(s, _)| s));
+
+    let transcript = participants[0].transcript().unwrap();
+    let transcript = Transcript::<S, R>::from_cbor(&transcript.to_cbor()).unwrap();
+    assert_eq!(vec![1, 2, 3], transcript.qualified().collect::<Vec<_>>());
+    assert_eq!(vec![4], transcript.disqualified().collect::<Vec<_>>());
+    for p in participants.iter().take(3) {
+        assert_eq!(transcript.to_cbor(), p.transcript().unwrap().to_cbor());
+    }
+    for (s, b) in &shares {
+        assert!(transcript.verify_share(s, b).is_ok());
+    }
+    assert!(transcript.verify_share(&shares[0].0, &shares[1].1).is_err());
+
+    // The shared secret is the sum of the qualified dealers' secrets
+    let secret_shares = shares.iter().map(|(s, _)| s.clone()).collect::<Vec<_>>();
+    let secret = Scheme::new(3, 4)
+        .unwrap()
+        .combine_shares::<S, S>(&secret_shares)
+        .unwrap();
+    let mut expected = S::zero();
+    for (_, _, dealt) in &dealings[..3] {
+        let dealer_shares = dealt[..3]
+            .iter()
+            .map(|(s, _)| s.clone())
+            .collect::<Vec<_>>();
+        let dealer_secret = Scheme::new(3, 4)
+            .unwrap()
+            .combine_shares::<S, S>(&dealer_shares)
+            .unwrap();
+        expected.add_assign(&dealer_secret);
+    }
+    assert_eq!(expected.to_bytes(), secret.to_bytes());
+
+    let mut late = Participant::<S, R>::new(4, 3, 4, g, h).unwrap();
+    assert!(late.finish().is_err());
+    late.deal(&mut rng).unwrap();
+    assert_eq!(
+        codes::DKG_NOT_ENOUGH_DEALERS,
+        late.finish().map(|_| ()).unwrap_err().code()
+    );
+}
+
+/// Test backing up a secret with custodians and restoring it
+#[cfg(feature = "backup")]
+pub fn backup_bundle<S: Field, R: Group<S>, D: Digest>() {
+    use super::backup::{create_backup, custodian_keypair, restore, verify_bundle, BackupBundle};
+
+    let mut rng = thread_rng();
+    let g = R::random(&mut rng);
+    let secret = S::random(&mut rng);
+    let (keys, custodians): (Vec<S>, Vec<R>) = (0..5)
+        .map(|_| custodian_keypair::<S, R>(&mut rng, &g))
+        .unzip();
+    assert!(create_backup::<S, R, D>(&mut rng, &secret, 6, &g, &custodians).is_err());
+
+    let bundle = create_backup::<S, R, D>(&mut rng, &secret, 3, &g, &custodians).unwrap();
+    let bundle = BackupBundle::<S, R>::from_cbor(&bundle.to_cbor()).unwrap();
+    assert!(verify_bundle::<S, R, D>(&bundle).is_ok());
+    assert_eq!(5, bundle.shares().len());
+
+    let shares = keys
+        .iter()
+        .enumerate()
+        .map(|(i, x)| bundle.unwrap_share::<D>(i as u32 + 1, x).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        codes::BACKUP_SHARE_DECRYPTION,
+        bundle.unwrap_share::<D>(1, &keys[1]).unwrap_err().code()
+    );
+    assert!(bundle.unwrap_share::<D>(6, &keys[0]).is_err());
+
+    let restored = restore(&bundle, &shares[2..]).unwrap();
+    assert_eq!(secret.to_bytes(), restored.to_bytes());
+    assert!(restore(&bundle, &shares[..2]).is_err());
+    let forged = Share::new(1, S::random(&mut rng).to_bytes());
+    assert!(restore(&bundle, &[forged, shares[1].clone(), shares[2].clone()]).is_err());
+
+    let mut cbor = bundle.to_cbor();
+    let last = cbor.len() - 40;
+    cbor[last] ^= 1;
+    let tampered = BackupBundle::<S, R>::from_cbor(&cbor).unwrap();
+    assert_eq!(
+        codes::BACKUP_MANIFEST_MISMATCH,
+        verify_bundle::<S, R, D>(&tampered).unwrap_err().code()
+    );
+}
+
+/// Test the protobuf messages of shares, verifiers and DKG rounds
+#[cfg(feature = "protobuf")]
+pub fn protobuf_messages<S: Field, R: Group<S>>() {
+    use super::proto::dkg_share;
+    use ursa_core::proto;
+
+    let mut rng = thread_rng();
+    let secret = S::random(&mut rng);
+
+    let feldman_vss = FeldmanVss::new(3, 5).unwrap();
+    let (verifier, shares) = feldman_vss
+        .split_secret::<S, R>(&mut rng, &secret, None)
+        .unwrap();
+    let verifier =
+        FeldmanVerifier::<S, R>::try_from(&proto::FeldmanVerifier::from(&verifier)).unwrap();
+    for s in &shares {
+        let share = Share::from(&proto::Share::from(s));
+        assert_eq!(s.to_bytes(), share.to_bytes());
+        assert!(feldman_vss.verify_share(&share, &verifier).is_ok());
+    }
+
+    let pedersen_vss = PedersenVss::new(3, 5).unwrap();
+    let res = pedersen_vss
+        .split_secret::<S, R>(&mut rng, &secret, None, None)
+        .unwrap();
+    let commitment = res.dkg_commitment(1);
+    let verifier = PedersenVerifier::<S, R>::try_from(&commitment).unwrap();
+    assert_eq!(res.verifier.to_bytes(), verifier.to_bytes());
+    let messages = res.dkg_shares(1);
+    assert_eq!(5, messages.len());
+    for m in &messages {
+        let (s, b) = dkg_share(m).unwrap();
+        assert_eq!(m.recipient, s.identifier());
+        assert!(pedersen_vss.verify_share(&s, &b, &verifier).is_ok());
+    }
+
+    let mut m = messages[0].clone();
+    m.recipient += 1;
+    assert!(dkg_share(&m).is_err());
+    m.blinding_share = None;
+    assert!(dkg_share(&m).is_err());
+    let commitment = proto::DkgCommitment {
+        dealer: 1,
+        verifier: None,
+    };
+    assert!(PedersenVerifier::<S, R>::try_from(&commitment).is_err());
+}
+
+/// Test proving and verifying discrete log equality
+pub fn dleq_prove_verify<S: Field, R: Group<S>, D: Digest>() {
+    let mut rng = thread_rng();
+    let x = S::random(&mut rng);
+    let g = R::random(&mut rng);
+    let h = R::random(&mut rng);
+    let mut a = R::zero();
+    a.add_assign(&g);
+    a.scalar_mul_assign(&x);
+    let mut b = R::zero();
+    b.add_assign(&h);
+    b.scalar_mul_assign(&x);
+
+    let proof = DleqProof::new::<R, D>(&mut rng, &x, &g, &a, &h, &b);
+    assert!(proof.verify::<R, D>(&g, &a, &h, &b));
+
+    let bytes = proof.to_bytes();
+    let proof = DleqProof::<S>::try_from(bytes.as_slice()).unwrap();
+    assert!(proof.verify::<R, D>(&g, &a, &h, &b));
+    assert!(DleqProof::<S>::try_from(&bytes[1..]).is_err());
+
+    // Swapped bases or a different exponent must fail
+    assert!(!proof.verify::<R, D>(&h, &a, &g, &b));
+    let y = S::random(&mut rng);
+    let mut c = R::zero();
+    c.add_assign(&h);
+    c.scalar_mul_assign(&y);
+    assert!(!proof.verify::<R, D>(&g, &a, &h, &c));
+    let proof = DleqProof::new::<R, D>(&mut rng, &x, &g, &a, &h, &c);
+    assert!(!proof.verify::<R, D>(&g, &a, &h, &c));
+}
+
+/// Test discrete log equality proofs bound to a caller's transcript
+pub fn dleq_transcript<S: Field, R: Group<S>>() {
+    let mut rng = thread_rng();
+    let x = S::random(&mut rng);
+    let g = R::random(&mut rng);
+    let h = R::random(&mut rng);
+    let mut a = R::zero();
+    a.add_assign(&g);
+    a.scalar_mul_assign(&x);
+    let mut b = R::zero();
+    b.add_assign(&h);
+    b.scalar_mul_assign(&x);
+
+    let session = |id: u64| {
+        let mut transcript = ProofTranscript::new(b"ursa_sharing test");
+        transcript.append_u64(b"session", id);
+        transcript
+    };
+    let proof = DleqProof::new_with_transcript(&mut rng, &mut session(1), &x, &g, &a, &h, &b);
+    assert!(proof.verify_with_transcript(&mut session(1), &g, &a, &h, &b));
+    // The proof does not carry over to another session or statement
+    assert!(!proof.verify_with_transcript(&mut session(2), &g, &a, &h, &b));
+    assert!(!proof.verify_with_transcript(&mut session(1), &h, &a, &g, &b));
+}
+
+/// Test batched discrete log equality proofs over many bases
+pub fn dleq_batch<S: Field, R: Group<S>, D: Digest>() {
+    let mut rng = thread_rng();
+    let x = S::random(&mut rng);
+    let g = R::random(&mut rng);
+    let mut a = R::zero();
+    a.add_assign(&g);
+    a.scalar_mul_assign(&x);
+
+    let mut hs = Vec::new();
+    let mut bs = Vec::new();
+    for _ in 0..5 {
+        let h = R::random(&mut rng);
+        let mut b = R::zero();
+        b.add_assign(&h);
+        b.scalar_mul_assign(&x);
+        hs.push(h);
+        bs.push(b);
+    }
+
+    let proof = DleqProof::new_batch::<R, D>(&mut rng, &x, &g, &a, &hs, &bs).unwrap();
+    assert!(proof.verify_batch::<R, D>(&g, &a, &hs, &bs));
+    assert!(!proof.verify_batch::<R, D>(&g, &a, &hs[1..], &bs[1..]));
+    assert!(DleqProof::new_batch::<R, D>(&mut rng, &x, &g, &a, &hs[1..], &bs).is_err());
+    assert!(DleqProof::new_batch::<R, D>(&mut rng, &x, &g, &a, &[], &[]).is_err());
+
+    // A single value with a different exponent fails the whole batch
+    bs[3].add_assign(&g);
+    assert!(!proof.verify_batch::<R, D>(&g, &a, &hs, &bs));
+    let proof = DleqProof::new_batch::<R, D>(&mut rng, &x, &g, &a, &hs, &bs).unwrap();
+    assert!(!proof.verify_batch::<R, D>(&g, &a, &hs, &bs));
+}
@@ -0,0 +1,253 @@
+//! The implementation of `src/ursa.udl`.
+//!
+//! Generate the bindings of a language from the built library with
+//!
+//! ```bash
+//! cargo build --release
+//! cargo run --bin uniffi-bindgen generate --library target/release/libursa_uniffi.so \
+//!     --language kotlin --out-dir out
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+use ursa::bn::BigNumber;
+use ursa::encryption::random_vec;
+use ursa::encryption::symm::prelude::*;
+use ursa::keys::{KeyGenOption, PrivateKey, PublicKey};
+use ursa::registry::{Algorithm, AnyKeyPair, AnyPublicKey};
+use ursa::sharing::shamir::{self, Share};
+use ursa::CryptoError;
+
+uniffi::include_scaffolding!("ursa");
+
+/// `CryptoError` with the message kept for the foreign exception
+#[derive(Debug)]
+pub enum UrsaError {
+    NoSuchAlgorithm(String),
+    ParseError(String),
+    SigningError(String),
+    KeyGenError(String),
+    DigestGenError(String),
+    GeneralError(String),
+    InvalidPoint(String),
+}
+
+impl From<CryptoError> for UrsaError {
+    fn from(e: CryptoError) -> Self {
+        match e {
+            CryptoError::NoSuchAlgorithm(s) => UrsaError::NoSuchAlgorithm(s),
+            CryptoError::ParseError(s) => UrsaError::ParseError(s),
+            CryptoError::SigningError(s) => UrsaError::SigningError(s),
+            CryptoError::KeyGenError(s) => UrsaError::KeyGenError(s),
+            CryptoError::DigestGenError(s) => UrsaError::DigestGenError(s),
+            CryptoError::GeneralError(s) => UrsaError::GeneralError(s),
+            CryptoError::InvalidPoint(s) => UrsaError::InvalidPoint(s),
+        }
+    }
+}
+
+impl fmt::Display for UrsaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UrsaError::NoSuchAlgorithm(s)
+            | UrsaError::ParseError(s)
+            | UrsaError::SigningError(s)
+            | UrsaError::KeyGenError(s)
+            | UrsaError::DigestGenError(s)
+            | UrsaError::GeneralError(s)
+            | UrsaError::InvalidPoint(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl std::error::Error for UrsaError {}
+
+type Result<T> = std::result::Result<T, UrsaError>;
+
+fn algorithms() -> Vec<String> {
+    Algorithm::all()
+        .into_iter()
+        .map(|a| a.name().to_string())
+        .collect()
+}
+
+fn verify(
+    algorithm: String,
+    public_key: Vec<u8>,
+    message: Vec<u8>,
+    signature: Vec<u8>,
+) -> Result<bool> {
+    let pk = AnyPublicKey::new(algorithm.parse()?, PublicKey(public_key));
+    match pk.verify(&message, &signature) {
+        Ok(valid) => Ok(valid),
+        Err(CryptoError::SigningError(_)) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn split_secret(secret: Vec<u8>, threshold: u8, total: u8, prime: Vec<u8>) -> Result<Vec<Vec<u8>>> {
+    let field = field(&prime)?;
+    let shares = shamir::split_secret(&secret, threshold, total, &field)?;
+    Ok(shares.iter().map(Share::to_bytes).collect())
+}
+
+fn combine_shares(shares: Vec<Vec<u8>>, prime: Vec<u8>) -> Result<Vec<u8>> {
+    let field = field(&prime)?;
+    let shares = shares
+        .iter()
+        .map(|s| {
+            if s.len() < 2 {
+                return Err(CryptoError::ParseError("Share is too short".to_string()));
+            }
+            Share::from_bytes(s, &field)
+        })
+        .collect::<std::result::Result<Vec<Share>, CryptoError>>()?;
+    Ok(shamir::combine_shares(shares, &field)?)
+}
+
+fn field(prime: &[u8]) -> Result<BigNumber> {
+    BigNumber::from_bytes(prime).map_err(|e| UrsaError::ParseError(e.to_string()))
+}
+
+pub struct KeyPair(AnyKeyPair);
+
+impl KeyPair {
+    fn new(algorithm: String) -> Result<Self> {
+        Self::with_option(algorithm, None)
+    }
+
+    fn from_seed(algorithm: String, seed: Vec<u8>) -> Result<Self> {
+        Self::with_option(algorithm, Some(KeyGenOption::UseSeed(seed)))
+    }
+
+    fn from_private_key(algorithm: String, private_key: Vec<u8>) -> Result<Self> {
+        let sk = PrivateKey(private_key);
+        Self::with_option(algorithm, Some(KeyGenOption::FromSecretKey(sk)))
+    }
+
+    fn with_option(algorithm: String, option: Option<KeyGenOption>) -> Result<Self> {
+        let algorithm = Algorithm::from_str(&algorithm)?;
+        Ok(KeyPair(algorithm.keypair(option)?))
+    }
+
+    fn algorithm(&self) -> String {
+        self.0.algorithm().name().to_string()
+    }
+
+    fn public_key(&self) -> Vec<u8> {
+        self.0.keys().public_key.0.clone()
+    }
+
+    fn private_key(&self) -> Vec<u8> {
+        self.0.private_key().0.clone()
+    }
+
+    fn sign(&self, message: Vec<u8>) -> Result<Vec<u8>> {
+        Ok(self.0.sign(&message)?)
+    }
+
+    fn key_exchange(&self, peer_public_key: Vec<u8>) -> Result<Vec<u8>> {
+        let peer = AnyPublicKey::new(self.0.algorithm(), PublicKey(peer_public_key));
+        Ok(self.0.key_exchange(&peer)?.0.clone())
+    }
+}
+
+pub struct Aead {
+    cipher: EncryptorType,
+    key: Vec<u8>,
+}
+
+impl Aead {
+    fn new(cipher: String, key: Vec<u8>) -> Result<Self> {
+        let cipher = parse_cipher(&cipher)?;
+        if !cipher.is_valid_keysize(key.len()) {
+            return Err(UrsaError::ParseError(format!(
+                "Invalid key length, expected {} bytes",
+                cipher.keysize()
+            )));
+        }
+        Ok(Aead { cipher, key })
+    }
+
+    fn generate(cipher: String) -> Result<Self> {
+        let cipher = parse_cipher(&cipher)?;
+        let key = random_vec(cipher.keysize())
+            .map_err(|_| UrsaError::KeyGenError("Unable to generate key".to_string()))?;
+        Ok(Aead { cipher, key })
+    }
+
+    fn key(&self) -> Vec<u8> {
+        self.key.clone()
+    }
+
+    fn encrypt(&self, aad: Vec<u8>, plaintext: Vec<u8>) -> Result<Vec<u8>> {
+        self.cipher
+            .gen_encryptor(&self.key)
+            .encrypt_easy(&aad, &plaintext)
+            .map_err(|_| UrsaError::GeneralError("Encryption failed".to_string()))
+    }
+
+    fn decrypt(&self, aad: Vec<u8>, ciphertext: Vec<u8>) -> Result<Vec<u8>> {
+        self.cipher
+            .gen_encryptor(&self.key)
+            .decrypt_easy(&aad, &ciphertext)
+            .map_err(|_| UrsaError::GeneralError("Decryption failed".to_string()))
+    }
+}
+
+fn parse_cipher(cipher: &str) -> Result<EncryptorType> {
+    EncryptorType::from_str(cipher).map_err(UrsaError::NoSuchAlgorithm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keypairs() {
+        let keys = KeyPair::from_seed("ed25519".to_string(), vec![1u8; 32]).unwrap();
+        let message = b"Wepa! This is a message that should be signed.".to_vec();
+        let signature = keys.sign(message.clone()).unwrap();
+        assert!(verify(
+            keys.algorithm(),
+            keys.public_key(),
+            message,
+            signature.clone()
+        )
+        .unwrap());
+        assert!(!verify(keys.algorithm(), keys.public_key(), vec![1u8], signature).unwrap());
+
+        let alice = KeyPair::new("x25519".to_string()).unwrap();
+        let bob = KeyPair::from_private_key("x25519".to_string(), vec![2u8; 32]).unwrap();
+        assert_eq!(
+            alice.key_exchange(bob.public_key()).unwrap(),
+            bob.key_exchange(alice.public_key()).unwrap()
+        );
+
+        match KeyPair::new("rsa".to_string()) {
+            Err(UrsaError::NoSuchAlgorithm(_)) => {}
+            _ => panic!("rsa is not supported"),
+        }
+    }
+
+    #[test]
+    fn aead() {
+        let aead = Aead::generate("aes-256-gcm".to_string()).unwrap();
+        let plaintext = b"Hello World! This is a secret message.".to_vec();
+        let ciphertext = aead.encrypt(vec![], plaintext.clone()).unwrap();
+        assert_eq!(plaintext, aead.decrypt(vec![], ciphertext).unwrap());
+        assert!(Aead::new("aes-256-gcm".to_string(), vec![0u8; 3]).is_err());
+    }
+
+    #[test]
+    fn sharing() {
+        // 2^127 - 1
+        let mut prime = vec![0xffu8; 16];
+        prime[0] = 0x7f;
+        let shares = split_secret(b"secret".to_vec(), 3, 5, prime.clone()).unwrap();
+        assert_eq!(
+            b"secret".to_vec(),
+            combine_shares(shares[2..].to_vec(), prime).unwrap()
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! Authenticated encryption with associated data.
+//!
+//! The algorithm names are the ones of `EncryptorType`: `aes-128-gcm`,
+//! `aes-256-gcm`, `aes-128-cbc-hmac-256`, `aes-256-cbc-hmac-512` and
+//! `xchacha20poly1305`.
+
+use super::to_js;
+use std::str::FromStr;
+use ursa::encryption::random_vec;
+use ursa::encryption::symm::prelude::*;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct Aead(Box<dyn DynEncryptor>);
+
+#[wasm_bindgen]
+impl Aead {
+    /// Create a cipher of `algorithm` with `key`
+    #[wasm_bindgen(constructor)]
+    pub fn new(algorithm: &str, key: &[u8]) -> Result<Aead, JsValue> {
+        let cipher = EncryptorType::from_str(algorithm).map_err(to_js)?;
+        if !cipher.is_valid_keysize(key.len()) {
+            return Err(JsValue::from_str(&format!(
+                "Invalid key length for {}, expected {} bytes",
+                algorithm,
+                cipher.keysize()
+            )));
+        }
+        Ok(Aead(cipher.gen_encryptor(key)))
+    }
+
+    /// A random key for `algorithm`
+    #[wasm_bindgen(js_name = generateKey)]
+    pub fn generate_key(algorithm: &str) -> Result<Vec<u8>, JsValue> {
+        let cipher = EncryptorType::from_str(algorithm).map_err(to_js)?;
+        random_vec(cipher.keysize()).map_err(to_js)
+    }
+
+    /// Encrypt `plaintext` under a random nonce. The nonce is prepended to
+    /// the ciphertext.
+    pub fn encrypt(&self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, JsValue> {
+        self.0.encrypt_easy(aad, plaintext).map_err(to_js)
+    }
+
+    /// Decrypt the output of `encrypt`
+    pub fn decrypt(&self, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, JsValue> {
+        self.0.decrypt_easy(aad, ciphertext).map_err(to_js)
+    }
+
+    #[wasm_bindgen(getter, js_name = keySize)]
+    pub fn key_size(&self) -> usize {
+        self.0.keysize()
+    }
+
+    #[wasm_bindgen(getter, js_name = nonceSize)]
+    pub fn nonce_size(&self) -> usize {
+        self.0.noncesize()
+    }
+}
@@ -0,0 +1,31 @@
+use ursa::keys::{PrivateKey, PublicKey};
+use wasm_bindgen::prelude::*;
+
+/// A public and private key of any of the schemes in this package
+#[wasm_bindgen]
+pub struct KeyPair {
+    public_key: Vec<u8>,
+    private_key: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl KeyPair {
+    #[wasm_bindgen(getter, js_name = publicKey)]
+    pub fn public_key(&self) -> Vec<u8> {
+        self.public_key.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = privateKey)]
+    pub fn private_key(&self) -> Vec<u8> {
+        self.private_key.clone()
+    }
+}
+
+impl From<(PublicKey, PrivateKey)> for KeyPair {
+    fn from((pk, sk): (PublicKey, PrivateKey)) -> Self {
+        KeyPair {
+            public_key: pk.0.clone(),
+            private_key: sk.0.clone(),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! WebAssembly bindings for the Ursa primitives.
+//!
+//! Every key, message, signature and ciphertext is a `Uint8Array` on the
+//! JavaScript side and errors are thrown as strings. Build the npm package
+//! with `wasm-pack build --release --target web` (or `--target nodejs`).
+//!
+//! ```js
+//! import init, { Ed25519 } from "ursa-wasm";
+//!
+//! await init();
+//! const scheme = new Ed25519();
+//! const keys = scheme.keypair();
+//! const signature = scheme.sign(message, keys.privateKey);
+//! scheme.verify(message, signature, keys.publicKey); // true
+//! ```
+
+#[cfg(feature = "aead")]
+pub mod aead;
+#[cfg(any(
+    feature = "ed25519",
+    feature = "secp256k1",
+    feature = "bls",
+    feature = "x25519"
+))]
+mod keypair;
+#[cfg(feature = "sharing")]
+pub mod sharing;
+#[cfg(any(feature = "ed25519", feature = "secp256k1", feature = "bls"))]
+pub mod signatures;
+#[cfg(feature = "x25519")]
+pub mod x25519;
+
+#[cfg(any(
+    feature = "ed25519",
+    feature = "secp256k1",
+    feature = "bls",
+    feature = "x25519"
+))]
+pub use keypair::KeyPair;
+use wasm_bindgen::prelude::*;
+
+/// Route Rust panics to `console.error`. Runs when the module is instantiated.
+#[wasm_bindgen(start)]
+pub fn start() {
+    console_error_panic_hook::set_once();
+}
+
+/// Convert any error to the string thrown in JavaScript
+pub(crate) fn to_js<E: std::fmt::Display>(e: E) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
@@ -0,0 +1,47 @@
+//! Shamir secret sharing over a prime field.
+//!
+//! The field is given as the big endian bytes of its prime, which must be
+//! larger than the secret. Each share is `value || identifier`.
+
+use super::to_js;
+use js_sys::{Array, Uint8Array};
+use ursa::bn::BigNumber;
+use ursa::sharing::shamir::{self, Share};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Split `secret` into `total` shares, `threshold` of which recover it
+#[wasm_bindgen(js_name = splitSecret)]
+pub fn split_secret(
+    secret: &[u8],
+    threshold: u8,
+    total: u8,
+    prime: &[u8],
+) -> Result<Array, JsValue> {
+    let field = BigNumber::from_bytes(prime).map_err(to_js)?;
+    let shares = shamir::split_secret(secret, threshold, total, &field).map_err(to_js)?;
+    Ok(shares
+        .iter()
+        .map(|s| JsValue::from(Uint8Array::from(&s.to_bytes()[..])))
+        .collect())
+}
+
+/// Recover the secret from an array of at least `threshold` shares
+#[wasm_bindgen(js_name = combineShares)]
+pub fn combine_shares(shares: Array, prime: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let field = BigNumber::from_bytes(prime).map_err(to_js)?;
+    let shares = shares
+        .iter()
+        .map(|s| {
+            let bytes = s
+                .dyn_into::<Uint8Array>()
+                .map_err(|_| JsValue::from_str("Shares must be Uint8Arrays"))?
+                .to_vec();
+            if bytes.len() < 2 {
+                return Err(JsValue::from_str("Share is too short"));
+            }
+            Share::from_bytes(bytes, &field).map_err(to_js)
+        })
+        .collect::<Result<Vec<Share>, JsValue>>()?;
+    shamir::combine_shares(shares, &field).map_err(to_js)
+}
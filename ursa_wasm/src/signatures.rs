@@ -0,0 +1,76 @@
+//! Signature schemes. Each one is a JavaScript class with the same methods.
+
+use super::{to_js, KeyPair};
+use ursa::keys::{KeyGenOption, PrivateKey, PublicKey};
+use ursa::signatures::SignatureScheme;
+use ursa::CryptoError;
+use wasm_bindgen::prelude::*;
+
+macro_rules! signature_impl {
+    ($name:ident, $scheme:ty) => {
+        #[wasm_bindgen]
+        pub struct $name($scheme);
+
+        #[wasm_bindgen]
+        impl $name {
+            #[wasm_bindgen(constructor)]
+            #[allow(clippy::new_without_default)]
+            pub fn new() -> Self {
+                $name(<$scheme>::new())
+            }
+
+            /// Generate a random keypair
+            pub fn keypair(&self) -> Result<KeyPair, JsValue> {
+                self.0.keypair(None).map(KeyPair::from).map_err(to_js)
+            }
+
+            /// Derive a keypair from `seed`
+            #[wasm_bindgen(js_name = keypairFromSeed)]
+            pub fn keypair_from_seed(&self, seed: &[u8]) -> Result<KeyPair, JsValue> {
+                self.0
+                    .keypair(Some(KeyGenOption::UseSeed(seed.to_vec())))
+                    .map(KeyPair::from)
+                    .map_err(to_js)
+            }
+
+            /// The public key of `private_key`
+            #[wasm_bindgen(js_name = publicKey)]
+            pub fn public_key(&self, private_key: &[u8]) -> Result<Vec<u8>, JsValue> {
+                let sk = PrivateKey(private_key.to_vec());
+                let (pk, _) = self
+                    .0
+                    .keypair(Some(KeyGenOption::FromSecretKey(sk)))
+                    .map_err(to_js)?;
+                Ok(pk.0.clone())
+            }
+
+            pub fn sign(&self, message: &[u8], private_key: &[u8]) -> Result<Vec<u8>, JsValue> {
+                let sk = PrivateKey(private_key.to_vec());
+                self.0.sign(message, &sk).map_err(to_js)
+            }
+
+            /// Returns false for a signature that doesn't verify and throws
+            /// for malformed keys
+            pub fn verify(
+                &self,
+                message: &[u8],
+                signature: &[u8],
+                public_key: &[u8],
+            ) -> Result<bool, JsValue> {
+                let pk = PublicKey(public_key.to_vec());
+                match self.0.verify(message, signature, &pk) {
+                    Ok(valid) => Ok(valid),
+                    Err(e @ CryptoError::ParseError(_)) => Err(to_js(e)),
+                    Err(_) => Ok(false),
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "ed25519")]
+signature_impl!(Ed25519, ursa::signatures::ed25519::Ed25519Sha512);
+#[cfg(feature = "secp256k1")]
+signature_impl!(Secp256k1, ursa::signatures::secp256k1::EcdsaSecp256k1Sha256);
+#[cfg(feature = "bls")]
+signature_impl!(Bls12381, ursa::signatures::bls::normal::Bls);
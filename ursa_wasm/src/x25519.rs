@@ -0,0 +1,56 @@
+//! X25519 key exchange
+
+use super::{to_js, KeyPair};
+use ursa::kex::{x25519::X25519Sha256, KeyExchangeScheme};
+use ursa::keys::{KeyGenOption, PrivateKey, PublicKey};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct X25519(X25519Sha256);
+
+#[wasm_bindgen]
+impl X25519 {
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        X25519(X25519Sha256::new())
+    }
+
+    /// Generate a random keypair
+    pub fn keypair(&self) -> Result<KeyPair, JsValue> {
+        self.0.keypair(None).map(KeyPair::from).map_err(to_js)
+    }
+
+    /// Derive a keypair from `seed`
+    #[wasm_bindgen(js_name = keypairFromSeed)]
+    pub fn keypair_from_seed(&self, seed: &[u8]) -> Result<KeyPair, JsValue> {
+        self.0
+            .keypair(Some(KeyGenOption::UseSeed(seed.to_vec())))
+            .map(KeyPair::from)
+            .map_err(to_js)
+    }
+
+    /// The SHA-256 hash of the Diffie-Hellman shared secret of
+    /// `private_key` and the peer's `public_key`
+    #[wasm_bindgen(js_name = sharedSecret)]
+    pub fn shared_secret(&self, private_key: &[u8], public_key: &[u8]) -> Result<Vec<u8>, JsValue> {
+        check_size(private_key, "private")?;
+        check_size(public_key, "public")?;
+        let sk = PrivateKey(private_key.to_vec());
+        let pk = PublicKey(public_key.to_vec());
+        let secret = self.0.compute_shared_secret(&sk, &pk).map_err(to_js)?;
+        Ok(secret.0.clone())
+    }
+}
+
+/// The scheme indexes keys without checking their length
+fn check_size(key: &[u8], kind: &str) -> Result<(), JsValue> {
+    if key.len() == 32 {
+        Ok(())
+    } else {
+        Err(JsValue::from_str(&format!(
+            "Invalid {} key length, expected 32 bytes",
+            kind
+        )))
+    }
+}
@@ -0,0 +1,92 @@
+//! Run with `wasm-pack test --node`
+
+use wasm_bindgen_test::*;
+
+#[cfg(feature = "ed25519")]
+#[wasm_bindgen_test]
+fn ed25519_sign_verify() {
+    use ursa_wasm::signatures::Ed25519;
+
+    let scheme = Ed25519::new();
+    let keys = scheme.keypair_from_seed(&[1u8; 32]).unwrap();
+    assert_eq!(
+        keys.public_key(),
+        scheme.public_key(&keys.private_key()).unwrap()
+    );
+    let message = b"Wepa! This is a message that should be signed.";
+    let signature = scheme.sign(message, &keys.private_key()).unwrap();
+    assert!(scheme
+        .verify(message, &signature, &keys.public_key())
+        .unwrap());
+    assert!(!scheme
+        .verify(b"other", &signature, &keys.public_key())
+        .unwrap());
+}
+
+#[cfg(feature = "secp256k1")]
+#[wasm_bindgen_test]
+fn secp256k1_sign_verify() {
+    use ursa_wasm::signatures::Secp256k1;
+
+    let scheme = Secp256k1::new();
+    let keys = scheme.keypair().unwrap();
+    let signature = scheme.sign(b"message", &keys.private_key()).unwrap();
+    assert!(scheme
+        .verify(b"message", &signature, &keys.public_key())
+        .unwrap());
+}
+
+#[cfg(feature = "x25519")]
+#[wasm_bindgen_test]
+fn x25519_shared_secret() {
+    use ursa_wasm::x25519::X25519;
+
+    let scheme = X25519::new();
+    let alice = scheme.keypair().unwrap();
+    let bob = scheme.keypair().unwrap();
+    assert_eq!(
+        scheme
+            .shared_secret(&alice.private_key(), &bob.public_key())
+            .unwrap(),
+        scheme
+            .shared_secret(&bob.private_key(), &alice.public_key())
+            .unwrap()
+    );
+    assert!(scheme
+        .shared_secret(&alice.private_key(), &[1u8; 3])
+        .is_err());
+}
+
+#[cfg(feature = "aead")]
+#[wasm_bindgen_test]
+fn aead_round_trip() {
+    use ursa_wasm::aead::Aead;
+
+    for algorithm in &["aes-128-gcm", "aes-256-gcm", "xchacha20poly1305"] {
+        let key = Aead::generate_key(algorithm).unwrap();
+        let cipher = Aead::new(algorithm, &key).unwrap();
+        assert_eq!(key.len(), cipher.key_size());
+        let plaintext = b"Hello World! This is a secret message.";
+        let ciphertext = cipher.encrypt(b"aad", plaintext).unwrap();
+        assert_eq!(
+            plaintext.to_vec(),
+            cipher.decrypt(b"aad", &ciphertext).unwrap()
+        );
+        assert!(cipher.decrypt(b"other", &ciphertext).is_err());
+    }
+    assert!(Aead::new("aes-128-gcm", &[0u8; 3]).is_err());
+}
+
+#[cfg(feature = "sharing")]
+#[wasm_bindgen_test]
+fn sharing_round_trip() {
+    use ursa_wasm::sharing::{combine_shares, split_secret};
+
+    // 2^127 - 1
+    let mut prime = vec![0xffu8; 16];
+    prime[0] = 0x7f;
+    let shares = split_secret(b"secret", 3, 5, &prime).unwrap();
+    assert_eq!(5, shares.length());
+    let subset = shares.slice(1, 4);
+    assert_eq!(b"secret".to_vec(), combine_shares(subset, &prime).unwrap());
+}